@@ -0,0 +1,510 @@
+use std::borrow::Cow;
+use std::io::{self, Cursor};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use super::{Amf0ReadError, Amf0Value};
+
+// AMF3 markers, defined in amf3_spec_121207.pdf section 3.1
+const UNDEFINED: u8 = 0x00;
+const NULL: u8 = 0x01;
+const FALSE: u8 = 0x02;
+const TRUE: u8 = 0x03;
+const INTEGER: u8 = 0x04;
+const DOUBLE: u8 = 0x05;
+const STRING: u8 = 0x06;
+const XML_DOC: u8 = 0x07;
+const DATE: u8 = 0x08;
+const ARRAY: u8 = 0x09;
+const OBJECT: u8 = 0x0a;
+const XML: u8 = 0x0b;
+
+/// The most values an AVM+ value may decode to, counting every copy of a referenced value.
+///
+/// References are decoded as copies, so a few bytes can otherwise expand exponentially.
+const MAX_VALUES: usize = 1 << 18;
+/// The deepest arrays and objects may be nested in an AVM+ value.
+const MAX_DEPTH: usize = 64;
+
+/// A U29 header of a value that can be sent by reference.
+enum Header {
+    /// An index into one of the reference tables.
+    Reference(usize),
+    /// The rest of the header of an inline value.
+    Inline(u32),
+}
+
+/// The traits of an object, shared by reference between objects of the same class.
+#[derive(Clone)]
+struct Traits<'a> {
+    dynamic: bool,
+    sealed: Vec<Cow<'a, str>>,
+}
+
+/// Decodes an AMF3 value, as found after an
+/// [`AVMPlusObject`](crate::Amf0Marker::AVMPlusObject) marker, into the closest [`Amf0Value`].
+///
+/// Every AVM+ value starts with empty reference tables.
+pub(crate) struct Amf3Decoder<'a, 'c> {
+    cursor: &'c mut Cursor<&'a [u8]>,
+    strings: Vec<Cow<'a, str>>,
+    /// The objects that can be referenced, with the number of values each decoded to.
+    objects: Vec<(Amf0Value<'a>, usize)>,
+    traits: Vec<Traits<'a>>,
+    /// The number of values decoded so far, see [`MAX_VALUES`].
+    values: usize,
+    depth: usize,
+}
+
+impl<'a, 'c> Amf3Decoder<'a, 'c> {
+    pub fn new(cursor: &'c mut Cursor<&'a [u8]>) -> Self {
+        Self {
+            cursor,
+            strings: Vec::new(),
+            objects: Vec::new(),
+            traits: Vec::new(),
+            values: 0,
+            depth: 0,
+        }
+    }
+
+    pub fn decode(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        if self.depth == MAX_DEPTH {
+            return Err(Amf0ReadError::Amf3TooDeep);
+        }
+
+        self.depth += 1;
+        let value = self.decode_value();
+        self.depth -= 1;
+
+        value
+    }
+
+    fn decode_value(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        self.count_values(1)?;
+        let marker = self.cursor.read_u8()?;
+
+        match marker {
+            UNDEFINED | NULL => Ok(Amf0Value::Null),
+            FALSE => Ok(Amf0Value::Boolean(false)),
+            TRUE => Ok(Amf0Value::Boolean(true)),
+            // A 29 bit signed integer.
+            INTEGER => Ok(Amf0Value::Number((((self.read_u29()? << 3) as i32) >> 3) as f64)),
+            DOUBLE => Ok(Amf0Value::Number(self.cursor.read_f64::<BigEndian>()?)),
+            STRING => Ok(Amf0Value::String(self.read_string()?)),
+            XML_DOC | XML => self.read_xml(),
+            DATE => self.read_date(),
+            ARRAY => self.read_array(),
+            OBJECT => self.read_object(),
+            _ => Err(Amf0ReadError::UnsupportedAmf3Type(marker)),
+        }
+    }
+
+    /// Counts `count` more decoded values, failing once there are more than [`MAX_VALUES`].
+    fn count_values(&mut self, count: usize) -> Result<(), Amf0ReadError> {
+        self.values = self.values.saturating_add(count);
+        if self.values > MAX_VALUES {
+            return Err(Amf0ReadError::Amf3TooLarge);
+        }
+
+        Ok(())
+    }
+
+    fn remaining(&self) -> usize {
+        self.cursor.get_ref().len().saturating_sub(self.cursor.position() as usize)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Amf0ReadError> {
+        if len > self.remaining() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let buf: &'a [u8] = self.cursor.get_ref();
+        let pos = self.cursor.position() as usize;
+        self.cursor.set_position((pos + len) as u64);
+
+        Ok(&buf[pos..pos + len])
+    }
+
+    /// Reads a variable length unsigned 29 bit integer.
+    fn read_u29(&mut self) -> Result<u32, Amf0ReadError> {
+        let mut value = 0;
+
+        // The first three bytes carry 7 bits each, with the high bit set if another byte follows.
+        for _ in 0..3 {
+            let byte = self.cursor.read_u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        // The fourth byte carries all 8 bits.
+        Ok((value << 8) | self.cursor.read_u8()? as u32)
+    }
+
+    fn read_header(&mut self) -> Result<Header, Amf0ReadError> {
+        let header = self.read_u29()?;
+
+        if header & 1 == 0 {
+            Ok(Header::Reference((header >> 1) as usize))
+        } else {
+            Ok(Header::Inline(header >> 1))
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Cow<'a, str>, Amf0ReadError> {
+        match self.read_header()? {
+            Header::Reference(index) => self
+                .strings
+                .get(index)
+                .cloned()
+                .ok_or(Amf0ReadError::InvalidAmf3Reference(index)),
+            Header::Inline(len) => {
+                let string = Cow::Borrowed(std::str::from_utf8(self.read_bytes(len as usize)?)?);
+
+                // The empty string is never sent by reference.
+                if !string.is_empty() {
+                    self.strings.push(string.clone());
+                }
+
+                Ok(string)
+            }
+        }
+    }
+
+    /// Copies a referenced object, counting the values of the copy.
+    fn object_reference(&mut self, index: usize) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        let (value, count) = self.objects.get(index).ok_or(Amf0ReadError::InvalidAmf3Reference(index))?;
+        let (value, count) = (value.clone(), *count);
+
+        // The reference itself was already counted as one value.
+        self.count_values(count - 1)?;
+
+        Ok(value)
+    }
+
+    /// Reserves the slot of an object in the reference table before its members are read,
+    /// as they count towards the table as well. References back to the object itself decode as null.
+    ///
+    /// Returns the slot and the number of values decoded so far, for [`Amf3Decoder::fill_object`].
+    fn reserve_object(&mut self) -> (usize, usize) {
+        self.objects.push((Amf0Value::Null, 1));
+        (self.objects.len() - 1, self.values)
+    }
+
+    /// Stores an object in the slot reserved for it, with the number of values it decoded to.
+    fn fill_object(&mut self, (slot, values): (usize, usize), value: Amf0Value<'a>) {
+        // Includes the object itself, which was counted before its slot was reserved.
+        self.objects[slot] = (value, self.values - values + 1);
+    }
+
+    fn read_xml(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        let len = match self.read_header()? {
+            Header::Reference(index) => return self.object_reference(index),
+            Header::Inline(len) => len,
+        };
+
+        let value = Amf0Value::String(Cow::Borrowed(std::str::from_utf8(self.read_bytes(len as usize)?)?));
+        self.objects.push((value.clone(), 1));
+
+        Ok(value)
+    }
+
+    /// Dates become the number of milliseconds since the epoch.
+    fn read_date(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        if let Header::Reference(index) = self.read_header()? {
+            return self.object_reference(index);
+        }
+
+        let value = Amf0Value::Number(self.cursor.read_f64::<BigEndian>()?);
+        self.objects.push((value.clone(), 1));
+
+        Ok(value)
+    }
+
+    /// Dense arrays become strict arrays. AMF0 has no arrays with both parts,
+    /// so those become objects with the dense values keyed by their index.
+    fn read_array(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        let len = match self.read_header()? {
+            Header::Reference(index) => return self.object_reference(index),
+            Header::Inline(len) => len as usize,
+        };
+
+        let slot = self.reserve_object();
+
+        let mut associative = Vec::new();
+        loop {
+            let key = self.read_string()?;
+            if key.is_empty() {
+                break;
+            }
+
+            associative.push((key, self.decode()?));
+        }
+
+        // Do not trust the length for the allocation, every value is at least 1 byte.
+        let mut dense = Vec::with_capacity(len.min(self.remaining()));
+        for _ in 0..len {
+            dense.push(self.decode()?);
+        }
+
+        let value = if associative.is_empty() {
+            Amf0Value::StrictArray(dense.into())
+        } else {
+            associative.extend(
+                dense
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, value)| (Cow::Owned(index.to_string()), value)),
+            );
+            Amf0Value::Object(associative.into())
+        };
+        self.fill_object(slot, value.clone());
+
+        Ok(value)
+    }
+
+    /// Objects become AMF0 objects with their sealed members followed by their dynamic members.
+    /// The class name is dropped.
+    fn read_object(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
+        let header = match self.read_header()? {
+            Header::Reference(index) => return self.object_reference(index),
+            Header::Inline(header) => header,
+        };
+
+        let traits = if header & 0b1 == 0 {
+            let index = (header >> 1) as usize;
+            self.traits
+                .get(index)
+                .cloned()
+                .ok_or(Amf0ReadError::InvalidAmf3Reference(index))?
+        } else if header & 0b10 != 0 {
+            // Externalizable objects are encoded by the class itself, so they cannot be read without knowing it.
+            return Err(Amf0ReadError::UnsupportedAmf3Type(OBJECT));
+        } else {
+            let _class_name = self.read_string()?;
+
+            let mut sealed = Vec::new();
+            for _ in 0..header >> 3 {
+                sealed.push(self.read_string()?);
+            }
+
+            let traits = Traits {
+                dynamic: header & 0b100 != 0,
+                sealed,
+            };
+            self.traits.push(traits.clone());
+            traits
+        };
+
+        let slot = self.reserve_object();
+
+        let mut properties = Vec::with_capacity(traits.sealed.len());
+        for key in traits.sealed {
+            properties.push((key, self.decode()?));
+        }
+
+        if traits.dynamic {
+            loop {
+                let key = self.read_string()?;
+                if key.is_empty() {
+                    break;
+                }
+
+                properties.push((key, self.decode()?));
+            }
+        }
+
+        let value = Amf0Value::Object(properties.into());
+        self.fill_object(slot, value.clone());
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::*;
+    use crate::{Amf0Decoder, Amf0Marker};
+
+    fn decode(amf3: &[u8]) -> Result<Amf0Value<'_>, Amf0ReadError> {
+        let mut decoder = Amf0Decoder::new(amf3);
+        let value = decoder.decode()?;
+        assert!(decoder.is_empty());
+        Ok(value)
+    }
+
+    #[test]
+    fn test_decode_scalars() {
+        let cases: [(&[u8], Amf0Value); 8] = [
+            (&[0x11, UNDEFINED], Amf0Value::Null),
+            (&[0x11, NULL], Amf0Value::Null),
+            (&[0x11, FALSE], Amf0Value::Boolean(false)),
+            (&[0x11, TRUE], Amf0Value::Boolean(true)),
+            (&[0x11, INTEGER, 0x7f], Amf0Value::Number(127.0)),
+            (&[0x11, INTEGER, 0x81, 0x00], Amf0Value::Number(128.0)),
+            // The largest negative number, -2^28.
+            (&[0x11, INTEGER, 0xc0, 0x80, 0x80, 0x00], Amf0Value::Number(-268_435_456.0)),
+            (&[0x11, INTEGER, 0xff, 0xff, 0xff, 0xff], Amf0Value::Number(-1.0)),
+        ];
+
+        for (amf3, expected) in cases {
+            assert_eq!(decode(amf3).unwrap(), expected);
+        }
+
+        let mut amf3 = vec![0x11, DOUBLE];
+        amf3.extend_from_slice(&1.5_f64.to_be_bytes());
+        assert_eq!(decode(&amf3).unwrap(), Amf0Value::Number(1.5));
+
+        let mut amf3 = vec![0x11, DATE, 0x01];
+        amf3.extend_from_slice(&1000.0_f64.to_be_bytes());
+        assert_eq!(decode(&amf3).unwrap(), Amf0Value::Number(1000.0));
+
+        let amf3 = [0x11, XML, 0x07, b'<', b'a', b'/'];
+        assert_eq!(decode(&amf3).unwrap(), Amf0Value::String("<a/".into()));
+    }
+
+    #[test]
+    fn test_decode_array() {
+        // Dense array of two strings, the second a reference to the first.
+        let amf3 = [0x11, ARRAY, 0x05, 0x01, STRING, 0x07, b'a', b'b', b'c', STRING, 0x00];
+        assert_eq!(
+            decode(&amf3).unwrap(),
+            Amf0Value::StrictArray(vec![Amf0Value::String("abc".into()), Amf0Value::String("abc".into())].into())
+        );
+
+        // Mixed array with the key "k" and one dense value.
+        let amf3 = [0x11, ARRAY, 0x03, 0x03, b'k', TRUE, 0x01, FALSE];
+        assert_eq!(
+            decode(&amf3).unwrap(),
+            Amf0Value::Object(
+                vec![
+                    ("k".into(), Amf0Value::Boolean(true)),
+                    ("0".into(), Amf0Value::Boolean(false))
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_object() {
+        #[rustfmt::skip]
+        let amf3 = [
+            0x11, ARRAY, 0x05, 0x01,
+            // Dynamic object of an anonymous class with the sealed member "app" and the dynamic member "x".
+            OBJECT, 0x1b, 0x01, 0x07, b'a', b'p', b'p',
+            STRING, 0x09, b'l', b'i', b'v', b'e',
+            0x03, b'x', INTEGER, 0x01,
+            0x01,
+            // An object with the same traits, by reference.
+            OBJECT, 0x01, STRING, 0x02,
+            0x01,
+        ];
+
+        assert_eq!(
+            decode(&amf3).unwrap(),
+            Amf0Value::StrictArray(
+                vec![
+                    Amf0Value::Object(
+                        vec![
+                            ("app".into(), Amf0Value::String("live".into())),
+                            ("x".into(), Amf0Value::Number(1.0)),
+                        ]
+                        .into()
+                    ),
+                    Amf0Value::Object(vec![("app".into(), Amf0Value::String("live".into()))].into()),
+                ]
+                .into()
+            )
+        );
+
+        // The array is object 0, and the object inside it object 1.
+        let amf3 = [0x11, ARRAY, 0x05, 0x01, OBJECT, 0x0b, 0x01, 0x01, OBJECT, 0x02];
+        assert_eq!(
+            decode(&amf3).unwrap(),
+            Amf0Value::StrictArray(vec![Amf0Value::Object(vec![].into()), Amf0Value::Object(vec![].into())].into())
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // Byte array
+        assert!(matches!(
+            decode(&[0x11, 0x0c, 0x01]),
+            Err(Amf0ReadError::UnsupportedAmf3Type(0x0c))
+        ));
+        // Externalizable object
+        assert!(matches!(
+            decode(&[0x11, OBJECT, 0x07, 0x01]),
+            Err(Amf0ReadError::UnsupportedAmf3Type(OBJECT))
+        ));
+        assert!(matches!(
+            decode(&[0x11, STRING, 0x00]),
+            Err(Amf0ReadError::InvalidAmf3Reference(0))
+        ));
+        assert!(matches!(
+            decode(&[0x11, OBJECT, 0x01]),
+            Err(Amf0ReadError::InvalidAmf3Reference(0))
+        ));
+        assert!(matches!(decode(&[0x11, STRING, 0x7f]), Err(Amf0ReadError::Io(_))));
+    }
+
+    /// An array of `levels` arrays, each holding two references to the one before it.
+    fn doubling_references(levels: u8) -> Vec<u8> {
+        let mut amf3 = vec![0x11, ARRAY, (levels << 1) | 1, 0x01, ARRAY, 0x01, 0x01];
+        // The outer array is object 0 and the empty array object 1.
+        for level in 1..levels {
+            amf3.extend_from_slice(&[ARRAY, 0x05, 0x01, ARRAY, level << 1, ARRAY, level << 1]);
+        }
+        amf3
+    }
+
+    #[test]
+    fn test_decode_reference_limit() {
+        let amf3 = doubling_references(8);
+        let value = decode(&amf3).unwrap();
+        let Amf0Value::StrictArray(levels) = value else {
+            panic!("expected a strict array");
+        };
+        assert_eq!(levels.len(), 8);
+
+        // Would decode to about 2^40 values.
+        assert!(matches!(decode(&doubling_references(40)), Err(Amf0ReadError::Amf3TooLarge)));
+    }
+
+    #[test]
+    fn test_decode_depth_limit() {
+        let nested = |depth: usize| {
+            let mut amf3 = vec![0x11];
+            for _ in 0..depth {
+                amf3.extend_from_slice(&[ARRAY, 0x03, 0x01]);
+            }
+            amf3.push(NULL);
+            amf3
+        };
+
+        assert!(decode(&nested(MAX_DEPTH - 1)).is_ok());
+        assert!(matches!(decode(&nested(MAX_DEPTH)), Err(Amf0ReadError::Amf3TooDeep)));
+        assert!(matches!(decode(&nested(20_000)), Err(Amf0ReadError::Amf3TooDeep)));
+    }
+
+    #[test]
+    fn test_decode_with_type() {
+        let amf3 = [0x11, STRING, 0x03, b'a'];
+
+        let mut decoder = Amf0Decoder::new(&amf3);
+        assert!(matches!(
+            decoder.decode_with_type(Amf0Marker::Object),
+            Err(Amf0ReadError::WrongType(Amf0Marker::Object, Amf0Marker::String))
+        ));
+        assert_eq!(
+            decoder.decode_with_type(Amf0Marker::String).unwrap(),
+            Amf0Value::String("a".into())
+        );
+        assert!(decoder.is_empty());
+    }
+}
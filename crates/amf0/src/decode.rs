@@ -4,7 +4,8 @@ use std::io::{Cursor, Seek, SeekFrom};
 use byteorder::{BigEndian, ReadBytesExt};
 use num_traits::FromPrimitive;
 
-use super::{Amf0Marker, Amf0ReadError, Amf0Value};
+use super::amf3::Amf3Decoder;
+use super::{Amf0Interner, Amf0Marker, Amf0ReadError, Amf0Value};
 
 /// An AMF0 Decoder.
 ///
@@ -46,6 +47,9 @@ impl<'a> Amf0Decoder<'a> {
     }
 
     /// Read the next encoded value from the decoder.
+    ///
+    /// AMF3 values, switched to with the [`Amf0Marker::AVMPlusObject`] marker, are
+    /// converted to the closest AMF0 value.
     pub fn decode(&mut self) -> Result<Amf0Value<'a>, Amf0ReadError> {
         let marker = self.cursor.read_u8()?;
         let marker = Amf0Marker::from_u8(marker).ok_or(Amf0ReadError::UnknownMarker(marker))?;
@@ -58,10 +62,20 @@ impl<'a> Amf0Decoder<'a> {
             Amf0Marker::Null => Ok(Amf0Value::Null),
             Amf0Marker::EcmaArray => Ok(Amf0Value::Object(self.read_ecma_array()?.into())),
             Amf0Marker::LongString => Ok(Amf0Value::LongString(self.read_long_string()?)),
+            Amf0Marker::StrictArray => Ok(Amf0Value::StrictArray(self.read_strict_array()?.into())),
+            Amf0Marker::AVMPlusObject => Amf3Decoder::new(&mut self.cursor).decode(),
             _ => Err(Amf0ReadError::UnsupportedType(marker)),
         }
     }
 
+    /// Read the next encoded value from the decoder, converted to `'static` without
+    /// allocating the strings known to `interner`.
+    ///
+    /// Use this over [`Amf0Value::to_owned`] for values that are kept around.
+    pub fn decode_interned(&mut self, interner: &Amf0Interner) -> Result<Amf0Value<'static>, Amf0ReadError> {
+        Ok(interner.intern_value(&self.decode()?))
+    }
+
     /// Read the next encoded value from the decoder and check if it matches the
     /// specified marker.
     pub fn decode_with_type(&mut self, specified_marker: Amf0Marker) -> Result<Amf0Value<'a>, Amf0ReadError> {
@@ -69,6 +83,18 @@ impl<'a> Amf0Decoder<'a> {
         self.cursor.seek(SeekFrom::Current(-1))?; // seek back to the original position
 
         let marker = Amf0Marker::from_u8(marker).ok_or(Amf0ReadError::UnknownMarker(marker))?;
+        if marker == Amf0Marker::AVMPlusObject && specified_marker != Amf0Marker::AVMPlusObject {
+            // The type of an AMF3 value is only known once it is converted.
+            let position = self.cursor.position();
+            let value = self.decode()?;
+            if value.marker() != specified_marker {
+                self.cursor.set_position(position);
+                return Err(Amf0ReadError::WrongType(specified_marker, value.marker()));
+            }
+
+            return Ok(value);
+        }
+
         if marker != specified_marker {
             return Err(Amf0ReadError::WrongType(specified_marker, marker));
         }
@@ -141,6 +167,19 @@ impl<'a> Amf0Decoder<'a> {
         Ok(properties)
     }
 
+    fn read_strict_array(&mut self) -> Result<Vec<Amf0Value<'a>>, Amf0ReadError> {
+        let len = self.cursor.read_u32::<BigEndian>()?;
+
+        // Do not trust the length for the allocation, every value is at least 1 byte.
+        let mut values = Vec::with_capacity((len as usize).min(self.cursor.get_ref().len()));
+
+        for _ in 0..len {
+            values.push(self.decode()?);
+        }
+
+        Ok(values)
+    }
+
     fn read_long_string(&mut self) -> Result<Cow<'a, str>, Amf0ReadError> {
         let l = self.cursor.read_u32::<BigEndian>()?;
 
@@ -232,6 +271,27 @@ mod tests {
         assert_eq!(value, Amf0Value::Object(vec![("test".into(), Amf0Value::Null)].into()));
     }
 
+    #[test]
+    fn test_reader_strict_array() {
+        let mut amf0_array = vec![0x0a, 0x00, 0x00, 0x00, 0x02]; // 2 values
+        amf0_array.extend_from_slice(&[0x02, 0x00, 0x04]); // 4 bytes
+        amf0_array.extend_from_slice(b"avc1");
+        amf0_array.extend_from_slice(&[0x05]); // null
+
+        let mut amf_reader = Amf0Decoder::new(&amf0_array);
+        let value = amf_reader.decode_with_type(Amf0Marker::StrictArray).unwrap();
+
+        assert_eq!(
+            value,
+            Amf0Value::StrictArray(vec![Amf0Value::String("avc1".into()), Amf0Value::Null].into())
+        );
+
+        // The length is larger than the remaining data.
+        let amf0_array = vec![0x0a, 0xff, 0xff, 0xff, 0xff, 0x05];
+        let mut amf_reader = Amf0Decoder::new(&amf0_array);
+        assert!(amf_reader.decode().is_err());
+    }
+
     #[test]
     fn test_reader_multi_value() {
         let mut amf0_multi = vec![0x00];
@@ -20,6 +20,7 @@ impl Amf0Encoder {
             Amf0Value::Number(val) => Self::encode_number(writer, *val),
             Amf0Value::String(val) => Self::encode_string(writer, val),
             Amf0Value::Object(val) => Self::encode_object(writer, val),
+            Amf0Value::StrictArray(val) => Self::encode_strict_array(writer, val),
             _ => Err(Amf0WriteError::UnsupportedType(value.marker())),
         }
     }
@@ -76,6 +77,17 @@ impl Amf0Encoder {
         Self::object_eof(writer)?;
         Ok(())
     }
+
+    /// Encode an AMF0 strict array
+    pub fn encode_strict_array(writer: &mut impl io::Write, values: &[Amf0Value<'_>]) -> Result<(), Amf0WriteError> {
+        writer.write_u8(Amf0Marker::StrictArray as u8)?;
+        writer.write_u32::<BigEndian>(values.len() as u32)?;
+        for value in values {
+            Self::encode(writer, value)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +181,22 @@ mod tests {
         assert_eq!(vec, amf0_object);
     }
 
+    #[test]
+    fn test_encode_strict_array() {
+        let mut amf0_array = vec![Amf0Marker::StrictArray as u8, 0x00, 0x00, 0x00, 0x02];
+        amf0_array.push(Amf0Marker::Boolean as u8);
+        amf0_array.push(0x01);
+        amf0_array.push(Amf0Marker::Null as u8);
+        let mut vec = Vec::<u8>::new();
+
+        Amf0Encoder::encode(
+            &mut vec,
+            &Amf0Value::StrictArray(vec![Amf0Value::Boolean(true), Amf0Value::Null].into()),
+        )
+        .unwrap();
+        assert_eq!(vec, amf0_array);
+    }
+
     #[test]
     fn test_encode_generic_error_unsupported_type() {
         let mut writer = Vec::<u8>::new();
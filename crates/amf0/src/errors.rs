@@ -22,6 +22,19 @@ pub enum Amf0ReadError {
     /// type.
     #[error("wrong type: expected {0:?}, got {1:?}")]
     WrongType(Amf0Marker, Amf0Marker),
+    /// An AMF3 value without an AMF0 equivalent was encountered, such as a
+    /// byte array or an externalizable object.
+    #[error("unsupported amf3 type: {0}")]
+    UnsupportedAmf3Type(u8),
+    /// An AMF3 value referenced a string, object or traits that were not read before.
+    #[error("invalid amf3 reference: {0}")]
+    InvalidAmf3Reference(usize),
+    /// An AMF3 value decoded to too many values, such as through repeated references.
+    #[error("amf3 value too large")]
+    Amf3TooLarge,
+    /// An AMF3 value nested arrays or objects too deeply.
+    #[error("amf3 value nested too deeply")]
+    Amf3TooDeep,
 }
 
 /// Errors that can occur when encoding AMF0 data.
@@ -69,6 +82,10 @@ mod tests {
                 Amf0ReadError::Io(Cursor::new(Vec::<u8>::new()).read_u8().unwrap_err()),
                 "io error: failed to fill whole buffer",
             ),
+            (Amf0ReadError::UnsupportedAmf3Type(12), "unsupported amf3 type: 12"),
+            (Amf0ReadError::InvalidAmf3Reference(3), "invalid amf3 reference: 3"),
+            (Amf0ReadError::Amf3TooLarge, "amf3 value too large"),
+            (Amf0ReadError::Amf3TooDeep, "amf3 value nested too deeply"),
         ];
 
         for (err, expected) in cases {
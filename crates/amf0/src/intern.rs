@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::Amf0Value;
+
+/// An interner for frequently seen AMF0 strings.
+///
+/// Values decoded by [`Amf0Decoder`](crate::Amf0Decoder) borrow from the decoded buffer,
+/// so keeping them around means converting them to `'static`, which allocates every
+/// string. Strings known to the interner are instead converted to their `&'static str`,
+/// which makes command names and object keys free to keep.
+///
+/// An interner is read only once built, so it can be shared between decoders, for example
+/// behind an [`Arc`](std::sync::Arc).
+#[derive(Debug, Clone, Default)]
+pub struct Amf0Interner {
+    strings: HashSet<&'static str>,
+}
+
+impl Amf0Interner {
+    /// Create a new interner without any strings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a string to the interner.
+    pub fn insert(&mut self, string: &'static str) {
+        self.strings.insert(string);
+    }
+
+    /// Returns the number of strings in the interner.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns true if the interner has no strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the interned copy of `string`, if it is known.
+    pub fn get(&self, string: &str) -> Option<&'static str> {
+        self.strings.get(string).copied()
+    }
+
+    /// Convert a string to `'static`, only allocating if it is not known.
+    pub fn intern(&self, string: &str) -> Cow<'static, str> {
+        match self.get(string) {
+            Some(interned) => Cow::Borrowed(interned),
+            None => Cow::Owned(string.to_owned()),
+        }
+    }
+
+    /// Convert a value to `'static`, like [`Amf0Value::to_owned`], interning all strings
+    /// and object keys.
+    pub fn intern_value(&self, value: &Amf0Value<'_>) -> Amf0Value<'static> {
+        match value {
+            Amf0Value::String(s) => Amf0Value::String(self.intern(s)),
+            Amf0Value::LongString(s) => Amf0Value::LongString(self.intern(s)),
+            Amf0Value::Object(o) => {
+                Amf0Value::Object(o.iter().map(|(k, v)| (self.intern(k), self.intern_value(v))).collect())
+            }
+            Amf0Value::StrictArray(a) => Amf0Value::StrictArray(a.iter().map(|v| self.intern_value(v)).collect()),
+            Amf0Value::Number(n) => Amf0Value::Number(*n),
+            Amf0Value::Boolean(b) => Amf0Value::Boolean(*b),
+            Amf0Value::Null => Amf0Value::Null,
+            Amf0Value::ObjectEnd => Amf0Value::ObjectEnd,
+        }
+    }
+}
+
+impl FromIterator<&'static str> for Amf0Interner {
+    fn from_iter<T: IntoIterator<Item = &'static str>>(iter: T) -> Self {
+        Self {
+            strings: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<&'static str> for Amf0Interner {
+    fn extend<T: IntoIterator<Item = &'static str>>(&mut self, iter: T) {
+        self.strings.extend(iter);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::Amf0Interner;
+    use crate::{Amf0Decoder, Amf0Encoder, Amf0Value};
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Amf0Interner::from_iter(["connect", "app"]);
+        assert_eq!(interner.len(), 2);
+        assert!(!interner.is_empty());
+        assert!(Amf0Interner::new().is_empty());
+
+        interner.insert("tcUrl");
+        interner.extend(["flashVer"]);
+        assert_eq!(interner.len(), 4);
+
+        assert_eq!(interner.get(&String::from("tcUrl")), Some("tcUrl"));
+        assert_eq!(interner.get("swfUrl"), None);
+
+        assert!(matches!(interner.intern("app"), Cow::Borrowed("app")));
+        assert!(matches!(interner.intern("live"), Cow::Owned(s) if s == "live"));
+    }
+
+    #[test]
+    fn test_interner_value() {
+        let interner = Amf0Interner::from_iter(["connect", "app"]);
+
+        let mut buf = Vec::new();
+        Amf0Encoder::encode_string(&mut buf, "connect").unwrap();
+        Amf0Encoder::encode_object(
+            &mut buf,
+            &[
+                ("app".into(), Amf0Value::String("live".into())),
+                (
+                    "list".into(),
+                    Amf0Value::StrictArray(Cow::Owned(vec![Amf0Value::String("app".into())])),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let mut decoder = Amf0Decoder::new(&buf);
+        let command = decoder.decode_interned(&interner).unwrap();
+        let object = decoder.decode_interned(&interner).unwrap();
+        drop(buf);
+
+        assert!(matches!(command, Amf0Value::String(Cow::Borrowed("connect"))));
+
+        let Amf0Value::Object(properties) = object else {
+            panic!("expected object");
+        };
+        assert!(matches!(properties[0].0, Cow::Borrowed("app")));
+        assert!(matches!(&properties[0].1, Amf0Value::String(Cow::Owned(s)) if s == "live"));
+        assert!(matches!(properties[1].0, Cow::Owned(_)));
+        let Amf0Value::StrictArray(list) = &properties[1].1 else {
+            panic!("expected strict array");
+        };
+        assert!(matches!(list[0], Amf0Value::String(Cow::Borrowed("app"))));
+    }
+}
@@ -29,12 +29,15 @@
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
+mod amf3;
 mod decode;
 mod define;
 mod encode;
 mod errors;
+mod intern;
 
 pub use crate::decode::Amf0Decoder;
 pub use crate::define::{Amf0Marker, Amf0Value};
 pub use crate::encode::Amf0Encoder;
 pub use crate::errors::{Amf0ReadError, Amf0WriteError};
+pub use crate::intern::Amf0Interner;
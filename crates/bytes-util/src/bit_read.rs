@@ -188,6 +188,33 @@ impl<W: io::Seek + io::Read> BitReader<W> {
 
         Ok(pos)
     }
+
+    /// Seeks to a position in bits from the start of the stream, such as one returned by
+    /// [`BitReader::bit_stream_position`].
+    ///
+    /// Together they can be used to bookmark a position and return to it after reading ahead.
+    pub fn seek_to_bit(&mut self, pos: u64) -> io::Result<()> {
+        self.bit_pos = 0;
+        self.data.seek(io::SeekFrom::Start(pos / 8))?;
+
+        // Same as in seek_bits, a partially read byte has to be read already.
+        if !pos.is_multiple_of(8) {
+            self.update_byte()?;
+            self.bit_pos = (pos % 8) as u8;
+        }
+
+        Ok(())
+    }
+
+    /// Reads multiple bits without consuming them
+    ///
+    /// The position is restored even if there are not enough bits left, in which case an error is returned.
+    pub fn peek_bits(&mut self, count: u8) -> io::Result<u64> {
+        let pos = self.bit_stream_position()?;
+        let bits = self.read_bits(count);
+        self.seek_to_bit(pos)?;
+        bits
+    }
 }
 
 impl<T: io::Seek + io::Read> io::Seek for BitReader<T> {
@@ -363,4 +390,50 @@ mod tests {
         assert_eq!(reader.bit_pos(), 1);
         assert_eq!(reader.data.stream_position().unwrap(), 4);
     }
+
+    #[test]
+    fn test_bit_reader_peek_bits() {
+        let binary = 0b10101010110011001111000101010101u32;
+        let mut reader = BitReader::new_from_slice(binary.to_be_bytes());
+
+        assert_eq!(reader.peek_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.bit_stream_position().unwrap(), 0);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+
+        // across a byte boundary
+        assert_eq!(reader.peek_bits(9).unwrap(), 0b010101100);
+        assert_eq!(reader.bit_stream_position().unwrap(), 3);
+        assert_eq!(reader.bit_pos(), 3);
+        assert_eq!(reader.read_bits(9).unwrap(), 0b010101100);
+
+        // not enough bits left, the position is kept
+        assert!(reader.peek_bits(64).is_err());
+        assert_eq!(reader.bit_stream_position().unwrap(), 12);
+        assert_eq!(reader.read_bits(4).unwrap(), 0b1100);
+    }
+
+    #[test]
+    fn test_bit_reader_seek_to_bit() {
+        let binary = 0b10101010110011001111000101010101u32;
+        let mut reader = BitReader::new_from_slice(binary.to_be_bytes());
+
+        reader.read_bits(5).unwrap();
+        let checkpoint = reader.bit_stream_position().unwrap();
+        assert_eq!(checkpoint, 5);
+
+        assert_eq!(reader.read_bits(12).unwrap(), 0b010110011001);
+        reader.seek_to_bit(checkpoint).unwrap();
+        assert_eq!(reader.bit_stream_position().unwrap(), 5);
+        assert_eq!(reader.read_bits(12).unwrap(), 0b010110011001);
+
+        reader.seek_to_bit(16).unwrap();
+        assert!(reader.is_aligned());
+        assert_eq!(reader.read_bits(8).unwrap(), 0b11110001);
+
+        reader.seek_to_bit(0).unwrap();
+        assert_eq!(reader.read_bits(32).unwrap(), binary as u64);
+
+        // past the end
+        assert!(reader.seek_to_bit(33).is_err());
+    }
 }
@@ -0,0 +1,172 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::Poll;
+
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::Context;
+
+/// Keeps the cleanup registered with [`Context::on_cancel`] armed while it is alive.
+///
+/// Dropping the guard before the context is done removes the cleanup, which is
+/// what work that finished normally wants. Once the context is done, the
+/// cleanup runs to completion regardless of the guard.
+#[derive(Debug)]
+#[must_use = "the cleanup is removed when the guard is dropped"]
+pub struct CleanupGuard(DropGuard);
+
+impl CleanupGuard {
+    /// Keeps the cleanup registered until the context is done, without holding the guard.
+    pub fn detach(self) {
+        self.0.disarm();
+    }
+}
+
+impl Context {
+    /// Runs `cleanup` once this context is done, holding the context until it finishes.
+    ///
+    /// The cleanup counts as active work, so [`Handler::shutdown`](crate::Handler::shutdown)
+    /// waits for it. Meant for flushing state of work that is cancelled,
+    /// instead of racing the work against [`Context::done`] by hand.
+    ///
+    /// The cleanup is removed when the returned guard is dropped before the
+    /// context is done, see [`CleanupGuard`]. Until then the registration holds
+    /// a clone of the context, like any other future attached to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use scuffle_context::Context;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    /// let flushed = Arc::new(AtomicBool::new(false));
+    ///
+    /// let cleanup_flushed = flushed.clone();
+    /// let guard = ctx.on_cancel(async move {
+    ///     tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    ///     cleanup_flushed.store(true, Ordering::SeqCst);
+    /// });
+    ///
+    /// drop(ctx);
+    /// // Waits for the cleanup to finish.
+    /// handler.shutdown().await;
+    /// assert!(flushed.load(Ordering::SeqCst));
+    /// # drop(guard);
+    /// # });
+    /// ```
+    pub fn on_cancel<F>(&self, cleanup: F) -> CleanupGuard
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let ctx = self.clone();
+        let removed = CancellationToken::new();
+        let guard = CleanupGuard(removed.clone().drop_guard());
+
+        tokio::spawn(async move {
+            {
+                let mut done = pin!(ctx.done());
+                let mut removed = pin!(removed.cancelled());
+                // The context being done wins over the guard being dropped at the same time.
+                let cancelled = std::future::poll_fn(|cx| {
+                    if done.as_mut().poll(cx).is_ready() {
+                        return Poll::Ready(true);
+                    }
+
+                    removed.as_mut().poll(cx).map(|_| false)
+                })
+                .await;
+
+                if !cancelled {
+                    return;
+                }
+            }
+
+            cleanup.await;
+            drop(ctx);
+        });
+
+        guard
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use scuffle_future_ext::FutureExt;
+
+    use crate::Context;
+
+    fn cleanup(ran: &Arc<AtomicBool>) -> impl Future<Output = ()> + Send + 'static {
+        let ran = ran.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            ran.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn on_cancel() {
+        let (ctx, handler) = Context::new();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        let _guard = ctx.on_cancel(cleanup(&ran));
+        drop(ctx);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!ran.load(Ordering::SeqCst));
+
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(500))
+            .await
+            .expect("shutdown timed out");
+        assert!(ran.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn on_cancel_removed() {
+        let (ctx, handler) = Context::new();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        let guard = ctx.on_cancel(cleanup(&ran));
+        drop(guard);
+        drop(ctx);
+
+        // Let the registration see that the guard is gone.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(10))
+            .await
+            .expect("shutdown timed out");
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn on_cancel_detach() {
+        let (ctx, handler) = Context::new();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        ctx.on_cancel(cleanup(&ran)).detach();
+        drop(ctx);
+
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(500))
+            .await
+            .expect("shutdown timed out");
+        assert!(ran.load(Ordering::SeqCst));
+    }
+}
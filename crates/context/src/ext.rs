@@ -1,8 +1,10 @@
 use std::future::{Future, IntoFuture};
 use std::pin::Pin;
 use std::task::Poll;
+use std::time::Duration;
 
 use futures_lite::Stream;
+use tokio::time::Sleep;
 use tokio_util::sync::{WaitForCancellationFuture, WaitForCancellationFutureOwned};
 
 use crate::{Context, ContextTracker};
@@ -10,7 +12,13 @@ use crate::{Context, ContextTracker};
 /// A reference to a context which implements [`Future`] and can be polled.
 /// Can either be owned or borrowed.
 ///
-/// Create by using the [`From`] implementations.
+/// Create by using the [`From`] implementations or [`Context::as_ref`].
+///
+/// A borrowed reference does not clone the context, so it does not touch the
+/// tracker count or the cancellation token's reference count. The borrowed
+/// context keeps [`Handler::shutdown`](crate::Handler::shutdown) waiting for as
+/// long as the reference can exist, so nothing needs to be tracked per future.
+/// Prefer it on hot paths that wrap many short-lived futures.
 pub struct ContextRef<'a> {
     inner: ContextRefInner<'a>,
 }
@@ -87,11 +95,47 @@ impl<F: Future> Future for FutureWithContext<'_, F> {
     }
 }
 
+/// The error returned by a future wrapped with [`ContextFutExt::with_context_err`]
+/// when its context was done before the future finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the context was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pin_project_lite::pin_project! {
+    /// A future with a context attached to it, which resolves to an error when
+    /// the context is done.
+    ///
+    /// Created by [`ContextFutExt::with_context_err`].
+    pub struct FutureWithContextErr<'a, F> {
+        #[pin]
+        inner: FutureWithContext<'a, F>,
+    }
+}
+
+impl<F: Future> Future for FutureWithContextErr<'_, F> {
+    type Output = Result<F::Output, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        self.project().inner.poll(cx).map(|v| v.ok_or(Cancelled))
+    }
+}
+
 /// Extends a future with useful functions.
 pub trait ContextFutExt<Fut> {
     /// Wraps a future with a context and cancels the future when the context is
     /// done.
     ///
+    /// Passing an owned [`Context`] tracks the future once, for as long as the
+    /// returned future exists. Passing `&Context` (or [`Context::as_ref`])
+    /// borrows the context instead and costs no atomic operations.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -111,6 +155,33 @@ pub trait ContextFutExt<Fut> {
     fn with_context<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContext<'a, Fut>
     where
         Self: Sized;
+
+    /// Like [`with_context`](ContextFutExt::with_context), but resolves to
+    /// `Err(`[`Cancelled`]`)` instead of `None` when the context is done.
+    ///
+    /// This lets cancellation flow through `?` and error types that wrap
+    /// [`std::error::Error`], such as `anyhow::Error`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Cancelled, Context, ContextFutExt};
+    /// # tokio_test::block_on(async {
+    /// async fn work(ctx: &Context) -> Result<u32, Box<dyn std::error::Error>> {
+    ///     let value = std::future::pending::<u32>().with_context_err(ctx).await?;
+    ///     Ok(value)
+    /// }
+    ///
+    /// let (ctx, handler) = Context::new();
+    /// handler.cancel();
+    ///
+    /// let err = work(&ctx).await.unwrap_err();
+    /// assert!(err.is::<Cancelled>());
+    /// # });
+    /// ```
+    fn with_context_err<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContextErr<'a, Fut>
+    where
+        Self: Sized;
 }
 
 impl<F: IntoFuture> ContextFutExt<F::IntoFuture> for F {
@@ -124,6 +195,15 @@ impl<F: IntoFuture> ContextFutExt<F::IntoFuture> for F {
             _marker: std::marker::PhantomData,
         }
     }
+
+    fn with_context_err<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContextErr<'a, F::IntoFuture>
+    where
+        F: IntoFuture,
+    {
+        FutureWithContextErr {
+            inner: self.with_context(ctx),
+        }
+    }
 }
 
 pin_project_lite::pin_project! {
@@ -157,6 +237,61 @@ impl<F: Stream> Stream for StreamWithContext<'_, F> {
     }
 }
 
+pin_project_lite::pin_project! {
+    /// A stream with a context attached to it, which keeps yielding items for a
+    /// grace period after the context is done.
+    ///
+    /// Created by [`ContextStreamExt::with_context_graceful`].
+    pub struct StreamWithContextGraceful<'a, F> {
+        #[pin]
+        stream: F,
+        #[pin]
+        ctx: ContextRefInner<'a>,
+        grace: Duration,
+        deadline: Option<Pin<Box<Sleep>>>,
+        done: bool,
+        _marker: std::marker::PhantomData<&'a ()>,
+    }
+}
+
+impl<F: Stream> Stream for StreamWithContextGraceful<'_, F> {
+    type Item = F::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.deadline.is_none() && this.ctx.poll(cx).is_ready() {
+            *this.deadline = Some(Box::pin(tokio::time::sleep(*this.grace)));
+        }
+
+        if let Some(deadline) = this.deadline.as_mut()
+            && deadline.as_mut().poll(cx).is_ready()
+        {
+            *this.done = true;
+            return Poll::Ready(None);
+        }
+
+        let item = this.stream.poll_next(cx);
+        if matches!(item, Poll::Ready(None)) {
+            *this.done = true;
+        }
+
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, self.stream.size_hint().1)
+        }
+    }
+}
+
 /// Extends a stream with useful functions.
 pub trait ContextStreamExt<Stream> {
     /// Wraps a stream with a context and stops the stream when the context is
@@ -185,6 +320,42 @@ pub trait ContextStreamExt<Stream> {
     fn with_context<'a>(self, ctx: impl Into<ContextRef<'a>>) -> StreamWithContext<'a, Stream>
     where
         Self: Sized;
+
+    /// Wraps a stream with a context and stops the stream once the context is
+    /// done and the grace period has passed.
+    ///
+    /// Until the grace period is over the stream is polled as usual, so items
+    /// that are already buffered or in flight, such as packets queued in a
+    /// channel, can still be drained. The stream ends early if it ends by itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextStreamExt};
+    /// # use futures_lite::StreamExt;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    /// tx.send(1).unwrap();
+    /// tx.send(2).unwrap();
+    ///
+    /// let stream = futures_lite::stream::unfold(rx, |mut rx| async move { Some((rx.recv().await?, rx)) })
+    ///     .with_context_graceful(ctx, std::time::Duration::from_millis(100));
+    ///
+    /// handler.cancel();
+    ///
+    /// // The buffered items are still received before the stream ends.
+    /// assert_eq!(stream.collect::<Vec<_>>().await, vec![1, 2]);
+    /// # });
+    /// ```
+    fn with_context_graceful<'a>(
+        self,
+        ctx: impl Into<ContextRef<'a>>,
+        grace: Duration,
+    ) -> StreamWithContextGraceful<'a, Stream>
+    where
+        Self: Sized;
 }
 
 impl<F: Stream> ContextStreamExt<F> for F {
@@ -195,6 +366,17 @@ impl<F: Stream> ContextStreamExt<F> for F {
             _marker: std::marker::PhantomData,
         }
     }
+
+    fn with_context_graceful<'a>(self, ctx: impl Into<ContextRef<'a>>, grace: Duration) -> StreamWithContextGraceful<'a, F> {
+        StreamWithContextGraceful {
+            stream: self,
+            ctx: ctx.into().inner,
+            grace,
+            deadline: None,
+            done: false,
+            _marker: std::marker::PhantomData,
+        }
+    }
 }
 
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
@@ -205,7 +387,7 @@ mod tests {
     use futures_lite::{Stream, StreamExt};
     use scuffle_future_ext::FutureExt;
 
-    use super::{Context, ContextFutExt, ContextStreamExt};
+    use super::{Cancelled, Context, ContextFutExt, ContextStreamExt};
 
     #[tokio::test]
     async fn future() {
@@ -261,6 +443,66 @@ mod tests {
         task.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn future_ctx_as_ref() {
+        let (ctx, handler) = Context::new();
+        let active_count = || ctx.tracker.0.active_count.load(std::sync::atomic::Ordering::Relaxed);
+        let before = active_count();
+
+        for i in 0..1000 {
+            let result = async {
+                // Borrowing does not create a new tracker.
+                assert_eq!(active_count(), before);
+                i
+            }
+            .with_context(ctx.as_ref())
+            .await;
+
+            assert_eq!(result, Some(i));
+        }
+
+        assert_eq!(active_count(), before);
+
+        handler.cancel();
+        assert_eq!(std::future::pending::<()>().with_context(ctx.as_ref()).await, None);
+
+        drop(ctx);
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn future_err() {
+        let (ctx, handler) = Context::new();
+
+        assert_eq!(async { 1 }.with_context_err(&ctx).await, Ok(1));
+
+        handler.cancel();
+        assert_eq!(std::future::pending::<()>().with_context_err(&ctx).await, Err(Cancelled));
+
+        // A finished future wins over a cancelled context.
+        assert_eq!(async { 2 }.with_context_err(ctx).await, Ok(2));
+
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn future_err_question_mark() {
+        async fn work(ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
+            std::future::pending::<()>().with_context_err(ctx).await?;
+            Ok(())
+        }
+
+        let (ctx, handler) = Context::new();
+        handler.cancel();
+
+        let err = work(&ctx).await.unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert_eq!(err.to_string(), "the context was cancelled");
+
+        drop(ctx);
+        handler.shutdown().await;
+    }
+
     #[tokio::test]
     async fn stream() {
         let (ctx, handler) = Context::new();
@@ -284,6 +526,69 @@ mod tests {
         handler.shutdown().await;
     }
 
+    #[tokio::test]
+    async fn stream_graceful() {
+        let (ctx, handler) = Context::new();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        {
+            let mut stream = pin!(
+                futures_lite::stream::unfold(rx, |mut rx| async move { Some((rx.recv().await?, rx)) })
+                    .with_context_graceful(ctx, std::time::Duration::from_millis(100))
+            );
+
+            tx.send(0).unwrap();
+            assert_eq!(stream.next().await, Some(0));
+
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+            handler.cancel();
+
+            // Buffered and in flight items are still yielded during the grace period.
+            assert_eq!(stream.next().await, Some(1));
+            assert_eq!(stream.next().await, Some(2));
+            tx.send(3).unwrap();
+            assert_eq!(stream.next().await, Some(3));
+
+            // Then the stream ends, even though the channel is still open.
+            assert_eq!(
+                stream.next().with_timeout(std::time::Duration::from_secs(1)).await.unwrap(),
+                None
+            );
+            assert_eq!(stream.next().await, None);
+        }
+
+        drop(tx);
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn stream_graceful_ends() {
+        let (ctx, handler) = Context::new();
+
+        {
+            let mut stream =
+                pin!(futures_lite::stream::iter(0..2).with_context_graceful(ctx, std::time::Duration::from_secs(10)));
+
+            handler.cancel();
+
+            // A stream that ends by itself does not wait for the grace period.
+            assert_eq!(stream.next().await, Some(0));
+            assert_eq!(stream.next().await, Some(1));
+            assert_eq!(
+                stream
+                    .next()
+                    .with_timeout(std::time::Duration::from_millis(200))
+                    .await
+                    .unwrap(),
+                None
+            );
+            assert_eq!(stream.size_hint(), (0, Some(0)));
+        }
+
+        handler.shutdown().await;
+    }
+
     #[tokio::test]
     async fn pending_stream() {
         let (ctx, handler) = Context::new();
@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{ContextValues, Handler};
+
+/// Handlers that are shut down one after another, in the order they were added.
+///
+/// Each stage is a [`Handler`] that is not a child of the handler the group
+/// was created with, so cancelling that handler does not cancel the stages
+/// right away. Instead, a [`Handler::shutdown`] of it shuts the stages down in
+/// order, once its own contexts are done: every stage is cancelled only after
+/// all contexts and trackers of the previous stage are dropped and its
+/// [`Handler::on_shutdown`] futures ran. A server would for example stop
+/// accepting connections first, then wait for the sessions and flush its
+/// outputs last.
+///
+/// The stages inherit the values of the handler the group was created with.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use scuffle_context::{ContextFutExt, Handler, ShutdownGroup};
+/// # tokio_test::block_on(async {
+/// let handler = Handler::new();
+/// let group = ShutdownGroup::new(&handler);
+/// let listener = group.add_stage("listener").context();
+/// let sessions = group.add_stage("sessions").context();
+///
+/// let order = Arc::new(Mutex::new(Vec::new()));
+/// for (ctx, name) in [(listener, "listener"), (sessions, "sessions")] {
+///     let order = order.clone();
+///     tokio::spawn(async move {
+///         std::future::pending::<()>().with_context(ctx).await;
+///         order.lock().unwrap().push(name);
+///     });
+/// }
+///
+/// handler.shutdown().await;
+/// assert_eq!(*order.lock().unwrap(), ["listener", "sessions"]);
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShutdownGroup {
+    values: Option<Arc<ContextValues>>,
+    stages: Arc<Mutex<Vec<Handler>>>,
+}
+
+impl ShutdownGroup {
+    /// Creates a group without stages that is shut down with `handler`.
+    pub fn new(handler: &Handler) -> Self {
+        let group = Self {
+            values: handler.token.0.values.clone(),
+            stages: Arc::default(),
+        };
+
+        let stages = Arc::clone(&group.stages);
+        handler.on_shutdown(async move { shutdown_stages(&stages).await });
+
+        group
+    }
+
+    #[must_use]
+    /// Adds a named stage that is shut down after all stages added before it.
+    ///
+    /// A stage added after the group was shut down is not shut down by it.
+    pub fn add_stage(&self, name: impl Into<String>) -> Handler {
+        let stage = Handler::from_parts(self.values.clone(), Some(name.into().into()));
+        self.stages.lock().unwrap().push(stage.clone());
+        stage
+    }
+
+    /// Returns the stages in the order they are shut down.
+    pub fn stages(&self) -> Vec<Handler> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// Shuts down the stages in order, without waiting for the handler the
+    /// group was created with.
+    ///
+    /// The handler still shuts the stages down again once it is shut down,
+    /// which returns right away for stages that are already done.
+    pub async fn shutdown(&self) {
+        shutdown_stages(&self.stages).await;
+    }
+}
+
+async fn shutdown_stages(stages: &Mutex<Vec<Handler>>) {
+    let stages = stages.lock().unwrap().clone();
+    for stage in stages {
+        stage.shutdown().await;
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use scuffle_future_ext::FutureExt;
+
+    use crate::{ContextFutExt, ContextKey, Handler, ShutdownGroup};
+
+    #[tokio::test]
+    async fn shutdown_in_order() {
+        let handler = Handler::new();
+        let group = ShutdownGroup::new(&handler);
+        let first = group.add_stage("first");
+        let second = group.add_stage("second");
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // The first stage takes a while to finish after being cancelled.
+        let ctx = first.context();
+        let first_order = order.clone();
+        tokio::spawn(async move {
+            ctx.done().await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            first_order.lock().unwrap().push("first");
+        });
+
+        let ctx = second.context();
+        let second_order = order.clone();
+        tokio::spawn(async move {
+            ctx.done().await;
+            second_order.lock().unwrap().push("second");
+        });
+
+        handler.cancel();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!first.is_done());
+        assert!(!second.is_done());
+
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(500))
+            .await
+            .expect("shutdown timed out");
+        assert!(first.is_done());
+        assert!(second.is_done());
+        assert_eq!(*order.lock().unwrap(), ["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_after_handler_contexts() {
+        let handler = Handler::new();
+        let group = ShutdownGroup::new(&handler);
+        let stage = group.add_stage("stage");
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let ctx = handler.context();
+        let handler_order = order.clone();
+        tokio::spawn(async move {
+            std::future::pending::<()>().with_context(&ctx).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            handler_order.lock().unwrap().push("handler");
+            drop(ctx);
+        });
+
+        let stage_order = order.clone();
+        stage.on_shutdown(async move { stage_order.lock().unwrap().push("stage") });
+
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(500))
+            .await
+            .expect("shutdown timed out");
+        assert_eq!(*order.lock().unwrap(), ["handler", "stage"]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_group() {
+        let handler = Handler::new();
+        let group = ShutdownGroup::new(&handler);
+        let stages = [group.add_stage("a"), group.add_stage("b")];
+        assert_eq!(
+            group.stages().iter().map(|stage| stage.name().unwrap()).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+
+        group.shutdown().await;
+        assert!(stages.iter().all(Handler::is_done));
+        assert!(!handler.is_done());
+
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn inherits_values() {
+        struct Key;
+
+        impl ContextKey for Key {
+            type Value = u32;
+        }
+
+        let handler = Handler::new();
+        let ctx = handler.context().with_value::<Key>(1);
+        let (_child_ctx, child) = ctx.new_child();
+
+        let stage = ShutdownGroup::new(&child).add_stage("stage");
+        assert_eq!(stage.context().value::<Key>(), Some(&1));
+    }
+}
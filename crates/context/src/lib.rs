@@ -35,41 +35,151 @@
 //!
 //! `SPDX-License-Identifier: MIT OR Apache-2.0`
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::any::{Any, TypeId};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
+use std::time::Duration;
 
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 
+use crate::sync::{AtomicBool, AtomicUsize, Notify, fence};
+
+/// Cleanup that runs when a context is cancelled.
+mod cleanup;
+
+pub use cleanup::CleanupGuard;
+
 /// For extending types.
 mod ext;
 
 pub use ext::*;
 
-/// Create by calling [`ContextTrackerInner::child`].
+/// Shutting down handlers in stages.
+mod group;
+
+pub use group::ShutdownGroup;
+
+/// Cancellation across process boundaries.
+#[cfg(feature = "process")]
+#[cfg_attr(docsrs, doc(cfg(feature = "process")))]
+mod process;
+
+/// Restarting futures with backoff.
+mod retry;
+
+pub use retry::RetryPolicy;
+
+/// Spawning tasks tied to a context.
+mod spawn;
+
+pub use spawn::spawn;
+
+/// Synchronization primitives that loom can replace.
+mod sync;
+
+/// Cancellation on shutdown signals.
+#[cfg(feature = "signals")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signals")))]
+mod signals;
+
+#[cfg(feature = "signals")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signals")))]
+pub use signals::{ShutdownSignal, install_signal_handler};
+
+/// Request contexts for tower based servers, such as tonic and axum.
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+mod tower;
+
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub use tower::{ClientDisconnected, ContextLayer, ContextResponseFuture, ContextService};
+
+/// A guard that counts as active work for [`Handler::shutdown`] while it is alive.
+///
+/// Created by calling [`Context::track`]. Unlike a [`Context`] it is not tied to a
+/// future, so it can be moved into synchronous code or work spawned outside of
+/// the async runtime (FFI threads, rayon jobs) to hold the shutdown open until
+/// that work finishes. Dropping the guard releases it.
 #[derive(Debug)]
-struct ContextTracker(Arc<ContextTrackerInner>);
+#[must_use = "the work is only tracked while the guard is alive"]
+pub struct ContextTracker(Arc<ContextTrackerInner>);
 
 impl Drop for ContextTracker {
     fn drop(&mut self) {
-        let prev_active_count = self.0.active_count.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+        // SeqCst, see the contract on `ContextTrackerInner`.
+        let prev_active_count = self.0.active_count.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+        self.0.changed(prev_active_count - 1);
+        fence(std::sync::atomic::Ordering::SeqCst);
         // If this was the last active `ContextTracker` and the context has been
         // stopped, then notify the waiters
-        if prev_active_count == 1 && self.0.stopped.load(std::sync::atomic::Ordering::Relaxed) {
+        if prev_active_count == 1 && self.0.stopped.load(std::sync::atomic::Ordering::SeqCst) {
             self.0.notify.notify_waiters();
         }
     }
 }
 
+type TrackerCallback = Box<dyn Fn(usize) + Send + Sync>;
+
+/// The shared state of the trackers of a handler.
+///
+/// # Memory ordering
+///
+/// [`ContextTrackerInner::wait`] must not miss the drop of the last tracker.
+/// The waiter stores `stopped` and then loads `active_count`, while the last
+/// tracker decrements `active_count` and then loads `stopped`. This is the
+/// store buffering pattern, where with Acquire/Release (or Relaxed) both loads
+/// may read the old value: the waiter sees one active tracker and the tracker
+/// sees a handler that is not stopped, so nobody sends the notification and
+/// the waiter hangs. Both sides therefore put a SeqCst fence between their write
+/// and their read. The fences are in a single total order, and the side whose
+/// fence comes second sees the write of the other:
+///
+/// - If the waiter loads `active_count` after the decrement, it sees 0 and
+///   returns without waiting.
+/// - Otherwise its store of `stopped` comes before the decrement, so the
+///   tracker sees `stopped` and calls `notify_waiters`. The waiter created its
+///   [`Notified`](tokio::sync::futures::Notified) future before loading the
+///   count, so it receives that notification even if it is not polled yet.
+///
+/// [`WeakContext::upgrade`] relies on the same argument with the increment in
+/// [`ContextTrackerInner::child`]: either it sees `stopped` and gives up the new
+/// tracker, or the waiter sees the new tracker and waits for it.
+///
+/// The accesses themselves are SeqCst as well, which alone would be enough, but
+/// loom only checks the argument through the fences. The models are in
+/// `loom_tests`, run with `just loom`.
+///
+/// Work done before a tracker is dropped happens before `wait` returns, through
+/// the SeqCst (and so Release) decrement read by the SeqCst (and so Acquire)
+/// load in `wait`, or through the notification. `has_on_change` is only a hint
+/// to skip the lock, the lock itself orders the callback.
 #[derive(Debug)]
 struct ContextTrackerInner {
     stopped: AtomicBool,
     /// This count keeps track of the number of `ContextTrackers` that exist for
     /// this `ContextTrackerInner`.
     active_count: AtomicUsize,
-    notify: tokio::sync::Notify,
+    notify: Notify,
+    /// Set when `on_change` holds a callback, so the lock is skipped otherwise.
+    has_on_change: AtomicBool,
+    /// Called with the new active count, set by [`Handler::on_tracker_change`].
+    on_change: RwLock<Option<DebugCallback>>,
+}
+
+/// A [`TrackerCallback`] that implements [`Debug`](std::fmt::Debug).
+struct DebugCallback(TrackerCallback);
+
+impl std::fmt::Debug for DebugCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrackerCallback").finish_non_exhaustive()
+    }
 }
 
 impl ContextTrackerInner {
@@ -77,28 +187,46 @@ impl ContextTrackerInner {
         Arc::new(Self {
             stopped: AtomicBool::new(false),
             active_count: AtomicUsize::new(0),
-            notify: tokio::sync::Notify::new(),
+            notify: Notify::new(),
+            has_on_change: AtomicBool::new(false),
+            on_change: RwLock::new(None),
         })
     }
 
     /// Create a new `ContextTracker` from an `Arc<ContextTrackerInner>`.
     fn child(self: &Arc<Self>) -> ContextTracker {
-        self.active_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // SeqCst for `WeakContext::upgrade`, see the contract on `ContextTrackerInner`.
+        let prev_active_count = self.active_count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.changed(prev_active_count + 1);
         ContextTracker(Arc::clone(self))
     }
 
+    /// Calls the callback set by [`Handler::on_tracker_change`], if any.
+    fn changed(&self, active_count: usize) {
+        if !self.has_on_change.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+
+        if let Some(callback) = &*self.on_change.read().unwrap() {
+            (callback.0)(active_count);
+        }
+    }
+
     /// Mark this `ContextTrackerInner` as stopped.
     fn stop(&self) {
-        self.stopped.store(true, std::sync::atomic::Ordering::Relaxed);
+        self.stopped.store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
     /// Wait for this `ContextTrackerInner` to be stopped and all associated
     /// `ContextTracker`s to be dropped.
     async fn wait(&self) {
+        // Created before the count is loaded, so a `notify_waiters` after the load is not missed.
         let notify = self.notify.notified();
 
+        // Orders the store of `stopped` before the load, see the contract on `ContextTrackerInner`.
+        fence(std::sync::atomic::Ordering::SeqCst);
         // If there are no active children, then the notify will never be called
-        if self.active_count.load(std::sync::atomic::Ordering::Relaxed) == 0 {
+        if self.active_count.load(std::sync::atomic::Ordering::SeqCst) == 0 {
             return;
         }
 
@@ -121,6 +249,8 @@ impl ContextTrackerInner {
 pub struct Context {
     token: CancellationToken,
     tracker: ContextTracker,
+    node: Arc<HandlerNode>,
+    values: Option<Arc<ContextValues>>,
 }
 
 impl Clone for Context {
@@ -128,7 +258,81 @@ impl Clone for Context {
         Self {
             token: self.token.clone(),
             tracker: self.tracker.0.child(),
+            node: Arc::clone(&self.node),
+            values: self.values.clone(),
+        }
+    }
+}
+
+/// A key for a value attached to a context with [`Context::with_value`].
+///
+/// The key is a type, usually a private unit struct, so values of different
+/// modules never collide.
+///
+/// # Example
+///
+/// ```rust
+/// # use scuffle_context::ContextKey;
+/// struct TenantId;
+///
+/// impl ContextKey for TenantId {
+///     type Value = String;
+/// }
+/// ```
+pub trait ContextKey: 'static {
+    /// The type of the value stored under this key.
+    type Value: Send + Sync + 'static;
+}
+
+/// The values attached to a context, as a list from the most recently attached value to the first.
+#[derive(Debug)]
+struct ContextValues {
+    key: TypeId,
+    value: Box<dyn Any + Send + Sync>,
+    parent: Option<Arc<ContextValues>>,
+}
+
+/// A weak reference to a [`Context`], created by [`Context::downgrade`].
+///
+/// Unlike a context it does not count as active work, so it does not keep
+/// [`Handler::shutdown`] waiting.
+#[derive(Debug, Clone)]
+pub struct WeakContext {
+    token: CancellationToken,
+    tracker: Weak<ContextTrackerInner>,
+    node: Weak<HandlerNode>,
+    values: Option<Arc<ContextValues>>,
+}
+
+impl WeakContext {
+    /// Returns the context again, or `None` once its handler is draining, done or dropped.
+    ///
+    /// New work is not started on a handler that is shutting down, as its
+    /// shutdown may already have stopped waiting for it.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Context> {
+        let tracker = self.tracker.upgrade()?;
+        let node = self.node.upgrade()?;
+
+        let tracker = tracker.child();
+        fence(std::sync::atomic::Ordering::SeqCst);
+        // Checked after tracking, a shutdown that started before has stopped the tracker.
+        if tracker.0.stopped.load(std::sync::atomic::Ordering::SeqCst) || self.token.is_cancelled() {
+            return None;
         }
+
+        Some(Context {
+            token: self.token.clone(),
+            tracker,
+            node,
+            values: self.values.clone(),
+        })
+    }
+
+    /// Returns true if the context is done.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.token.is_cancelled()
     }
 }
 
@@ -153,19 +357,88 @@ impl Context {
     /// let (child, child_handler) = parent.new_child();
     /// ```
     pub fn new_child(&self) -> (Self, Handler) {
-        let token = self.token.child_token();
-        let tracker = ContextTrackerInner::new();
+        self.new_child_with_name(None)
+    }
+
+    #[must_use]
+    /// Create a new named child context from this context.
+    /// Returns a new child context and child handler of this context.
+    ///
+    /// The name shows up in [`Handler::pending_report`], to tell which work
+    /// holds up a shutdown.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Context;
+    ///
+    /// let (parent, parent_handler) = Context::new();
+    /// let (child, child_handler) = parent.new_child_named("rtmp-session-123");
+    /// assert_eq!(child.name(), Some("rtmp-session-123"));
+    /// ```
+    pub fn new_child_named(&self, name: impl Into<String>) -> (Self, Handler) {
+        self.new_child_with_name(Some(name.into().into()))
+    }
+
+    fn new_child_with_name(&self, name: Option<Arc<str>>) -> (Self, Handler) {
+        let handler = Handler::from_parts(self.values.clone(), name);
+        self.node.attach(&handler.token.0);
+
+        (handler.context(), handler)
+    }
+
+    /// Returns the name of the handler of this context, if it was created with a name.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.node.name.as_deref()
+    }
+
+    #[must_use]
+    /// Returns a copy of this context with `value` attached under the key `K`.
+    ///
+    /// The value is inherited by clones and child contexts of the returned
+    /// context, and by the contexts of child handlers created from it. A value
+    /// attached under a key that is already set shadows the previous value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextKey};
+    /// struct RequestId;
+    ///
+    /// impl ContextKey for RequestId {
+    ///     type Value = u64;
+    /// }
+    ///
+    /// let (ctx, handler) = Context::new();
+    /// let ctx = ctx.with_value::<RequestId>(42);
+    ///
+    /// let (child, _child_handler) = ctx.new_child();
+    /// assert_eq!(child.value::<RequestId>(), Some(&42));
+    /// ```
+    pub fn with_value<K: ContextKey>(&self, value: K::Value) -> Self {
+        let mut ctx = self.clone();
+        ctx.values = Some(Arc::new(ContextValues {
+            key: TypeId::of::<K>(),
+            value: Box::new(value),
+            parent: self.values.clone(),
+        }));
+        ctx
+    }
+
+    /// Returns the value attached under the key `K`, if any.
+    #[must_use]
+    pub fn value<K: ContextKey>(&self) -> Option<&K::Value> {
+        let mut current = self.values.as_deref();
+        while let Some(values) = current {
+            if values.key == TypeId::of::<K>() {
+                return values.value.downcast_ref();
+            }
+
+            current = values.parent.as_deref();
+        }
 
-        (
-            Self {
-                tracker: tracker.child(),
-                token: token.clone(),
-            },
-            Handler {
-                token: Arc::new(TokenDropGuard(token)),
-                tracker,
-            },
-        )
+        None
     }
 
     #[must_use]
@@ -174,14 +447,122 @@ impl Context {
         Handler::global().context()
     }
 
+    /// Borrows the context, for attaching it to futures and streams without cloning it.
+    ///
+    /// The same as passing `&Context` to [`ContextFutExt::with_context`] or
+    /// [`ContextStreamExt::with_context`], see [`ContextRef`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextFutExt};
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// for i in 0..1000 {
+    ///     // No clone of the context per future.
+    ///     assert_eq!(async { i }.with_context(ctx.as_ref()).await, Some(i));
+    /// }
+    ///
+    /// drop(ctx);
+    /// handler.shutdown().await;
+    /// # });
+    /// ```
+    #[must_use]
+    pub fn as_ref(&self) -> ContextRef<'_> {
+        ContextRef::from(self)
+    }
+
+    #[must_use]
+    /// Create a new child context that is cancelled at `deadline`.
+    /// Returns a new child context and child handler of this context.
+    ///
+    /// The child is still cancelled together with this context, so it never
+    /// outlives an earlier deadline of its ancestors. Dropping the returned
+    /// handler cancels the child right away, like with [`Context::new_child`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime, see [`Handler::cancel_at`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextFutExt};
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let deadline = tokio::time::Instant::now() + std::time::Duration::from_millis(10);
+    /// let (child, _child_handler) = ctx.with_deadline(deadline);
+    /// assert_eq!(child.deadline(), Some(deadline));
+    ///
+    /// // The future is cancelled when the deadline passes.
+    /// let result = std::future::pending::<()>().with_context(&child).await;
+    /// assert_eq!(result, None);
+    /// # drop((ctx, child));
+    /// # handler.shutdown().await;
+    /// # });
+    /// ```
+    pub fn with_deadline(&self, deadline: Instant) -> (Self, Handler) {
+        let (ctx, handler) = self.new_child();
+        handler.cancel_at(deadline);
+        (ctx, handler)
+    }
+
+    #[must_use]
+    /// Create a new child context that is cancelled after `timeout`.
+    ///
+    /// The same as [`Context::with_deadline`] with a deadline of now + `timeout`.
+    pub fn with_timeout(&self, timeout: Duration) -> (Self, Handler) {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Returns the deadline at which this context is cancelled, if any.
+    ///
+    /// This is the earliest deadline of the context's handler and all of its
+    /// ancestors.
+    #[must_use]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.node.deadline()
+    }
+
     /// Wait for the context to be done (the handler to be shutdown).
-    pub async fn done(&self) {
+    /// Returns the reason the context was cancelled with.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{CancellationReason, Handler};
+    /// # tokio_test::block_on(async {
+    /// #[derive(Debug, PartialEq)]
+    /// enum Shutdown {
+    ///     Terminate,
+    ///     Reload,
+    /// }
+    ///
+    /// let handler = Handler::new();
+    /// let (ctx, _child) = handler.new_child();
+    ///
+    /// handler.cancel_with(CancellationReason::custom(Shutdown::Reload));
+    ///
+    /// let reason = ctx.done().await;
+    /// assert_eq!(reason.downcast_ref::<Shutdown>(), Some(&Shutdown::Reload));
+    /// # });
+    /// ```
+    pub async fn done(&self) -> CancellationReason {
         self.token.cancelled().await;
+        self.reason().unwrap_or(CancellationReason::Cancelled)
     }
 
     /// The same as [`Context::done`] but takes ownership of the context.
-    pub async fn into_done(self) {
-        self.done().await;
+    pub async fn into_done(self) -> CancellationReason {
+        self.done().await
+    }
+
+    /// Returns the reason the context was cancelled with, `None` if it is not done.
+    #[must_use]
+    pub fn reason(&self) -> Option<CancellationReason> {
+        self.node.reason.get().cloned()
     }
 
     /// Returns true if the context is done.
@@ -189,79 +570,760 @@ impl Context {
     pub fn is_done(&self) -> bool {
         self.token.is_cancelled()
     }
-}
 
-/// A wrapper type around [`CancellationToken`] that will cancel the token as
-/// soon as it is dropped.
-#[derive(Debug)]
-struct TokenDropGuard(CancellationToken);
+    /// Wait for the context to be asked to drain, by [`Handler::drain`] or
+    /// [`Handler::shutdown_graceful`].
+    ///
+    /// Draining is a soft cancellation: the work should stop accepting new
+    /// items, finish what is in flight and then drop the context. Futures
+    /// attached with [`ContextFutExt::with_context`] keep running until the
+    /// context is done. A context that is done is always draining.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Handler;
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new();
+    /// let ctx = handler.context();
+    ///
+    /// tokio::spawn(async move {
+    ///     // Stop accepting new work and flush what is buffered.
+    ///     ctx.draining().await;
+    ///     assert!(!ctx.is_done());
+    /// });
+    ///
+    /// handler.shutdown_graceful(std::time::Duration::from_secs(5)).await;
+    /// # });
+    /// ```
+    pub async fn draining(&self) {
+        self.node.draining.cancelled().await;
+    }
 
-impl TokenDropGuard {
+    /// Returns true if the context is asked to drain, see [`Context::draining`].
     #[must_use]
-    fn child(&self) -> CancellationToken {
-        self.0.child_token()
+    pub fn is_draining(&self) -> bool {
+        self.node.draining.is_cancelled()
+    }
+
+    /// Returns a guard that counts as active work for this context's handler.
+    ///
+    /// [`Handler::shutdown`] and [`Handler::wait`] will not return until the
+    /// guard is dropped, even if this context is dropped first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Context;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let guard = ctx.track();
+    /// std::thread::spawn(move || {
+    ///     // Do some blocking work
+    ///     drop(guard);
+    /// });
+    ///
+    /// drop(ctx);
+    /// // Waits for the thread to drop the guard.
+    /// handler.shutdown().await;
+    /// # });
+    /// ```
+    pub fn track(&self) -> ContextTracker {
+        self.tracker.0.child()
+    }
+
+    /// Returns a weak reference to this context, which does not count as active
+    /// work for [`Handler::shutdown`].
+    ///
+    /// Useful for registries and caches that should not hold a shutdown open,
+    /// see [`WeakContext::upgrade`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Context;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let weak = ctx.downgrade();
+    /// assert!(weak.upgrade().is_some());
+    ///
+    /// drop(ctx);
+    /// // The weak reference does not keep the shutdown waiting.
+    /// handler.shutdown().await;
+    /// assert!(weak.upgrade().is_none());
+    /// # });
+    /// ```
+    #[must_use]
+    pub fn downgrade(&self) -> WeakContext {
+        WeakContext {
+            token: self.token.clone(),
+            tracker: Arc::downgrade(&self.tracker.0),
+            node: Arc::downgrade(&self.node),
+            values: self.values.clone(),
+        }
     }
 
-    fn cancel(&self) {
-        self.0.cancel();
+    /// Runs a blocking closure on the blocking thread pool, tracked by this context.
+    ///
+    /// The task counts as active work for [`Handler::shutdown`] until the
+    /// closure returns. Blocking code cannot be cancelled from the outside, so
+    /// the closure is given a [`CancelFlag`] to check whether it should stop
+    /// early.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Context;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let task = ctx.spawn_blocking(|flag| {
+    ///     let mut chunks = 0;
+    ///     while !flag.is_cancelled() && chunks < 10 {
+    ///         // Process a chunk of work
+    ///         chunks += 1;
+    ///     }
+    ///     chunks
+    /// });
+    ///
+    /// drop(ctx);
+    /// // Waits for the closure to return.
+    /// handler.shutdown().await;
+    /// assert!(task.await.unwrap() <= 10);
+    /// # });
+    /// ```
+    pub fn spawn_blocking<F, R>(&self, f: F) -> tokio::task::JoinHandle<R>
+    where
+        F: FnOnce(CancelFlag) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let flag = CancelFlag(self.token.clone());
+        let tracker = self.track();
+
+        tokio::task::spawn_blocking(move || {
+            let result = f(flag);
+            drop(tracker);
+            result
+        })
     }
 }
 
-impl Drop for TokenDropGuard {
-    fn drop(&mut self) {
-        self.cancel();
+/// A best-effort cancellation flag for blocking code.
+///
+/// Given to the closure of [`Context::spawn_blocking`]. Check it between units
+/// of work to stop early when the context is done.
+#[derive(Debug, Clone)]
+pub struct CancelFlag(CancellationToken);
+
+impl CancelFlag {
+    /// Returns true if the context the flag was created from is done.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled()
     }
 }
 
-/// A handler is used to manage contexts and to cancel them.
+/// Why a context was cancelled.
+///
+/// The reason is set by the handler that is cancelled first and is passed down
+/// to all of its child contexts and handlers, see [`Handler::cancel_with`].
 #[derive(Debug, Clone)]
-pub struct Handler {
-    token: Arc<TokenDropGuard>,
-    tracker: Arc<ContextTrackerInner>,
+pub enum CancellationReason {
+    /// The handler was cancelled by [`Handler::cancel`] or [`Handler::shutdown`].
+    Cancelled,
+    /// The handler was dropped.
+    HandlerDropped,
+    /// The deadline of the handler passed, see [`Handler::cancel_at`].
+    DeadlineExceeded,
+    /// A reason defined by the application, see [`CancellationReason::custom`].
+    Custom(Arc<dyn Any + Send + Sync>),
 }
 
-impl Default for Handler {
-    fn default() -> Self {
-        Self::new()
+impl CancellationReason {
+    /// Creates a reason with an application defined payload, such as the
+    /// signal that triggered the shutdown.
+    pub fn custom<T: Any + Send + Sync>(payload: T) -> Self {
+        Self::Custom(Arc::new(payload))
     }
-}
 
-impl Handler {
+    /// Returns the payload of a [`CancellationReason::Custom`] reason if it is of type `T`.
     #[must_use]
-    /// Create a new handler.
-    pub fn new() -> Handler {
-        let token = CancellationToken::new();
-        let tracker = ContextTrackerInner::new();
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        match self {
+            Self::Custom(payload) => payload.downcast_ref(),
+            _ => None,
+        }
+    }
+}
 
-        Handler {
-            token: Arc::new(TokenDropGuard(token)),
+/// A handler in the tree of handlers.
+///
+/// Handlers are linked through this node instead of through the token tree,
+/// because a [`CancellationToken`] cannot be moved to another parent. The
+/// contexts of a handler are child tokens of its own token, so they follow it
+/// when it is reparented.
+#[derive(Debug)]
+struct HandlerNode {
+    token: CancellationToken,
+    /// The handler this handler is attached to.
+    parent: Mutex<Weak<HandlerNode>>,
+    /// The handlers that are cancelled when this handler is cancelled.
+    children: Mutex<Vec<Weak<HandlerNode>>>,
+    /// When this handler cancels itself, set by [`Handler::cancel_at`].
+    deadline: Mutex<Option<Instant>>,
+    /// The values of the context this handler was created from, inherited by its contexts.
+    values: Option<Arc<ContextValues>>,
+    /// Set once, before the token is cancelled.
+    reason: OnceLock<CancellationReason>,
+    /// Cancelled when the handler is asked to drain, or when it is cancelled.
+    draining: CancellationToken,
+    /// The name of the handler, for [`Handler::pending_report`].
+    name: Option<Arc<str>>,
+    /// The tracker of the handler, for [`Handler::pending_report`].
+    tracker: Weak<ContextTrackerInner>,
+    /// The futures registered with [`Handler::on_shutdown`].
+    finishers: Mutex<FinisherState>,
+    /// Cancelled once the finishers of this handler ran.
+    finished: CancellationToken,
+}
+
+impl HandlerNode {
+    fn new(values: Option<Arc<ContextValues>>, name: Option<Arc<str>>, tracker: Weak<ContextTrackerInner>) -> Arc<Self> {
+        Arc::new(Self {
+            token: CancellationToken::new(),
+            parent: Mutex::new(Weak::new()),
+            children: Mutex::new(Vec::new()),
+            deadline: Mutex::new(None),
+            values,
+            reason: OnceLock::new(),
+            draining: CancellationToken::new(),
+            name,
             tracker,
+            finishers: Mutex::new(FinisherState::Pending(Finishers::default())),
+            finished: CancellationToken::new(),
+        })
+    }
+
+    /// Adds this handler and its descendants with live contexts to `handlers`,
+    /// `path` is the path of the parent.
+    fn report(&self, path: &mut String, handlers: &mut Vec<PendingHandler>) {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(self.name.as_deref().unwrap_or("<unnamed>"));
+
+        let active_count = self
+            .tracker
+            .upgrade()
+            .map_or(0, |tracker| tracker.active_count.load(std::sync::atomic::Ordering::Relaxed));
+        if active_count > 0 {
+            handlers.push(PendingHandler {
+                path: path.clone(),
+                active_count,
+            });
+        }
+
+        let children = self
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for child in children {
+            child.report(path, handlers);
         }
+
+        path.truncate(len);
     }
 
-    #[must_use]
-    /// Returns the global handler.
-    pub fn global() -> &'static Self {
-        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+    /// Attaches `child` to this handler, cancelling it right away if this
+    /// handler is already cancelled.
+    fn attach(self: &Arc<Self>, child: &Arc<Self>) {
+        *child.parent.lock().unwrap() = Arc::downgrade(self);
 
-        GLOBAL.get_or_init(Handler::new)
+        let mut children = self.children.lock().unwrap();
+        // Checked while holding the lock, `cancel` cancels the token while holding it.
+        if self.token.is_cancelled() {
+            drop(children);
+            child.cancel(self.reason.get().cloned().unwrap_or(CancellationReason::Cancelled));
+            return;
+        }
+
+        // Prune dropped handlers before growing, so the list stays proportional to the live handlers.
+        if children.len() == children.capacity() {
+            children.retain(|child| child.strong_count() > 0);
+        }
+
+        children.push(Arc::downgrade(child));
+
+        // Also checked while holding the lock, `drain` collects the children while holding it.
+        if self.draining.is_cancelled() {
+            drop(children);
+            child.drain();
+        }
     }
 
-    /// Shutdown the handler and wait for all contexts to be done.
-    pub async fn shutdown(&self) {
-        self.cancel();
-        self.done().await;
+    /// Detaches this handler from its parent.
+    fn detach(self: &Arc<Self>) {
+        let parent = std::mem::take(&mut *self.parent.lock().unwrap());
+        if let Some(parent) = parent.upgrade() {
+            parent
+                .children
+                .lock()
+                .unwrap()
+                .retain(|child| child.strong_count() > 0 && !std::ptr::eq(child.as_ptr(), Arc::as_ptr(self)));
+        }
     }
 
-    /// Waits for the handler to be done (waiting for all contexts to be done).
-    pub async fn done(&self) {
-        self.token.0.cancelled().await;
-        self.wait().await;
+    /// Returns true if `other` is this handler or one of its ancestors.
+    fn is_self_or_ancestor(self: &Arc<Self>, other: &Arc<Self>) -> bool {
+        let mut current = Some(Arc::clone(self));
+        while let Some(node) = current {
+            if Arc::ptr_eq(&node, other) {
+                return true;
+            }
+
+            current = node.parent.lock().unwrap().upgrade();
+        }
+
+        false
     }
 
-    /// Waits for the handler to be done (waiting for all contexts to be done).
+    /// Returns the earliest deadline of this handler and its ancestors.
+    fn deadline(self: &Arc<Self>) -> Option<Instant> {
+        let mut deadline: Option<Instant> = None;
+        let mut current = Some(Arc::clone(self));
+        while let Some(node) = current {
+            if let Some(node_deadline) = *node.deadline.lock().unwrap() {
+                deadline = Some(deadline.map_or(node_deadline, |deadline| deadline.min(node_deadline)));
+            }
+
+            current = node.parent.lock().unwrap().upgrade();
+        }
+
+        deadline
+    }
+
+    /// Asks this handler and its children to drain.
+    fn drain(&self) {
+        let children = {
+            let children = self.children.lock().unwrap();
+            self.draining.cancel();
+            children.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+        };
+
+        for child in children {
+            child.drain();
+        }
+    }
+
+    /// Cancels this handler and its children, a handler keeps the first reason it was cancelled with.
+    fn cancel(&self, reason: CancellationReason) {
+        let children = {
+            let children = self.children.lock().unwrap();
+            // An error means the handler was cancelled before, its reason is kept.
+            let _ = self.reason.set(reason);
+            self.draining.cancel();
+            self.token.cancel();
+            // The children are kept, so they still show up in `Handler::pending_report` during a shutdown.
+            children.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+        };
+
+        let reason = self.reason.get().expect("the reason was set above");
+        for child in children {
+            child.cancel(reason.clone());
+        }
+    }
+
+    /// Runs the finishers of the descendants of this handler and then its own,
+    /// each once the contexts of its handler are done.
+    ///
+    /// Only waits for the contexts of handlers that have finishers, the ones of
+    /// other descendants are not waited for, like in a shutdown without finishers.
+    ///
+    /// Returns once the finishers ran, also when another call is running them.
+    fn finish(self: Arc<Self>) -> Finisher {
+        Box::pin(async move {
+            let children = self
+                .children
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>();
+            for child in children {
+                if child.has_unfinished_finishers() {
+                    child.finish().await;
+                }
+            }
+
+            let has_finishers = matches!(
+                &*self.finishers.lock().unwrap(),
+                FinisherState::Pending(finishers) if !finishers.is_empty()
+            );
+            let tracker = self.tracker.upgrade().filter(|_| has_finishers);
+            if let Some(tracker) = tracker {
+                // The tracker of a child is only stopped when its own handler is cancelled.
+                tracker.stop();
+                tracker.wait().await;
+            }
+
+            let state = std::mem::replace(&mut *self.finishers.lock().unwrap(), FinisherState::Taken);
+            match state {
+                FinisherState::Pending(finishers) => {
+                    // Also marks the finishers as done if one of them panics.
+                    let _guard = self.finished.clone().drop_guard();
+                    for finisher in finishers.adopted.into_iter().chain(finishers.own) {
+                        finisher.await;
+                    }
+                }
+                FinisherState::Taken => self.finished.cancelled().await,
+                // Put back, `Taken` would make the next call wait forever.
+                FinisherState::HandedOver => *self.finishers.lock().unwrap() = FinisherState::HandedOver,
+            }
+        })
+    }
+
+    /// Returns true if this handler or one of its descendants has finishers
+    /// that did not run yet, or that another shutdown is running.
+    fn has_unfinished_finishers(&self) -> bool {
+        match &*self.finishers.lock().unwrap() {
+            FinisherState::Pending(finishers) if !finishers.is_empty() => return true,
+            FinisherState::Taken if !self.finished.is_cancelled() => return true,
+            _ => {}
+        }
+
+        let children = self
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        children.iter().any(|child| child.has_unfinished_finishers())
+    }
+
+    /// Hands the finishers of this handler to the closest ancestor that has
+    /// not run its own yet, as the handler is dropped and cannot run them.
+    fn hand_over_finishers(&self) {
+        let finishers = {
+            let mut state = self.finishers.lock().unwrap();
+            match std::mem::replace(&mut *state, FinisherState::HandedOver) {
+                FinisherState::Pending(finishers) => finishers,
+                taken => {
+                    *state = taken;
+                    return;
+                }
+            }
+        };
+
+        let mut finishers = finishers.adopted.into_iter().chain(finishers.own).collect::<Vec<_>>();
+        if finishers.is_empty() {
+            return;
+        }
+
+        let mut current = self.parent.lock().unwrap().upgrade();
+        while let Some(node) = current {
+            if let FinisherState::Pending(pending) = &mut *node.finishers.lock().unwrap() {
+                pending.adopted.append(&mut finishers);
+                return;
+            }
+
+            current = node.parent.lock().unwrap().upgrade();
+        }
+
+        run_detached(finishers);
+    }
+}
+
+/// A future registered with [`Handler::on_shutdown`].
+type Finisher = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The finishers of a handler, see [`Handler::on_shutdown`].
+#[derive(Default)]
+struct Finishers {
+    /// The finishers of dropped children, run before the own ones.
+    adopted: Vec<Finisher>,
+    own: Vec<Finisher>,
+}
+
+impl Finishers {
+    fn is_empty(&self) -> bool {
+        self.adopted.is_empty() && self.own.is_empty()
+    }
+}
+
+/// Where the finishers of a handler are.
+enum FinisherState {
+    /// Not run yet.
+    Pending(Finishers),
+    /// Taken by the shutdown running them.
+    Taken,
+    /// Handed to an ancestor when the handler was dropped.
+    HandedOver,
+}
+
+impl std::fmt::Debug for FinisherState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending(finishers) => f
+                .debug_struct("Pending")
+                .field("adopted", &finishers.adopted.len())
+                .field("own", &finishers.own.len())
+                .finish(),
+            Self::Taken => f.write_str("Taken"),
+            Self::HandedOver => f.write_str("HandedOver"),
+        }
+    }
+}
+
+/// Runs finishers that no handler can run anymore on the current runtime,
+/// or drops them when there is none.
+fn run_detached(finishers: Vec<Finisher>) {
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn(async move {
+            for finisher in finishers {
+                finisher.await;
+            }
+        });
+    }
+}
+
+/// A wrapper type around [`HandlerNode`] that will cancel the handler as
+/// soon as it is dropped.
+#[derive(Debug)]
+struct TokenDropGuard(Arc<HandlerNode>);
+
+impl TokenDropGuard {
+    #[must_use]
+    fn child(&self) -> CancellationToken {
+        self.0.token.child_token()
+    }
+
+    fn cancel(&self, reason: CancellationReason) {
+        self.0.cancel(reason);
+    }
+}
+
+impl Drop for TokenDropGuard {
+    fn drop(&mut self) {
+        self.cancel(CancellationReason::HandlerDropped);
+        self.0.hand_over_finishers();
+    }
+}
+
+/// The error returned by [`Handler::reparent`] when the new parent is the
+/// handler itself or one of its descendants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReparentError;
+
+impl std::fmt::Display for ReparentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cannot reparent a handler under itself or one of its descendants")
+    }
+}
+
+impl std::error::Error for ReparentError {}
+
+/// How [`Handler::shutdown_graceful`] finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    /// All contexts were dropped while draining, before the timeout.
+    Drained,
+    /// The timeout passed while contexts were still alive, so the handler was cancelled.
+    Escalated,
+}
+
+/// The handlers with live contexts, returned by [`Handler::pending_report`].
+///
+/// Displays as one line per handler.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PendingReport {
+    /// The handlers, in depth first order.
+    pub handlers: Vec<PendingHandler>,
+}
+
+impl PendingReport {
+    /// Returns true if no handler has live contexts.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+impl std::fmt::Display for PendingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for handler in &self.handlers {
+            writeln!(f, "{}: {} active", handler.path, handler.active_count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A handler with live contexts, see [`PendingReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingHandler {
+    /// The names of the handler and its ancestors up to the reporting handler,
+    /// joined by `/`. Handlers without a name show up as `<unnamed>`.
+    pub path: String,
+    /// The number of live contexts and [`ContextTracker`]s of the handler.
+    pub active_count: usize,
+}
+
+/// A handler is used to manage contexts and to cancel them.
+#[derive(Debug, Clone)]
+pub struct Handler {
+    token: Arc<TokenDropGuard>,
+    tracker: Arc<ContextTrackerInner>,
+}
+
+impl Default for Handler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Handler {
+    #[must_use]
+    /// Create a new handler.
+    pub fn new() -> Handler {
+        Self::from_parts(None, None)
+    }
+
+    #[must_use]
+    /// Create a new handler with a name, see [`Handler::pending_report`].
+    pub fn new_named(name: impl Into<String>) -> Handler {
+        Self::from_parts(None, Some(name.into().into()))
+    }
+
+    fn from_parts(values: Option<Arc<ContextValues>>, name: Option<Arc<str>>) -> Handler {
+        let tracker = ContextTrackerInner::new();
+        Handler {
+            token: Arc::new(TokenDropGuard(HandlerNode::new(values, name, Arc::downgrade(&tracker)))),
+            tracker,
+        }
+    }
+
+    #[must_use]
+    /// Returns the global handler.
+    pub fn global() -> &'static Self {
+        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+
+        GLOBAL.get_or_init(Handler::new)
+    }
+
+    /// Shutdown the handler and wait for all contexts to be done.
+    ///
+    /// Only the contexts of this handler are waited for, the contexts of child
+    /// handlers are cancelled but not waited for. Afterwards the futures
+    /// registered with [`Handler::on_shutdown`] on this handler and its children
+    /// are run, see there, which also waits for the contexts of the children
+    /// that registered some.
+    pub async fn shutdown(&self) {
+        self.cancel();
+        self.done().await;
+        Arc::clone(&self.token.0).finish().await;
+    }
+
+    /// Registers a future that is run once by [`Handler::shutdown`], after all
+    /// contexts are done and before it returns.
+    ///
+    /// Meant for work that has to happen last, such as flushing logs or
+    /// deregistering from service discovery. A shutdown of this handler or of
+    /// one of its ancestors runs the futures of the children first, each once
+    /// the contexts of that child are done, and then the futures of this
+    /// handler in the order they were registered. Concurrent or repeated
+    /// shutdowns run them only once, and all of them wait for the futures to
+    /// finish.
+    ///
+    /// When the handler is dropped before a shutdown, its futures are handed
+    /// to its parent, which runs them before its own. Without a parent they
+    /// are spawned on the current Tokio runtime, the same happens to futures
+    /// registered after the shutdown already ran them. Outside of a runtime
+    /// they are dropped without running.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::{Arc, Mutex};
+    /// # use scuffle_context::Handler;
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new();
+    /// let (ctx, child) = handler.new_child();
+    /// let order = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let parent_order = order.clone();
+    /// handler.on_shutdown(async move { parent_order.lock().unwrap().push("parent") });
+    /// let child_order = order.clone();
+    /// child.on_shutdown(async move { child_order.lock().unwrap().push("child") });
+    ///
+    /// drop(ctx);
+    /// handler.shutdown().await;
+    /// assert_eq!(*order.lock().unwrap(), ["child", "parent"]);
+    /// # });
+    /// ```
+    pub fn on_shutdown(&self, fut: impl Future<Output = ()> + Send + 'static) {
+        let fut: Finisher = Box::pin(fut);
+        match &mut *self.token.0.finishers.lock().unwrap() {
+            FinisherState::Pending(finishers) => finishers.own.push(fut),
+            _ => run_detached(vec![fut]),
+        }
+    }
+
+    /// Shutdown the handler in two phases, first asking its contexts to drain
+    /// and then cancelling them if they do not finish in time.
+    ///
+    /// The handler and its children are asked to drain (see
+    /// [`Context::draining`]), then this waits up to `timeout` for all contexts
+    /// of the handler to be dropped. Afterwards the handler is cancelled and
+    /// this waits for the remaining contexts, like [`Handler::shutdown`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{ContextFutExt, Handler, ShutdownOutcome};
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new();
+    /// let ctx = handler.context();
+    ///
+    /// tokio::spawn(async move {
+    ///     // This work ignores the request to drain.
+    ///     std::future::pending::<()>().with_context(ctx).await;
+    /// });
+    ///
+    /// let outcome = handler.shutdown_graceful(std::time::Duration::from_millis(10)).await;
+    /// assert_eq!(outcome, ShutdownOutcome::Escalated);
+    /// # });
+    /// ```
+    pub async fn shutdown_graceful(&self, timeout: Duration) -> ShutdownOutcome {
+        self.drain();
+
+        let outcome = match tokio::time::timeout(timeout, self.wait()).await {
+            Ok(()) => ShutdownOutcome::Drained,
+            Err(_) => ShutdownOutcome::Escalated,
+        };
+
+        self.shutdown().await;
+        outcome
+    }
+
+    /// Waits for the handler to be done (waiting for all contexts to be done).
+    pub async fn done(&self) {
+        self.token.0.token.cancelled().await;
+        self.wait().await;
+    }
+
+    /// Waits for the handler to be done (waiting for all contexts to be done).
     /// Returns once all contexts are done, even if the handler is not done and
     /// contexts can be created after this call.
+    ///
+    /// Everything a task did before dropping its [`Context`] or [`ContextTracker`]
+    /// happens before this returns, so its writes are visible afterwards without
+    /// further synchronization.
     pub async fn wait(&self) {
         self.tracker.wait().await;
     }
@@ -272,6 +1334,8 @@ impl Handler {
         Context {
             token: self.token.child(),
             tracker: self.tracker.child(),
+            node: Arc::clone(&self.token.0),
+            values: self.token.0.values.clone(),
         }
     }
 
@@ -281,24 +1345,290 @@ impl Handler {
         self.context().new_child()
     }
 
+    #[must_use]
+    /// Create a new named child context from this handler, see [`Context::new_child_named`].
+    pub fn new_child_named(&self, name: impl Into<String>) -> (Context, Handler) {
+        self.context().new_child_named(name)
+    }
+
+    /// Returns the name of the handler, if it was created with a name.
+    pub fn name(&self) -> Option<&str> {
+        self.token.0.name.as_deref()
+    }
+
+    /// Lists this handler and its descendants that still have live contexts.
+    ///
+    /// Meant for debugging a shutdown that takes long, to see which work is
+    /// still running. Handlers are identified by their names, given with
+    /// [`Handler::new_child_named`], and listed in depth first order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Handler;
+    /// let handler = Handler::new_named("server");
+    /// let (session, _session_handler) = handler.new_child_named("rtmp-session-123");
+    /// let (_idle, _idle_handler) = handler.new_child_named("rtmp-session-456");
+    /// drop(_idle);
+    ///
+    /// let report = handler.pending_report();
+    /// assert_eq!(report.to_string(), "server/rtmp-session-123: 1 active\n");
+    /// # drop(session);
+    /// ```
+    pub fn pending_report(&self) -> PendingReport {
+        let mut handlers = Vec::new();
+        self.token.0.report(&mut String::new(), &mut handlers);
+        PendingReport { handlers }
+    }
+
+    /// Asserts that all contexts of this handler and its descendants are
+    /// dropped within `budget`, meant for shutdown tests.
+    ///
+    /// This does not cancel the handler, cancel it or ask it to drain first.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the [`Handler::pending_report`] of the contexts that are
+    /// still alive once `budget` passed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use scuffle_context::{ContextFutExt, Handler};
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new_named("server");
+    /// let (ctx, _session) = handler.new_child_named("session");
+    ///
+    /// tokio::spawn(async move {
+    ///     std::future::pending::<()>().with_context(ctx).await;
+    /// });
+    ///
+    /// handler.cancel();
+    /// handler.assert_drained_within(Duration::from_secs(1)).await;
+    /// # });
+    /// ```
+    pub async fn assert_drained_within(&self, budget: Duration) {
+        /// How often the contexts are checked, the contexts of child handlers are not waited for directly.
+        const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+        let deadline = Instant::now() + budget;
+        loop {
+            let report = self.pending_report();
+            if report.is_empty() {
+                return;
+            }
+
+            if Instant::now() >= deadline {
+                panic!("contexts still active after {budget:?}:\n{report}");
+            }
+
+            tokio::time::sleep(POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+    }
+
     /// Cancel the handler.
     pub fn cancel(&self) {
+        self.cancel_with(CancellationReason::Cancelled);
+    }
+
+    /// Cancel the handler with a reason, which is passed down to all child
+    /// contexts and handlers and returned by [`Context::done`].
+    ///
+    /// If the handler is already cancelled, it keeps its previous reason.
+    pub fn cancel_with(&self, reason: CancellationReason) {
         self.tracker.stop();
-        self.token.cancel();
+        self.token.cancel(reason);
+    }
+
+    /// Returns the reason the handler was cancelled with, `None` if it is not done.
+    pub fn reason(&self) -> Option<CancellationReason> {
+        self.token.0.reason.get().cloned()
     }
 
     /// Returns true if the handler is done.
     pub fn is_done(&self) -> bool {
-        self.token.0.is_cancelled()
+        self.token.0.token.is_cancelled()
+    }
+
+    /// Asks the contexts of this handler and its children to drain, without cancelling them.
+    ///
+    /// See [`Context::draining`]. [`Handler::wait`] returns once all contexts
+    /// of the handler are dropped.
+    pub fn drain(&self) {
+        self.tracker.stop();
+        self.token.0.drain();
+    }
+
+    /// Returns true if the handler is asked to drain or is done.
+    pub fn is_draining(&self) -> bool {
+        self.token.0.draining.is_cancelled()
+    }
+
+    /// Returns the number of contexts and [`ContextTracker`]s of this handler
+    /// that are alive, the work [`Handler::shutdown`] waits for.
+    ///
+    /// The contexts of child handlers are counted by the child handlers.
+    pub fn active_count(&self) -> usize {
+        self.tracker.active_count.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Sets a callback that is called with the new [`Handler::active_count`]
+    /// every time it changes, replacing the previous callback.
+    ///
+    /// This is meant for exporting the work pending shutdown as a metric.
+    /// The callback is called on the thread that created or dropped the
+    /// context, so it should be cheap. Changes on different threads can be
+    /// reported out of order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use scuffle_context::Handler;
+    /// let handler = Handler::new();
+    ///
+    /// let gauge = Arc::new(AtomicUsize::new(0));
+    /// handler.on_tracker_change({
+    ///     let gauge = gauge.clone();
+    ///     move |active| gauge.store(active, Ordering::Relaxed)
+    /// });
+    ///
+    /// let ctx = handler.context();
+    /// assert_eq!(gauge.load(Ordering::Relaxed), 1);
+    ///
+    /// drop(ctx);
+    /// assert_eq!(gauge.load(Ordering::Relaxed), 0);
+    /// ```
+    pub fn on_tracker_change(&self, callback: impl Fn(usize) + Send + Sync + 'static) {
+        *self.tracker.on_change.write().unwrap() = Some(DebugCallback(Box::new(callback)));
+        self.tracker.has_on_change.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Cancels the handler when `deadline` passes, like calling [`Handler::cancel_with`] with
+    /// [`CancellationReason::DeadlineExceeded`] at that time.
+    ///
+    /// If the handler already has an earlier deadline, it is kept. A deadline
+    /// in the past cancels the handler right away. The timer is stopped when
+    /// the handler is cancelled or dropped before the deadline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime, as the timer is spawned on it.
+    pub fn cancel_at(&self, deadline: Instant) {
+        {
+            let mut current = self.token.0.deadline.lock().unwrap();
+            if current.is_some_and(|current| current <= deadline) {
+                return;
+            }
+
+            *current = Some(deadline);
+        }
+
+        if deadline <= Instant::now() {
+            self.cancel_with(CancellationReason::DeadlineExceeded);
+            return;
+        }
+
+        let token = self.token.0.token.clone();
+        let node = Arc::downgrade(&self.token.0);
+        let tracker = Arc::downgrade(&self.tracker);
+        tokio::spawn(async move {
+            if token.run_until_cancelled(tokio::time::sleep_until(deadline)).await.is_none() {
+                return;
+            }
+
+            if let Some(tracker) = tracker.upgrade() {
+                tracker.stop();
+            }
+
+            if let Some(node) = node.upgrade() {
+                node.cancel(CancellationReason::DeadlineExceeded);
+            }
+        });
+    }
+
+    /// Cancels the handler after `timeout`.
+    ///
+    /// The same as [`Handler::cancel_at`] with a deadline of now + `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Handler;
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new();
+    /// let ctx = handler.context();
+    ///
+    /// handler.cancel_after(std::time::Duration::from_millis(10));
+    /// ctx.done().await;
+    /// assert!(handler.is_done());
+    /// # });
+    /// ```
+    pub fn cancel_after(&self, timeout: Duration) {
+        self.cancel_at(Instant::now() + timeout);
+    }
+
+    /// Returns the deadline at which this handler is cancelled, if any.
+    ///
+    /// This is the earliest deadline of this handler and all of its ancestors.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.token.0.deadline()
+    }
+
+    /// Moves this handler, with its contexts and child handlers, under `new_parent`.
+    ///
+    /// Afterwards the handler is cancelled when `new_parent` is cancelled, and
+    /// no longer when its previous parent is. Contexts created before the move
+    /// are kept, so long-lived work can be handed off between supervisors
+    /// without being restarted. If `new_parent` is already done, the handler
+    /// is cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Handler;
+    /// let old_supervisor = Handler::new();
+    /// let new_supervisor = Handler::new();
+    ///
+    /// let (ctx, session) = old_supervisor.new_child();
+    /// session.reparent(&new_supervisor).unwrap();
+    ///
+    /// old_supervisor.cancel();
+    /// assert!(!ctx.is_done());
+    ///
+    /// new_supervisor.cancel();
+    /// assert!(ctx.is_done());
+    /// ```
+    pub fn reparent(&self, new_parent: &Handler) -> Result<(), ReparentError> {
+        if new_parent.token.0.is_self_or_ancestor(&self.token.0) {
+            return Err(ReparentError);
+        }
+
+        self.token.0.detach();
+        new_parent.token.0.attach(&self.token.0);
+
+        Ok(())
+    }
+
+    /// Detaches this handler from its parent, so it is only cancelled when it is
+    /// cancelled itself or dropped.
+    pub fn detach(&self) {
+        self.token.0.detach();
     }
 }
 
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
     use scuffle_future_ext::FutureExt;
+    use tokio::time::Instant;
 
-    use crate::{Context, Handler};
+    use crate::{CancellationReason, Context, ContextKey, Handler, PendingHandler, ReparentError, ShutdownOutcome};
 
     #[tokio::test]
     async fn new() {
@@ -397,6 +1727,588 @@ mod tests {
         assert!(handler.is_done());
     }
 
+    #[tokio::test]
+    async fn track() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let guard = ctx.track();
+        drop(ctx);
+
+        // The guard keeps the shutdown open after the context is dropped.
+        assert!(
+            handler
+                .shutdown()
+                .with_timeout(std::time::Duration::from_millis(200))
+                .await
+                .is_err()
+        );
+
+        let thread = std::thread::spawn(move || drop(guard));
+        assert!(
+            handler
+                .shutdown()
+                .with_timeout(std::time::Duration::from_millis(200))
+                .await
+                .is_ok()
+        );
+        thread.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn spawn_blocking() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let task = ctx.spawn_blocking(move |flag| {
+            started_tx.send(()).unwrap();
+            while !flag.is_cancelled() {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            42
+        });
+        drop(ctx);
+
+        started_rx.recv().unwrap();
+        // The blocking task keeps the handler busy until it observes the cancellation.
+        assert!(
+            handler
+                .wait()
+                .with_timeout(std::time::Duration::from_millis(50))
+                .await
+                .is_err()
+        );
+
+        assert!(
+            handler
+                .shutdown()
+                .with_timeout(std::time::Duration::from_millis(200))
+                .await
+                .is_ok()
+        );
+        assert_eq!(task.await.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn reparent() {
+        let old_parent = Handler::new();
+        let new_parent = Handler::new();
+
+        let (ctx, handler) = old_parent.new_child();
+        let (grandchild_ctx, _grandchild_handler) = ctx.new_child();
+
+        handler.reparent(&new_parent).unwrap();
+
+        // Contexts created before the move follow the handler.
+        old_parent.cancel();
+        assert!(!handler.is_done());
+        assert!(!ctx.is_done());
+        assert!(!grandchild_ctx.is_done());
+        assert!(!handler.context().is_done());
+
+        new_parent.cancel();
+        assert!(handler.is_done());
+        assert!(ctx.is_done());
+        assert!(grandchild_ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn reparent_cancelled_parent() {
+        let parent = Handler::new();
+        parent.cancel();
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+        handler.reparent(&parent).unwrap();
+
+        assert!(handler.is_done());
+        assert!(ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn reparent_cycle() {
+        let handler = Handler::new();
+        let (ctx, child) = handler.new_child();
+        let (_, grandchild) = ctx.new_child();
+
+        assert_eq!(handler.reparent(&handler), Err(ReparentError));
+        assert_eq!(handler.reparent(&grandchild), Err(ReparentError));
+
+        // The failed attempts leave the tree untouched.
+        handler.cancel();
+        assert!(child.is_done());
+        assert!(grandchild.is_done());
+    }
+
+    #[tokio::test]
+    async fn detach() {
+        let parent = Handler::new();
+        let (ctx, handler) = parent.new_child();
+
+        handler.detach();
+        parent.cancel();
+        assert!(!ctx.is_done());
+
+        drop(handler);
+        assert!(ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn cancel_after() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        assert_eq!(handler.deadline(), None);
+
+        handler.cancel_after(Duration::from_millis(50));
+        let deadline = handler.deadline().unwrap();
+
+        // A later deadline does not replace the earlier one.
+        handler.cancel_after(Duration::from_secs(60));
+        assert_eq!(handler.deadline(), Some(deadline));
+        assert_eq!(ctx.deadline(), Some(deadline));
+
+        assert!(!ctx.is_done());
+        assert!(ctx.done().with_timeout(Duration::from_secs(1)).await.is_ok());
+        assert!(handler.is_done());
+
+        // Cancelled by the deadline the same way as by `cancel`, so the shutdown only waits for the context.
+        drop(ctx);
+        assert!(handler.shutdown().with_timeout(Duration::from_millis(200)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn cancel_at_past_deadline() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        handler.cancel_at(Instant::now());
+        assert!(handler.is_done());
+        assert!(ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn with_deadline() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let parent_deadline = Instant::now() + Duration::from_millis(50);
+        let (parent, _parent_handler) = ctx.with_deadline(parent_deadline);
+
+        // A child never outlives the deadline of its parent.
+        let (child, _child_handler) = parent.with_timeout(Duration::from_secs(60));
+        assert_eq!(child.deadline(), Some(parent_deadline));
+
+        let child_deadline = Instant::now() + Duration::from_millis(10);
+        let (early_child, _early_child_handler) = parent.with_deadline(child_deadline);
+        assert_eq!(early_child.deadline(), Some(child_deadline));
+
+        assert!(early_child.done().with_timeout(Duration::from_secs(1)).await.is_ok());
+        assert!(!parent.is_done());
+
+        assert!(child.done().with_timeout(Duration::from_secs(1)).await.is_ok());
+        assert!(parent.is_done());
+        assert!(!ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn deadline_reparent() {
+        let handler = Handler::new();
+        let (ctx, child) = handler.context().with_timeout(Duration::from_millis(10));
+
+        // Reparenting after the deadline was set keeps it.
+        let other = Handler::new();
+        child.reparent(&other).unwrap();
+        assert!(ctx.deadline().is_some());
+
+        other.cancel();
+        assert!(ctx.is_done());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(child.is_done());
+    }
+
+    #[tokio::test]
+    async fn cancel_reason() {
+        #[derive(Debug, PartialEq)]
+        enum Shutdown {
+            Terminate,
+            Reload,
+        }
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (child_ctx, child_handler) = ctx.new_child();
+        assert!(ctx.reason().is_none());
+        assert!(handler.reason().is_none());
+
+        handler.cancel_with(CancellationReason::custom(Shutdown::Terminate));
+
+        // The reason is passed down to the children.
+        for reason in [ctx.done().await, child_ctx.done().await, child_handler.reason().unwrap()] {
+            assert_eq!(reason.downcast_ref::<Shutdown>(), Some(&Shutdown::Terminate));
+            assert!(reason.downcast_ref::<u32>().is_none());
+        }
+
+        // The first reason is kept.
+        handler.cancel_with(CancellationReason::custom(Shutdown::Reload));
+        assert_eq!(ctx.reason().unwrap().downcast_ref::<Shutdown>(), Some(&Shutdown::Terminate));
+
+        // A handler attached to a cancelled handler gets its reason.
+        let (late_ctx, _late_handler) = ctx.new_child();
+        assert_eq!(
+            late_ctx.reason().unwrap().downcast_ref::<Shutdown>(),
+            Some(&Shutdown::Terminate)
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_reason_builtin() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        handler.cancel();
+        assert!(matches!(ctx.done().await, CancellationReason::Cancelled));
+        assert!(ctx.done().await.downcast_ref::<()>().is_none());
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+        drop(handler);
+        assert!(matches!(ctx.into_done().await, CancellationReason::HandlerDropped));
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+        handler.cancel_after(Duration::from_millis(10));
+        assert!(matches!(ctx.done().await, CancellationReason::DeadlineExceeded));
+        assert!(matches!(handler.reason(), Some(CancellationReason::DeadlineExceeded)));
+    }
+
+    #[tokio::test]
+    async fn values() {
+        struct RequestId;
+
+        impl ContextKey for RequestId {
+            type Value = u64;
+        }
+
+        struct Tenant;
+
+        impl ContextKey for Tenant {
+            type Value = String;
+        }
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+        assert_eq!(ctx.value::<RequestId>(), None);
+
+        let ctx = ctx.with_value::<RequestId>(1).with_value::<Tenant>("scuffle".into());
+        assert_eq!(ctx.value::<RequestId>(), Some(&1));
+        assert_eq!(ctx.value::<Tenant>().map(String::as_str), Some("scuffle"));
+
+        // The values are inherited by clones, child contexts and the contexts of child handlers.
+        let (child, child_handler) = ctx.clone().new_child();
+        for ctx in [ctx.clone(), child.clone(), child_handler.context()] {
+            assert_eq!(ctx.value::<RequestId>(), Some(&1));
+            assert_eq!(ctx.value::<Tenant>().map(String::as_str), Some("scuffle"));
+        }
+
+        // Shadowing a value does not change the parent.
+        let shadowed = child.with_value::<RequestId>(2);
+        assert_eq!(shadowed.value::<RequestId>(), Some(&2));
+        assert_eq!(shadowed.value::<Tenant>().map(String::as_str), Some("scuffle"));
+        assert_eq!(child.value::<RequestId>(), Some(&1));
+
+        // Values are request scoped, the handler's own contexts do not get them.
+        assert_eq!(handler.context().value::<RequestId>(), None);
+
+        // A derived context shares the cancellation of the context it was created from.
+        handler.cancel();
+        assert!(shadowed.is_done());
+    }
+
+    #[tokio::test]
+    async fn shutdown_graceful_drained() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (child_ctx, _child_handler) = ctx.new_child();
+        assert!(!ctx.is_draining());
+
+        let task = tokio::spawn(async move {
+            ctx.draining().await;
+            // The context is still usable while draining.
+            assert!(!ctx.is_done());
+            assert!(child_ctx.is_draining());
+            assert!(!child_ctx.is_done());
+        });
+
+        let outcome = handler.shutdown_graceful(Duration::from_secs(1)).await;
+        assert_eq!(outcome, ShutdownOutcome::Drained);
+        assert!(handler.is_done());
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_graceful_escalated() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let task = tokio::spawn(async move {
+            // Ignores the request to drain and only stops when cancelled.
+            ctx.into_done().await
+        });
+
+        let outcome = handler.shutdown_graceful(Duration::from_millis(50)).await;
+        assert_eq!(outcome, ShutdownOutcome::Escalated);
+        assert!(matches!(task.await.unwrap(), CancellationReason::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn on_shutdown_order() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (child_ctx, child_handler) = ctx.new_child();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for (handler, name) in [(&handler, "parent"), (&child_handler, "child")] {
+            let order = order.clone();
+            handler.on_shutdown(async move { order.lock().unwrap().push(name) });
+        }
+
+        let task = tokio::spawn({
+            let order = order.clone();
+            async move {
+                child_ctx.done().await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                order.lock().unwrap().push("child context");
+                drop(child_ctx);
+                ctx.done().await;
+                order.lock().unwrap().push("context");
+            }
+        });
+
+        handler.shutdown().await;
+        task.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), ["child context", "context", "child", "parent"]);
+    }
+
+    #[tokio::test]
+    async fn on_shutdown_once() {
+        let handler = Handler::new();
+        let count = Arc::new(Mutex::new(0));
+
+        handler.on_shutdown({
+            let count = count.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                *count.lock().unwrap() += 1;
+            }
+        });
+
+        let other = handler.clone();
+        tokio::join!(handler.shutdown(), other.shutdown());
+        // Both shutdowns wait for the finisher.
+        assert_eq!(*count.lock().unwrap(), 1);
+
+        handler.shutdown().await;
+        assert_eq!(*count.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn shutdown_does_not_wait_for_children() {
+        let handler = Handler::new();
+        let (_child_ctx, _child) = handler.new_child();
+
+        // Without finishers, only the contexts of the handler itself are waited for.
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(200))
+            .await
+            .expect("shutdown waited for the contexts of a child");
+    }
+
+    #[tokio::test]
+    async fn on_shutdown_dropped_child() {
+        let handler = Handler::new();
+        let (ctx, child_handler) = handler.new_child();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for (handler, name) in [(&handler, "parent"), (&child_handler, "child")] {
+            let order = order.clone();
+            handler.on_shutdown(async move { order.lock().unwrap().push(name) });
+        }
+
+        // The child handler goes away with its finisher still pending.
+        drop(child_handler);
+        drop(ctx);
+
+        handler.shutdown().await;
+        assert_eq!(*order.lock().unwrap(), ["child", "parent"]);
+    }
+
+    #[tokio::test]
+    async fn drain() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (child_ctx, child_handler) = ctx.new_child();
+
+        handler.drain();
+        assert!(handler.is_draining());
+        assert!(ctx.is_draining());
+        assert!(child_handler.is_draining());
+        assert!(child_ctx.is_draining());
+        assert!(!child_ctx.is_done());
+
+        // A handler attached to a draining handler drains as well.
+        let (late_ctx, _late_handler) = ctx.new_child();
+        assert!(late_ctx.is_draining());
+
+        // A cancelled handler is always draining.
+        let other = Handler::new();
+        other.cancel();
+        assert!(other.is_draining());
+        assert!(other.context().is_draining());
+    }
+
+    #[tokio::test]
+    async fn active_count() {
+        let handler = Handler::new();
+        assert_eq!(handler.active_count(), 0);
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        handler.on_tracker_change({
+            let changes = changes.clone();
+            move |active| changes.lock().unwrap().push(active)
+        });
+
+        let ctx = handler.context();
+        let clone = ctx.clone();
+        let guard = ctx.track();
+        assert_eq!(handler.active_count(), 3);
+
+        // Contexts of child handlers are counted by the child.
+        let (child_ctx, child_handler) = ctx.new_child();
+        assert_eq!(handler.active_count(), 3);
+        assert_eq!(child_handler.active_count(), 1);
+
+        drop((ctx, clone, guard, child_ctx));
+        assert_eq!(handler.active_count(), 0);
+        assert_eq!(*changes.lock().unwrap(), [1, 2, 3, 2, 1, 0]);
+
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn weak_context() {
+        struct Key;
+
+        impl ContextKey for Key {
+            type Value = u32;
+        }
+
+        let handler = Handler::new();
+        let ctx = handler.context().with_value::<Key>(1);
+        let weak = ctx.downgrade();
+        assert_eq!(handler.active_count(), 1);
+
+        let upgraded = weak.upgrade().unwrap();
+        assert_eq!(handler.active_count(), 2);
+        assert_eq!(upgraded.value::<Key>(), Some(&1));
+        drop((ctx, upgraded));
+
+        // Only weak references are left, so the shutdown does not wait.
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(200))
+            .await
+            .expect("shutdown waited for a weak context");
+        assert!(weak.is_done());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(handler.active_count(), 0);
+
+        // A draining handler does not hand out new contexts either.
+        let handler = Handler::new();
+        let weak = handler.context().downgrade();
+        handler.drain();
+        assert!(!weak.is_done());
+        assert!(weak.upgrade().is_none());
+
+        // Nor does a dropped one.
+        let weak = Handler::new().context().downgrade();
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn pending_report() {
+        let handler = Handler::new_named("server");
+        assert_eq!(handler.name(), Some("server"));
+        assert!(handler.pending_report().is_empty());
+
+        let (session, session_handler) = handler.new_child_named("rtmp-session-123");
+        assert_eq!(session.name(), Some("rtmp-session-123"));
+        let (unnamed, _unnamed_handler) = session.new_child();
+        let (stream, _stream_handler) = session_handler.new_child_named("stream");
+        let _guard = stream.track();
+        drop(unnamed);
+
+        let report = handler.pending_report();
+        assert_eq!(
+            report.handlers,
+            [
+                PendingHandler {
+                    path: "server/rtmp-session-123".to_string(),
+                    active_count: 1,
+                },
+                PendingHandler {
+                    path: "server/rtmp-session-123/stream".to_string(),
+                    active_count: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            session_handler.pending_report().to_string(),
+            "rtmp-session-123: 1 active\nrtmp-session-123/stream: 2 active\n"
+        );
+
+        // The children are still listed while the handler shuts down.
+        handler.cancel();
+        assert_eq!(handler.pending_report(), report);
+
+        drop(stream);
+        assert_eq!(handler.pending_report().handlers.len(), 2);
+        drop((session, _guard));
+        assert!(handler.pending_report().is_empty());
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn assert_drained_within() {
+        let handler = Handler::new_named("server");
+        handler.assert_drained_within(Duration::ZERO).await;
+
+        let (session, _session_handler) = handler.new_child_named("session");
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(session);
+        });
+
+        handler.assert_drained_within(Duration::from_secs(1)).await;
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn assert_drained_within_panics() {
+        let handler = Handler::new_named("server");
+        let (_session, _session_handler) = handler.new_child_named("session");
+
+        let err = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.assert_drained_within(Duration::from_millis(10)).await }
+        })
+        .await
+        .unwrap_err();
+
+        let message = err.into_panic().downcast::<String>().unwrap();
+        assert_eq!(*message, "contexts still active after 10ms:\nserver/session: 1 active\n");
+    }
+
     #[tokio::test]
     async fn global_handler() {
         let handler = Handler::global();
@@ -413,4 +2325,122 @@ mod tests {
         assert!(child_handler.is_done());
         assert!(child_ctx.is_done());
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutdown_races_last_drop() {
+        // Drops the last context on another thread at the same time as the shutdown
+        // stops the tracker, the window where a lost notification would hang.
+        for _ in 0..1000 {
+            let handler = Handler::new();
+            let ctx = handler.context();
+            let barrier = Arc::new(std::sync::Barrier::new(2));
+
+            let thread = std::thread::spawn({
+                let barrier = barrier.clone();
+                move || {
+                    barrier.wait();
+                    drop(ctx);
+                }
+            });
+
+            barrier.wait();
+            handler
+                .shutdown()
+                .with_timeout(Duration::from_secs(1))
+                .await
+                .expect("shutdown missed the drop of the last context");
+            thread.join().unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutdown_races_upgrade() {
+        // Either the upgrade sees the shutdown and fails, or the shutdown waits for the upgraded context.
+        for _ in 0..1000 {
+            let handler = Handler::new();
+            let weak = handler.context().downgrade();
+            let barrier = Arc::new(std::sync::Barrier::new(2));
+            let written = Arc::new(Mutex::new(false));
+
+            let thread = std::thread::spawn({
+                let barrier = barrier.clone();
+                let written = written.clone();
+                move || {
+                    barrier.wait();
+                    if let Some(ctx) = weak.upgrade() {
+                        std::thread::yield_now();
+                        *written.lock().unwrap() = true;
+                        drop(ctx);
+                        true
+                    } else {
+                        false
+                    }
+                }
+            });
+
+            barrier.wait();
+            handler
+                .shutdown()
+                .with_timeout(Duration::from_secs(1))
+                .await
+                .expect("shutdown hung");
+
+            let upgraded_before_wait = *written.lock().unwrap();
+            let upgraded = thread.join().unwrap();
+            // An upgraded context held the shutdown open until it was dropped.
+            assert!(!upgraded || upgraded_before_wait);
+        }
+    }
+}
+
+/// Models of the tracker contract, run with `just loom`.
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::sync::Arc;
+    use loom::sync::atomic::AtomicBool;
+
+    use crate::Handler;
+
+    #[test]
+    fn shutdown_races_last_drop() {
+        // A lost notification leaves `wait` parked forever, which loom reports as a deadlock.
+        loom::model(|| {
+            let handler = Handler::new();
+            let threads = [handler.context(), handler.context()].map(|ctx| loom::thread::spawn(move || drop(ctx)));
+
+            handler.tracker.stop();
+            loom::future::block_on(handler.tracker.wait());
+
+            for thread in threads {
+                thread.join().unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn shutdown_races_upgrade() {
+        // Either the upgrade sees the shutdown and fails, or `wait` returns after the upgraded context is dropped.
+        loom::model(|| {
+            let handler = Handler::new();
+            let weak = handler.context().downgrade();
+            let waited = Arc::new(AtomicBool::new(false));
+
+            let thread = loom::thread::spawn({
+                let waited = waited.clone();
+                move || {
+                    if let Some(ctx) = weak.upgrade() {
+                        assert!(!waited.load(std::sync::atomic::Ordering::SeqCst));
+                        drop(ctx);
+                    }
+                }
+            });
+
+            handler.tracker.stop();
+            loom::future::block_on(handler.tracker.wait());
+            waited.store(true, std::sync::atomic::Ordering::SeqCst);
+
+            thread.join().unwrap();
+        });
+    }
 }
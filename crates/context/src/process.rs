@@ -0,0 +1,177 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{Context, Handler};
+
+/// The byte written to the pipe when the parent context is cancelled.
+const CANCEL_BYTE: u8 = b'c';
+
+impl Context {
+    /// Mirrors the cancellation of this context to another process.
+    ///
+    /// Waits for the context to be done and then writes a cancel message to `writer`,
+    /// the write end of a pipe dedicated to it, such as one created with
+    /// [`std::io::pipe`] whose read end is inherited by the child process. The
+    /// child process can use [`Handler::cancel_from`] on the read end to cancel its
+    /// own handler. The pipe must not carry anything else, so a pipe that is used
+    /// for other data, like the stdin of the child, does not work.
+    ///
+    /// The context is held until the message is written. If the parent should also
+    /// wait for the child to exit during a graceful shutdown, keep a clone of the
+    /// context alive until the child has exited.
+    ///
+    /// # Example
+    ///
+    /// Both ends in one process, in place of the two ends of the pipe:
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, Handler};
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    /// let child_handler = Handler::new();
+    ///
+    /// let (writer, reader) = tokio::io::duplex(64);
+    /// tokio::spawn(ctx.forward_cancel(writer));
+    ///
+    /// handler.cancel();
+    /// // The child's handler is cancelled as well.
+    /// child_handler.cancel_from(reader).await.unwrap();
+    /// assert!(child_handler.is_done());
+    /// # });
+    /// ```
+    pub async fn forward_cancel<W: AsyncWrite + Unpin>(self, mut writer: W) -> std::io::Result<()> {
+        self.done().await;
+
+        writer.write_all(&[CANCEL_BYTE]).await?;
+        writer.flush().await
+    }
+}
+
+impl Handler {
+    /// Cancels this handler once the parent process cancels its context.
+    ///
+    /// This is the counterpart of [`Context::forward_cancel`]. It reads from `reader`,
+    /// the read end of the pipe dedicated to it, until the cancel message is
+    /// received and then cancels the handler.
+    /// Reaching the end of the stream (for example because the parent process exited
+    /// and the pipe was closed) or a read error also cancels the handler, so a child
+    /// process never outlives its parent by accident.
+    ///
+    /// Any other data means the pipe is not dedicated to the cancel message. The
+    /// handler is cancelled as well and an [`InvalidData`](std::io::ErrorKind::InvalidData)
+    /// error is returned.
+    pub async fn cancel_from<R: AsyncRead + Unpin>(&self, mut reader: R) -> std::io::Result<()> {
+        let mut buf = [0];
+
+        let result = match reader.read(&mut buf).await {
+            Ok(0) => Ok(()),
+            Ok(_) if buf[0] == CANCEL_BYTE => Ok(()),
+            Ok(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "unexpected data instead of the cancel message",
+            )),
+            Err(err) => Err(err),
+        };
+
+        self.cancel();
+        result
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use scuffle_future_ext::FutureExt;
+    use tokio::io::AsyncWriteExt;
+
+    use crate::Handler;
+
+    #[tokio::test]
+    async fn forward_cancel() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let child_handler = Handler::new();
+        let child_ctx = child_handler.context();
+
+        let (writer, reader) = tokio::io::duplex(64);
+
+        let forward = tokio::spawn(ctx.forward_cancel(writer));
+        let listen = tokio::spawn({
+            let child_handler = child_handler.clone();
+            async move { child_handler.cancel_from(reader).await }
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!child_ctx.is_done());
+
+        handler.cancel();
+
+        forward.await.unwrap().unwrap();
+        listen
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert!(child_ctx.is_done());
+        assert!(child_handler.is_done());
+    }
+
+    #[tokio::test]
+    async fn cancel_on_eof() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let (writer, reader) = tokio::io::duplex(64);
+
+        let listen = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.cancel_from(reader).await }
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!ctx.is_done());
+
+        drop(writer);
+
+        listen
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn cancel_on_unexpected_data() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let (mut writer, reader) = tokio::io::duplex(64);
+
+        // Data other than the cancel message, such as the stdin of the child, is an error.
+        writer.write_all(b"hello").await.unwrap();
+
+        let err = handler
+            .cancel_from(reader)
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(ctx.is_done());
+    }
+
+    #[tokio::test]
+    async fn forward_cancel_closed_pipe() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (writer, reader) = tokio::io::duplex(64);
+        drop(reader);
+
+        handler.cancel();
+
+        assert!(ctx.forward_cancel(writer).await.is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::time::Duration;
+
+use crate::{Context, ContextFutExt};
+
+/// How [`Context::run_retry`] waits between attempts.
+///
+/// The delay starts at the initial delay and is multiplied by the multiplier
+/// after every failed attempt, up to the maximum delay. Each delay is then
+/// randomly moved by up to the jitter fraction in either direction, so many
+/// tasks that fail at once do not all retry at the same time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_attempts: Option<u32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.1,
+            max_attempts: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy, which starts at 100ms, doubles up to 30s
+    /// with 10% jitter and retries forever.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the delay after the first failed attempt.
+    pub const fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Sets the largest delay between two attempts.
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the factor the delay grows by after every failed attempt, at least 1.
+    pub const fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Sets the fraction each delay is randomly moved by, between 0 and 1.
+    pub const fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Limits the number of attempts, `None` retries until the context is done.
+    pub const fn with_max_attempts(mut self, max_attempts: Option<u32>) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Returns the delay before the attempt after `failures` failed attempts, without jitter.
+    fn base_delay(&self, failures: u32) -> Duration {
+        let factor = self.multiplier.powi(failures.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = self.initial_delay.as_secs_f64() * factor;
+
+        Duration::try_from_secs_f64(delay)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Returns the delay before the attempt after `failures` failed attempts.
+    fn delay(&self, failures: u32) -> Duration {
+        let delay = self.base_delay(failures);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+
+        // A random number in 0..1, the standard library has no rng but hashers are randomly seeded.
+        let random = (RandomState::new().hash_one(failures) >> 11) as f64 / (1u64 << 53) as f64;
+        delay.mul_f64(1.0 + self.jitter * (random * 2.0 - 1.0))
+    }
+}
+
+impl Context {
+    /// Runs futures created by `factory` until one succeeds, waiting between
+    /// attempts as configured by `policy`.
+    ///
+    /// This is the loop around a connection that has to be rebuilt when it fails,
+    /// such as a session with an upstream server. Both the attempts and the waits
+    /// are cancelled when the context is done, and as the returned future borrows
+    /// the context it counts as tracked work until it finishes.
+    ///
+    /// Returns `None` if the context is done first, the output of the first
+    /// successful attempt, or the error of the last attempt once the maximum
+    /// number of attempts is reached.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, RetryPolicy};
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let mut attempts = 0;
+    /// let result = ctx
+    ///     .run_retry(
+    ///         || {
+    ///             attempts += 1;
+    ///             let attempt = attempts;
+    ///             async move { if attempt < 3 { Err("not yet") } else { Ok(attempt) } }
+    ///         },
+    ///         RetryPolicy::new().with_initial_delay(std::time::Duration::from_millis(1)),
+    ///     )
+    ///     .await;
+    ///
+    /// assert_eq!(result, Some(Ok(3)));
+    /// # drop(ctx);
+    /// # handler.shutdown().await;
+    /// # });
+    /// ```
+    pub async fn run_retry<F, Fut, T, E>(&self, mut factory: F, policy: RetryPolicy) -> Option<Result<T, E>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut failures = 0u32;
+
+        loop {
+            let err = match factory().with_context(self).await? {
+                Ok(value) => return Some(Ok(value)),
+                Err(err) => err,
+            };
+
+            failures = failures.saturating_add(1);
+            if policy.max_attempts.is_some_and(|max| failures >= max) {
+                return Some(Err(err));
+            }
+
+            tokio::time::sleep(policy.delay(failures)).with_context(self).await?;
+        }
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use crate::{Context, RetryPolicy};
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy::new()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(1))
+            .with_jitter(0.0);
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(4), Duration::from_millis(800));
+        assert_eq!(policy.delay(5), Duration::from_secs(1));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn delay_jitter() {
+        let policy = RetryPolicy::new()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_jitter(0.5);
+
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(150));
+        }
+    }
+
+    #[tokio::test]
+    async fn run_retry_max_attempts() {
+        let (ctx, handler) = Context::new();
+        let attempts = AtomicU32::new(0);
+
+        let result = ctx
+            .run_retry(
+                || async {
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    Err::<(), _>("failed")
+                },
+                RetryPolicy::new()
+                    .with_initial_delay(Duration::from_millis(1))
+                    .with_max_attempts(Some(3)),
+            )
+            .await;
+
+        assert_eq!(result, Some(Err("failed")));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+        drop(ctx);
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn run_retry_cancel() {
+        let (ctx, handler) = Context::new();
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let task = tokio::spawn({
+            let attempts = attempts.clone();
+            async move {
+                ctx.run_retry(
+                    || async {
+                        attempts.fetch_add(1, Ordering::Relaxed);
+                        Err::<(), _>("failed")
+                    },
+                    RetryPolicy::new().with_initial_delay(Duration::from_secs(60)),
+                )
+                .await
+            }
+        });
+
+        while attempts.load(Ordering::Relaxed) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The task is waiting for the next attempt and holds the shutdown open until it is cancelled.
+        handler.shutdown().await;
+
+        assert_eq!(task.await.unwrap(), None);
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,125 @@
+use std::future::Future;
+
+use crate::{CancellationReason, Handler};
+
+/// The signal that cancelled a handler, see [`Handler::cancel_on_signal`].
+///
+/// The handler is cancelled with it as a [`CancellationReason::Custom`] reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    /// `SIGINT` on Unix, Ctrl-C on Windows.
+    Interrupt,
+    /// `SIGTERM` on Unix, closing the console on Windows.
+    Terminate,
+}
+
+impl Handler {
+    /// Returns a future that cancels this handler when the process receives a shutdown signal.
+    ///
+    /// On Unix these are `SIGINT` and `SIGTERM`, on Windows Ctrl-C and closing
+    /// the console. The signal listeners are installed right away, so a signal
+    /// received before the future is polled is not lost. The future resolves
+    /// with the signal after cancelling the handler with it as the reason.
+    ///
+    /// Once installed, the default action of the signals (terminating the
+    /// process) no longer applies for the rest of the process lifetime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal listeners cannot be installed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use scuffle_context::{Handler, ShutdownSignal};
+    /// # tokio_test::block_on(async {
+    /// let handler = Handler::new();
+    /// tokio::spawn(handler.cancel_on_signal().unwrap());
+    ///
+    /// let reason = handler.context().done().await;
+    /// println!("shutting down: {:?}", reason.downcast_ref::<ShutdownSignal>());
+    /// # });
+    /// ```
+    pub fn cancel_on_signal(&self) -> std::io::Result<impl Future<Output = ShutdownSignal> + Send + 'static> {
+        #[cfg(unix)]
+        let (mut interrupt, mut terminate) = {
+            use tokio::signal::unix::{SignalKind, signal};
+            (signal(SignalKind::interrupt())?, signal(SignalKind::terminate())?)
+        };
+
+        #[cfg(windows)]
+        let (mut interrupt, mut terminate) = (tokio::signal::windows::ctrl_c()?, tokio::signal::windows::ctrl_close()?);
+
+        let handler = self.clone();
+
+        Ok(async move {
+            let signal = tokio::select! {
+                _ = interrupt.recv() => ShutdownSignal::Interrupt,
+                _ = terminate.recv() => ShutdownSignal::Terminate,
+            };
+
+            handler.cancel_with(CancellationReason::custom(signal));
+            signal
+        })
+    }
+}
+
+/// Returns a future that cancels the global handler when the process receives a shutdown signal.
+///
+/// The same as calling [`Handler::cancel_on_signal`] on [`Handler::global`],
+/// so [`Context::global`](crate::Context::global) and every context created
+/// with [`Context::new`](crate::Context::new) are cancelled.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use scuffle_context::Context;
+/// # tokio_test::block_on(async {
+/// tokio::spawn(scuffle_context::install_signal_handler().unwrap());
+///
+/// let (ctx, handler) = Context::new();
+/// // Run the service with `ctx`, until a signal is received.
+/// ctx.done().await;
+/// handler.shutdown().await;
+/// # });
+/// ```
+pub fn install_signal_handler() -> std::io::Result<impl Future<Output = ShutdownSignal> + Send + 'static> {
+    Handler::global().cancel_on_signal()
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, unix))]
+mod tests {
+    use scuffle_future_ext::FutureExt;
+
+    use crate::{Handler, ShutdownSignal};
+
+    #[tokio::test]
+    async fn cancel_on_signal() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        let listener = tokio::spawn(handler.cancel_on_signal().unwrap());
+        assert!(!ctx.is_done());
+
+        let status = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let signal = listener
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(signal, ShutdownSignal::Terminate);
+
+        let reason = ctx.done().await;
+        assert_eq!(reason.downcast_ref::<ShutdownSignal>(), Some(&ShutdownSignal::Terminate));
+    }
+}
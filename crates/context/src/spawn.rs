@@ -0,0 +1,181 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::{Context, ContextFutExt};
+
+/// Spawns a future on the Tokio runtime, cancelled when `ctx` is done.
+///
+/// This is the same as [`Context::spawn`], but takes ownership of the context.
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+pub fn spawn<F>(ctx: Context, fut: F) -> JoinHandle<Option<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(fut.with_context(ctx))
+}
+
+impl Context {
+    /// Spawns a future on the Tokio runtime, cancelled when this context is done.
+    ///
+    /// A shorthand for `tokio::spawn(fut.with_context(ctx.clone()))`. The task
+    /// holds a clone of the context, so it counts as active work for
+    /// [`Handler::shutdown`](crate::Handler::shutdown) until it finishes.
+    ///
+    /// The task outputs `None` if the context is done before the future
+    /// finishes. Aborting the returned handle drops the future and the context
+    /// along with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::Context;
+    /// # tokio_test::block_on(async {
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let task = ctx.spawn(async {
+    ///     tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    /// });
+    ///
+    /// drop(ctx);
+    /// // Will stop the spawned task.
+    /// handler.shutdown().await;
+    /// assert_eq!(task.await.unwrap(), None);
+    /// # });
+    /// ```
+    pub fn spawn<F>(&self, fut: F) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        spawn(self.clone(), fut)
+    }
+
+    /// Spawns a future on the Tokio runtime, which may keep running for `grace`
+    /// after this context is done before it is aborted.
+    ///
+    /// Use this for work that should get a chance to finish cleanly, such as
+    /// flushing a file, and only be cut off if it takes too long. The future
+    /// can check [`Context::is_done`] on a clone of the context to learn that
+    /// it should wrap up.
+    ///
+    /// The task outputs `None` if the future was aborted. It counts as active
+    /// work for [`Handler::shutdown`](crate::Handler::shutdown) until it
+    /// finishes or is aborted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn spawn_graceful<F>(&self, fut: F, grace: Duration) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let ctx = self.clone();
+        tokio::spawn(async move {
+            let mut fut = pin!(fut);
+
+            {
+                let mut done = pin!(ctx.done());
+                let output = std::future::poll_fn(|cx| {
+                    if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                        return Poll::Ready(Some(output));
+                    }
+
+                    done.as_mut().poll(cx).map(|_| None)
+                })
+                .await;
+
+                if output.is_some() {
+                    return output;
+                }
+            }
+
+            let output = tokio::time::timeout(grace, fut).await.ok();
+            drop(ctx);
+            output
+        })
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use scuffle_future_ext::FutureExt;
+
+    use crate::Context;
+
+    #[tokio::test]
+    async fn spawn() {
+        let (ctx, handler) = Context::new();
+
+        let task = super::spawn(ctx.clone(), async { 1 });
+        assert_eq!(task.await.unwrap(), Some(1));
+
+        let task = ctx.spawn(std::future::pending::<()>());
+        drop(ctx);
+        handler.shutdown().await;
+        assert_eq!(task.await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn spawn_abort() {
+        let (ctx, handler) = Context::new();
+
+        let task = ctx.spawn(std::future::pending::<()>());
+        drop(ctx);
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+
+        // The context of the aborted task was dropped along with it.
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_millis(200))
+            .await
+            .expect("shutdown waited for an aborted task");
+    }
+
+    #[tokio::test]
+    async fn spawn_graceful() {
+        let (ctx, handler) = Context::new();
+
+        // Finishes within the grace period.
+        let task = ctx.spawn_graceful(
+            {
+                let ctx = ctx.clone();
+                async move {
+                    ctx.done().await;
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    1
+                }
+            },
+            Duration::from_secs(10),
+        );
+
+        // Never finishes, so it is aborted after the grace period.
+        let stuck = ctx.spawn_graceful(std::future::pending::<()>(), Duration::from_millis(10));
+
+        drop(ctx);
+        handler
+            .shutdown()
+            .with_timeout(Duration::from_secs(1))
+            .await
+            .expect("shutdown did not finish");
+
+        assert_eq!(task.await.unwrap(), Some(1));
+        assert_eq!(stuck.await.unwrap(), None);
+    }
+}
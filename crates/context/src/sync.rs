@@ -0,0 +1,94 @@
+//! The synchronization primitives of the tracker.
+//!
+//! In tests built with `--cfg loom` they are replaced by
+//! [loom](https://docs.rs/loom) ones, so the models in the tests explore every
+//! interleaving of stopping, tracking and waiting.
+
+#[cfg(not(all(test, loom)))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize, fence};
+
+#[cfg(all(test, loom))]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicUsize, fence};
+#[cfg(not(all(test, loom)))]
+pub(crate) use tokio::sync::Notify;
+
+#[cfg(all(test, loom))]
+pub(crate) use self::notify::Notify;
+
+/// A loom stand-in for [`tokio::sync::Notify`], which loom can not see into.
+#[cfg(all(test, loom))]
+mod notify {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    use loom::sync::Mutex;
+
+    /// The part of [`tokio::sync::Notify`] used by the tracker.
+    ///
+    /// Like the one of tokio, a [`Notified`] future receives every
+    /// [`Notify::notify_waiters`] call made after it was created, even if it was
+    /// not polled yet.
+    pub(crate) struct Notify {
+        state: Mutex<State>,
+    }
+
+    #[derive(Default)]
+    struct State {
+        /// The number of `notify_waiters` calls so far.
+        generation: usize,
+        wakers: Vec<Waker>,
+    }
+
+    impl std::fmt::Debug for Notify {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Notify").finish_non_exhaustive()
+        }
+    }
+
+    impl Notify {
+        pub(crate) fn new() -> Self {
+            Self {
+                state: Mutex::new(State::default()),
+            }
+        }
+
+        pub(crate) fn notified(&self) -> Notified<'_> {
+            Notified {
+                notify: self,
+                generation: self.state.lock().unwrap().generation,
+            }
+        }
+
+        pub(crate) fn notify_waiters(&self) {
+            let wakers = {
+                let mut state = self.state.lock().unwrap();
+                state.generation += 1;
+                std::mem::take(&mut state.wakers)
+            };
+
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+
+    pub(crate) struct Notified<'a> {
+        notify: &'a Notify,
+        generation: usize,
+    }
+
+    impl Future for Notified<'_> {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.notify.state.lock().unwrap();
+            if state.generation != self.generation {
+                return Poll::Ready(());
+            }
+
+            state.wakers.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll, ready};
+
+use tokio::task::futures::TaskLocalFuture;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{CancellationReason, Context, Handler};
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: Context;
+}
+
+impl Context {
+    /// Returns the context of the request being handled, set by a [`ContextLayer`].
+    ///
+    /// Returns `None` outside of a request handled by a [`ContextService`],
+    /// including in tasks spawned from it. Pass the context along explicitly to
+    /// spawned tasks instead. The context is done once the response is ready, so
+    /// it does not cover streaming bodies, see
+    /// [streaming responses](ContextLayer#streaming-responses).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextFutExt};
+    /// async fn handler() -> &'static str {
+    ///     let ctx = Context::current().expect("no request context");
+    ///
+    ///     // Stops once the client disconnects or the server shuts down.
+    ///     match tokio::time::sleep(std::time::Duration::from_secs(1)).with_context(ctx).await {
+    ///         Some(()) => "done",
+    ///         None => "cancelled",
+    ///     }
+    /// }
+    /// ```
+    pub fn current() -> Option<Context> {
+        REQUEST_CONTEXT.try_with(Context::clone).ok()
+    }
+}
+
+/// The [`CancellationReason`] of a request context whose response future was
+/// dropped before it finished, usually because the client disconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientDisconnected;
+
+/// A [`Layer`] that handles every request with its own child context of a [`Handler`].
+///
+/// Works with any [`tower`](https://docs.rs/tower) based server, such as
+/// `tonic` and `axum`. The context of a request can be retrieved with
+/// [`Context::current`] while the request is handled, and is:
+///
+/// - cancelled when the handler is, so requests see a server shutdown,
+/// - cancelled with [`ClientDisconnected`] when the response future is dropped
+///   before it finished, which hyper does when the client disconnects,
+/// - cancelled once the response is ready, stopping work spawned with it,
+/// - counted as active work until then, so [`Handler::shutdown`] waits for
+///   in-flight requests. The request context is a context of a child handler,
+///   which the shutdown of the handler does not wait for, so every request
+///   also holds a context of the handler given to the layer.
+///
+/// # Streaming responses
+///
+/// The response is ready once the response future resolves, which for HTTP
+/// servers is when the headers are ready, not when the body has been sent.
+/// The request context is done before a streaming body is polled, and the
+/// shutdown no longer waits for the body. Streaming bodies, such as server
+/// sent events or tonic server streams, should use a context of the handler
+/// given to the layer instead, attached with
+/// [`ContextStreamExt::with_context`](crate::ContextStreamExt::with_context).
+/// It is cancelled by a shutdown and counted as active work until the body is
+/// dropped, which hyper does once the body ends or the client disconnects.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let handler = Handler::new_named("http");
+/// let app = axum::Router::new()
+///     .route("/", axum::routing::get(index))
+///     .route("/events", axum::routing::get(events))
+///     .with_state(handler.clone())
+///     .layer(ContextLayer::new(handler.clone()));
+///
+/// async fn events(State(handler): State<Handler>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+///     // Not `Context::current()`, which is done as soon as this returns.
+///     Sse::new(event_stream().with_context(handler.context()))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextLayer {
+    handler: Handler,
+}
+
+impl ContextLayer {
+    /// Creates a layer creating request contexts as children of `handler`.
+    pub fn new(handler: Handler) -> Self {
+        Self { handler }
+    }
+}
+
+impl<S> Layer<S> for ContextLayer {
+    type Service = ContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ContextService {
+            inner,
+            handler: self.handler.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`ContextLayer`].
+#[derive(Debug, Clone)]
+pub struct ContextService<S> {
+    inner: S,
+    handler: Handler,
+}
+
+impl<S, R> Service<R> for ContextService<S>
+where
+    S: Service<R>,
+{
+    type Error = S::Error;
+    type Future = ContextResponseFuture<S::Future>;
+    type Response = S::Response;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        // Held until the response is ready, the shutdown of the handler does not wait for the child.
+        let parent = self.handler.context();
+        let (ctx, handler) = parent.new_child();
+
+        // Work done by the service before returning its future sees the context as well.
+        let inner = REQUEST_CONTEXT.sync_scope(ctx.clone(), || self.inner.call(request));
+
+        ContextResponseFuture {
+            inner: REQUEST_CONTEXT.scope(ctx, inner),
+            guard: CancelGuard {
+                handler,
+                completed: false,
+                _parent: parent,
+            },
+        }
+    }
+}
+
+/// Cancels the request context when the response future is dropped.
+#[derive(Debug)]
+struct CancelGuard {
+    handler: Handler,
+    completed: bool,
+    /// The context of the handler given to the layer, dropped after the request context is cancelled.
+    _parent: Context,
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            self.handler.cancel();
+        } else {
+            self.handler.cancel_with(CancellationReason::custom(ClientDisconnected));
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response future of [`ContextService`].
+    ///
+    /// Cancels the request context once it resolves, see [streaming responses](ContextLayer#streaming-responses).
+    pub struct ContextResponseFuture<F> {
+        #[pin]
+        inner: TaskLocalFuture<Context, F>,
+        guard: CancelGuard,
+    }
+}
+
+impl<F: Future> Future for ContextResponseFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.inner.poll(cx));
+        this.guard.completed = true;
+        Poll::Ready(output)
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context as TaskContext, Poll};
+    use std::time::Duration;
+
+    use scuffle_future_ext::FutureExt;
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    use super::{ClientDisconnected, ContextLayer};
+    use crate::{CancellationReason, Context, Handler};
+
+    /// Stores the context seen by `call` and responds with the context seen by the future after `delay`.
+    #[derive(Clone)]
+    struct Capture {
+        called_with: Arc<Mutex<Option<Context>>>,
+        delay: Duration,
+    }
+
+    impl Capture {
+        fn new(delay: Duration) -> Self {
+            Self {
+                called_with: Arc::default(),
+                delay,
+            }
+        }
+
+        fn called_with(&self) -> Context {
+            self.called_with.lock().unwrap().clone().expect("service was not called")
+        }
+    }
+
+    impl Service<()> for Capture {
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<Option<Context>, Infallible>> + Send>>;
+        type Response = Option<Context>;
+
+        fn poll_ready(&mut self, _: &mut TaskContext<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            *self.called_with.lock().unwrap() = Context::current();
+
+            let delay = self.delay;
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                Ok(Context::current())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_context() {
+        assert!(Context::current().is_none());
+
+        let handler = Handler::new();
+        let capture = Capture::new(Duration::ZERO);
+        let mut service = ContextLayer::new(handler.clone()).layer(capture.clone());
+
+        let ctx = service.call(()).await.unwrap().expect("no context in the response future");
+
+        // The service and its future see the same context, which is cancelled once the response is ready.
+        assert!(ctx.is_done());
+        assert!(capture.called_with().is_done());
+        assert!(matches!(ctx.reason(), Some(CancellationReason::Cancelled)));
+        assert!(!handler.is_done());
+    }
+
+    #[tokio::test]
+    async fn test_request_context_client_disconnected() {
+        let handler = Handler::new();
+        let capture = Capture::new(Duration::from_secs(60));
+        let mut service = ContextLayer::new(handler.clone()).layer(capture.clone());
+
+        let mut fut = Box::pin(service.call(()));
+        assert!(futures_lite::future::poll_once(&mut fut).await.is_none());
+
+        let ctx = capture.called_with();
+        assert!(!ctx.is_done());
+
+        // Dropping the response future before it finished is what a disconnect looks like.
+        drop(fut);
+        assert!(ctx.is_done());
+        assert!(ctx.reason().unwrap().downcast_ref::<ClientDisconnected>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_request_context_shutdown() {
+        let handler = Handler::new();
+        let capture = Capture::new(Duration::from_secs(60));
+        let mut service = ContextLayer::new(handler.clone()).layer(capture.clone());
+
+        let mut fut = Box::pin(service.call(()));
+        assert!(futures_lite::future::poll_once(&mut fut).await.is_none());
+
+        // Requests see the handler being shut down, and the shutdown waits for them.
+        let shutdown = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.shutdown().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(capture.called_with().is_done());
+        assert!(!shutdown.is_finished());
+
+        drop(fut);
+        shutdown
+            .with_timeout(Duration::from_millis(200))
+            .await
+            .expect("shutdown timed out")
+            .unwrap();
+    }
+}
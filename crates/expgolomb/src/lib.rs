@@ -53,6 +53,9 @@ use scuffle_bytes_util::{BitReader, BitWriter};
 /// - [`BitReader`]
 pub trait BitReaderExpGolombExt {
     /// Reads an Exp-Golomb encoded number
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the encoded number does not fit in a `u64`,
+    /// which means there are more than 63 leading zero bits.
     fn read_exp_golomb(&mut self) -> io::Result<u64>;
 
     /// Reads a signed Exp-Golomb encoded number
@@ -72,6 +75,12 @@ impl<R: io::Read> BitReaderExpGolombExt for BitReader<R> {
         let mut leading_zeros = 0;
         while !self.read_bit()? {
             leading_zeros += 1;
+            if leading_zeros > 63 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "exp-golomb value does not fit in a u64",
+                ));
+            }
         }
 
         let mut result = 1;
@@ -91,15 +100,17 @@ impl<R: io::Read> BitReaderExpGolombExt for BitReader<R> {
 /// - [`BitWriter`]
 pub trait BitWriterExpGolombExt {
     /// Writes an Exp-Golomb encoded number
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error for [`u64::MAX`], which would need 64 leading
+    /// zero bits and could not be read back by [`BitReaderExpGolombExt::read_exp_golomb`].
     fn write_exp_golomb(&mut self, input: u64) -> io::Result<()>;
 
     /// Writes a signed Exp-Golomb encoded number
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error for [`i64::MIN`], which would be encoded as 2^64.
     fn write_signed_exp_golomb(&mut self, number: i64) -> io::Result<()> {
-        let number = if number <= 0 {
-            -number as u64 * 2
-        } else {
-            number as u64 * 2 - 1
-        };
+        let number = signed_to_unsigned(number)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "signed exp-golomb value does not fit in a u64"))?;
 
         self.write_exp_golomb(number)
     }
@@ -107,48 +118,58 @@ pub trait BitWriterExpGolombExt {
 
 impl<W: io::Write> BitWriterExpGolombExt for BitWriter<W> {
     fn write_exp_golomb(&mut self, input: u64) -> io::Result<()> {
-        let mut number = input + 1;
-        let mut leading_zeros = 0;
-        while number > 1 {
-            number >>= 1;
-            leading_zeros += 1;
-        }
+        let Some(number) = input.checked_add(1) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "exp-golomb value does not fit in a u64",
+            ));
+        };
 
+        let leading_zeros = number.ilog2() as u8;
         for _ in 0..leading_zeros {
             self.write_bit(false)?;
         }
 
-        self.write_bits(input + 1, leading_zeros + 1)?;
+        self.write_bits(number, leading_zeros + 1)?;
 
         Ok(())
     }
 }
 
+/// Maps a signed number onto the unsigned number it is encoded as: 0, 1, -1, 2, -2, ... become 0, 1, 2, 3, 4, ...
+///
+/// Returns `None` for [`i64::MIN`], which maps to 2^64.
+fn signed_to_unsigned(number: i64) -> Option<u64> {
+    if number <= 0 {
+        number.unsigned_abs().checked_mul(2)
+    } else {
+        Some(number as u64 * 2 - 1)
+    }
+}
+
 /// Returns the number of bits that a signed Exp-Golomb encoded number would take up.
 ///
 /// See: <https://en.wikipedia.org/wiki/Exponential-Golomb_coding>
 pub fn size_of_signed_exp_golomb(number: i64) -> u64 {
     let number = if number <= 0 {
-        -number as u64 * 2
+        number.unsigned_abs() as u128 * 2
     } else {
-        number as u64 * 2 - 1
+        number as u128 * 2 - 1
     };
 
-    size_of_exp_golomb(number)
+    size_of_exp_golomb_u128(number)
 }
 
 /// Returns the number of bits that an Exp-Golomb encoded number would take up.
 ///
 /// See: <https://en.wikipedia.org/wiki/Exponential-Golomb_coding>
 pub fn size_of_exp_golomb(number: u64) -> u64 {
-    let mut number = number + 1;
-    let mut leading_zeros = 0;
-    while number > 1 {
-        number >>= 1;
-        leading_zeros += 1;
-    }
+    size_of_exp_golomb_u128(number as u128)
+}
 
-    leading_zeros * 2 + 1
+/// The size computation of [`size_of_exp_golomb`], with room for the encodings that do not fit in a `u64`.
+fn size_of_exp_golomb_u128(number: u128) -> u64 {
+    (number + 1).ilog2() as u64 * 2 + 1
 }
 
 #[cfg(test)]
@@ -367,6 +388,21 @@ mod tests {
         assert_eq!(get_remaining_bits(&bit_reader), remaining_bits - 154);
     }
 
+    #[test]
+    fn test_exp_glob_decode_too_large() {
+        // 64 leading zeros can never be a valid u64.
+        let mut bit_reader = BitReader::new(std::io::Cursor::new(vec![0; 9]));
+        let err = bit_reader.read_exp_golomb().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // 63 leading zeros are the largest encoding that fits.
+        let mut bit_writer = BitWriter::<Vec<u8>>::default();
+        bit_writer.write_exp_golomb(u64::MAX - 1).unwrap();
+        let data = bit_writer.finish().unwrap();
+        let mut bit_reader = BitReader::new(std::io::Cursor::new(data));
+        assert_eq!(bit_reader.read_exp_golomb().unwrap(), u64::MAX - 1);
+    }
+
     #[test]
     fn test_expg_sizes() {
         assert_eq!(1, size_of_exp_golomb(0)); // 0b1
@@ -384,5 +420,32 @@ mod tests {
         assert_eq!(5, size_of_signed_exp_golomb(-2)); // 0b00101
         assert_eq!(5, size_of_signed_exp_golomb(3)); // 0b00110
         assert_eq!(5, size_of_signed_exp_golomb(-3)); // 0b00111
+
+        // 63 leading zeros, the 1 and 63 bits
+        assert_eq!(127, size_of_exp_golomb(u64::MAX - 1));
+        assert_eq!(127, size_of_signed_exp_golomb(i64::MIN + 1));
+        // these do not fit in a u64 when encoded, so they can not be written
+        assert_eq!(129, size_of_exp_golomb(u64::MAX));
+        assert_eq!(129, size_of_signed_exp_golomb(i64::MIN));
+    }
+
+    #[test]
+    fn test_exp_glob_encode_too_large() {
+        let mut bit_writer = BitWriter::<Vec<u8>>::default();
+
+        let err = bit_writer.write_exp_golomb(u64::MAX).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let err = bit_writer.write_signed_exp_golomb(i64::MIN).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // nothing was written
+        assert!(bit_writer.finish().unwrap().is_empty());
+
+        let mut bit_writer = BitWriter::<Vec<u8>>::default();
+        bit_writer.write_signed_exp_golomb(i64::MIN + 1).unwrap();
+        let data = bit_writer.finish().unwrap();
+        let mut bit_reader = BitReader::new(std::io::Cursor::new(data));
+        assert_eq!(bit_reader.read_signed_exp_golomb().unwrap(), i64::MIN + 1);
     }
 }
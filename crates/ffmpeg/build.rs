@@ -0,0 +1,101 @@
+//! Detects the version of the FFmpeg libraries the crate is built against.
+//!
+//! The bindings from `rusty_ffmpeg` are generated from the installed headers, so
+//! items added in newer versions of FFmpeg only exist when building against them.
+//! Emits the following cfgs, each meaning "at least this version":
+//!
+//! - `ffmpeg_7`: FFmpeg 7.0 (libavcodec 61)
+//! - `ffmpeg_7_1`: FFmpeg 7.1 (libavcodec 61.19)
+//!
+//! Without either the crate is built for FFmpeg 6.1 (libavcodec 60.31).
+//! Set `SCUFFLE_FFMPEG_VERSION` (for example `6.1`) to skip the detection.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A libavcodec version, `(major, minor)`.
+type Version = (u32, u32);
+
+/// The version assumed when it cannot be detected, the latest supported one.
+const FALLBACK: Version = (61, 19);
+
+fn parse_version(version: &str) -> Option<Version> {
+    let mut parts = version.trim().split('.').map(|part| part.parse::<u32>().ok());
+    Some((parts.next()??, parts.next().flatten().unwrap_or(0)))
+}
+
+/// Maps an FFmpeg release such as `6.1` or `7` to its libavcodec version.
+fn ffmpeg_to_libavcodec((major, minor): Version) -> Version {
+    match (major, minor) {
+        (6, _) => (60, 31),
+        (7, 0) => (61, 3),
+        (7, _) => (61, 19),
+        _ => FALLBACK,
+    }
+}
+
+/// Asks pkg-config for the version of libavcodec, honoring the same search path as `rusty_ffmpeg`.
+fn from_pkg_config() -> Option<Version> {
+    let mut command = Command::new("pkg-config");
+    command.args(["--modversion", "libavcodec"]);
+
+    if let Some(path) = std::env::var_os("FFMPEG_PKG_CONFIG_PATH") {
+        let mut paths = vec![PathBuf::from(path)];
+        if let Some(existing) = std::env::var_os("PKG_CONFIG_PATH") {
+            paths.extend(std::env::split_paths(&existing));
+        }
+        command.env("PKG_CONFIG_PATH", std::env::join_paths(paths).ok()?);
+    }
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_version(std::str::from_utf8(&output.stdout).ok()?)
+}
+
+/// Reads the version defines from the libavcodec headers in `FFMPEG_INCLUDE_DIR`.
+fn from_headers() -> Option<Version> {
+    let include_dir = PathBuf::from(std::env::var_os("FFMPEG_INCLUDE_DIR")?);
+
+    let define = |file: &str, name: &str| -> Option<u32> {
+        let header = std::fs::read_to_string(include_dir.join("libavcodec").join(file)).ok()?;
+        header.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("#define")?.trim().strip_prefix(name)?;
+            value.trim().parse().ok()
+        })
+    };
+
+    let major = define("version_major.h", "LIBAVCODEC_VERSION_MAJOR")?;
+    let minor = define("version.h", "LIBAVCODEC_VERSION_MINOR")?;
+    Some((major, minor))
+}
+
+fn main() {
+    println!("cargo::rerun-if-env-changed=SCUFFLE_FFMPEG_VERSION");
+    println!("cargo::rerun-if-env-changed=FFMPEG_PKG_CONFIG_PATH");
+    println!("cargo::rerun-if-env-changed=FFMPEG_INCLUDE_DIR");
+    println!("cargo::rerun-if-env-changed=PKG_CONFIG_PATH");
+    println!("cargo::rustc-check-cfg=cfg(ffmpeg_7)");
+    println!("cargo::rustc-check-cfg=cfg(ffmpeg_7_1)");
+
+    let version = std::env::var("SCUFFLE_FFMPEG_VERSION")
+        .ok()
+        .and_then(|version| parse_version(&version))
+        .map(ffmpeg_to_libavcodec)
+        .or_else(from_pkg_config)
+        .or_else(from_headers)
+        .unwrap_or_else(|| {
+            println!("cargo::warning=could not detect the FFmpeg version, assuming FFmpeg 7.1");
+            FALLBACK
+        });
+
+    if version >= (61, 0) {
+        println!("cargo::rustc-cfg=ffmpeg_7");
+    }
+
+    if version >= (61, 19) {
+        println!("cargo::rustc-cfg=ffmpeg_7_1");
+    }
+}
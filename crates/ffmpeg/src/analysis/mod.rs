@@ -0,0 +1,2 @@
+/// Audio waveform peak extraction, for rendering waveforms in a UI.
+pub mod waveform;
@@ -0,0 +1,348 @@
+use crate::decoder::Decoder;
+use crate::error::FfmpegError;
+use crate::frame::{AudioChannelLayout, AudioFrame};
+use crate::io::Input;
+use crate::resampler::Resampler;
+use crate::{AVMediaType, AVSampleFormat};
+
+/// How the samples of a bucket are reduced to peaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PeakMode {
+    /// Two values per bucket, the smallest and the largest sample.
+    #[default]
+    MinMax,
+    /// One value per bucket, the root mean square of the samples.
+    Rms,
+}
+
+/// Options for extracting a [`Waveform`].
+#[derive(Debug, Clone, bon::Builder)]
+pub struct WaveformOptions {
+    /// The number of buckets per second of audio.
+    #[builder(default = 100)]
+    pub buckets_per_second: u32,
+    /// How the samples of a bucket are reduced to peaks.
+    #[builder(default)]
+    pub mode: PeakMode,
+}
+
+impl Default for WaveformOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// The peaks of an audio stream, downmixed to mono.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// The sample rate the peaks were computed at.
+    pub sample_rate: i32,
+    /// The number of buckets per second of audio.
+    pub buckets_per_second: u32,
+    /// How the samples of a bucket were reduced to peaks.
+    pub mode: PeakMode,
+    /// The peaks, in the range `-1.0..=1.0`.
+    ///
+    /// For [`PeakMode::MinMax`] this contains a `min, max` pair per bucket,
+    /// for [`PeakMode::Rms`] a single value per bucket.
+    pub peaks: Vec<f32>,
+}
+
+impl Waveform {
+    /// Returns the number of buckets in the waveform.
+    pub fn bucket_count(&self) -> usize {
+        match self.mode {
+            PeakMode::MinMax => self.peaks.len() / 2,
+            PeakMode::Rms => self.peaks.len(),
+        }
+    }
+}
+
+/// Computes a [`Waveform`] from decoded [`AudioFrame`]s.
+///
+/// Frames are resampled to mono `f32` at the sample rate of the first frame,
+/// so any sample format, planar or packed, and channel layout is accepted.
+pub struct WaveformAnalyzer {
+    options: WaveformOptions,
+    resampler: Option<(Resampler, (AVSampleFormat, i32, usize))>,
+    accumulator: Option<PeakAccumulator>,
+}
+
+impl WaveformAnalyzer {
+    /// Creates a new analyzer.
+    pub fn new(options: WaveformOptions) -> Result<Self, FfmpegError> {
+        if options.buckets_per_second == 0 {
+            return Err(FfmpegError::Arguments("buckets_per_second must be positive"));
+        }
+
+        Ok(Self {
+            options,
+            resampler: None,
+            accumulator: None,
+        })
+    }
+
+    /// Adds a decoded frame to the waveform.
+    pub fn push_frame(&mut self, frame: &AudioFrame) -> Result<(), FfmpegError> {
+        if frame.sample_rate() <= 0 {
+            return Err(FfmpegError::Arguments("sample_rate must be positive"));
+        }
+
+        let input = (AVSampleFormat(frame.format()), frame.sample_rate(), frame.channel_count());
+        let accumulator = self.accumulator.get_or_insert_with(|| {
+            PeakAccumulator::new(self.options.mode, self.options.buckets_per_second, frame.sample_rate())
+        });
+
+        // The resampler is recreated if the input changes mid stream, the output sample rate stays the same.
+        let resampler = match &mut self.resampler {
+            Some((resampler, current)) if *current == input => resampler,
+            resampler => {
+                let new = Resampler::new(
+                    AudioChannelLayout::new(input.2 as i32)?,
+                    input.0,
+                    input.1,
+                    AudioChannelLayout::new(1)?,
+                    AVSampleFormat::Flt,
+                    accumulator.sample_rate,
+                )?;
+                &mut resampler.insert((new, input)).0
+            }
+        };
+
+        let mono = resampler.process(frame)?;
+        let len = mono.nb_samples().max(0) as usize * std::mem::size_of::<f32>();
+        let Some(data) = mono.data(0) else {
+            return Ok(());
+        };
+
+        for sample in data[..len.min(data.len())].chunks_exact(std::mem::size_of::<f32>()) {
+            accumulator.push(f32::from_ne_bytes(sample.try_into().expect("chunk is 4 bytes")));
+        }
+
+        Ok(())
+    }
+
+    /// Finishes the last bucket and returns the waveform.
+    pub fn finish(self) -> Waveform {
+        match self.accumulator {
+            Some(accumulator) => accumulator.finish(),
+            None => Waveform {
+                sample_rate: 0,
+                buckets_per_second: self.options.buckets_per_second,
+                mode: self.options.mode,
+                peaks: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Decodes the best audio stream of `input` and returns its [`Waveform`].
+pub fn waveform<T: Send + Sync>(input: &mut Input<T>, options: WaveformOptions) -> Result<Waveform, FfmpegError> {
+    let streams = input.streams();
+    let stream = streams.best(AVMediaType::Audio).ok_or(FfmpegError::NoStream)?;
+    let stream_index = stream.index();
+    let mut decoder = Decoder::new(&stream)?.audio().map_err(|_| FfmpegError::NoDecoder)?;
+
+    let mut analyzer = WaveformAnalyzer::new(options)?;
+
+    while let Some(packet) = input.receive_packet()? {
+        if packet.stream_index() != stream_index {
+            continue;
+        }
+
+        decoder.send_packet(&packet)?;
+        while let Some(frame) = decoder.receive_frame()? {
+            analyzer.push_frame(&frame)?;
+        }
+    }
+
+    decoder.send_eof()?;
+    while let Some(frame) = decoder.receive_frame()? {
+        analyzer.push_frame(&frame)?;
+    }
+
+    Ok(analyzer.finish())
+}
+
+/// Splits a stream of mono samples into buckets and reduces each bucket to its peaks.
+struct PeakAccumulator {
+    mode: PeakMode,
+    buckets_per_second: u32,
+    sample_rate: i32,
+    /// The number of samples seen so far.
+    samples: u64,
+    /// The index of the current bucket.
+    bucket: u64,
+    /// The sample index at which the current bucket ends.
+    bucket_end: u64,
+    min: f32,
+    max: f32,
+    sum_squares: f64,
+    count: u64,
+    peaks: Vec<f32>,
+}
+
+impl PeakAccumulator {
+    fn new(mode: PeakMode, buckets_per_second: u32, sample_rate: i32) -> Self {
+        let mut accumulator = Self {
+            mode,
+            buckets_per_second,
+            sample_rate,
+            samples: 0,
+            bucket: 0,
+            bucket_end: 0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            sum_squares: 0.0,
+            count: 0,
+            peaks: Vec::new(),
+        };
+        accumulator.bucket_end = accumulator.end_of(0);
+        accumulator
+    }
+
+    /// Bucket boundaries are computed from the bucket index, so a sample rate
+    /// that is not a multiple of the bucket rate does not drift over time.
+    fn end_of(&self, bucket: u64) -> u64 {
+        (bucket + 1) * self.sample_rate.max(0) as u64 / self.buckets_per_second as u64
+    }
+
+    fn push(&mut self, sample: f32) {
+        while self.samples >= self.bucket_end {
+            self.flush();
+        }
+
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+        self.sum_squares += sample as f64 * sample as f64;
+        self.count += 1;
+        self.samples += 1;
+    }
+
+    fn flush(&mut self) {
+        match self.mode {
+            PeakMode::MinMax if self.count == 0 => self.peaks.extend([0.0, 0.0]),
+            PeakMode::MinMax => self.peaks.extend([self.min, self.max]),
+            PeakMode::Rms if self.count == 0 => self.peaks.push(0.0),
+            PeakMode::Rms => self.peaks.push((self.sum_squares / self.count as f64).sqrt() as f32),
+        }
+
+        self.min = f32::INFINITY;
+        self.max = f32::NEG_INFINITY;
+        self.sum_squares = 0.0;
+        self.count = 0;
+        self.bucket += 1;
+        self.bucket_end = self.end_of(self.bucket);
+    }
+
+    fn finish(mut self) -> Waveform {
+        if self.count > 0 {
+            self.flush();
+        }
+
+        Waveform {
+            sample_rate: self.sample_rate,
+            buckets_per_second: self.buckets_per_second,
+            mode: self.mode,
+            peaks: self.peaks,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::PeakAccumulator;
+    use crate::AVSampleFormat;
+    use crate::analysis::waveform::{PeakMode, WaveformAnalyzer, WaveformOptions, waveform};
+    use crate::frame::{AudioChannelLayout, AudioFrame};
+    use crate::io::Input;
+
+    #[test]
+    fn test_peak_accumulator_min_max() {
+        let mut accumulator = PeakAccumulator::new(PeakMode::MinMax, 2, 8);
+        for sample in [0.1, -0.5, 0.25, 0.0, 1.0, 0.5, -0.25, 0.0, 0.75] {
+            accumulator.push(sample);
+        }
+
+        let waveform = accumulator.finish();
+        assert_eq!(waveform.bucket_count(), 3);
+        assert_eq!(waveform.peaks, vec![-0.5, 0.25, -0.25, 1.0, 0.75, 0.75]);
+    }
+
+    #[test]
+    fn test_peak_accumulator_rms() {
+        let mut accumulator = PeakAccumulator::new(PeakMode::Rms, 2, 8);
+        for sample in [0.5, -0.5, 0.5, -0.5, 0.0, 0.0, 0.0, 0.0] {
+            accumulator.push(sample);
+        }
+
+        let waveform = accumulator.finish();
+        assert_eq!(waveform.peaks, vec![0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_peak_accumulator_fractional_buckets() {
+        // 3 buckets per second at 10 samples per second, buckets of 3, 3 and 4 samples.
+        let mut accumulator = PeakAccumulator::new(PeakMode::Rms, 3, 10);
+        for _ in 0..20 {
+            accumulator.push(1.0);
+        }
+
+        assert_eq!(accumulator.finish().bucket_count(), 6);
+
+        // More buckets than samples, empty buckets are filled with silence.
+        let mut accumulator = PeakAccumulator::new(PeakMode::MinMax, 4, 2);
+        accumulator.push(0.5);
+        accumulator.push(-0.5);
+        assert_eq!(accumulator.finish().peaks, vec![0.0, 0.0, 0.5, 0.5, 0.0, 0.0, -0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_waveform_analyzer_frames() {
+        assert!(WaveformAnalyzer::new(WaveformOptions::builder().buckets_per_second(0).build()).is_err());
+
+        let mut analyzer = WaveformAnalyzer::new(WaveformOptions::builder().buckets_per_second(4).build())
+            .expect("Failed to create analyzer");
+
+        let mut frame = AudioFrame::builder()
+            .channel_layout(AudioChannelLayout::new(1).expect("Failed to create channel layout"))
+            .nb_samples(8000)
+            .sample_fmt(AVSampleFormat::S16)
+            .sample_rate(8000)
+            .build()
+            .expect("Failed to create frame");
+
+        let data = frame.data_mut(0).expect("Failed to get data");
+        for (i, sample) in data.chunks_exact_mut(2).enumerate() {
+            // Half scale square wave, silent in the second half.
+            let value: i16 = if i >= 4000 {
+                0
+            } else if i % 2 == 0 {
+                16384
+            } else {
+                -16384
+            };
+            sample.copy_from_slice(&value.to_ne_bytes());
+        }
+
+        analyzer.push_frame(&frame).expect("Failed to push frame");
+        let waveform = analyzer.finish();
+
+        assert_eq!(waveform.sample_rate, 8000);
+        assert_eq!(waveform.bucket_count(), 4);
+        assert_eq!(waveform.peaks, vec![-0.5, 0.5, -0.5, 0.5, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_waveform_file() {
+        let mut input = Input::open("../../assets/avc_aac.mp4").expect("Failed to open file");
+        let waveform = waveform(&mut input, WaveformOptions::builder().mode(PeakMode::Rms).build())
+            .expect("Failed to compute waveform");
+
+        assert_eq!(waveform.buckets_per_second, 100);
+        assert!(waveform.bucket_count() > 0);
+        assert!(waveform.peaks.iter().all(|peak| (0.0..=1.0).contains(peak)));
+        assert!(waveform.peaks.iter().any(|peak| *peak > 0.0));
+    }
+}
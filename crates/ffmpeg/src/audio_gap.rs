@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::frame::AudioFrame;
+use crate::rational::Rational;
+
+/// Options for an [`AudioGapFiller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioGapOptions {
+    /// Gaps up to this long are ignored, as sources often have small timestamp jitter.
+    pub tolerance: Duration,
+    /// Gaps longer than this are reported but not filled, because they are more
+    /// likely a discontinuity in the source than lost frames.
+    pub max_gap: Duration,
+    /// Whether gaps are filled with silence, or only reported.
+    pub fill: bool,
+}
+
+impl Default for AudioGapOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: Duration::from_millis(20),
+            max_gap: Duration::from_secs(2),
+            fill: true,
+        }
+    }
+}
+
+/// A snapshot of the metrics of an [`AudioGapFiller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AudioGapMetrics {
+    /// The number of gaps found.
+    pub gaps: u64,
+    /// The number of gaps that were filled with silence.
+    pub filled: u64,
+    /// The number of silent samples inserted.
+    pub silence_samples: u64,
+    /// The number of frames that started before the previous frame ended.
+    pub overlaps: u64,
+}
+
+/// A gap in the timestamps of an audio stream, found before a frame.
+#[derive(Debug)]
+pub struct AudioGap {
+    /// Where the previous frame ended, in the time base of the frames.
+    pub pts: i64,
+    /// The length of the gap, in the time base of the frames.
+    pub duration: i64,
+    /// The time base of the frames.
+    pub time_base: Rational,
+    /// Silent frames covering the gap, to be sent before the frame.
+    ///
+    /// Empty if the gap was not filled, because filling is disabled, the gap is longer
+    /// than [`AudioGapOptions::max_gap`] or it is shorter than half a frame.
+    pub silence: Vec<AudioFrame>,
+}
+
+impl AudioGap {
+    /// Returns the length of the gap as a [`Duration`].
+    pub fn to_duration(&self) -> Duration {
+        let micros = rescale(self.duration, self.time_base.into(), AVRational { num: 1, den: 1_000_000 });
+        Duration::from_micros(micros.max(0) as u64)
+    }
+}
+
+/// Finds gaps in the timestamps of decoded audio, and fills them with silence.
+///
+/// Flaky sources, such as live ingest over a bad connection, lose audio frames while
+/// the video keeps going. Encoders and muxers pack audio samples back to back, so
+/// every lost frame moves the audio ahead of the video. Inserting silence of the same
+/// length keeps them in sync.
+///
+/// Frames must be passed in pts order. Each gap is filled with whole frames of the size
+/// of the frame after the gap, so encoders with a fixed frame size accept them. The
+/// remainder of less than a frame is left as is.
+#[derive(Debug)]
+pub struct AudioGapFiller {
+    options: AudioGapOptions,
+    /// Where the previous frame ended, in the time base of the frames.
+    next_pts: Option<i64>,
+    metrics: AudioGapMetrics,
+}
+
+/// Rescales `value` from one time base to another, rounding to the nearest value.
+fn rescale(value: i64, from: AVRational, to: AVRational) -> i64 {
+    // Safety: `av_rescale_q` is safe to call.
+    unsafe { av_rescale_q(value, from, to) }
+}
+
+/// Converts a duration to the given time base.
+fn duration_to(duration: Duration, time_base: AVRational) -> i64 {
+    let micros = i64::try_from(duration.as_micros()).unwrap_or(i64::MAX);
+    rescale(micros, AVRational { num: 1, den: 1_000_000 }, time_base)
+}
+
+impl AudioGapFiller {
+    /// Creates a new gap filler.
+    pub fn new(options: AudioGapOptions) -> Self {
+        Self {
+            options,
+            next_pts: None,
+            metrics: AudioGapMetrics::default(),
+        }
+    }
+
+    /// Checks `frame` for a gap since the previous frame.
+    ///
+    /// Returns the gap, with the silence to send before `frame`, or `None` if the frame
+    /// follows the previous frame. Frames without a pts, time base or sample rate are
+    /// not checked.
+    pub fn check(&mut self, frame: &AudioFrame) -> Result<Option<AudioGap>, FfmpegError> {
+        let time_base: AVRational = frame.time_base().into();
+        let (Some(pts), true, true) = (frame.pts(), time_base.num > 0, frame.sample_rate() > 0) else {
+            return Ok(None);
+        };
+
+        let sample_time_base = AVRational {
+            num: 1,
+            den: frame.sample_rate(),
+        };
+        let frame_duration = rescale(frame.nb_samples() as i64, sample_time_base, time_base);
+        let next_pts = self.next_pts.replace(pts.saturating_add(frame_duration));
+
+        let Some(next_pts) = next_pts else {
+            return Ok(None);
+        };
+
+        let gap = pts.saturating_sub(next_pts);
+        let tolerance = duration_to(self.options.tolerance, time_base);
+        if gap < -tolerance {
+            self.metrics.overlaps += 1;
+            return Ok(None);
+        }
+
+        if gap <= tolerance {
+            return Ok(None);
+        }
+
+        self.metrics.gaps += 1;
+
+        let mut silence = Vec::new();
+        if self.options.fill && gap <= duration_to(self.options.max_gap, time_base) && frame.nb_samples() > 0 {
+            let gap_samples = rescale(gap, time_base, sample_time_base);
+            let frames = (gap_samples + frame.nb_samples() as i64 / 2) / frame.nb_samples() as i64;
+
+            for index in 0..frames {
+                let mut silent = frame.silence_like(frame.nb_samples())?;
+                silent.set_pts(Some(next_pts + index * frame_duration));
+                silent.set_duration(Some(frame_duration));
+                silence.push(silent);
+            }
+
+            if !silence.is_empty() {
+                self.metrics.filled += 1;
+                self.metrics.silence_samples += (frames * frame.nb_samples() as i64) as u64;
+            }
+        }
+
+        Ok(Some(AudioGap {
+            pts: next_pts,
+            duration: gap,
+            time_base: time_base.into(),
+            silence,
+        }))
+    }
+
+    /// Forgets the previous frame, so the next frame is not checked.
+    ///
+    /// Call this after a seek or another intended jump in the timestamps.
+    pub fn reset(&mut self) {
+        self.next_pts = None;
+    }
+
+    /// Returns the options of the gap filler.
+    pub const fn options(&self) -> &AudioGapOptions {
+        &self.options
+    }
+
+    /// Returns a snapshot of the metrics.
+    pub const fn metrics(&self) -> AudioGapMetrics {
+        self.metrics
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::AVSampleFormat;
+    use crate::audio_gap::{AudioGapFiller, AudioGapMetrics, AudioGapOptions};
+    use crate::frame::{AudioChannelLayout, AudioFrame};
+    use crate::rational::Rational;
+
+    /// A frame of 1024 samples at 48kHz, in a 1/48000 time base.
+    fn frame(pts: i64, sample_fmt: AVSampleFormat) -> AudioFrame {
+        AudioFrame::builder()
+            .channel_layout(AudioChannelLayout::new(2).expect("failed to create layout"))
+            .nb_samples(1024)
+            .sample_fmt(sample_fmt)
+            .sample_rate(48000)
+            .pts(pts)
+            .time_base(Rational::static_new::<1, 48000>())
+            .build()
+            .expect("failed to build frame")
+    }
+
+    #[test]
+    fn test_no_gap() {
+        let mut filler = AudioGapFiller::new(AudioGapOptions::default());
+
+        for index in 0..10 {
+            // A bit of jitter is within the tolerance.
+            let pts = index * 1024 + if index % 2 == 0 { 0 } else { 100 };
+            assert!(filler.check(&frame(pts, AVSampleFormat::Fltp)).unwrap().is_none());
+        }
+
+        assert_eq!(filler.metrics(), AudioGapMetrics::default());
+    }
+
+    #[test]
+    fn test_fill_gap() {
+        let mut filler = AudioGapFiller::new(AudioGapOptions::default());
+
+        assert!(filler.check(&frame(0, AVSampleFormat::U8)).unwrap().is_none());
+
+        // Three frames are missing.
+        let gap = filler
+            .check(&frame(4 * 1024, AVSampleFormat::U8))
+            .unwrap()
+            .expect("no gap found");
+        assert_eq!(gap.pts, 1024);
+        assert_eq!(gap.duration, 3 * 1024);
+        assert_eq!(gap.to_duration(), Duration::from_millis(64));
+
+        assert_eq!(gap.silence.len(), 3);
+        for (index, silence) in gap.silence.iter().enumerate() {
+            assert_eq!(silence.pts(), Some(1024 + index as i64 * 1024));
+            assert_eq!(silence.duration(), Some(1024));
+            assert_eq!(silence.nb_samples(), 1024);
+            assert_eq!(silence.sample_format(), AVSampleFormat::U8);
+            assert_eq!(silence.time_base(), Rational::static_new::<1, 48000>());
+            // Silence of unsigned samples is the middle value.
+            assert!(silence.data(0).unwrap()[..2048].iter().all(|sample| *sample == 0x80));
+        }
+
+        assert_eq!(
+            filler.metrics(),
+            AudioGapMetrics {
+                gaps: 1,
+                filled: 1,
+                silence_samples: 3 * 1024,
+                overlaps: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_gap_not_filled() {
+        let mut filler = AudioGapFiller::new(AudioGapOptions {
+            max_gap: Duration::from_secs(1),
+            ..Default::default()
+        });
+
+        assert!(filler.check(&frame(0, AVSampleFormat::Fltp)).unwrap().is_none());
+
+        // Longer than the max gap.
+        let gap = filler
+            .check(&frame(48000 * 2, AVSampleFormat::Fltp))
+            .unwrap()
+            .expect("no gap found");
+        assert!(gap.silence.is_empty());
+
+        // Only reported.
+        let mut filler = AudioGapFiller::new(AudioGapOptions {
+            fill: false,
+            ..Default::default()
+        });
+        assert!(filler.check(&frame(0, AVSampleFormat::Fltp)).unwrap().is_none());
+        let gap = filler
+            .check(&frame(4 * 1024, AVSampleFormat::Fltp))
+            .unwrap()
+            .expect("no gap found");
+        assert!(gap.silence.is_empty());
+
+        assert_eq!(filler.metrics().gaps, 1);
+        assert_eq!(filler.metrics().filled, 0);
+    }
+
+    #[test]
+    fn test_overlap_and_reset() {
+        let mut filler = AudioGapFiller::new(AudioGapOptions::default());
+
+        assert!(filler.check(&frame(48000, AVSampleFormat::Fltp)).unwrap().is_none());
+        assert!(filler.check(&frame(0, AVSampleFormat::Fltp)).unwrap().is_none());
+        assert_eq!(filler.metrics().overlaps, 1);
+
+        // After a reset the next frame is not compared to the previous one.
+        filler.reset();
+        assert!(filler.check(&frame(96000, AVSampleFormat::Fltp)).unwrap().is_none());
+        assert_eq!(filler.metrics().gaps, 0);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::dict::{Dictionary, MetadataKey};
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::rational::Rational;
+
+/// A chapter of an input or output, such as a scene of a film.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    /// The id of the chapter, unique within an input or output.
+    pub id: i64,
+    /// The time base of the start and end of the chapter.
+    pub time_base: Rational,
+    /// The start of the chapter, in the time base of the chapter.
+    pub start: i64,
+    /// The end of the chapter, in the time base of the chapter.
+    pub end: i64,
+    /// The metadata of the chapter, usually its title.
+    pub metadata: Dictionary,
+}
+
+impl Chapter {
+    /// Creates a chapter without metadata.
+    pub fn new(id: i64, time_base: impl Into<Rational>, start: i64, end: i64) -> Self {
+        Self {
+            id,
+            time_base: time_base.into(),
+            start,
+            end,
+            metadata: Dictionary::new(),
+        }
+    }
+
+    /// Sets the title of the chapter.
+    pub fn with_title(mut self, title: &str) -> Result<Self, FfmpegError> {
+        self.metadata.set(MetadataKey::Title, title)?;
+        Ok(self)
+    }
+
+    /// Returns the title of the chapter, if it has one.
+    pub fn title(&self) -> Option<&str> {
+        self.metadata.get_str(MetadataKey::Title)
+    }
+
+    /// Returns the start of the chapter in seconds.
+    pub fn start_seconds(&self) -> f64 {
+        self.start as f64 * self.time_base.as_f64()
+    }
+
+    /// Returns the end of the chapter in seconds.
+    pub fn end_seconds(&self) -> f64 {
+        self.end as f64 * self.time_base.as_f64()
+    }
+}
+
+/// Copies the chapters of a format context.
+pub(crate) fn read_chapters(context: &AVFormatContext) -> Vec<Chapter> {
+    if context.chapters.is_null() {
+        return Vec::new();
+    }
+
+    // Safety: `chapters` points to `nb_chapters` chapters owned by the format context.
+    let chapters = unsafe { std::slice::from_raw_parts(context.chapters, context.nb_chapters as usize) };
+    chapters
+        .iter()
+        // Safety: The chapters are valid for as long as the format context.
+        .filter_map(|chapter| unsafe { chapter.as_ref() })
+        .map(|chapter| Chapter {
+            id: chapter.id,
+            time_base: chapter.time_base.into(),
+            start: chapter.start,
+            end: chapter.end,
+            // Safety: The metadata is owned by the chapter, the clone copies it.
+            metadata: unsafe { Dictionary::from_ptr_ref(chapter.metadata) }.clone(),
+        })
+        .collect()
+}
+
+/// Adds a chapter to a format context, which frees it together with the context.
+pub(crate) fn add_chapter(context: &mut AVFormatContext, chapter: Chapter) -> Result<(), FfmpegError> {
+    if chapter.end < chapter.start {
+        return Err(FfmpegError::Arguments("chapter ends before it starts"));
+    }
+
+    if read_chapters(context).iter().any(|existing| existing.id == chapter.id) {
+        return Err(FfmpegError::Arguments("chapter id already exists"));
+    }
+
+    // Safety: `av_mallocz` is safe to call, the chapter is zeroed which is a valid `AVChapter`.
+    let ptr = unsafe { av_mallocz(std::mem::size_of::<AVChapter>()) } as *mut AVChapter;
+    // Safety: The pointer is either null or points to a zeroed `AVChapter`.
+    let Some(raw) = (unsafe { ptr.as_mut() }) else {
+        return Err(FfmpegError::Alloc);
+    };
+
+    raw.id = chapter.id;
+    raw.time_base = chapter.time_base.into();
+    raw.start = chapter.start;
+    raw.end = chapter.end;
+    raw.metadata = chapter.metadata.leak();
+
+    // Safety: `chapters` is an array of `nb_chapters` chapter pointers allocated by FFmpeg,
+    // `av_dynarray_add_nofree` grows it with `av_realloc`.
+    let ret = unsafe {
+        av_dynarray_add_nofree(
+            (&raw mut context.chapters).cast(),
+            (&raw mut context.nb_chapters).cast(),
+            ptr.cast(),
+        )
+    };
+
+    if ret < 0 {
+        // Safety: The chapter was not added, so we still own it and its metadata.
+        unsafe {
+            av_dict_free(&mut raw.metadata);
+            av_free(ptr.cast());
+        }
+
+        return Err(FfmpegError::Alloc);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::chapter::Chapter;
+    use crate::rational::Rational;
+
+    #[test]
+    fn test_chapter_seconds() {
+        let chapter = Chapter::new(1, Rational::static_new::<1, 1000>(), 1500, 4000)
+            .with_title("Intro")
+            .expect("Failed to set title");
+
+        assert_eq!(chapter.title(), Some("Intro"));
+        assert_eq!(chapter.start_seconds(), 1.5);
+        assert_eq!(chapter.end_seconds(), 4.0);
+    }
+}
@@ -1,6 +1,6 @@
 use rusty_ffmpeg::ffi::*;
 
-use crate::AVCodecID;
+use crate::{AVCodecID, AVHWDeviceType, AVPixelFormat};
 
 /// A wrapper around an [`AVCodec`] pointer.
 ///
@@ -60,6 +60,12 @@ impl DecoderCodec {
         self.0
     }
 
+    /// Returns the hardware pixel format the codec uses with a device of `device_type`,
+    /// or `None` if it cannot use the device.
+    pub fn hw_pixel_format(&self, device_type: AVHWDeviceType) -> Option<AVPixelFormat> {
+        crate::hwdevice::codec_hw_pixel_format(self.0, device_type)
+    }
+
     /// Creates a [`DecoderCodec`] from a raw pointer.
     ///
     /// # Safety
@@ -126,6 +132,12 @@ impl EncoderCodec {
         self.0
     }
 
+    /// Returns the hardware pixel format the codec uses with a device of `device_type`,
+    /// or `None` if it cannot use the device.
+    pub fn hw_pixel_format(&self, device_type: AVHWDeviceType) -> Option<AVPixelFormat> {
+        crate::hwdevice::codec_hw_pixel_format(self.0, device_type)
+    }
+
     /// Creates an [`EncoderCodec`] from a raw pointer.
     ///
     /// # Safety
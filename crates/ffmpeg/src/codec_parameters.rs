@@ -0,0 +1,315 @@
+use crate::decoder::GenericDecoder;
+use crate::encoder::Encoder;
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::rational::Rational;
+use crate::smart_object::SmartPtr;
+use crate::stream::Stream;
+use crate::{AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
+
+/// An owned copy of the parameters of a codec, a wrapper around an [`AVCodecParameters`].
+///
+/// These describe an encoded stream: the codec, the dimensions or sample rate and the
+/// extradata. They can be taken from a [`Stream`], a decoder or an [`Encoder`], inspected,
+/// and copied into another stream for stream copy or into a codec context.
+pub struct CodecParameters(SmartPtr<AVCodecParameters>);
+
+/// Safety: `CodecParameters` owns its data and can be sent between threads.
+unsafe impl Send for CodecParameters {}
+
+impl std::fmt::Debug for CodecParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CodecParameters")
+            .field("codec_type", &self.codec_type())
+            .field("codec_id", &self.codec_id())
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .field("sample_rate", &self.sample_rate())
+            .field("channels", &self.channels())
+            .field("bit_rate", &self.bit_rate())
+            .field("extradata_size", &self.extradata().len())
+            .finish()
+    }
+}
+
+impl Clone for CodecParameters {
+    fn clone(&self) -> Self {
+        let mut params = Self::new().expect("failed to allocate codec parameters");
+        params.copy_from(self.as_ptr()).expect("failed to copy codec parameters");
+        params
+    }
+}
+
+impl CodecParameters {
+    /// Allocates new, empty codec parameters.
+    pub fn new() -> Result<Self, FfmpegError> {
+        // Safety: `avcodec_parameters_alloc` is safe to call.
+        let params = unsafe { avcodec_parameters_alloc() };
+
+        let destructor = |ptr: &mut *mut AVCodecParameters| {
+            // Safety: The pointer here is valid, it comes from `avcodec_parameters_alloc`.
+            unsafe { avcodec_parameters_free(ptr) };
+        };
+
+        // Safety: `params` is a valid pointer, and `destructor` has been setup to free the parameters.
+        let params = unsafe { SmartPtr::wrap_non_null(params, destructor) }.ok_or(FfmpegError::Alloc)?;
+
+        Ok(Self(params))
+    }
+
+    /// Copies the codec parameters of a stream.
+    pub fn from_stream(stream: &Stream<'_>) -> Result<Self, FfmpegError> {
+        let params = stream.codec_parameters().ok_or(FfmpegError::NoStream)?;
+
+        let mut copy = Self::new()?;
+        copy.copy_from(params)?;
+        Ok(copy)
+    }
+
+    /// Takes the codec parameters of an opened decoder.
+    pub fn from_decoder(decoder: &GenericDecoder) -> Result<Self, FfmpegError> {
+        Self::from_context(decoder.as_ptr())
+    }
+
+    /// Takes the codec parameters of an opened encoder, including the extradata it produced.
+    pub fn from_encoder(encoder: &Encoder) -> Result<Self, FfmpegError> {
+        Self::from_context(encoder.as_ptr())
+    }
+
+    fn from_context(context: *const AVCodecContext) -> Result<Self, FfmpegError> {
+        let mut params = Self::new()?;
+
+        // Safety: Both pointers are valid, the context is borrowed for the duration of the call.
+        FfmpegErrorCode(unsafe { avcodec_parameters_from_context(params.0.as_mut_ptr(), context) }).result()?;
+
+        Ok(params)
+    }
+
+    fn copy_from(&mut self, params: *const AVCodecParameters) -> Result<(), FfmpegError> {
+        // Safety: Both pointers are valid, `avcodec_parameters_copy` replaces the previous contents.
+        FfmpegErrorCode(unsafe { avcodec_parameters_copy(self.0.as_mut_ptr(), params) }).result()?;
+        Ok(())
+    }
+
+    /// Copies the codec parameters into a stream, for example an output stream
+    /// that packets are copied to without decoding them.
+    pub fn copy_to_stream(&self, stream: &mut Stream<'_>) -> Result<(), FfmpegError> {
+        let params = stream.codec_parameters_mut().ok_or(FfmpegError::NoStream)?;
+
+        // Safety: Both pointers are valid, `avcodec_parameters_copy` replaces the previous contents.
+        FfmpegErrorCode(unsafe { avcodec_parameters_copy(params, self.as_ptr()) }).result()?;
+
+        Ok(())
+    }
+
+    /// Copies the codec parameters into a codec context, before it is opened.
+    ///
+    /// This sets up an encoder with the same dimensions, formats and bit rate as
+    /// the stream the parameters describe.
+    pub fn copy_to_encoder_context(&self, context: &mut AVCodecContext) -> Result<(), FfmpegError> {
+        // Safety: Both pointers are valid, the context is borrowed mutably for the duration of the call.
+        FfmpegErrorCode(unsafe { avcodec_parameters_to_context(context, self.as_ptr()) }).result()?;
+        Ok(())
+    }
+
+    /// Returns a pointer to the codec parameters.
+    pub const fn as_ptr(&self) -> *const AVCodecParameters {
+        self.0.as_ptr()
+    }
+
+    /// Returns a mutable pointer to the codec parameters.
+    pub const fn as_mut_ptr(&mut self) -> *mut AVCodecParameters {
+        self.0.as_mut_ptr()
+    }
+
+    /// Returns the media type of the codec.
+    pub const fn codec_type(&self) -> AVMediaType {
+        AVMediaType(self.0.as_deref_except().codec_type)
+    }
+
+    /// Returns the id of the codec.
+    pub const fn codec_id(&self) -> AVCodecID {
+        AVCodecID(self.0.as_deref_except().codec_id as _)
+    }
+
+    /// Returns the container specific tag of the codec, such as a fourcc.
+    pub const fn codec_tag(&self) -> u32 {
+        self.0.as_deref_except().codec_tag
+    }
+
+    /// Sets the container specific tag of the codec.
+    ///
+    /// Set it to 0 before copying the parameters into a stream of another container,
+    /// so the muxer picks the tag it uses for the codec.
+    pub const fn set_codec_tag(&mut self, codec_tag: u32) {
+        self.0.as_deref_mut_except().codec_tag = codec_tag;
+    }
+
+    /// Returns the width of the video, 0 for other media types.
+    pub const fn width(&self) -> i32 {
+        self.0.as_deref_except().width
+    }
+
+    /// Returns the height of the video, 0 for other media types.
+    pub const fn height(&self) -> i32 {
+        self.0.as_deref_except().height
+    }
+
+    /// Returns the pixel format of the video, `None` for other media types.
+    pub fn pixel_format(&self) -> Option<AVPixelFormat> {
+        (self.codec_type() == AVMediaType::Video).then(|| AVPixelFormat(self.0.as_deref_except().format))
+    }
+
+    /// Returns the sample aspect ratio of the video.
+    pub fn sample_aspect_ratio(&self) -> Rational {
+        self.0.as_deref_except().sample_aspect_ratio.into()
+    }
+
+    /// Returns the frame rate of the video, as stored by the container.
+    pub fn frame_rate(&self) -> Rational {
+        self.0.as_deref_except().framerate.into()
+    }
+
+    /// Returns the sample format of the audio, `None` for other media types.
+    pub fn sample_format(&self) -> Option<AVSampleFormat> {
+        (self.codec_type() == AVMediaType::Audio).then(|| AVSampleFormat(self.0.as_deref_except().format))
+    }
+
+    /// Returns the sample rate of the audio, 0 for other media types.
+    pub const fn sample_rate(&self) -> i32 {
+        self.0.as_deref_except().sample_rate
+    }
+
+    /// Returns the number of audio channels, 0 for other media types.
+    pub const fn channels(&self) -> i32 {
+        self.0.as_deref_except().ch_layout.nb_channels
+    }
+
+    /// Returns the number of samples per audio frame, 0 if unknown or variable.
+    pub const fn frame_size(&self) -> i32 {
+        self.0.as_deref_except().frame_size
+    }
+
+    /// Returns the average bit rate, 0 if unknown.
+    pub const fn bit_rate(&self) -> i64 {
+        self.0.as_deref_except().bit_rate
+    }
+
+    /// Returns the codec specific profile.
+    pub const fn profile(&self) -> i32 {
+        self.0.as_deref_except().profile
+    }
+
+    /// Returns the codec specific level.
+    pub const fn level(&self) -> i32 {
+        self.0.as_deref_except().level
+    }
+
+    /// Returns the extradata, such as the decoder configuration record of H.264
+    /// or the AudioSpecificConfig of AAC. Empty if the codec has none.
+    pub const fn extradata(&self) -> &[u8] {
+        let params = self.0.as_deref_except();
+        if params.extradata.is_null() || params.extradata_size <= 0 {
+            return &[];
+        }
+
+        // Safety: FFmpeg keeps `extradata_size` bytes at `extradata`, the pointer was checked to be non-null.
+        unsafe { std::slice::from_raw_parts(params.extradata, params.extradata_size as usize) }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::codec_parameters::CodecParameters;
+    use crate::decoder::Decoder;
+    use crate::ffi::*;
+    use crate::io::{Input, Output, OutputOptions};
+    use crate::smart_object::SmartPtr;
+    use crate::{AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
+
+    const PATH: &str = "../../assets/avc_aac_large.mp4";
+
+    #[test]
+    fn test_codec_parameters_from_stream() {
+        let input = Input::open(PATH).expect("Failed to open input");
+        let streams = input.streams();
+
+        let video = streams.best(AVMediaType::Video).expect("No video stream");
+        let params = CodecParameters::from_stream(&video).expect("Failed to copy codec parameters");
+        assert_eq!(params.codec_type(), AVMediaType::Video);
+        assert_eq!(params.codec_id(), AVCodecID::H264);
+        assert!(params.width() > 0 && params.height() > 0);
+        assert_eq!(params.pixel_format(), Some(AVPixelFormat::Yuv420p));
+        assert_eq!(params.sample_format(), None);
+        // The decoder configuration record, version 1.
+        assert_eq!(params.extradata().first(), Some(&1));
+
+        let audio = streams.best(AVMediaType::Audio).expect("No audio stream");
+        let params = CodecParameters::from_stream(&audio).expect("Failed to copy codec parameters");
+        assert_eq!(params.codec_type(), AVMediaType::Audio);
+        assert_eq!(params.codec_id(), AVCodecID::Aac);
+        assert!(params.sample_rate() > 0);
+        assert!(params.channels() > 0);
+        assert_eq!(params.sample_format(), Some(AVSampleFormat::Fltp));
+        assert_eq!(params.pixel_format(), None);
+        assert!(!params.extradata().is_empty());
+
+        // Clones are deep copies.
+        let clone = params.clone();
+        assert_ne!(clone.as_ptr(), params.as_ptr());
+        assert_eq!(clone.extradata(), params.extradata());
+    }
+
+    #[test]
+    fn test_codec_parameters_from_decoder() {
+        let mut input = Input::open(PATH).expect("Failed to open input");
+        let mut streams = input.streams_mut();
+        let stream = streams.best_mut(AVMediaType::Video).expect("No video stream");
+
+        let decoder = Decoder::new(&stream).expect("Failed to create decoder");
+        let Decoder::Video(decoder) = decoder else {
+            panic!("expected a video decoder");
+        };
+
+        let params = CodecParameters::from_decoder(&decoder).expect("Failed to get codec parameters");
+        assert_eq!(params.codec_id(), AVCodecID::H264);
+        assert_eq!(params.width(), decoder.width());
+        assert_eq!(params.height(), decoder.height());
+    }
+
+    #[test]
+    fn test_codec_parameters_copy() {
+        let input = Input::open(PATH).expect("Failed to open input");
+        let streams = input.streams();
+        let video = streams.best(AVMediaType::Video).expect("No video stream");
+        let mut params = CodecParameters::from_stream(&video).expect("Failed to copy codec parameters");
+        params.set_codec_tag(0);
+
+        let options = OutputOptions::builder().format_name("mpegts").unwrap().build();
+        let mut output = Output::new(std::io::Cursor::new(Vec::new()), options).expect("Failed to create output");
+        let mut stream = output.add_stream(None).expect("Failed to add stream");
+        params.copy_to_stream(&mut stream).expect("Failed to copy to stream");
+
+        let copied = CodecParameters::from_stream(&stream).expect("Failed to copy codec parameters");
+        assert_eq!(copied.codec_id(), AVCodecID::H264);
+        assert_eq!(copied.width(), params.width());
+        assert_eq!(copied.extradata(), params.extradata());
+
+        // Safety: `avcodec_alloc_context3` is safe to call with a null codec.
+        let context = unsafe { avcodec_alloc_context3(std::ptr::null()) };
+        let destructor = |ptr: &mut *mut AVCodecContext| {
+            // Safety: The pointer here is valid.
+            unsafe { avcodec_free_context(ptr) };
+        };
+        // Safety: The pointer is valid and the destructor frees it.
+        let mut context = unsafe { SmartPtr::wrap_non_null(context, destructor) }.expect("Failed to allocate context");
+
+        params
+            .copy_to_encoder_context(context.as_deref_mut_except())
+            .expect("Failed to copy to context");
+        assert_eq!(context.as_deref_except().width, params.width());
+        assert_eq!(context.as_deref_except().height, params.height());
+        assert_eq!(AVPixelFormat(context.as_deref_except().pix_fmt), AVPixelFormat::Yuv420p);
+    }
+}
@@ -2,10 +2,13 @@ use crate::codec::DecoderCodec;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::frame::{AudioFrame, GenericFrame, VideoFrame};
+use crate::frame_pool::FramePool;
+use crate::hwdevice::HwDeviceContext;
 use crate::packet::Packet;
 use crate::rational::Rational;
 use crate::smart_object::SmartPtr;
 use crate::stream::Stream;
+use crate::threading::{ThreadType, Threading};
 use crate::{AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
 
 /// Either a [`VideoDecoder`] or an [`AudioDecoder`].
@@ -70,8 +73,28 @@ impl std::fmt::Debug for AudioDecoder {
 pub struct DecoderOptions {
     /// The codec to use for decoding.
     pub codec: Option<DecoderCodec>,
-    /// The number of threads to use for decoding.
+    /// The number of threads to use for decoding, 0 lets FFmpeg pick one based on the number of cores.
     pub thread_count: i32,
+    /// The kinds of threading the decoder may use.
+    ///
+    /// If `None` the default of the decoder is kept, which allows both frame and slice threading.
+    pub thread_type: Option<ThreadType>,
+    /// The threading mode, overrides `thread_count` if set to [`Threading::SingleThreaded`].
+    ///
+    /// If `None` the global default set by [`Threading::set_default`] is used.
+    pub threading: Option<Threading>,
+    /// Whether the decoder crops frames to the cropping in the bitstream, `true` by default.
+    ///
+    /// When disabled, the crop is left on the frames, see [`VideoFrame::crop`], and can be applied
+    /// later with [`VideoFrame::apply_cropping`] or combined with a crop of its own, such as
+    /// one that removes letterboxing.
+    pub apply_cropping: bool,
+    /// The hardware device to decode on.
+    ///
+    /// The decoder then outputs frames in hardware memory, in the pixel format
+    /// returned by [`DecoderCodec::hw_pixel_format`]. Download them with
+    /// [`VideoFrame::transfer_to_software`]. Ignored by audio decoders.
+    pub hw_device: Option<HwDeviceContext>,
 }
 
 /// The default options for a [`Decoder`].
@@ -80,6 +103,10 @@ impl Default for DecoderOptions {
         Self {
             codec: None,
             thread_count: 1,
+            thread_type: None,
+            threading: None,
+            apply_cropping: true,
+            hw_device: None,
         }
     }
 }
@@ -96,6 +123,26 @@ impl Decoder {
             return Err(FfmpegError::NoDecoder);
         };
 
+        Self::open(codec_params, ist.time_base(), options, || {
+            // Safety: Even though we are upcasting `AVFormatContext` from a const pointer to a
+            // mutable pointer, it is still safe becasuse av_guess_frame_rate does not use
+            // the pointer to modify the `AVFormatContext`. https://github.com/FFmpeg/FFmpeg/blame/268d0b6527cba1ebac1f44347578617341f85c35/libavformat/avformat.c#L763
+            // The function does not use the pointer at all, it only uses the `AVStream`
+            // pointer to get the `AVRational`
+            let format_context = unsafe { ist.format_context() };
+
+            // Safety: See above.
+            unsafe { av_guess_frame_rate(format_context, ist.as_ptr() as *mut AVStream, std::ptr::null_mut()) }
+        })
+    }
+
+    /// Creates a new [`Decoder`] from codec parameters, `frame_rate` is only called for video decoders.
+    pub(crate) fn open(
+        codec_params: &AVCodecParameters,
+        time_base: Rational,
+        options: DecoderOptions,
+        frame_rate: impl FnOnce() -> AVRational,
+    ) -> Result<Self, FfmpegError> {
         let codec = options
             .codec
             .or_else(|| DecoderCodec::new(AVCodecID(codec_params.codec_id as _)))
@@ -121,21 +168,21 @@ impl Decoder {
 
         let decoder_mut = decoder.as_deref_mut_except();
 
-        decoder_mut.pkt_timebase = ist.time_base().into();
-        decoder_mut.time_base = ist.time_base().into();
+        decoder_mut.pkt_timebase = time_base.into();
+        decoder_mut.time_base = time_base.into();
         decoder_mut.thread_count = options.thread_count;
+        decoder_mut.thread_type = options
+            .thread_type
+            .map_or(decoder_mut.thread_type, |thread_type| thread_type.0);
+        Threading::resolve(options.threading).apply(decoder_mut);
 
         if AVMediaType(decoder_mut.codec_type) == AVMediaType::Video {
-            // Safety: Even though we are upcasting `AVFormatContext` from a const pointer to a
-            // mutable pointer, it is still safe becasuse av_guess_frame_rate does not use
-            // the pointer to modify the `AVFormatContext`. https://github.com/FFmpeg/FFmpeg/blame/268d0b6527cba1ebac1f44347578617341f85c35/libavformat/avformat.c#L763
-            // The function does not use the pointer at all, it only uses the `AVStream`
-            // pointer to get the `AVRational`
-            let format_context = unsafe { ist.format_context() };
+            decoder_mut.framerate = frame_rate();
+            decoder_mut.apply_cropping = options.apply_cropping.into();
 
-            decoder_mut.framerate =
-                // Safety: See above.
-                unsafe { av_guess_frame_rate(format_context, ist.as_ptr() as *mut AVStream, std::ptr::null_mut()) };
+            if let Some(hw_device) = &options.hw_device {
+                decoder_mut.hw_device_ctx = hw_device.new_ref()?;
+            }
         }
 
         if matches!(AVMediaType(decoder_mut.codec_type), AVMediaType::Video | AVMediaType::Audio) {
@@ -178,34 +225,55 @@ impl GenericDecoder {
         self.decoder.as_deref_except().time_base
     }
 
+    /// Returns the number of threads the decoder uses.
+    ///
+    /// Once opened, a `thread_count` of 0 is replaced with the number of threads FFmpeg picked.
+    pub const fn thread_count(&self) -> i32 {
+        self.decoder.as_deref_except().thread_count
+    }
+
+    /// Returns the kind of threading the decoder uses, [`ThreadType::NONE`] if it runs on a single thread.
+    pub const fn active_thread_type(&self) -> ThreadType {
+        ThreadType(self.decoder.as_deref_except().active_thread_type)
+    }
+
+    /// Returns a pointer to the codec context of the decoder.
+    pub(crate) const fn as_ptr(&self) -> *const AVCodecContext {
+        self.decoder.as_ptr()
+    }
+
     /// Sends a packet to the decoder.
     pub fn send_packet(&mut self, packet: &Packet) -> Result<(), FfmpegError> {
         // Safety: `packet` is a valid pointer, and `self.decoder` is a valid pointer.
-        FfmpegErrorCode(unsafe { avcodec_send_packet(self.decoder.as_mut_ptr(), packet.as_ptr()) }).result()?;
+        FfmpegErrorCode(unsafe { avcodec_send_packet(self.decoder.as_mut_ptr(), packet.as_ptr()) }).classify()?;
         Ok(())
     }
 
     /// Sends an end-of-file packet to the decoder.
     pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
         // Safety: `self.decoder` is a valid pointer.
-        FfmpegErrorCode(unsafe { avcodec_send_packet(self.decoder.as_mut_ptr(), std::ptr::null()) }).result()?;
+        FfmpegErrorCode(unsafe { avcodec_send_packet(self.decoder.as_mut_ptr(), std::ptr::null()) }).classify()?;
         Ok(())
     }
 
     /// Receives a frame from the decoder.
     pub fn receive_frame(&mut self) -> Result<Option<GenericFrame>, FfmpegError> {
         let mut frame = GenericFrame::new()?;
+        Ok(self.receive_into(&mut frame)?.then_some(frame))
+    }
 
+    /// Receives a frame from the decoder into `frame`, returning false if there is none.
+    fn receive_into(&mut self, frame: &mut GenericFrame) -> Result<bool, FfmpegError> {
         // Safety: `frame` is a valid pointer, and `self.decoder` is a valid pointer.
         let ret = FfmpegErrorCode(unsafe { avcodec_receive_frame(self.decoder.as_mut_ptr(), frame.as_mut_ptr()) });
 
         match ret {
-            FfmpegErrorCode::Eagain | FfmpegErrorCode::Eof => Ok(None),
+            FfmpegErrorCode::Eagain | FfmpegErrorCode::Eof => Ok(false),
             code if code.is_success() => {
                 frame.set_time_base(self.decoder.as_deref_except().time_base);
-                Ok(Some(frame))
+                Ok(true)
             }
-            code => Err(FfmpegError::Code(code)),
+            code => Err(FfmpegError::from_code(code)),
         }
     }
 }
@@ -240,6 +308,24 @@ impl VideoDecoder {
     pub fn receive_frame(&mut self) -> Result<Option<VideoFrame>, FfmpegError> {
         Ok(self.0.receive_frame()?.map(|frame| frame.video()))
     }
+
+    /// Receives a frame from the decoder, reusing a frame recycled to `pool`.
+    ///
+    /// The data of decoded frames already comes from the buffer pool of the decoder, so
+    /// only the frame itself is taken from `pool`, whatever its size and pixel format.
+    /// Give frames back with [`FramePool::recycle`] once done with them, so the next
+    /// frames do not need to be allocated.
+    pub fn receive_frame_into(&mut self, pool: &FramePool) -> Result<Option<VideoFrame>, FfmpegError> {
+        let mut frame = pool.take_frame()?;
+
+        match self.0.receive_into(&mut frame) {
+            Ok(true) => Ok(Some(frame.video())),
+            result => {
+                pool.recycle(frame);
+                result.map(|_| None)
+            }
+        }
+    }
 }
 
 impl std::ops::Deref for VideoDecoder {
@@ -296,8 +382,10 @@ impl std::ops::DerefMut for AudioDecoder {
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
     use crate::codec::DecoderCodec;
-    use crate::decoder::{Decoder, DecoderOptions};
+    use crate::decoder::{Decoder, DecoderOptions, VideoDecoder};
+    use crate::frame_pool::FramePool;
     use crate::io::Input;
+    use crate::threading::{ThreadType, Threading};
     use crate::{AVCodecID, AVMediaType};
 
     #[test]
@@ -322,6 +410,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::H264).expect("Failed to find H264 codec")),
             thread_count: 2,
+            ..Default::default()
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let generic_decoder = match decoder {
@@ -363,6 +452,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::H264).expect("Failed to find H264 codec")),
             thread_count: 2,
+            ..Default::default()
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
 
@@ -414,6 +504,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::Aac).expect("Failed to find AAC codec")),
             thread_count: 2,
+            ..Default::default()
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let audio_decoder = match decoder {
@@ -440,6 +531,45 @@ mod tests {
 
         assert!(default_options.codec.is_none(), "Expected default codec to be None");
         assert_eq!(default_options.thread_count, 1, "Expected default thread_count to be 1");
+        assert!(
+            default_options.thread_type.is_none(),
+            "Expected default thread_type to be None"
+        );
+        assert!(default_options.apply_cropping, "Expected cropping to be applied by default");
+    }
+
+    #[test]
+    fn test_decoder_thread_settings() {
+        let input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+        let streams = input.streams();
+        let stream = streams.best(AVMediaType::Video).expect("No video stream found");
+
+        let decoder_options = DecoderOptions {
+            thread_count: 4,
+            thread_type: Some(ThreadType::Slice),
+            threading: Some(Threading::Auto),
+            ..Default::default()
+        };
+        let decoder = Decoder::with_options(&stream, decoder_options)
+            .expect("Failed to create Decoder")
+            .video()
+            .expect("Expected a video decoder");
+
+        assert_eq!(decoder.thread_count(), 4);
+        assert_eq!(decoder.active_thread_type(), ThreadType::Slice);
+
+        let decoder_options = DecoderOptions {
+            thread_count: 4,
+            threading: Some(Threading::SingleThreaded),
+            ..Default::default()
+        };
+        let decoder = Decoder::with_options(&stream, decoder_options)
+            .expect("Failed to create Decoder")
+            .video()
+            .expect("Expected a video decoder");
+
+        assert_eq!(decoder.thread_count(), 1);
+        assert_eq!(decoder.active_thread_type(), ThreadType::NONE);
     }
 
     #[test]
@@ -537,6 +667,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: None,
             thread_count: 2,
+            ..Default::default()
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let mut video_decoder = match decoder {
@@ -573,6 +704,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: None,
             thread_count: 2,
+            ..Default::default()
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let mut audio_decoder = match decoder {
@@ -641,4 +773,54 @@ mod tests {
         insta::assert_debug_snapshot!("test_decoder_video", video_frames);
         insta::assert_debug_snapshot!("test_decoder_audio", audio_frames);
     }
+
+    #[test]
+    fn test_decoder_receive_frame_into() {
+        let mut input = Input::open("../../assets/avc_aac.mp4").expect("Failed to open file");
+        let streams = input.streams();
+        let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+        let video_stream_index = video_stream.index();
+        let mut decoder = Decoder::new(&video_stream)
+            .expect("Failed to create decoder")
+            .video()
+            .expect("Failed to get video decoder");
+
+        let pool = FramePool::builder()
+            .width(decoder.width())
+            .height(decoder.height())
+            .pix_fmt(decoder.pixel_format())
+            .build()
+            .expect("Failed to create pool");
+
+        let mut frames = 0;
+        let mut reused = 0;
+        let mut previous = None;
+        let mut receive = |decoder: &mut VideoDecoder| {
+            while let Some(frame) = decoder.receive_frame_into(&pool).expect("Failed to receive frame") {
+                assert_eq!(frame.width() as i32, decoder.width());
+                if previous == Some(frame.as_ptr()) {
+                    reused += 1;
+                }
+
+                previous = Some(frame.as_ptr());
+                frames += 1;
+                pool.recycle(frame);
+            }
+        };
+
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() == video_stream_index {
+                decoder.send_packet(&packet).expect("Failed to send packet");
+                receive(&mut decoder);
+            }
+        }
+
+        decoder.send_eof().expect("Failed to send eof");
+        receive(&mut decoder);
+
+        // Recycled frames are reused for the next frame.
+        assert!(frames > 1);
+        assert_eq!(reused, frames - 1);
+        assert_eq!(pool.spare_frames(), 1);
+    }
 }
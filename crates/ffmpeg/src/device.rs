@@ -0,0 +1,216 @@
+use std::ffi::CStr;
+use std::sync::Once;
+
+use crate::AVMediaType;
+use crate::dict::Dictionary;
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+
+/// Registers the devices of libavdevice with libavformat, once.
+fn register() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| {
+        // Safety: `avdevice_register_all` is safe to call, `Once` makes sure it only runs once.
+        unsafe { avdevice_register_all() };
+    });
+}
+
+/// A wrapper around an [`AVInputFormat`] pointer of a capture device, such as `v4l2` or `alsa`.
+///
+/// Use [`InputDevice::video`] and [`InputDevice::audio`] to list the devices FFmpeg was built with,
+/// or [`InputDevice::by_name`] to find one. Open a source of the device with
+/// [`Input::open_device`](crate::io::Input::open_device).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct InputDevice(*const AVInputFormat);
+
+/// Safety: The format points to static data of FFmpeg, which can be shared between threads.
+unsafe impl Send for InputDevice {}
+
+/// Safety: See the `Send` impl.
+unsafe impl Sync for InputDevice {}
+
+impl std::fmt::Debug for InputDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputDevice")
+            .field("name", &self.name())
+            .field("long_name", &self.long_name())
+            .finish()
+    }
+}
+
+impl InputDevice {
+    /// Returns the video capture devices, such as `v4l2`, `dshow` or `avfoundation`.
+    pub fn video() -> impl Iterator<Item = Self> {
+        register();
+
+        let mut current = std::ptr::null();
+        std::iter::from_fn(move || {
+            // Safety: `av_input_video_device_next` is safe to call with null or a previously returned format.
+            current = unsafe { av_input_video_device_next(current) };
+            (!current.is_null()).then_some(Self(current))
+        })
+    }
+
+    /// Returns the audio capture devices, such as `alsa`, `pulse` or `dshow`.
+    pub fn audio() -> impl Iterator<Item = Self> {
+        register();
+
+        let mut current = std::ptr::null();
+        std::iter::from_fn(move || {
+            // Safety: `av_input_audio_device_next` is safe to call with null or a previously returned format.
+            current = unsafe { av_input_audio_device_next(current) };
+            (!current.is_null()).then_some(Self(current))
+        })
+    }
+
+    /// Finds a capture device by name, returns `None` if FFmpeg was not built with it.
+    ///
+    /// Devices that capture both video and audio, such as `dshow`, are found as well.
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::video().chain(Self::audio()).find(|device| device.name() == name)
+    }
+
+    /// Returns the short name of the device, such as `v4l2`.
+    pub fn name(&self) -> &'static str {
+        // Safety: The pointer points to a valid format with a static name.
+        let name = unsafe { (*self.0).name };
+        // Safety: The name is a valid c-string.
+        unsafe { CStr::from_ptr(name) }.to_str().unwrap_or_default()
+    }
+
+    /// Returns the descriptive name of the device, such as `Video4Linux2 device grab`.
+    pub fn long_name(&self) -> &'static str {
+        // Safety: The pointer points to a valid format.
+        let long_name = unsafe { (*self.0).long_name };
+        if long_name.is_null() {
+            return "";
+        }
+
+        // Safety: The long name is a valid c-string.
+        unsafe { CStr::from_ptr(long_name) }.to_str().unwrap_or_default()
+    }
+
+    /// Lists the sources of the device, such as the cameras of `v4l2`.
+    ///
+    /// `options` are the private options of the device. Not all devices can list their sources,
+    /// those return an error.
+    pub fn sources(&self, options: &Dictionary) -> Result<Vec<DeviceSource>, FfmpegError> {
+        let mut list: *mut AVDeviceInfoList = std::ptr::null_mut();
+
+        // Safety: `avdevice_list_input_sources` is safe to call with a valid format, and only copies the options.
+        let ret = unsafe { avdevice_list_input_sources(self.0, std::ptr::null(), options.as_ptr() as *mut _, &mut list) };
+
+        // Freed on every path, the list may be allocated even when listing fails.
+        let list = DeviceInfoListGuard(list);
+        FfmpegErrorCode(ret).result()?;
+
+        // Safety: The list is either null or valid until the guard is dropped.
+        let Some(list) = (unsafe { list.0.as_ref() }) else {
+            return Ok(Vec::new());
+        };
+
+        let devices = if list.devices.is_null() || list.nb_devices <= 0 {
+            &[][..]
+        } else {
+            // Safety: `devices` points to `nb_devices` device pointers.
+            unsafe { std::slice::from_raw_parts(list.devices, list.nb_devices as usize) }
+        };
+
+        Ok(devices
+            .iter()
+            .enumerate()
+            // Safety: Each device pointer is either null or valid until the guard is dropped.
+            .filter_map(|(index, device)| unsafe { device.as_ref() }.map(|device| (index, device)))
+            .map(|(index, device)| DeviceSource::new(device, index as i32 == list.default_device))
+            .collect())
+    }
+
+    /// Returns the raw pointer to the [`AVInputFormat`].
+    pub const fn as_ptr(&self) -> *const AVInputFormat {
+        self.0
+    }
+}
+
+/// Frees an [`AVDeviceInfoList`] when dropped.
+struct DeviceInfoListGuard(*mut AVDeviceInfoList);
+
+impl Drop for DeviceInfoListGuard {
+    fn drop(&mut self) {
+        // Safety: `avdevice_free_list_devices` is safe to call with a null or valid list.
+        unsafe { avdevice_free_list_devices(&mut self.0) };
+    }
+}
+
+/// A source of an [`InputDevice`], such as a camera or a microphone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceSource {
+    /// The name to open the source with, such as `/dev/video0` or `hw:0`.
+    pub name: String,
+    /// A human readable description of the source.
+    pub description: String,
+    /// The kinds of media the source captures, empty if the device does not say.
+    pub media_types: Vec<AVMediaType>,
+    /// True if this is the source the device uses by default.
+    pub is_default: bool,
+}
+
+impl DeviceSource {
+    fn new(device: &AVDeviceInfo, is_default: bool) -> Self {
+        let string = |ptr: *mut std::ffi::c_char| {
+            if ptr.is_null() {
+                String::new()
+            } else {
+                // Safety: The pointer is a valid c-string owned by the device list.
+                unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+            }
+        };
+
+        let media_types = if device.media_types.is_null() || device.nb_media_types <= 0 {
+            &[][..]
+        } else {
+            // Safety: `media_types` points to `nb_media_types` media types.
+            unsafe { std::slice::from_raw_parts(device.media_types, device.nb_media_types as usize) }
+        };
+
+        Self {
+            name: string(device.device_name),
+            description: string(device.device_description),
+            media_types: media_types.iter().map(|media_type| AVMediaType(*media_type)).collect(),
+            is_default,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::device::InputDevice;
+
+    #[test]
+    fn test_input_device_lookup() {
+        for device in InputDevice::video().chain(InputDevice::audio()) {
+            assert!(!device.name().is_empty());
+            assert_eq!(InputDevice::by_name(device.name()), Some(device));
+        }
+
+        assert_eq!(InputDevice::by_name("not-a-device"), None);
+    }
+
+    #[test]
+    fn test_input_device_lavfi() {
+        // `lavfi` is a virtual device, present in most builds, that captures from a filter graph.
+        let Some(device) = InputDevice::by_name("lavfi") else {
+            return;
+        };
+
+        let mut input =
+            crate::io::Input::open_device(device, "testsrc=size=64x48:rate=10", &mut crate::io::InputOptions::default())
+                .expect("Failed to open device");
+
+        let stream = input.streams().best(crate::AVMediaType::Video).expect("No video stream");
+        let parameters = stream.codec_parameters().expect("Missing codec parameters");
+        assert_eq!((parameters.width, parameters.height), (64, 48));
+        assert!(input.receive_packet().expect("Failed to read packet").is_some());
+    }
+}
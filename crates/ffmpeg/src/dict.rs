@@ -125,6 +125,62 @@ impl<'a> CStringLike<'a> for CString {
     }
 }
 
+/// Well known metadata keys of containers and streams.
+///
+/// These can be used anywhere a key is expected, for example with
+/// [`Dictionary::set`] and [`Dictionary::get_str`].
+///
+/// ```rust
+/// use scuffle_ffmpeg::dict::{Dictionary, MetadataKey};
+///
+/// let mut metadata = Dictionary::new();
+/// metadata.set(MetadataKey::Title, "My Stream").expect("Failed to set title");
+/// metadata.set(MetadataKey::Language, "eng").expect("Failed to set language");
+///
+/// assert_eq!(metadata.get_str(MetadataKey::Title), Some("My Stream"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataKey {
+    /// The title of the file or stream, `title`.
+    Title,
+    /// The language of the stream as an ISO 639-2 code, `language`.
+    Language,
+    /// The application or library that wrote the file, `encoder`.
+    Encoder,
+    /// A free form comment, `comment`.
+    Comment,
+    /// The author of the work, `artist`.
+    Artist,
+    /// The copyright notice, `copyright`.
+    Copyright,
+    /// The date the file was created, in ISO 8601 format, `creation_time`.
+    CreationTime,
+    /// The name of the handler of an mp4 track, `handler_name`.
+    HandlerName,
+}
+
+impl MetadataKey {
+    /// Returns the key as it is stored in the dictionary.
+    pub const fn as_c_str(&self) -> &'static CStr {
+        match self {
+            Self::Title => c"title",
+            Self::Language => c"language",
+            Self::Encoder => c"encoder",
+            Self::Comment => c"comment",
+            Self::Artist => c"artist",
+            Self::Copyright => c"copyright",
+            Self::CreationTime => c"creation_time",
+            Self::HandlerName => c"handler_name",
+        }
+    }
+}
+
+impl<'a> CStringLike<'a> for MetadataKey {
+    fn into_c_str(self) -> Option<Cow<'a, CStr>> {
+        Some(Cow::Borrowed(self.as_c_str()))
+    }
+}
+
 impl Dictionary {
     /// Creates a new dictionary.
     pub const fn new() -> Self {
@@ -203,11 +259,45 @@ impl Dictionary {
         Some(unsafe { CStr::from_ptr(mut_ref.value as *const _) })
     }
 
+    /// Returns the value associated with the given key, if it is valid UTF-8.
+    pub fn get_str<'a>(&self, key: impl CStringLike<'a>) -> Option<&str> {
+        self.get(key)?.to_str().ok()
+    }
+
+    /// Removes the given key from the dictionary.
+    /// Returns true if the key was present.
+    pub fn remove<'a>(&mut self, key: impl CStringLike<'a>) -> Result<bool, FfmpegError> {
+        let key = key.into_c_str().ok_or(FfmpegError::Arguments("key cannot be empty"))?;
+
+        // Safety: av_dict_get is safe to call
+        let found = !unsafe { av_dict_get(self.as_ptr(), key.as_ptr() as *const _, std::ptr::null_mut(), 0) }.is_null();
+        if !found {
+            return Ok(false);
+        }
+
+        // Safety: av_dict_set is safe to call, a null value removes the key.
+        FfmpegErrorCode(unsafe { av_dict_set(self.ptr.as_mut(), key.as_ptr() as *const _, std::ptr::null(), 0) })
+            .result()?;
+        Ok(true)
+    }
+
+    /// Returns the number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        // Safety: av_dict_count is safe to call with any dictionary, including null.
+        unsafe { av_dict_count(self.as_ptr()) as usize }
+    }
+
     /// Returns true if the dictionary is empty.
     pub fn is_empty(&self) -> bool {
         self.iter().next().is_none()
     }
 
+    /// Returns an iterator over the entries whose key and value are valid UTF-8.
+    pub fn iter_str(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.iter()
+            .filter_map(|(key, value)| Some((key.to_str().ok()?, value.to_str().ok()?)))
+    }
+
     /// Returns an iterator over the dictionary.
     pub const fn iter(&self) -> DictionaryIterator {
         DictionaryIterator::new(self)
@@ -315,12 +405,35 @@ mod tests {
     use std::collections::HashMap;
     use std::ffi::CStr;
 
-    use crate::dict::Dictionary;
+    use crate::dict::{Dictionary, MetadataKey};
 
     fn sort_hashmap<K: Ord, V>(map: std::collections::HashMap<K, V>) -> std::collections::BTreeMap<K, V> {
         map.into_iter().collect()
     }
 
+    #[test]
+    fn test_dict_metadata_keys() {
+        let mut dict = Dictionary::new();
+        assert_eq!(dict.len(), 0);
+
+        dict.set(MetadataKey::Title, "My Stream").expect("Failed to set title");
+        dict.set(MetadataKey::Language, "eng").expect("Failed to set language");
+        dict.set(MetadataKey::Encoder, "scuffle").expect("Failed to set encoder");
+
+        assert_eq!(dict.len(), 3);
+        assert_eq!(dict.get_str(MetadataKey::Title), Some("My Stream"));
+        assert_eq!(dict.get(c"language"), Some(c"eng"));
+        assert_eq!(dict.get_str(MetadataKey::Comment), None);
+
+        assert_eq!(dict.remove(MetadataKey::Encoder), Ok(true));
+        assert_eq!(dict.remove(MetadataKey::Encoder), Ok(false));
+        assert_eq!(dict.get_str(MetadataKey::Encoder), None);
+
+        let mut entries: Vec<_> = dict.iter_str().collect();
+        entries.sort();
+        assert_eq!(entries, vec![("language", "eng"), ("title", "My Stream")]);
+    }
+
     #[test]
     fn test_dict_default_and_items() {
         let mut dict = Dictionary::default();
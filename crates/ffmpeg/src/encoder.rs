@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::ffi::CStr;
 use std::ptr::NonNull;
 
 use crate::codec::EncoderCodec;
@@ -5,10 +7,12 @@ use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::frame::{AudioChannelLayout, GenericFrame};
+use crate::hwdevice::{HwDeviceContext, HwFramesContext};
 use crate::io::Output;
 use crate::packet::Packet;
 use crate::rational::Rational;
 use crate::smart_object::SmartPtr;
+use crate::threading::{ThreadType, Threading};
 use crate::{AVFormatFlags, AVPixelFormat, AVSampleFormat};
 
 /// Represents an encoder.
@@ -23,6 +27,91 @@ pub struct Encoder {
 /// Safety: `Encoder` can be sent between threads.
 unsafe impl Send for Encoder {}
 
+/// The speed preset of x264, x265 and encoders using the same names, such as `libvvenc`.
+/// Slower presets compress better at the same quality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncoderPreset {
+    /// `ultrafast`
+    Ultrafast,
+    /// `superfast`
+    Superfast,
+    /// `veryfast`
+    Veryfast,
+    /// `faster`
+    Faster,
+    /// `fast`
+    Fast,
+    /// `medium`, the default of x264 and x265.
+    Medium,
+    /// `slow`
+    Slow,
+    /// `slower`
+    Slower,
+    /// `veryslow`
+    Veryslow,
+    /// `placebo`
+    Placebo,
+}
+
+impl EncoderPreset {
+    /// Returns the name of the preset, as passed to the encoder.
+    pub const fn name(self) -> &'static CStr {
+        match self {
+            Self::Ultrafast => c"ultrafast",
+            Self::Superfast => c"superfast",
+            Self::Veryfast => c"veryfast",
+            Self::Faster => c"faster",
+            Self::Fast => c"fast",
+            Self::Medium => c"medium",
+            Self::Slow => c"slow",
+            Self::Slower => c"slower",
+            Self::Veryslow => c"veryslow",
+            Self::Placebo => c"placebo",
+        }
+    }
+}
+
+/// The tuning of x264 and x265 for a kind of content or use case.
+///
+/// x265 only supports [`Grain`](Self::Grain), [`FastDecode`](Self::FastDecode),
+/// [`ZeroLatency`](Self::ZeroLatency), [`Animation`](Self::Animation),
+/// [`Psnr`](Self::Psnr) and [`Ssim`](Self::Ssim).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncoderTune {
+    /// `film`, for high quality movie content.
+    Film,
+    /// `animation`, for cartoons.
+    Animation,
+    /// `grain`, preserves film grain.
+    Grain,
+    /// `stillimage`, for slideshow like content.
+    StillImage,
+    /// `psnr`, optimizes for the PSNR metric.
+    Psnr,
+    /// `ssim`, optimizes for the SSIM metric.
+    Ssim,
+    /// `fastdecode`, disables filters that slow down decoding.
+    FastDecode,
+    /// `zerolatency`, disables frame buffering for live streaming.
+    ZeroLatency,
+}
+
+impl EncoderTune {
+    /// Returns the name of the tuning, as passed to the encoder.
+    pub const fn name(self) -> &'static CStr {
+        match self {
+            Self::Film => c"film",
+            Self::Animation => c"animation",
+            Self::Grain => c"grain",
+            Self::StillImage => c"stillimage",
+            Self::Psnr => c"psnr",
+            Self::Ssim => c"ssim",
+            Self::FastDecode => c"fastdecode",
+            Self::ZeroLatency => c"zerolatency",
+        }
+    }
+}
+
 /// Represents the settings for a video encoder.
 #[derive(bon::Builder)]
 pub struct VideoEncoderSettings {
@@ -33,8 +122,14 @@ pub struct VideoEncoderSettings {
     gop_size: Option<i32>,
     qmax: Option<i32>,
     qmin: Option<i32>,
+    /// The number of threads to use, 0 lets FFmpeg pick one based on the number of cores.
     thread_count: Option<i32>,
-    thread_type: Option<i32>,
+    /// The kinds of threading the encoder may use.
+    #[builder(into)]
+    thread_type: Option<ThreadType>,
+    /// Overrides `thread_count` and `thread_type` if set to [`Threading::SingleThreaded`].
+    /// Falls back to the global default set by [`Threading::set_default`].
+    threading: Option<Threading>,
     sample_aspect_ratio: Option<Rational>,
     bitrate: Option<i64>,
     rc_min_rate: Option<i64>,
@@ -42,8 +137,32 @@ pub struct VideoEncoderSettings {
     rc_buffer_size: Option<i32>,
     max_b_frames: Option<i32>,
     codec_specific_options: Option<Dictionary>,
+    /// The speed preset of the encoder, the `preset` option.
+    preset: Option<EncoderPreset>,
+    /// The tuning of the encoder, the `tune` option.
+    tune: Option<EncoderTune>,
+    /// The constant rate factor, the `crf` option. Lower values give a higher quality.
+    crf: Option<f32>,
+    /// The profile, the `profile` option, such as `high` for x264 or `main10` for x265.
+    #[builder(into)]
+    profile: Option<String>,
+    /// The level times ten, such as 41 for level 4.1.
+    level: Option<i32>,
+    /// Private options of the encoder, such as `x264-params`, set before it is opened.
+    ///
+    /// Unlike [`codec_specific_options`](Self::codec_specific_options), which ignores options
+    /// the encoder does not have, an unknown option or invalid value fails [`Encoder::new`]
+    /// with [`FfmpegError::InvalidOption`]. Options set here override the typed ones.
+    codec_opts: Option<Dictionary>,
     flags: Option<i32>,
     flags2: Option<i32>,
+    /// The hardware device to encode on, for encoders such as `h264_nvenc` that
+    /// accept frames in system memory and upload them themselves.
+    hw_device: Option<HwDeviceContext>,
+    /// The pool the frames sent to the encoder come from, required by encoders such
+    /// as `h264_vaapi` that only accept hardware frames. `pixel_format` must then be
+    /// the [`format`](HwFramesContext::format) of the pool.
+    hw_frames: Option<HwFramesContext>,
 }
 
 impl VideoEncoderSettings {
@@ -64,7 +183,8 @@ impl VideoEncoderSettings {
             .unwrap_or(encoder.sample_aspect_ratio);
         encoder.framerate = self.frame_rate.into();
         encoder.thread_count = self.thread_count.unwrap_or(encoder.thread_count);
-        encoder.thread_type = self.thread_type.unwrap_or(encoder.thread_type);
+        encoder.thread_type = self.thread_type.map_or(encoder.thread_type, |thread_type| thread_type.0);
+        Threading::resolve(self.threading).apply(encoder);
         encoder.gop_size = self.gop_size.unwrap_or(encoder.gop_size);
         encoder.qmax = self.qmax.unwrap_or(encoder.qmax);
         encoder.qmin = self.qmin.unwrap_or(encoder.qmin);
@@ -73,11 +193,47 @@ impl VideoEncoderSettings {
         encoder.rc_max_rate = self.rc_max_rate.unwrap_or(encoder.rc_max_rate);
         encoder.rc_buffer_size = self.rc_buffer_size.unwrap_or(encoder.rc_buffer_size);
         encoder.max_b_frames = self.max_b_frames.unwrap_or(encoder.max_b_frames);
+        encoder.level = self.level.unwrap_or(encoder.level);
         encoder.flags = self.flags.unwrap_or(encoder.flags);
         encoder.flags2 = self.flags2.unwrap_or(encoder.flags2);
 
+        if let Some(hw_device) = &self.hw_device {
+            encoder.hw_device_ctx = hw_device.new_ref()?;
+        }
+
+        if let Some(hw_frames) = &self.hw_frames {
+            encoder.hw_frames_ctx = hw_frames.new_ref()?;
+        }
+
         Ok(())
     }
+
+    /// Returns the private options to set with `av_opt_set`, the typed ones followed by `codec_opts`.
+    fn private_options(&self) -> Result<Option<Dictionary>, FfmpegError> {
+        let mut options = Dictionary::new();
+
+        if let Some(preset) = self.preset {
+            options.set(c"preset", preset.name())?;
+        }
+
+        if let Some(tune) = self.tune {
+            options.set(c"tune", tune.name())?;
+        }
+
+        if let Some(crf) = self.crf {
+            options.set(c"crf", crf.to_string().as_str())?;
+        }
+
+        if let Some(profile) = &self.profile {
+            options.set(c"profile", profile.as_str())?;
+        }
+
+        if let Some(codec_opts) = &self.codec_opts {
+            options.extend(codec_opts)?;
+        }
+
+        Ok((!options.is_empty()).then_some(options))
+    }
 }
 
 /// Represents the settings for an audio encoder.
@@ -86,8 +242,14 @@ pub struct AudioEncoderSettings {
     sample_rate: i32,
     ch_layout: AudioChannelLayout,
     sample_fmt: AVSampleFormat,
+    /// The number of threads to use, 0 lets FFmpeg pick one based on the number of cores.
     thread_count: Option<i32>,
-    thread_type: Option<i32>,
+    /// The kinds of threading the encoder may use.
+    #[builder(into)]
+    thread_type: Option<ThreadType>,
+    /// Overrides `thread_count` and `thread_type` if set to [`Threading::SingleThreaded`].
+    /// Falls back to the global default set by [`Threading::set_default`].
+    threading: Option<Threading>,
     bitrate: Option<i64>,
     rc_min_rate: Option<i64>,
     rc_max_rate: Option<i64>,
@@ -109,7 +271,8 @@ impl AudioEncoderSettings {
         self.ch_layout.apply(&mut encoder.ch_layout);
         encoder.sample_fmt = self.sample_fmt.into();
         encoder.thread_count = self.thread_count.unwrap_or(encoder.thread_count);
-        encoder.thread_type = self.thread_type.unwrap_or(encoder.thread_type);
+        encoder.thread_type = self.thread_type.map_or(encoder.thread_type, |thread_type| thread_type.0);
+        Threading::resolve(self.threading).apply(encoder);
         encoder.bit_rate = self.bitrate.unwrap_or(encoder.bit_rate);
         encoder.rc_min_rate = self.rc_min_rate.unwrap_or(encoder.rc_min_rate);
         encoder.rc_max_rate = self.rc_max_rate.unwrap_or(encoder.rc_max_rate);
@@ -121,6 +284,30 @@ impl AudioEncoderSettings {
     }
 }
 
+/// Sets private options of an encoder that is not opened yet, such as `preset` of x264.
+fn set_private_options(encoder: &mut AVCodecContext, options: &Dictionary) -> Result<(), FfmpegError> {
+    for (key, value) in options {
+        // Safety: The context is valid, `av_opt_set` copies the value.
+        let code = FfmpegErrorCode(unsafe {
+            av_opt_set(
+                (encoder as *mut AVCodecContext).cast(),
+                key.as_ptr(),
+                value.as_ptr(),
+                AV_OPT_SEARCH_CHILDREN as i32,
+            )
+        });
+
+        if !code.is_success() {
+            return Err(FfmpegError::InvalidOption {
+                name: key.to_string_lossy().into_owned(),
+                code,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 /// Represents the settings for an encoder.
 pub enum EncoderSettings {
     /// Video encoder settings.
@@ -137,6 +324,13 @@ impl EncoderSettings {
         }
     }
 
+    fn private_options(&self) -> Result<Option<Dictionary>, FfmpegError> {
+        match self {
+            EncoderSettings::Video(video_settings) => video_settings.private_options(),
+            EncoderSettings::Audio(_) => Ok(None),
+        }
+    }
+
     const fn codec_specific_options(&mut self) -> Option<&mut Dictionary> {
         match self {
             EncoderSettings::Video(video_settings) => video_settings.codec_specific_options.as_mut(),
@@ -203,8 +397,14 @@ impl Encoder {
             .map(|options| options.as_mut_ptr_ref() as *mut *mut _)
             .unwrap_or(std::ptr::null_mut());
 
+        let private_options = settings.private_options()?;
+
         settings.apply(encoder_mut)?;
 
+        if let Some(private_options) = &private_options {
+            set_private_options(encoder_mut, private_options)?;
+        }
+
         if global_header {
             encoder_mut.flags |= AV_CODEC_FLAG_GLOBAL_HEADER as i32;
         }
@@ -234,14 +434,14 @@ impl Encoder {
     /// Sends an EOF frame to the encoder.
     pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
         // Safety: `self.encoder` is a valid pointer.
-        FfmpegErrorCode(unsafe { avcodec_send_frame(self.encoder.as_mut_ptr(), std::ptr::null()) }).result()?;
+        FfmpegErrorCode(unsafe { avcodec_send_frame(self.encoder.as_mut_ptr(), std::ptr::null()) }).classify()?;
         Ok(())
     }
 
     /// Sends a frame to the encoder.
     pub fn send_frame(&mut self, frame: &GenericFrame) -> Result<(), FfmpegError> {
         // Safety: `self.encoder` and `frame` are valid pointers.
-        FfmpegErrorCode(unsafe { avcodec_send_frame(self.encoder.as_mut_ptr(), frame.as_ptr()) }).result()?;
+        FfmpegErrorCode(unsafe { avcodec_send_frame(self.encoder.as_mut_ptr(), frame.as_ptr()) }).classify()?;
         Ok(())
     }
 
@@ -274,7 +474,7 @@ impl Encoder {
                 packet.set_stream_index(self.stream_index);
                 Ok(Some(packet))
             }
-            code => Err(FfmpegError::Code(code)),
+            code => Err(FfmpegError::from_code(code)),
         }
     }
 
@@ -292,11 +492,106 @@ impl Encoder {
     pub const fn outgoing_time_base(&self) -> Rational {
         self.outgoing_time_base
     }
+
+    /// Returns the number of samples per channel an audio frame sent to the encoder must have.
+    ///
+    /// Returns 0 if the encoder accepts any frame size, or for video encoders.
+    pub const fn frame_size(&self) -> i32 {
+        self.encoder.as_deref_except().frame_size
+    }
+
+    /// Returns the number of threads the encoder uses.
+    ///
+    /// Once opened, a `thread_count` of 0 is replaced with the number of threads FFmpeg picked.
+    pub const fn thread_count(&self) -> i32 {
+        self.encoder.as_deref_except().thread_count
+    }
+
+    /// Returns the kind of threading the encoder uses, [`ThreadType::NONE`] if it runs on a single thread.
+    pub const fn active_thread_type(&self) -> ThreadType {
+        ThreadType(self.encoder.as_deref_except().active_thread_type)
+    }
+
+    /// Returns a pointer to the codec context of the encoder.
+    pub(crate) const fn as_ptr(&self) -> *const AVCodecContext {
+        self.encoder.as_ptr()
+    }
+}
+
+/// The number of packets [`drain`] wrote for an encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrainedStream {
+    /// The output stream index of the encoder.
+    pub stream_index: i32,
+    /// The number of packets written to the output.
+    pub packets: usize,
+}
+
+/// Flushes the encoders and finishes the output.
+///
+/// Sends EOF to every encoder, writes their remaining packets to the output
+/// in dts order across all encoders, and then writes the trailer.
+/// Returns the number of packets written per encoder, in the same order as `encoders`.
+pub fn drain<T: Send + Sync>(
+    encoders: &mut [&mut Encoder],
+    output: &mut Output<T>,
+) -> Result<Vec<DrainedStream>, FfmpegError> {
+    for encoder in encoders.iter_mut() {
+        encoder.send_eof()?;
+    }
+
+    let mut pending = encoders
+        .iter_mut()
+        .map(|encoder| encoder.receive_packet())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut drained = encoders
+        .iter()
+        .map(|encoder| DrainedStream {
+            stream_index: encoder.stream_index(),
+            packets: 0,
+        })
+        .collect::<Vec<_>>();
+
+    loop {
+        let next = pending
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, packet)| Some((idx, packet_ts(packet.as_ref()?), encoders[idx].outgoing_time_base())))
+            .min_by(|(_, a, a_time_base), (_, b, b_time_base)| compare_ts(*a, *a_time_base, *b, *b_time_base))
+            .map(|(idx, _, _)| idx);
+
+        let Some(idx) = next else {
+            break;
+        };
+
+        let next_packet = encoders[idx].receive_packet()?;
+        if let Some(packet) = std::mem::replace(&mut pending[idx], next_packet) {
+            output.write_interleaved_packet(packet)?;
+            drained[idx].packets += 1;
+        }
+    }
+
+    output.write_trailer()?;
+
+    Ok(drained)
+}
+
+fn packet_ts(packet: &Packet) -> i64 {
+    packet.dts().or(packet.pts()).unwrap_or(i64::MIN)
+}
+
+/// Compares two timestamps in different time bases without rounding.
+fn compare_ts(a: i64, a_time_base: Rational, b: i64, b_time_base: Rational) -> Ordering {
+    let a = a as i128 * a_time_base.numerator as i128 * b_time_base.denominator.get() as i128;
+    let b = b as i128 * b_time_base.numerator as i128 * a_time_base.denominator.get() as i128;
+    a.cmp(&b)
 }
 
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
+    use std::cmp::Ordering;
     use std::io::Write;
 
     use bytes::{Buf, Bytes};
@@ -306,11 +601,15 @@ mod tests {
     use crate::codec::EncoderCodec;
     use crate::decoder::Decoder;
     use crate::dict::Dictionary;
-    use crate::encoder::{AudioChannelLayout, AudioEncoderSettings, Encoder, EncoderSettings, VideoEncoderSettings};
-    use crate::error::FfmpegError;
+    use crate::encoder::{
+        AudioChannelLayout, AudioEncoderSettings, Encoder, EncoderPreset, EncoderSettings, EncoderTune,
+        VideoEncoderSettings, compare_ts, drain,
+    };
+    use crate::error::{FfmpegError, FfmpegErrorCode};
     use crate::ffi::AVCodecContext;
     use crate::io::{Input, Output, OutputOptions};
     use crate::rational::Rational;
+    use crate::threading::{ThreadType, Threading};
     use crate::{AVChannelOrder, AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
 
     #[test]
@@ -366,7 +665,7 @@ mod tests {
         assert_eq!(settings.qmax, Some(qmax));
         assert_eq!(settings.qmin, Some(qmin));
         assert_eq!(settings.thread_count, Some(thread_count));
-        assert_eq!(settings.thread_type, Some(thread_type));
+        assert_eq!(settings.thread_type, Some(ThreadType(thread_type)));
         assert_eq!(settings.bitrate, Some(bitrate));
         assert_eq!(settings.rc_min_rate, Some(rc_min_rate));
         assert_eq!(settings.rc_max_rate, Some(rc_max_rate));
@@ -403,6 +702,107 @@ mod tests {
         assert_eq!(encoder.flags2, flags2);
     }
 
+    #[test]
+    fn test_video_encoder_private_options() {
+        let mut codec_opts = Dictionary::new();
+        codec_opts.set("crf", "18").unwrap();
+        codec_opts.set("x264-params", "keyint=60").unwrap();
+
+        let settings = VideoEncoderSettings::builder()
+            .width(1920)
+            .height(1080)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .preset(EncoderPreset::Veryfast)
+            .tune(EncoderTune::ZeroLatency)
+            .crf(23.5)
+            .profile("high")
+            .level(41)
+            .codec_opts(codec_opts)
+            .build();
+
+        let options = settings.private_options().unwrap().expect("Missing private options");
+        assert_eq!(options.get_str("preset"), Some("veryfast"));
+        assert_eq!(options.get_str("tune"), Some("zerolatency"));
+        assert_eq!(options.get_str("profile"), Some("high"));
+        assert_eq!(options.get_str("x264-params"), Some("keyint=60"));
+        // `codec_opts` override the typed options.
+        assert_eq!(options.get_str("crf"), Some("18"));
+
+        // Safety: We are zeroing the memory for the encoder context.
+        let mut encoder = unsafe { std::mem::zeroed::<AVCodecContext>() };
+        settings.apply(&mut encoder).expect("Failed to apply settings");
+        assert_eq!(encoder.level, 41);
+
+        let settings = VideoEncoderSettings::builder()
+            .width(1920)
+            .height(1080)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .build();
+        assert!(settings.private_options().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_encoder_new_invalid_option() {
+        let new_encoder = |key: &str, value: &str| {
+            let codec = EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder");
+            let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+            let mut output = Output::new(std::io::Cursor::new(Vec::new()), options).expect("Failed to create Output");
+            let mut codec_opts = Dictionary::new();
+            codec_opts.set(key, value).unwrap();
+            let settings = VideoEncoderSettings::builder()
+                .width(640)
+                .height(480)
+                .frame_rate(30.into())
+                .pixel_format(AVPixelFormat::Yuv420p)
+                .codec_opts(codec_opts)
+                .build();
+
+            Encoder::new(
+                codec,
+                &mut output,
+                AVRational { num: 1, den: 1000 },
+                AVRational { num: 1, den: 1000 },
+                settings,
+            )
+        };
+
+        assert!(new_encoder("mpeg_quant", "1").is_ok());
+        assert_eq!(
+            new_encoder("not_an_option", "1").unwrap_err(),
+            FfmpegError::InvalidOption {
+                name: "not_an_option".into(),
+                code: FfmpegErrorCode::OptionNotFound,
+            }
+        );
+        assert!(matches!(
+            new_encoder("mpeg_quant", "not a number").unwrap_err(),
+            FfmpegError::InvalidOption { name, .. } if name == "mpeg_quant"
+        ));
+    }
+
+    #[test]
+    fn test_video_encoder_apply_single_threaded() {
+        let settings = VideoEncoderSettings::builder()
+            .width(1920)
+            .height(1080)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .thread_count(8)
+            .thread_type(2)
+            .threading(Threading::SingleThreaded)
+            .build();
+
+        // Safety: We are zeroing the memory for the encoder context.
+        let mut encoder = unsafe { std::mem::zeroed::<AVCodecContext>() };
+        let result = settings.apply(&mut encoder);
+        assert!(result.is_ok(), "Failed to apply settings: {:?}", result.err());
+
+        assert_eq!(encoder.thread_count, 1);
+        assert_eq!(encoder.thread_type, 0);
+    }
+
     #[test]
     fn test_video_encoder_settings_apply_error() {
         let settings = VideoEncoderSettings::builder()
@@ -460,7 +860,7 @@ mod tests {
         assert_eq!(settings.ch_layout.channel_count(), 2);
         assert_eq!(settings.sample_fmt, sample_fmt);
         assert_eq!(settings.thread_count, Some(thread_count));
-        assert_eq!(settings.thread_type, Some(thread_type));
+        assert_eq!(settings.thread_type, Some(ThreadType(thread_type)));
         assert_eq!(settings.bitrate, Some(bitrate));
         assert_eq!(settings.rc_min_rate, Some(rc_min_rate));
         assert_eq!(settings.rc_max_rate, Some(rc_max_rate));
@@ -689,6 +1089,34 @@ mod tests {
         assert_eq!(encoder.stream_index, 0);
     }
 
+    #[test]
+    fn test_encoder_thread_settings() {
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder");
+        let data = std::io::Cursor::new(Vec::new());
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+        let mut output = Output::new(data, options).expect("Failed to create Output");
+        let settings = VideoEncoderSettings::builder()
+            .width(640)
+            .height(480)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .thread_count(2)
+            .thread_type(ThreadType::Slice)
+            .threading(Threading::Auto)
+            .build();
+        let encoder = Encoder::new(
+            codec,
+            &mut output,
+            AVRational { num: 1, den: 1000 },
+            AVRational { num: 1, den: 1000 },
+            settings,
+        )
+        .expect("Failed to create encoder");
+
+        assert_eq!(encoder.thread_count(), 2);
+        assert_eq!(encoder.active_thread_type(), ThreadType::Slice);
+    }
+
     #[test]
     fn test_send_eof() {
         let codec = EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder");
@@ -712,7 +1140,11 @@ mod tests {
 
         let result = encoder.send_eof();
         assert!(result.is_ok(), "send_eof returned an error: {:?}", result.err());
-        assert!(encoder.send_eof().is_err(), "send_eof should return an error");
+        assert_eq!(
+            encoder.send_eof(),
+            Err(FfmpegError::EndOfFile),
+            "send_eof should return an error"
+        );
     }
 
     #[test]
@@ -834,6 +1266,78 @@ mod tests {
         insta::assert_debug_snapshot!("test_encoder_encode_video", &boxes);
     }
 
+    #[test]
+    fn test_compare_ts() {
+        let ms = Rational::static_new::<1, 1000>();
+        let mpeg = Rational::static_new::<1, 90000>();
+
+        assert_eq!(compare_ts(1, ms, 90, mpeg), Ordering::Equal);
+        assert_eq!(compare_ts(1, ms, 91, mpeg), Ordering::Less);
+        assert_eq!(compare_ts(2, ms, 91, mpeg), Ordering::Greater);
+        assert_eq!(compare_ts(i64::MIN, ms, i64::MIN, mpeg), Ordering::Less);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut input = Input::open("../../assets/avc_aac.mp4").expect("Failed to open input file");
+        let streams = input.streams();
+        let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+        let input_stream_index = video_stream.index();
+        let time_base = video_stream.time_base();
+        let mut decoder = Decoder::new(&video_stream)
+            .expect("Failed to create decoder")
+            .video()
+            .expect("Failed to create video decoder");
+        let mut output = Output::seekable(
+            std::io::Cursor::new(Vec::new()),
+            OutputOptions::builder().format_name("mp4").unwrap().build(),
+        )
+        .expect("Failed to create Output");
+
+        let settings = || {
+            VideoEncoderSettings::builder()
+                .width(decoder.width())
+                .height(decoder.height())
+                .frame_rate(decoder.frame_rate())
+                .pixel_format(decoder.pixel_format())
+                .build()
+        };
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder");
+        let mut first =
+            Encoder::new(codec, &mut output, time_base, time_base, settings()).expect("Failed to create encoder");
+        let mut second =
+            Encoder::new(codec, &mut output, time_base, time_base, settings()).expect("Failed to create encoder");
+
+        output.write_header().expect("Failed to write header");
+
+        let mut frames = 0;
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() == input_stream_index {
+                decoder.send_packet(&packet).expect("Failed to send packet");
+                while let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                    // Only send frames, all packets are left for drain to write.
+                    first.send_frame(&frame).expect("Failed to send frame");
+                    second.send_frame(&frame).expect("Failed to send frame");
+                    frames += 1;
+                    while let Some(packet) = first.receive_packet().expect("Failed to receive packet") {
+                        output.write_interleaved_packet(packet).expect("Failed to write packet");
+                        frames -= 1;
+                    }
+                    while second.receive_packet().expect("Failed to receive packet").is_some() {}
+                }
+            }
+        }
+
+        let drained = drain(&mut [&mut first, &mut second], &mut output).expect("Failed to drain");
+
+        assert_eq!(drained.len(), 2);
+        assert_eq!(drained[0].stream_index, first.stream_index());
+        assert_eq!(drained[1].stream_index, second.stream_index());
+        assert_eq!(drained[0].packets, frames);
+        assert!(output.write_trailer().is_err(), "trailer should already be written");
+        assert!(drain(&mut [&mut first], &mut output).is_err(), "encoders are already flushed");
+    }
+
     /// make sure [#248](https://github.com/ScuffleCloud/scuffle/pull/248) doesn't happen again
     #[test]
     fn test_pr_248() {
@@ -1017,6 +1017,7 @@ nutype_enum! {
 
         /// LEAD codec.
         /// A proprietary video format.
+        #[cfg(ffmpeg_7)]
         Lead = AV_CODEC_ID_LEAD as _,
 
         /// PCM Signed 16-bit Little Endian codec.
@@ -1845,14 +1846,17 @@ nutype_enum! {
 
         /// OSQ codec.
         /// A proprietary audio format.
+        #[cfg(ffmpeg_7)]
         Osq = AV_CODEC_ID_OSQ as _,
 
         /// QOA codec.
         /// Quite OK Audio, a simple and efficient lossy audio codec.
+        #[cfg(ffmpeg_7)]
         Qoa = AV_CODEC_ID_QOA as _,
 
         /// LC3 codec.
         /// Low Complexity Communication Codec, used in Bluetooth LE Audio.
+        #[cfg(ffmpeg_7_1)]
         Lc3 = AV_CODEC_ID_LC3 as _,
 
         /// DVD Subtitle codec.
@@ -2005,10 +2009,12 @@ nutype_enum! {
 
         /// SMPTE 2038 codec.
         /// A metadata format used in digital broadcasting.
+        #[cfg(ffmpeg_7)]
         Smpte2038 = AV_CODEC_ID_SMPTE_2038 as _,
 
         /// LCEVC codec.
         /// Low Complexity Enhancement Video Coding, a scalable video enhancement format.
+        #[cfg(ffmpeg_7_1)]
         Lcevc = AV_CODEC_ID_LCEVC as _,
 
         /// Probe codec.
@@ -2033,10 +2039,12 @@ nutype_enum! {
 
         /// Null Video codec.
         /// A placeholder for discarded video streams.
+        #[cfg(ffmpeg_7)]
         VNull = AV_CODEC_ID_VNULL as _,
 
         /// Null Audio codec.
         /// A placeholder for discarded audio streams.
+        #[cfg(ffmpeg_7)]
         ANull = AV_CODEC_ID_ANULL as _,
     }
 }
@@ -0,0 +1,83 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVColorPrimaries>() == std::mem::size_of_val(&AVCOL_PRI_UNSPECIFIED));
+};
+
+nutype_enum! {
+    /// Chromaticity coordinates of the source primaries, FFmpeg's `AVColorPrimaries`.
+    ///
+    /// The values match ISO/IEC 23091-4 / ITU-T H.273.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorPrimaries(i32) {
+        /// ITU-R BT.709, also sRGB.
+        /// - **Equivalent to**: `AVCOL_PRI_BT709`
+        Bt709 = AVCOL_PRI_BT709 as _,
+
+        /// Unspecified primaries.
+        /// - **Equivalent to**: `AVCOL_PRI_UNSPECIFIED`
+        Unspecified = AVCOL_PRI_UNSPECIFIED as _,
+
+        /// ITU-R BT.470 System M.
+        /// - **Equivalent to**: `AVCOL_PRI_BT470M`
+        Bt470m = AVCOL_PRI_BT470M as _,
+
+        /// ITU-R BT.470 System B, G (PAL / SECAM).
+        /// - **Equivalent to**: `AVCOL_PRI_BT470BG`
+        Bt470bg = AVCOL_PRI_BT470BG as _,
+
+        /// SMPTE 170M (NTSC).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE170M`
+        Smpte170m = AVCOL_PRI_SMPTE170M as _,
+
+        /// SMPTE 240M.
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE240M`
+        Smpte240m = AVCOL_PRI_SMPTE240M as _,
+
+        /// Generic film (color filters using Illuminant C).
+        /// - **Equivalent to**: `AVCOL_PRI_FILM`
+        Film = AVCOL_PRI_FILM as _,
+
+        /// ITU-R BT.2020, the wide color gamut of UHD and HDR video.
+        /// - **Equivalent to**: `AVCOL_PRI_BT2020`
+        Bt2020 = AVCOL_PRI_BT2020 as _,
+
+        /// SMPTE ST 428-1 (CIE 1931 XYZ).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE428`
+        Smpte428 = AVCOL_PRI_SMPTE428 as _,
+
+        /// SMPTE ST 431-2 (DCI-P3).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE431`
+        Smpte431 = AVCOL_PRI_SMPTE431 as _,
+
+        /// SMPTE ST 432-1 (Display P3).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE432`
+        Smpte432 = AVCOL_PRI_SMPTE432 as _,
+
+        /// EBU Tech. 3213-E.
+        /// - **Equivalent to**: `AVCOL_PRI_EBU3213`
+        Ebu3213 = AVCOL_PRI_EBU3213 as _,
+    }
+}
+
+impl PartialEq<i32> for AVColorPrimaries {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorPrimaries {
+    fn from(value: u32) -> Self {
+        AVColorPrimaries(value as _)
+    }
+}
+
+impl From<AVColorPrimaries> for u32 {
+    fn from(value: AVColorPrimaries) -> Self {
+        value.0 as u32
+    }
+}
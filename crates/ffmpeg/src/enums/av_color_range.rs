@@ -0,0 +1,45 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVColorRange>() == std::mem::size_of_val(&AVCOL_RANGE_UNSPECIFIED));
+};
+
+nutype_enum! {
+    /// The range of the sample values, FFmpeg's `AVColorRange`.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorRange(i32) {
+        /// Unspecified range.
+        /// - **Equivalent to**: `AVCOL_RANGE_UNSPECIFIED`
+        Unspecified = AVCOL_RANGE_UNSPECIFIED as _,
+
+        /// Limited ("TV" or "MPEG") range, 16-235 for 8-bit luma.
+        /// - **Equivalent to**: `AVCOL_RANGE_MPEG`
+        Mpeg = AVCOL_RANGE_MPEG as _,
+
+        /// Full ("PC" or "JPEG") range, 0-255 for 8-bit.
+        /// - **Equivalent to**: `AVCOL_RANGE_JPEG`
+        Jpeg = AVCOL_RANGE_JPEG as _,
+    }
+}
+
+impl PartialEq<i32> for AVColorRange {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorRange {
+    fn from(value: u32) -> Self {
+        AVColorRange(value as _)
+    }
+}
+
+impl From<AVColorRange> for u32 {
+    fn from(value: AVColorRange) -> Self {
+        value.0 as u32
+    }
+}
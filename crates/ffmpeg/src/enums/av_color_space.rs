@@ -0,0 +1,83 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVColorSpace>() == std::mem::size_of_val(&AVCOL_SPC_UNSPECIFIED));
+};
+
+nutype_enum! {
+    /// YUV color space (matrix coefficients), FFmpeg's `AVColorSpace`.
+    ///
+    /// The values match ISO/IEC 23091-4 / ITU-T H.273.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorSpace(i32) {
+        /// RGB, the identity matrix (GBR order).
+        /// - **Equivalent to**: `AVCOL_SPC_RGB`
+        Rgb = AVCOL_SPC_RGB as _,
+
+        /// ITU-R BT.709.
+        /// - **Equivalent to**: `AVCOL_SPC_BT709`
+        Bt709 = AVCOL_SPC_BT709 as _,
+
+        /// Unspecified color space.
+        /// - **Equivalent to**: `AVCOL_SPC_UNSPECIFIED`
+        Unspecified = AVCOL_SPC_UNSPECIFIED as _,
+
+        /// FCC Title 47 Code of Federal Regulations 73.682 (a)(20).
+        /// - **Equivalent to**: `AVCOL_SPC_FCC`
+        Fcc = AVCOL_SPC_FCC as _,
+
+        /// ITU-R BT.470 System B, G and BT.601 625 (PAL / SECAM).
+        /// - **Equivalent to**: `AVCOL_SPC_BT470BG`
+        Bt470bg = AVCOL_SPC_BT470BG as _,
+
+        /// SMPTE 170M and BT.601 525 (NTSC).
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE170M`
+        Smpte170m = AVCOL_SPC_SMPTE170M as _,
+
+        /// SMPTE 240M.
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE240M`
+        Smpte240m = AVCOL_SPC_SMPTE240M as _,
+
+        /// YCgCo.
+        /// - **Equivalent to**: `AVCOL_SPC_YCGCO`
+        Ycgco = AVCOL_SPC_YCGCO as _,
+
+        /// ITU-R BT.2020 non-constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_BT2020_NCL`
+        Bt2020Ncl = AVCOL_SPC_BT2020_NCL as _,
+
+        /// ITU-R BT.2020 constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_BT2020_CL`
+        Bt2020Cl = AVCOL_SPC_BT2020_CL as _,
+
+        /// SMPTE ST 2085.
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE2085`
+        Smpte2085 = AVCOL_SPC_SMPTE2085 as _,
+
+        /// ITU-R BT.2100-0 ICtCp.
+        /// - **Equivalent to**: `AVCOL_SPC_ICTCP`
+        Ictcp = AVCOL_SPC_ICTCP as _,
+    }
+}
+
+impl PartialEq<i32> for AVColorSpace {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorSpace {
+    fn from(value: u32) -> Self {
+        AVColorSpace(value as _)
+    }
+}
+
+impl From<AVColorSpace> for u32 {
+    fn from(value: AVColorSpace) -> Self {
+        value.0 as u32
+    }
+}
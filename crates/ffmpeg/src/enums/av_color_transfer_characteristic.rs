@@ -0,0 +1,91 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVColorTransferCharacteristic>() == std::mem::size_of_val(&AVCOL_TRC_UNSPECIFIED));
+};
+
+nutype_enum! {
+    /// Color transfer characteristics, FFmpeg's `AVColorTransferCharacteristic`.
+    ///
+    /// The transfer characteristic tells whether the video is SDR or HDR,
+    /// HDR10 uses [`AVColorTransferCharacteristic::Smpte2084`] (PQ) and HLG uses
+    /// [`AVColorTransferCharacteristic::AribStdB67`].
+    ///
+    /// The values match ISO/IEC 23091-4 / ITU-T H.273.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorTransferCharacteristic(i32) {
+        /// ITU-R BT.709, the transfer of most SDR video.
+        /// - **Equivalent to**: `AVCOL_TRC_BT709`
+        Bt709 = AVCOL_TRC_BT709 as _,
+
+        /// Unspecified transfer characteristic.
+        /// - **Equivalent to**: `AVCOL_TRC_UNSPECIFIED`
+        Unspecified = AVCOL_TRC_UNSPECIFIED as _,
+
+        /// Gamma 2.2 (ITU-R BT.470 System M).
+        /// - **Equivalent to**: `AVCOL_TRC_GAMMA22`
+        Gamma22 = AVCOL_TRC_GAMMA22 as _,
+
+        /// Gamma 2.8 (ITU-R BT.470 System B, G).
+        /// - **Equivalent to**: `AVCOL_TRC_GAMMA28`
+        Gamma28 = AVCOL_TRC_GAMMA28 as _,
+
+        /// SMPTE 170M, functionally identical to BT.709.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE170M`
+        Smpte170m = AVCOL_TRC_SMPTE170M as _,
+
+        /// SMPTE 240M.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE240M`
+        Smpte240m = AVCOL_TRC_SMPTE240M as _,
+
+        /// Linear transfer characteristic.
+        /// - **Equivalent to**: `AVCOL_TRC_LINEAR`
+        Linear = AVCOL_TRC_LINEAR as _,
+
+        /// IEC 61966-2-1 (sRGB).
+        /// - **Equivalent to**: `AVCOL_TRC_IEC61966_2_1`
+        Iec61966_2_1 = AVCOL_TRC_IEC61966_2_1 as _,
+
+        /// ITU-R BT.2020 for 10-bit systems, functionally identical to BT.709.
+        /// - **Equivalent to**: `AVCOL_TRC_BT2020_10`
+        Bt2020_10 = AVCOL_TRC_BT2020_10 as _,
+
+        /// ITU-R BT.2020 for 12-bit systems, functionally identical to BT.709.
+        /// - **Equivalent to**: `AVCOL_TRC_BT2020_12`
+        Bt2020_12 = AVCOL_TRC_BT2020_12 as _,
+
+        /// SMPTE ST 2084 perceptual quantizer (PQ), used by HDR10.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE2084`
+        Smpte2084 = AVCOL_TRC_SMPTE2084 as _,
+
+        /// SMPTE ST 428-1.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE428`
+        Smpte428 = AVCOL_TRC_SMPTE428 as _,
+
+        /// ARIB STD-B67, hybrid log-gamma (HLG).
+        /// - **Equivalent to**: `AVCOL_TRC_ARIB_STD_B67`
+        AribStdB67 = AVCOL_TRC_ARIB_STD_B67 as _,
+    }
+}
+
+impl PartialEq<i32> for AVColorTransferCharacteristic {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorTransferCharacteristic {
+    fn from(value: u32) -> Self {
+        AVColorTransferCharacteristic(value as _)
+    }
+}
+
+impl From<AVColorTransferCharacteristic> for u32 {
+    fn from(value: AVColorTransferCharacteristic) -> Self {
+        value.0 as u32
+    }
+}
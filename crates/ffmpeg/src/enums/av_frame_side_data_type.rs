@@ -0,0 +1,72 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVFrameSideDataType>() == std::mem::size_of_val(&AV_FRAME_DATA_A53_CC));
+};
+
+nutype_enum! {
+    /// Frame side data types used in FFmpeg's `AVFrameSideDataType`.
+    ///
+    /// Side data attached to a frame is passed through to the encoder, which can
+    /// turn some of it into in-band metadata, such as SEI messages in H.264 / HEVC.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/frame_8h.html>
+    pub enum AVFrameSideDataType(i32) {
+        /// **ATSC A53 Part 4 closed captions** (CEA-608 / CEA-708).
+        /// - **Format**: A list of `cc_data` triplets, as found in the `cc_data_pkt` of an A53 user data SEI.
+        /// - **Equivalent to**: `AV_FRAME_DATA_A53_CC`
+        A53Cc = AV_FRAME_DATA_A53_CC as _,
+
+        /// **Stereoscopic 3D metadata**.
+        /// - **Format**: An `AVStereo3D` struct.
+        /// - **Equivalent to**: `AV_FRAME_DATA_STEREO3D`
+        Stereo3D = AV_FRAME_DATA_STEREO3D as _,
+
+        /// **Display transformation matrix**.
+        /// - **Format**: A 3x3 matrix of `i32`.
+        /// - **Equivalent to**: `AV_FRAME_DATA_DISPLAYMATRIX`
+        DisplayMatrix = AV_FRAME_DATA_DISPLAYMATRIX as _,
+
+        /// **Mastering display metadata** (SMPTE ST 2086).
+        /// - **Format**: An `AVMasteringDisplayMetadata` struct.
+        /// - **Equivalent to**: `AV_FRAME_DATA_MASTERING_DISPLAY_METADATA`
+        MasteringDisplayMetadata = AV_FRAME_DATA_MASTERING_DISPLAY_METADATA as _,
+
+        /// **Content light level** (CTA-861.3).
+        /// - **Format**: An `AVContentLightMetadata` struct.
+        /// - **Equivalent to**: `AV_FRAME_DATA_CONTENT_LIGHT_LEVEL`
+        ContentLightLevel = AV_FRAME_DATA_CONTENT_LIGHT_LEVEL as _,
+
+        /// **SMPTE 12-1 timecode**.
+        /// - **Format**: An array of 4 `u32`, the first being the number of timecodes (1 to 3)
+        ///   followed by the timecodes in SMPTE 12-1 binary representation.
+        /// - **Equivalent to**: `AV_FRAME_DATA_S12M_TIMECODE`
+        S12mTimecode = AV_FRAME_DATA_S12M_TIMECODE as _,
+
+        /// **User data unregistered SEI**.
+        /// - **Format**: A 16 byte UUID followed by the user data payload.
+        /// - **Equivalent to**: `AV_FRAME_DATA_SEI_UNREGISTERED`
+        SeiUnregistered = AV_FRAME_DATA_SEI_UNREGISTERED as _,
+    }
+}
+
+impl PartialEq<i32> for AVFrameSideDataType {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVFrameSideDataType {
+    fn from(value: u32) -> Self {
+        AVFrameSideDataType(value as _)
+    }
+}
+
+impl From<AVFrameSideDataType> for u32 {
+    fn from(value: AVFrameSideDataType) -> Self {
+        value.0 as u32
+    }
+}
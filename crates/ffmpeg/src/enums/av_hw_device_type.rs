@@ -0,0 +1,84 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVHWDeviceType>() == std::mem::size_of_val(&AV_HWDEVICE_TYPE_NONE));
+};
+
+nutype_enum! {
+    /// Hardware device types used in FFmpeg's `AVHWDeviceType` enumeration.
+    ///
+    /// Which of these are available depends on how FFmpeg was built and on the
+    /// hardware of the host.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/hwcontext_8h.html>
+    pub enum AVHWDeviceType(i32) {
+        /// No hardware device.
+        /// Corresponds to `AV_HWDEVICE_TYPE_NONE`.
+        None = AV_HWDEVICE_TYPE_NONE as _,
+
+        /// NVIDIA VDPAU.
+        /// Corresponds to `AV_HWDEVICE_TYPE_VDPAU`.
+        Vdpau = AV_HWDEVICE_TYPE_VDPAU as _,
+
+        /// NVIDIA CUDA, used by the NVDEC decoders and NVENC encoders.
+        /// Corresponds to `AV_HWDEVICE_TYPE_CUDA`.
+        Cuda = AV_HWDEVICE_TYPE_CUDA as _,
+
+        /// VA-API, used on Linux with Intel and AMD GPUs.
+        /// Corresponds to `AV_HWDEVICE_TYPE_VAAPI`.
+        Vaapi = AV_HWDEVICE_TYPE_VAAPI as _,
+
+        /// DirectX Video Acceleration 2.
+        /// Corresponds to `AV_HWDEVICE_TYPE_DXVA2`.
+        Dxva2 = AV_HWDEVICE_TYPE_DXVA2 as _,
+
+        /// Intel Quick Sync Video.
+        /// Corresponds to `AV_HWDEVICE_TYPE_QSV`.
+        Qsv = AV_HWDEVICE_TYPE_QSV as _,
+
+        /// Apple VideoToolbox.
+        /// Corresponds to `AV_HWDEVICE_TYPE_VIDEOTOOLBOX`.
+        VideoToolbox = AV_HWDEVICE_TYPE_VIDEOTOOLBOX as _,
+
+        /// Direct3D 11 Video Acceleration.
+        /// Corresponds to `AV_HWDEVICE_TYPE_D3D11VA`.
+        D3d11va = AV_HWDEVICE_TYPE_D3D11VA as _,
+
+        /// Linux Direct Rendering Manager.
+        /// Corresponds to `AV_HWDEVICE_TYPE_DRM`.
+        Drm = AV_HWDEVICE_TYPE_DRM as _,
+
+        /// OpenCL.
+        /// Corresponds to `AV_HWDEVICE_TYPE_OPENCL`.
+        OpenCl = AV_HWDEVICE_TYPE_OPENCL as _,
+
+        /// Android MediaCodec.
+        /// Corresponds to `AV_HWDEVICE_TYPE_MEDIACODEC`.
+        MediaCodec = AV_HWDEVICE_TYPE_MEDIACODEC as _,
+
+        /// Vulkan.
+        /// Corresponds to `AV_HWDEVICE_TYPE_VULKAN`.
+        Vulkan = AV_HWDEVICE_TYPE_VULKAN as _,
+    }
+}
+
+impl PartialEq<i32> for AVHWDeviceType {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVHWDeviceType {
+    fn from(value: u32) -> Self {
+        AVHWDeviceType(value as i32)
+    }
+}
+
+impl From<AVHWDeviceType> for u32 {
+    fn from(value: AVHWDeviceType) -> Self {
+        value.0 as u32
+    }
+}
@@ -0,0 +1,91 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+const _: () = {
+    assert!(std::mem::size_of::<AVPacketSideDataType>() == std::mem::size_of_val(&AV_PKT_DATA_A53_CC));
+};
+
+nutype_enum! {
+    /// Packet side data types used in FFmpeg's `AVPacketSideDataType`.
+    ///
+    /// Demuxers attach container level metadata to packets, and encoders attach
+    /// metadata that muxers write into the container.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/packet_8h.html>
+    pub enum AVPacketSideDataType(i32) {
+        /// **New extradata**, the codec configuration changed mid-stream.
+        /// - **Format**: The new extradata.
+        /// - **Equivalent to**: `AV_PKT_DATA_NEW_EXTRADATA`
+        NewExtradata = AV_PKT_DATA_NEW_EXTRADATA as _,
+
+        /// **Display transformation matrix**.
+        /// - **Format**: A 3x3 matrix of `i32`.
+        /// - **Equivalent to**: `AV_PKT_DATA_DISPLAYMATRIX`
+        DisplayMatrix = AV_PKT_DATA_DISPLAYMATRIX as _,
+
+        /// **Stereoscopic 3D metadata**.
+        /// - **Format**: An `AVStereo3D` struct.
+        /// - **Equivalent to**: `AV_PKT_DATA_STEREO3D`
+        Stereo3D = AV_PKT_DATA_STEREO3D as _,
+
+        /// **Samples to skip** at the start or end of the packet, for encoder delay and padding.
+        /// - **Format**: Two little endian `u32`, the samples to skip at the start and at the end.
+        /// - **Equivalent to**: `AV_PKT_DATA_SKIP_SAMPLES`
+        SkipSamples = AV_PKT_DATA_SKIP_SAMPLES as _,
+
+        /// **Mastering display metadata** (SMPTE ST 2086).
+        /// - **Format**: An `AVMasteringDisplayMetadata` struct.
+        /// - **Equivalent to**: `AV_PKT_DATA_MASTERING_DISPLAY_METADATA`
+        MasteringDisplayMetadata = AV_PKT_DATA_MASTERING_DISPLAY_METADATA as _,
+
+        /// **Content light level** (CTA-861.3).
+        /// - **Format**: An `AVContentLightMetadata` struct.
+        /// - **Equivalent to**: `AV_PKT_DATA_CONTENT_LIGHT_LEVEL`
+        ContentLightLevel = AV_PKT_DATA_CONTENT_LIGHT_LEVEL as _,
+
+        /// **ATSC A53 Part 4 closed captions** (CEA-608 / CEA-708).
+        /// - **Format**: A list of `cc_data` triplets.
+        /// - **Equivalent to**: `AV_PKT_DATA_A53_CC`
+        A53Cc = AV_PKT_DATA_A53_CC as _,
+
+        /// **ICC profile**.
+        /// - **Format**: An ICC profile as an opaque octet buffer.
+        /// - **Equivalent to**: `AV_PKT_DATA_ICC_PROFILE`
+        IccProfile = AV_PKT_DATA_ICC_PROFILE as _,
+
+        /// **Dolby Vision configuration**.
+        /// - **Format**: An `AVDOVIDecoderConfigurationRecord` struct.
+        /// - **Equivalent to**: `AV_PKT_DATA_DOVI_CONF`
+        DoviConf = AV_PKT_DATA_DOVI_CONF as _,
+
+        /// **SMPTE 12-1 timecode**.
+        /// - **Format**: An array of 4 `u32`, the number of timecodes followed by the timecodes.
+        /// - **Equivalent to**: `AV_PKT_DATA_S12M_TIMECODE`
+        S12mTimecode = AV_PKT_DATA_S12M_TIMECODE as _,
+
+        /// **HDR10+ dynamic metadata** (SMPTE ST 2094-40).
+        /// - **Format**: The ITU-T T.35 payload of the metadata.
+        /// - **Equivalent to**: `AV_PKT_DATA_DYNAMIC_HDR10_PLUS`
+        DynamicHdr10Plus = AV_PKT_DATA_DYNAMIC_HDR10_PLUS as _,
+    }
+}
+
+impl PartialEq<i32> for AVPacketSideDataType {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVPacketSideDataType {
+    fn from(value: u32) -> Self {
+        AVPacketSideDataType(value as _)
+    }
+}
+
+impl From<AVPacketSideDataType> for u32 {
+    fn from(value: AVPacketSideDataType) -> Self {
+        value.0 as u32
+    }
+}
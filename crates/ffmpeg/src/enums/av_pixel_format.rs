@@ -147,10 +147,39 @@ nutype_enum! {
         /// Corresponds to `AV_PIX_FMT_VAAPI`.
         Vaapi = AV_PIX_FMT_VAAPI as _,
 
+        /// Hardware-accelerated format through CUDA.
+        /// Corresponds to `AV_PIX_FMT_CUDA`.
+        Cuda = AV_PIX_FMT_CUDA as _,
+
+        /// Hardware-accelerated format through Intel Quick Sync Video.
+        /// Corresponds to `AV_PIX_FMT_QSV`.
+        Qsv = AV_PIX_FMT_QSV as _,
+
+        /// Hardware-accelerated format through VideoToolbox.
+        /// Corresponds to `AV_PIX_FMT_VIDEOTOOLBOX`.
+        VideoToolbox = AV_PIX_FMT_VIDEOTOOLBOX as _,
+
+        /// Semi-planar YUV 4:2:0 format, 12 bits per pixel.
+        /// A Y plane followed by an interleaved UV plane, the usual software
+        /// format of hardware frames.
+        /// Corresponds to `AV_PIX_FMT_NV12`.
+        Nv12 = AV_PIX_FMT_NV12 as _,
+
         /// Planar GBR format, 4:4:4 subsampling.
         /// Corresponds to `AV_PIX_FMT_GBRP`.
         Gbrp = AV_PIX_FMT_GBRP as _,
 
+        /// Packed RGBA format, 8 bits per channel (32bpp).
+        /// Stored as RGBARGBA...
+        /// Corresponds to `AV_PIX_FMT_RGBA`.
+        Rgba = AV_PIX_FMT_RGBA as _,
+
+        /// Planar YUV 4:2:0 format in the full range, 12 bits per pixel.
+        /// Deprecated by FFmpeg in favour of `Yuv420p` with a full color range,
+        /// but still the format the MJPEG encoder and swscale use for it.
+        /// Corresponds to `AV_PIX_FMT_YUVJ420P`.
+        Yuvj420p = AV_PIX_FMT_YUVJ420P as _,
+
         /// Format count, not an actual pixel format.
         /// Used internally by FFmpeg.
         /// Corresponds to `AV_PIX_FMT_NB`.
@@ -36,3 +36,24 @@ pub use av_pkt_flags::*;
 
 mod av_discard;
 pub use av_discard::*;
+
+mod av_frame_side_data_type;
+pub use av_frame_side_data_type::*;
+
+mod av_packet_side_data_type;
+pub use av_packet_side_data_type::*;
+
+mod av_color_primaries;
+pub use av_color_primaries::*;
+
+mod av_color_transfer_characteristic;
+pub use av_color_transfer_characteristic::*;
+
+mod av_color_space;
+pub use av_color_space::*;
+
+mod av_color_range;
+pub use av_color_range::*;
+
+mod av_hw_device_type;
+pub use av_hw_device_type::*;
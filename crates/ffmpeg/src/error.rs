@@ -2,8 +2,13 @@ use nutype_enum::nutype_enum;
 
 use crate::ffi::*;
 
-#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[derive(Debug, thiserror::Error)]
 /// An error that occurs when the ffmpeg operation fails.
+///
+/// Errors of the decode, encode and mux paths are classified into the structured variants
+/// [`NeedsMoreInput`](Self::NeedsMoreInput), [`EndOfFile`](Self::EndOfFile),
+/// [`InvalidData`](Self::InvalidData), [`Io`](Self::Io) and [`Bug`](Self::Bug),
+/// see [`FfmpegError::from_code`]. Other error codes are kept as [`Code`](Self::Code).
 pub enum FfmpegError {
     /// An error that occurs when the memory allocation fails.
     #[error("failed to allocate memory")]
@@ -29,8 +34,88 @@ pub enum FfmpegError {
     /// An error that occurs when the arguments are invalid.
     #[error("invalid arguments: {0}")]
     Arguments(&'static str),
+    /// The operation cannot proceed in the current state, more input must be sent
+    /// or pending output must be received first, then the operation can be retried.
+    #[error("needs more input")]
+    NeedsMoreInput,
+    /// The end of the stream was reached, no more data will be produced or accepted.
+    #[error("end of file")]
+    EndOfFile,
+    /// The data is invalid or corrupt.
+    #[error("invalid data")]
+    InvalidData,
+    /// An operating system error, such as a failing write of the output.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// FFmpeg hit an internal bug.
+    #[error("internal ffmpeg bug")]
+    Bug,
+    /// An option could not be set, because it does not exist or the value is invalid.
+    #[error("invalid option {name}: {code}")]
+    InvalidOption {
+        /// The name of the option.
+        name: String,
+        /// The error code returned when setting it.
+        code: FfmpegErrorCode,
+    },
 }
 
+impl FfmpegError {
+    /// Classifies an error code into one of the structured variants.
+    ///
+    /// Codes that are errno values become [`FfmpegError::Io`], apart from `EAGAIN` which becomes
+    /// [`FfmpegError::NeedsMoreInput`] and `ENOMEM` which becomes [`FfmpegError::Alloc`].
+    /// Codes without a structured variant are kept as [`FfmpegError::Code`].
+    pub fn from_code(code: FfmpegErrorCode) -> Self {
+        match code {
+            FfmpegErrorCode::Eagain => Self::NeedsMoreInput,
+            FfmpegErrorCode::Eof => Self::EndOfFile,
+            FfmpegErrorCode::InvalidData => Self::InvalidData,
+            FfmpegErrorCode::Bug | FfmpegErrorCode::Bug2 => Self::Bug,
+            code if code.0 == AVERROR(ENOMEM) => Self::Alloc,
+            // FFmpeg's own error codes are tags with a large magnitude, errno values are small.
+            FfmpegErrorCode(code) if (-4095..0).contains(&code) => Self::Io(std::io::Error::from_raw_os_error(-code)),
+            code => Self::Code(code),
+        }
+    }
+
+    /// Returns true if the operation that failed can be retried as is, or after receiving
+    /// pending output.
+    ///
+    /// This is the case for [`FfmpegError::NeedsMoreInput`] and for interrupted or timed out
+    /// [`FfmpegError::Io`] errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::NeedsMoreInput => true,
+            Self::Io(error) => matches!(
+                error.kind(),
+                std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for FfmpegError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Code(a), Self::Code(b)) => a == b,
+            (Self::Arguments(a), Self::Arguments(b)) => a == b,
+            (Self::InvalidOption { name: a, code: a_code }, Self::InvalidOption { name: b, code: b_code }) => {
+                a == b && a_code == b_code
+            }
+            // `std::io::Error` is not comparable, the os error or else the kind is compared instead.
+            (Self::Io(a), Self::Io(b)) => match (a.raw_os_error(), b.raw_os_error()) {
+                (Some(a), Some(b)) => a == b,
+                _ => a.kind() == b.kind(),
+            },
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for FfmpegError {}
+
 nutype_enum! {
     /// An enum that represents the ffmpeg error code.
     pub enum FfmpegErrorCode(i32) {
@@ -100,6 +185,14 @@ impl FfmpegErrorCode {
         }
     }
 
+    /// Like [`FfmpegErrorCode::result`], but classifies the error with [`FfmpegError::from_code`].
+    pub fn classify(self) -> Result<i32, FfmpegError> {
+        match self {
+            code if code.is_success() => Ok(code.0),
+            code => Err(FfmpegError::from_code(code)),
+        }
+    }
+
     /// Returns true if the error code is a success code.
     pub const fn is_success(self) -> bool {
         self.0 >= 0
@@ -241,10 +334,61 @@ mod tests {
                 FfmpegError::Arguments("invalid argument example"),
                 "invalid arguments: invalid argument example",
             ),
+            (FfmpegError::NeedsMoreInput, "needs more input"),
+            (FfmpegError::EndOfFile, "end of file"),
+            (FfmpegError::InvalidData, "invalid data"),
+            (FfmpegError::Bug, "internal ffmpeg bug"),
+            (
+                FfmpegError::InvalidOption {
+                    name: "crf".into(),
+                    code: FfmpegErrorCode::OptionNotFound,
+                },
+                "invalid option crf: option not found",
+            ),
         ];
 
         for (error, expected) in cases {
             assert_eq!(error.to_string(), expected);
         }
     }
+
+    #[test]
+    fn test_ffmpeg_error_from_code() {
+        let cases = [
+            (FfmpegErrorCode::Eagain, FfmpegError::NeedsMoreInput),
+            (FfmpegErrorCode::Eof, FfmpegError::EndOfFile),
+            (FfmpegErrorCode::InvalidData, FfmpegError::InvalidData),
+            (FfmpegErrorCode::Bug, FfmpegError::Bug),
+            (FfmpegErrorCode::Bug2, FfmpegError::Bug),
+            (FfmpegErrorCode(AVERROR(ENOMEM)), FfmpegError::Alloc),
+            (
+                FfmpegErrorCode(AVERROR(libc::EPIPE)),
+                FfmpegError::Io(std::io::Error::from_raw_os_error(libc::EPIPE)),
+            ),
+            (
+                FfmpegErrorCode::MuxerNotFound,
+                FfmpegError::Code(FfmpegErrorCode::MuxerNotFound),
+            ),
+            (FfmpegErrorCode::Unknown, FfmpegError::Code(FfmpegErrorCode::Unknown)),
+        ];
+
+        for (code, expected) in cases {
+            assert_eq!(FfmpegError::from_code(code), expected, "Failed for code: {code}");
+        }
+
+        assert_eq!(FfmpegErrorCode(0).classify(), Ok(0));
+        assert_eq!(FfmpegErrorCode::Eagain.classify(), Err(FfmpegError::NeedsMoreInput));
+    }
+
+    #[test]
+    fn test_ffmpeg_error_is_retryable() {
+        assert!(FfmpegError::NeedsMoreInput.is_retryable());
+        assert!(FfmpegError::Io(std::io::ErrorKind::Interrupted.into()).is_retryable());
+        assert!(FfmpegError::Io(std::io::ErrorKind::TimedOut.into()).is_retryable());
+        assert!(!FfmpegError::Io(std::io::ErrorKind::BrokenPipe.into()).is_retryable());
+        assert!(!FfmpegError::EndOfFile.is_retryable());
+        assert!(!FfmpegError::InvalidData.is_retryable());
+        assert!(!FfmpegError::Bug.is_retryable());
+        assert!(!FfmpegError::Code(FfmpegErrorCode::Unknown).is_retryable());
+    }
 }
@@ -0,0 +1,725 @@
+use std::ffi::CStr;
+use std::fmt::Write as _;
+
+use super::{Filter, FilterContextSink, FilterContextSource, FilterGraph};
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::frame::AudioChannelLayout;
+use crate::rational::Rational;
+use crate::{AVPixelFormat, AVSampleFormat};
+
+/// The arguments of a `buffer` source, which video frames are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bon::Builder)]
+pub struct VideoSourceArgs {
+    /// The width of the frames.
+    pub width: i32,
+    /// The height of the frames.
+    pub height: i32,
+    /// The pixel format of the frames.
+    pub pixel_format: AVPixelFormat,
+    /// The time base of the frame timestamps.
+    #[builder(into)]
+    pub time_base: Rational,
+    /// The sample aspect ratio of the frames.
+    #[builder(default = Rational::ONE, into)]
+    pub sample_aspect_ratio: Rational,
+    /// The frame rate of the frames, if known.
+    #[builder(into)]
+    pub frame_rate: Option<Rational>,
+}
+
+impl VideoSourceArgs {
+    fn args(&self) -> String {
+        let mut args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
+            self.width,
+            self.height,
+            self.pixel_format.0,
+            rational(self.time_base),
+            rational(self.sample_aspect_ratio),
+        );
+
+        if let Some(frame_rate) = self.frame_rate {
+            write!(args, ":frame_rate={}", rational(frame_rate)).expect("writing to a string never fails");
+        }
+
+        args
+    }
+}
+
+/// The arguments of an `abuffer` source, which audio frames are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bon::Builder)]
+pub struct AudioSourceArgs {
+    /// The sample rate of the frames.
+    pub sample_rate: i32,
+    /// The sample format of the frames.
+    pub sample_format: AVSampleFormat,
+    /// The number of channels of the frames, in the default layout for that many channels.
+    pub channels: i32,
+    /// The time base of the frame timestamps, `1/sample_rate` if not set.
+    #[builder(into)]
+    pub time_base: Option<Rational>,
+}
+
+impl AudioSourceArgs {
+    fn args(&self) -> String {
+        let time_base = self
+            .time_base
+            .map(rational)
+            .unwrap_or_else(|| format!("1/{}", self.sample_rate));
+
+        // The layout is described, a channel count alone leaves the channel order unspecified.
+        let channels = default_layout_name(self.channels)
+            .map(|layout| format!("channel_layout={layout}"))
+            .unwrap_or_else(|| format!("channels={}", self.channels));
+
+        format!(
+            "sample_rate={}:sample_fmt={}:{channels}:time_base={time_base}",
+            self.sample_rate, self.sample_format.0,
+        )
+    }
+}
+
+/// A filter chain of the graph, `[in]filter,filter[out]` in the filter graph syntax.
+#[derive(Debug, Clone, Default)]
+struct Chain {
+    inputs: Vec<String>,
+    filters: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl Chain {
+    fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.filters.is_empty() && self.outputs.is_empty()
+    }
+}
+
+/// A buffer source or sink of the graph.
+#[derive(Debug, Clone)]
+struct Endpoint {
+    name: String,
+    filter: &'static str,
+    args: String,
+}
+
+/// Builds a [`FilterGraph`] from typed filters, instead of a filter graph string.
+///
+/// Frames enter the graph through buffer sources and leave it through buffer sinks, both
+/// named. Filters are added to the current chain, which starts at the pads named with
+/// [`input`](Self::input) and ends at the pads named with [`output`](Self::output).
+/// Source and sink names are pad names as well, and pads that are not named are linked to
+/// the sources and sinks in the order they were added, so a graph with a single source and
+/// sink needs no names at all.
+///
+/// ```rust,no_run
+/// # use scuffle_ffmpeg::AVPixelFormat;
+/// # use scuffle_ffmpeg::filter_graph::{FilterGraphBuilder, VideoSourceArgs};
+/// # use scuffle_ffmpeg::rational::Rational;
+/// # fn example() -> Result<(), scuffle_ffmpeg::error::FfmpegError> {
+/// let args = |width, height| {
+///     VideoSourceArgs::builder()
+///         .width(width)
+///         .height(height)
+///         .pixel_format(AVPixelFormat::Yuv420p)
+///         .time_base(Rational::static_new::<1, 30>())
+///         .build()
+/// };
+///
+/// let mut graph = FilterGraphBuilder::new()
+///     .video_source("main", args(1920, 1080))
+///     .video_source("logo", args(256, 256))
+///     .video_sink("out")
+///     // [logo]scale=w=64:h=64[small]
+///     .input("logo")
+///     .scale(64, 64)
+///     .output("small")
+///     // [main][small]overlay=x=16:y=16,fps=fps=30/1[out]
+///     .input("main")
+///     .input("small")
+///     .overlay(16, 16)
+///     .fps(30)
+///     .output("out")
+///     .build()?;
+///
+/// let mut main = graph.source("main").expect("source was added");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use = "builders do nothing unless built"]
+pub struct FilterGraphBuilder {
+    sources: Vec<Endpoint>,
+    sinks: Vec<Endpoint>,
+    chains: Vec<Chain>,
+    current: Chain,
+    thread_count: Option<i32>,
+    /// The first invalid argument, returned by [`build`](Self::build).
+    error: Option<&'static str>,
+}
+
+impl FilterGraphBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `buffer` source named `name`, which video frames are sent to.
+    pub fn video_source(self, name: impl Into<String>, args: VideoSourceArgs) -> Self {
+        self.source(name.into(), "buffer", args.args())
+    }
+
+    /// Adds an `abuffer` source named `name`, which audio frames are sent to.
+    pub fn audio_source(self, name: impl Into<String>, args: AudioSourceArgs) -> Self {
+        self.source(name.into(), "abuffer", args.args())
+    }
+
+    /// Adds a `buffersink` named `name`, which video frames are received from.
+    pub fn video_sink(self, name: impl Into<String>) -> Self {
+        self.sink(name.into(), "buffersink")
+    }
+
+    /// Adds an `abuffersink` named `name`, which audio frames are received from.
+    pub fn audio_sink(self, name: impl Into<String>) -> Self {
+        self.sink(name.into(), "abuffersink")
+    }
+
+    /// Sets the number of threads the filters may use, 0 lets FFmpeg decide.
+    pub const fn thread_count(mut self, threads: i32) -> Self {
+        self.thread_count = Some(threads);
+        self
+    }
+
+    /// Links the pad named `label` to the next input of the current chain.
+    ///
+    /// Starts a new chain if the current one already has filters.
+    pub fn input(mut self, label: impl Into<String>) -> Self {
+        let label = self.label(label.into());
+        if !self.current.filters.is_empty() || !self.current.outputs.is_empty() {
+            self.finish_chain();
+        }
+
+        self.current.inputs.push(label);
+        self
+    }
+
+    /// Names the next output of the current chain `label`.
+    ///
+    /// The next filter added starts a new chain.
+    pub fn output(mut self, label: impl Into<String>) -> Self {
+        let label = self.label(label.into());
+        self.current.outputs.push(label);
+        self
+    }
+
+    /// Adds a filter with options, for filters without a typed method.
+    ///
+    /// Option values are escaped, so they can contain any character.
+    pub fn filter<'a>(mut self, name: &str, options: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.error
+                .get_or_insert("filter names may only contain alphanumeric characters and underscores");
+            return self;
+        }
+
+        let mut filter = name.to_owned();
+        for (index, (key, value)) in options.into_iter().enumerate() {
+            filter.push(if index == 0 { '=' } else { ':' });
+            filter.push_str(key);
+            filter.push('=');
+            escape(value, &mut filter);
+        }
+
+        if !self.current.outputs.is_empty() {
+            self.finish_chain();
+        }
+
+        self.current.filters.push(filter);
+        self
+    }
+
+    /// Scales video to `width` by `height`, `scale`.
+    ///
+    /// A size of -1 keeps the aspect ratio, -2 does so as well while keeping the size even.
+    pub fn scale(self, width: i32, height: i32) -> Self {
+        self.filter(
+            "scale",
+            [("w", width.to_string().as_str()), ("h", height.to_string().as_str())],
+        )
+    }
+
+    /// Converts video to a constant frame rate by dropping or duplicating frames, `fps`.
+    pub fn fps(self, frame_rate: impl Into<Rational>) -> Self {
+        self.filter("fps", [("fps", rational(frame_rate.into()).as_str())])
+    }
+
+    /// Converts video to `pixel_format`, `format`.
+    pub fn format(mut self, pixel_format: AVPixelFormat) -> Self {
+        // Safety: `av_get_pix_fmt_name` is safe to call with any pixel format.
+        let name = unsafe { av_get_pix_fmt_name(pixel_format.0) };
+        match c_name(name) {
+            Some(name) => self.filter("format", [("pix_fmts", name)]),
+            None => {
+                self.error.get_or_insert("unknown pixel format");
+                self
+            }
+        }
+    }
+
+    /// Crops video to `width` by `height`, starting `x` and `y` pixels from the top left, `crop`.
+    pub fn crop(self, width: i32, height: i32, x: i32, y: i32) -> Self {
+        let [width, height, x, y] = [width, height, x, y].map(|value| value.to_string());
+        self.filter("crop", [("w", &*width), ("h", &*height), ("x", &*x), ("y", &*y)])
+    }
+
+    /// Draws the second input on top of the first, `x` and `y` pixels from the top left, `overlay`.
+    ///
+    /// Takes two inputs, usually named with [`input`](Self::input).
+    pub fn overlay(self, x: i32, y: i32) -> Self {
+        self.filter("overlay", [("x", x.to_string().as_str()), ("y", y.to_string().as_str())])
+    }
+
+    /// Resamples audio to `sample_rate`, `aresample`.
+    pub fn aresample(self, sample_rate: i32) -> Self {
+        self.filter("aresample", [("out_sample_rate", sample_rate.to_string().as_str())])
+    }
+
+    /// Converts audio to `sample_format`, `aformat`.
+    pub fn aformat(mut self, sample_format: AVSampleFormat) -> Self {
+        // Safety: `av_get_sample_fmt_name` is safe to call with any sample format.
+        let name = unsafe { av_get_sample_fmt_name(sample_format.0) };
+        match c_name(name) {
+            Some(name) => self.filter("aformat", [("sample_fmts", name)]),
+            None => {
+                self.error.get_or_insert("unknown sample format");
+                self
+            }
+        }
+    }
+
+    /// Returns the graph in the filter graph syntax, as it is passed to FFmpeg.
+    ///
+    /// Sources and sinks are not part of it, they are linked through their pad names.
+    pub fn spec(&self) -> String {
+        let chains = self
+            .chains
+            .iter()
+            .chain(Some(&self.current).filter(|chain| !chain.is_empty()));
+
+        let mut spec = String::new();
+        for (index, chain) in chains.enumerate() {
+            if index > 0 {
+                spec.push(';');
+            }
+
+            for label in &chain.inputs {
+                write!(spec, "[{label}]").expect("writing to a string never fails");
+            }
+
+            // A chain without filters passes its input through.
+            if chain.filters.is_empty() {
+                spec.push_str(if self.is_audio(chain) { "anull" } else { "null" });
+            } else {
+                spec.push_str(&chain.filters.join(","));
+            }
+
+            for label in &chain.outputs {
+                write!(spec, "[{label}]").expect("writing to a string never fails");
+            }
+        }
+
+        spec
+    }
+
+    /// Creates and configures the graph.
+    pub fn build(self) -> Result<BuiltFilterGraph, FfmpegError> {
+        if let Some(error) = self.error {
+            return Err(FfmpegError::Arguments(error));
+        }
+
+        let mut graph = FilterGraph::new()?;
+        if let Some(threads) = self.thread_count {
+            graph.set_thread_count(threads);
+        }
+
+        for endpoint in self.sources.iter().chain(&self.sinks) {
+            let filter = Filter::get(endpoint.filter).ok_or(FfmpegError::NoFilter)?;
+            graph.add(filter, &endpoint.name, &endpoint.args)?;
+        }
+
+        let spec = self.spec();
+
+        // Pads are prepended to the lists, so they are added in reverse to link unnamed pads in order.
+        let mut parser = super::FilterGraphParser::new(&mut graph);
+        for source in self.sources.iter().rev() {
+            parser = parser.output(&source.name, 0)?;
+        }
+        for sink in self.sinks.iter().rev() {
+            parser = parser.input(&sink.name, 0)?;
+        }
+        parser.parse(&spec)?;
+
+        graph.validate()?;
+
+        Ok(BuiltFilterGraph {
+            graph,
+            sources: self.sources.into_iter().map(|source| source.name).collect(),
+            sinks: self.sinks.into_iter().map(|sink| sink.name).collect(),
+        })
+    }
+
+    fn source(mut self, name: String, filter: &'static str, args: String) -> Self {
+        let name = self.endpoint_name(name);
+        self.sources.push(Endpoint { name, filter, args });
+        self
+    }
+
+    fn sink(mut self, name: String, filter: &'static str) -> Self {
+        let name = self.endpoint_name(name);
+        self.sinks.push(Endpoint {
+            name,
+            filter,
+            args: String::new(),
+        });
+        self
+    }
+
+    fn endpoint_name(&mut self, name: String) -> String {
+        let name = self.label(name);
+        if self.sources.iter().chain(&self.sinks).any(|endpoint| endpoint.name == name) {
+            self.error.get_or_insert("source and sink names must be unique");
+        }
+
+        name
+    }
+
+    fn label(&mut self, label: String) -> String {
+        if label.is_empty() || label.contains(['[', ']']) {
+            self.error.get_or_insert("pad names must not be empty or contain brackets");
+        }
+
+        label
+    }
+
+    fn finish_chain(&mut self) {
+        self.chains.push(std::mem::take(&mut self.current));
+    }
+
+    /// True if the chain starts at an audio source, used to pick the pass through filter.
+    fn is_audio(&self, chain: &Chain) -> bool {
+        chain.inputs.first().is_some_and(|input| {
+            self.sources
+                .iter()
+                .any(|source| &source.name == input && source.filter == "abuffer")
+        })
+    }
+}
+
+/// A filter graph created by a [`FilterGraphBuilder`], with its buffer sources and sinks.
+pub struct BuiltFilterGraph {
+    graph: FilterGraph,
+    sources: Vec<String>,
+    sinks: Vec<String>,
+}
+
+impl std::fmt::Debug for BuiltFilterGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuiltFilterGraph")
+            .field("sources", &self.sources)
+            .field("sinks", &self.sinks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BuiltFilterGraph {
+    /// Returns the source named `name`, to send frames to.
+    pub fn source(&mut self, name: &str) -> Option<FilterContextSource<'_>> {
+        if !self.sources.iter().any(|source| source == name) {
+            return None;
+        }
+
+        self.graph.get(name).map(|context| context.source())
+    }
+
+    /// Returns the sink named `name`, to receive frames from.
+    pub fn sink(&mut self, name: &str) -> Option<FilterContextSink<'_>> {
+        if !self.sinks.iter().any(|sink| sink == name) {
+            return None;
+        }
+
+        self.graph.get(name).map(|context| context.sink())
+    }
+
+    /// Returns the names of the sources, in the order they were added.
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().map(String::as_str)
+    }
+
+    /// Returns the names of the sinks, in the order they were added.
+    pub fn sinks(&self) -> impl Iterator<Item = &str> {
+        self.sinks.iter().map(String::as_str)
+    }
+
+    /// Returns the underlying filter graph.
+    pub const fn graph(&self) -> &FilterGraph {
+        &self.graph
+    }
+
+    /// Returns the underlying filter graph mutably, to [`dump`](FilterGraph::dump) it for example.
+    pub const fn graph_mut(&mut self) -> &mut FilterGraph {
+        &mut self.graph
+    }
+
+    /// Consumes the graph, returning the underlying filter graph.
+    pub fn into_graph(self) -> FilterGraph {
+        self.graph
+    }
+}
+
+fn rational(value: Rational) -> String {
+    format!("{}/{}", value.numerator, value.denominator)
+}
+
+fn default_layout_name(channels: i32) -> Option<String> {
+    let layout = AudioChannelLayout::new(channels).ok()?;
+    let mut name = [0 as libc::c_char; 64];
+
+    // Safety: `av_channel_layout_describe` is safe to call with a valid layout, and writes at most `name.len()` bytes.
+    let ret = unsafe { av_channel_layout_describe(layout.as_ptr(), name.as_mut_ptr(), name.len()) };
+    if ret < 0 || ret as usize > name.len() {
+        return None;
+    }
+
+    // Safety: The name was written as a nul terminated c string.
+    unsafe { CStr::from_ptr(name.as_ptr()) }.to_str().ok().map(str::to_owned)
+}
+
+fn c_name(name: *const libc::c_char) -> Option<&'static str> {
+    if name.is_null() {
+        return None;
+    }
+
+    // Safety: format names are valid static c strings.
+    unsafe { CStr::from_ptr(name) }.to_str().ok()
+}
+
+/// Escapes an option value, first for the option parser and then for the filter graph parser.
+fn escape(value: &str, out: &mut String) {
+    let mut option = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::{AudioSourceArgs, FilterGraphBuilder, VideoSourceArgs, escape};
+    use crate::error::FfmpegError;
+    use crate::frame::{AudioChannelLayout, AudioFrame, VideoFrame};
+    use crate::rational::Rational;
+    use crate::{AVPixelFormat, AVSampleFormat};
+
+    fn video_args(width: i32, height: i32) -> VideoSourceArgs {
+        VideoSourceArgs::builder()
+            .width(width)
+            .height(height)
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .time_base(Rational::static_new::<1, 30>())
+            .build()
+    }
+
+    #[test]
+    fn test_source_args() {
+        assert_eq!(
+            video_args(64, 48).args(),
+            "video_size=64x48:pix_fmt=0:time_base=1/30:pixel_aspect=1/1"
+        );
+
+        let args = VideoSourceArgs {
+            frame_rate: Some(30.into()),
+            ..video_args(64, 48)
+        };
+        assert!(args.args().ends_with(":frame_rate=30/1"));
+
+        let args = AudioSourceArgs::builder()
+            .sample_rate(48000)
+            .sample_format(AVSampleFormat::S16)
+            .channels(2)
+            .build();
+        assert_eq!(
+            args.args(),
+            "sample_rate=48000:sample_fmt=1:channel_layout=stereo:time_base=1/48000"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut out = String::new();
+        escape("plain", &mut out);
+        assert_eq!(out, "plain");
+
+        let mut out = String::new();
+        escape("a:b,c", &mut out);
+        assert_eq!(out, r"a\\\:b\,c");
+    }
+
+    #[test]
+    fn test_filter_graph_builder_spec() {
+        let builder = FilterGraphBuilder::new()
+            .input("logo")
+            .scale(64, -1)
+            .output("small")
+            .input("main")
+            .input("small")
+            .overlay(16, 8)
+            .fps(30)
+            .format(AVPixelFormat::Yuv420p)
+            .output("out");
+        assert_eq!(
+            builder.spec(),
+            "[logo]scale=w=64:h=-1[small];[main][small]overlay=x=16:y=8,fps=fps=30/1,format=pix_fmts=yuv420p[out]"
+        );
+
+        let builder = FilterGraphBuilder::new()
+            .scale(32, 24)
+            .output("a")
+            .crop(16, 16, 0, 0)
+            .filter("drawtext", [("text", "a:b")]);
+        assert_eq!(
+            builder.spec(),
+            r"scale=w=32:h=24[a];crop=w=16:h=16:x=0:y=0,drawtext=text=a\\\:b"
+        );
+
+        // A chain without filters passes its input through.
+        let builder = FilterGraphBuilder::new().input("in").output("out");
+        assert_eq!(builder.spec(), "[in]null[out]");
+    }
+
+    #[test]
+    fn test_filter_graph_builder_invalid() {
+        let result = FilterGraphBuilder::new().video_sink("out").video_sink("out").build();
+        assert_eq!(
+            result.unwrap_err(),
+            FfmpegError::Arguments("source and sink names must be unique")
+        );
+
+        let result = FilterGraphBuilder::new().input("[in]").build();
+        assert_eq!(
+            result.unwrap_err(),
+            FfmpegError::Arguments("pad names must not be empty or contain brackets")
+        );
+
+        let result = FilterGraphBuilder::new().filter("scale=1:1,null", []).build();
+        assert!(matches!(result.unwrap_err(), FfmpegError::Arguments(_)));
+
+        let result = FilterGraphBuilder::new().format(AVPixelFormat::None).build();
+        assert_eq!(result.unwrap_err(), FfmpegError::Arguments("unknown pixel format"));
+    }
+
+    #[test]
+    fn test_filter_graph_builder_video() {
+        let mut graph = FilterGraphBuilder::new()
+            .video_source("in", video_args(64, 48))
+            .video_sink("out")
+            .scale(32, 24)
+            .build()
+            .expect("Failed to build filter graph");
+
+        assert_eq!(graph.sources().collect::<Vec<_>>(), ["in"]);
+        assert_eq!(graph.sinks().collect::<Vec<_>>(), ["out"]);
+        assert!(graph.source("out").is_none());
+        assert!(graph.sink("in").is_none());
+
+        let frame = VideoFrame::builder()
+            .width(64)
+            .height(48)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .pts(0)
+            .build()
+            .expect("Failed to create frame");
+        graph
+            .source("in")
+            .expect("Missing source")
+            .send_frame(&frame)
+            .expect("Failed to send frame");
+        graph.source("in").expect("Missing source").send_eof(None).unwrap();
+
+        let mut sink = graph.sink("out").expect("Missing sink");
+        assert_eq!((sink.width(), sink.height()), (32, 24));
+
+        let frame = sink
+            .receive_frame()
+            .expect("Failed to receive frame")
+            .expect("No frame")
+            .video();
+        assert_eq!((frame.width(), frame.height()), (32, 24));
+    }
+
+    #[test]
+    fn test_filter_graph_builder_overlay() {
+        let mut graph = FilterGraphBuilder::new()
+            .video_source("main", video_args(64, 48))
+            .video_source("logo", video_args(32, 32))
+            .video_sink("out")
+            .input("logo")
+            .scale(8, 8)
+            .output("small")
+            .input("main")
+            .input("small")
+            .overlay(4, 4)
+            .fps(30)
+            .output("out")
+            .build()
+            .expect("Failed to build filter graph");
+
+        let sink = graph.sink("out").expect("Missing sink");
+        assert_eq!((sink.width(), sink.height()), (64, 48));
+        assert_eq!(sink.frame_rate(), Rational::from(30));
+    }
+
+    #[test]
+    fn test_filter_graph_builder_audio() {
+        let mut graph = FilterGraphBuilder::new()
+            .audio_source(
+                "in",
+                AudioSourceArgs::builder()
+                    .sample_rate(44100)
+                    .sample_format(AVSampleFormat::S16)
+                    .channels(2)
+                    .build(),
+            )
+            .audio_sink("out")
+            .aresample(48000)
+            .aformat(AVSampleFormat::Fltp)
+            .build()
+            .expect("Failed to build filter graph");
+
+        let frame = AudioFrame::builder()
+            .sample_fmt(AVSampleFormat::S16)
+            .nb_samples(1024)
+            .sample_rate(44100)
+            .channel_layout(AudioChannelLayout::new(2).expect("Failed to create channel layout"))
+            .build()
+            .expect("Failed to create frame");
+        graph
+            .source("in")
+            .expect("Missing source")
+            .send_frame(&frame)
+            .expect("Failed to send frame");
+
+        let sink = graph.sink("out").expect("Missing sink");
+        assert_eq!(sink.sample_rate(), 48000);
+        assert_eq!(sink.format(), AVSampleFormat::Fltp.0);
+        assert_eq!(sink.channels(), 2);
+    }
+}
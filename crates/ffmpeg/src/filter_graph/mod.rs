@@ -4,8 +4,13 @@ use std::ptr::NonNull;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::frame::GenericFrame;
+use crate::rational::Rational;
 use crate::smart_object::SmartPtr;
 
+mod builder;
+
+pub use builder::{AudioSourceArgs, BuiltFilterGraph, FilterGraphBuilder, VideoSourceArgs};
+
 /// A filter graph. Used to chain filters together when transforming media data.
 pub struct FilterGraph(SmartPtr<AVFilterGraph>);
 
@@ -315,6 +320,65 @@ impl FilterContextSink<'_> {
             code => Err(FfmpegError::Code(code)),
         }
     }
+
+    /// Returns the time base of the frames produced by the sink.
+    ///
+    /// This and the other properties of the sink are only known once the filter
+    /// graph has been configured with [`FilterGraph::validate`].
+    pub fn time_base(&self) -> Rational {
+        // Safety: `av_buffersink_get_time_base` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_time_base(&*self.0) }.into()
+    }
+
+    /// Returns the format of the frames produced by the sink, an [`AVPixelFormat`](crate::AVPixelFormat)
+    /// for video and an [`AVSampleFormat`](crate::AVSampleFormat) for audio.
+    pub fn format(&self) -> i32 {
+        // Safety: `av_buffersink_get_format` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_format(&*self.0) }
+    }
+
+    /// Returns the width of the video frames produced by the sink.
+    pub fn width(&self) -> i32 {
+        // Safety: `av_buffersink_get_w` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_w(&*self.0) }
+    }
+
+    /// Returns the height of the video frames produced by the sink.
+    pub fn height(&self) -> i32 {
+        // Safety: `av_buffersink_get_h` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_h(&*self.0) }
+    }
+
+    /// Returns the frame rate of the video frames produced by the sink.
+    pub fn frame_rate(&self) -> Rational {
+        // Safety: `av_buffersink_get_frame_rate` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_frame_rate(&*self.0) }.into()
+    }
+
+    /// Returns the sample aspect ratio of the video frames produced by the sink.
+    pub fn sample_aspect_ratio(&self) -> Rational {
+        // Safety: `av_buffersink_get_sample_aspect_ratio` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_sample_aspect_ratio(&*self.0) }.into()
+    }
+
+    /// Returns the sample rate of the audio frames produced by the sink.
+    pub fn sample_rate(&self) -> i32 {
+        // Safety: `av_buffersink_get_sample_rate` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_sample_rate(&*self.0) }
+    }
+
+    /// Returns the number of channels of the audio frames produced by the sink.
+    pub fn channels(&self) -> i32 {
+        // Safety: `av_buffersink_get_channels` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_get_channels(&*self.0) }
+    }
+
+    /// Makes the sink return audio frames with exactly `frame_size` samples,
+    /// except for the last one. Needed for encoders with a fixed frame size.
+    pub fn set_frame_size(&mut self, frame_size: u32) {
+        // Safety: `av_buffersink_set_frame_size` is safe to call, `self.0` is a valid pointer.
+        unsafe { av_buffersink_set_frame_size(self.0, frame_size) };
+    }
 }
 
 #[cfg(test)]
@@ -4,10 +4,18 @@ use std::ptr::NonNull;
 use crate::consts::{Const, Mut};
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
+use crate::hwdevice::HwFramesContext;
 use crate::rational::Rational;
+use crate::side_data::{
+    A53_CC_TRIPLET_SIZE, ContentLightLevel, DisplayMatrix, MasteringDisplayMetadata, SEI_UNREGISTERED_UUID_SIZE,
+    SmpteTimecode, read_side_data, write_side_data,
+};
 use crate::smart_object::{SmartObject, SmartPtr};
 use crate::utils::{check_i64, or_nopts};
-use crate::{AVPictureType, AVPixelFormat, AVSampleFormat};
+use crate::{
+    AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVFrameSideDataType, AVPictureType,
+    AVPixelFormat, AVSampleFormat,
+};
 
 /// Wrapper around the data buffers of AVFrame that handles bottom-to-top line iteration
 #[derive(Debug, PartialEq)]
@@ -15,6 +23,7 @@ pub struct FrameData {
     // this may point to the start of the last line of the buffer
     ptr: NonNull<u8>,
     linesize: i32,
+    width: i32,
     height: i32,
 }
 
@@ -69,6 +78,12 @@ impl core::ops::IndexMut<usize> for FrameData {
 }
 
 impl FrameData {
+    /// Returns the number of bytes per row that hold pixel data.
+    /// This may be less than the linesize, which includes alignment padding.
+    pub const fn width(&self) -> i32 {
+        self.width
+    }
+
     /// Returns the height of the underlying data, in bytes
     pub const fn height(&self) -> i32 {
         self.height
@@ -293,6 +308,199 @@ impl GenericFrame {
         }
         Some(self.0.as_deref_except().linesize[index])
     }
+
+    /// Returns the first side data of the given type attached to the frame.
+    pub fn side_data(&self, side_data_type: AVFrameSideDataType) -> Option<&[u8]> {
+        // Safety: `self.as_ptr()` is a valid pointer to an `AVFrame`.
+        let side_data = unsafe { av_frame_get_side_data(self.as_ptr(), side_data_type.0 as _) };
+        // Safety: `av_frame_get_side_data` returns either null or a valid pointer owned by the frame.
+        let side_data = unsafe { side_data.as_ref() }?;
+
+        if side_data.data.is_null() || side_data.size == 0 {
+            return Some(&[]);
+        }
+
+        // Safety: `data` is valid for `size` bytes and lives as long as the frame.
+        Some(unsafe { std::slice::from_raw_parts(side_data.data, side_data.size as usize) })
+    }
+
+    /// Returns all side data attached to the frame, in the order it was added.
+    pub fn all_side_data(&self) -> impl Iterator<Item = (AVFrameSideDataType, &[u8])> {
+        let frame = self.0.as_deref_except();
+        let entries = if frame.side_data.is_null() || frame.nb_side_data <= 0 {
+            &[][..]
+        } else {
+            // Safety: `side_data` points to `nb_side_data` side data pointers owned by the frame.
+            unsafe { std::slice::from_raw_parts(frame.side_data, frame.nb_side_data as usize) }
+        };
+
+        entries.iter().filter_map(|side_data| {
+            // Safety: Each side data pointer is either null or valid for as long as the frame.
+            let side_data = unsafe { side_data.as_ref() }?;
+            let data = if side_data.data.is_null() || side_data.size == 0 {
+                &[][..]
+            } else {
+                // Safety: `data` is valid for `size` bytes and lives as long as the frame.
+                unsafe { std::slice::from_raw_parts(side_data.data, side_data.size as usize) }
+            };
+
+            Some((AVFrameSideDataType(side_data.type_ as _), data))
+        })
+    }
+
+    /// Allocates `size` zeroed bytes of side data of the given type and returns them to be filled in.
+    ///
+    /// Existing side data of the same type is kept, use [`GenericFrame::remove_side_data`] first to replace it.
+    pub fn new_side_data(&mut self, side_data_type: AVFrameSideDataType, size: usize) -> Result<&mut [u8], FfmpegError> {
+        // Safety: `self.as_mut_ptr()` is a valid pointer to an `AVFrame`.
+        let side_data = unsafe { av_frame_new_side_data(self.as_mut_ptr(), side_data_type.0 as _, size as _) };
+        // Safety: `av_frame_new_side_data` returns either null or a valid pointer owned by the frame.
+        let side_data = unsafe { side_data.as_mut() }.ok_or(FfmpegError::Alloc)?;
+
+        if size == 0 || side_data.data.is_null() {
+            return Ok(&mut []);
+        }
+
+        // Safety: `av_frame_new_side_data` allocated `size` bytes, which live as long as the frame.
+        let data = unsafe { std::slice::from_raw_parts_mut(side_data.data, size) };
+        data.fill(0);
+        Ok(data)
+    }
+
+    /// Attaches a copy of `data` as side data of the given type to the frame.
+    ///
+    /// Existing side data of the same type is kept, use [`GenericFrame::remove_side_data`] first to replace it.
+    /// The side data is passed to the encoder along with the frame. Whether it ends up in the
+    /// bitstream depends on the encoder and its options.
+    pub fn add_side_data(&mut self, side_data_type: AVFrameSideDataType, data: &[u8]) -> Result<(), FfmpegError> {
+        self.new_side_data(side_data_type, data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Replaces the side data of the given type with a single `T`.
+    fn set_side_data_value<T: Copy>(&mut self, side_data_type: AVFrameSideDataType, value: T) -> Result<(), FfmpegError> {
+        self.remove_side_data(side_data_type);
+        write_side_data(self.new_side_data(side_data_type, std::mem::size_of::<T>())?, value);
+        Ok(())
+    }
+
+    /// Removes all side data from the frame.
+    pub fn clear_side_data(&mut self) {
+        let types: Vec<_> = self.all_side_data().map(|(side_data_type, _)| side_data_type).collect();
+        for side_data_type in types {
+            self.remove_side_data(side_data_type);
+        }
+    }
+
+    /// Replaces the side data of the frame with the side data of `other`.
+    ///
+    /// The side data buffers are shared, not copied. Use this to carry metadata such as
+    /// HDR10 mastering display metadata over to frames created from `other`.
+    pub fn copy_side_data(&mut self, other: &GenericFrame) -> Result<(), FfmpegError> {
+        self.clear_side_data();
+
+        let other = other.0.as_deref_except();
+        if other.side_data.is_null() || other.nb_side_data <= 0 {
+            return Ok(());
+        }
+
+        // Safety: `side_data` points to `nb_side_data` side data pointers owned by the other frame.
+        let entries = unsafe { std::slice::from_raw_parts(other.side_data, other.nb_side_data as usize) };
+        for side_data in entries {
+            // Safety: Each side data pointer is either null or valid for as long as the other frame.
+            let Some(side_data) = (unsafe { side_data.as_ref() }) else {
+                continue;
+            };
+
+            // Safety: `av_buffer_ref` is safe to call with a valid buffer.
+            let mut buf = unsafe { av_buffer_ref(side_data.buf) };
+            if buf.is_null() {
+                return Err(FfmpegError::Alloc);
+            }
+
+            // Safety: `self.as_mut_ptr()` is a valid frame, the frame takes ownership of `buf` on success.
+            let new = unsafe { av_frame_new_side_data_from_buf(self.as_mut_ptr(), side_data.type_, buf) };
+            if new.is_null() {
+                // Safety: The buffer is still ours when attaching it failed.
+                unsafe { av_buffer_unref(&mut buf) };
+                return Err(FfmpegError::Alloc);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes all side data of the given type from the frame.
+    pub fn remove_side_data(&mut self, side_data_type: AVFrameSideDataType) {
+        // Safety: `self.as_mut_ptr()` is a valid pointer to an `AVFrame`.
+        unsafe { av_frame_remove_side_data(self.as_mut_ptr(), side_data_type.0 as _) };
+    }
+
+    /// Sets the SMPTE 12-1 timecode of the frame, for encoders that insert a time code SEI.
+    ///
+    /// `rate` is the frame rate the timecode counts frames in.
+    pub fn set_s12m_timecode(&mut self, timecode: SmpteTimecode, rate: impl Into<Rational>) -> Result<(), FfmpegError> {
+        // The side data is an array of 4 u32, the number of timecodes followed by up to 3 timecodes.
+        let mut data = [0u8; 16];
+        data[0..4].copy_from_slice(&1u32.to_ne_bytes());
+        data[4..8].copy_from_slice(&timecode.to_s12m(rate.into()).to_ne_bytes());
+
+        self.remove_side_data(AVFrameSideDataType::S12mTimecode);
+        self.add_side_data(AVFrameSideDataType::S12mTimecode, &data)
+    }
+
+    /// Returns the first SMPTE 12-1 timecode attached to the frame.
+    pub fn s12m_timecode(&self, rate: impl Into<Rational>) -> Option<SmpteTimecode> {
+        let data = self.side_data(AVFrameSideDataType::S12mTimecode)?;
+        if data.len() < 8 || u32::from_ne_bytes(data[0..4].try_into().unwrap()) == 0 {
+            return None;
+        }
+
+        let tc = u32::from_ne_bytes(data[4..8].try_into().unwrap());
+        Some(SmpteTimecode::from_s12m(tc, rate.into()))
+    }
+
+    /// Sets the ATSC A53 closed caption data of the frame.
+    ///
+    /// `cc_data` is a list of 3 byte `cc_data` triplets carrying CEA-608 / CEA-708 captions.
+    /// `libx264` inserts them as a registered user data SEI when its `a53cc` option is enabled (the default).
+    pub fn set_a53_captions(&mut self, cc_data: &[u8]) -> Result<(), FfmpegError> {
+        if !cc_data.len().is_multiple_of(A53_CC_TRIPLET_SIZE) {
+            return Err(FfmpegError::Arguments("caption data must be made of 3 byte triplets"));
+        }
+
+        self.remove_side_data(AVFrameSideDataType::A53Cc);
+        self.add_side_data(AVFrameSideDataType::A53Cc, cc_data)
+    }
+
+    /// Returns the ATSC A53 closed caption data of the frame, a list of 3 byte `cc_data` triplets.
+    ///
+    /// Decoders attach the captions they find in the bitstream.
+    pub fn a53_captions(&self) -> Option<&[u8]> {
+        self.side_data(AVFrameSideDataType::A53Cc)
+    }
+
+    /// Adds a user data unregistered SEI message to the frame.
+    ///
+    /// Multiple messages can be added to the same frame.
+    /// `libx264` inserts them when its `udu_sei` option is enabled.
+    pub fn add_sei_unregistered(
+        &mut self,
+        uuid: [u8; SEI_UNREGISTERED_UUID_SIZE],
+        payload: &[u8],
+    ) -> Result<(), FfmpegError> {
+        let mut data = Vec::with_capacity(SEI_UNREGISTERED_UUID_SIZE + payload.len());
+        data.extend_from_slice(&uuid);
+        data.extend_from_slice(payload);
+
+        self.add_side_data(AVFrameSideDataType::SeiUnregistered, &data)
+    }
+}
+
+impl AsRef<GenericFrame> for GenericFrame {
+    fn as_ref(&self) -> &GenericFrame {
+        self
+    }
 }
 
 impl std::fmt::Debug for GenericFrame {
@@ -311,6 +519,26 @@ impl std::fmt::Debug for GenericFrame {
 }
 
 #[bon::bon]
+/// The number of pixels to remove from each edge of a [`VideoFrame`], see [`VideoFrame::set_crop`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Crop {
+    /// Pixels to remove from the top.
+    pub top: usize,
+    /// Pixels to remove from the bottom.
+    pub bottom: usize,
+    /// Pixels to remove from the left.
+    pub left: usize,
+    /// Pixels to remove from the right.
+    pub right: usize,
+}
+
+impl Crop {
+    /// Returns true if nothing is cropped.
+    pub const fn is_empty(&self) -> bool {
+        self.top == 0 && self.bottom == 0 && self.left == 0 && self.right == 0
+    }
+}
+
 impl VideoFrame {
     /// Creates a new [`VideoFrame`]
     #[builder]
@@ -364,6 +592,90 @@ impl VideoFrame {
         self.0.0.as_deref_except().height as usize
     }
 
+    /// Returns the area of the frame that is still to be cropped away.
+    ///
+    /// Set by decoders opened with [`apply_cropping`](crate::decoder::DecoderOptions::apply_cropping)
+    /// disabled, from the cropping in the bitstream (such as the H.264 frame cropping).
+    pub const fn crop(&self) -> Crop {
+        let inner = self.0.0.as_deref_except();
+        Crop {
+            top: inner.crop_top,
+            bottom: inner.crop_bottom,
+            left: inner.crop_left,
+            right: inner.crop_right,
+        }
+    }
+
+    /// Sets the area of the frame to crop away, applied by [`VideoFrame::apply_cropping`].
+    ///
+    /// Encoders ignore the crop, so it has to be applied before the frame is sent to one.
+    /// Returns an error if the crop does not leave at least one pixel in each direction.
+    pub fn set_crop(&mut self, crop: Crop) -> Result<(), FfmpegError> {
+        let fits = |start: usize, end: usize, size: usize| start.checked_add(end).is_some_and(|total| total < size);
+        if !fits(crop.top, crop.bottom, self.height()) || !fits(crop.left, crop.right, self.width()) {
+            return Err(FfmpegError::Arguments("crop must leave at least one pixel"));
+        }
+
+        let inner = self.0.0.as_deref_mut_except();
+        inner.crop_top = crop.top;
+        inner.crop_bottom = crop.bottom;
+        inner.crop_left = crop.left;
+        inner.crop_right = crop.right;
+        Ok(())
+    }
+
+    /// Applies the crop set with [`VideoFrame::set_crop`] or by the decoder, without copying the data.
+    ///
+    /// Only the data pointers, width and height of the frame are changed, so the
+    /// frame still shares its buffers with the decoder. The data pointers of
+    /// the frame stay aligned unless `unaligned` is set, which may leave part of
+    /// the left crop in place, see [`VideoFrame::crop`]. With `unaligned` set the
+    /// crop is always applied exactly, but encoders and filters that rely on
+    /// aligned data may become slower. Hardware and bitstream formats only support
+    /// cropping the bottom and right edges.
+    pub fn apply_cropping(&mut self, unaligned: bool) -> Result<(), FfmpegError> {
+        let flags = if unaligned { AV_FRAME_CROP_UNALIGNED as i32 } else { 0 };
+
+        // Safety: `av_frame_apply_cropping` is safe to call with a valid frame.
+        FfmpegErrorCode(unsafe { av_frame_apply_cropping(self.as_mut_ptr(), flags) }).result()?;
+        Ok(())
+    }
+
+    /// Returns the mastering display metadata of the frame, part of the HDR10 metadata.
+    pub fn mastering_display_metadata(&self) -> Option<MasteringDisplayMetadata> {
+        let data = self.side_data(AVFrameSideDataType::MasteringDisplayMetadata)?;
+        read_side_data::<AVMasteringDisplayMetadata>(data).map(Into::into)
+    }
+
+    /// Sets the mastering display metadata of the frame, replacing any existing one.
+    pub fn set_mastering_display_metadata(&mut self, metadata: MasteringDisplayMetadata) -> Result<(), FfmpegError> {
+        self.set_side_data_value(
+            AVFrameSideDataType::MasteringDisplayMetadata,
+            AVMasteringDisplayMetadata::from(metadata),
+        )
+    }
+
+    /// Returns the content light level of the frame, part of the HDR10 metadata.
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        let data = self.side_data(AVFrameSideDataType::ContentLightLevel)?;
+        read_side_data::<AVContentLightMetadata>(data).map(Into::into)
+    }
+
+    /// Sets the content light level of the frame, replacing any existing one.
+    pub fn set_content_light_level(&mut self, level: ContentLightLevel) -> Result<(), FfmpegError> {
+        self.set_side_data_value(AVFrameSideDataType::ContentLightLevel, AVContentLightMetadata::from(level))
+    }
+
+    /// Returns the display matrix of the frame, which describes how the frame should be rotated for display.
+    pub fn display_matrix(&self) -> Option<DisplayMatrix> {
+        read_side_data::<[i32; 9]>(self.side_data(AVFrameSideDataType::DisplayMatrix)?).map(DisplayMatrix)
+    }
+
+    /// Sets the display matrix of the frame, replacing any existing one.
+    pub fn set_display_matrix(&mut self, matrix: DisplayMatrix) -> Result<(), FfmpegError> {
+        self.set_side_data_value(AVFrameSideDataType::DisplayMatrix, matrix.0)
+    }
+
     /// Returns the sample aspect ratio of the frame.
     pub fn sample_aspect_ratio(&self) -> Rational {
         self.0.0.as_deref_except().sample_aspect_ratio.into()
@@ -389,66 +701,197 @@ impl VideoFrame {
         self.0.0.as_deref_mut_except().pict_type = pict_type.0 as _;
     }
 
-    /// Returns a reference to the data of the frame. By specifying the index of the plane.
-    pub fn data(&self, index: usize) -> Option<Const<FrameData, '_>> {
-        // Safety: av_pix_fmt_desc_get is safe to call
-        let descriptor = unsafe { rusty_ffmpeg::ffi::av_pix_fmt_desc_get(self.format().into()) };
-        // Safety: as_ref is safe to call here
-        let descriptor = unsafe { descriptor.as_ref()? };
-
-        let line = self.linesize(index)?;
-        let height = {
-            // palette data
-            if descriptor.flags & rusty_ffmpeg::ffi::AV_PIX_FMT_FLAG_PAL as u64 != 0 && index == 1 {
-                1
-            } else if index > 0 {
-                self.height() >> descriptor.log2_chroma_h
-            } else {
-                self.height()
-            }
+    /// Returns the color primaries of the frame.
+    pub const fn color_primaries(&self) -> AVColorPrimaries {
+        AVColorPrimaries(self.0.0.as_deref_except().color_primaries as _)
+    }
+
+    /// Sets the color primaries of the frame.
+    pub const fn set_color_primaries(&mut self, color_primaries: AVColorPrimaries) {
+        self.0.0.as_deref_mut_except().color_primaries = color_primaries.0 as _;
+    }
+
+    /// Returns the color transfer characteristic of the frame.
+    pub const fn color_trc(&self) -> AVColorTransferCharacteristic {
+        AVColorTransferCharacteristic(self.0.0.as_deref_except().color_trc as _)
+    }
+
+    /// Sets the color transfer characteristic of the frame.
+    pub const fn set_color_trc(&mut self, color_trc: AVColorTransferCharacteristic) {
+        self.0.0.as_deref_mut_except().color_trc = color_trc.0 as _;
+    }
+
+    /// Returns the color space of the frame.
+    pub const fn colorspace(&self) -> AVColorSpace {
+        AVColorSpace(self.0.0.as_deref_except().colorspace as _)
+    }
+
+    /// Sets the color space of the frame.
+    pub const fn set_colorspace(&mut self, colorspace: AVColorSpace) {
+        self.0.0.as_deref_mut_except().colorspace = colorspace.0 as _;
+    }
+
+    /// Returns the color range of the frame.
+    pub const fn color_range(&self) -> AVColorRange {
+        AVColorRange(self.0.0.as_deref_except().color_range as _)
+    }
+
+    /// Sets the color range of the frame.
+    pub const fn set_color_range(&mut self, color_range: AVColorRange) {
+        self.0.0.as_deref_mut_except().color_range = color_range.0 as _;
+    }
+
+    /// Returns the number of data planes of the frame, including the palette of paletted formats.
+    ///
+    /// Hardware frames have no planes.
+    pub fn plane_count(&self) -> usize {
+        let Some(descriptor) = self.descriptor() else {
+            return 0;
         };
 
-        let raw = NonNull::new(*(self.0.0.as_deref_except().data.get(index)?))?;
+        if descriptor.flags & AV_PIX_FMT_FLAG_HWACCEL as u64 != 0 {
+            return 0;
+        }
 
-        Some(Const::new(FrameData {
-            ptr: raw,
-            linesize: line,
-            height: height as i32,
-        }))
+        // Safety: av_pix_fmt_count_planes is safe to call with any pixel format
+        let planes = unsafe { av_pix_fmt_count_planes(self.format().into()) }.max(0) as usize;
+
+        // The palette is stored in its own plane, after the indices.
+        if descriptor.flags & AV_PIX_FMT_FLAG_PAL as u64 != 0 {
+            planes + 1
+        } else {
+            planes
+        }
+    }
+
+    /// Returns a reference to the data of the frame. By specifying the index of the plane.
+    ///
+    /// Returns `None` if the pixel format has no plane at `index`.
+    pub fn data(&self, index: usize) -> Option<Const<'_, FrameData>> {
+        self.plane(index).map(Const::new)
     }
 
     /// Returns a mutable reference to the data of the frame. By specifying the index of the plane.
-    pub fn data_mut(&mut self, index: usize) -> Option<Mut<FrameData, '_>> {
+    ///
+    /// Returns `None` if the pixel format has no plane at `index`.
+    pub fn data_mut(&mut self, index: usize) -> Option<Mut<'_, FrameData>> {
+        self.plane(index).map(Mut::new)
+    }
+
+    /// Returns an iterator over the data planes of the frame.
+    ///
+    /// Each plane is sized according to the pixel format, so chroma planes
+    /// of subsampled formats have fewer rows and bytes per row than the luma plane.
+    pub fn planes(&self) -> impl Iterator<Item = Const<'_, FrameData>> {
+        (0..self.plane_count()).filter_map(|index| self.data(index))
+    }
+
+    /// Returns an iterator over the mutable data planes of the frame, see [`VideoFrame::planes`].
+    pub fn planes_mut(&mut self) -> impl Iterator<Item = Mut<'_, FrameData>> {
+        let planes = (0..self.plane_count())
+            .filter_map(|index| self.plane(index))
+            .collect::<Vec<_>>();
+        planes.into_iter().map(Mut::new)
+    }
+
+    fn descriptor(&self) -> Option<&'static AVPixFmtDescriptor> {
         // Safety: av_pix_fmt_desc_get is safe to call
-        let descriptor = unsafe { rusty_ffmpeg::ffi::av_pix_fmt_desc_get(self.format().into()) };
-        // Safety: as_ref is safe to call here
-        let descriptor = unsafe { descriptor.as_ref()? };
-
-        let line = self.linesize(index)?;
-        let height = {
-            // palette data
-            if descriptor.flags & rusty_ffmpeg::ffi::AV_PIX_FMT_FLAG_PAL as u64 != 0 && index == 1 {
-                1
-            } else if index > 0 {
-                self.height() >> descriptor.log2_chroma_h
-            } else {
-                self.height()
-            }
+        let descriptor = unsafe { av_pix_fmt_desc_get(self.format().into()) };
+        // Safety: the descriptor is either null or points to a static descriptor
+        unsafe { descriptor.as_ref() }
+    }
+
+    /// Builds the view of plane `index`, the caller ties it to the lifetime of the frame.
+    fn plane(&self, index: usize) -> Option<FrameData> {
+        if index >= self.plane_count() {
+            return None;
+        }
+
+        let descriptor = self.descriptor()?;
+        let height = self.height() as i32;
+        let (width, height) = if descriptor.flags & AV_PIX_FMT_FLAG_PAL as u64 != 0 && index == 1 {
+            // 256 RGBA entries
+            (256 * 4, 1)
+        } else {
+            // Safety: av_image_get_linesize is safe to call with any pixel format and plane
+            let width = unsafe { av_image_get_linesize(self.format().into(), self.width() as i32, index as i32) };
+            // Planes 1 and 2 are the chroma planes, the alpha plane is not subsampled.
+            // Rounds up like `AV_CEIL_RSHIFT`, so the last row of odd heights is included.
+            let height = match index {
+                1 | 2 => -((-height) >> descriptor.log2_chroma_h),
+                _ => height,
+            };
+            (width, height)
         };
 
+        let linesize = self.linesize(index)?;
+        if width < 0 || linesize.unsigned_abs() < width as u32 {
+            return None;
+        }
+
         let raw = NonNull::new(*(self.0.0.as_deref_except().data.get(index)?))?;
 
-        Some(Mut::new(FrameData {
+        Some(FrameData {
             ptr: raw,
-            linesize: line,
-            height: height as i32,
-        }))
+            linesize,
+            width,
+            height,
+        })
     }
 
     /// Get the pixel format of the frame.
     pub const fn format(&self) -> AVPixelFormat {
         AVPixelFormat(self.0.0.as_deref_except().format)
     }
+
+    /// Returns true if the data of the frame is in the memory of a hardware device.
+    pub const fn is_hardware(&self) -> bool {
+        !self.0.0.as_deref_except().hw_frames_ctx.is_null()
+    }
+
+    /// Downloads a hardware frame into a new frame in system memory.
+    ///
+    /// `format` selects the pixel format of the new frame, `None` uses the format of the
+    /// data in hardware memory. The timestamps and other properties are copied.
+    pub fn transfer_to_software(&self, format: Option<AVPixelFormat>) -> Result<VideoFrame, FfmpegError> {
+        if !self.is_hardware() {
+            return Err(FfmpegError::Arguments("frame is not a hardware frame"));
+        }
+
+        let mut frame = GenericFrame::new()?;
+        if let Some(format) = format {
+            frame.0.as_deref_mut_except().format = format.into();
+        }
+
+        // Safety: `av_hwframe_transfer_data` is safe to call with an empty destination and a hardware source frame.
+        FfmpegErrorCode(unsafe { av_hwframe_transfer_data(frame.as_mut_ptr(), self.as_ptr(), 0) }).result()?;
+
+        // Safety: `av_frame_copy_props` is safe to call with two valid frames.
+        FfmpegErrorCode(unsafe { av_frame_copy_props(frame.as_mut_ptr(), self.as_ptr()) }).result()?;
+
+        Ok(frame.video())
+    }
+
+    /// Uploads the frame into a new frame from the pool `frames`.
+    ///
+    /// The frame must have the size of the pool and be in a format the device can upload,
+    /// usually the [`sw_format`](HwFramesContext::sw_format) of the pool. The timestamps and
+    /// other properties are copied.
+    pub fn transfer_to_hardware(&self, frames: &HwFramesContext) -> Result<VideoFrame, FfmpegError> {
+        if self.is_hardware() {
+            return Err(FfmpegError::Arguments("frame is already a hardware frame"));
+        }
+
+        let mut frame = frames.get_buffer()?;
+
+        // Safety: `av_hwframe_transfer_data` is safe to call with a hardware destination and a software source frame.
+        FfmpegErrorCode(unsafe { av_hwframe_transfer_data(frame.as_mut_ptr(), self.as_ptr(), 0) }).result()?;
+
+        // Safety: `av_frame_copy_props` is safe to call with two valid frames.
+        FfmpegErrorCode(unsafe { av_frame_copy_props(frame.as_mut_ptr(), self.as_ptr()) }).result()?;
+
+        Ok(frame)
+    }
 }
 
 impl std::fmt::Debug for VideoFrame {
@@ -484,6 +927,18 @@ impl std::ops::DerefMut for VideoFrame {
     }
 }
 
+impl AsRef<GenericFrame> for VideoFrame {
+    fn as_ref(&self) -> &GenericFrame {
+        &self.0
+    }
+}
+
+impl From<VideoFrame> for GenericFrame {
+    fn from(frame: VideoFrame) -> Self {
+        frame.0
+    }
+}
+
 /// A thin wrapper around `AVChannelLayout` to make it easier to use.
 pub struct AudioChannelLayout(SmartObject<AVChannelLayout>);
 
@@ -632,6 +1087,39 @@ impl AudioFrame {
         self.0.0.as_deref_mut_except().sample_rate = sample_rate as i32;
     }
 
+    /// Returns the sample format of the frame.
+    pub const fn sample_format(&self) -> AVSampleFormat {
+        AVSampleFormat(self.0.0.as_deref_except().format)
+    }
+
+    /// Creates a silent frame of `nb_samples` samples, with the channel layout,
+    /// sample format, sample rate and time base of this frame.
+    ///
+    /// The pts and duration of the new frame are not set.
+    pub fn silence_like(&self, nb_samples: i32) -> Result<AudioFrame, FfmpegError> {
+        let mut channel_layout = AudioChannelLayout::default();
+        // Safety: both layouts are valid, `av_channel_layout_copy` is safe to call.
+        FfmpegErrorCode(unsafe { av_channel_layout_copy(channel_layout.0.inner_mut(), &self.channel_layout()) }).result()?;
+
+        let mut frame = AudioFrame::builder()
+            .channel_layout(channel_layout)
+            .nb_samples(nb_samples)
+            .sample_fmt(self.sample_format())
+            .sample_rate(self.sample_rate())
+            .time_base(self.time_base())
+            .build()?;
+
+        let inner = frame.0.0.as_deref_mut_except();
+        // Safety: the buffers of the frame were allocated by the builder for `nb_samples` samples of
+        // `nb_channels` channels in the sample format, `av_samples_set_silence` is safe to call.
+        FfmpegErrorCode(unsafe {
+            av_samples_set_silence(inner.extended_data, 0, nb_samples, inner.ch_layout.nb_channels, inner.format)
+        })
+        .result()?;
+
+        Ok(frame)
+    }
+
     /// Returns a reference to the data of the frame. By specifying the index of the plane.
     pub fn data(&self, index: usize) -> Option<&[u8]> {
         let ptr = *self.0.0.as_deref_except().data.get(index)?;
@@ -703,6 +1191,18 @@ impl std::ops::DerefMut for AudioFrame {
     }
 }
 
+impl AsRef<GenericFrame> for AudioFrame {
+    fn as_ref(&self) -> &GenericFrame {
+        &self.0
+    }
+}
+
+impl From<AudioFrame> for GenericFrame {
+    fn from(frame: AudioFrame) -> Self {
+        frame.0
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
@@ -710,9 +1210,128 @@ mod tests {
     use rand::{Rng, rng};
 
     use super::FrameData;
-    use crate::frame::{AudioChannelLayout, AudioFrame, GenericFrame, VideoFrame};
+    use crate::frame::{AudioChannelLayout, AudioFrame, Crop, GenericFrame, VideoFrame};
     use crate::rational::Rational;
-    use crate::{AVChannelOrder, AVPictureType, AVPixelFormat, AVSampleFormat};
+    use crate::side_data::{
+        ContentLightLevel, DisplayMatrix, MasteringDisplayLuminance, MasteringDisplayMetadata, SmpteTimecode,
+    };
+    use crate::{
+        AVChannelOrder, AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVFrameSideDataType,
+        AVPictureType, AVPixelFormat, AVSampleFormat,
+    };
+
+    #[test]
+    fn test_frame_side_data() {
+        let mut frame = GenericFrame::new().expect("Failed to create frame");
+        assert_eq!(frame.side_data(AVFrameSideDataType::A53Cc), None);
+
+        frame
+            .set_a53_captions(&[0xfc, 0x94, 0x20, 0xfc, 0x94, 0x2c])
+            .expect("Failed to set captions");
+        assert_eq!(
+            frame.side_data(AVFrameSideDataType::A53Cc),
+            Some([0xfc, 0x94, 0x20, 0xfc, 0x94, 0x2c].as_slice())
+        );
+
+        // Setting captions again replaces the previous ones.
+        frame.set_a53_captions(&[0xfc, 0x80, 0x80]).expect("Failed to set captions");
+        assert_eq!(
+            frame.side_data(AVFrameSideDataType::A53Cc),
+            Some([0xfc, 0x80, 0x80].as_slice())
+        );
+        assert!(frame.set_a53_captions(&[0xfc, 0x80]).is_err());
+
+        frame.add_sei_unregistered([0xaa; 16], b"hello").expect("Failed to add SEI");
+        let sei = frame
+            .side_data(AVFrameSideDataType::SeiUnregistered)
+            .expect("SEI should be attached");
+        assert_eq!(&sei[..16], &[0xaa; 16]);
+        assert_eq!(&sei[16..], b"hello");
+
+        let rate = Rational::static_new::<25, 1>();
+        let timecode = SmpteTimecode::new(1, 2, 3, 4);
+        frame.set_s12m_timecode(timecode, rate).expect("Failed to set timecode");
+        assert_eq!(frame.s12m_timecode(rate), Some(timecode));
+
+        frame.remove_side_data(AVFrameSideDataType::S12mTimecode);
+        assert_eq!(frame.s12m_timecode(rate), None);
+        assert!(frame.side_data(AVFrameSideDataType::A53Cc).is_some());
+
+        // Side data is kept when cloning the frame.
+        let cloned = frame.clone();
+        assert_eq!(
+            cloned.side_data(AVFrameSideDataType::A53Cc),
+            Some([0xfc, 0x80, 0x80].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_video_frame_hdr_side_data() {
+        let mut frame = VideoFrame::builder()
+            .width(16)
+            .height(16)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create frame");
+        assert_eq!(frame.mastering_display_metadata(), None);
+        assert_eq!(frame.content_light_level(), None);
+        assert_eq!(frame.display_matrix(), None);
+
+        let metadata = MasteringDisplayMetadata {
+            primaries: None,
+            luminance: Some(MasteringDisplayLuminance {
+                min: Rational::new(1, 10000.try_into().unwrap()),
+                max: Rational::new(1000, 1.try_into().unwrap()),
+            }),
+        };
+        frame
+            .set_mastering_display_metadata(metadata)
+            .expect("Failed to set mastering display metadata");
+        assert_eq!(frame.mastering_display_metadata(), Some(metadata));
+
+        let level = ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        };
+        frame
+            .set_content_light_level(level)
+            .expect("Failed to set content light level");
+        frame
+            .set_content_light_level(level)
+            .expect("Failed to set content light level");
+        assert_eq!(frame.content_light_level(), Some(level));
+        assert_eq!(
+            frame
+                .all_side_data()
+                .filter(|(side_data_type, _)| *side_data_type == AVFrameSideDataType::ContentLightLevel)
+                .count(),
+            1,
+            "setting replaces the previous side data"
+        );
+
+        frame
+            .set_display_matrix(DisplayMatrix::from_rotation(-90.0))
+            .expect("Failed to set display matrix");
+        assert_eq!(frame.display_matrix().and_then(|matrix| matrix.rotation()), Some(-90.0));
+
+        // Frames created from another frame, such as by the scaler, can take over its side data.
+        let mut other = VideoFrame::builder()
+            .width(8)
+            .height(8)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create frame");
+        other.set_a53_captions(&[0xfc, 0x80, 0x80]).expect("Failed to set captions");
+        other.copy_side_data(&frame).expect("Failed to copy side data");
+        assert_eq!(other.a53_captions(), None);
+        assert_eq!(other.mastering_display_metadata(), Some(metadata));
+        assert_eq!(other.content_light_level(), Some(level));
+        assert_eq!(other.all_side_data().count(), 3);
+
+        other.clear_side_data();
+        assert_eq!(other.all_side_data().count(), 0);
+        assert_eq!(frame.all_side_data().count(), 3);
+    }
 
     #[test]
     fn test_frame_clone() {
@@ -829,6 +1448,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_color_metadata() {
+        let mut video_frame = GenericFrame::new().expect("Failed to create frame").video();
+        assert_eq!(video_frame.color_primaries(), AVColorPrimaries::Unspecified);
+        assert_eq!(video_frame.color_trc(), AVColorTransferCharacteristic::Unspecified);
+        assert_eq!(video_frame.colorspace(), AVColorSpace::Unspecified);
+        assert_eq!(video_frame.color_range(), AVColorRange::Unspecified);
+
+        video_frame.set_color_primaries(AVColorPrimaries::Bt2020);
+        video_frame.set_color_trc(AVColorTransferCharacteristic::Smpte2084);
+        video_frame.set_colorspace(AVColorSpace::Bt2020Ncl);
+        video_frame.set_color_range(AVColorRange::Mpeg);
+
+        assert_eq!(video_frame.color_primaries(), AVColorPrimaries::Bt2020);
+        assert_eq!(video_frame.color_trc(), AVColorTransferCharacteristic::Smpte2084);
+        assert_eq!(video_frame.colorspace(), AVColorSpace::Bt2020Ncl);
+        assert_eq!(video_frame.color_range(), AVColorRange::Mpeg);
+    }
+
     #[test]
     fn test_data_allocation_and_access() {
         let mut video_frame = VideoFrame::builder()
@@ -869,6 +1507,115 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_video_frame_planes() {
+        let plane_sizes = |pix_fmt: AVPixelFormat, width: i32, height: i32| {
+            let frame = VideoFrame::builder()
+                .width(width)
+                .height(height)
+                .pix_fmt(pix_fmt)
+                .build()
+                .expect("Failed to create VideoFrame");
+
+            assert!(frame.data(frame.plane_count()).is_none());
+            frame
+                .planes()
+                .map(|plane| {
+                    assert!(plane.linesize() >= plane.width());
+                    (plane.width(), plane.height())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Odd sizes round the chroma planes up, so the last column and row are included.
+        assert_eq!(plane_sizes(AVPixelFormat::Yuv420p, 15, 15), vec![(15, 15), (8, 8), (8, 8)]);
+        assert_eq!(plane_sizes(AVPixelFormat::Yuv422p, 15, 15), vec![(15, 15), (8, 15), (8, 15)]);
+        assert_eq!(
+            plane_sizes(AVPixelFormat::Yuv444p, 15, 15),
+            vec![(15, 15), (15, 15), (15, 15)]
+        );
+        assert_eq!(plane_sizes(AVPixelFormat::Gbrp, 4, 2), vec![(4, 2), (4, 2), (4, 2)]);
+        assert_eq!(plane_sizes(AVPixelFormat::Rgb24, 15, 3), vec![(45, 3)]);
+        assert_eq!(
+            plane_sizes(AVPixelFormat::Yuv420p16Le, 15, 15),
+            vec![(30, 15), (16, 8), (16, 8)]
+        );
+    }
+
+    #[test]
+    fn test_video_frame_crop() {
+        let mut video_frame = VideoFrame::builder()
+            .width(64)
+            .height(48)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create VideoFrame");
+        assert!(video_frame.crop().is_empty());
+
+        for (index, mut plane) in video_frame.planes_mut().enumerate() {
+            for row in 0..plane.height() {
+                plane
+                    .get_row_mut(row as usize)
+                    .unwrap()
+                    .fill((index as i32 * 100 + row) as u8);
+            }
+        }
+
+        let row = video_frame.data(0).unwrap().get_row(8).unwrap().as_ptr();
+        let crop = Crop {
+            top: 8,
+            bottom: 8,
+            left: 0,
+            right: 16,
+        };
+        video_frame.set_crop(crop).expect("Failed to set crop");
+        assert_eq!(video_frame.crop(), crop);
+
+        video_frame.apply_cropping(false).expect("Failed to apply cropping");
+        assert_eq!(video_frame.width(), 48);
+        assert_eq!(video_frame.height(), 32);
+        assert!(video_frame.crop().is_empty());
+
+        // The data is not copied, the planes point further into the same buffers.
+        let plane = video_frame.data(0).unwrap();
+        assert_eq!(plane.get_row(0).unwrap().as_ptr(), row);
+        assert_eq!(plane.get_row(0).unwrap()[0], 8);
+        assert_eq!(video_frame.data(1).unwrap().get_row(0).unwrap()[0], 104);
+
+        assert!(
+            video_frame
+                .set_crop(Crop {
+                    left: 24,
+                    right: 24,
+                    ..Default::default()
+                })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_video_frame_planes_mut() {
+        let mut video_frame = VideoFrame::builder()
+            .width(15)
+            .height(15)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create VideoFrame");
+
+        assert_eq!(video_frame.plane_count(), 3);
+
+        for (value, mut plane) in video_frame.planes_mut().enumerate() {
+            plane.fill(value as u8 + 1);
+        }
+
+        for (value, plane) in video_frame.planes().enumerate() {
+            for row in 0..plane.height() {
+                let row = plane.get_row(row as usize).unwrap();
+                assert!(row[..plane.width() as usize].iter().all(|byte| *byte == value as u8 + 1));
+            }
+        }
+    }
+
     #[test]
     fn test_video_frame_debug() {
         let video_frame = VideoFrame::builder()
@@ -1075,6 +1822,7 @@ mod tests {
         let frame_data = FrameData {
             ptr: core::ptr::NonNull::new(data.as_mut_ptr()).unwrap(),
             linesize: 3,
+            width: 3,
             height: 2,
         };
 
@@ -1097,6 +1845,7 @@ mod tests {
         let frame_data = FrameData {
             ptr: core::ptr::NonNull::new(end_ptr).unwrap(),
             linesize,
+            width: 3,
             height,
         };
 
@@ -1121,12 +1870,14 @@ mod tests {
         let inverse_frame_data = FrameData {
             ptr: core::ptr::NonNull::new(end_ptr).unwrap(),
             linesize,
+            width: 3,
             height,
         };
 
         let frame_data = FrameData {
             ptr: core::ptr::NonNull::new(data.as_mut_ptr()).unwrap(),
             linesize: linesize.abs(),
+            width: 3,
             height,
         };
 
@@ -1151,6 +1902,7 @@ mod tests {
         let mut frame_data = FrameData {
             ptr: core::ptr::NonNull::new(data.as_mut_ptr()).unwrap(),
             linesize: 3,
+            width: 3,
             height: 2,
         };
 
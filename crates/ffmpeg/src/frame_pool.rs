@@ -0,0 +1,333 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::AVPixelFormat;
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::frame::{GenericFrame, VideoFrame};
+
+/// The alignment used when none is given, enough for the widest SIMD instructions FFmpeg uses.
+const DEFAULT_ALIGNMENT: i32 = 64;
+
+/// Extra bytes after every plane, as some SIMD code reads a little past the end of a row.
+const PLANE_PADDING: usize = 16;
+
+/// The number of spare frames kept when no limit is given.
+const DEFAULT_MAX_SPARE_FRAMES: usize = 16;
+
+/// A plane of the frames of a [`FramePool`], with the pool its buffers come from.
+struct Plane {
+    pool: *mut AVBufferPool,
+    linesize: i32,
+}
+
+struct Inner {
+    width: i32,
+    height: i32,
+    pixel_format: AVPixelFormat,
+    planes: Vec<Plane>,
+    /// Frames given back with [`FramePool::recycle`], without their buffers.
+    spare: Mutex<Vec<GenericFrame>>,
+    max_spare_frames: usize,
+}
+
+/// Safety: `AVBufferPool` is thread safe, buffers can be taken from and returned to it from any thread.
+unsafe impl Send for Inner {}
+
+/// Safety: `AVBufferPool` is thread safe, buffers can be taken from and returned to it from any thread.
+unsafe impl Sync for Inner {}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for plane in &mut self.planes {
+            // Safety: The pool is valid, it is freed once the buffers still in use are returned.
+            unsafe { av_buffer_pool_uninit(&mut plane.pool) };
+        }
+    }
+}
+
+/// A pool of video frames of the same size and pixel format, reusing their allocations.
+///
+/// Allocating the buffers of a frame for every frame adds up in transcoding loops. Frames
+/// taken from a pool get their buffers from an `AVBufferPool` for each plane, so once the
+/// frames are dropped or [recycled](FramePool::recycle) their buffers are handed out again
+/// instead of being freed. Recycled frames keep their `AVFrame` as well, so in a steady state
+/// only the small reference counted wrappers around the buffers are allocated.
+///
+/// A pool is cheap to clone, clones share their buffers. Frames can outlive the pool.
+///
+/// Used by [`VideoDecoder::receive_frame_into`](crate::decoder::VideoDecoder::receive_frame_into)
+/// and [`VideoScaler::process_into`](crate::scaler::VideoScaler::process_into).
+#[derive(Clone)]
+pub struct FramePool(Arc<Inner>);
+
+impl std::fmt::Debug for FramePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FramePool")
+            .field("width", &self.0.width)
+            .field("height", &self.0.height)
+            .field("pixel_format", &self.0.pixel_format)
+            .field("spare_frames", &self.spare_frames())
+            .finish()
+    }
+}
+
+#[bon::bon]
+impl FramePool {
+    /// Creates a new [`FramePool`] for frames of the given size and pixel format.
+    #[builder]
+    pub fn new(
+        width: i32,
+        height: i32,
+        pix_fmt: AVPixelFormat,
+        /// Alignment of the rows of the data buffers, a power of two, set to 0 for automatic.
+        #[builder(default = 0)]
+        alignment: i32,
+        /// The number of recycled frames to keep, more are dropped.
+        #[builder(default = DEFAULT_MAX_SPARE_FRAMES)]
+        max_spare_frames: usize,
+    ) -> Result<Self, FfmpegError> {
+        if width <= 0 || height <= 0 {
+            return Err(FfmpegError::Arguments("width and height must be positive and not 0"));
+        }
+        if alignment < 0 || (alignment != 0 && !(alignment as u32).is_power_of_two()) {
+            return Err(FfmpegError::Arguments("alignment must be a power of two"));
+        }
+
+        let alignment = if alignment == 0 { DEFAULT_ALIGNMENT } else { alignment };
+
+        // The same layout `av_frame_get_buffer` uses, rows aligned and the height padded.
+        let mut linesizes = [0; 4];
+        // Safety: `linesizes` has room for the 4 planes a pixel format can have.
+        FfmpegErrorCode(unsafe { av_image_fill_linesizes(linesizes.as_mut_ptr(), pix_fmt.into(), align(width, alignment)) })
+            .result()?;
+        for linesize in &mut linesizes {
+            *linesize = align(*linesize, alignment);
+        }
+
+        let mut sizes = [0; 4];
+        let strides = linesizes.map(|linesize| linesize as _);
+        // Safety: `sizes` and `strides` have room for the 4 planes a pixel format can have.
+        FfmpegErrorCode(unsafe {
+            av_image_fill_plane_sizes(sizes.as_mut_ptr(), pix_fmt.into(), align(height, 32), strides.as_ptr())
+        })
+        .result()?;
+
+        let mut inner = Inner {
+            width,
+            height,
+            pixel_format: pix_fmt,
+            planes: Vec::new(),
+            spare: Mutex::default(),
+            max_spare_frames,
+        };
+
+        for (size, linesize) in sizes.into_iter().zip(linesizes).take_while(|(size, _)| *size > 0) {
+            // Safety: `av_buffer_pool_init` is safe to call, buffers are allocated with `av_malloc`.
+            let pool = unsafe { av_buffer_pool_init(size + PLANE_PADDING, None) };
+            if pool.is_null() {
+                return Err(FfmpegError::Alloc);
+            }
+
+            inner.planes.push(Plane { pool, linesize });
+        }
+
+        Ok(Self(Arc::new(inner)))
+    }
+
+    /// Returns the width of the frames.
+    pub fn width(&self) -> i32 {
+        self.0.width
+    }
+
+    /// Returns the height of the frames.
+    pub fn height(&self) -> i32 {
+        self.0.height
+    }
+
+    /// Returns the pixel format of the frames.
+    pub fn pixel_format(&self) -> AVPixelFormat {
+        self.0.pixel_format
+    }
+
+    /// Returns the number of recycled frames waiting to be reused.
+    pub fn spare_frames(&self) -> usize {
+        self.spare().len()
+    }
+
+    /// Returns a frame with buffers from the pool.
+    ///
+    /// Like [`VideoFrame::builder`], the data of the frame is not initialized, and
+    /// may contain the data of a frame that used the buffers before.
+    pub fn get(&self) -> Result<VideoFrame, FfmpegError> {
+        let mut frame = self.take_frame()?;
+
+        if let Err(err) = self.attach_buffers(&mut frame) {
+            self.recycle(frame);
+            return Err(err);
+        }
+
+        Ok(frame.video())
+    }
+
+    /// Gives a frame back to the pool, so it can be reused.
+    ///
+    /// The buffers of the frame are released, they go back to the pool they came from once no
+    /// other frame references them. Any frame can be recycled, not only frames from this pool.
+    pub fn recycle(&self, frame: impl Into<GenericFrame>) {
+        let mut frame = frame.into();
+
+        // Safety: The frame is valid, unreferencing releases its buffers and resets its fields.
+        unsafe { av_frame_unref(frame.as_mut_ptr()) };
+
+        let mut spare = self.spare();
+        if spare.len() < self.0.max_spare_frames {
+            spare.push(frame);
+        }
+    }
+
+    /// Returns a recycled frame, or a new one if there is none. The frame has no buffers.
+    pub(crate) fn take_frame(&self) -> Result<GenericFrame, FfmpegError> {
+        match self.spare().pop() {
+            Some(frame) => Ok(frame),
+            None => GenericFrame::new(),
+        }
+    }
+
+    /// Attaches buffers from the pool to a frame without buffers.
+    fn attach_buffers(&self, frame: &mut GenericFrame) -> Result<(), FfmpegError> {
+        // Safety: The frame is valid, and we have exclusive access to it.
+        let inner = unsafe { &mut *frame.as_mut_ptr() };
+
+        inner.width = self.0.width;
+        inner.height = self.0.height;
+        inner.format = self.0.pixel_format.0;
+
+        for (index, plane) in self.0.planes.iter().enumerate() {
+            // Safety: The pool is valid for as long as `self`.
+            let buffer = unsafe { av_buffer_pool_get(plane.pool) };
+            if buffer.is_null() {
+                // The buffers attached so far are released when the frame is recycled.
+                return Err(FfmpegError::Alloc);
+            }
+
+            inner.buf[index] = buffer;
+            // Safety: The buffer is not null, and valid.
+            inner.data[index] = unsafe { (*buffer).data };
+            inner.linesize[index] = plane.linesize;
+        }
+
+        inner.extended_data = inner.data.as_mut_ptr();
+
+        Ok(())
+    }
+
+    fn spare(&self) -> MutexGuard<'_, Vec<GenericFrame>> {
+        // The lock is never held across a panic point that leaves the frames inconsistent.
+        self.0.spare.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Rounds `value` up to a multiple of `alignment`, which must be a power of two.
+const fn align(value: i32, alignment: i32) -> i32 {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::AVPixelFormat;
+    use crate::error::FfmpegError;
+    use crate::frame::VideoFrame;
+    use crate::frame_pool::FramePool;
+
+    #[test]
+    fn test_frame_pool_get() {
+        let pool = FramePool::builder()
+            .width(1280)
+            .height(720)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create pool");
+
+        let mut frame = pool.get().expect("Failed to get frame");
+        assert_eq!((frame.width(), frame.height()), (1280, 720));
+        assert_eq!(frame.format(), AVPixelFormat::Yuv420p);
+
+        // The planes have the same layout as a frame that allocated its own buffers.
+        let allocated = VideoFrame::builder()
+            .width(1280)
+            .height(720)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create frame");
+        for index in 0..3 {
+            let plane = frame.data(index).expect("Missing plane");
+            let expected = allocated.data(index).expect("Missing plane");
+            assert_eq!((plane.width(), plane.height()), (expected.width(), expected.height()));
+            assert!(plane.linesize() >= expected.width());
+        }
+        assert!(frame.data(3).is_none());
+
+        for mut plane in frame.planes_mut() {
+            plane.fill(0x80);
+        }
+        assert_eq!(frame.data(0).unwrap().get_row(719).unwrap()[1279], 0x80);
+    }
+
+    #[test]
+    fn test_frame_pool_recycle() {
+        let pool = FramePool::builder()
+            .width(64)
+            .height(48)
+            .pix_fmt(AVPixelFormat::Rgb24)
+            .max_spare_frames(1)
+            .build()
+            .expect("Failed to create pool");
+
+        let frame = pool.get().expect("Failed to get frame");
+        let frame_ptr = frame.as_ptr();
+        let data_ptr = frame.data(0).unwrap().get_row(0).unwrap().as_ptr();
+
+        pool.recycle(frame);
+        assert_eq!(pool.spare_frames(), 1);
+
+        // Both the frame and its buffers are reused.
+        let frame = pool.get().expect("Failed to get frame");
+        assert_eq!(frame.as_ptr(), frame_ptr);
+        assert_eq!(frame.data(0).unwrap().get_row(0).unwrap().as_ptr(), data_ptr);
+        assert_eq!(pool.spare_frames(), 0);
+
+        // Spare frames above the limit are dropped.
+        let other = pool.get().expect("Failed to get frame");
+        pool.recycle(frame);
+        pool.recycle(other);
+        assert_eq!(pool.spare_frames(), 1);
+
+        // Frames outlive the pool.
+        let frame = pool.get().expect("Failed to get frame");
+        drop(pool);
+        assert_eq!(frame.data(0).unwrap().height(), 48);
+    }
+
+    #[test]
+    fn test_frame_pool_invalid() {
+        let builder = || FramePool::builder().pix_fmt(AVPixelFormat::Yuv420p);
+
+        assert_eq!(
+            builder().width(0).height(720).build().unwrap_err(),
+            FfmpegError::Arguments("width and height must be positive and not 0")
+        );
+        assert_eq!(
+            builder().width(1280).height(720).alignment(24).build().unwrap_err(),
+            FfmpegError::Arguments("alignment must be a power of two")
+        );
+        assert!(
+            FramePool::builder()
+                .width(1280)
+                .height(720)
+                .pix_fmt(AVPixelFormat::Vaapi)
+                .build()
+                .is_err()
+        );
+    }
+}
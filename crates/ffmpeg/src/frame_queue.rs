@@ -0,0 +1,444 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::frame::GenericFrame;
+
+/// What to do when a frame is pushed into a full [`FrameQueue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Block the producer until there is space in the queue.
+    #[default]
+    Block,
+    /// Drop the queued frame with the lowest pts to make room for the new frame.
+    ///
+    /// This is useful for live pipelines where latency matters more than completeness.
+    DropOldest,
+    /// Drop the frame that is being pushed.
+    DropNewest,
+}
+
+/// A snapshot of the metrics of a [`FrameQueue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameQueueMetrics {
+    /// The number of frames currently in the queue.
+    pub depth: usize,
+    /// The highest number of frames that were in the queue at the same time.
+    pub peak_depth: usize,
+    /// The number of frames that were accepted by the queue.
+    pub pushed: u64,
+    /// The number of frames that were taken out of the queue.
+    pub popped: u64,
+    /// The number of frames that were dropped because of the [`DropPolicy`].
+    pub dropped: u64,
+    /// The number of frames that were rejected because their pts was older than the last popped frame.
+    pub rejected: u64,
+}
+
+/// An error returned when a frame could not be pushed into a [`FrameQueue`].
+///
+/// The frame is handed back to the caller.
+#[derive(Debug, thiserror::Error)]
+pub enum FrameQueueError<F> {
+    /// The queue has been closed.
+    #[error("frame queue is closed")]
+    Closed(F),
+    /// The queue is full, only returned by [`FrameQueue::try_push`].
+    #[error("frame queue is full")]
+    Full(F),
+    /// The pts of the frame is older than the pts of the last popped frame,
+    /// so it can no longer be delivered in order.
+    #[error("frame pts {pts} is older than the last popped pts {last_pts}")]
+    OutOfOrder {
+        /// The rejected frame.
+        frame: F,
+        /// The pts of the rejected frame.
+        pts: i64,
+        /// The pts of the last popped frame.
+        last_pts: i64,
+    },
+}
+
+impl<F> FrameQueueError<F> {
+    /// Returns the frame that could not be pushed.
+    pub fn into_frame(self) -> F {
+        match self {
+            Self::Closed(frame) | Self::Full(frame) | Self::OutOfOrder { frame, .. } => frame,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State<F> {
+    frames: VecDeque<F>,
+    last_pts: Option<i64>,
+    closed: bool,
+    metrics: FrameQueueMetrics,
+}
+
+#[derive(Debug)]
+struct Shared<F> {
+    state: Mutex<State<F>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+/// A bounded queue to hand frames from one thread to another,
+/// for example from a decoder thread to an encoder thread.
+///
+/// Frames are kept sorted by pts, so frames pushed slightly out of order are
+/// delivered in order. Frames without a pts are delivered after all frames that
+/// were queued before them. A frame whose pts is older than the pts of the last
+/// popped frame is rejected with [`FrameQueueError::OutOfOrder`], which guarantees
+/// that the pts of the popped frames never decreases.
+///
+/// The queue can be cloned to get another handle to the same queue.
+#[derive(Debug)]
+pub struct FrameQueue<F = GenericFrame> {
+    shared: Arc<Shared<F>>,
+}
+
+impl<F> Clone for FrameQueue<F> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<F: AsRef<GenericFrame>> FrameQueue<F> {
+    /// Creates a new queue that holds at most `capacity` frames.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize, drop_policy: DropPolicy) -> Self {
+        assert!(capacity > 0, "frame queue capacity must be greater than 0");
+
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    frames: VecDeque::with_capacity(capacity),
+                    last_pts: None,
+                    closed: false,
+                    metrics: FrameQueueMetrics::default(),
+                }),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+                capacity,
+                drop_policy,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<F>> {
+        // A panic while holding the lock cannot leave the state inconsistent, so we ignore poisoning.
+        self.shared.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Pushes a frame into the queue.
+    ///
+    /// If the queue is full the [`DropPolicy`] decides what happens,
+    /// with [`DropPolicy::Block`] this call blocks until there is space in the queue.
+    pub fn push(&self, frame: F) -> Result<(), FrameQueueError<F>> {
+        self.push_inner(frame, true)
+    }
+
+    /// Pushes a frame into the queue without blocking.
+    ///
+    /// Same as [`FrameQueue::push`], except that [`FrameQueueError::Full`] is returned
+    /// instead of blocking when the queue is full and the policy is [`DropPolicy::Block`].
+    pub fn try_push(&self, frame: F) -> Result<(), FrameQueueError<F>> {
+        self.push_inner(frame, false)
+    }
+
+    fn push_inner(&self, frame: F, block: bool) -> Result<(), FrameQueueError<F>> {
+        let mut state = self.lock();
+
+        loop {
+            if state.closed {
+                return Err(FrameQueueError::Closed(frame));
+            }
+
+            let pts = frame.as_ref().pts();
+            if let (Some(pts), Some(last_pts)) = (pts, state.last_pts)
+                && pts < last_pts
+            {
+                state.metrics.rejected += 1;
+                return Err(FrameQueueError::OutOfOrder { frame, pts, last_pts });
+            }
+
+            if state.frames.len() < self.shared.capacity {
+                break;
+            }
+
+            match self.shared.drop_policy {
+                DropPolicy::Block if block => {
+                    state = self.shared.not_full.wait(state).unwrap_or_else(|err| err.into_inner());
+                }
+                DropPolicy::Block => return Err(FrameQueueError::Full(frame)),
+                DropPolicy::DropOldest => {
+                    state.frames.pop_front();
+                    state.metrics.dropped += 1;
+                    break;
+                }
+                DropPolicy::DropNewest => {
+                    state.metrics.dropped += 1;
+                    return Ok(());
+                }
+            }
+        }
+
+        let position = match frame.as_ref().pts() {
+            Some(pts) => state
+                .frames
+                .iter()
+                .rposition(|queued| queued.as_ref().pts().is_some_and(|queued_pts| queued_pts <= pts))
+                .map_or(0, |idx| idx + 1),
+            None => state.frames.len(),
+        };
+        state.frames.insert(position, frame);
+
+        state.metrics.pushed += 1;
+        state.metrics.depth = state.frames.len();
+        state.metrics.peak_depth = state.metrics.peak_depth.max(state.frames.len());
+
+        drop(state);
+        self.shared.not_empty.notify_one();
+
+        Ok(())
+    }
+
+    /// Pops the frame with the lowest pts from the queue.
+    ///
+    /// Blocks until a frame is available. Returns `None` once the queue is closed and empty.
+    pub fn pop(&self) -> Option<F> {
+        let mut state = self.lock();
+
+        while state.frames.is_empty() && !state.closed {
+            state = self.shared.not_empty.wait(state).unwrap_or_else(|err| err.into_inner());
+        }
+
+        self.pop_front(state)
+    }
+
+    /// Pops the frame with the lowest pts from the queue without blocking.
+    pub fn try_pop(&self) -> Option<F> {
+        self.pop_front(self.lock())
+    }
+
+    /// Pops the frame with the lowest pts from the queue, waiting at most `timeout` for a frame.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<F> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+
+        while state.frames.is_empty() && !state.closed {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+
+        self.pop_front(state)
+    }
+
+    fn pop_front(&self, mut state: MutexGuard<'_, State<F>>) -> Option<F> {
+        let frame = state.frames.pop_front()?;
+
+        if let Some(pts) = frame.as_ref().pts() {
+            state.last_pts = Some(pts);
+        }
+        state.metrics.popped += 1;
+        state.metrics.depth = state.frames.len();
+
+        drop(state);
+        self.shared.not_full.notify_one();
+
+        Some(frame)
+    }
+
+    /// Closes the queue.
+    ///
+    /// Pushing frames fails from now on, frames that are already queued can still be popped.
+    /// All blocked producers and consumers are woken up.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+    }
+
+    /// Returns true if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Returns the number of frames in the queue.
+    pub fn len(&self) -> usize {
+        self.lock().frames.len()
+    }
+
+    /// Returns true if there are no frames in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.lock().frames.is_empty()
+    }
+
+    /// Returns the maximum number of frames the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Returns the drop policy of the queue.
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.shared.drop_policy
+    }
+
+    /// Returns a snapshot of the queue metrics.
+    pub fn metrics(&self) -> FrameQueueMetrics {
+        self.lock().metrics
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::AVPixelFormat;
+    use crate::frame::{GenericFrame, VideoFrame};
+    use crate::frame_queue::{DropPolicy, FrameQueue, FrameQueueError, FrameQueueMetrics};
+
+    fn frame(pts: Option<i64>) -> VideoFrame {
+        let mut frame = VideoFrame::builder()
+            .width(16)
+            .height(16)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("failed to build VideoFrame");
+        frame.set_pts(pts);
+        frame
+    }
+
+    fn pop_pts(queue: &FrameQueue<VideoFrame>) -> Option<Option<i64>> {
+        queue.try_pop().map(|frame| frame.pts())
+    }
+
+    #[test]
+    fn test_frame_queue_reorders_by_pts() {
+        let queue = FrameQueue::new(8, DropPolicy::Block);
+
+        for pts in [Some(2), Some(0), Some(3), None, Some(1)] {
+            queue.push(frame(pts)).expect("failed to push frame");
+        }
+
+        assert_eq!(pop_pts(&queue), Some(Some(0)));
+        assert_eq!(pop_pts(&queue), Some(Some(1)));
+        assert_eq!(pop_pts(&queue), Some(Some(2)));
+        assert_eq!(pop_pts(&queue), Some(Some(3)));
+        assert_eq!(pop_pts(&queue), Some(None));
+        assert_eq!(pop_pts(&queue), None);
+    }
+
+    #[test]
+    fn test_frame_queue_rejects_out_of_order() {
+        let queue = FrameQueue::new(4, DropPolicy::Block);
+
+        queue.push(frame(Some(10))).expect("failed to push frame");
+        assert_eq!(pop_pts(&queue), Some(Some(10)));
+
+        let err = queue.push(frame(Some(5))).expect_err("frame should be rejected");
+        assert!(matches!(
+            err,
+            FrameQueueError::OutOfOrder {
+                pts: 5,
+                last_pts: 10,
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), "frame pts 5 is older than the last popped pts 10");
+        assert_eq!(err.into_frame().pts(), Some(5));
+
+        queue.push(frame(Some(10))).expect("equal pts should be accepted");
+        assert_eq!(queue.metrics().rejected, 1);
+    }
+
+    #[test]
+    fn test_frame_queue_drop_policies() {
+        let queue = FrameQueue::new(2, DropPolicy::Block);
+        queue.push(frame(Some(0))).expect("failed to push frame");
+        queue.push(frame(Some(1))).expect("failed to push frame");
+        assert!(matches!(queue.try_push(frame(Some(2))), Err(FrameQueueError::Full(_))));
+
+        let queue = FrameQueue::new(2, DropPolicy::DropOldest);
+        for pts in 0..4 {
+            queue.push(frame(Some(pts))).expect("failed to push frame");
+        }
+        assert_eq!(pop_pts(&queue), Some(Some(2)));
+        assert_eq!(pop_pts(&queue), Some(Some(3)));
+
+        let queue = FrameQueue::new(2, DropPolicy::DropNewest);
+        for pts in 0..4 {
+            queue.push(frame(Some(pts))).expect("failed to push frame");
+        }
+        assert_eq!(pop_pts(&queue), Some(Some(0)));
+        assert_eq!(pop_pts(&queue), Some(Some(1)));
+
+        assert_eq!(
+            queue.metrics(),
+            FrameQueueMetrics {
+                depth: 0,
+                peak_depth: 2,
+                pushed: 2,
+                popped: 2,
+                dropped: 2,
+                rejected: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_frame_queue_close() {
+        let queue = FrameQueue::new(2, DropPolicy::Block);
+        queue.push(frame(Some(0))).expect("failed to push frame");
+        queue.close();
+
+        assert!(queue.is_closed());
+        assert!(matches!(queue.push(frame(Some(1))), Err(FrameQueueError::Closed(_))));
+        assert_eq!(queue.pop().map(|frame| frame.pts()), Some(Some(0)));
+        assert!(queue.pop().is_none());
+        assert!(queue.pop_timeout(Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn test_frame_queue_cross_thread() {
+        let queue = FrameQueue::<GenericFrame>::new(2, DropPolicy::Block);
+
+        let producer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                for pts in 0..16 {
+                    queue.push(frame(Some(pts)).into()).expect("failed to push frame");
+                }
+                queue.close();
+            })
+        };
+
+        let mut received = Vec::new();
+        while let Some(frame) = queue.pop() {
+            received.push(frame.pts().expect("frame has no pts"));
+        }
+
+        producer.join().expect("producer panicked");
+
+        assert_eq!(received, (0..16).collect::<Vec<_>>());
+        assert!(queue.metrics().peak_depth <= queue.capacity());
+        assert!(queue.pop_timeout(Duration::from_millis(10)).is_none());
+    }
+}
@@ -0,0 +1,319 @@
+use std::io;
+
+use bytes::Bytes;
+use scuffle_h264::{AVCDecoderConfigurationRecord, AspectRatioIdc, NALUnitType, Sps};
+
+use crate::decoder::{Decoder, DecoderOptions, VideoDecoder};
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::rational::Rational;
+use crate::smart_object::SmartPtr;
+use crate::stream::Stream;
+use crate::{AVCodecID, AVColorRange, AVMediaType};
+
+/// The sample aspect ratios of `aspect_ratio_idc` 1 to 16.
+///
+/// ISO/IEC-14496-10-2022 - E.2.1 Table E-1
+const SAMPLE_ASPECT_RATIOS: [(i32, i32); 16] = [
+    (1, 1),
+    (12, 11),
+    (10, 11),
+    (16, 11),
+    (40, 33),
+    (24, 11),
+    (20, 11),
+    (32, 11),
+    (80, 33),
+    (18, 11),
+    (15, 11),
+    (64, 33),
+    (160, 99),
+    (4, 3),
+    (3, 2),
+    (2, 1),
+];
+
+/// Returns the sample aspect ratio signalled by the sps, if any.
+fn sample_aspect_ratio(sps: &Sps) -> Option<AVRational> {
+    let sar = sps.sample_aspect_ratio.as_ref()?;
+    let (num, den) = if sar.aspect_ratio_idc == AspectRatioIdc::ExtendedSar {
+        (sar.sar_width as i32, sar.sar_height as i32)
+    } else {
+        *SAMPLE_ASPECT_RATIOS.get((sar.aspect_ratio_idc.0 as usize).checked_sub(1)?)?
+    };
+
+    (num != 0 && den != 0).then_some(AVRational { num, den })
+}
+
+/// Returns the frame rate signalled by the sps, if any, see [`Sps::frame_rate`].
+fn frame_rate(sps: &Sps) -> Option<AVRational> {
+    let timing_info = sps.timing_info.as_ref()?;
+    let mut num = timing_info.time_scale.get() as u64;
+    let mut den = timing_info.num_units_in_tick.get() as u64 * 2;
+
+    let (mut a, mut b) = (num, den);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    num /= a;
+    den /= a;
+
+    Some(AVRational {
+        num: num.try_into().ok()?,
+        den: den.try_into().ok()?,
+    })
+}
+
+/// Parses the first sps of the record.
+fn parse_sps(config: &AVCDecoderConfigurationRecord) -> Result<Sps, FfmpegError> {
+    let sps = config
+        .sps
+        .first()
+        .ok_or(FfmpegError::Arguments("no sps in the decoder configuration record"))?;
+    Sps::parse_with_emulation_prevention(io::Cursor::new(sps)).map_err(|_| FfmpegErrorCode::InvalidData.into())
+}
+
+/// Fills `params` with the parameters of an H.264 stream described by `config`.
+fn set_codec_parameters(params: &mut AVCodecParameters, config: &AVCDecoderConfigurationRecord) -> Result<(), FfmpegError> {
+    let sps = parse_sps(config)?;
+
+    let mut extradata = Vec::with_capacity(config.size() as usize);
+    config
+        .build(&mut extradata)
+        .map_err(|_| FfmpegError::Arguments("invalid decoder configuration record"))?;
+    let extradata_size = i32::try_from(extradata.len()).map_err(|_| FfmpegError::Arguments("extradata too large"))?;
+
+    // Safety: `av_mallocz` is safe to call, the padding is required by FFmpeg for extradata.
+    let data = unsafe { av_mallocz(extradata.len() + AV_INPUT_BUFFER_PADDING_SIZE as usize) } as *mut u8;
+    if data.is_null() {
+        return Err(FfmpegError::Alloc);
+    }
+
+    // Safety: `data` was allocated above with room for `extradata` and does not overlap it.
+    unsafe { std::ptr::copy_nonoverlapping(extradata.as_ptr(), data, extradata.len()) };
+
+    // Safety: `params.extradata` is either null or was allocated by FFmpeg, `av_freep` sets it to null.
+    unsafe { av_freep(&mut params.extradata as *mut *mut u8 as *mut libc::c_void) };
+    params.extradata = data;
+    params.extradata_size = extradata_size;
+
+    params.codec_type = AVMediaType::Video.0 as _;
+    params.codec_id = AVCodecID::H264.0 as _;
+    params.profile = sps.profile_idc as i32;
+    params.level = sps.level_idc as i32;
+    params.width = i32::try_from(sps.width()).map_err(|_| FfmpegError::Arguments("sps width too large"))?;
+    params.height = i32::try_from(sps.height()).map_err(|_| FfmpegError::Arguments("sps height too large"))?;
+    params.sample_aspect_ratio = sample_aspect_ratio(&sps).unwrap_or(AVRational { num: 0, den: 1 });
+    params.framerate = frame_rate(&sps).unwrap_or(AVRational { num: 0, den: 1 });
+
+    if let Some(color_config) = &sps.color_config {
+        let color_range = if color_config.video_full_range_flag {
+            AVColorRange::Jpeg
+        } else {
+            AVColorRange::Mpeg
+        };
+        params.color_range = color_range.0 as _;
+        // FFmpeg uses the values of ITU-T H.273 for these, same as the sps.
+        params.color_primaries = color_config.color_primaries as _;
+        params.color_trc = color_config.transfer_characteristics as _;
+        params.color_space = color_config.matrix_coefficients as _;
+    }
+
+    Ok(())
+}
+
+/// Parses the first sps found in H.264 extradata.
+///
+/// The extradata is either an `AVCDecoderConfigurationRecord`, as stored by mp4 and flv,
+/// or Annex B NAL units with start codes, as stored by mpegts and raw h264 streams.
+///
+/// Returns `None` if the extradata contains no sps.
+pub fn sps_from_extradata(extradata: &[u8]) -> Result<Option<Sps>, FfmpegError> {
+    fn invalid_data<E>(_: E) -> FfmpegError {
+        FfmpegError::Code(FfmpegErrorCode::InvalidData)
+    }
+
+    if extradata.first() == Some(&1) {
+        let config = AVCDecoderConfigurationRecord::parse(&mut io::Cursor::new(Bytes::copy_from_slice(extradata)))
+            .map_err(invalid_data)?;
+        return config
+            .sps
+            .first()
+            .map(|sps| Sps::parse_with_emulation_prevention(io::Cursor::new(sps)))
+            .transpose()
+            .map_err(invalid_data);
+    }
+
+    // Annex B, every NAL unit follows a 0x000001 start code.
+    let mut rest = extradata;
+    while let Some(start) = rest.windows(3).position(|window| window == [0, 0, 1]) {
+        rest = &rest[start + 3..];
+        let end = rest.windows(3).position(|window| window == [0, 0, 1]).unwrap_or(rest.len());
+        let nal_unit = &rest[..end];
+
+        if nal_unit
+            .first()
+            .is_some_and(|header| NALUnitType(header & 0x1f) == NALUnitType::SPS)
+        {
+            return Sps::parse_with_emulation_prevention(io::Cursor::new(nal_unit))
+                .map(Some)
+                .map_err(invalid_data);
+        }
+    }
+
+    Ok(None)
+}
+
+impl Decoder {
+    /// Creates an H.264 decoder from a decoder configuration record, without a container.
+    ///
+    /// This is for streams that arrive outside of FFmpeg, such as over rtmp. The width, height,
+    /// profile, level, frame rate and colors are taken from the first sps of the record,
+    /// and the record becomes the extradata of the decoder, so the packets sent to it must be
+    /// in the length prefixed format of the record.
+    ///
+    /// `time_base` is the time base of the packets sent to the decoder.
+    pub fn from_h264_config(
+        config: &AVCDecoderConfigurationRecord,
+        time_base: impl Into<Rational>,
+        options: DecoderOptions,
+    ) -> Result<VideoDecoder, FfmpegError> {
+        // Safety: `avcodec_parameters_alloc` is safe to call.
+        let params = unsafe { avcodec_parameters_alloc() };
+
+        let destructor = |ptr: &mut *mut AVCodecParameters| {
+            // Safety: The pointer here is valid.
+            unsafe { avcodec_parameters_free(ptr) };
+        };
+
+        // Safety: `params` is a valid pointer, and `destructor` has been setup to free the parameters.
+        let mut params = unsafe { SmartPtr::wrap_non_null(params, destructor) }.ok_or(FfmpegError::Alloc)?;
+        set_codec_parameters(params.as_deref_mut_except(), config)?;
+
+        let frame_rate = params.as_deref_except().framerate;
+        Self::open(params.as_deref_except(), time_base.into(), options, || frame_rate)?
+            .video()
+            .map_err(|_| FfmpegError::NoDecoder)
+    }
+}
+
+impl Stream<'_> {
+    /// Sets the codec parameters of the stream to an H.264 stream described by `config`.
+    ///
+    /// This lets packets from outside of FFmpeg, such as from rtmp, be muxed without an encoder.
+    /// See [`Decoder::from_h264_config`] for which parameters are set.
+    pub fn set_h264_config(&mut self, config: &AVCDecoderConfigurationRecord) -> Result<(), FfmpegError> {
+        let params = self.codec_parameters_mut().ok_or(FfmpegError::NoStream)?;
+        set_codec_parameters(params, config)
+    }
+
+    /// Parses the sps from the extradata of the stream, see [`sps_from_extradata`].
+    ///
+    /// Returns `None` if the stream is not H.264 or has no sps in its extradata.
+    pub fn h264_sps(&self) -> Result<Option<Sps>, FfmpegError> {
+        let Some(params) = self.codec_parameters() else {
+            return Ok(None);
+        };
+
+        if AVCodecID(params.codec_id as _) != AVCodecID::H264 || params.extradata.is_null() || params.extradata_size <= 0 {
+            return Ok(None);
+        }
+
+        // Safety: FFmpeg keeps `extradata_size` bytes at `extradata`, the pointer was checked to be non-null.
+        let extradata = unsafe { std::slice::from_raw_parts(params.extradata, params.extradata_size as usize) };
+        sps_from_extradata(extradata)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io;
+
+    use bytes::Bytes;
+    use scuffle_h264::AVCDecoderConfigurationRecord;
+
+    use crate::AVMediaType;
+    use crate::decoder::Decoder;
+    use crate::error::FfmpegError;
+    use crate::io::Input;
+    use crate::rational::Rational;
+
+    const PATH: &str = "../../assets/avc_aac_large.mp4";
+
+    /// The avcC of a 480x852 high profile stream, with an sps that signals 30 fps.
+    const AVCC: &[u8] = b"\x01d\0\x1f\xff\xe1\0\x17\x67\x64\x00\x1F\xAC\xD9\x41\xE0\x6D\xF9\xE6\xA0\x20\x20\x28\x00\x00\x00\x08\x00\x00\x01\xE0\x01\0\x06h\xeb\xe3\xcb\"\xc0\xfd\xf8\xf8\0";
+
+    #[test]
+    fn test_sps_from_extradata() {
+        let sps = super::sps_from_extradata(AVCC).unwrap().expect("no sps");
+        assert_eq!(sps.width(), 480);
+        assert_eq!(sps.height(), 852);
+
+        let config = AVCDecoderConfigurationRecord::parse(&mut io::Cursor::new(Bytes::from_static(AVCC))).unwrap();
+        let mut annexb = Vec::new();
+        for nal_unit in config.sps.iter().chain(&config.pps) {
+            annexb.extend_from_slice(&[0, 0, 0, 1]);
+            annexb.extend_from_slice(nal_unit);
+        }
+
+        assert_eq!(super::sps_from_extradata(&annexb).unwrap(), Some(sps));
+        assert_eq!(super::sps_from_extradata(&annexb[27..]).unwrap(), None);
+        assert_eq!(super::sps_from_extradata(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decoder_from_h264_config() {
+        let config = AVCDecoderConfigurationRecord::parse(&mut io::Cursor::new(Bytes::from_static(AVCC))).unwrap();
+        let decoder = Decoder::from_h264_config(&config, Rational::static_new::<1, 1000>(), Default::default())
+            .expect("failed to create decoder");
+
+        assert_eq!(decoder.width(), 480);
+        assert_eq!(decoder.height(), 852);
+        assert_eq!(decoder.frame_rate(), Rational::static_new::<30, 1>());
+
+        let config = AVCDecoderConfigurationRecord { sps: vec![], ..config };
+        assert!(matches!(
+            Decoder::from_h264_config(&config, 1, Default::default()),
+            Err(FfmpegError::Arguments(_))
+        ));
+    }
+
+    #[test]
+    fn test_h264_round_trip() {
+        let mut input = Input::open(PATH).expect("failed to open input");
+        let stream = input.streams().best(AVMediaType::Video).expect("no video stream");
+        let index = stream.index();
+        let time_base = stream.time_base();
+        let params = stream.codec_parameters().expect("no codec parameters");
+        let (width, height) = (params.width, params.height);
+
+        let sps = stream.h264_sps().expect("failed to parse sps").expect("no sps");
+        assert_eq!(sps.width(), width as u64);
+        assert_eq!(sps.height(), height as u64);
+
+        // Safety: The extradata of an mp4 stream is an avcC.
+        let extradata = unsafe { std::slice::from_raw_parts(params.extradata, params.extradata_size as usize) };
+        let config = AVCDecoderConfigurationRecord::parse(&mut io::Cursor::new(Bytes::copy_from_slice(extradata))).unwrap();
+        let mut decoder =
+            Decoder::from_h264_config(&config, time_base, Default::default()).expect("failed to create decoder");
+
+        let frame = loop {
+            let packet = input
+                .receive_packet()
+                .expect("failed to read packet")
+                .expect("no frame decoded");
+            if packet.stream_index() != index {
+                continue;
+            }
+
+            decoder.send_packet(&packet).expect("failed to send packet");
+            if let Some(frame) = decoder.receive_frame().expect("failed to receive frame") {
+                break frame;
+            }
+        };
+
+        assert_eq!(frame.width(), width as usize);
+        assert_eq!(frame.height(), height as usize);
+    }
+}
@@ -0,0 +1,370 @@
+use std::ffi::{CStr, CString};
+
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::frame::{GenericFrame, VideoFrame};
+use crate::smart_object::SmartPtr;
+use crate::{AVHWDeviceType, AVPixelFormat};
+
+fn buffer_destructor(ptr: &mut *mut AVBufferRef) {
+    // Safety: `av_buffer_unref` is safe to call with a pointer to a buffer reference we own.
+    unsafe { av_buffer_unref(ptr) };
+}
+
+/// Creates a new reference to `buffer`.
+fn buffer_ref(buffer: &SmartPtr<AVBufferRef>) -> Result<SmartPtr<AVBufferRef>, FfmpegError> {
+    // Safety: `av_buffer_ref` is safe to call with a valid buffer reference.
+    let buffer = unsafe { av_buffer_ref(buffer.as_ptr()) };
+
+    // Safety: The pointer here is valid and the destructor releases the new reference.
+    unsafe { SmartPtr::wrap_non_null(buffer, buffer_destructor) }.ok_or(FfmpegError::Alloc)
+}
+
+impl AVHWDeviceType {
+    /// Finds a device type by its FFmpeg name, such as `vaapi`, `cuda` or `videotoolbox`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = CString::new(name).ok()?;
+
+        // Safety: `av_hwdevice_find_type_by_name` is safe to call with a valid c-string.
+        let device_type = AVHWDeviceType(unsafe { av_hwdevice_find_type_by_name(name.as_ptr()) } as _);
+        (device_type != AVHWDeviceType::None).then_some(device_type)
+    }
+
+    /// Returns the FFmpeg name of the device type, or `None` for [`AVHWDeviceType::None`].
+    pub fn name(self) -> Option<&'static str> {
+        // Safety: `av_hwdevice_get_type_name` is safe to call with any device type.
+        let name = unsafe { av_hwdevice_get_type_name(self.0 as _) };
+        if name.is_null() {
+            return None;
+        }
+
+        // Safety: The name is a static c-string owned by FFmpeg.
+        unsafe { CStr::from_ptr(name) }.to_str().ok()
+    }
+
+    /// Returns the device types FFmpeg was built with.
+    ///
+    /// A type being listed does not mean a device of that type is present on the host,
+    /// that is only known once [`HwDeviceContext::new`] succeeds.
+    pub fn available() -> Vec<Self> {
+        let mut types = Vec::new();
+        let mut device_type = AVHWDeviceType::None;
+
+        loop {
+            // Safety: `av_hwdevice_iterate_types` is safe to call with any device type.
+            device_type = AVHWDeviceType(unsafe { av_hwdevice_iterate_types(device_type.0 as _) } as _);
+            if device_type == AVHWDeviceType::None {
+                break types;
+            }
+
+            types.push(device_type);
+        }
+    }
+}
+
+/// Returns the hardware pixel format `codec` uses with a device of `device_type`,
+/// or `None` if the codec does not support the device type.
+pub(crate) fn codec_hw_pixel_format(codec: *const AVCodec, device_type: AVHWDeviceType) -> Option<AVPixelFormat> {
+    if codec.is_null() || device_type == AVHWDeviceType::None {
+        return None;
+    }
+
+    let methods = (AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX | AV_CODEC_HW_CONFIG_METHOD_HW_FRAMES_CTX) as i32;
+
+    for index in 0.. {
+        // Safety: `avcodec_get_hw_config` is safe to call with a valid codec and returns null past the last config.
+        let config = unsafe { avcodec_get_hw_config(codec, index) };
+
+        // Safety: The pointer is either null or points to a static config owned by FFmpeg.
+        let config = unsafe { config.as_ref() }?;
+
+        if AVHWDeviceType(config.device_type as _) == device_type && config.methods & methods != 0 {
+            return Some(AVPixelFormat(config.pix_fmt as _));
+        }
+    }
+
+    None
+}
+
+/// A hardware device, such as a GPU opened through VA-API, CUDA or VideoToolbox.
+///
+/// Pass it to a decoder with [`DecoderOptions::hw_device`](crate::decoder::DecoderOptions::hw_device)
+/// to decode into hardware frames, or to an encoder through
+/// [`VideoEncoderSettings`](crate::encoder::VideoEncoderSettings). The device is reference
+/// counted, cloning it shares the same device.
+pub struct HwDeviceContext(SmartPtr<AVBufferRef>);
+
+/// Safety: `HwDeviceContext` can be sent between threads, the device is reference counted atomically.
+unsafe impl Send for HwDeviceContext {}
+
+impl std::fmt::Debug for HwDeviceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HwDeviceContext")
+            .field("device_type", &self.device_type())
+            .finish()
+    }
+}
+
+impl Clone for HwDeviceContext {
+    fn clone(&self) -> Self {
+        Self(buffer_ref(&self.0).expect("failed to reference hardware device"))
+    }
+}
+
+impl HwDeviceContext {
+    /// Opens a hardware device of `device_type`.
+    ///
+    /// `device` selects the device in a type specific way, such as a DRM node like
+    /// `/dev/dri/renderD128` for VA-API or a GPU index for CUDA. `None` opens the default device.
+    pub fn new(device_type: AVHWDeviceType, device: Option<&str>) -> Result<Self, FfmpegError> {
+        if device_type == AVHWDeviceType::None {
+            return Err(FfmpegError::Arguments("device_type must be set"));
+        }
+
+        let device = device
+            .map(CString::new)
+            .transpose()
+            .map_err(|_| FfmpegError::Arguments("device must not contain nul bytes"))?;
+
+        let mut context = SmartPtr::null(buffer_destructor);
+
+        // Safety: `av_hwdevice_ctx_create` is safe to call, `context` is a valid pointer to a null buffer
+        // reference and `device` is either null or a valid c-string.
+        FfmpegErrorCode(unsafe {
+            av_hwdevice_ctx_create(
+                context.as_mut(),
+                device_type.0 as _,
+                device.as_ref().map_or(std::ptr::null(), |device| device.as_ptr()),
+                std::ptr::null_mut(),
+                0,
+            )
+        })
+        .result()?;
+
+        if context.as_ptr().is_null() {
+            return Err(FfmpegError::Alloc);
+        }
+
+        Ok(Self(context))
+    }
+
+    /// Returns the type of the device.
+    pub fn device_type(&self) -> AVHWDeviceType {
+        let context = self.0.as_deref_except().data as *const AVHWDeviceContext;
+
+        // Safety: The data of a hardware device buffer is an `AVHWDeviceContext`.
+        AVHWDeviceType(unsafe { (*context).type_ } as _)
+    }
+
+    /// Returns a new reference to the device, to be owned by a codec context.
+    pub(crate) fn new_ref(&self) -> Result<*mut AVBufferRef, FfmpegError> {
+        Ok(buffer_ref(&self.0)?.into_inner())
+    }
+
+    /// Returns a pointer to the device buffer reference.
+    pub(crate) const fn as_ptr(&self) -> *const AVBufferRef {
+        self.0.as_ptr()
+    }
+}
+
+/// A pool of frames in the memory of a [`HwDeviceContext`].
+///
+/// Encoders such as `h264_vaapi` only accept frames from a pool, frames in system
+/// memory are uploaded to it with [`VideoFrame::transfer_to_hardware`].
+pub struct HwFramesContext(SmartPtr<AVBufferRef>);
+
+/// Safety: `HwFramesContext` can be sent between threads, the pool is reference counted atomically.
+unsafe impl Send for HwFramesContext {}
+
+impl std::fmt::Debug for HwFramesContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HwFramesContext")
+            .field("format", &self.format())
+            .field("sw_format", &self.sw_format())
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .finish()
+    }
+}
+
+impl Clone for HwFramesContext {
+    fn clone(&self) -> Self {
+        Self(buffer_ref(&self.0).expect("failed to reference hardware frames"))
+    }
+}
+
+#[bon::bon]
+impl HwFramesContext {
+    /// Creates a new pool of hardware frames on `device`.
+    ///
+    /// `format` is the hardware pixel format, such as [`AVPixelFormat::Vaapi`], and `sw_format`
+    /// the format of the data in hardware memory, usually [`AVPixelFormat::Nv12`].
+    #[builder]
+    pub fn new(
+        device: &HwDeviceContext,
+        format: AVPixelFormat,
+        sw_format: AVPixelFormat,
+        width: i32,
+        height: i32,
+        /// The number of frames allocated up front, some devices cannot grow the pool later.
+        #[builder(default = 0)]
+        initial_pool_size: i32,
+    ) -> Result<Self, FfmpegError> {
+        if width <= 0 || height <= 0 {
+            return Err(FfmpegError::Arguments("width and height must be positive and not 0"));
+        }
+        if format == AVPixelFormat::None || sw_format == AVPixelFormat::None {
+            return Err(FfmpegError::Arguments("format and sw_format must be set"));
+        }
+
+        // Safety: `av_hwframe_ctx_alloc` is safe to call with a valid device reference, it takes its own reference.
+        let frames = unsafe { av_hwframe_ctx_alloc(device.as_ptr().cast_mut()) };
+
+        // Safety: The pointer here is valid and the destructor releases the reference.
+        let mut frames = unsafe { SmartPtr::wrap_non_null(frames, buffer_destructor) }.ok_or(FfmpegError::Alloc)?;
+
+        let context = frames.as_deref_mut_except().data as *mut AVHWFramesContext;
+
+        // Safety: The data of a hardware frames buffer is an `AVHWFramesContext`, which is not initialized yet.
+        let context = unsafe { &mut *context };
+        context.format = format.into();
+        context.sw_format = sw_format.into();
+        context.width = width;
+        context.height = height;
+        context.initial_pool_size = initial_pool_size;
+
+        // Safety: `av_hwframe_ctx_init` is safe to call with the configured frames context.
+        FfmpegErrorCode(unsafe { av_hwframe_ctx_init(frames.as_mut_ptr()) }).result()?;
+
+        Ok(Self(frames))
+    }
+
+    const fn context(&self) -> &AVHWFramesContext {
+        let context = self.0.as_deref_except().data as *const AVHWFramesContext;
+
+        // Safety: The data of a hardware frames buffer is an `AVHWFramesContext`.
+        unsafe { &*context }
+    }
+
+    /// Returns the hardware pixel format of the frames.
+    pub const fn format(&self) -> AVPixelFormat {
+        AVPixelFormat(self.context().format)
+    }
+
+    /// Returns the pixel format of the data in hardware memory.
+    pub const fn sw_format(&self) -> AVPixelFormat {
+        AVPixelFormat(self.context().sw_format)
+    }
+
+    /// Returns the width of the frames.
+    pub const fn width(&self) -> i32 {
+        self.context().width
+    }
+
+    /// Returns the height of the frames.
+    pub const fn height(&self) -> i32 {
+        self.context().height
+    }
+
+    /// Takes an unused frame from the pool.
+    ///
+    /// The contents of the frame are undefined, fill it with [`VideoFrame::transfer_to_hardware`]
+    /// or by passing it to a hardware filter.
+    pub fn get_buffer(&self) -> Result<VideoFrame, FfmpegError> {
+        let mut frame = GenericFrame::new()?;
+
+        // Safety: `av_hwframe_get_buffer` is safe to call with an initialized frames context and an empty frame.
+        FfmpegErrorCode(unsafe { av_hwframe_get_buffer(self.0.as_ptr().cast_mut(), frame.as_mut_ptr(), 0) }).result()?;
+
+        Ok(frame.video())
+    }
+
+    /// Returns a new reference to the pool, to be owned by a codec context.
+    pub(crate) fn new_ref(&self) -> Result<*mut AVBufferRef, FfmpegError> {
+        Ok(buffer_ref(&self.0)?.into_inner())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::codec::{DecoderCodec, EncoderCodec};
+    use crate::error::FfmpegError;
+    use crate::frame::VideoFrame;
+    use crate::hwdevice::HwDeviceContext;
+    use crate::{AVCodecID, AVHWDeviceType, AVPixelFormat};
+
+    #[test]
+    fn test_device_type_names() {
+        assert_eq!(AVHWDeviceType::Vaapi.name(), Some("vaapi"));
+        assert_eq!(AVHWDeviceType::Cuda.name(), Some("cuda"));
+        assert_eq!(AVHWDeviceType::VideoToolbox.name(), Some("videotoolbox"));
+        assert_eq!(AVHWDeviceType::None.name(), None);
+
+        assert_eq!(AVHWDeviceType::from_name("vaapi"), Some(AVHWDeviceType::Vaapi));
+        assert_eq!(AVHWDeviceType::from_name("qsv"), Some(AVHWDeviceType::Qsv));
+        assert_eq!(AVHWDeviceType::from_name("not-a-device"), None);
+        assert_eq!(AVHWDeviceType::from_name("vaapi\0"), None);
+    }
+
+    #[test]
+    fn test_available_types() {
+        for device_type in AVHWDeviceType::available() {
+            assert_ne!(device_type, AVHWDeviceType::None);
+            assert!(device_type.name().is_some(), "{device_type:?} has no name");
+        }
+    }
+
+    #[test]
+    fn test_device_errors() {
+        assert!(matches!(
+            HwDeviceContext::new(AVHWDeviceType::None, None),
+            Err(FfmpegError::Arguments(_))
+        ));
+        assert!(matches!(
+            HwDeviceContext::new(AVHWDeviceType::Vaapi, Some("/dev/dri/\0")),
+            Err(FfmpegError::Arguments(_))
+        ));
+        assert!(HwDeviceContext::new(AVHWDeviceType::Vaapi, Some("/dev/dri/does-not-exist")).is_err());
+    }
+
+    #[test]
+    fn test_software_frame() {
+        let frame = VideoFrame::builder()
+            .width(16)
+            .height(16)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("failed to build frame");
+
+        assert!(!frame.is_hardware());
+        assert!(matches!(
+            frame.transfer_to_software(None),
+            Err(FfmpegError::Arguments("frame is not a hardware frame"))
+        ));
+    }
+
+    #[test]
+    fn test_codec_hw_pixel_format() {
+        let decoder = DecoderCodec::new(AVCodecID::H264).expect("failed to find h264 decoder");
+        assert_eq!(decoder.hw_pixel_format(AVHWDeviceType::None), None);
+        assert_eq!(DecoderCodec::empty().hw_pixel_format(AVHWDeviceType::Vaapi), None);
+
+        let encoder = EncoderCodec::new(AVCodecID::Mjpeg).expect("failed to find mjpeg encoder");
+        assert_eq!(encoder.hw_pixel_format(AVHWDeviceType::Cuda), None);
+    }
+
+    /// Runs against the first device the host has, if any.
+    #[test]
+    fn test_device_roundtrip() {
+        let Some(device) = AVHWDeviceType::available()
+            .into_iter()
+            .find_map(|device_type| HwDeviceContext::new(device_type, None).ok())
+        else {
+            return;
+        };
+
+        let clone = device.clone();
+        assert_eq!(clone.device_type(), device.device_type());
+    }
+}
@@ -0,0 +1,365 @@
+use crate::codec::EncoderCodec;
+use crate::decoder::Decoder;
+use crate::dict::Dictionary;
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::frame::VideoFrame;
+use crate::io::Input;
+use crate::packet::Packet;
+use crate::scaler::VideoScaler;
+use crate::smart_object::SmartPtr;
+use crate::{AVCodecID, AVMediaType, AVPixelFormat};
+
+/// The image formats a [`VideoFrame`] can be encoded to with [`VideoFrame::encode_image`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    /// PNG, lossless. Frames with an alpha channel keep it.
+    Png,
+    /// JPEG, encoded with the MJPEG encoder.
+    Jpeg,
+    /// WebP, encoded with `libwebp`, which requires FFmpeg to be built with it.
+    WebP,
+}
+
+impl ImageFormat {
+    /// Returns the id of the codec of the format.
+    pub const fn codec_id(self) -> AVCodecID {
+        match self {
+            Self::Png => AVCodecID::Png,
+            Self::Jpeg => AVCodecID::Mjpeg,
+            Self::WebP => AVCodecID::WebP,
+        }
+    }
+
+    /// Returns the MIME type of the format, such as `image/jpeg`.
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::WebP => "image/webp",
+        }
+    }
+
+    /// Returns the usual file extension of the format, without the dot.
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::WebP => "webp",
+        }
+    }
+
+    /// Returns the encoder of the format.
+    ///
+    /// `libwebp` is picked by name, the id also finds `libwebp_anim`, which is meant for animations.
+    fn encoder(self) -> Option<EncoderCodec> {
+        match self {
+            Self::WebP => EncoderCodec::by_name("libwebp"),
+            _ => EncoderCodec::new(self.codec_id()),
+        }
+    }
+
+    /// Returns the pixel format frames in `input` are converted to before they are encoded.
+    fn pixel_format(self, input: AVPixelFormat) -> AVPixelFormat {
+        match self {
+            Self::Png if has_alpha(input) => AVPixelFormat::Rgba,
+            Self::Png => AVPixelFormat::Rgb24,
+            // The MJPEG encoder expects the full range, which swscale only converts to for this format.
+            Self::Jpeg => AVPixelFormat::Yuvj420p,
+            Self::WebP => AVPixelFormat::Yuv420p,
+        }
+    }
+}
+
+/// Returns true if `pixel_format` has an alpha channel.
+fn has_alpha(pixel_format: AVPixelFormat) -> bool {
+    // Safety: `av_pix_fmt_desc_get` is safe to call with any pixel format, it returns null for unknown ones.
+    let descriptor = unsafe { av_pix_fmt_desc_get(pixel_format.into()) };
+    // Safety: The descriptor is either null or points to a static descriptor.
+    unsafe { descriptor.as_ref() }.is_some_and(|descriptor| descriptor.flags & AV_PIX_FMT_FLAG_ALPHA as u64 != 0)
+}
+
+/// Returns the largest size with the aspect ratio of `width` by `height` that fits in the bounds.
+///
+/// Frames smaller than the bounds keep their size.
+fn fit(width: i32, height: i32, max_width: i32, max_height: i32) -> (i32, i32) {
+    if width <= max_width && height <= max_height {
+        return (width, height);
+    }
+
+    let (width, height) = (i64::from(width), i64::from(height));
+    let (max_width, max_height) = (i64::from(max_width), i64::from(max_height));
+
+    // Compare `max_width / width` with `max_height / height` without rounding.
+    let (fitted_width, fitted_height) = if max_width * height <= max_height * width {
+        (max_width, height * max_width / width)
+    } else {
+        (width * max_height / height, max_height)
+    };
+
+    (fitted_width.max(1) as i32, fitted_height.max(1) as i32)
+}
+
+impl VideoFrame {
+    /// Decodes a single image, such as a PNG, JPEG or WebP file, into a frame.
+    ///
+    /// The format is detected from the data. The frame has the pixel format of the image,
+    /// such as [`AVPixelFormat::Rgb24`] for most PNG files.
+    pub fn decode_image(data: &[u8]) -> Result<VideoFrame, FfmpegError> {
+        let mut input = Input::new(std::io::Cursor::new(data))?;
+
+        let (stream_index, mut decoder) = {
+            let streams = input.streams();
+            let stream = streams.best(AVMediaType::Video).ok_or(FfmpegError::NoStream)?;
+            let decoder = Decoder::new(&stream)?.video().map_err(|_| FfmpegError::NoDecoder)?;
+            (stream.index(), decoder)
+        };
+
+        while let Some(packet) = input.receive_packet()? {
+            if packet.stream_index() != stream_index {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+            if let Some(frame) = decoder.receive_frame()? {
+                return Ok(frame);
+            }
+        }
+
+        decoder.send_eof()?;
+        decoder.receive_frame()?.ok_or(FfmpegError::NoFrame)
+    }
+
+    /// Encodes the frame as an image, such as a thumbnail.
+    ///
+    /// The frame is converted to a pixel format the encoder accepts, and downloaded first
+    /// if it is a [hardware frame](VideoFrame::is_hardware). `quality` goes from 0 to 100,
+    /// higher values give larger files. It is ignored for [`ImageFormat::Png`], which is lossless.
+    ///
+    /// Returns [`FfmpegError::NoEncoder`] if FFmpeg was built without the encoder of the format.
+    pub fn encode_image(&self, format: ImageFormat, quality: u8) -> Result<Vec<u8>, FfmpegError> {
+        self.encode_image_sized(format, quality, self.width() as i32, self.height() as i32)
+    }
+
+    /// Encodes the frame as an image that fits in `max_width` by `max_height`, see [`VideoFrame::encode_image`].
+    ///
+    /// The aspect ratio of the frame is kept, frames that already fit are not scaled up.
+    pub fn encode_thumbnail(
+        &self,
+        format: ImageFormat,
+        quality: u8,
+        max_width: i32,
+        max_height: i32,
+    ) -> Result<Vec<u8>, FfmpegError> {
+        if max_width <= 0 || max_height <= 0 {
+            return Err(FfmpegError::Arguments("max_width and max_height must be positive and not 0"));
+        }
+
+        let (width, height) = fit(self.width() as i32, self.height() as i32, max_width, max_height);
+        self.encode_image_sized(format, quality, width, height)
+    }
+
+    fn encode_image_sized(&self, format: ImageFormat, quality: u8, width: i32, height: i32) -> Result<Vec<u8>, FfmpegError> {
+        if quality > 100 {
+            return Err(FfmpegError::Arguments("quality must be between 0 and 100"));
+        }
+
+        let codec = format.encoder().ok_or(FfmpegError::NoEncoder)?;
+
+        let software;
+        let frame = if self.is_hardware() {
+            software = self.transfer_to_software(None)?;
+            &software
+        } else {
+            self
+        };
+
+        let pixel_format = format.pixel_format(frame.format());
+
+        let mut scaler;
+        let frame = if frame.format() == pixel_format && frame.width() as i32 == width && frame.height() as i32 == height {
+            frame
+        } else {
+            scaler = VideoScaler::new(
+                frame.width() as i32,
+                frame.height() as i32,
+                frame.format(),
+                width,
+                height,
+                pixel_format,
+            )?;
+            scaler.process(frame)?
+        };
+
+        // A new reference to the data, so the quality and timestamp can be set without changing `self`.
+        let mut frame = frame.clone();
+        frame.set_pts(Some(0));
+
+        // Safety: `avcodec_alloc_context3` is safe to call.
+        let context = unsafe { avcodec_alloc_context3(codec.as_ptr()) };
+
+        let destructor = |ptr: &mut *mut AVCodecContext| {
+            // Safety: The pointer here is valid, it comes from `avcodec_alloc_context3`.
+            unsafe { avcodec_free_context(ptr) };
+        };
+
+        // Safety: `context` is a valid pointer, and `destructor` has been setup to free the context.
+        let mut context = unsafe { SmartPtr::wrap_non_null(context, destructor) }.ok_or(FfmpegError::Alloc)?;
+
+        let context_mut = context.as_deref_mut_except();
+        context_mut.width = width;
+        context_mut.height = height;
+        context_mut.pix_fmt = pixel_format.into();
+        context_mut.time_base = AVRational { num: 1, den: 1 };
+        context_mut.sample_aspect_ratio = frame.sample_aspect_ratio().into();
+
+        let mut options = Dictionary::new();
+        match format {
+            ImageFormat::Png => {}
+            ImageFormat::Jpeg => {
+                // Maps the quality to the quantizer scale, from 31 for 0 down to 2 for 100.
+                let qscale = 31 - i32::from(quality) * 29 / 100;
+                context_mut.flags |= AV_CODEC_FLAG_QSCALE as i32;
+                context_mut.global_quality = qscale * FF_QP2LAMBDA as i32;
+                // The encoder takes the quantizer of each picture from the frame.
+                // Safety: The frame is valid and owned by us.
+                unsafe { (*frame.as_mut_ptr()).quality = context_mut.global_quality };
+            }
+            ImageFormat::WebP => {
+                options.set(c"quality", quality.to_string().as_str())?;
+            }
+        }
+
+        // Safety: `avcodec_open2` is safe to call, the context, codec and options are valid.
+        FfmpegErrorCode(unsafe { avcodec_open2(context_mut, codec.as_ptr(), options.as_mut_ptr_ref()) }).result()?;
+
+        // Safety: The context is open and the frame is valid.
+        FfmpegErrorCode(unsafe { avcodec_send_frame(context.as_mut_ptr(), frame.as_ptr()) }).result()?;
+        // Safety: The context is open, a null frame flushes it.
+        FfmpegErrorCode(unsafe { avcodec_send_frame(context.as_mut_ptr(), std::ptr::null()) }).result()?;
+
+        let mut packet = Packet::new()?;
+        // Safety: The context is open and the packet is valid.
+        FfmpegErrorCode(unsafe { avcodec_receive_packet(context.as_mut_ptr(), packet.as_mut_ptr()) }).result()?;
+
+        Ok(packet.data().to_vec())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::AVPixelFormat;
+    use crate::decoder::Decoder;
+    use crate::error::FfmpegError;
+    use crate::frame::VideoFrame;
+    use crate::image::{ImageFormat, fit};
+    use crate::io::Input;
+
+    fn first_video_frame() -> VideoFrame {
+        let mut input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open input");
+        let (stream_index, mut decoder) = {
+            let streams = input.streams();
+            let stream = streams.best(crate::AVMediaType::Video).expect("No video stream");
+            let decoder = Decoder::new(&stream)
+                .expect("Failed to create decoder")
+                .video()
+                .expect("Expected a video decoder");
+            (stream.index(), decoder)
+        };
+
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() != stream_index {
+                continue;
+            }
+
+            decoder.send_packet(&packet).expect("Failed to send packet");
+            if let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                return frame;
+            }
+        }
+
+        panic!("No video frame decoded");
+    }
+
+    #[test]
+    fn test_image_fit() {
+        assert_eq!(fit(3840, 2160, 320, 320), (320, 180));
+        assert_eq!(fit(1080, 1920, 320, 320), (180, 320));
+        assert_eq!(fit(100, 50, 320, 320), (100, 50));
+        assert_eq!(fit(10000, 1, 100, 100), (100, 1));
+    }
+
+    #[test]
+    fn test_image_png_roundtrip() {
+        let mut frame = VideoFrame::builder()
+            .width(16)
+            .height(8)
+            .pix_fmt(AVPixelFormat::Rgb24)
+            .build()
+            .expect("Failed to create frame");
+
+        let mut data = frame.data_mut(0).expect("No data");
+        for row in 0..8 {
+            let line = data.get_row_mut(row).expect("row is out of bounds");
+            for (i, byte) in line[..16 * 3].iter_mut().enumerate() {
+                *byte = (row * 16 * 3 + i) as u8;
+            }
+        }
+
+        let png = frame.encode_image(ImageFormat::Png, 100).expect("Failed to encode PNG");
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoded = VideoFrame::decode_image(&png).expect("Failed to decode PNG");
+        assert_eq!(decoded.width(), 16);
+        assert_eq!(decoded.height(), 8);
+        assert_eq!(decoded.format(), AVPixelFormat::Rgb24);
+
+        let original = frame.data(0).expect("No data");
+        let decoded = decoded.data(0).expect("No data");
+        for row in 0..8 {
+            assert_eq!(
+                original.get_row(row).unwrap()[..16 * 3],
+                decoded.get_row(row).unwrap()[..16 * 3]
+            );
+        }
+    }
+
+    #[test]
+    fn test_image_jpeg_thumbnail() {
+        let frame = first_video_frame();
+
+        let low = frame
+            .encode_thumbnail(ImageFormat::Jpeg, 10, 320, 320)
+            .expect("Failed to encode JPEG");
+        let high = frame
+            .encode_thumbnail(ImageFormat::Jpeg, 90, 320, 320)
+            .expect("Failed to encode JPEG");
+        assert_eq!(&high[..2], &[0xff, 0xd8]);
+        assert!(high.len() > low.len(), "{} <= {}", high.len(), low.len());
+
+        let decoded = VideoFrame::decode_image(&high).expect("Failed to decode JPEG");
+        assert_eq!(decoded.width(), 320);
+        assert_eq!(decoded.height(), 180);
+    }
+
+    #[test]
+    fn test_image_invalid_arguments() {
+        let frame = VideoFrame::builder()
+            .width(16)
+            .height(16)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .build()
+            .expect("Failed to create frame");
+
+        assert!(matches!(
+            frame.encode_image(ImageFormat::Jpeg, 101),
+            Err(FfmpegError::Arguments(_))
+        ));
+        assert!(matches!(
+            frame.encode_thumbnail(ImageFormat::Jpeg, 75, 0, 100),
+            Err(FfmpegError::Arguments(_))
+        ));
+        assert!(VideoFrame::decode_image(b"not an image").is_err());
+    }
+}
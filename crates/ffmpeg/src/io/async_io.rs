@@ -0,0 +1,347 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Handle;
+
+use super::{Input, InputOptions, Output, OutputOptions};
+use crate::dict::Dictionary;
+use crate::error::FfmpegError;
+use crate::packet::Packet;
+
+/// Adapts an async reader or writer to the blocking `std::io` traits FFmpeg reads and writes through.
+///
+/// Every call blocks on the runtime the adapter was created on, so it must only be used
+/// from a blocking thread, such as one from [`tokio::task::spawn_blocking`].
+/// [`AsyncInput`] and [`AsyncOutput`] take care of that.
+#[derive(Debug)]
+pub struct AsyncIoCompat<T> {
+    inner: T,
+    handle: Handle,
+}
+
+impl<T> AsyncIoCompat<T> {
+    /// Creates a new `AsyncIoCompat` that blocks on the runtime of `handle`.
+    pub const fn new(inner: T, handle: Handle) -> Self {
+        Self { inner, handle }
+    }
+
+    /// Returns a reference to the wrapped reader or writer.
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Consumes the `AsyncIoCompat` and returns the wrapped reader or writer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> std::io::Read for AsyncIoCompat<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.handle.block_on(self.inner.read(buf))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> std::io::Write for AsyncIoCompat<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.handle.block_on(self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.handle.block_on(self.inner.flush())
+    }
+}
+
+impl<T: AsyncSeek + Unpin> std::io::Seek for AsyncIoCompat<T> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.handle.block_on(self.inner.seek(pos))
+    }
+}
+
+/// Runs `f` on a blocking thread of the Tokio runtime, forwarding panics.
+async fn spawn_blocking<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> Result<R, FfmpegError> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => Ok(result),
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(_) => Err(FfmpegError::Arguments("the runtime is shutting down")),
+    }
+}
+
+/// Runs `f` with the value in `slot` on a blocking thread and puts the value back afterwards.
+///
+/// If the returned future is dropped before `f` finishes, the value is dropped along
+/// with the blocking task and `slot` stays empty.
+async fn with_blocking<S, R>(slot: &mut Option<S>, f: impl FnOnce(&mut S) -> R + Send + 'static) -> Result<R, FfmpegError>
+where
+    S: Send + 'static,
+    R: Send + 'static,
+{
+    let mut value = slot
+        .take()
+        .ok_or(FfmpegError::Arguments("closed because a previous operation was cancelled"))?;
+
+    let (value, result) = spawn_blocking(move || {
+        let result = f(&mut value);
+        (value, result)
+    })
+    .await?;
+
+    *slot = Some(value);
+    Ok(result)
+}
+
+/// An [`Input`] reading from an [`AsyncRead`], such as a network socket.
+///
+/// FFmpeg reads synchronously, so opening the input and receiving packets run on
+/// a blocking thread of the Tokio runtime, which waits on the reader. The async
+/// methods therefore never block the calling task.
+///
+/// The async methods are not cancel safe. If one of their futures is dropped before
+/// it finishes, the input is closed and every later call returns an error.
+pub struct AsyncInput<T: Send + Sync + 'static> {
+    input: Option<Input<AsyncIoCompat<T>>>,
+}
+
+impl<T: AsyncRead + Unpin + Send + Sync + 'static> AsyncInput<T> {
+    /// Opens an input reading from `reader`, with the default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub async fn new(reader: T) -> Result<Self, FfmpegError> {
+        Self::with_options(reader, InputOptions::default()).await
+    }
+
+    /// Opens an input reading from `reader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub async fn with_options<I>(reader: T, mut options: InputOptions<I>) -> Result<Self, FfmpegError>
+    where
+        I: FnMut() -> bool + Send + 'static,
+    {
+        let reader = AsyncIoCompat::new(reader, Handle::current());
+        let input = spawn_blocking(move || Input::with_options(reader, &mut options)).await??;
+
+        Ok(Self { input: Some(input) })
+    }
+
+    /// Opens a seekable input reading from `reader`, with the default options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub async fn seekable(reader: T) -> Result<Self, FfmpegError>
+    where
+        T: AsyncSeek,
+    {
+        Self::seekable_with_options(reader, InputOptions::default()).await
+    }
+
+    /// Opens a seekable input reading from `reader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub async fn seekable_with_options<I>(reader: T, options: InputOptions<I>) -> Result<Self, FfmpegError>
+    where
+        T: AsyncSeek,
+        I: FnMut() -> bool + Send + 'static,
+    {
+        let reader = AsyncIoCompat::new(reader, Handle::current());
+        let input = spawn_blocking(move || Input::seekable_with_options(reader, options)).await??;
+
+        Ok(Self { input: Some(input) })
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncInput<T> {
+    /// Receives the next packet, `None` once the input is exhausted.
+    pub async fn receive_packet(&mut self) -> Result<Option<Packet>, FfmpegError> {
+        self.with_input(Input::receive_packet).await?
+    }
+
+    /// Runs `f` with the input on a blocking thread.
+    ///
+    /// Use this for operations without an async wrapper that may read from the input.
+    pub async fn with_input<R: Send + 'static>(
+        &mut self,
+        f: impl FnOnce(&mut Input<AsyncIoCompat<T>>) -> R + Send + 'static,
+    ) -> Result<R, FfmpegError> {
+        with_blocking(&mut self.input, f).await
+    }
+
+    /// Returns the input, to inspect its streams.
+    ///
+    /// Returns `None` if the input was closed by a cancelled operation.
+    pub const fn input(&self) -> Option<&Input<AsyncIoCompat<T>>> {
+        self.input.as_ref()
+    }
+
+    /// Returns the input mutably, for operations that do not read from it.
+    ///
+    /// Returns `None` if the input was closed by a cancelled operation.
+    pub const fn input_mut(&mut self) -> Option<&mut Input<AsyncIoCompat<T>>> {
+        self.input.as_mut()
+    }
+
+    /// Consumes the `AsyncInput` and returns the input, to be used from a blocking thread.
+    ///
+    /// Returns `None` if the input was closed by a cancelled operation.
+    pub fn into_input(self) -> Option<Input<AsyncIoCompat<T>>> {
+        self.input
+    }
+}
+
+/// An [`Output`] writing to an [`AsyncWrite`], such as a network socket.
+///
+/// Adding streams does not write anything and is done on the output returned by
+/// [`AsyncOutput::output_mut`]. Writing the header, packets and trailer runs on
+/// a blocking thread of the Tokio runtime, which waits on the writer.
+///
+/// The async methods are not cancel safe. If one of their futures is dropped before
+/// it finishes, the output is closed and every later call returns an error.
+pub struct AsyncOutput<T: Send + Sync + 'static> {
+    output: Option<Output<AsyncIoCompat<T>>>,
+}
+
+impl<T: AsyncWrite + Unpin + Send + Sync + 'static> AsyncOutput<T> {
+    /// Creates an output writing to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn new(writer: T, options: OutputOptions) -> Result<Self, FfmpegError> {
+        Ok(Self {
+            output: Some(Output::new(AsyncIoCompat::new(writer, Handle::current()), options)?),
+        })
+    }
+
+    /// Creates a seekable output writing to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn seekable(writer: T, options: OutputOptions) -> Result<Self, FfmpegError>
+    where
+        T: AsyncSeek,
+    {
+        Ok(Self {
+            output: Some(Output::seekable(AsyncIoCompat::new(writer, Handle::current()), options)?),
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncOutput<T> {
+    /// Writes the header, see [`Output::write_header`].
+    pub async fn write_header(&mut self) -> Result<(), FfmpegError> {
+        self.with_output(Output::write_header).await?
+    }
+
+    /// Writes the header with muxer options, see [`Output::write_header_with_options`].
+    ///
+    /// Returns the options the muxer did not use.
+    pub async fn write_header_with_options(&mut self, mut options: Dictionary) -> Result<Dictionary, FfmpegError> {
+        self.with_output(move |output| output.write_header_with_options(&mut options).map(|_| options))
+            .await?
+    }
+
+    /// Writes a packet, see [`Output::write_packet`].
+    pub async fn write_packet(&mut self, packet: Packet) -> Result<(), FfmpegError> {
+        self.with_output(move |output| output.write_packet(&packet)).await?
+    }
+
+    /// Writes a packet in interleaved order, see [`Output::write_interleaved_packet`].
+    pub async fn write_interleaved_packet(&mut self, packet: Packet) -> Result<(), FfmpegError> {
+        self.with_output(move |output| output.write_interleaved_packet(packet))
+            .await?
+    }
+
+    /// Writes the trailer, see [`Output::write_trailer`].
+    pub async fn write_trailer(&mut self) -> Result<(), FfmpegError> {
+        self.with_output(Output::write_trailer).await?
+    }
+
+    /// Flushes the io buffer to the writer, see [`Output::flush_io`].
+    pub async fn flush_io(&mut self) -> Result<(), FfmpegError> {
+        self.with_output(Output::flush_io).await?
+    }
+
+    /// Runs `f` with the output on a blocking thread.
+    ///
+    /// Use this for operations without an async wrapper that may write to the output.
+    pub async fn with_output<R: Send + 'static>(
+        &mut self,
+        f: impl FnOnce(&mut Output<AsyncIoCompat<T>>) -> R + Send + 'static,
+    ) -> Result<R, FfmpegError> {
+        with_blocking(&mut self.output, f).await
+    }
+
+    /// Returns the output, to add streams or set metadata before the header is written.
+    ///
+    /// Returns `None` if the output was closed by a cancelled operation.
+    pub const fn output_mut(&mut self) -> Option<&mut Output<AsyncIoCompat<T>>> {
+        self.output.as_mut()
+    }
+
+    /// Consumes the `AsyncOutput` and returns the writer.
+    ///
+    /// Returns `None` if the output was closed by a cancelled operation.
+    pub fn into_inner(self) -> Option<T> {
+        self.output.map(|output| output.into_inner().into_inner())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::io::{AsyncInput, AsyncOutput, OutputOptions};
+
+    fn asset() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets/avc_aac_large.mp4")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_input_seekable() {
+        let file = tokio::fs::File::open(asset()).await.expect("failed to open asset");
+        let mut input = AsyncInput::seekable(file).await.expect("failed to open input");
+
+        assert_eq!(input.input().unwrap().streams().len(), 2);
+
+        let mut packets = 0;
+        while input.receive_packet().await.expect("failed to receive packet").is_some() {
+            packets += 1;
+        }
+
+        assert!(packets > 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_remux() {
+        let file = tokio::fs::File::open(asset()).await.expect("failed to open asset");
+        let mut input = AsyncInput::seekable(file).await.expect("failed to open input");
+
+        let mut output = AsyncOutput::new(Vec::new(), OutputOptions::builder().format_name("mpegts").unwrap().build())
+            .expect("failed to create output");
+
+        for stream in input.input_mut().unwrap().streams_mut() {
+            output
+                .output_mut()
+                .unwrap()
+                .copy_stream(&stream)
+                .expect("failed to copy stream")
+                .expect("failed to add stream");
+        }
+
+        output.write_header().await.expect("failed to write header");
+        while let Some(packet) = input.receive_packet().await.expect("failed to receive packet") {
+            output.write_interleaved_packet(packet).await.expect("failed to write packet");
+        }
+        output.write_trailer().await.expect("failed to write trailer");
+
+        let bytes = output.into_inner().expect("output was closed");
+        assert!(!bytes.is_empty());
+        assert_eq!(bytes[0], 0x47, "expected an mpegts sync byte");
+    }
+}
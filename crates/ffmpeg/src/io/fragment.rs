@@ -0,0 +1,198 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::internal::{WriteBuffer, seek, write_packet};
+use crate::AVSeekWhence;
+use crate::ffi::*;
+use crate::utils::check_i64;
+
+/// Options for writing fragmented MP4, such as CMAF chunks for LL-HLS or DASH.
+///
+/// Each option corresponds to a `movflags` flag or option of the `mp4` muxer. They
+/// can only be used with MP4 based formats, such as `mp4`, `mov` and `ismv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bon::Builder)]
+pub struct FragmentedMp4Options {
+    /// Starts a new fragment at every video keyframe, `frag_keyframe`.
+    #[builder(default = true)]
+    pub frag_keyframe: bool,
+    /// Writes a `moov` box without samples with the header, `empty_moov`.
+    ///
+    /// Without it the first fragment is written as part of the `moov` box.
+    #[builder(default = true)]
+    pub empty_moov: bool,
+    /// Makes the data offsets of a fragment relative to its `moof` box, `default_base_moof`.
+    ///
+    /// Required by CMAF, so fragments can be served on their own.
+    #[builder(default = true)]
+    pub default_base_moof: bool,
+    /// Starts a new fragment once the current one is this long, `frag_duration`.
+    ///
+    /// Combined with [`frag_keyframe`](Self::frag_keyframe), fragments can also start
+    /// between keyframes, which is how LL-HLS parts are made.
+    pub fragment_duration: Option<Duration>,
+}
+
+impl Default for FragmentedMp4Options {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl FragmentedMp4Options {
+    /// Returns the `movflags` to add, in the syntax of a flags option.
+    pub(crate) fn movflags(&self) -> String {
+        [
+            (self.frag_keyframe, "+frag_keyframe"),
+            (self.empty_moov, "+empty_moov"),
+            (self.default_base_moof, "+default_base_moof"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, flag)| enabled.then_some(flag))
+        .collect()
+    }
+}
+
+/// A fragment written to an [`Output`](super::Output), a `moof` box followed by its `mdat` box.
+///
+/// The bytes before the first fragment are the header, the init segment of CMAF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFragment {
+    /// The offset of the fragment from the start of the output, in bytes.
+    pub offset: u64,
+    /// The size of the fragment, in bytes.
+    pub size: u64,
+    /// The decode timestamp of the first sample in `AV_TIME_BASE` units, if the muxer reported it.
+    pub time: Option<i64>,
+    /// True if the fragment starts with a keyframe.
+    pub keyframe: bool,
+}
+
+/// The callback an [`Output`](super::Output) reports each [`OutputFragment`] to,
+/// see [`OutputOptionsBuilder::on_fragment`](super::OutputOptionsBuilder::on_fragment).
+#[derive(Clone)]
+pub(crate) struct FragmentCallback(pub(crate) Arc<dyn Fn(&OutputFragment) + Send + Sync>);
+
+impl std::fmt::Debug for FragmentCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("FragmentCallback").finish_non_exhaustive()
+    }
+}
+
+/// Sits between the io context of an output and its writer, to find the fragments
+/// in the data markers the muxer writes.
+///
+/// The io context passes data to the writer once its buffer is flushed, and the
+/// muxer places a marker before every fragment. Once the data after a marker is
+/// written and nothing is left in the buffer, the fragment is complete.
+pub(crate) struct FragmentSink<T> {
+    writer: *mut T,
+    callback: FragmentCallback,
+    /// The position of the writer.
+    position: u64,
+    /// The fragment being written, reported once it is complete.
+    pending: Option<OutputFragment>,
+}
+
+impl<T> FragmentSink<T> {
+    pub(crate) const fn new(writer: *mut T, callback: FragmentCallback) -> Self {
+        Self {
+            writer,
+            callback,
+            position: 0,
+            pending: None,
+        }
+    }
+
+    /// Reports the fragment being written, if any.
+    pub(crate) fn finish(&mut self) {
+        if let Some(mut fragment) = self.pending.take() {
+            fragment.size = self.position.saturating_sub(fragment.offset);
+            (self.callback.0)(&fragment);
+        }
+    }
+}
+
+/// Safety: The function must be used with the same type as the one used to
+/// generically create the function pointer, and the opaque pointer must be a `FragmentSink<T>`.
+pub(crate) unsafe extern "C" fn write_data_type<T: std::io::Write>(
+    opaque: *mut libc::c_void,
+    buf: WriteBuffer,
+    buf_size: i32,
+    data_type: AVIODataMarkerType,
+    time: i64,
+) -> i32 {
+    // Safety: The pointer is valid given the way this function is constructed, the opaque pointer is a pointer to a FragmentSink<T>.
+    let sink = unsafe { &mut *(opaque as *mut FragmentSink<T>) };
+
+    match data_type {
+        AVIO_DATA_MARKER_SYNC_POINT | AVIO_DATA_MARKER_BOUNDARY_POINT => {
+            sink.finish();
+            sink.pending = Some(OutputFragment {
+                offset: sink.position,
+                size: 0,
+                time: check_i64(time),
+                keyframe: data_type == AVIO_DATA_MARKER_SYNC_POINT,
+            });
+        }
+        AVIO_DATA_MARKER_HEADER | AVIO_DATA_MARKER_TRAILER => sink.finish(),
+        _ => {}
+    }
+
+    // Safety: The writer is valid for as long as the sink, and `write_packet` is called with the same type.
+    let ret = unsafe { write_packet::<T>(sink.writer.cast(), buf, buf_size) };
+    if ret > 0 {
+        sink.position += ret as u64;
+    }
+
+    ret
+}
+
+/// Safety: The function must be used with the same type as the one used to
+/// generically create the function pointer, and the opaque pointer must be a `FragmentSink<T>`.
+pub(crate) unsafe extern "C" fn write_sink<T: std::io::Write>(
+    opaque: *mut libc::c_void,
+    buf: WriteBuffer,
+    buf_size: i32,
+) -> i32 {
+    // Safety: The function has the same requirements as this one.
+    unsafe { write_data_type::<T>(opaque, buf, buf_size, AVIO_DATA_MARKER_UNKNOWN, AV_NOPTS_VALUE) }
+}
+
+/// Safety: The function must be used with the same type as the one used to
+/// generically create the function pointer, and the opaque pointer must be a `FragmentSink<T>`.
+pub(crate) unsafe extern "C" fn seek_sink<T: std::io::Seek>(opaque: *mut libc::c_void, offset: i64, whence: i32) -> i64 {
+    // Safety: The pointer is valid given the way this function is constructed, the opaque pointer is a pointer to a FragmentSink<T>.
+    let sink = unsafe { &mut *(opaque as *mut FragmentSink<T>) };
+
+    // Safety: The writer is valid for as long as the sink, and `seek` is called with the same type.
+    let ret = unsafe { seek::<T>(sink.writer.cast(), offset, whence) };
+
+    // Size requests do not move the writer.
+    if ret >= 0 && AVSeekWhence(whence) & AVSeekWhence::Size == 0 {
+        sink.position = ret as u64;
+    }
+
+    ret
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::io::FragmentedMp4Options;
+
+    #[test]
+    fn test_fragmented_mp4_movflags() {
+        let options = FragmentedMp4Options::default();
+        assert_eq!(options.movflags(), "+frag_keyframe+empty_moov+default_base_moof");
+        assert_eq!(options.fragment_duration, None);
+
+        let options = FragmentedMp4Options::builder()
+            .empty_moov(false)
+            .default_base_moof(false)
+            .fragment_duration(Duration::from_millis(500))
+            .build();
+        assert_eq!(options.movflags(), "+frag_keyframe");
+    }
+}
@@ -1,13 +1,18 @@
 use std::ffi::CStr;
 
 use super::internal::{Inner, InnerOptions, read_packet, seek};
+use crate::chapter::{Chapter, read_chapters};
 use crate::consts::{Const, DEFAULT_BUFFER_SIZE};
+#[cfg(feature = "avdevice")]
+use crate::device::InputDevice;
 use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::packet::{Packet, Packets};
+use crate::program::{Program, raw_programs, raw_stream_indices, read_programs};
 use crate::smart_object::SmartObject;
 use crate::stream::Streams;
+use crate::{AVDiscard, AVFmtFlags};
 
 /// Represents an input stream.
 pub struct Input<T: Send + Sync> {
@@ -18,14 +23,40 @@ pub struct Input<T: Send + Sync> {
 unsafe impl<T: Send + Sync> Send for Input<T> {}
 
 /// Represents the options for an input stream.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, bon::Builder)]
+#[builder(start_fn(vis = "", name = builder_internal))]
 pub struct InputOptions<I: FnMut() -> bool> {
-    /// The buffer size for the input stream.
+    /// The size of the io buffer for the input stream, in bytes.
+    ///
+    /// Each read asks the reader for up to this many bytes. Live inputs want a
+    /// small buffer, batch jobs read fewer and larger chunks with a large one.
+    /// Must be between 1 and `i32::MAX`.
+    #[builder(default = DEFAULT_BUFFER_SIZE)]
     pub buffer_size: usize,
     /// The dictionary for the input stream.
+    #[builder(default)]
     pub dictionary: Dictionary,
     /// The interrupt callback for the input stream.
     pub interrupt_callback: Option<I>,
+    /// Format flags to enable on the demuxer, equivalent to `-fflags`.
+    ///
+    /// The flags are added on top of the flags ffmpeg enables by default.
+    /// Live ingestion typically wants [`AVFmtFlags::NoBuffer`], while inputs
+    /// with broken timestamps can use [`AVFmtFlags::GenPts`] or [`AVFmtFlags::IgnoreDts`].
+    pub format_flags: Option<AVFmtFlags>,
+    /// The maximum number of bytes read while probing the stream info, equivalent to `-probesize`.
+    pub probe_size: Option<i64>,
+    /// The maximum duration in microseconds analyzed while probing the input, equivalent to `-analyzeduration`.
+    pub analyze_duration: Option<i64>,
+    /// The maximum demux-decode delay in microseconds, equivalent to `-max_delay`.
+    pub max_delay: Option<i32>,
+}
+
+impl InputOptions<fn() -> bool> {
+    /// Creates a builder for [`InputOptions`] without an interrupt callback.
+    pub fn builder() -> InputOptionsBuilder<fn() -> bool> {
+        Self::builder_internal()
+    }
 }
 
 /// Default implementation for `InputOptions`.
@@ -35,7 +66,24 @@ impl Default for InputOptions<fn() -> bool> {
             buffer_size: DEFAULT_BUFFER_SIZE,
             dictionary: Dictionary::new(),
             interrupt_callback: None,
+            format_flags: None,
+            probe_size: None,
+            analyze_duration: None,
+            max_delay: None,
+        }
+    }
+}
+
+impl<I: FnMut() -> bool> InputOptions<I> {
+    /// Applies the typed format options to the context before the input is opened.
+    fn apply(&self, context: &mut AVFormatContext) {
+        if let Some(format_flags) = self.format_flags {
+            context.flags |= format_flags.0;
         }
+
+        context.probesize = self.probe_size.unwrap_or(context.probesize);
+        context.max_analyze_duration = self.analyze_duration.unwrap_or(context.max_analyze_duration);
+        context.max_delay = self.max_delay.unwrap_or(context.max_delay);
     }
 }
 
@@ -57,7 +105,8 @@ impl<T: std::io::Read + Send + Sync> Input<T> {
                 },
             )?,
             None,
-            &mut options.dictionary,
+            std::ptr::null(),
+            options,
         )
     }
 
@@ -85,7 +134,8 @@ impl<T: std::io::Read + Send + Sync> Input<T> {
                 },
             )?,
             None,
-            &mut options.dictionary,
+            std::ptr::null(),
+            &mut options,
         )
     }
 }
@@ -116,6 +166,62 @@ impl<T: Send + Sync> Input<T> {
         unsafe { Streams::new(self.inner.inner_mut().context.as_mut_ptr()) }
     }
 
+    /// Returns the metadata of the input, such as its title.
+    pub const fn metadata(&self) -> Const<'_, Dictionary> {
+        // Safety: The metadata does not outlive the input, see `Const::new`.
+        Const::new(unsafe { Dictionary::from_ptr_ref(self.inner.inner_ref().context.as_deref_except().metadata) })
+    }
+
+    /// Returns the chapters of the input.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        read_chapters(self.inner.inner_ref().context.as_deref_except())
+    }
+
+    /// Returns the programs of the input, MPEG-TS inputs can have several.
+    ///
+    /// Inputs without programs return no programs, all of their streams belong together.
+    pub fn programs(&self) -> Vec<Program> {
+        read_programs(self.inner.inner_ref().context.as_deref_except())
+    }
+
+    /// Only demuxes the streams of the program with the given id.
+    ///
+    /// The other programs and the streams that are not part of the program are
+    /// discarded, so [`Input::packets`] no longer returns their packets.
+    pub fn select_program(&mut self, id: i32) -> Result<(), FfmpegError> {
+        let context = self.inner.inner_mut().context.as_deref_mut_except();
+        let mut stream_indices = None;
+        for &program in raw_programs(context) {
+            // Safety: The programs are valid for as long as the input.
+            let Some(program) = (unsafe { program.as_mut() }) else {
+                continue;
+            };
+
+            if program.id == id {
+                program.discard = AVDiscard::Default.into();
+                stream_indices = Some(raw_stream_indices(program).to_vec());
+            } else {
+                program.discard = AVDiscard::All.into();
+            }
+        }
+
+        let stream_indices = stream_indices.ok_or(FfmpegError::Arguments("program not found"))?;
+        let mut streams = self.streams_mut();
+        for index in 0..streams.len() {
+            let Some(mut stream) = streams.get(index) else {
+                continue;
+            };
+
+            if stream_indices.contains(&(index as u32)) {
+                stream.set_discard(AVDiscard::Default);
+            } else {
+                stream.set_discard(AVDiscard::All);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the packets of the input stream.
     pub const fn packets(&mut self) -> Packets<'_> {
         // Safety: See the documentation of `Packets::new`.
@@ -127,14 +233,27 @@ impl<T: Send + Sync> Input<T> {
         self.packets().receive()
     }
 
-    fn create_input(mut inner: Inner<T>, path: Option<&CStr>, dictionary: &mut Dictionary) -> Result<Self, FfmpegError> {
+    fn create_input(
+        mut inner: Inner<T>,
+        path: Option<&CStr>,
+        format: *const AVInputFormat,
+        options: &mut InputOptions<impl FnMut() -> bool>,
+    ) -> Result<Self, FfmpegError> {
+        if inner.context.as_ptr().is_null() {
+            // The context has to exist before opening the input so that the options can be applied to it.
+            // Safety: avformat_alloc_context is safe to call
+            *inner.context.as_mut() = unsafe { avformat_alloc_context() };
+        }
+
+        options.apply(inner.context.as_deref_mut().ok_or(FfmpegError::Alloc)?);
+
         // Safety: avformat_open_input is safe to call
         FfmpegErrorCode(unsafe {
             avformat_open_input(
                 inner.context.as_mut(),
                 path.map(|p| p.as_ptr()).unwrap_or(std::ptr::null()),
-                std::ptr::null(),
-                dictionary.as_mut_ptr_ref(),
+                format,
+                options.dictionary.as_mut_ptr_ref(),
             )
         })
         .result()?;
@@ -161,11 +280,38 @@ impl<T: Send + Sync> Input<T> {
 impl Input<()> {
     /// Opens an input stream from a file path.
     pub fn open(path: &str) -> Result<Self, FfmpegError> {
+        Self::open_with_options(path, &mut InputOptions::default())
+    }
+
+    /// Opens an input stream from a file path with custom options.
+    ///
+    /// The buffer size is ignored as ffmpeg manages the io context for file paths.
+    pub fn open_with_options(path: &str, options: &mut InputOptions<impl FnMut() -> bool>) -> Result<Self, FfmpegError> {
         // We immediately create an input and setup the inner, before using it.
         // Safety: When we pass this inner to `create_input` with a valid path, the inner will be initialized by ffmpeg using the path.
         let inner = unsafe { Inner::empty() };
 
-        Self::create_input(inner, Some(&std::ffi::CString::new(path).unwrap()), &mut Dictionary::new())
+        Self::create_input(inner, Some(&std::ffi::CString::new(path).unwrap()), std::ptr::null(), options)
+    }
+
+    /// Opens a source of a capture device, such as `/dev/video0` with `v4l2`.
+    ///
+    /// The names of the sources of a device are listed by [`InputDevice::sources`].
+    /// Device specific settings, such as `video_size` or `framerate`, are passed in
+    /// the dictionary of the options. The buffer size is ignored.
+    #[cfg(feature = "avdevice")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "avdevice")))]
+    pub fn open_device(
+        device: InputDevice,
+        source: &str,
+        options: &mut InputOptions<impl FnMut() -> bool>,
+    ) -> Result<Self, FfmpegError> {
+        let source = std::ffi::CString::new(source).map_err(|_| FfmpegError::Arguments("source contains a nul byte"))?;
+
+        // Safety: When we pass this inner to `create_input` with a valid path, the inner will be initialized by ffmpeg using the path.
+        let inner = unsafe { Inner::empty() };
+
+        Self::create_input(inner, Some(&source), device.as_ptr(), options)
     }
 }
 
@@ -177,6 +323,8 @@ mod tests {
     use insta::Settings;
 
     use super::{DEFAULT_BUFFER_SIZE, FfmpegError, Input, InputOptions};
+    use crate::io::{Output, OutputOptions};
+    use crate::{AVDiscard, AVFmtFlags, AVMediaType};
 
     fn configure_insta_filters(settings: &mut Settings) {
         settings.add_filter(r"0x0000000000000000", "[NULL_POINTER]");
@@ -190,6 +338,71 @@ mod tests {
         assert_eq!(default_options.buffer_size, DEFAULT_BUFFER_SIZE);
         assert!(default_options.dictionary.is_empty());
         assert!(default_options.interrupt_callback.is_none());
+        assert!(default_options.format_flags.is_none());
+        assert!(default_options.probe_size.is_none());
+        assert!(default_options.analyze_duration.is_none());
+        assert!(default_options.max_delay.is_none());
+    }
+
+    #[test]
+    fn test_input_options_apply() {
+        let mut options = InputOptions::builder()
+            .format_flags(AVFmtFlags::NoBuffer | AVFmtFlags::GenPts)
+            .probe_size(4096)
+            .analyze_duration(500_000)
+            .max_delay(0)
+            .build();
+
+        assert_eq!(options.buffer_size, DEFAULT_BUFFER_SIZE);
+
+        let input =
+            Input::open_with_options("../../assets/avc_aac_large.mp4", &mut options).expect("Failed to open valid file");
+        // Safety: The pointer is valid for the lifetime of the input.
+        let context = unsafe { &*input.as_ptr() };
+
+        assert_ne!(context.flags & AVFmtFlags::NoBuffer.0, 0);
+        assert_ne!(context.flags & AVFmtFlags::GenPts.0, 0);
+        assert_eq!(context.probesize, 4096);
+        assert_eq!(context.max_analyze_duration, 500_000);
+        assert_eq!(context.max_delay, 0);
+    }
+
+    #[test]
+    fn test_input_programs() {
+        let input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+        assert!(input.programs().is_empty());
+        assert!(input.chapters().is_empty());
+
+        // The MPEG-TS muxer puts all streams into a single program with the id 1.
+        let mut output = Output::seekable(
+            Cursor::new(Vec::new()),
+            OutputOptions::builder().format_name("mpegts").unwrap().build(),
+        )
+        .expect("Failed to create Output");
+        let streams = input.streams();
+        for stream in streams.iter() {
+            output.copy_stream(&stream).expect("Failed to copy stream");
+        }
+        output.write_header().expect("Failed to write header");
+        output.write_trailer().expect("Failed to write trailer");
+
+        let mut input = Input::seekable(Cursor::new(output.into_inner().into_inner())).expect("Failed to read output");
+        let programs = input.programs();
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].id, 1);
+        assert!(programs[0].contains_stream(0));
+        assert!(programs[0].contains_stream(1));
+
+        let streams = input.streams();
+        assert_eq!(
+            streams.best_index_in_program(AVMediaType::Audio, &programs[0]),
+            streams.best_index(AVMediaType::Audio)
+        );
+        drop(streams);
+
+        assert!(input.select_program(2).is_err());
+        input.select_program(1).expect("Failed to select program");
+        assert_eq!(input.programs()[0].discard, AVDiscard::Default);
     }
 
     #[test]
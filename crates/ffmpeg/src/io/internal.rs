@@ -7,6 +7,13 @@ use crate::{AVIOFlag, AVSeekWhence};
 
 const AVERROR_IO: i32 = AVERROR(EIO);
 
+/// The buffer passed to the write callback, which is only const since FFmpeg 7.
+#[cfg(ffmpeg_7)]
+pub(crate) type WriteBuffer = *const u8;
+/// The buffer passed to the write callback, which is only const since FFmpeg 7.
+#[cfg(not(ffmpeg_7))]
+pub(crate) type WriteBuffer = *mut u8;
+
 /// Safety: The function must be used with the same type as the one used to
 /// generically create the function pointer
 pub(crate) unsafe extern "C" fn read_packet<T: std::io::Read>(
@@ -32,7 +39,7 @@ pub(crate) unsafe extern "C" fn read_packet<T: std::io::Read>(
 /// generically create the function pointer
 pub(crate) unsafe extern "C" fn write_packet<T: std::io::Write>(
     opaque: *mut libc::c_void,
-    buf: *const u8,
+    buf: WriteBuffer,
     buf_size: i32,
 ) -> i32 {
     // Safety: The pointer is valid given the way this function is constructed, the opaque pointer is a pointer to a T.
@@ -92,16 +99,18 @@ pub(crate) unsafe extern "C" fn seek<T: std::io::Seek>(opaque: *mut libc::c_void
     }
 }
 
+// The fields are dropped in declaration order. The format context and the io context
+// both point into `data`, so they have to be freed before it.
 pub(crate) struct Inner<T: Send + Sync> {
-    pub(crate) data: Option<Box<T>>,
     pub(crate) context: SmartPtr<AVFormatContext>,
     _io: SmartPtr<AVIOContext>,
+    pub(crate) data: Option<Box<T>>,
 }
 
 pub(crate) struct InnerOptions {
     pub(crate) buffer_size: usize,
     pub(crate) read_fn: Option<unsafe extern "C" fn(*mut c_void, *mut u8, i32) -> i32>,
-    pub(crate) write_fn: Option<unsafe extern "C" fn(*mut c_void, *const u8, i32) -> i32>,
+    pub(crate) write_fn: Option<unsafe extern "C" fn(*mut c_void, WriteBuffer, i32) -> i32>,
     pub(crate) seek_fn: Option<unsafe extern "C" fn(*mut c_void, i64, i32) -> i64>,
     pub(crate) output_format: *const AVOutputFormat,
 }
@@ -121,6 +130,10 @@ impl Default for InnerOptions {
 impl<T: Send + Sync> Inner<T> {
     /// Creates a new `Inner` instance.
     pub fn new(data: T, options: InnerOptions) -> Result<Self, FfmpegError> {
+        if options.buffer_size == 0 || options.buffer_size > i32::MAX as usize {
+            return Err(FfmpegError::Arguments("buffer size must be between 1 and i32::MAX"));
+        }
+
         // Safety: av_malloc is safe to call
         let buffer = unsafe { av_malloc(options.buffer_size) };
 
@@ -213,9 +226,9 @@ impl<T: Send + Sync> Inner<T> {
         context.as_deref_mut().expect("Context is null").pb = io.as_mut_ptr();
 
         Ok(Self {
-            data: Some(data),
             context,
             _io: io,
+            data: Some(data),
         })
     }
 }
@@ -225,7 +238,6 @@ impl Inner<()> {
     /// Safety: this function is marked as unsafe because it must be initialized and setup correctltly before returning it to the user.
     pub unsafe fn empty() -> Self {
         Self {
-            data: Some(Box::new(())),
             context: SmartPtr::null(|mut_ref| {
                 // We own this resource so we need to free it
                 let ptr = *mut_ref;
@@ -234,6 +246,7 @@ impl Inner<()> {
                 *mut_ref = std::ptr::null_mut();
             }),
             _io: SmartPtr::null(|_| {}),
+            data: Some(Box::new(())),
         }
     }
 
@@ -322,7 +335,8 @@ mod tests {
 
         // Safety: The pointer is valid.
         unsafe {
-            let result = write_packet::<Cursor<Vec<u8>>>((&raw mut data) as *mut c_void, buf.as_ptr(), buf.len() as i32);
+            let result =
+                write_packet::<Cursor<Vec<u8>>>((&raw mut data) as *mut c_void, buf.as_ptr() as _, buf.len() as i32);
             assert_eq!(result, buf.len() as i32);
 
             let written_data = data.get_ref();
@@ -380,7 +394,7 @@ mod tests {
             CALL_COUNT.store(0, Ordering::SeqCst);
         });
 
-        unsafe extern "C" fn dummy_write_fn(_opaque: *mut libc::c_void, _buf: *const u8, _buf_size: i32) -> i32 {
+        unsafe extern "C" fn dummy_write_fn(_opaque: *mut libc::c_void, _buf: WriteBuffer, _buf_size: i32) -> i32 {
             CALL_COUNT.fetch_add(1, Ordering::SeqCst);
             BUF_SIZE_TRACKER.store(_buf_size as usize, Ordering::SeqCst);
             0 // simulate success
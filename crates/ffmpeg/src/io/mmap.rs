@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::input::{Input, InputOptions};
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::AVERROR;
+
+/// A read-only memory mapped file, used as the source of an [`Input`].
+///
+/// Reading copies straight from the mapped pages into the io buffer of FFmpeg,
+/// there is no read syscall and no intermediate buffer, and seeking only moves an
+/// offset. This helps with large local files and seek heavy workloads, such as
+/// generating thumbnails from multi-GB mezzanine files.
+#[derive(Debug)]
+pub struct MappedFile {
+    map: memmap2::Mmap,
+    position: usize,
+}
+
+impl MappedFile {
+    /// Maps the file at `path` into memory.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped, by this or any other process.
+    /// Doing so is undefined behavior, as the mapped memory is treated as immutable.
+    pub unsafe fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        // Safety: the caller guarantees the file is not modified while it is mapped.
+        unsafe { Self::from_file(&File::open(path)?) }
+    }
+
+    /// Maps an open file into memory, the file can be closed afterwards.
+    ///
+    /// # Safety
+    /// See [`MappedFile::open`].
+    pub unsafe fn from_file(file: &File) -> std::io::Result<Self> {
+        // Safety: the caller guarantees the file is not modified while it is mapped.
+        let map = unsafe { memmap2::Mmap::map(file) }?;
+
+        Ok(Self { map, position: 0 })
+    }
+
+    /// Returns the contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Read for MappedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.map.get(self.position..).unwrap_or_default();
+        let len = remaining.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+impl Seek for MappedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.map.len() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => (self.position as u64).checked_add_signed(offset),
+        };
+
+        let position = position
+            .and_then(|position| usize::try_from(position).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        // Seeking past the end is allowed, reads there return no data.
+        self.position = position;
+
+        Ok(position as u64)
+    }
+}
+
+impl Input<MappedFile> {
+    /// Creates a new seekable `Input` from a memory mapped file with default options.
+    pub fn mmap(file: MappedFile) -> Result<Self, FfmpegError> {
+        Self::seekable(file)
+    }
+
+    /// Creates a new seekable `Input` from a memory mapped file with custom options.
+    ///
+    /// As reads are cheap copies from memory, a small buffer size does not cost extra syscalls.
+    pub fn mmap_with_options(file: MappedFile, options: InputOptions<impl FnMut() -> bool>) -> Result<Self, FfmpegError> {
+        Self::seekable_with_options(file, options)
+    }
+
+    /// Maps the file at `path` into memory and opens it as a seekable `Input`.
+    ///
+    /// IO errors are returned as the matching [`FfmpegErrorCode`].
+    ///
+    /// # Safety
+    /// See [`MappedFile::open`].
+    pub unsafe fn mmap_path(path: impl AsRef<Path>) -> Result<Self, FfmpegError> {
+        // Safety: the caller guarantees the file is not modified while it is mapped.
+        let file = unsafe { MappedFile::open(path) }
+            .map_err(|err| FfmpegErrorCode(AVERROR(err.raw_os_error().unwrap_or(libc::EIO))))?;
+
+        Self::mmap(file)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::{Read, Seek, SeekFrom};
+
+    use super::MappedFile;
+    use crate::error::{FfmpegError, FfmpegErrorCode};
+    use crate::ffi::AVERROR;
+    use crate::io::Input;
+    use crate::packet::Packet;
+
+    const PATH: &str = "../../assets/avc_aac_large.mp4";
+
+    #[test]
+    fn test_mapped_file_read_seek() {
+        // Safety: the test asset is not modified.
+        let mut file = unsafe { MappedFile::open(PATH) }.expect("failed to map file");
+        let contents = std::fs::read(PATH).expect("failed to read file");
+        assert_eq!(file.len(), contents.len());
+        assert!(!file.is_empty());
+        assert_eq!(file.as_bytes(), contents.as_slice());
+
+        let mut buf = [0; 8];
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, contents[..8]);
+
+        assert_eq!(file.seek(SeekFrom::End(-4)).unwrap(), contents.len() as u64 - 4);
+        assert_eq!(file.read(&mut buf).unwrap(), 4);
+        assert_eq!(buf[..4], contents[contents.len() - 4..]);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+
+        assert_eq!(file.seek(SeekFrom::Current(-6)).unwrap(), contents.len() as u64 - 6);
+        assert!(file.seek(SeekFrom::Current(-(contents.len() as i64))).is_err());
+
+        // Past the end is allowed, but there is nothing to read.
+        assert_eq!(file.seek(SeekFrom::Start(u32::MAX as u64)).unwrap(), u32::MAX as u64);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_mmap_input() {
+        // Safety: the test asset is not modified.
+        let mut mapped = unsafe { Input::mmap_path(PATH) }.expect("failed to open mapped input");
+        let mut opened = Input::open(PATH).expect("failed to open input");
+
+        assert_eq!(mapped.streams().len(), opened.streams().len());
+
+        let summary = |packet: Result<Packet, FfmpegError>| {
+            let packet = packet.unwrap();
+            (packet.stream_index(), packet.pts(), packet.data().len())
+        };
+        let mapped_packets = mapped.packets().map(summary).collect::<Vec<_>>();
+        let opened_packets = opened.packets().map(summary).collect::<Vec<_>>();
+        assert!(!mapped_packets.is_empty());
+        assert_eq!(mapped_packets, opened_packets);
+    }
+
+    #[test]
+    fn test_mmap_path_missing() {
+        // Safety: the file does not exist.
+        let err = unsafe { Input::mmap_path("invalid_file.mp4") }.unwrap_err();
+        assert_eq!(err, FfmpegError::Code(FfmpegErrorCode(AVERROR(libc::ENOENT))));
+    }
+}
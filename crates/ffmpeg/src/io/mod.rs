@@ -1,11 +1,25 @@
+#[cfg(feature = "tokio")]
+mod async_io;
+mod fragment;
 mod input;
 mod internal;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod output;
+mod tee;
 
 /// A module that contains the channel implementation for io operations.
 #[cfg(feature = "channel")]
 #[cfg_attr(docsrs, doc(cfg(feature = "channel")))]
 pub mod channel;
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use async_io::*;
+pub use fragment::{FragmentedMp4Options, OutputFragment};
 pub use input::*;
+#[cfg(feature = "mmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub use mmap::*;
 pub use output::*;
+pub use tee::{Tee, TeeSink, TeeSinkOptions, TeeSinkStatus};
@@ -1,26 +1,63 @@
 use std::ffi::CString;
 use std::ptr::NonNull;
+use std::sync::Arc;
 
+use super::fragment::{
+    FragmentCallback, FragmentSink, FragmentedMp4Options, OutputFragment, seek_sink, write_data_type, write_sink,
+};
 use super::internal::{Inner, InnerOptions, seek, write_packet};
-use crate::consts::DEFAULT_BUFFER_SIZE;
+use crate::chapter::{Chapter, add_chapter, read_chapters};
+use crate::consts::{Const, DEFAULT_BUFFER_SIZE};
 use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::packet::Packet;
-use crate::stream::Stream;
+use crate::stream::{Stream, Streams};
 use crate::{AVFmtFlags, AVFormatFlags};
 
 /// A struct that represents the options for the output.
 #[derive(Debug, Clone, bon::Builder)]
 pub struct OutputOptions {
-    /// The buffer size for the output.
+    /// The size of the io buffer for the output, in bytes.
+    ///
+    /// Data is only handed to the writer once the buffer is full or flushed.
+    /// Live outputs want a small buffer, or to call [`Output::flush_io`] at
+    /// segment boundaries, while batch jobs write fewer and larger chunks with a large one.
+    /// Must be between 1 and `i32::MAX`.
     #[builder(default = DEFAULT_BUFFER_SIZE)]
     buffer_size: usize,
+    /// Flushes the io buffer after every packet, equivalent to `-flush_packets`.
+    ///
+    /// By default FFmpeg decides based on the output format.
+    flush_packets: Option<bool>,
+    /// Writes fragmented MP4, see [`FragmentedMp4Options`].
+    ///
+    /// Creating the output fails if the format is not MP4 based.
+    fragmented_mp4: Option<FragmentedMp4Options>,
+    #[builder(setters(vis = "", name = on_fragment_internal))]
+    on_fragment: Option<FragmentCallback>,
     #[builder(setters(vis = "", name = format_ffi_internal))]
     format_ffi: *const AVOutputFormat,
 }
 
 impl<S: output_options_builder::State> OutputOptionsBuilder<S> {
+    /// Sets a callback that is called with every fragment of a fragmented output,
+    /// such as the `moof` and `mdat` boxes of fragmented MP4, once all of it was
+    /// handed to the writer.
+    ///
+    /// Only formats that mark their fragments report them, such as MP4 with
+    /// [`fragmented_mp4`](Self::fragmented_mp4) set. The callback is called from the
+    /// write methods of the [`Output`].
+    pub fn on_fragment(
+        self,
+        on_fragment: impl Fn(&OutputFragment) + Send + Sync + 'static,
+    ) -> OutputOptionsBuilder<output_options_builder::SetOnFragment<S>>
+    where
+        S::OnFragment: output_options_builder::IsUnset,
+    {
+        self.on_fragment_internal(FragmentCallback(Arc::new(on_fragment)))
+    }
+
     /// Sets the format FFI.
     ///
     /// Returns an error if the format FFI is null.
@@ -91,6 +128,8 @@ impl<S: output_options_builder::State> OutputOptionsBuilder<S> {
 pub struct Output<T: Send + Sync> {
     inner: Inner<T>,
     state: OutputState,
+    // Dropped after `inner`, the io context points to it.
+    fragments: Option<Box<FragmentSink<T>>>,
 }
 
 /// Safety: `T` must be `Send` and `Sync`.
@@ -124,7 +163,10 @@ impl<T: std::io::Write + Send + Sync> Output<T> {
                 },
             )?,
             state: OutputState::Uninitialized,
-        })
+            fragments: None,
+        }
+        .apply_options(&options)?
+        .with_fragment_sink(&options, None))
     }
 
     /// Creates a new `Output` with the given output and options. The output must be seekable.
@@ -144,12 +186,122 @@ impl<T: std::io::Write + Send + Sync> Output<T> {
                 },
             )?,
             state: OutputState::Uninitialized,
-        })
+            fragments: None,
+        }
+        .apply_options(&options)?
+        .with_fragment_sink(&options, Some(seek_sink::<T>)))
+    }
+
+    /// Routes the writes through a [`FragmentSink`] if a fragment callback is set,
+    /// replacing the seek function with `seek_fn` if given.
+    fn with_fragment_sink(
+        mut self,
+        options: &OutputOptions,
+        seek_fn: Option<unsafe extern "C" fn(*mut libc::c_void, i64, i32) -> i64>,
+    ) -> Self {
+        let Some(callback) = options.on_fragment.clone() else {
+            return self;
+        };
+
+        let Some(writer) = self.inner.data.as_deref_mut() else {
+            return self;
+        };
+
+        let mut sink = Box::new(FragmentSink::new(writer as *mut T, callback));
+
+        let pb = self.inner.context.as_deref_mut_except().pb;
+        // Safety: The io context is valid and non-null for as long as the format context.
+        let pb = unsafe { &mut *pb };
+        pb.opaque = sink.as_mut() as *mut FragmentSink<T> as *mut libc::c_void;
+        pb.write_packet = Some(write_sink::<T>);
+        pb.write_data_type = Some(write_data_type::<T>);
+        if seek_fn.is_some() {
+            pb.seek = seek_fn;
+        }
+
+        self.fragments = Some(sink);
+        self
     }
 }
 
 impl<T: Send + Sync> Output<T> {
-    /// Sets the metadata for the output.
+    /// Applies the options that are set on the format context.
+    fn apply_options(mut self, options: &OutputOptions) -> Result<Self, FfmpegError> {
+        if let Some(flush_packets) = options.flush_packets {
+            self.inner.context.as_deref_mut_except().flush_packets = flush_packets as i32;
+        }
+
+        if let Some(fragmented_mp4) = &options.fragmented_mp4 {
+            self.apply_fragmented_mp4(fragmented_mp4)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the fragmentation options of the muxer.
+    fn apply_fragmented_mp4(&mut self, options: &FragmentedMp4Options) -> Result<(), FfmpegError> {
+        const UNSUPPORTED: FfmpegError = FfmpegError::Arguments("fragmented mp4 options require an mp4 based format");
+
+        let movflags = options.movflags();
+        if !movflags.is_empty() {
+            let movflags = CString::new(movflags).or(Err(UNSUPPORTED))?;
+            // Safety: The context is valid, `av_opt_set` copies the value.
+            FfmpegErrorCode(unsafe {
+                av_opt_set(
+                    self.as_mut_ptr().cast(),
+                    c"movflags".as_ptr(),
+                    movflags.as_ptr(),
+                    AV_OPT_SEARCH_CHILDREN as i32,
+                )
+            })
+            .result()
+            .or(Err(UNSUPPORTED))?;
+        }
+
+        if let Some(fragment_duration) = options.fragment_duration {
+            let micros = fragment_duration.as_micros().min(i32::MAX as u128) as i64;
+            // Safety: The context is valid.
+            FfmpegErrorCode(unsafe {
+                av_opt_set_int(
+                    self.as_mut_ptr().cast(),
+                    c"frag_duration".as_ptr(),
+                    micros,
+                    AV_OPT_SEARCH_CHILDREN as i32,
+                )
+            })
+            .result()
+            .or(Err(UNSUPPORTED))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reports the fragment being written, once the io buffer is empty and all of it reached the writer.
+    fn report_fragment(&mut self) {
+        let Some(sink) = &mut self.fragments else {
+            return;
+        };
+
+        // Safety: The io context is valid for as long as the format context.
+        let Some(pb) = (unsafe { self.inner.context.as_deref_except().pb.as_ref() }) else {
+            return;
+        };
+
+        if pb.buf_ptr == pb.buffer {
+            sink.finish();
+        }
+    }
+
+    /// Returns the metadata of the output.
+    pub const fn metadata(&self) -> Const<'_, Dictionary> {
+        // Safety: The metadata does not outlive the output, see `Const::new`.
+        Const::new(unsafe { Dictionary::from_ptr_ref(self.inner.context.as_deref_except().metadata) })
+    }
+
+    /// Sets the metadata for the output, such as its title and encoder.
+    ///
+    /// Muxers write the metadata with the header, so it must be set before
+    /// [`Output::write_header`] is called.
     pub fn set_metadata(&mut self, metadata: Dictionary) {
         // Safety: We want to replace the metadata from the context (if one exists). This is safe as the metadata should be a valid pointer.
         unsafe {
@@ -159,6 +311,24 @@ impl<T: Send + Sync> Output<T> {
         self.inner.context.as_deref_mut_except().metadata = metadata.leak();
     }
 
+    /// Returns the chapters of the output.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        read_chapters(self.inner.context.as_deref_except())
+    }
+
+    /// Adds a chapter to the output.
+    ///
+    /// Muxers write the chapters with the header or the trailer, so they must
+    /// be added before [`Output::write_header`] is called. Not every container
+    /// supports chapters, `mp4` and `matroska` do.
+    pub fn add_chapter(&mut self, chapter: Chapter) -> Result<(), FfmpegError> {
+        if self.state != OutputState::Uninitialized {
+            return Err(FfmpegError::Arguments("cannot add chapter after header has been written"));
+        }
+
+        add_chapter(self.inner.context.as_deref_mut_except(), chapter)
+    }
+
     /// Returns the pointer to the underlying AVFormatContext.
     pub const fn as_ptr(&self) -> *const AVFormatContext {
         self.inner.context.as_ptr()
@@ -169,6 +339,18 @@ impl<T: Send + Sync> Output<T> {
         self.inner.context.as_mut_ptr()
     }
 
+    /// Returns the streams of the output.
+    ///
+    /// Muxers may change the time base of the streams when the header is written,
+    /// so packets must be converted to the time base read after [`Output::write_header`].
+    pub const fn streams(&self) -> Const<'_, Streams<'_>> {
+        // Safety: See the documentation of `Streams::new`.
+        // We upcast the pointer to be mut because the function signature requires it.
+        // However we do not mutate the pointer as its returned as a `Const<Streams>` which
+        // restricts the mutability of the streams to be const.
+        unsafe { Const::new(Streams::new(self.inner.context.as_ptr() as *mut _)) }
+    }
+
     /// Adds a new stream to the output.
     pub fn add_stream(&mut self, codec: Option<*const AVCodec>) -> Option<Stream<'_>> {
         let mut stream =
@@ -217,8 +399,9 @@ impl<T: Send + Sync> Output<T> {
 
         // Safety: `avformat_write_header` is safe to call, if the header has not been
         // written yet.
-        FfmpegErrorCode(unsafe { avformat_write_header(self.as_mut_ptr(), std::ptr::null_mut()) }).result()?;
+        FfmpegErrorCode(unsafe { avformat_write_header(self.as_mut_ptr(), std::ptr::null_mut()) }).classify()?;
         self.state = OutputState::HeaderWritten;
+        self.report_fragment();
 
         Ok(())
     }
@@ -231,8 +414,9 @@ impl<T: Send + Sync> Output<T> {
 
         // Safety: `avformat_write_header` is safe to call, if the header has not been
         // written yet.
-        FfmpegErrorCode(unsafe { avformat_write_header(self.as_mut_ptr(), options.as_mut_ptr_ref()) }).result()?;
+        FfmpegErrorCode(unsafe { avformat_write_header(self.as_mut_ptr(), options.as_mut_ptr_ref()) }).classify()?;
         self.state = OutputState::HeaderWritten;
+        self.report_fragment();
 
         Ok(())
     }
@@ -246,8 +430,9 @@ impl<T: Send + Sync> Output<T> {
         }
 
         // Safety: `av_write_trailer` is safe to call, once the header has been written.
-        FfmpegErrorCode(unsafe { av_write_trailer(self.as_mut_ptr()) }).result()?;
+        FfmpegErrorCode(unsafe { av_write_trailer(self.as_mut_ptr()) }).classify()?;
         self.state = OutputState::TrailerWritten;
+        self.report_fragment();
 
         Ok(())
     }
@@ -265,7 +450,8 @@ impl<T: Send + Sync> Output<T> {
 
         // Safety: `av_interleaved_write_frame` is safe to call, once the header has
         // been written.
-        FfmpegErrorCode(unsafe { av_interleaved_write_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }).result()?;
+        FfmpegErrorCode(unsafe { av_interleaved_write_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }).classify()?;
+        self.report_fragment();
         Ok(())
     }
 
@@ -278,7 +464,30 @@ impl<T: Send + Sync> Output<T> {
         }
 
         // Safety: `av_write_frame` is safe to call, once the header has been written.
-        FfmpegErrorCode(unsafe { av_write_frame(self.as_mut_ptr(), packet.as_ptr() as *mut _) }).result()?;
+        FfmpegErrorCode(unsafe { av_write_frame(self.as_mut_ptr(), packet.as_ptr() as *mut _) }).classify()?;
+        self.report_fragment();
+        Ok(())
+    }
+
+    /// Writes the data in the io buffer to the output right away, instead of
+    /// waiting for the buffer to fill up.
+    ///
+    /// This only flushes the io layer, packets that are held back by the muxer,
+    /// for example for interleaving, are not written.
+    pub fn flush_io(&mut self) -> Result<(), FfmpegError> {
+        let pb = self.inner.context.as_deref_mut_except().pb;
+        if pb.is_null() {
+            return Ok(());
+        }
+
+        // Safety: `avio_flush` is safe to call, the io context is valid for as long as the format context.
+        unsafe { avio_flush(pb) };
+
+        // Safety: The io context is valid and non-null.
+        let error = unsafe { (*pb).error };
+        FfmpegErrorCode(error).classify()?;
+        self.report_fragment();
+
         Ok(())
     }
 
@@ -304,6 +513,7 @@ impl Output<()> {
         Ok(Self {
             inner: Inner::open_output(path)?,
             state: OutputState::Uninitialized,
+            fragments: None,
         })
     }
 }
@@ -320,10 +530,12 @@ mod tests {
     use sha2::Digest;
     use tempfile::Builder;
 
-    use crate::dict::Dictionary;
+    use crate::chapter::Chapter;
+    use crate::dict::{Dictionary, MetadataKey};
     use crate::error::FfmpegError;
     use crate::io::output::{AVCodec, AVRational, OutputState};
-    use crate::io::{Input, Output, OutputOptions};
+    use crate::io::{FragmentedMp4Options, Input, Output, OutputFragment, OutputOptions};
+    use crate::rational::Rational;
     use crate::{AVFmtFlags, AVMediaType};
 
     #[test]
@@ -394,6 +606,84 @@ mod tests {
         assert!(!output.as_ptr().is_null());
     }
 
+    #[test]
+    fn test_output_metadata_roundtrip() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let video = streams.best(AVMediaType::Video).expect("no video stream found");
+
+        let options = OutputOptions::builder().format_name("matroska").unwrap().build();
+        let mut output = Output::seekable(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+
+        let mut stream = output
+            .copy_stream(&video)
+            .expect("Failed to copy stream")
+            .expect("Failed to copy stream");
+        stream.set_metadata(
+            Dictionary::try_from_iter([(MetadataKey::Title, "Camera 1"), (MetadataKey::Language, "ger")])
+                .expect("Failed to create metadata"),
+        );
+
+        output.set_metadata(
+            Dictionary::try_from_iter([(MetadataKey::Title, "My Stream")]).expect("Failed to create metadata"),
+        );
+        assert_eq!(output.metadata().get_str(MetadataKey::Title), Some("My Stream"));
+
+        output.write_header().expect("Failed to write header");
+        output.write_trailer().expect("Failed to write trailer");
+
+        let data = output.into_inner().into_inner();
+        let input = Input::seekable(Cursor::new(data)).expect("Failed to read output");
+        assert_eq!(input.metadata().get_str(MetadataKey::Title), Some("My Stream"));
+
+        let streams = input.streams();
+        let stream = streams.best(AVMediaType::Video).expect("no video stream found");
+        assert_eq!(stream.metadata().get_str(MetadataKey::Title), Some("Camera 1"));
+        assert_eq!(stream.metadata().get_str(MetadataKey::Language), Some("ger"));
+    }
+
+    #[test]
+    fn test_output_chapters_roundtrip() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let video = streams.best(AVMediaType::Video).expect("no video stream found");
+
+        let options = OutputOptions::builder().format_name("matroska").unwrap().build();
+        let mut output = Output::seekable(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        output.copy_stream(&video).expect("Failed to copy stream");
+
+        let time_base = Rational::static_new::<1, 1000>();
+        let intro = Chapter::new(1, time_base, 0, 500)
+            .with_title("Intro")
+            .expect("Failed to set title");
+        output.add_chapter(intro).expect("Failed to add chapter");
+        output
+            .add_chapter(Chapter::new(2, time_base, 500, 1000))
+            .expect("Failed to add chapter");
+
+        assert!(output.add_chapter(Chapter::new(2, time_base, 1000, 1500)).is_err());
+        assert!(output.add_chapter(Chapter::new(3, time_base, 1500, 1000)).is_err());
+        assert_eq!(output.chapters().len(), 2);
+
+        output.write_header().expect("Failed to write header");
+        assert!(output.add_chapter(Chapter::new(3, time_base, 1000, 1500)).is_err());
+        output.write_trailer().expect("Failed to write trailer");
+
+        let data = output.into_inner().into_inner();
+        let input = Input::seekable(Cursor::new(data)).expect("Failed to read output");
+        let chapters = input.chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title(), Some("Intro"));
+        assert_eq!(chapters[0].start_seconds(), 0.0);
+        assert_eq!(chapters[0].end_seconds(), 0.5);
+        assert_eq!(chapters[1].start_seconds(), 0.5);
+        assert_eq!(chapters[1].end_seconds(), 1.0);
+    }
+
     #[test]
     fn test_output_as_mut_ptr() {
         let data = Cursor::new(Vec::new());
@@ -537,6 +827,54 @@ mod tests {
         insta::assert_debug_snapshot!("test_output_write_mp4_trailer", get_boxes!(output));
     }
 
+    #[test]
+    fn test_output_flush_io() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let best_video_stream = streams.best(AVMediaType::Video).expect("no video stream found");
+
+        let written = |output: &Output<Cursor<Vec<u8>>>| output.inner.data.as_ref().unwrap().get_ref().len();
+
+        let options = OutputOptions::builder()
+            .format_name("flv")
+            .unwrap()
+            .buffer_size(1024 * 1024)
+            .flush_packets(false)
+            .build();
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+        output.write_header().expect("Failed to write header");
+
+        // The header is still in the io buffer.
+        assert_eq!(written(&output), 0);
+
+        output.flush_io().expect("Failed to flush");
+        assert!(written(&output) > 0);
+
+        // Every packet is flushed with `flush_packets`.
+        let options = OutputOptions::builder()
+            .format_name("flv")
+            .unwrap()
+            .buffer_size(1024 * 1024)
+            .flush_packets(true)
+            .build();
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+        output.write_header().expect("Failed to write header");
+        assert!(written(&output) > 0);
+    }
+
+    #[test]
+    fn test_output_invalid_buffer_size() {
+        let options = OutputOptions::builder().format_name("flv").unwrap().buffer_size(0).build();
+        assert!(matches!(
+            Output::new(Cursor::new(Vec::new()), options),
+            Err(FfmpegError::Arguments(_))
+        ));
+    }
+
     #[test]
     fn test_output_write_mp4_fragmented() {
         let data = Cursor::new(Vec::new());
@@ -589,4 +927,71 @@ mod tests {
 
         insta::assert_debug_snapshot!("test_output_write_mp4_fragmented_trailer", get_boxes!(output));
     }
+
+    #[test]
+    fn test_output_on_fragment() {
+        let fragments = std::sync::Arc::new(std::sync::Mutex::new(Vec::<OutputFragment>::new()));
+
+        let options = OutputOptions::builder()
+            .format_name("mp4")
+            .unwrap()
+            .fragmented_mp4(FragmentedMp4Options::default())
+            .on_fragment({
+                let fragments = fragments.clone();
+                move |fragment| fragments.lock().unwrap().push(*fragment)
+            })
+            .build();
+
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+
+        let mut input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let best_video_stream = streams.best(AVMediaType::Video).expect("no video stream found");
+        let best_video_stream_index = best_video_stream.index();
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+
+        output.write_header().expect("Failed to write header");
+        assert!(fragments.lock().unwrap().is_empty(), "Expected no fragment after the header");
+
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() == best_video_stream_index {
+                output.write_packet(&packet).expect("Failed to write packet");
+            }
+        }
+
+        output.write_trailer().expect("Failed to write trailer");
+
+        let data = output.into_inner().into_inner();
+        let fragments = fragments.lock().unwrap();
+        assert!(!fragments.is_empty(), "Expected fragments to be reported");
+
+        // Fragments follow each other, each one starts with a `moof` box.
+        let mut offset = fragments[0].offset;
+        assert!(offset > 0, "Expected the header before the first fragment");
+        for fragment in fragments.iter() {
+            assert_eq!(fragment.offset, offset);
+            assert!(fragment.keyframe);
+
+            let start = fragment.offset as usize;
+            assert_eq!(&data[start + 4..start + 8], b"moof");
+            offset += fragment.size;
+        }
+        assert!(offset as usize <= data.len());
+    }
+
+    #[test]
+    fn test_output_fragmented_mp4_unsupported_format() {
+        let options = OutputOptions::builder()
+            .format_name("flv")
+            .unwrap()
+            .fragmented_mp4(FragmentedMp4Options::default())
+            .build();
+
+        assert!(matches!(
+            Output::new(Cursor::new(Vec::new()), options),
+            Err(FfmpegError::Arguments(_))
+        ));
+    }
 }
@@ -0,0 +1,358 @@
+use super::Output;
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::packet::Packet;
+use crate::rational::Rational;
+
+/// The outputs a [`Tee`] writes to, whatever they write into.
+trait TeeOutput: Send {
+    fn write_header(&mut self) -> Result<(), FfmpegError>;
+
+    fn write_interleaved_packet(&mut self, packet: Packet) -> Result<(), FfmpegError>;
+
+    fn write_trailer(&mut self) -> Result<(), FfmpegError>;
+
+    fn as_ptr(&self) -> *const AVFormatContext;
+
+    /// Returns the time base of the stream at `index`, `None` if the output has no such stream.
+    fn stream_time_base(&self, index: i32) -> Option<Rational> {
+        // Safety: The context is valid for as long as the output.
+        let context = unsafe { &*self.as_ptr() };
+        if index < 0 || index as u32 >= context.nb_streams {
+            return None;
+        }
+
+        // Safety: `streams` points to `nb_streams` valid streams.
+        let stream = unsafe { *context.streams.add(index as usize) };
+        // Safety: The stream is valid.
+        Some(unsafe { (*stream).time_base }.into())
+    }
+}
+
+impl<T: Send + Sync> TeeOutput for Output<T> {
+    fn write_header(&mut self) -> Result<(), FfmpegError> {
+        Output::write_header(self)
+    }
+
+    fn write_interleaved_packet(&mut self, packet: Packet) -> Result<(), FfmpegError> {
+        Output::write_interleaved_packet(self, packet)
+    }
+
+    fn write_trailer(&mut self) -> Result<(), FfmpegError> {
+        Output::write_trailer(self)
+    }
+
+    fn as_ptr(&self) -> *const AVFormatContext {
+        Output::as_ptr(self)
+    }
+}
+
+/// Options of a sink of a [`Tee`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, bon::Builder)]
+pub struct TeeSinkOptions {
+    /// Fail the whole tee when writing to this sink fails.
+    ///
+    /// By default a failing sink is only marked as [`TeeSinkStatus::Failed`], and the
+    /// other sinks keep being written to.
+    #[builder(default)]
+    pub required: bool,
+}
+
+/// The status of a sink of a [`Tee`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum TeeSinkStatus {
+    /// The header has not been written yet.
+    Pending,
+    /// The header was written, packets are written to the sink.
+    Active,
+    /// Writing to the sink failed, nothing is written to it anymore.
+    Failed(FfmpegError),
+    /// The trailer was written.
+    Finished,
+}
+
+/// A sink of a [`Tee`], with its status.
+pub struct TeeSink {
+    name: String,
+    output: Box<dyn TeeOutput>,
+    options: TeeSinkOptions,
+    status: TeeSinkStatus,
+    packets_written: u64,
+}
+
+impl std::fmt::Debug for TeeSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TeeSink")
+            .field("name", &self.name)
+            .field("options", &self.options)
+            .field("status", &self.status)
+            .field("packets_written", &self.packets_written)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TeeSink {
+    /// Returns the name the sink was added with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the options the sink was added with.
+    pub const fn options(&self) -> &TeeSinkOptions {
+        &self.options
+    }
+
+    /// Returns the status of the sink.
+    pub const fn status(&self) -> &TeeSinkStatus {
+        &self.status
+    }
+
+    /// Returns the number of packets written to the sink.
+    pub const fn packets_written(&self) -> u64 {
+        self.packets_written
+    }
+
+    /// Runs `write` unless the sink failed, marking the sink as failed if it fails.
+    ///
+    /// Fails if the sink is required, the error itself is kept in the status.
+    fn write(&mut self, write: impl FnOnce(&mut dyn TeeOutput) -> Result<(), FfmpegError>) -> Result<(), FfmpegError> {
+        if matches!(self.status, TeeSinkStatus::Failed(_)) {
+            return Ok(());
+        }
+
+        let Err(error) = write(self.output.as_mut()) else {
+            return Ok(());
+        };
+
+        self.status = TeeSinkStatus::Failed(error);
+        if self.options.required {
+            return Err(FfmpegError::Arguments("a required tee sink failed"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the same packets to several [`Output`]s, like the `tee` muxer of FFmpeg.
+///
+/// The outputs can write into anything, such as a file, an RTMP server and a fragmented
+/// MP4 segmenter. They must have the same streams, added before they are added to the
+/// tee, for example with [`Output::copy_stream`].
+///
+/// A sink that fails is marked as [`TeeSinkStatus::Failed`] and no longer written to,
+/// while the other sinks keep going. Sinks added as
+/// [`required`](TeeSinkOptions::required) fail the whole tee instead. A tee whose sinks
+/// have all failed fails as well. Either way, the error of a sink is in its status.
+#[derive(Debug, Default)]
+pub struct Tee {
+    sinks: Vec<TeeSink>,
+}
+
+impl Tee {
+    /// Creates a tee without sinks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sink, returning its index.
+    ///
+    /// Sinks must be added before the header is written.
+    pub fn add_sink<T: Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        output: Output<T>,
+        options: TeeSinkOptions,
+    ) -> usize {
+        self.sinks.push(TeeSink {
+            name: name.into(),
+            output: Box::new(output),
+            options,
+            status: TeeSinkStatus::Pending,
+            packets_written: 0,
+        });
+
+        self.sinks.len() - 1
+    }
+
+    /// Returns the sinks, in the order they were added.
+    pub fn sinks(&self) -> &[TeeSink] {
+        &self.sinks
+    }
+
+    /// Returns the sink with the given name.
+    pub fn sink(&self, name: &str) -> Option<&TeeSink> {
+        self.sinks.iter().find(|sink| sink.name == name)
+    }
+
+    /// Writes the header to every sink.
+    pub fn write_header(&mut self) -> Result<(), FfmpegError> {
+        for sink in &mut self.sinks {
+            if sink.status != TeeSinkStatus::Pending {
+                return Err(FfmpegError::Arguments("header already written"));
+            }
+
+            sink.write(|output| output.write_header())?;
+            if sink.status == TeeSinkStatus::Pending {
+                sink.status = TeeSinkStatus::Active;
+            }
+        }
+
+        self.check_sinks()
+    }
+
+    /// Writes a packet to every sink, with timestamps in `time_base`.
+    ///
+    /// The timestamps are converted to the time base of the stream of each sink, and
+    /// the packets are interleaved, see [`Output::write_interleaved_packet`].
+    pub fn write_packet(&mut self, packet: &Packet, time_base: impl Into<Rational>) -> Result<(), FfmpegError> {
+        let time_base = time_base.into();
+
+        for sink in &mut self.sinks {
+            if matches!(sink.status, TeeSinkStatus::Pending | TeeSinkStatus::Finished) {
+                return Err(FfmpegError::Arguments(
+                    "cannot write packet before header or after trailer has been written",
+                ));
+            }
+
+            sink.write(|output| {
+                let stream_time_base = output.stream_time_base(packet.stream_index()).ok_or(FfmpegError::NoStream)?;
+
+                let mut packet = packet.clone();
+                packet.convert_timebase(time_base, stream_time_base);
+                output.write_interleaved_packet(packet)
+            })?;
+
+            if sink.status == TeeSinkStatus::Active {
+                sink.packets_written += 1;
+            }
+        }
+
+        self.check_sinks()
+    }
+
+    /// Writes the trailer to every sink that has not failed.
+    pub fn write_trailer(&mut self) -> Result<(), FfmpegError> {
+        for sink in &mut self.sinks {
+            sink.write(|output| output.write_trailer())?;
+            if sink.status == TeeSinkStatus::Active {
+                sink.status = TeeSinkStatus::Finished;
+            }
+        }
+
+        self.check_sinks()
+    }
+
+    /// Fails if every sink has failed, there is nothing left to write to.
+    fn check_sinks(&self) -> Result<(), FfmpegError> {
+        if !self.sinks.is_empty() && self.sinks.iter().all(|sink| matches!(sink.status, TeeSinkStatus::Failed(_))) {
+            return Err(FfmpegError::Arguments("every tee sink has failed"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::{Cursor, Write};
+    use std::path::PathBuf;
+
+    use super::{Tee, TeeSinkOptions, TeeSinkStatus};
+    use crate::error::FfmpegError;
+    use crate::io::{Input, Output, OutputOptions};
+
+    /// A writer for a destination that went away.
+    struct BrokenWriter;
+
+    impl Write for BrokenWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writes every packet of the test file through a tee with a working and a broken sink.
+    fn run_tee(broken: TeeSinkOptions) -> (Tee, Result<(), FfmpegError>) {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let mut input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+
+        let options = || OutputOptions::builder().format_name("flv").unwrap().build();
+        let mut working = Output::new(Cursor::new(Vec::new()), options()).expect("Failed to create Output");
+        let mut broken_output = Output::new(BrokenWriter, options()).expect("Failed to create Output");
+
+        let streams = input.streams();
+        let time_bases = streams.iter().map(|stream| stream.time_base()).collect::<Vec<_>>();
+        for stream in streams.iter() {
+            working.copy_stream(&stream).expect("Failed to copy stream");
+            broken_output.copy_stream(&stream).expect("Failed to copy stream");
+        }
+
+        let mut tee = Tee::new();
+        tee.add_sink("working", working, TeeSinkOptions::default());
+        tee.add_sink("broken", broken_output, broken);
+
+        let result = (|| {
+            tee.write_header()?;
+            while let Some(packet) = input.receive_packet()? {
+                tee.write_packet(&packet, time_bases[packet.stream_index() as usize])?;
+            }
+            tee.write_trailer()
+        })();
+
+        (tee, result)
+    }
+
+    #[test]
+    fn test_tee_isolates_failures() {
+        let (tee, result) = run_tee(TeeSinkOptions::default());
+        result.expect("Failed to write through the tee");
+
+        let working = tee.sink("working").expect("Missing sink");
+        assert_eq!(working.status(), &TeeSinkStatus::Finished);
+        assert!(working.packets_written() > 0);
+
+        let broken = tee.sink("broken").expect("Missing sink");
+        assert!(matches!(broken.status(), TeeSinkStatus::Failed(FfmpegError::Io(_))));
+        assert!(broken.packets_written() < working.packets_written());
+    }
+
+    #[test]
+    fn test_tee_required_sink() {
+        let (tee, result) = run_tee(TeeSinkOptions::builder().required(true).build());
+        assert_eq!(result, Err(FfmpegError::Arguments("a required tee sink failed")));
+        assert!(matches!(
+            tee.sink("broken").expect("Missing sink").status(),
+            TeeSinkStatus::Failed(FfmpegError::Io(_))
+        ));
+        assert_eq!(tee.sink("working").expect("Missing sink").status(), &TeeSinkStatus::Active);
+    }
+
+    #[test]
+    fn test_tee_state() {
+        let mut tee = Tee::new();
+        assert!(tee.sinks().is_empty());
+        tee.write_header().expect("A tee without sinks has nothing to fail");
+
+        let output = Output::new(
+            Cursor::new(Vec::new()),
+            OutputOptions::builder().format_name("flv").unwrap().build(),
+        )
+        .expect("Failed to create Output");
+        assert_eq!(tee.add_sink("sink", output, TeeSinkOptions::default()), 0);
+        assert_eq!(tee.sinks()[0].name(), "sink");
+        assert_eq!(tee.sinks()[0].status(), &TeeSinkStatus::Pending);
+
+        let packet = crate::packet::Packet::new().unwrap();
+        assert_eq!(
+            tee.write_packet(&packet, 1),
+            Err(FfmpegError::Arguments(
+                "cannot write packet before header or after trailer has been written"
+            ))
+        );
+    }
+}
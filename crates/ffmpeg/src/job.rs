@@ -0,0 +1,1147 @@
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::ops::DerefMut;
+
+use crate::codec::EncoderCodec;
+use crate::decoder::{Decoder, GenericDecoder};
+use crate::dict::Dictionary;
+use crate::encoder::{AudioEncoderSettings, Encoder, VideoEncoderSettings, drain};
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::filter_graph::{Filter, FilterGraph};
+use crate::frame::AudioChannelLayout;
+use crate::io::{Input, Output, OutputOptions};
+use crate::packet::Packet;
+use crate::rational::Rational;
+use crate::stream::Stream;
+use crate::{AVMediaType, AVPixelFormat, AVSampleFormat};
+
+/// A declarative description of a transcode, usually deserialized from JSON.
+///
+/// # Example
+///
+/// ```json
+/// {
+///     "inputs": [{ "path": "input.mp4" }],
+///     "outputs": [{
+///         "path": "output.mp4",
+///         "streams": [
+///             { "input": 0, "select": "best_video", "filter": "scale=640:-2", "codec": { "type": "video", "name": "libx264" } },
+///             { "input": 0, "select": "best_audio", "codec": { "type": "copy" } }
+///         ]
+///     }]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TranscodeJob {
+    /// The files to read from.
+    pub inputs: Vec<JobInput>,
+    /// The files to write to.
+    pub outputs: Vec<JobOutput>,
+}
+
+/// An input of a [`TranscodeJob`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobInput {
+    /// The path or url of the input.
+    pub path: String,
+}
+
+/// An output of a [`TranscodeJob`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobOutput {
+    /// The path of the output file.
+    pub path: String,
+    /// The container format, such as `mp4` or `matroska`. Guessed from `path` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// The streams of the output, in order.
+    pub streams: Vec<JobStream>,
+}
+
+/// A stream of a [`JobOutput`], mapped from a stream of an input.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobStream {
+    /// The index of the input in [`TranscodeJob::inputs`].
+    pub input: usize,
+    /// The stream of the input to use.
+    pub select: StreamSelector,
+    /// A filter chain applied to the decoded frames, in FFmpeg's filter graph syntax.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// How the stream is encoded.
+    pub codec: JobCodec,
+}
+
+/// Selects a stream of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamSelector {
+    /// The stream with the given index.
+    Index(usize),
+    /// The best video stream, as picked by FFmpeg.
+    BestVideo,
+    /// The best audio stream, as picked by FFmpeg.
+    BestAudio,
+}
+
+/// How a [`JobStream`] is encoded.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobCodec {
+    /// The packets are copied without decoding.
+    Copy,
+    /// The stream is decoded and encoded with a video encoder.
+    Video(VideoCodecJob),
+    /// The stream is decoded and encoded with an audio encoder.
+    Audio(AudioCodecJob),
+}
+
+/// The settings of a video encoder in a [`TranscodeJob`].
+///
+/// Properties that are not set are taken from the decoded (and filtered) frames.
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VideoCodecJob {
+    /// The name of the encoder, such as `libx264`.
+    pub name: String,
+    /// The width to scale to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    /// The height to scale to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+    /// The pixel format to convert to, such as `yuv420p`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_format: Option<String>,
+    /// The target bitrate in bits per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<i64>,
+    /// The number of frames between keyframes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop_size: Option<i32>,
+    /// The maximum number of consecutive B-frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_b_frames: Option<i32>,
+    /// Encoder specific options, such as `preset` for `libx264`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+/// The settings of an audio encoder in a [`TranscodeJob`].
+///
+/// Properties that are not set are taken from the decoded (and filtered) frames.
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AudioCodecJob {
+    /// The name of the encoder, such as `aac`.
+    pub name: String,
+    /// The sample rate to resample to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<i32>,
+    /// The number of channels to mix to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<i32>,
+    /// The sample format to convert to, such as `fltp`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_format: Option<String>,
+    /// The target bitrate in bits per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<i64>,
+    /// Encoder specific options.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+/// An error building or running a [`TranscodeJob`].
+///
+/// Every error references the field of the job it was caused by,
+/// such as `outputs[0].streams[1].codec.name`.
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    /// A field of the job has an invalid value.
+    #[error("{field}: {message}")]
+    Invalid {
+        /// The path of the field.
+        field: String,
+        /// Why the value is invalid.
+        message: String,
+    },
+    /// An FFmpeg call failed for the part of the job at `field`.
+    #[error("{field}: {source}")]
+    Ffmpeg {
+        /// The path of the field.
+        field: String,
+        /// The underlying error.
+        #[source]
+        source: FfmpegError,
+    },
+    /// An IO error for the part of the job at `field`.
+    #[error("{field}: {source}")]
+    Io {
+        /// The path of the field.
+        field: String,
+        /// The underlying error.
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl JobError {
+    /// Returns the path of the field the error was caused by.
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Invalid { field, .. } | Self::Ffmpeg { field, .. } | Self::Io { field, .. } => field,
+        }
+    }
+
+    fn invalid(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    fn ffmpeg(field: impl Into<String>) -> impl FnOnce(FfmpegError) -> Self {
+        let field = field.into();
+        move |source| Self::Ffmpeg { field, source }
+    }
+}
+
+impl TranscodeJob {
+    /// Checks the job without opening any file.
+    ///
+    /// Verifies that inputs referenced by streams exist, and that encoders,
+    /// formats and pixel / sample formats are known to FFmpeg.
+    pub fn validate(&self) -> Result<(), JobError> {
+        self.plan().map(|_| ())
+    }
+
+    /// Runs the job, reading all inputs and writing all outputs.
+    ///
+    /// Each transcoded stream gets its own decoder and filter graph, so the same
+    /// input stream can be mapped into several outputs with different settings.
+    pub fn run(&self) -> Result<(), JobError> {
+        let plan = self.plan()?;
+
+        let mut inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| Input::open(&input.path).map_err(JobError::ffmpeg(format!("inputs[{idx}].path"))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = plan
+            .into_iter()
+            .map(|output| OutputPipeline::new(output, &mut inputs))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Inputs are read one packet at a time in turn, so outputs that combine
+        // several inputs receive their packets roughly interleaved.
+        let mut done = vec![false; inputs.len()];
+        while done.contains(&false) {
+            for (idx, input) in inputs.iter_mut().enumerate() {
+                if done[idx] {
+                    continue;
+                }
+
+                let packet = input
+                    .receive_packet()
+                    .map_err(JobError::ffmpeg(format!("inputs[{idx}].path")))?;
+
+                for output in &mut outputs {
+                    output.process(idx, packet.as_ref())?;
+                }
+
+                done[idx] = packet.is_none();
+            }
+        }
+
+        for output in outputs {
+            output.finish()?;
+        }
+
+        Ok(())
+    }
+
+    fn plan(&self) -> Result<Vec<PlannedOutput<'_>>, JobError> {
+        if self.inputs.is_empty() {
+            return Err(JobError::invalid("inputs", "at least one input is required"));
+        }
+
+        if self.outputs.is_empty() {
+            return Err(JobError::invalid("outputs", "at least one output is required"));
+        }
+
+        self.outputs
+            .iter()
+            .enumerate()
+            .map(|(output_idx, output)| {
+                let field = format!("outputs[{output_idx}]");
+
+                let format_name = output
+                    .format
+                    .as_deref()
+                    .map(CString::new)
+                    .transpose()
+                    .map_err(|_| JobError::invalid(format!("{field}.format"), "must not contain a nul byte"))?;
+                let path = CString::new(output.path.as_str())
+                    .map_err(|_| JobError::invalid(format!("{field}.path"), "must not contain a nul byte"))?;
+                // Safety: `av_guess_format` is safe to call with null or valid c-strings.
+                let format = unsafe {
+                    av_guess_format(
+                        format_name.as_ref().map(|name| name.as_ptr()).unwrap_or(std::ptr::null()),
+                        path.as_ptr(),
+                        std::ptr::null(),
+                    )
+                };
+                if format.is_null() {
+                    return Err(match output.format {
+                        Some(_) => JobError::invalid(format!("{field}.format"), "unknown format"),
+                        None => JobError::invalid(format!("{field}.path"), "could not guess the format"),
+                    });
+                }
+
+                if output.streams.is_empty() {
+                    return Err(JobError::invalid(
+                        format!("{field}.streams"),
+                        "at least one stream is required",
+                    ));
+                }
+
+                let streams = output
+                    .streams
+                    .iter()
+                    .enumerate()
+                    .map(|(stream_idx, stream)| self.plan_stream(format!("{field}.streams[{stream_idx}]"), stream))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(PlannedOutput {
+                    field,
+                    job: output,
+                    format,
+                    streams,
+                })
+            })
+            .collect()
+    }
+
+    fn plan_stream<'a>(&self, field: String, stream: &'a JobStream) -> Result<PlannedStream<'a>, JobError> {
+        if stream.input >= self.inputs.len() {
+            return Err(JobError::invalid(
+                format!("{field}.input"),
+                format!("no input with index {}", stream.input),
+            ));
+        }
+
+        let codec = match &stream.codec {
+            JobCodec::Copy => {
+                if stream.filter.is_some() {
+                    return Err(JobError::invalid(
+                        format!("{field}.filter"),
+                        "copied streams cannot be filtered",
+                    ));
+                }
+
+                PlannedCodec::Copy
+            }
+            JobCodec::Video(video) => {
+                let codec = find_encoder(&format!("{field}.codec.name"), &video.name, AVMediaType::Video)?;
+
+                for (name, value) in [("width", video.width), ("height", video.height), ("gop_size", video.gop_size)] {
+                    if value.is_some_and(|value| value <= 0) {
+                        return Err(JobError::invalid(format!("{field}.codec.{name}"), "must be positive"));
+                    }
+                }
+
+                if let Some(pixel_format) = &video.pixel_format {
+                    let name = CString::new(pixel_format.as_str()).unwrap_or_default();
+                    // Safety: `av_get_pix_fmt` is safe to call with a valid c-string.
+                    if AVPixelFormat(unsafe { av_get_pix_fmt(name.as_ptr()) }) == AVPixelFormat::None {
+                        return Err(JobError::invalid(
+                            format!("{field}.codec.pixel_format"),
+                            "unknown pixel format",
+                        ));
+                    }
+                }
+
+                PlannedCodec::Video(codec)
+            }
+            JobCodec::Audio(audio) => {
+                let codec = find_encoder(&format!("{field}.codec.name"), &audio.name, AVMediaType::Audio)?;
+
+                for (name, value) in [("sample_rate", audio.sample_rate), ("channels", audio.channels)] {
+                    if value.is_some_and(|value| value <= 0) {
+                        return Err(JobError::invalid(format!("{field}.codec.{name}"), "must be positive"));
+                    }
+                }
+
+                if let Some(sample_format) = &audio.sample_format {
+                    let name = CString::new(sample_format.as_str()).unwrap_or_default();
+                    // Safety: `av_get_sample_fmt` is safe to call with a valid c-string.
+                    if AVSampleFormat(unsafe { av_get_sample_fmt(name.as_ptr()) }) == AVSampleFormat::None {
+                        return Err(JobError::invalid(
+                            format!("{field}.codec.sample_format"),
+                            "unknown sample format",
+                        ));
+                    }
+                }
+
+                PlannedCodec::Audio(codec)
+            }
+        };
+
+        Ok(PlannedStream {
+            field,
+            job: stream,
+            codec,
+        })
+    }
+}
+
+fn find_encoder(field: &str, name: &str, media_type: AVMediaType) -> Result<EncoderCodec, JobError> {
+    let codec = EncoderCodec::by_name(name).ok_or_else(|| JobError::invalid(field, "unknown encoder"))?;
+
+    // Safety: `codec` is a valid non-null pointer returned by `avcodec_find_encoder_by_name`.
+    let codec_type = AVMediaType(unsafe { (*codec.as_ptr()).type_ });
+    if codec_type != media_type {
+        return Err(JobError::invalid(
+            field,
+            format!(
+                "not a {} encoder",
+                if media_type == AVMediaType::Video { "video" } else { "audio" }
+            ),
+        ));
+    }
+
+    Ok(codec)
+}
+
+struct PlannedOutput<'a> {
+    field: String,
+    job: &'a JobOutput,
+    format: *const AVOutputFormat,
+    streams: Vec<PlannedStream<'a>>,
+}
+
+struct PlannedStream<'a> {
+    field: String,
+    job: &'a JobStream,
+    codec: PlannedCodec,
+}
+
+enum PlannedCodec {
+    Copy,
+    Video(EncoderCodec),
+    Audio(EncoderCodec),
+}
+
+struct OutputPipeline {
+    field: String,
+    output: Output<File>,
+    streams: Vec<StreamPipeline>,
+}
+
+struct StreamPipeline {
+    field: String,
+    input: usize,
+    input_stream: i32,
+    kind: StreamKind,
+}
+
+enum StreamKind {
+    Copy {
+        stream_index: i32,
+        input_time_base: Rational,
+        /// The time base of the output stream, known once the header is written.
+        output_time_base: Rational,
+    },
+    Transcode(Box<Transcoder>),
+}
+
+struct Transcoder {
+    decoder: Decoder,
+    graph: FilterGraph,
+    encoder: Encoder,
+}
+
+/// The names of the buffer source and sink in every filter graph.
+const SOURCE_NAME: &str = "in";
+const SINK_NAME: &str = "out";
+
+impl OutputPipeline {
+    fn new(plan: PlannedOutput<'_>, inputs: &mut [Input<()>]) -> Result<Self, JobError> {
+        let file = File::create(&plan.job.path).map_err(|source| JobError::Io {
+            field: format!("{}.path", plan.field),
+            source,
+        })?;
+
+        let options = OutputOptions::builder()
+            .format_ffi(plan.format)
+            .map_err(JobError::ffmpeg(format!("{}.format", plan.field)))?
+            .build();
+        let mut output = Output::seekable(file, options).map_err(JobError::ffmpeg(format!("{}.path", plan.field)))?;
+
+        let streams = plan
+            .streams
+            .into_iter()
+            .map(|stream| StreamPipeline::new(stream, inputs, &mut output))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        output
+            .write_header()
+            .map_err(JobError::ffmpeg(format!("{}.path", plan.field)))?;
+
+        let mut pipeline = Self {
+            field: plan.field,
+            output,
+            streams,
+        };
+
+        // The muxer may have picked another time base for the copied streams.
+        let output_streams = pipeline.output.streams();
+        for stream in &mut pipeline.streams {
+            let StreamKind::Copy {
+                stream_index,
+                output_time_base,
+                ..
+            } = &mut stream.kind
+            else {
+                continue;
+            };
+
+            if let Some(output_stream) = output_streams.iter().nth(*stream_index as usize) {
+                *output_time_base = output_stream.time_base();
+            }
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Handles a packet read from an input, `None` marks the end of the input.
+    fn process(&mut self, input: usize, packet: Option<&Packet>) -> Result<(), JobError> {
+        for stream in &mut self.streams {
+            if stream.input != input || packet.is_some_and(|packet| packet.stream_index() != stream.input_stream) {
+                continue;
+            }
+
+            match (&mut stream.kind, packet) {
+                (
+                    StreamKind::Copy {
+                        stream_index,
+                        input_time_base,
+                        output_time_base,
+                    },
+                    Some(packet),
+                ) => {
+                    let mut packet = packet.clone();
+                    packet.convert_timebase(*input_time_base, *output_time_base);
+                    packet.set_stream_index(*stream_index);
+                    self.output
+                        .write_interleaved_packet(packet)
+                        .map_err(JobError::ffmpeg(&stream.field))?;
+                }
+                (StreamKind::Copy { .. }, None) => {}
+                (StreamKind::Transcode(transcoder), packet) => transcoder
+                    .process(packet, &mut self.output)
+                    .map_err(JobError::ffmpeg(&stream.field))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the encoders and writes the trailer.
+    fn finish(mut self) -> Result<(), JobError> {
+        let mut encoders = self
+            .streams
+            .iter_mut()
+            .filter_map(|stream| match &mut stream.kind {
+                StreamKind::Transcode(transcoder) => Some(&mut transcoder.encoder),
+                StreamKind::Copy { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        drain(&mut encoders, &mut self.output).map_err(JobError::ffmpeg(self.field))?;
+
+        Ok(())
+    }
+}
+
+impl StreamPipeline {
+    fn new(plan: PlannedStream<'_>, inputs: &mut [Input<()>], output: &mut Output<File>) -> Result<Self, JobError> {
+        let field = plan.field;
+        let job = plan.job;
+
+        let mut streams = inputs[job.input].streams_mut();
+        let index = match job.select {
+            StreamSelector::Index(index) => Some(index),
+            StreamSelector::BestVideo => streams.best_index(AVMediaType::Video),
+            StreamSelector::BestAudio => streams.best_index(AVMediaType::Audio),
+        };
+        let stream = index
+            .and_then(|index| streams.get(index))
+            .ok_or_else(|| JobError::invalid(format!("{field}.select"), "no such stream in the input"))?;
+        let input_stream = stream.index();
+
+        let kind = match plan.codec {
+            PlannedCodec::Copy => {
+                let stream_index = output
+                    .copy_stream(&stream)
+                    .map_err(JobError::ffmpeg(&field))?
+                    .ok_or_else(|| JobError::invalid(format!("{field}.select"), "stream has no codec parameters"))?
+                    .index();
+
+                StreamKind::Copy {
+                    stream_index,
+                    input_time_base: stream.time_base(),
+                    output_time_base: stream.time_base(),
+                }
+            }
+            PlannedCodec::Video(codec) => {
+                let JobCodec::Video(settings) = &job.codec else {
+                    unreachable!("planned as a video codec");
+                };
+
+                StreamKind::Transcode(Box::new(Transcoder::video(&field, &stream, job, settings, codec, output)?))
+            }
+            PlannedCodec::Audio(codec) => {
+                let JobCodec::Audio(settings) = &job.codec else {
+                    unreachable!("planned as an audio codec");
+                };
+
+                StreamKind::Transcode(Box::new(Transcoder::audio(&field, &stream, job, settings, codec, output)?))
+            }
+        };
+
+        Ok(Self {
+            field,
+            input: job.input,
+            input_stream,
+            kind,
+        })
+    }
+}
+
+impl Transcoder {
+    fn video(
+        field: &str,
+        stream: &Stream<'_>,
+        job: &JobStream,
+        settings: &VideoCodecJob,
+        codec: EncoderCodec,
+        output: &mut Output<File>,
+    ) -> Result<Self, JobError> {
+        let decoder = Decoder::new(stream)
+            .map_err(JobError::ffmpeg(format!("{field}.select")))?
+            .video()
+            .map_err(|_| JobError::invalid(format!("{field}.select"), "not a video stream"))?;
+
+        let time_base = stream.time_base();
+        let sample_aspect_ratio = decoder.sample_aspect_ratio();
+        let frame_rate = decoder.frame_rate();
+        let mut source_args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            decoder.width(),
+            decoder.height(),
+            decoder.pixel_format().0,
+            time_base.numerator,
+            time_base.denominator,
+            sample_aspect_ratio.numerator,
+            sample_aspect_ratio.denominator,
+        );
+        if frame_rate.numerator > 0 {
+            source_args.push_str(&format!(":frame_rate={}/{}", frame_rate.numerator, frame_rate.denominator));
+        }
+
+        // Convert to the requested size and pixel format after the user filter.
+        // Without a requested pixel format, pick one the encoder supports.
+        let mut chain = job.filter.iter().cloned().collect::<Vec<_>>();
+        if settings.width.is_some() || settings.height.is_some() {
+            chain.push(format!(
+                "scale={}:{}",
+                settings.width.unwrap_or(-2),
+                settings.height.unwrap_or(-2)
+            ));
+        }
+        // Safety: `codec` is a valid non-null pointer.
+        let pix_fmts = unsafe { (*codec.as_ptr()).pix_fmts };
+        // Safety: `pix_fmts` is either null or terminated by `AV_PIX_FMT_NONE`.
+        let supported = unsafe { supported_formats(pix_fmts.cast(), AVPixelFormat::None.0) };
+        match &settings.pixel_format {
+            Some(pixel_format) => chain.push(format!("format=pix_fmts={pixel_format}")),
+            None if !supported.is_empty() => {
+                // Safety: the formats come from the codec and are valid pixel formats.
+                let names = supported
+                    .iter()
+                    .filter_map(|fmt| c_name(unsafe { av_get_pix_fmt_name(*fmt) }));
+                chain.push(format!("format=pix_fmts={}", names.collect::<Vec<_>>().join("|")));
+            }
+            None => {}
+        }
+
+        let mut graph = build_graph(field, "buffer", &source_args, "buffersink", &chain)?;
+        let sink = graph.get(SINK_NAME).expect("sink was added").sink();
+
+        let frame_rate = Some(sink.frame_rate())
+            .filter(|rate| rate.numerator > 0)
+            .unwrap_or_else(|| stream.avg_frame_rate());
+        let encoder_settings = VideoEncoderSettings::builder()
+            .width(sink.width())
+            .height(sink.height())
+            .pixel_format(AVPixelFormat(sink.format()))
+            .frame_rate(frame_rate)
+            .sample_aspect_ratio(sink.sample_aspect_ratio())
+            .maybe_bitrate(settings.bitrate)
+            .maybe_gop_size(settings.gop_size)
+            .maybe_max_b_frames(settings.max_b_frames)
+            .maybe_codec_specific_options(codec_options(field, &settings.options)?)
+            .build();
+        let time_base = sink.time_base();
+
+        let encoder = Encoder::new(codec, output, time_base, time_base, encoder_settings)
+            .map_err(JobError::ffmpeg(format!("{field}.codec")))?;
+
+        Ok(Self {
+            decoder: Decoder::Video(decoder),
+            graph,
+            encoder,
+        })
+    }
+
+    fn audio(
+        field: &str,
+        stream: &Stream<'_>,
+        job: &JobStream,
+        settings: &AudioCodecJob,
+        codec: EncoderCodec,
+        output: &mut Output<File>,
+    ) -> Result<Self, JobError> {
+        let decoder = Decoder::new(stream)
+            .map_err(JobError::ffmpeg(format!("{field}.select")))?
+            .audio()
+            .map_err(|_| JobError::invalid(format!("{field}.select"), "not an audio stream"))?;
+
+        let time_base = stream.time_base();
+        let source_args = format!(
+            "sample_rate={}:sample_fmt={}:channels={}:time_base={}/{}",
+            decoder.sample_rate(),
+            decoder.sample_format().0,
+            decoder.channels(),
+            time_base.numerator,
+            time_base.denominator,
+        );
+
+        // Convert to the requested format after the user filter, see `video`.
+        let mut chain = job.filter.iter().cloned().collect::<Vec<_>>();
+        let mut aformat = Vec::new();
+        // Safety: `codec` is a valid non-null pointer.
+        let sample_fmts = unsafe { (*codec.as_ptr()).sample_fmts };
+        // Safety: `sample_fmts` is either null or terminated by `AV_SAMPLE_FMT_NONE`.
+        let supported = unsafe { supported_formats(sample_fmts.cast(), AVSampleFormat::None.0) };
+        match &settings.sample_format {
+            Some(sample_format) => aformat.push(format!("sample_fmts={sample_format}")),
+            None if !supported.is_empty() => {
+                // Safety: the formats come from the codec and are valid sample formats.
+                let names = supported
+                    .iter()
+                    .filter_map(|fmt| c_name(unsafe { av_get_sample_fmt_name(*fmt) }));
+                aformat.push(format!("sample_fmts={}", names.collect::<Vec<_>>().join("|")));
+            }
+            None => {}
+        }
+        if let Some(sample_rate) = settings.sample_rate {
+            aformat.push(format!("sample_rates={sample_rate}"));
+        }
+        if let Some(channels) = settings.channels {
+            aformat.push(format!("channel_layouts={channels}c"));
+        }
+        if !aformat.is_empty() {
+            chain.push(format!("aformat={}", aformat.join(":")));
+        }
+
+        let mut graph = build_graph(field, "abuffer", &source_args, "abuffersink", &chain)?;
+        let sink = graph.get(SINK_NAME).expect("sink was added").sink();
+
+        let encoder_settings = AudioEncoderSettings::builder()
+            .sample_rate(sink.sample_rate())
+            .ch_layout(AudioChannelLayout::new(sink.channels()).map_err(JobError::ffmpeg(format!("{field}.codec")))?)
+            .sample_fmt(AVSampleFormat(sink.format()))
+            .maybe_bitrate(settings.bitrate)
+            .maybe_codec_specific_options(codec_options(field, &settings.options)?)
+            .build();
+        let time_base = sink.time_base();
+
+        let encoder = Encoder::new(codec, output, time_base, time_base, encoder_settings)
+            .map_err(JobError::ffmpeg(format!("{field}.codec")))?;
+
+        if encoder.frame_size() > 0 {
+            graph
+                .get(SINK_NAME)
+                .expect("sink was added")
+                .sink()
+                .set_frame_size(encoder.frame_size() as u32);
+        }
+
+        Ok(Self {
+            decoder: Decoder::Audio(decoder),
+            graph,
+            encoder,
+        })
+    }
+
+    /// Decodes, filters and encodes a packet, `None` flushes the decoder and the filter graph.
+    /// The encoder is flushed by [`drain`].
+    fn process<T: Send + Sync>(&mut self, packet: Option<&Packet>, output: &mut Output<T>) -> Result<(), FfmpegError> {
+        let decoder: &mut GenericDecoder = match &mut self.decoder {
+            Decoder::Video(decoder) => decoder.deref_mut(),
+            Decoder::Audio(decoder) => decoder.deref_mut(),
+        };
+
+        match packet {
+            Some(packet) => decoder.send_packet(packet)?,
+            None => decoder.send_eof()?,
+        }
+
+        while let Some(frame) = decoder.receive_frame()? {
+            self.graph
+                .get(SOURCE_NAME)
+                .expect("source was added")
+                .source()
+                .send_frame(&frame)?;
+            Self::encode(&mut self.graph, &mut self.encoder, output)?;
+        }
+
+        if packet.is_none() {
+            self.graph
+                .get(SOURCE_NAME)
+                .expect("source was added")
+                .source()
+                .send_eof(None)?;
+            Self::encode(&mut self.graph, &mut self.encoder, output)?;
+        }
+
+        Ok(())
+    }
+
+    fn encode<T: Send + Sync>(
+        graph: &mut FilterGraph,
+        encoder: &mut Encoder,
+        output: &mut Output<T>,
+    ) -> Result<(), FfmpegError> {
+        while let Some(frame) = graph.get(SINK_NAME).expect("sink was added").sink().receive_frame()? {
+            encoder.send_frame(&frame)?;
+            while let Some(packet) = encoder.receive_packet()? {
+                output.write_interleaved_packet(packet)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds `source -> chain -> sink`, with an empty chain passing frames through.
+fn build_graph(field: &str, source: &str, source_args: &str, sink: &str, chain: &[String]) -> Result<FilterGraph, JobError> {
+    let error = || JobError::ffmpeg(format!("{field}.filter"));
+
+    let mut graph = FilterGraph::new().map_err(error())?;
+    for (filter, name, args) in [(source, SOURCE_NAME, source_args), (sink, SINK_NAME, "")] {
+        let filter = Filter::get(filter).ok_or(FfmpegError::NoFilter).map_err(error())?;
+        graph.add(filter, name, args).map_err(error())?;
+    }
+
+    let spec = if chain.is_empty() {
+        if source == "buffer" { "null" } else { "anull" }.to_string()
+    } else {
+        chain.join(",")
+    };
+
+    // The open input of the chain is fed by the source, and its open output feeds the sink.
+    graph
+        .input(SINK_NAME, 0)
+        .and_then(|parser| parser.output(SOURCE_NAME, 0))
+        .and_then(|parser| parser.parse(&spec))
+        .map_err(error())?;
+    graph.validate().map_err(error())?;
+
+    Ok(graph)
+}
+
+fn codec_options(field: &str, options: &BTreeMap<String, String>) -> Result<Option<Dictionary>, JobError> {
+    if options.is_empty() {
+        return Ok(None);
+    }
+
+    Dictionary::try_from_iter(options.iter().map(|(key, value)| (key.as_str(), value.as_str())))
+        .map(Some)
+        .map_err(JobError::ffmpeg(format!("{field}.codec.options")))
+}
+
+/// Reads a list of formats terminated by `none`.
+///
+/// # Safety
+/// `formats` must be null or point to a list terminated by `none`.
+unsafe fn supported_formats(formats: *const i32, none: i32) -> Vec<i32> {
+    let mut supported = Vec::new();
+    if formats.is_null() {
+        return supported;
+    }
+
+    for idx in 0.. {
+        // Safety: the list is terminated by `none`, so every read up to it is in bounds.
+        let fmt = unsafe { *formats.add(idx) };
+        if fmt == none {
+            break;
+        }
+
+        supported.push(fmt);
+    }
+
+    supported
+}
+
+fn c_name(name: *const std::ffi::c_char) -> Option<String> {
+    if name.is_null() {
+        return None;
+    }
+
+    // Safety: the pointer is a valid nul terminated static string returned by FFmpeg.
+    Some(unsafe { CStr::from_ptr(name) }.to_str().ok()?.to_owned())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::AVMediaType;
+    use crate::io::Input;
+    use crate::job::{JobCodec, JobError, StreamSelector, TranscodeJob, VideoCodecJob};
+
+    fn job(json: serde_json::Value) -> TranscodeJob {
+        serde_json::from_value(json).expect("Failed to deserialize job")
+    }
+
+    #[test]
+    fn test_job_deserialize() {
+        let job = job(serde_json::json!({
+            "inputs": [{ "path": "input.mp4" }],
+            "outputs": [{
+                "path": "output.mkv",
+                "format": "matroska",
+                "streams": [
+                    {
+                        "input": 0,
+                        "select": "best_video",
+                        "filter": "scale=640:-2",
+                        "codec": { "type": "video", "name": "mpeg4", "bitrate": 1000000, "options": { "qscale": "3" } }
+                    },
+                    { "input": 0, "select": { "index": 1 }, "codec": { "type": "copy" } }
+                ]
+            }]
+        }));
+
+        let streams = &job.outputs[0].streams;
+        assert_eq!(streams[0].select, StreamSelector::BestVideo);
+        assert_eq!(
+            streams[0].codec,
+            JobCodec::Video(VideoCodecJob {
+                name: "mpeg4".into(),
+                bitrate: Some(1_000_000),
+                options: [("qscale".to_string(), "3".to_string())].into(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(streams[1].select, StreamSelector::Index(1));
+        assert_eq!(streams[1].codec, JobCodec::Copy);
+
+        // Round trips through serde.
+        let value = serde_json::to_value(&job).expect("Failed to serialize job");
+        assert_eq!(
+            serde_json::from_value::<TranscodeJob>(value).expect("Failed to deserialize job"),
+            job
+        );
+
+        // Unknown fields are rejected.
+        assert!(
+            serde_json::from_value::<TranscodeJob>(serde_json::json!({
+                "inputs": [{ "path": "input.mp4", "seek": 10 }],
+                "outputs": []
+            }))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_job_validate() {
+        let invalid = |json: serde_json::Value| match job(json).validate() {
+            Err(JobError::Invalid { field, .. }) => field,
+            result => panic!("expected an invalid field, got {result:?}"),
+        };
+
+        let output = |stream: serde_json::Value| {
+            serde_json::json!({
+                "inputs": [{ "path": "input.mp4" }],
+                "outputs": [{ "path": "output.mp4", "streams": [stream] }]
+            })
+        };
+
+        assert_eq!(
+            invalid(output(
+                serde_json::json!({ "input": 1, "select": "best_video", "codec": { "type": "copy" } })
+            )),
+            "outputs[0].streams[0].input"
+        );
+        assert_eq!(
+            invalid(output(
+                serde_json::json!({ "input": 0, "select": "best_video", "filter": "null", "codec": { "type": "copy" } })
+            )),
+            "outputs[0].streams[0].filter"
+        );
+        assert_eq!(
+            invalid(output(
+                serde_json::json!({ "input": 0, "select": "best_video", "codec": { "type": "video", "name": "does-not-exist" } })
+            )),
+            "outputs[0].streams[0].codec.name"
+        );
+        assert_eq!(
+            invalid(output(
+                serde_json::json!({ "input": 0, "select": "best_audio", "codec": { "type": "audio", "name": "mpeg4" } })
+            )),
+            "outputs[0].streams[0].codec.name"
+        );
+        assert_eq!(
+            invalid(output(serde_json::json!({
+                "input": 0,
+                "select": "best_video",
+                "codec": { "type": "video", "name": "mpeg4", "pixel_format": "not-a-format" }
+            }))),
+            "outputs[0].streams[0].codec.pixel_format"
+        );
+        assert_eq!(
+            invalid(output(serde_json::json!({
+                "input": 0,
+                "select": "best_audio",
+                "codec": { "type": "audio", "name": "aac", "sample_rate": 0 }
+            }))),
+            "outputs[0].streams[0].codec.sample_rate"
+        );
+        assert_eq!(
+            invalid(serde_json::json!({
+                "inputs": [{ "path": "input.mp4" }],
+                "outputs": [{ "path": "output.unknown-extension", "streams": [] }]
+            })),
+            "outputs[0].path"
+        );
+        assert_eq!(
+            invalid(serde_json::json!({
+                "inputs": [{ "path": "input.mp4" }],
+                "outputs": [{ "path": "output.mp4", "format": "not-a-format", "streams": [] }]
+            })),
+            "outputs[0].format"
+        );
+
+        job(output(
+            serde_json::json!({ "input": 0, "select": "best_video", "codec": { "type": "copy" } }),
+        ))
+        .validate()
+        .expect("job should be valid");
+    }
+
+    #[test]
+    fn test_job_run() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("output.mp4");
+
+        let job = job(serde_json::json!({
+            "inputs": [{ "path": "../../assets/avc_aac.mp4" }],
+            "outputs": [{
+                "path": path.to_str().unwrap(),
+                "streams": [
+                    {
+                        "input": 0,
+                        "select": "best_video",
+                        "filter": "scale=320:-2",
+                        "codec": { "type": "video", "name": "mpeg4", "gop_size": 30 }
+                    },
+                    {
+                        "input": 0,
+                        "select": "best_audio",
+                        "codec": { "type": "audio", "name": "aac", "sample_rate": 22050, "channels": 1 }
+                    },
+                    { "input": 0, "select": "best_audio", "codec": { "type": "copy" } }
+                ]
+            }]
+        }));
+
+        job.run().expect("Failed to run job");
+
+        let input = Input::open(path.to_str().unwrap()).expect("Failed to open output");
+        let streams = input.streams();
+        assert_eq!(streams.len(), 3);
+
+        let video = streams.best(AVMediaType::Video).expect("No video stream");
+        let codec_parameters = video.codec_parameters().expect("No codec parameters");
+        assert_eq!(codec_parameters.width, 320);
+
+        let audio = streams.iter().nth(1).expect("No audio stream");
+        let codec_parameters = audio.codec_parameters().expect("No codec parameters");
+        assert_eq!(codec_parameters.sample_rate, 22050);
+        assert_eq!(codec_parameters.ch_layout.nb_channels, 1);
+    }
+
+    #[test]
+    fn test_job_run_copy_across_containers() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let input_path = "../../assets/avc_aac.mp4";
+        let expected = Input::open(input_path)
+            .expect("Failed to open input")
+            .probe()
+            .format
+            .duration
+            .expect("No input duration");
+
+        // Matroska and MPEG-TS use other time bases than the mp4 input.
+        let outputs = ["output.mkv", "output.ts"].map(|name| dir.path().join(name));
+        let job = job(serde_json::json!({
+            "inputs": [{ "path": input_path }],
+            "outputs": outputs.iter().map(|path| serde_json::json!({
+                "path": path.to_str().unwrap(),
+                "streams": [
+                    { "input": 0, "select": "best_video", "codec": { "type": "copy" } },
+                    { "input": 0, "select": "best_audio", "codec": { "type": "copy" } }
+                ]
+            })).collect::<Vec<_>>()
+        }));
+
+        job.run().expect("Failed to run job");
+
+        for path in &outputs {
+            let report = Input::open(path.to_str().unwrap()).expect("Failed to open output").probe();
+            let duration = report.format.duration.expect("No output duration");
+            assert!(
+                (duration - expected).abs() < 0.1,
+                "{}: duration {duration} != {expected}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_job_run_missing_input() {
+        let job = job(serde_json::json!({
+            "inputs": [{ "path": "does-not-exist.mp4" }],
+            "outputs": [{
+                "path": "output.mp4",
+                "streams": [{ "input": 0, "select": "best_video", "codec": { "type": "copy" } }]
+            }]
+        }));
+
+        let error = job.run().expect_err("job should fail");
+        assert!(matches!(error, JobError::Ffmpeg { .. }));
+        assert_eq!(error.field(), "inputs[0].path");
+    }
+}
@@ -4,7 +4,9 @@
 //!
 //! This crate aims to provide a simple-safe interface to the native ffmpeg c-bindings.
 //!
-//! Currently this crate only supports the latest versions of ffmpeg (7.x.x).
+//! This crate supports ffmpeg 6.1 and 7.x. The version is detected when the crate is built,
+//! set `SCUFFLE_FFMPEG_VERSION` (for example `6.1`) to override it. A few codec ids are only
+//! available when building against ffmpeg 7.
 //!
 //! ## How is this different from other ffmpeg crates?
 //!
@@ -176,19 +178,10 @@
 //! video_decoder.send_eof()?;
 //! audio_decoder.send_eof()?;
 //!
-//! // 16. Receive the remaining packets from the encoders.
-//! while let Some(packet) = video_encoder.receive_packet()? {
-//!     output.write_packet(&packet)?;
-//! }
-//!
-//! while let Some(packet) = audio_encoder.receive_packet()? {
-//!     output.write_packet(&packet)?;
-//! }
-//!
-//! // 17. Write the trailer to the output.
-//! output.write_trailer()?;
+//! // 16. Flush the encoders, write their remaining packets and the trailer to the output.
+//! scuffle_ffmpeg::encoder::drain(&mut [&mut video_encoder, &mut audio_encoder], &mut output)?;
 //!
-//! // 18. Do something with the output data (write to disk, upload to s3, etc).
+//! // 17. Do something with the output data (write to disk, upload to s3, etc).
 //! let output_data = output.into_inner();
 //! # drop(output_data);
 //! # Ok(())
@@ -214,12 +207,24 @@
 #![deny(clippy::undocumented_unsafe_blocks)]
 #![deny(clippy::multiple_unsafe_ops_per_block)]
 
+/// Media analysis helpers.
+pub mod analysis;
+/// Detecting and filling gaps in audio timestamps.
+pub mod audio_gap;
+/// Chapters of inputs and outputs.
+pub mod chapter;
 /// Codec specific functionality.
 pub mod codec;
+/// Typed codec parameters of streams, decoders and encoders.
+pub mod codec_parameters;
 /// Constants.
 pub mod consts;
 /// Decoder specific functionality.
 pub mod decoder;
+/// Listing and opening capture devices, such as cameras and microphones.
+#[cfg(feature = "avdevice")]
+#[cfg_attr(docsrs, doc(cfg(feature = "avdevice")))]
+pub mod device;
 /// Dictionary specific functionality.
 pub mod dict;
 /// Encoder specific functionality.
@@ -230,25 +235,52 @@ pub mod error;
 pub mod filter_graph;
 /// Frame specific functionality.
 pub mod frame;
+/// A pool of frames reusing their allocations.
+pub mod frame_pool;
+/// A bounded queue for handing frames between threads.
+pub mod frame_queue;
+/// Conversions between [`scuffle_h264`] types and codec parameters.
+#[cfg(feature = "h264")]
+#[cfg_attr(docsrs, doc(cfg(feature = "h264")))]
+pub mod h264;
+/// Hardware acceleration devices and frames.
+pub mod hwdevice;
+pub mod image;
 /// Input/Output specific functionality.
 pub mod io;
+/// Declarative transcode jobs.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod job;
 /// Logging specific functionality.
 pub mod log;
 /// Packet specific functionality.
 pub mod packet;
+/// Container and codec details of inputs, similar to ffprobe.
+pub mod probe;
+/// Programs of inputs, such as the channels of an MPEG-TS.
+pub mod program;
 /// Rational number specific functionality.
 pub mod rational;
 /// [`frame::AudioFrame`] resampling and format conversion.
 pub mod resampler;
 /// Scaler specific functionality.
 pub mod scaler;
+/// Helpers for frame side data, such as timecodes and captions.
+pub mod side_data;
 /// Stream specific functionality.
 pub mod stream;
+/// Threading configuration for decoders and encoders.
+pub mod threading;
+/// HDR to SDR tone mapping.
+pub mod tonemap;
 /// Utility functionality.
 pub mod utils;
 
+pub use lifecycle::{init, shutdown};
 pub use rusty_ffmpeg::ffi;
 
+mod lifecycle;
 mod smart_object;
 
 mod enums;
@@ -0,0 +1,194 @@
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::log::log_callback_unset;
+
+/// Initializes the global network state of FFmpeg.
+///
+/// This is only needed for inputs and outputs that use network protocols, such as
+/// `rtmp://` or `https://` urls, FFmpeg will initialize the network on demand otherwise.
+/// Calling it up front makes the global state explicit, so it can be released again by [`shutdown`].
+pub fn init() -> Result<(), FfmpegError> {
+    // Safety: `avformat_network_init` is safe to call.
+    FfmpegErrorCode(unsafe { avformat_network_init() }).result()?;
+    Ok(())
+}
+
+/// Releases the global state held by FFmpeg and this crate.
+///
+/// This deinitializes the network state set up by [`init`] and removes the log callback,
+/// which frees the callback set by [`log_callback_set`](crate::log::log_callback_set).
+///
+/// Long running services do not need this, it exists so that a process can shut down
+/// without any reachable allocations left behind, which keeps tools like Valgrind and
+/// the address sanitizer quiet. All wrappers of this crate can be dropped in any order
+/// and are independent of each other, but they should all be dropped before calling this.
+///
+/// [`init`] can be called again afterwards.
+pub fn shutdown() {
+    log_callback_unset();
+
+    // Safety: `avformat_network_deinit` is safe to call, it undoes `avformat_network_init`.
+    unsafe {
+        avformat_network_deinit();
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::any::Any;
+
+    use crate::codec::EncoderCodec;
+    use crate::decoder::Decoder;
+    use crate::encoder::{Encoder, VideoEncoderSettings};
+    use crate::io::{Input, Output, OutputOptions};
+    use crate::rational::Rational;
+    use crate::{AVMediaType, AVPixelFormat};
+
+    const PATH: &str = "../../assets/avc_aac_large.mp4";
+
+    /// Drops the values returned by `create` in every possible order, creating them again for each order.
+    fn drop_all_orders(create: impl Fn() -> Vec<Box<dyn Any>>) {
+        let len = create().len();
+        let mut order = (0..len).collect::<Vec<_>>();
+
+        // Heap's algorithm, iterative version.
+        let mut counters = vec![0; len];
+        let mut drop_in = |order: &[usize]| {
+            let mut values = create().into_iter().map(Some).collect::<Vec<_>>();
+            for &index in order {
+                drop(values[index].take().expect("dropped twice"));
+            }
+        };
+
+        drop_in(&order);
+
+        let mut i = 0;
+        while i < len {
+            if counters[i] < i {
+                order.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+                drop_in(&order);
+                counters[i] += 1;
+                i = 0;
+            } else {
+                counters[i] = 0;
+                i += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        super::init().expect("failed to init");
+        // Initializing twice is fine.
+        super::init().expect("failed to init");
+    }
+
+    #[test]
+    fn test_drop_order_decode() {
+        drop_all_orders(|| {
+            let mut input = Input::open(PATH).expect("failed to open input");
+            let stream = input.streams().best(AVMediaType::Video).expect("no video stream");
+            let index = stream.index();
+            let mut decoder = Decoder::new(&stream)
+                .expect("failed to create decoder")
+                .video()
+                .expect("not a video decoder");
+
+            let mut packet = None;
+            let mut frame = None;
+            while frame.is_none() {
+                let next = input
+                    .receive_packet()
+                    .expect("failed to read packet")
+                    .expect("no frame decoded");
+                if next.stream_index() != index {
+                    continue;
+                }
+
+                decoder.send_packet(&next).expect("failed to send packet");
+                frame = decoder.receive_frame().expect("failed to receive frame");
+                packet = Some(next);
+            }
+
+            vec![
+                Box::new(input) as Box<dyn Any>,
+                Box::new(decoder),
+                Box::new(packet.unwrap()),
+                Box::new(frame.unwrap()),
+            ]
+        });
+    }
+
+    #[test]
+    fn test_drop_order_encode() {
+        drop_all_orders(|| {
+            let mut input = Input::open(PATH).expect("failed to open input");
+            let stream = input.streams().best(AVMediaType::Video).expect("no video stream");
+            let index = stream.index();
+            let time_base = stream.time_base();
+            let mut decoder = Decoder::new(&stream)
+                .expect("failed to create decoder")
+                .video()
+                .expect("not a video decoder");
+
+            let mut output = Output::new(Vec::new(), OutputOptions::builder().format_name("mp4").unwrap().build())
+                .expect("failed to create output");
+            let codec = EncoderCodec::by_name("libx264").expect("no h264 encoder");
+            let settings = VideoEncoderSettings::builder()
+                .width(decoder.width())
+                .height(decoder.height())
+                .frame_rate(decoder.frame_rate())
+                .pixel_format(decoder.pixel_format())
+                .build();
+            let mut encoder =
+                Encoder::new(codec, &mut output, time_base, time_base, settings).expect("failed to create encoder");
+
+            let frame = loop {
+                let packet = input
+                    .receive_packet()
+                    .expect("failed to read packet")
+                    .expect("no frame decoded");
+                if packet.stream_index() != index {
+                    continue;
+                }
+
+                decoder.send_packet(&packet).expect("failed to send packet");
+                if let Some(frame) = decoder.receive_frame().expect("failed to receive frame") {
+                    break frame;
+                }
+            };
+
+            // The encoder holds on to the frame until it is flushed.
+            encoder.send_frame(&frame).expect("failed to send frame");
+
+            vec![
+                Box::new(input) as Box<dyn Any>,
+                Box::new(decoder),
+                Box::new(output),
+                Box::new(encoder),
+                Box::new(frame),
+            ]
+        });
+    }
+
+    #[test]
+    fn test_drop_after_into_inner() {
+        let mut output = Output::new(Vec::new(), OutputOptions::builder().format_name("mp4").unwrap().build())
+            .expect("failed to create output");
+        let codec = EncoderCodec::by_name("libx264").expect("no h264 encoder");
+        let settings = VideoEncoderSettings::builder()
+            .width(64)
+            .height(64)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .build();
+        let time_base = Rational::static_new::<1, 30>();
+        let encoder = Encoder::new(codec, &mut output, time_base, time_base, settings).expect("failed to create encoder");
+
+        // The data is taken out while the encoder still exists.
+        let data = output.into_inner();
+        drop(encoder);
+        assert!(data.is_empty());
+    }
+}
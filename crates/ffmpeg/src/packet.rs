@@ -3,9 +3,10 @@ use std::marker::PhantomData;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::rational::Rational;
+use crate::side_data::{ContentLightLevel, DisplayMatrix, MasteringDisplayMetadata, read_side_data};
 use crate::smart_object::SmartPtr;
 use crate::utils::{check_i64, or_nopts};
-use crate::{AVPktFlags, AVRounding};
+use crate::{AVPacketSideDataType, AVPktFlags, AVRounding};
 
 /// A collection of packets. [`Packets`] implements [`Iterator`] and will yield packets until the end of the stream is reached.
 /// A wrapper around an [`AVFormatContext`].
@@ -44,7 +45,7 @@ impl Packets<'_> {
         match FfmpegErrorCode(unsafe { av_read_frame(self.context, packet.as_mut_ptr()) }) {
             code if code.is_success() => Ok(Some(packet)),
             FfmpegErrorCode::Eof => Ok(None),
-            code => Err(FfmpegError::Code(code)),
+            code => Err(FfmpegError::from_code(code)),
         }
     }
 }
@@ -238,6 +239,94 @@ impl Packet {
     pub const fn flags(&self) -> AVPktFlags {
         AVPktFlags(self.0.as_deref_except().flags)
     }
+
+    /// Returns the side data of the given type attached to the packet.
+    pub fn side_data(&self, side_data_type: AVPacketSideDataType) -> Option<&[u8]> {
+        let mut size = 0;
+        // Safety: `self.as_ptr()` is a valid pointer to an `AVPacket`.
+        let data = unsafe { av_packet_get_side_data(self.as_ptr(), side_data_type.0 as _, &mut size) };
+        if data.is_null() {
+            return None;
+        }
+
+        if size == 0 {
+            return Some(&[]);
+        }
+
+        // Safety: `data` is valid for `size` bytes and lives as long as the packet.
+        Some(unsafe { std::slice::from_raw_parts(data, size) })
+    }
+
+    /// Returns all side data attached to the packet.
+    pub fn all_side_data(&self) -> impl Iterator<Item = (AVPacketSideDataType, &[u8])> {
+        let packet = self.0.as_deref_except();
+        let entries = if packet.side_data.is_null() || packet.side_data_elems <= 0 {
+            &[][..]
+        } else {
+            // Safety: `side_data` points to `side_data_elems` side data entries owned by the packet.
+            unsafe { std::slice::from_raw_parts(packet.side_data, packet.side_data_elems as usize) }
+        };
+
+        entries.iter().map(|side_data| {
+            let data = if side_data.data.is_null() || side_data.size == 0 {
+                &[][..]
+            } else {
+                // Safety: `data` is valid for `size` bytes and lives as long as the packet.
+                unsafe { std::slice::from_raw_parts(side_data.data, side_data.size as usize) }
+            };
+
+            (AVPacketSideDataType(side_data.type_ as _), data)
+        })
+    }
+
+    /// Allocates `size` zeroed bytes of side data of the given type and returns them to be filled in.
+    ///
+    /// Existing side data of the same type is replaced.
+    pub fn new_side_data(&mut self, side_data_type: AVPacketSideDataType, size: usize) -> Result<&mut [u8], FfmpegError> {
+        // Safety: `self.as_mut_ptr()` is a valid pointer to an `AVPacket`.
+        let data = unsafe { av_packet_new_side_data(self.as_mut_ptr(), side_data_type.0 as _, size as _) };
+        if data.is_null() {
+            return Err(FfmpegError::Alloc);
+        }
+
+        if size == 0 {
+            return Ok(&mut []);
+        }
+
+        // Safety: `av_packet_new_side_data` allocated `size` bytes, which live as long as the packet.
+        let data = unsafe { std::slice::from_raw_parts_mut(data, size) };
+        data.fill(0);
+        Ok(data)
+    }
+
+    /// Attaches a copy of `data` as side data of the given type to the packet, replacing existing side data of that type.
+    pub fn add_side_data(&mut self, side_data_type: AVPacketSideDataType, data: &[u8]) -> Result<(), FfmpegError> {
+        self.new_side_data(side_data_type, data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Removes all side data from the packet.
+    pub fn clear_side_data(&mut self) {
+        // Safety: `self.as_mut_ptr()` is a valid pointer to an `AVPacket`.
+        unsafe { av_packet_free_side_data(self.as_mut_ptr()) };
+    }
+
+    /// Returns the mastering display metadata attached to the packet, part of the HDR10 metadata.
+    pub fn mastering_display_metadata(&self) -> Option<MasteringDisplayMetadata> {
+        let data = self.side_data(AVPacketSideDataType::MasteringDisplayMetadata)?;
+        read_side_data::<AVMasteringDisplayMetadata>(data).map(Into::into)
+    }
+
+    /// Returns the content light level attached to the packet, part of the HDR10 metadata.
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        let data = self.side_data(AVPacketSideDataType::ContentLightLevel)?;
+        read_side_data::<AVContentLightMetadata>(data).map(Into::into)
+    }
+
+    /// Returns the display matrix attached to the packet.
+    pub fn display_matrix(&self) -> Option<DisplayMatrix> {
+        read_side_data::<[i32; 9]>(self.side_data(AVPacketSideDataType::DisplayMatrix)?).map(DisplayMatrix)
+    }
 }
 
 #[cfg(test)]
@@ -245,8 +334,10 @@ impl Packet {
 mod tests {
     use insta::assert_debug_snapshot;
 
+    use crate::AVPacketSideDataType;
     use crate::ffi::AVRational;
     use crate::packet::Packet;
+    use crate::side_data::DisplayMatrix;
 
     #[test]
     fn test_packet_clone_snapshot() {
@@ -380,4 +471,49 @@ mod tests {
             "Expected the data slice to be empty when packet size is zero"
         );
     }
+
+    #[test]
+    fn test_packet_side_data() {
+        let mut packet = Packet::new().expect("Failed to create Packet");
+        assert_eq!(packet.side_data(AVPacketSideDataType::A53Cc), None);
+        assert_eq!(packet.all_side_data().count(), 0);
+
+        let data = packet
+            .new_side_data(AVPacketSideDataType::SkipSamples, 10)
+            .expect("Failed to allocate side data");
+        assert_eq!(data, &[0; 10]);
+        data[0] = 42;
+        assert_eq!(
+            packet.side_data(AVPacketSideDataType::SkipSamples).map(|data| data[0]),
+            Some(42)
+        );
+
+        packet
+            .add_side_data(AVPacketSideDataType::A53Cc, &[0xfc, 0x80, 0x80])
+            .expect("Failed to add side data");
+        packet
+            .add_side_data(AVPacketSideDataType::A53Cc, &[0xfc, 0x94, 0x20])
+            .expect("Failed to add side data");
+        assert_eq!(
+            packet.side_data(AVPacketSideDataType::A53Cc),
+            Some([0xfc, 0x94, 0x20].as_slice())
+        );
+        assert_eq!(packet.all_side_data().count(), 2);
+
+        let matrix = DisplayMatrix::from_rotation(90.0);
+        let bytes: Vec<u8> = matrix.0.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        packet
+            .add_side_data(AVPacketSideDataType::DisplayMatrix, &bytes)
+            .expect("Failed to add side data");
+        assert_eq!(packet.display_matrix(), Some(matrix));
+        assert_eq!(packet.mastering_display_metadata(), None);
+
+        // Side data is kept when cloning the packet.
+        let cloned = packet.clone();
+        assert_eq!(cloned.display_matrix(), Some(matrix));
+
+        packet.clear_side_data();
+        assert_eq!(packet.all_side_data().count(), 0);
+        assert_eq!(cloned.all_side_data().count(), 3);
+    }
 }
@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::ffi::{CStr, c_char};
+
+use crate::dict::Dictionary;
+use crate::ffi::*;
+use crate::io::Input;
+use crate::rational::Rational;
+use crate::stream::Stream;
+use crate::utils::check_i64;
+use crate::{AVMediaType, AVPixelFormat, AVSampleFormat};
+
+/// The container and codec details of an input, see [`Input::probe`].
+///
+/// Holds about the same information as the JSON output of
+/// `ffprobe -show_format -show_streams -show_chapters`. Times are in seconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeReport {
+    /// The details of the container.
+    pub format: ProbeFormat,
+    /// The streams of the input, in order of their index.
+    pub streams: Vec<ProbeStream>,
+    /// The chapters of the input, in the order the container lists them.
+    pub chapters: Vec<ProbeChapter>,
+}
+
+/// The container details of a [`ProbeReport`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeFormat {
+    /// The short names of the demuxer, such as `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub format_name: String,
+    /// The descriptive name of the demuxer.
+    pub format_long_name: Option<String>,
+    /// The start time of the input.
+    pub start_time: Option<f64>,
+    /// The duration of the input.
+    pub duration: Option<f64>,
+    /// The total bit rate of the input in bits per second.
+    pub bit_rate: Option<i64>,
+    /// The metadata of the input, such as its title.
+    pub tags: BTreeMap<String, String>,
+}
+
+/// The details of a stream of a [`ProbeReport`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeStream {
+    /// The index of the stream.
+    pub index: i32,
+    /// The media type, such as `video` or `audio`.
+    pub codec_type: Option<String>,
+    /// The short name of the codec, such as `h264`.
+    pub codec_name: String,
+    /// The name of the codec profile, such as `High`.
+    pub profile: Option<String>,
+    /// The codec specific level, if known.
+    pub level: Option<i32>,
+    /// The container specific tag of the codec, such as a fourcc.
+    pub codec_tag: u32,
+    /// The bit rate of the stream in bits per second.
+    pub bit_rate: Option<i64>,
+    /// The time base of the timestamps of the stream.
+    pub time_base: Rational,
+    /// The start time of the stream.
+    pub start_time: Option<f64>,
+    /// The duration of the stream.
+    pub duration: Option<f64>,
+    /// The number of frames in the stream, if the container stores it.
+    pub nb_frames: Option<i64>,
+    /// The disposition flags of the stream, such as default or forced.
+    pub disposition: i32,
+    /// The width of the video.
+    pub width: Option<i32>,
+    /// The height of the video.
+    pub height: Option<i32>,
+    /// The pixel format of the video, such as `yuv420p`.
+    pub pix_fmt: Option<String>,
+    /// The sample aspect ratio of the video.
+    pub sample_aspect_ratio: Option<Rational>,
+    /// The average frame rate of the video.
+    pub avg_frame_rate: Option<Rational>,
+    /// The lowest frame rate that all timestamps of the video can be represented in.
+    pub r_frame_rate: Option<Rational>,
+    /// The counter-clockwise display rotation of the video in degrees.
+    pub rotation: Option<f64>,
+    /// The sample format of the audio, such as `fltp`.
+    pub sample_fmt: Option<String>,
+    /// The sample rate of the audio.
+    pub sample_rate: Option<i32>,
+    /// The number of audio channels.
+    pub channels: Option<i32>,
+    /// The metadata of the stream, such as its language.
+    pub tags: BTreeMap<String, String>,
+}
+
+/// A chapter of a [`ProbeReport`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeChapter {
+    /// The container specific id of the chapter.
+    pub id: i64,
+    /// The start time of the chapter.
+    pub start_time: f64,
+    /// The end time of the chapter.
+    pub end_time: f64,
+    /// The metadata of the chapter, such as its title.
+    pub tags: BTreeMap<String, String>,
+}
+
+impl<T: Send + Sync> Input<T> {
+    /// Returns the container and codec details of the input.
+    ///
+    /// Only looks at what was read while opening the input, no packets are
+    /// read or decoded.
+    pub fn probe(&self) -> ProbeReport {
+        // Safety: The pointer is valid for the lifetime of the input.
+        let context = unsafe { &*self.as_ptr() };
+
+        // Safety: The input format is set once the input is opened and is static.
+        let input_format = unsafe { context.iformat.as_ref() };
+        let format = ProbeFormat {
+            format_name: input_format.and_then(|format| c_name(format.name)).unwrap_or_default(),
+            format_long_name: input_format.and_then(|format| c_name(format.long_name)),
+            start_time: check_i64(context.start_time).map(|ts| ts as f64 / AV_TIME_BASE as f64),
+            duration: check_i64(context.duration).map(|ts| ts as f64 / AV_TIME_BASE as f64),
+            bit_rate: (context.bit_rate > 0).then_some(context.bit_rate),
+            tags: tags(&self.metadata()),
+        };
+
+        let streams = self.streams();
+        let streams = streams.iter().map(|stream| probe_stream(&stream)).collect();
+
+        let chapters = self
+            .chapters()
+            .into_iter()
+            .map(|chapter| ProbeChapter {
+                id: chapter.id,
+                start_time: chapter.start_seconds(),
+                end_time: chapter.end_seconds(),
+                tags: tags(&chapter.metadata),
+            })
+            .collect();
+
+        ProbeReport {
+            format,
+            streams,
+            chapters,
+        }
+    }
+}
+
+fn probe_stream(stream: &Stream<'_>) -> ProbeStream {
+    let time_base = stream.time_base();
+    let mut probe = ProbeStream {
+        index: stream.index(),
+        codec_type: None,
+        codec_name: String::new(),
+        profile: None,
+        level: None,
+        codec_tag: 0,
+        bit_rate: None,
+        time_base,
+        start_time: stream.start_time().map(|ts| seconds(ts, time_base)),
+        duration: stream.duration().map(|ts| seconds(ts, time_base)),
+        nb_frames: stream.nb_frames().filter(|&nb_frames| nb_frames > 0),
+        disposition: stream.disposition(),
+        width: None,
+        height: None,
+        pix_fmt: None,
+        sample_aspect_ratio: None,
+        avg_frame_rate: None,
+        r_frame_rate: None,
+        rotation: None,
+        sample_fmt: None,
+        sample_rate: None,
+        channels: None,
+        tags: tags(&stream.metadata()),
+    };
+
+    let Some(params) = stream.codec_parameters() else {
+        return probe;
+    };
+
+    // Safety: `av_get_media_type_string` is safe to call with any media type.
+    probe.codec_type = c_name(unsafe { av_get_media_type_string(params.codec_type) });
+    // Safety: `avcodec_get_name` is safe to call with any codec id.
+    probe.codec_name = c_name(unsafe { avcodec_get_name(params.codec_id) }).unwrap_or_default();
+    // Safety: `avcodec_profile_name` is safe to call with any codec id and profile.
+    probe.profile = c_name(unsafe { avcodec_profile_name(params.codec_id, params.profile) });
+    probe.level = (params.level >= 0).then_some(params.level);
+    probe.codec_tag = params.codec_tag;
+    probe.bit_rate = (params.bit_rate > 0).then_some(params.bit_rate);
+
+    match AVMediaType(params.codec_type) {
+        AVMediaType::Video => {
+            let pixel_format = AVPixelFormat(params.format);
+            probe.width = Some(params.width);
+            probe.height = Some(params.height);
+            // Safety: `av_get_pix_fmt_name` is safe to call with any pixel format.
+            probe.pix_fmt = c_name(unsafe { av_get_pix_fmt_name(pixel_format.0) });
+            probe.sample_aspect_ratio = non_zero(stream.sample_aspect_ratio());
+            probe.avg_frame_rate = non_zero(stream.avg_frame_rate());
+            probe.r_frame_rate = non_zero(stream.r_frame_rate());
+            probe.rotation = stream.display_matrix().and_then(|matrix| matrix.rotation());
+        }
+        AVMediaType::Audio => {
+            let sample_format = AVSampleFormat(params.format);
+            // Safety: `av_get_sample_fmt_name` is safe to call with any sample format.
+            probe.sample_fmt = c_name(unsafe { av_get_sample_fmt_name(sample_format.0) });
+            probe.sample_rate = Some(params.sample_rate);
+            probe.channels = Some(params.ch_layout.nb_channels);
+        }
+        _ => {}
+    }
+
+    probe
+}
+
+fn seconds(ts: i64, time_base: Rational) -> f64 {
+    ts as f64 * time_base.as_f64()
+}
+
+fn non_zero(rational: Rational) -> Option<Rational> {
+    (rational.numerator != 0).then_some(rational)
+}
+
+fn tags(dictionary: &Dictionary) -> BTreeMap<String, String> {
+    dictionary
+        .iter_str()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect()
+}
+
+fn c_name(name: *const c_char) -> Option<String> {
+    if name.is_null() {
+        return None;
+    }
+
+    // Safety: the pointer is a valid nul terminated static string returned by FFmpeg.
+    Some(unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::io::Input;
+    #[cfg(feature = "serde")]
+    use crate::probe::ProbeReport;
+    use crate::rational::Rational;
+
+    #[test]
+    fn test_probe() {
+        let input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+        let report = input.probe();
+
+        assert_eq!(report.format.format_name, "mov,mp4,m4a,3gp,3g2,mj2");
+        assert!(report.format.duration.is_some_and(|duration| duration > 0.0));
+        assert!(report.format.bit_rate.is_some());
+        assert!(report.chapters.is_empty());
+        assert_eq!(report.streams.len(), 2);
+
+        let video = &report.streams[0];
+        assert_eq!(video.index, 0);
+        assert_eq!(video.codec_type.as_deref(), Some("video"));
+        assert_eq!(video.codec_name, "h264");
+        assert_eq!(video.time_base, Rational::static_new::<1, 15360>());
+        assert_eq!(video.duration, Some(16384.0 / 15360.0));
+        assert_eq!(video.nb_frames, Some(64));
+        assert_eq!(video.pix_fmt.as_deref(), Some("yuv420p"));
+        assert_eq!(video.avg_frame_rate, Some(Rational::static_new::<60, 1>()));
+        assert_eq!(video.rotation, None);
+        assert_eq!(video.sample_fmt, None);
+        assert_eq!(video.tags.get("language").map(String::as_str), Some("und"));
+
+        let audio = &report.streams[1];
+        assert_eq!(audio.codec_type.as_deref(), Some("audio"));
+        assert_eq!(audio.codec_name, "aac");
+        assert_eq!(audio.profile.as_deref(), Some("LC"));
+        assert_eq!(audio.sample_fmt.as_deref(), Some("fltp"));
+        assert_eq!(audio.sample_rate, Some(48000));
+        assert_eq!(audio.width, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_probe_serde() {
+        let input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+        let report = input.probe();
+
+        let json = serde_json::to_value(&report).expect("Failed to serialize report");
+        assert_eq!(json["streams"][0]["codec_name"], "h264");
+        assert_eq!(json["streams"][0]["time_base"]["denominator"], 15360);
+
+        let deserialized: ProbeReport = serde_json::from_value(json).expect("Failed to deserialize report");
+        assert_eq!(deserialized.format.format_name, report.format.format_name);
+        assert_eq!(deserialized.streams[1].tags, report.streams[1].tags);
+        assert_eq!(deserialized.streams[0].time_base, report.streams[0].time_base);
+    }
+}
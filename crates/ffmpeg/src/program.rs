@@ -0,0 +1,95 @@
+use crate::AVDiscard;
+use crate::dict::Dictionary;
+use crate::ffi::*;
+
+/// A program of an input, the streams that are played together.
+///
+/// MPEG-TS inputs can carry several programs, such as the channels of a
+/// broadcast, each with its own video and audio streams.
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// The id of the program, the program number for MPEG-TS.
+    pub id: i32,
+    /// The program number as stored in the program association table.
+    pub program_num: i32,
+    /// The PID of the program map table of the program.
+    pub pmt_pid: i32,
+    /// The PID of the stream that carries the clock of the program.
+    pub pcr_pid: i32,
+    /// Whether the demuxer discards the packets of the program.
+    pub discard: AVDiscard,
+    /// The indices of the streams of the program.
+    pub stream_indices: Vec<usize>,
+    /// The metadata of the program, such as its service name.
+    pub metadata: Dictionary,
+}
+
+impl Program {
+    /// Returns whether the stream with the given index belongs to the program.
+    pub fn contains_stream(&self, stream_index: usize) -> bool {
+        self.stream_indices.contains(&stream_index)
+    }
+}
+
+/// Returns the programs of a format context.
+pub(crate) fn raw_programs(context: &AVFormatContext) -> &[*mut AVProgram] {
+    if context.programs.is_null() {
+        return &[];
+    }
+
+    // Safety: `programs` points to `nb_programs` program pointers owned by the format context.
+    unsafe { std::slice::from_raw_parts(context.programs, context.nb_programs as usize) }
+}
+
+/// Returns the stream indices of a program.
+pub(crate) fn raw_stream_indices(program: &AVProgram) -> &[u32] {
+    if program.stream_index.is_null() {
+        return &[];
+    }
+
+    // Safety: `stream_index` points to `nb_stream_indexes` stream indices owned by the program.
+    unsafe { std::slice::from_raw_parts(program.stream_index, program.nb_stream_indexes as usize) }
+}
+
+/// Copies the programs of a format context.
+pub(crate) fn read_programs(context: &AVFormatContext) -> Vec<Program> {
+    raw_programs(context)
+        .iter()
+        // Safety: The programs are valid for as long as the format context.
+        .filter_map(|program| unsafe { program.as_ref() })
+        .map(|program| Program {
+            id: program.id,
+            program_num: program.program_num,
+            pmt_pid: program.pmt_pid,
+            pcr_pid: program.pcr_pid,
+            discard: AVDiscard(program.discard),
+            stream_indices: raw_stream_indices(program).iter().map(|&index| index as usize).collect(),
+            // Safety: The metadata is owned by the program, the clone copies it.
+            metadata: unsafe { Dictionary::from_ptr_ref(program.metadata) }.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::AVDiscard;
+    use crate::dict::Dictionary;
+    use crate::program::Program;
+
+    #[test]
+    fn test_program_contains_stream() {
+        let program = Program {
+            id: 1,
+            program_num: 1,
+            pmt_pid: 0x1000,
+            pcr_pid: 0x100,
+            discard: AVDiscard::Default,
+            stream_indices: vec![0, 2],
+            metadata: Dictionary::new(),
+        };
+
+        assert!(program.contains_stream(2));
+        assert!(!program.contains_stream(1));
+    }
+}
@@ -4,6 +4,7 @@ use rusty_ffmpeg::ffi::AVRational;
 
 /// A rational number.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rational {
     /// Numerator.
     pub numerator: i32,
@@ -1,8 +1,12 @@
-use rusty_ffmpeg::ffi::{SwrContext, swr_alloc_set_opts2, swr_convert_frame, swr_free, swr_init};
+use rusty_ffmpeg::ffi::{
+    AVAudioFifo, AVRational, SwrContext, av_audio_fifo_alloc, av_audio_fifo_free, av_audio_fifo_read, av_audio_fifo_size,
+    av_audio_fifo_write, av_rescale_q, swr_alloc_set_opts2, swr_convert_frame, swr_free, swr_get_delay, swr_init,
+};
 
 use crate::enums::AVSampleFormat;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::frame::{AudioChannelLayout, AudioFrame, GenericFrame};
+use crate::rational::Rational;
 use crate::smart_object::SmartPtr;
 
 /// A wrapper around an [`SwrContext`]. Which is used to resample and convert [`AudioFrame`]s.
@@ -62,6 +66,24 @@ impl Resampler {
 
     /// Process an [`AudioFrame`] thought the resampler
     pub fn process(&mut self, input: &AudioFrame) -> Result<AudioFrame, FfmpegError> {
+        self.convert(input.as_ptr())
+    }
+
+    /// Drains the samples the resampler buffered, after the last input frame.
+    ///
+    /// Returns `None` once no samples are left.
+    pub fn flush(&mut self) -> Result<Option<AudioFrame>, FfmpegError> {
+        let frame = self.convert(std::ptr::null())?;
+        Ok((frame.nb_samples() > 0).then_some(frame))
+    }
+
+    /// The number of samples the resampler buffered, in the output sample rate.
+    pub fn delay(&self) -> i64 {
+        // Safety: self.ptr is initialized and valid, swr_get_delay is safe to call
+        unsafe { swr_get_delay(self.ptr.as_ptr().cast_mut(), self.sample_rate as i64) }
+    }
+
+    fn convert(&mut self, input: *const rusty_ffmpeg::ffi::AVFrame) -> Result<AudioFrame, FfmpegError> {
         let mut out = GenericFrame::new()?;
 
         // Safety: the GenericFrame is allocated
@@ -71,7 +93,7 @@ impl Resampler {
         inner.sample_rate = self.sample_rate();
 
         // Safety: self.ptr is initialized and valid, data buffers of out get initialized here, swr_convert_frame is safe to call
-        FfmpegErrorCode(unsafe { swr_convert_frame(self.ptr.as_mut_ptr(), out.as_mut_ptr(), input.as_ptr()) }).result()?;
+        FfmpegErrorCode(unsafe { swr_convert_frame(self.ptr.as_mut_ptr(), out.as_mut_ptr(), input) }).result()?;
 
         // Safety: swr_convert_frame was successful, the pointer is valid;
         Ok(out.audio())
@@ -93,15 +115,194 @@ impl Resampler {
     }
 }
 
+/// Resamples [`AudioFrame`]s into frames of a fixed number of samples.
+///
+/// Decoders output frames of any size, while encoders such as AAC only accept
+/// frames of exactly [`Encoder::frame_size`](crate::encoder::Encoder::frame_size)
+/// samples. The resampled samples are buffered and handed out in frames of
+/// `frame_size` samples, with timestamps in a time base of `1 / output_sample_rate`
+/// counted from the pts of the first frame.
+///
+/// Works like an encoder: send frames with [`AudioResampler::send_frame`] and take the
+/// resampled frames out with [`AudioResampler::receive_frame`] until it returns `None`.
+/// After the last frame, call [`AudioResampler::send_eof`] so the buffered samples are
+/// drained, the last frame may then be shorter than `frame_size`.
+pub struct AudioResampler {
+    resampler: Resampler,
+    fifo: SmartPtr<AVAudioFifo>,
+    frame_size: Option<i32>,
+    next_pts: Option<i64>,
+    eof: bool,
+}
+
+/// Safety: `AudioResampler` can be sent between threads.
+unsafe impl Send for AudioResampler {}
+
+#[bon::bon]
+impl AudioResampler {
+    /// Creates a new [`AudioResampler`].
+    #[builder]
+    pub fn new(
+        input_ch_layout: AudioChannelLayout,
+        input_sample_fmt: AVSampleFormat,
+        input_sample_rate: i32,
+        output_ch_layout: AudioChannelLayout,
+        output_sample_fmt: AVSampleFormat,
+        output_sample_rate: i32,
+        /// The number of samples in every output frame, usually the frame size of the encoder.
+        /// If not set, all buffered samples are returned in one frame.
+        frame_size: Option<i32>,
+    ) -> Result<Self, FfmpegError> {
+        if frame_size.is_some_and(|frame_size| frame_size <= 0) {
+            return Err(FfmpegError::Arguments("frame_size must be positive and not 0"));
+        }
+
+        let channels = output_ch_layout.channel_count();
+        let resampler = Resampler::new(
+            input_ch_layout,
+            input_sample_fmt,
+            input_sample_rate,
+            output_ch_layout,
+            output_sample_fmt,
+            output_sample_rate,
+        )?;
+
+        // Safety: av_audio_fifo_alloc is safe to call, the fifo grows as samples are written.
+        let fifo = unsafe { av_audio_fifo_alloc(output_sample_fmt.0 as _, channels, frame_size.unwrap_or(1024)) };
+
+        let destructor = |fifo: &mut *mut AVAudioFifo| {
+            // Safety: av_audio_fifo_free is safe to call with a fifo from av_audio_fifo_alloc
+            unsafe { av_audio_fifo_free(*fifo) };
+        };
+
+        // Safety: the pointer is valid and the destructor frees the fifo
+        let fifo = unsafe { SmartPtr::wrap_non_null(fifo, destructor) }.ok_or(FfmpegError::Alloc)?;
+
+        Ok(Self {
+            resampler,
+            fifo,
+            frame_size,
+            next_pts: None,
+            eof: false,
+        })
+    }
+
+    /// Sends a frame to the resampler.
+    pub fn send_frame(&mut self, frame: &AudioFrame) -> Result<(), FfmpegError> {
+        if self.eof {
+            return Err(FfmpegError::Arguments("send_frame called after send_eof"));
+        }
+
+        if self.next_pts.is_none() {
+            let time_base: AVRational = frame.time_base().into();
+            if let (Some(pts), true) = (frame.pts(), time_base.num > 0) {
+                // Safety: av_rescale_q is safe to call
+                self.next_pts = Some(unsafe { av_rescale_q(pts, time_base, self.time_base().into()) });
+            }
+        }
+
+        let converted = self.resampler.process(frame)?;
+        self.write(&converted)
+    }
+
+    /// Drains the samples buffered in the resampler, after the last frame was sent.
+    pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
+        if self.eof {
+            return Ok(());
+        }
+
+        while let Some(frame) = self.resampler.flush()? {
+            self.write(&frame)?;
+        }
+
+        self.eof = true;
+        Ok(())
+    }
+
+    /// Receives a resampled frame, `None` if not enough samples are buffered for a full frame.
+    ///
+    /// After [`AudioResampler::send_eof`] the remaining samples are returned in a shorter frame.
+    pub fn receive_frame(&mut self) -> Result<Option<AudioFrame>, FfmpegError> {
+        let buffered = self.buffered_samples();
+        let nb_samples = match self.frame_size {
+            Some(frame_size) if buffered >= frame_size => frame_size,
+            Some(_) if self.eof => buffered,
+            Some(_) => 0,
+            None => buffered,
+        };
+
+        if nb_samples == 0 {
+            return Ok(None);
+        }
+
+        let pts = self.next_pts.unwrap_or(0);
+        let mut frame = AudioFrame::builder()
+            .channel_layout(self.resampler.channel_layout().copy()?)
+            .nb_samples(nb_samples)
+            .sample_fmt(self.resampler.sample_format())
+            .sample_rate(self.resampler.sample_rate())
+            .pts(pts)
+            .duration(nb_samples as i64)
+            .time_base(self.time_base())
+            .build()?;
+
+        // Safety: the frame was just allocated and is valid
+        let data = unsafe { (*frame.as_mut_ptr()).extended_data };
+
+        // Safety: the frame has buffers for `nb_samples` samples in the format and layout of the fifo
+        let read = unsafe { av_audio_fifo_read(self.fifo.as_mut_ptr(), data as _, nb_samples) };
+        FfmpegErrorCode(read).result()?;
+
+        self.next_pts = Some(pts + nb_samples as i64);
+        Ok(Some(frame))
+    }
+
+    /// The number of resampled samples waiting to be received.
+    pub fn buffered_samples(&self) -> i32 {
+        // Safety: the fifo is valid, av_audio_fifo_size is safe to call
+        unsafe { av_audio_fifo_size(self.fifo.as_ptr().cast_mut()) }
+    }
+
+    /// The time base of the output frames, `1 / output_sample_rate`.
+    pub fn time_base(&self) -> Rational {
+        AVRational {
+            num: 1,
+            den: self.resampler.sample_rate(),
+        }
+        .into()
+    }
+
+    /// The underlying resampler, which holds the output format.
+    pub const fn resampler(&self) -> &Resampler {
+        &self.resampler
+    }
+
+    fn write(&mut self, frame: &AudioFrame) -> Result<(), FfmpegError> {
+        if frame.nb_samples() == 0 {
+            return Ok(());
+        }
+
+        // Safety: the frame is valid
+        let data = unsafe { (*frame.as_ptr()).extended_data };
+
+        // Safety: the frame holds `nb_samples` samples in the format and layout of the fifo
+        let written = unsafe { av_audio_fifo_write(self.fifo.as_mut_ptr(), data as _, frame.nb_samples()) };
+        FfmpegErrorCode(written).result()?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
     use rand::{Rng, rng};
     use rusty_ffmpeg::ffi::swr_is_initialized;
 
-    use super::Resampler;
+    use super::{AudioResampler, Resampler};
     use crate::AVSampleFormat;
     use crate::frame::{AudioChannelLayout, AudioFrame};
+    use crate::rational::Rational;
 
     #[test]
     fn test_resampler_new() {
@@ -167,4 +368,82 @@ mod tests {
         assert!(output.data(1).is_some(), "Second data buffer of output frame is None");
         assert_eq!(output.sample_rate(), 48000, "Output sample rate was not 48000");
     }
+
+    #[test]
+    fn test_audio_resampler_frame_size() {
+        let mut resampler = AudioResampler::builder()
+            .input_ch_layout(AudioChannelLayout::new(2).expect("Failed to create new AudioChannelLayout"))
+            .input_sample_fmt(AVSampleFormat::S16)
+            .input_sample_rate(44100)
+            .output_ch_layout(AudioChannelLayout::new(2).expect("Failed to create new AudioChannelLayout"))
+            .output_sample_fmt(AVSampleFormat::Fltp)
+            .output_sample_rate(48000)
+            .frame_size(1024)
+            .build()
+            .expect("Failed to create new AudioResampler");
+
+        assert_eq!(resampler.time_base(), Rational::static_new::<1, 48000>());
+
+        let mut frames = Vec::new();
+        for index in 0..10 {
+            let mut frame = AudioFrame::builder()
+                .channel_layout(AudioChannelLayout::new(2).expect("Failed to create new AudioChannelLayout"))
+                .nb_samples(1000)
+                .sample_fmt(AVSampleFormat::S16)
+                .sample_rate(44100)
+                .pts(1000 * index)
+                .time_base(Rational::static_new::<1, 44100>())
+                .build()
+                .expect("Failed to create new AudioFrame");
+            rng().fill(frame.data_mut(0).unwrap());
+
+            resampler.send_frame(&frame).expect("Failed to send frame");
+            while let Some(frame) = resampler.receive_frame().expect("Failed to receive frame") {
+                frames.push(frame);
+            }
+        }
+
+        // Everything but the samples held back by the resampler is in full frames.
+        assert!(frames.iter().all(|frame| frame.nb_samples() == 1024));
+        assert!(resampler.buffered_samples() < 1024);
+
+        resampler.send_eof().expect("Failed to send eof");
+        while let Some(frame) = resampler.receive_frame().expect("Failed to receive frame") {
+            frames.push(frame);
+        }
+
+        let (last, full) = frames.split_last().unwrap();
+        assert!(full.iter().all(|frame| frame.nb_samples() == 1024));
+        assert!(last.nb_samples() <= 1024);
+
+        for (index, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.pts(), Some(index as i64 * 1024));
+            assert_eq!(frame.sample_format(), AVSampleFormat::Fltp);
+            assert_eq!(frame.sample_rate(), 48000);
+            assert_eq!(frame.time_base(), Rational::static_new::<1, 48000>());
+        }
+
+        // 10000 samples at 44100Hz are about 10884 samples at 48000Hz.
+        let total: i64 = frames.iter().map(|frame| frame.nb_samples() as i64).sum();
+        assert!((10880..=10890).contains(&total), "unexpected number of samples: {total}");
+
+        assert_eq!(resampler.buffered_samples(), 0);
+        assert!(resampler.receive_frame().unwrap().is_none());
+        assert!(resampler.send_frame(&frames[0]).is_err());
+    }
+
+    #[test]
+    fn test_audio_resampler_invalid_frame_size() {
+        let resampler = AudioResampler::builder()
+            .input_ch_layout(AudioChannelLayout::new(1).expect("Failed to create new AudioChannelLayout"))
+            .input_sample_fmt(AVSampleFormat::S16)
+            .input_sample_rate(44100)
+            .output_ch_layout(AudioChannelLayout::new(1).expect("Failed to create new AudioChannelLayout"))
+            .output_sample_fmt(AVSampleFormat::Fltp)
+            .output_sample_rate(48000)
+            .frame_size(0)
+            .build();
+
+        assert!(resampler.is_err());
+    }
 }
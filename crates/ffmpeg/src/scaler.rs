@@ -1,23 +1,141 @@
+use std::ffi::CStr;
+
 use crate::AVPixelFormat;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::frame::VideoFrame;
+use crate::frame_pool::FramePool;
 use crate::smart_object::SmartPtr;
 
+/// The algorithm used to scale frames, see [`ScalerOptions::algorithm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleAlgorithm {
+    /// A faster, less accurate bilinear filter.
+    FastBilinear,
+    /// Bilinear filter, a good tradeoff between speed and quality.
+    #[default]
+    Bilinear,
+    /// Bicubic filter, sharper than bilinear.
+    Bicubic,
+    /// Lanczos filter, the sharpest of the common filters, best for downscaling.
+    Lanczos,
+    /// Natural bicubic spline filter.
+    Spline,
+    /// Nearest neighbor, the fastest and lowest quality.
+    Point,
+    /// Averages the area of the input pixels covered by each output pixel.
+    Area,
+}
+
+impl ScaleAlgorithm {
+    const fn flags(self) -> u32 {
+        match self {
+            Self::FastBilinear => SWS_FAST_BILINEAR,
+            Self::Bilinear => SWS_BILINEAR,
+            Self::Bicubic => SWS_BICUBIC,
+            Self::Lanczos => SWS_LANCZOS,
+            Self::Spline => SWS_SPLINE,
+            Self::Point => SWS_POINT,
+            Self::Area => SWS_AREA,
+        }
+    }
+}
+
+/// How the scaler dithers when converting to a lower bit depth, see [`ScalerOptions::dither`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleDither {
+    /// Lets swscale pick, usually ordered dithering.
+    #[default]
+    Auto,
+    /// Ordered dithering with a Bayer matrix.
+    Bayer,
+    /// Error diffusion dithering.
+    ErrorDiffusion,
+    /// Arithmetic dithering, based on addition.
+    ArithmeticAdd,
+    /// Arithmetic dithering, based on xor.
+    ArithmeticXor,
+    /// No dithering, values are truncated.
+    None,
+}
+
+impl ScaleDither {
+    const fn name(self) -> &'static CStr {
+        match self {
+            Self::Auto => c"auto",
+            Self::Bayer => c"bayer",
+            Self::ErrorDiffusion => c"ed",
+            Self::ArithmeticAdd => c"a_dither",
+            Self::ArithmeticXor => c"x_dither",
+            Self::None => c"none",
+        }
+    }
+}
+
+/// Quality options of a [`VideoScaler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bon::Builder)]
+pub struct ScalerOptions {
+    /// The scaling algorithm.
+    #[builder(default)]
+    pub algorithm: ScaleAlgorithm,
+    /// The dithering used when converting to a lower bit depth.
+    #[builder(default)]
+    pub dither: ScaleDither,
+    /// Interpolates the chroma planes at full resolution, instead of scaling subsampled
+    /// chroma as is. Slower, but avoids chroma bleeding when converting to or from RGB.
+    #[builder(default)]
+    pub full_chroma_interpolation: bool,
+    /// Rounds accurately instead of favoring speed.
+    #[builder(default)]
+    pub accurate_rounding: bool,
+}
+
+impl Default for ScalerOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl ScalerOptions {
+    /// Returns the `sws_flags` of the options.
+    const fn flags(&self) -> u32 {
+        let mut flags = self.algorithm.flags();
+        if self.full_chroma_interpolation {
+            flags |= SWS_FULL_CHR_H_INT | SWS_FULL_CHR_H_INP;
+        }
+        if self.accurate_rounding {
+            flags |= SWS_ACCURATE_RND;
+        }
+        flags
+    }
+}
+
+/// The size and pixel format of the frames a [`VideoScaler`] scales from or to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ScalerFormat {
+    width: i32,
+    height: i32,
+    pixel_format: AVPixelFormat,
+}
+
 /// A scaler is a wrapper around an [`SwsContext`]. Which is used to scale or transform video frames.
+///
+/// The scaler follows changes of the input size or pixel format, as adaptive sources do,
+/// by re-creating the context when a frame with a different size or format is processed.
+/// The output size and format do not change.
 pub struct VideoScaler {
     ptr: SmartPtr<SwsContext>,
     frame: VideoFrame,
-    pixel_format: AVPixelFormat,
-    width: i32,
-    height: i32,
+    input: ScalerFormat,
+    output: ScalerFormat,
+    options: ScalerOptions,
 }
 
 /// Safety: `Scaler` is safe to send between threads.
 unsafe impl Send for VideoScaler {}
 
 impl VideoScaler {
-    /// Creates a new `Scaler` instance.
+    /// Creates a new `Scaler` instance, with the default [`ScalerOptions`].
     pub fn new(
         input_width: i32,
         input_height: i32,
@@ -26,22 +144,64 @@ impl VideoScaler {
         height: i32,
         pixel_format: AVPixelFormat,
     ) -> Result<Self, FfmpegError> {
-        // Safety: `sws_getContext` is safe to call, and the pointer returned is valid.
-        let ptr = unsafe {
-            sws_getContext(
-                input_width,
-                input_height,
-                incoming_pixel_fmt.into(),
-                width,
-                height,
-                pixel_format.into(),
-                SWS_BILINEAR as i32,
-                std::ptr::null_mut(),
-                std::ptr::null_mut(),
-                std::ptr::null(),
-            )
+        Self::with_options(
+            input_width,
+            input_height,
+            incoming_pixel_fmt,
+            width,
+            height,
+            pixel_format,
+            ScalerOptions::default(),
+        )
+    }
+
+    /// Creates a new `Scaler` instance with the given quality options.
+    pub fn with_options(
+        input_width: i32,
+        input_height: i32,
+        incoming_pixel_fmt: AVPixelFormat,
+        width: i32,
+        height: i32,
+        pixel_format: AVPixelFormat,
+        options: ScalerOptions,
+    ) -> Result<Self, FfmpegError> {
+        let input = ScalerFormat {
+            width: input_width,
+            height: input_height,
+            pixel_format: incoming_pixel_fmt,
+        };
+        let output = ScalerFormat {
+            width,
+            height,
+            pixel_format,
         };
 
+        let ptr = Self::alloc_context(input, output, options)?;
+
+        let frame = VideoFrame::builder()
+            .width(width)
+            .height(height)
+            .pix_fmt(pixel_format)
+            .build()?;
+
+        Ok(Self {
+            ptr,
+            frame,
+            input,
+            output,
+            options,
+        })
+    }
+
+    /// Allocates and initializes a context scaling from `input` to `output`.
+    fn alloc_context(
+        input: ScalerFormat,
+        output: ScalerFormat,
+        options: ScalerOptions,
+    ) -> Result<SmartPtr<SwsContext>, FfmpegError> {
+        // Safety: `sws_alloc_context` is safe to call.
+        let ptr = unsafe { sws_alloc_context() };
+
         let destructor = |ptr: &mut *mut SwsContext| {
             // Safety: `sws_freeContext` is safe to call.
             unsafe {
@@ -52,66 +212,145 @@ impl VideoScaler {
         };
 
         // Safety: `ptr` is a valid pointer & `destructor` has been setup to free the context.
-        let ptr = unsafe { SmartPtr::wrap_non_null(ptr, destructor) }.ok_or(FfmpegError::Alloc)?;
+        let mut ptr = unsafe { SmartPtr::wrap_non_null(ptr, destructor) }.ok_or(FfmpegError::Alloc)?;
 
-        let frame = VideoFrame::builder()
-            .width(width)
-            .height(height)
-            .pix_fmt(pixel_format)
-            .build()?;
+        let int_options = [
+            (c"srcw", i64::from(input.width)),
+            (c"srch", i64::from(input.height)),
+            (c"src_format", i64::from(input.pixel_format.0)),
+            (c"dstw", i64::from(output.width)),
+            (c"dsth", i64::from(output.height)),
+            (c"dst_format", i64::from(output.pixel_format.0)),
+            (c"sws_flags", i64::from(options.flags())),
+        ];
 
-        Ok(Self {
-            ptr,
-            frame,
-            pixel_format,
-            width,
-            height,
+        for (name, value) in int_options {
+            // Safety: The context is valid and `name` is a valid c-string.
+            FfmpegErrorCode(unsafe { av_opt_set_int(ptr.as_mut_ptr().cast(), name.as_ptr(), value, 0) }).result()?;
+        }
+
+        // Safety: The context is valid and the name and value are valid c-strings.
+        FfmpegErrorCode(unsafe {
+            av_opt_set(
+                ptr.as_mut_ptr().cast(),
+                c"sws_dither".as_ptr(),
+                options.dither.name().as_ptr(),
+                0,
+            )
         })
+        .result()?;
+
+        // Safety: The context is valid and configured, the filters may be null.
+        FfmpegErrorCode(unsafe { sws_init_context(ptr.as_mut_ptr(), std::ptr::null_mut(), std::ptr::null_mut()) })
+            .result()?;
+
+        Ok(ptr)
     }
 
     /// Returns the pixel format of the scalar.
     pub const fn pixel_format(&self) -> AVPixelFormat {
-        self.pixel_format
+        self.output.pixel_format
     }
 
     /// Returns the width of the scalar.
     pub const fn width(&self) -> i32 {
-        self.width
+        self.output.width
     }
 
     /// Returns the height of the scalar.
     pub const fn height(&self) -> i32 {
-        self.height
+        self.output.height
+    }
+
+    /// Returns the quality options of the scalar.
+    pub const fn options(&self) -> ScalerOptions {
+        self.options
+    }
+
+    /// Changes the quality options of the scalar, re-creating the context.
+    pub fn set_options(&mut self, options: ScalerOptions) -> Result<(), FfmpegError> {
+        self.ptr = Self::alloc_context(self.input, self.output, options)?;
+        self.options = options;
+        Ok(())
     }
 
     /// Processes a frame through the scalar.
+    ///
+    /// If the size or pixel format of `frame` differs from the previous input,
+    /// the context is re-created for it first.
     pub fn process<'a>(&'a mut self, frame: &VideoFrame) -> Result<&'a VideoFrame, FfmpegError> {
+        self.follow_input(frame)?;
+        Self::scale(&mut self.ptr, frame, &mut self.frame)?;
+        Ok(&self.frame)
+    }
+
+    /// Processes a frame through the scalar into a frame from `pool`, see [`VideoScaler::process`].
+    ///
+    /// Unlike the frame returned by [`VideoScaler::process`], which is overwritten by the
+    /// next frame, the returned frame is owned and can be kept, such as to be queued
+    /// for an encoder. `pool` must have the output size and pixel format of the scaler.
+    pub fn process_into(&mut self, frame: &VideoFrame, pool: &FramePool) -> Result<VideoFrame, FfmpegError> {
+        let pool_format = ScalerFormat {
+            width: pool.width(),
+            height: pool.height(),
+            pixel_format: pool.pixel_format(),
+        };
+        if pool_format != self.output {
+            return Err(FfmpegError::Arguments("frame pool does not match the scaler output"));
+        }
+
+        self.follow_input(frame)?;
+
+        let mut output = pool.get()?;
+        Self::scale(&mut self.ptr, frame, &mut output)?;
+        Ok(output)
+    }
+
+    /// Re-creates the context if the size or pixel format of `frame` differs from the previous input.
+    fn follow_input(&mut self, frame: &VideoFrame) -> Result<(), FfmpegError> {
+        let input = ScalerFormat {
+            width: frame.width() as i32,
+            height: frame.height() as i32,
+            pixel_format: frame.format(),
+        };
+
+        if input != self.input {
+            self.ptr = Self::alloc_context(input, self.output, self.options)?;
+            self.input = input;
+        }
+
+        Ok(())
+    }
+
+    /// Scales `frame` into `output` with the context `ptr`.
+    fn scale(ptr: &mut SmartPtr<SwsContext>, frame: &VideoFrame, output: &mut VideoFrame) -> Result<(), FfmpegError> {
         // Safety: `frame` is a valid pointer, and `self.ptr` is a valid pointer.
         let frame_ptr = unsafe { frame.as_ptr().as_ref().unwrap() };
-        // Safety: `self.frame` is a valid pointer.
-        let self_frame_ptr = unsafe { self.frame.as_ptr().as_ref().unwrap() };
+        // Safety: `output` is a valid pointer.
+        let output_ptr = unsafe { output.as_ptr().as_ref().unwrap() };
 
         // Safety: `sws_scale` is safe to call.
         FfmpegErrorCode(unsafe {
             sws_scale(
-                self.ptr.as_mut_ptr(),
+                ptr.as_mut_ptr(),
                 frame_ptr.data.as_ptr() as *const *const u8,
                 frame_ptr.linesize.as_ptr(),
                 0,
                 frame_ptr.height,
-                self_frame_ptr.data.as_ptr(),
-                self_frame_ptr.linesize.as_ptr(),
+                output_ptr.data.as_ptr(),
+                output_ptr.linesize.as_ptr(),
             )
         })
         .result()?;
 
         // Copy the other fields from the input frame to the output frame.
-        self.frame.set_dts(frame.dts());
-        self.frame.set_pts(frame.pts());
-        self.frame.set_duration(frame.duration());
-        self.frame.set_time_base(frame.time_base());
+        output.set_dts(frame.dts());
+        output.set_pts(frame.pts());
+        output.set_duration(frame.duration());
+        output.set_time_base(frame.time_base());
+        output.copy_side_data(frame)?;
 
-        Ok(&self.frame)
+        Ok(())
     }
 }
 
@@ -121,8 +360,10 @@ mod tests {
     use insta::assert_debug_snapshot;
     use rand::Rng;
 
+    use crate::ffi::*;
     use crate::frame::VideoFrame;
-    use crate::scaler::{AVPixelFormat, VideoScaler};
+    use crate::frame_pool::FramePool;
+    use crate::scaler::{AVPixelFormat, ScaleAlgorithm, ScaleDither, ScalerOptions, VideoScaler};
 
     #[test]
     fn test_scalar_new() {
@@ -233,4 +474,97 @@ mod tests {
         }
         ");
     }
+
+    #[test]
+    fn test_scaler_options_flags() {
+        assert_eq!(ScalerOptions::default().flags(), SWS_BILINEAR);
+
+        let options = ScalerOptions::builder()
+            .algorithm(ScaleAlgorithm::Lanczos)
+            .full_chroma_interpolation(true)
+            .accurate_rounding(true)
+            .build();
+        assert_eq!(
+            options.flags(),
+            SWS_LANCZOS | SWS_FULL_CHR_H_INT | SWS_FULL_CHR_H_INP | SWS_ACCURATE_RND
+        );
+    }
+
+    #[test]
+    fn test_scaler_input_change() {
+        let options = ScalerOptions::builder()
+            .algorithm(ScaleAlgorithm::Spline)
+            .dither(ScaleDither::ErrorDiffusion)
+            .build();
+        let mut scaler =
+            VideoScaler::with_options(1280, 720, AVPixelFormat::Yuv420p, 640, 360, AVPixelFormat::Rgb24, options)
+                .expect("Failed to create scaler");
+        assert_eq!(scaler.options(), options);
+
+        // An adaptive source switching renditions, the scaler follows without being re-created.
+        for (width, height, pix_fmt) in [
+            (1280, 720, AVPixelFormat::Yuv420p),
+            (1920, 1080, AVPixelFormat::Yuv420p),
+            (854, 480, AVPixelFormat::Nv12),
+        ] {
+            let frame = VideoFrame::builder()
+                .width(width)
+                .height(height)
+                .pix_fmt(pix_fmt)
+                .build()
+                .expect("Failed to create frame");
+
+            let output = scaler.process(&frame).expect("Failed to scale frame");
+            assert_eq!((output.width(), output.height()), (640, 360));
+            assert_eq!(output.format(), AVPixelFormat::Rgb24);
+        }
+
+        scaler
+            .set_options(ScalerOptions::builder().algorithm(ScaleAlgorithm::Point).build())
+            .expect("Failed to change options");
+        assert_eq!(scaler.options().algorithm, ScaleAlgorithm::Point);
+    }
+
+    #[test]
+    fn test_scaler_process_into() {
+        let mut scaler = VideoScaler::new(1280, 720, AVPixelFormat::Yuv420p, 640, 360, AVPixelFormat::Rgb24)
+            .expect("Failed to create scaler");
+        let pool = FramePool::builder()
+            .width(640)
+            .height(360)
+            .pix_fmt(AVPixelFormat::Rgb24)
+            .build()
+            .expect("Failed to create pool");
+
+        let mut input = VideoFrame::builder()
+            .width(1280)
+            .height(720)
+            .pix_fmt(AVPixelFormat::Yuv420p)
+            .pts(42)
+            .build()
+            .expect("Failed to create frame");
+        for mut plane in input.planes_mut() {
+            plane.fill(0x80);
+        }
+
+        // Every output is its own frame, scaling the next one does not overwrite it.
+        let first = scaler.process_into(&input, &pool).expect("Failed to scale frame");
+        let second = scaler.process_into(&input, &pool).expect("Failed to scale frame");
+        assert_ne!(first.as_ptr(), second.as_ptr());
+        assert_eq!((first.width(), first.height()), (640, 360));
+        assert_eq!(first.format(), AVPixelFormat::Rgb24);
+        assert_eq!(first.pts(), Some(42));
+        assert_eq!(first.data(0).unwrap().get_row(0), second.data(0).unwrap().get_row(0));
+
+        let other = FramePool::builder()
+            .width(1280)
+            .height(720)
+            .pix_fmt(AVPixelFormat::Rgb24)
+            .build()
+            .expect("Failed to create pool");
+        assert_eq!(
+            scaler.process_into(&input, &other).unwrap_err(),
+            crate::error::FfmpegError::Arguments("frame pool does not match the scaler output")
+        );
+    }
 }
@@ -0,0 +1,388 @@
+use crate::ffi::*;
+use crate::rational::Rational;
+
+/// The `cc_data` of a single caption packet is made of 3 byte triplets.
+pub const A53_CC_TRIPLET_SIZE: usize = 3;
+
+/// The size of the UUID prefix of a user data unregistered SEI message.
+pub const SEI_UNREGISTERED_UUID_SIZE: usize = 16;
+
+/// A SMPTE 12-1 timecode.
+///
+/// Attach it to a frame with [`GenericFrame::set_s12m_timecode`](crate::frame::GenericFrame::set_s12m_timecode)
+/// to have encoders that support it (such as `h264_nvenc` with its `s12m_tc` option) emit a picture
+/// timing SEI message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SmpteTimecode {
+    /// Hours, 0 to 23.
+    pub hours: u8,
+    /// Minutes, 0 to 59.
+    pub minutes: u8,
+    /// Seconds, 0 to 59.
+    pub seconds: u8,
+    /// Frames, in the frame rate the timecode is encoded for.
+    pub frames: u8,
+    /// Whether the timecode uses drop frame counting (29.97 and 59.94 fps).
+    pub drop_frame: bool,
+}
+
+impl SmpteTimecode {
+    /// Creates a new non drop frame timecode.
+    pub const fn new(hours: u8, minutes: u8, seconds: u8, frames: u8) -> Self {
+        Self {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            drop_frame: false,
+        }
+    }
+
+    /// Converts the timecode to its SMPTE 12-1 binary representation.
+    ///
+    /// Frame rates above 30 fps store the frame pair number and use the field bit to
+    /// mark odd frames, the same as `av_timecode_get_smpte`.
+    pub fn to_s12m(self, rate: Rational) -> u32 {
+        let mut tc = 0;
+        let mut ff = self.frames as u32;
+
+        if rate_above_30(rate) {
+            if ff % 2 == 1 {
+                tc |= field_bit(rate);
+            }
+            ff /= 2;
+        }
+
+        let hh = self.hours as u32 % 24;
+        let mm = (self.minutes as u32).min(59);
+        let ss = (self.seconds as u32).min(59);
+        let ff = ff % 40;
+
+        tc |= (self.drop_frame as u32) << 30;
+        tc |= (ff / 10) << 28;
+        tc |= (ff % 10) << 24;
+        tc |= (ss / 10) << 20;
+        tc |= (ss % 10) << 16;
+        tc |= (mm / 10) << 12;
+        tc |= (mm % 10) << 8;
+        tc |= (hh / 10) << 4;
+        tc |= hh % 10;
+        tc
+    }
+
+    /// Parses a timecode from its SMPTE 12-1 binary representation.
+    pub fn from_s12m(tc: u32, rate: Rational) -> Self {
+        let mut frames = bcd_to_u8((tc >> 24) & 0x3f);
+
+        if rate_above_30(rate) {
+            frames = frames * 2 + ((tc & field_bit(rate)) != 0) as u8;
+        }
+
+        Self {
+            hours: bcd_to_u8(tc & 0x3f),
+            minutes: bcd_to_u8((tc >> 8) & 0x7f),
+            seconds: bcd_to_u8((tc >> 16) & 0x7f),
+            frames,
+            drop_frame: (tc & (1 << 30)) != 0,
+        }
+    }
+}
+
+impl std::fmt::Display for SmpteTimecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sep = if self.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{sep}{:02}",
+            self.hours, self.minutes, self.seconds, self.frames
+        )
+    }
+}
+
+/// Mastering display metadata (SMPTE ST 2086), the color volume of the display the content was graded on.
+///
+/// Together with [`ContentLightLevel`] this is the static metadata of HDR10. Decoders attach it
+/// to frames and encoders such as `libx265` write it into the bitstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MasteringDisplayMetadata {
+    /// The primaries and white point of the display, `None` if unknown.
+    pub primaries: Option<MasteringDisplayPrimaries>,
+    /// The luminance range of the display, `None` if unknown.
+    pub luminance: Option<MasteringDisplayLuminance>,
+}
+
+/// The CIE 1931 xy chromaticity coordinates of a mastering display, each as `[x, y]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasteringDisplayPrimaries {
+    /// The red primary.
+    pub red: [Rational; 2],
+    /// The green primary.
+    pub green: [Rational; 2],
+    /// The blue primary.
+    pub blue: [Rational; 2],
+    /// The white point.
+    pub white_point: [Rational; 2],
+}
+
+/// The luminance range of a mastering display, in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasteringDisplayLuminance {
+    /// The minimum luminance.
+    pub min: Rational,
+    /// The maximum luminance.
+    pub max: Rational,
+}
+
+impl From<AVMasteringDisplayMetadata> for MasteringDisplayMetadata {
+    fn from(raw: AVMasteringDisplayMetadata) -> Self {
+        let point = |point: [AVRational; 2]| point.map(Rational::from);
+
+        Self {
+            primaries: (raw.has_primaries != 0).then(|| MasteringDisplayPrimaries {
+                red: point(raw.display_primaries[0]),
+                green: point(raw.display_primaries[1]),
+                blue: point(raw.display_primaries[2]),
+                white_point: point(raw.white_point),
+            }),
+            luminance: (raw.has_luminance != 0).then(|| MasteringDisplayLuminance {
+                min: raw.min_luminance.into(),
+                max: raw.max_luminance.into(),
+            }),
+        }
+    }
+}
+
+impl From<MasteringDisplayMetadata> for AVMasteringDisplayMetadata {
+    fn from(metadata: MasteringDisplayMetadata) -> Self {
+        let point = |point: [Rational; 2]| point.map(AVRational::from);
+        let zero = AVRational { num: 0, den: 1 };
+
+        let primaries = metadata.primaries;
+        let luminance = metadata.luminance;
+
+        Self {
+            display_primaries: primaries.map_or([[zero; 2]; 3], |primaries| {
+                [point(primaries.red), point(primaries.green), point(primaries.blue)]
+            }),
+            white_point: primaries.map_or([zero; 2], |primaries| point(primaries.white_point)),
+            min_luminance: luminance.map_or(zero, |luminance| luminance.min.into()),
+            max_luminance: luminance.map_or(zero, |luminance| luminance.max.into()),
+            has_primaries: primaries.is_some() as _,
+            has_luminance: luminance.is_some() as _,
+        }
+    }
+}
+
+/// Content light level metadata (CTA-861.3), the brightest pixel and frame of the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContentLightLevel {
+    /// The maximum content light level (MaxCLL), in cd/m².
+    pub max_cll: u32,
+    /// The maximum frame average light level (MaxFALL), in cd/m².
+    pub max_fall: u32,
+}
+
+impl From<AVContentLightMetadata> for ContentLightLevel {
+    fn from(raw: AVContentLightMetadata) -> Self {
+        Self {
+            max_cll: raw.MaxCLL as u32,
+            max_fall: raw.MaxFALL as u32,
+        }
+    }
+}
+
+impl From<ContentLightLevel> for AVContentLightMetadata {
+    fn from(level: ContentLightLevel) -> Self {
+        Self {
+            MaxCLL: level.max_cll as _,
+            MaxFALL: level.max_fall as _,
+        }
+    }
+}
+
+/// A 3x3 transformation matrix that maps decoded pixels to their display position.
+///
+/// Usually only describes a rotation, such as the one phone cameras store.
+/// See `libavutil/display.h` for the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMatrix(pub [i32; 9]);
+
+impl DisplayMatrix {
+    /// Creates a matrix that rotates by `angle` degrees counter-clockwise.
+    pub fn from_rotation(angle: f64) -> Self {
+        let mut matrix = [0; 9];
+        // Safety: `av_display_rotation_set` writes 9 values to the matrix.
+        unsafe { av_display_rotation_set(matrix.as_mut_ptr(), angle) };
+        Self(matrix)
+    }
+
+    /// Returns the counter-clockwise rotation of the matrix in degrees, in the range \[-180, 180\].
+    ///
+    /// Returns `None` if the matrix is singular.
+    pub fn rotation(&self) -> Option<f64> {
+        // Safety: `av_display_rotation_get` reads 9 values from the matrix.
+        let angle = unsafe { av_display_rotation_get(self.0.as_ptr()) };
+        (!angle.is_nan()).then_some(angle)
+    }
+}
+
+/// Reads a `T` from side data, returns `None` if the side data is too small to hold one.
+pub(crate) fn read_side_data<T: Copy>(data: &[u8]) -> Option<T> {
+    if data.len() < std::mem::size_of::<T>() {
+        return None;
+    }
+
+    // Safety: `data` holds at least `size_of::<T>()` bytes, the side data types read with this are
+    // plain C structs without padding, `read_unaligned` does not require the data to be aligned.
+    Some(unsafe { std::ptr::read_unaligned(data.as_ptr().cast::<T>()) })
+}
+
+/// Writes `value` into side data allocated for it.
+pub(crate) fn write_side_data<T: Copy>(data: &mut [u8], value: T) {
+    assert!(data.len() >= std::mem::size_of::<T>(), "side data is too small");
+
+    // Safety: `data` holds at least `size_of::<T>()` bytes, `write_unaligned` does not require the data to be aligned.
+    unsafe { std::ptr::write_unaligned(data.as_mut_ptr().cast::<T>(), value) };
+}
+
+fn rate_above_30(rate: Rational) -> bool {
+    rate.numerator as i64 > 30 * rate.denominator.get() as i64
+}
+
+fn field_bit(rate: Rational) -> u32 {
+    // 50 fps uses the binary group flag bit 7, every other rate uses bit 23.
+    if rate.numerator as i64 == 50 * rate.denominator.get() as i64 {
+        1 << 7
+    } else {
+        1 << 23
+    }
+}
+
+fn bcd_to_u8(bcd: u32) -> u8 {
+    let low = bcd & 0xf;
+    let high = bcd >> 4;
+    if low > 9 || high > 9 {
+        return 0;
+    }
+
+    (high * 10 + low) as u8
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::ffi::*;
+    use crate::rational::Rational;
+    use crate::side_data::{
+        ContentLightLevel, DisplayMatrix, MasteringDisplayLuminance, MasteringDisplayMetadata, MasteringDisplayPrimaries,
+        SmpteTimecode, read_side_data, write_side_data,
+    };
+
+    #[test]
+    fn test_s12m_round_trip() {
+        let rate = Rational::static_new::<25, 1>();
+        let timecode = SmpteTimecode::new(10, 20, 30, 24);
+        let tc = timecode.to_s12m(rate);
+        assert_eq!(tc, 0x2430_2010);
+        assert_eq!(SmpteTimecode::from_s12m(tc, rate), timecode);
+        assert_eq!(timecode.to_string(), "10:20:30:24");
+    }
+
+    #[test]
+    fn test_s12m_drop_frame() {
+        let rate = Rational::static_new::<30000, 1001>();
+        let timecode = SmpteTimecode {
+            drop_frame: true,
+            ..SmpteTimecode::new(1, 0, 0, 2)
+        };
+        let tc = timecode.to_s12m(rate);
+        assert_eq!(tc, 0x4200_0001);
+        assert_eq!(SmpteTimecode::from_s12m(tc, rate), timecode);
+        assert_eq!(timecode.to_string(), "01:00:00;02");
+    }
+
+    #[test]
+    fn test_s12m_high_frame_rate() {
+        let rate = Rational::static_new::<50, 1>();
+        let timecode = SmpteTimecode::new(0, 0, 1, 49);
+        let tc = timecode.to_s12m(rate);
+        // frame pair 24, field bit 7 set.
+        assert_eq!(tc, 0x2401_0080);
+        assert_eq!(SmpteTimecode::from_s12m(tc, rate), timecode);
+
+        let rate = Rational::static_new::<60, 1>();
+        let timecode = SmpteTimecode::new(0, 0, 1, 59);
+        let tc = timecode.to_s12m(rate);
+        // frame pair 29, field bit 23 set.
+        assert_eq!(tc, 0x2981_0000);
+        assert_eq!(SmpteTimecode::from_s12m(tc, rate), timecode);
+    }
+
+    #[test]
+    fn test_s12m_clamps() {
+        let rate = Rational::static_new::<25, 1>();
+        let timecode = SmpteTimecode::new(25, 75, 61, 3);
+        assert_eq!(
+            SmpteTimecode::from_s12m(timecode.to_s12m(rate), rate),
+            SmpteTimecode::new(1, 59, 59, 3)
+        );
+
+        // Invalid BCD digits are treated as 0.
+        assert_eq!(SmpteTimecode::from_s12m(0x0000_000f, rate), SmpteTimecode::default());
+    }
+
+    #[test]
+    fn test_mastering_display_metadata_round_trip() {
+        let point = |x: i32, y: i32| {
+            [
+                Rational::new(x, 50000.try_into().unwrap()),
+                Rational::new(y, 50000.try_into().unwrap()),
+            ]
+        };
+        let metadata = MasteringDisplayMetadata {
+            primaries: Some(MasteringDisplayPrimaries {
+                red: point(34000, 16000),
+                green: point(13250, 34500),
+                blue: point(7500, 3000),
+                white_point: point(15635, 16450),
+            }),
+            luminance: Some(MasteringDisplayLuminance {
+                min: Rational::new(50, 10000.try_into().unwrap()),
+                max: Rational::new(10_000_000, 10000.try_into().unwrap()),
+            }),
+        };
+
+        let raw = AVMasteringDisplayMetadata::from(metadata);
+        assert_eq!(raw.has_primaries, 1);
+        assert_eq!(raw.has_luminance, 1);
+        assert_eq!(MasteringDisplayMetadata::from(raw), metadata);
+
+        let mut data = vec![0; std::mem::size_of::<AVMasteringDisplayMetadata>()];
+        write_side_data(&mut data, raw);
+        let read: AVMasteringDisplayMetadata = read_side_data(&data).expect("side data is large enough");
+        assert_eq!(MasteringDisplayMetadata::from(read), metadata);
+        assert!(read_side_data::<AVMasteringDisplayMetadata>(&data[1..]).is_none());
+
+        let unknown = MasteringDisplayMetadata::default();
+        assert_eq!(
+            MasteringDisplayMetadata::from(AVMasteringDisplayMetadata::from(unknown)),
+            unknown
+        );
+    }
+
+    #[test]
+    fn test_content_light_level_round_trip() {
+        let level = ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        };
+        assert_eq!(ContentLightLevel::from(AVContentLightMetadata::from(level)), level);
+    }
+
+    #[test]
+    fn test_display_matrix_rotation() {
+        let matrix = DisplayMatrix::from_rotation(90.0);
+        assert_eq!(matrix.rotation(), Some(90.0));
+        assert_eq!(DisplayMatrix([0; 9]).rotation(), None);
+    }
+}
@@ -3,9 +3,11 @@ use std::marker::PhantomData;
 use crate::consts::{Const, Mut};
 use crate::dict::Dictionary;
 use crate::ffi::*;
+use crate::program::Program;
 use crate::rational::Rational;
+use crate::side_data::{DisplayMatrix, read_side_data};
 use crate::utils::check_i64;
-use crate::{AVDiscard, AVMediaType};
+use crate::{AVDiscard, AVMediaType, AVPacketSideDataType};
 
 /// A collection of streams. Streams implements [`IntoIterator`] to iterate over the streams.
 pub struct Streams<'a> {
@@ -56,6 +58,32 @@ impl<'a> Streams<'a> {
         Some(stream as usize)
     }
 
+    /// Returns the index of the best stream of the given media type in a program.
+    ///
+    /// Prefers the stream with the default disposition, otherwise the first
+    /// stream of the media type in the program.
+    pub fn best_index_in_program(&self, media_type: AVMediaType, program: &Program) -> Option<usize> {
+        let mut best = None;
+        for &index in &program.stream_indices {
+            // Safety: The stream is only read and not returned.
+            let Some(stream) = (unsafe { self.get_unchecked(index) }) else {
+                continue;
+            };
+
+            if stream.codec_parameters().map(|params| AVMediaType(params.codec_type)) != Some(media_type) {
+                continue;
+            }
+
+            if stream.disposition() & AV_DISPOSITION_DEFAULT as i32 != 0 {
+                return Some(index);
+            }
+
+            best.get_or_insert(index);
+        }
+
+        best
+    }
+
     /// Returns the best stream of the given media type.
     pub fn best(&'a self, media_type: AVMediaType) -> Option<Const<'a, Stream<'a>>> {
         let stream = self.best_index(media_type)?;
@@ -198,6 +226,12 @@ impl<'a> Stream<'a> {
         unsafe { self.0.codecpar.as_ref() }
     }
 
+    /// Returns a mutable reference to the codec parameters of the stream.
+    pub const fn codec_parameters_mut(&mut self) -> Option<&mut AVCodecParameters> {
+        // Safety: the pointer is valid
+        unsafe { self.0.codecpar.as_mut() }
+    }
+
     /// Returns the time base of the stream.
     pub fn time_base(&self) -> Rational {
         self.0.time_base.into()
@@ -282,12 +316,26 @@ impl<'a> Stream<'a> {
     }
 
     /// Returns a mutable reference to the metadata of the stream.
+    ///
+    /// This edits the existing metadata in place. A stream without metadata, such as
+    /// a newly added output stream, has nowhere to store new entries, use
+    /// [`Stream::set_metadata`] for those.
     pub const fn metadata_mut(&mut self) -> Mut<'_, Dictionary> {
         // Safety: the pointer metadata pointer does not live longer than this object,
         // see `Mut::new`
         Mut::new(unsafe { Dictionary::from_ptr_ref(self.0.metadata) })
     }
 
+    /// Replaces the metadata of the stream, such as its title and language.
+    ///
+    /// For output streams this must be done before the header is written.
+    pub fn set_metadata(&mut self, metadata: Dictionary) {
+        // Safety: The stream owns its metadata, taking ownership of it frees the previous metadata.
+        drop(unsafe { Dictionary::from_ptr_owned(self.0.metadata) });
+
+        self.0.metadata = metadata.leak();
+    }
+
     /// Returns the average frame rate of the stream.
     pub fn avg_frame_rate(&self) -> Rational {
         self.0.avg_frame_rate.into()
@@ -298,6 +346,28 @@ impl<'a> Stream<'a> {
         self.0.r_frame_rate.into()
     }
 
+    /// Returns the display matrix of the stream, which usually holds the rotation of the video.
+    pub fn display_matrix(&self) -> Option<DisplayMatrix> {
+        let params = self.codec_parameters()?;
+        // Safety: `coded_side_data` holds `nb_coded_side_data` entries owned by the codec parameters.
+        let side_data = unsafe {
+            av_packet_side_data_get(
+                params.coded_side_data,
+                params.nb_coded_side_data,
+                AVPacketSideDataType::DisplayMatrix.0 as _,
+            )
+        };
+        // Safety: the side data is either null or valid for as long as the stream.
+        let side_data = unsafe { side_data.as_ref() }?;
+        if side_data.data.is_null() {
+            return None;
+        }
+
+        // Safety: `data` is valid for `size` bytes and lives as long as the stream.
+        let data = unsafe { std::slice::from_raw_parts(side_data.data, side_data.size) };
+        read_side_data::<[i32; 9]>(data).map(DisplayMatrix)
+    }
+
     /// Returns the format context of the stream.
     ///
     /// # Safety
@@ -572,6 +642,16 @@ mod tests {
         assert!(real_frame_rate.as_f64() > 0.0, "Expected non-zero r_frame_rate numerator");
     }
 
+    #[test]
+    fn test_stream_display_matrix() {
+        let valid_file_path = "../../assets/avc_aac_large.mp4";
+        let mut input = Input::open(valid_file_path).expect("Failed to open valid file");
+        let mut streams = input.streams_mut();
+        let stream = streams.get(0).expect("Expected a valid stream");
+
+        assert_eq!(stream.display_matrix(), None, "Expected the stream to not be rotated");
+    }
+
     #[test]
     fn test_stream_format_context() {
         let valid_file_path = "../../assets/avc_aac_large.mp4";
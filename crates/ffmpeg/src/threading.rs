@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use nutype_enum::{bitwise_enum, nutype_enum};
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// The kinds of threading a codec may use, the `FF_THREAD_*` flags of `AVCodecContext::thread_type`.
+    ///
+    /// The flags can be combined, in which case the codec picks the one it supports,
+    /// preferring frame threading.
+    pub enum ThreadType(i32) {
+        /// Decode or encode more than one frame at once.
+        /// - **Cost**: Adds a delay of one frame per thread.
+        /// - **Equivalent to**: `FF_THREAD_FRAME`
+        Frame = FF_THREAD_FRAME as _,
+
+        /// Decode or encode more than one part of a single frame at once.
+        /// - **Cost**: Only helps if the stream was encoded with multiple slices.
+        /// - **Equivalent to**: `FF_THREAD_SLICE`
+        Slice = FF_THREAD_SLICE as _,
+    }
+}
+
+bitwise_enum!(ThreadType);
+
+impl ThreadType {
+    /// No threading is used.
+    pub const NONE: Self = Self(0);
+
+    /// Returns true if all flags of `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// Controls how a codec context is allowed to use threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Threading {
+    /// Use the thread settings of the decoder / encoder as configured.
+    #[default]
+    Auto = 0,
+    /// Force single threaded operation.
+    ///
+    /// This sets `thread_count` to 1 and disables both frame and slice threading,
+    /// which makes the output deterministic. This is useful for golden tests and
+    /// when running many small jobs in parallel, where ffmpeg's thread pools would
+    /// oversubscribe the host.
+    SingleThreaded = 1,
+}
+
+static DEFAULT_THREADING: AtomicU8 = AtomicU8::new(Threading::Auto as u8);
+
+impl Threading {
+    /// Sets the global default used by decoders and encoders that do not specify
+    /// their own [`Threading`].
+    pub fn set_default(threading: Threading) {
+        DEFAULT_THREADING.store(threading as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the global default set by [`Threading::set_default`].
+    pub fn default_global() -> Threading {
+        match DEFAULT_THREADING.load(Ordering::Relaxed) {
+            1 => Threading::SingleThreaded,
+            _ => Threading::Auto,
+        }
+    }
+
+    /// Returns `threading`, or the global default if it is `None`.
+    pub(crate) fn resolve(threading: Option<Threading>) -> Threading {
+        threading.unwrap_or_else(Self::default_global)
+    }
+
+    /// Applies the threading mode to a codec context. Must be called before the codec is opened.
+    pub(crate) const fn apply(self, context: &mut AVCodecContext) {
+        match self {
+            Threading::Auto => {}
+            Threading::SingleThreaded => {
+                context.thread_count = 1;
+                context.thread_type = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::ffi::*;
+    use crate::threading::{ThreadType, Threading};
+
+    #[test]
+    fn test_threading_apply() {
+        // Safety: We are zeroing the memory for the codec context.
+        let mut context = unsafe { std::mem::zeroed::<AVCodecContext>() };
+        context.thread_count = 8;
+        context.thread_type = (FF_THREAD_FRAME | FF_THREAD_SLICE) as i32;
+
+        Threading::Auto.apply(&mut context);
+        assert_eq!(context.thread_count, 8);
+        assert_eq!(context.thread_type, (FF_THREAD_FRAME | FF_THREAD_SLICE) as i32);
+
+        Threading::SingleThreaded.apply(&mut context);
+        assert_eq!(context.thread_count, 1);
+        assert_eq!(context.thread_type, 0);
+    }
+
+    #[test]
+    fn test_threading_resolve() {
+        assert_eq!(Threading::resolve(Some(Threading::SingleThreaded)), Threading::SingleThreaded);
+        assert_eq!(Threading::resolve(Some(Threading::Auto)), Threading::Auto);
+        // The global default is not changed by any test, as that would affect tests running in parallel.
+        assert_eq!(Threading::resolve(None), Threading::Auto);
+    }
+
+    #[test]
+    fn test_thread_type_flags() {
+        let both = ThreadType::Frame | ThreadType::Slice;
+        assert_eq!(both.0, (FF_THREAD_FRAME | FF_THREAD_SLICE) as i32);
+        assert!(both.contains(ThreadType::Frame));
+        assert!(both.contains(ThreadType::Slice));
+        assert!(!ThreadType::Slice.contains(ThreadType::Frame));
+        assert!(ThreadType::Frame.contains(ThreadType::NONE));
+    }
+}
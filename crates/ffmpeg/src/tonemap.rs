@@ -0,0 +1,385 @@
+use std::ffi::CStr;
+
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::filter_graph::{Filter, FilterGraph};
+use crate::frame::VideoFrame;
+use crate::rational::Rational;
+use crate::{AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVPixelFormat};
+
+const SOURCE_NAME: &str = "in";
+const SINK_NAME: &str = "out";
+
+/// The curve used to map HDR luminance into the SDR range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapAlgorithm {
+    /// John Hable's filmic curve, preserves detail in both dark and bright areas.
+    #[default]
+    Hable,
+    /// Mobius curve, keeps in-range colors accurate and only compresses the highlights.
+    Mobius,
+    /// Simple Reinhard curve.
+    Reinhard,
+    /// Hard clips out of range values.
+    Clip,
+    /// The ITU-R BT.2390 EETF.
+    ///
+    /// This curve is only implemented by the `libplacebo` filter, which requires
+    /// FFmpeg to be built with libplacebo and a Vulkan device at runtime.
+    Bt2390,
+}
+
+impl ToneMapAlgorithm {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Hable => "hable",
+            Self::Mobius => "mobius",
+            Self::Reinhard => "reinhard",
+            Self::Clip => "clip",
+            Self::Bt2390 => "bt.2390",
+        }
+    }
+}
+
+/// The color metadata of a video frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorMetadata {
+    /// The color primaries.
+    pub primaries: AVColorPrimaries,
+    /// The transfer characteristic.
+    pub transfer: AVColorTransferCharacteristic,
+    /// The color space (matrix coefficients).
+    pub space: AVColorSpace,
+    /// The range of the sample values.
+    pub range: AVColorRange,
+}
+
+impl ColorMetadata {
+    /// The metadata of HDR10 video: BT.2020 primaries and matrix, PQ transfer and limited range.
+    pub const HDR10: Self = Self {
+        primaries: AVColorPrimaries::Bt2020,
+        transfer: AVColorTransferCharacteristic::Smpte2084,
+        space: AVColorSpace::Bt2020Ncl,
+        range: AVColorRange::Mpeg,
+    };
+
+    /// Reads the color metadata of a frame.
+    pub const fn from_frame(frame: &VideoFrame) -> Self {
+        Self {
+            primaries: frame.color_primaries(),
+            transfer: frame.color_trc(),
+            space: frame.colorspace(),
+            range: frame.color_range(),
+        }
+    }
+
+    /// Returns true if the transfer characteristic is PQ or HLG.
+    pub fn is_hdr(&self) -> bool {
+        self.transfer == AVColorTransferCharacteristic::Smpte2084
+            || self.transfer == AVColorTransferCharacteristic::AribStdB67
+    }
+
+    /// Returns the `setparams` filter tagging frames with this metadata.
+    ///
+    /// Values the filters do not understand or that are unspecified are replaced by
+    /// the [`ColorMetadata::HDR10`] ones, since untagged HDR sources are almost always HDR10.
+    fn setparams(&self) -> String {
+        let primaries = match self.primaries {
+            AVColorPrimaries::Bt709 => "bt709",
+            AVColorPrimaries::Smpte431 => "smpte431",
+            AVColorPrimaries::Smpte432 => "smpte432",
+            _ => "bt2020",
+        };
+        let transfer = match self.transfer {
+            AVColorTransferCharacteristic::AribStdB67 => "arib-std-b67",
+            _ => "smpte2084",
+        };
+        let space = match self.space {
+            AVColorSpace::Bt709 => "bt709",
+            AVColorSpace::Bt2020Cl => "bt2020c",
+            _ => "bt2020nc",
+        };
+        let range = match self.range {
+            AVColorRange::Jpeg => "pc",
+            _ => "tv",
+        };
+
+        format!("setparams=color_primaries={primaries}:color_trc={transfer}:colorspace={space}:range={range}")
+    }
+}
+
+/// Options for converting HDR video to SDR.
+#[derive(Debug, Clone, bon::Builder)]
+pub struct ToneMapOptions {
+    /// The tone mapping curve.
+    #[builder(default)]
+    pub algorithm: ToneMapAlgorithm,
+    /// The luminance in nits that maps to SDR white, used when linearizing the input.
+    ///
+    /// Only used by the zscale based algorithms.
+    #[builder(default = 100.0)]
+    pub nominal_peak: f64,
+    /// How strongly to desaturate highlights, `0` disables it.
+    ///
+    /// Only used by the zscale based algorithms, FFmpeg's default is used if not set.
+    pub desaturation: Option<f64>,
+    /// The signal peak to map from, relative to the nominal peak.
+    ///
+    /// Only used by the zscale based algorithms, it is detected from the frame side data if not set.
+    pub peak: Option<f64>,
+    /// The pixel format of the output frames.
+    #[builder(default = AVPixelFormat::Yuv420p)]
+    pub pixel_format: AVPixelFormat,
+}
+
+impl Default for ToneMapOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl ToneMapOptions {
+    /// Returns the filter chain converting frames with the `input` color metadata to BT.709 SDR.
+    ///
+    /// The frames are tagged with `input` first, see [`ColorMetadata`] for how missing values are handled.
+    /// [`ToneMapAlgorithm::Bt2390`] uses the `libplacebo` filter, every other algorithm
+    /// linearizes the input with `zscale` and maps it with the `tonemap` filter.
+    pub fn filter_spec(&self, input: &ColorMetadata) -> Result<String, FfmpegError> {
+        let pixel_format = pixel_format_name(self.pixel_format).ok_or(FfmpegError::Arguments("invalid pixel format"))?;
+
+        let mut chain = vec![input.setparams()];
+        if self.algorithm == ToneMapAlgorithm::Bt2390 {
+            chain.push(format!(
+                "libplacebo=tonemapping={}:colorspace=bt709:color_primaries=bt709:color_trc=bt709:range=tv:format={pixel_format}",
+                self.algorithm.name(),
+            ));
+            return Ok(chain.join(","));
+        }
+
+        let mut tonemap = format!("tonemap=tonemap={}", self.algorithm.name());
+        if let Some(desaturation) = self.desaturation {
+            tonemap.push_str(&format!(":desat={desaturation}"));
+        }
+        if let Some(peak) = self.peak {
+            tonemap.push_str(&format!(":peak={peak}"));
+        }
+
+        chain.extend([
+            format!("zscale=t=linear:npl={}", self.nominal_peak),
+            "format=gbrpf32le".to_string(),
+            "zscale=p=bt709".to_string(),
+            tonemap,
+            "zscale=t=bt709:m=bt709:r=tv".to_string(),
+            format!("format={pixel_format}"),
+        ]);
+
+        Ok(chain.join(","))
+    }
+}
+
+/// Converts HDR video frames to BT.709 SDR.
+///
+/// The filter graph is configured from the frame passed to [`ToneMapper::new`], all frames sent to it must
+/// have the same size, pixel format and color metadata.
+pub struct ToneMapper {
+    graph: FilterGraph,
+}
+
+impl std::fmt::Debug for ToneMapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToneMapper").finish_non_exhaustive()
+    }
+}
+
+impl ToneMapper {
+    /// Creates a tone mapper for frames like `frame`, with timestamps in `time_base`.
+    pub fn new(frame: &VideoFrame, time_base: impl Into<Rational>, options: &ToneMapOptions) -> Result<Self, FfmpegError> {
+        let time_base = time_base.into();
+        let sample_aspect_ratio = Some(frame.sample_aspect_ratio())
+            .filter(|sar| sar.numerator > 0)
+            .unwrap_or(Rational::ONE);
+        let source_args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            frame.width(),
+            frame.height(),
+            frame.format().0,
+            time_base.numerator,
+            time_base.denominator,
+            sample_aspect_ratio.numerator,
+            sample_aspect_ratio.denominator,
+        );
+        let spec = options.filter_spec(&ColorMetadata::from_frame(frame))?;
+
+        let mut graph = FilterGraph::new()?;
+        for (filter, name, args) in [("buffer", SOURCE_NAME, source_args.as_str()), ("buffersink", SINK_NAME, "")] {
+            graph.add(Filter::get(filter).ok_or(FfmpegError::NoFilter)?, name, args)?;
+        }
+
+        graph.input(SINK_NAME, 0)?.output(SOURCE_NAME, 0)?.parse(&spec)?;
+        graph.validate()?;
+
+        Ok(Self { graph })
+    }
+
+    /// Sends a frame to the tone mapper.
+    pub fn send_frame(&mut self, frame: &VideoFrame) -> Result<(), FfmpegError> {
+        self.graph
+            .get(SOURCE_NAME)
+            .expect("source was added")
+            .source()
+            .send_frame(frame)
+    }
+
+    /// Signals the end of the input, so the remaining frames can be received.
+    pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
+        self.graph.get(SOURCE_NAME).expect("source was added").source().send_eof(None)
+    }
+
+    /// Receives a tone mapped frame, `None` if more input is needed.
+    pub fn receive_frame(&mut self) -> Result<Option<VideoFrame>, FfmpegError> {
+        let frame = self.graph.get(SINK_NAME).expect("sink was added").sink().receive_frame()?;
+        Ok(frame.map(|frame| frame.video()))
+    }
+}
+
+fn pixel_format_name(pixel_format: AVPixelFormat) -> Option<&'static str> {
+    // Safety: `av_get_pix_fmt_name` is safe to call with any pixel format.
+    let name = unsafe { av_get_pix_fmt_name(pixel_format.0) };
+    if name.is_null() {
+        return None;
+    }
+
+    // Safety: the name is a valid static c string.
+    unsafe { CStr::from_ptr(name) }.to_str().ok()
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::{ColorMetadata, ToneMapAlgorithm, ToneMapOptions, ToneMapper};
+    use crate::filter_graph::Filter;
+    use crate::frame::VideoFrame;
+    use crate::rational::Rational;
+    use crate::{AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVPixelFormat};
+
+    const UNSPECIFIED: ColorMetadata = ColorMetadata {
+        primaries: AVColorPrimaries::Unspecified,
+        transfer: AVColorTransferCharacteristic::Unspecified,
+        space: AVColorSpace::Unspecified,
+        range: AVColorRange::Unspecified,
+    };
+
+    #[test]
+    fn test_is_hdr() {
+        assert!(ColorMetadata::HDR10.is_hdr());
+        assert!(
+            ColorMetadata {
+                transfer: AVColorTransferCharacteristic::AribStdB67,
+                ..ColorMetadata::HDR10
+            }
+            .is_hdr()
+        );
+        assert!(!UNSPECIFIED.is_hdr());
+        assert!(
+            !ColorMetadata {
+                primaries: AVColorPrimaries::Bt709,
+                transfer: AVColorTransferCharacteristic::Bt709,
+                space: AVColorSpace::Bt709,
+                range: AVColorRange::Mpeg,
+            }
+            .is_hdr()
+        );
+    }
+
+    #[test]
+    fn test_filter_spec() {
+        let options = ToneMapOptions::builder().desaturation(0.0).build();
+        assert_eq!(
+            options.filter_spec(&ColorMetadata::HDR10).unwrap(),
+            "setparams=color_primaries=bt2020:color_trc=smpte2084:colorspace=bt2020nc:range=tv,\
+             zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap=tonemap=hable:desat=0,\
+             zscale=t=bt709:m=bt709:r=tv,format=yuv420p"
+        );
+
+        // Unspecified metadata is treated as HDR10.
+        assert_eq!(
+            options.filter_spec(&UNSPECIFIED).unwrap(),
+            options.filter_spec(&ColorMetadata::HDR10).unwrap()
+        );
+
+        let options = ToneMapOptions::builder()
+            .algorithm(ToneMapAlgorithm::Mobius)
+            .nominal_peak(203.0)
+            .peak(10.0)
+            .pixel_format(AVPixelFormat::Yuv444p)
+            .build();
+        let hlg = ColorMetadata {
+            transfer: AVColorTransferCharacteristic::AribStdB67,
+            range: AVColorRange::Jpeg,
+            ..ColorMetadata::HDR10
+        };
+        assert_eq!(
+            options.filter_spec(&hlg).unwrap(),
+            "setparams=color_primaries=bt2020:color_trc=arib-std-b67:colorspace=bt2020nc:range=pc,\
+             zscale=t=linear:npl=203,format=gbrpf32le,zscale=p=bt709,tonemap=tonemap=mobius:peak=10,\
+             zscale=t=bt709:m=bt709:r=tv,format=yuv444p"
+        );
+    }
+
+    #[test]
+    fn test_filter_spec_bt2390() {
+        let options = ToneMapOptions::builder().algorithm(ToneMapAlgorithm::Bt2390).build();
+        assert_eq!(
+            options.filter_spec(&ColorMetadata::HDR10).unwrap(),
+            "setparams=color_primaries=bt2020:color_trc=smpte2084:colorspace=bt2020nc:range=tv,\
+             libplacebo=tonemapping=bt.2390:colorspace=bt709:color_primaries=bt709:color_trc=bt709:range=tv:format=yuv420p"
+        );
+    }
+
+    #[test]
+    fn test_filter_spec_invalid_pixel_format() {
+        let options = ToneMapOptions::builder().pixel_format(AVPixelFormat::None).build();
+        assert!(options.filter_spec(&ColorMetadata::HDR10).is_err());
+    }
+
+    #[test]
+    fn test_tone_mapper() {
+        if Filter::get("zscale").is_none() {
+            // FFmpeg was built without zimg.
+            return;
+        }
+
+        let mut frame = VideoFrame::builder()
+            .width(64)
+            .height(32)
+            .pix_fmt(AVPixelFormat::Yuv420p16Le)
+            .time_base(Rational::static_new::<1, 30>())
+            .pts(0)
+            .build()
+            .expect("failed to create frame");
+        for mut plane in frame.planes_mut() {
+            plane.fill(0x40);
+        }
+        frame.set_color_primaries(AVColorPrimaries::Bt2020);
+        frame.set_color_trc(AVColorTransferCharacteristic::Smpte2084);
+        frame.set_colorspace(AVColorSpace::Bt2020Ncl);
+        frame.set_color_range(AVColorRange::Mpeg);
+
+        let mut tone_mapper = ToneMapper::new(&frame, Rational::static_new::<1, 30>(), &ToneMapOptions::default())
+            .expect("failed to create tone mapper");
+        tone_mapper.send_frame(&frame).expect("failed to send frame");
+        tone_mapper.send_eof().expect("failed to send eof");
+
+        let output = tone_mapper
+            .receive_frame()
+            .expect("failed to receive frame")
+            .expect("no frame");
+        assert_eq!(output.width(), 64);
+        assert_eq!(output.height(), 32);
+        assert_eq!(output.format(), AVPixelFormat::Yuv420p);
+        assert_eq!(output.color_primaries(), AVColorPrimaries::Bt709);
+        assert_eq!(output.color_trc(), AVColorTransferCharacteristic::Bt709);
+        assert_eq!(output.colorspace(), AVColorSpace::Bt709);
+        assert!(tone_mapper.receive_frame().expect("failed to receive frame").is_none());
+    }
+}
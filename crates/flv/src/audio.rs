@@ -1,4 +1,4 @@
-use std::io;
+use std::io::{self, Read};
 
 use byteorder::ReadBytesExt;
 use bytes::Bytes;
@@ -6,6 +6,7 @@ use nutype_enum::nutype_enum;
 use scuffle_bytes_util::BytesCursorExt;
 
 use super::aac::{AacPacket, AacPacketType};
+use super::enhanced::{AvMultitrackType, demux_tracks, skip_mod_ex};
 
 /// FLV Tag Audio Data
 ///
@@ -14,6 +15,10 @@ use super::aac::{AacPacket, AacPacketType};
 /// Defined by:
 /// - video_file_format_spec_v10.pdf (Chapter 1 - The FLV File Format - Audio tags)
 /// - video_file_format_spec_v10_1.pdf (Annex E.4.2.1 - AUDIODATA)
+/// - enhanced_rtmp-v2.pdf (Enhanced Audio)
+///
+/// For enhanced audio ([`SoundFormat::ExHeader`]) the bits of the sound rate, size and type
+/// carry the packet type instead, and have no meaning.
 #[derive(Debug, Clone, PartialEq)]
 pub struct AudioData {
     /// The sound rate of the audio data. (2 bits)
@@ -39,7 +44,12 @@ impl AudioData {
         let sound_type = SoundType::from(byte & 0b1);
 
         // Now we can demux the body of the audio data
-        let body = AudioDataBody::demux(sound_format, reader)?;
+        let body = if sound_format == SoundFormat::ExHeader {
+            // The enhanced packet type takes the place of the rate, size and type bits.
+            AudioDataBody::Enhanced(EnhancedAudioPacket::demux(AudioPacketType::from(byte & 0b0000_1111), reader)?)
+        } else {
+            AudioDataBody::demux(sound_format, reader)?
+        };
 
         Ok(AudioData {
             sound_rate,
@@ -77,6 +87,8 @@ nutype_enum! {
         G711ALaw = 7,
         /// G.711 Mu-Law logarithmic PCM
         G711MuLaw = 8,
+        /// Enhanced audio, the codec is given by an [`AudioFourCC`]
+        ExHeader = 9,
         /// AAC
         Aac = 10,
         /// Speex
@@ -99,6 +111,9 @@ nutype_enum! {
 pub enum AudioDataBody {
     /// AAC Audio Packet
     Aac(AacPacket),
+    /// Enhanced Audio Packet (Opus, FLAC, AC-3, etc.)
+    /// When [`SoundFormat::ExHeader`] is used
+    Enhanced(EnhancedAudioPacket),
     /// Some other audio format we don't know how to parse
     Unknown { sound_format: SoundFormat, data: Bytes },
 }
@@ -123,6 +138,140 @@ impl AudioDataBody {
     }
 }
 
+nutype_enum! {
+    /// Audio Packet Type
+    ///
+    /// The type of packet in enhanced audio.
+    ///
+    /// Defined by:
+    /// - enhanced_rtmp-v2.pdf (Enhanced Audio)
+    pub enum AudioPacketType(u8) {
+        /// Sequence Start
+        SequenceStart = 0,
+        /// Coded Frames
+        CodedFrames = 1,
+        /// Sequence End
+        SequenceEnd = 2,
+        /// Multichannel Config
+        MultichannelConfig = 4,
+        /// Multitrack, the packet contains several tracks
+        Multitrack = 5,
+        /// Packet modifier extensions precede the actual packet type
+        ModEx = 7,
+    }
+}
+
+nutype_enum! {
+    /// FLV Audio FourCC
+    ///
+    /// Denotes the different types of audio codecs that can be used in enhanced audio.
+    ///
+    /// Defined by:
+    /// - enhanced_rtmp-v2.pdf (Enhanced Audio)
+    pub enum AudioFourCC([u8; 4]) {
+        /// AC-3
+        Ac3 = *b"ac-3",
+        /// E-AC-3
+        Eac3 = *b"ec-3",
+        /// Opus
+        Opus = *b"Opus",
+        /// MP3
+        Mp3 = *b".mp3",
+        /// FLAC
+        Flac = *b"fLaC",
+        /// AAC
+        Aac = *b"mp4a",
+    }
+}
+
+/// An Enhanced Audio Packet
+///
+/// The enhanced spec adds modern codecs and multitrack audio to the FLV file format.
+/// The payloads are kept as they are, their format depends on the codec.
+///
+/// Defined by:
+/// - enhanced_rtmp-v2.pdf (Enhanced Audio)
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnhancedAudioPacket {
+    /// Sequence Start, the codec configuration such as the AAC AudioSpecificConfig
+    SequenceStart { audio_codec: AudioFourCC, data: Bytes },
+    /// Coded Frames
+    CodedFrames { audio_codec: AudioFourCC, data: Bytes },
+    /// Sequence End
+    SequenceEnd { audio_codec: AudioFourCC },
+    /// Multichannel Config, the channel order and count
+    MultichannelConfig { audio_codec: AudioFourCC, data: Bytes },
+    /// Several audio tracks in one packet
+    Multitrack {
+        multitrack_type: AvMultitrackType,
+        tracks: Vec<AudioTrack>,
+    },
+    /// We don't know how to parse it
+    Unknown {
+        packet_type: AudioPacketType,
+        audio_codec: AudioFourCC,
+        data: Bytes,
+    },
+}
+
+impl EnhancedAudioPacket {
+    /// Demux an enhanced audio packet from the given reader, positioned after the
+    /// first byte of the audio data.
+    ///
+    /// The reader will be entirely consumed.
+    pub fn demux(packet_type: AudioPacketType, reader: &mut io::Cursor<Bytes>) -> io::Result<Self> {
+        let packet_type = AudioPacketType::from(skip_mod_ex(reader, packet_type.0, AudioPacketType::ModEx.0)?);
+
+        if packet_type == AudioPacketType::Multitrack {
+            let byte = reader.read_u8()?;
+            let multitrack_type = AvMultitrackType::from(byte >> 4);
+            let packet_type = AudioPacketType::from(byte & 0b0000_1111);
+
+            let tracks = demux_tracks(multitrack_type, reader, |audio_codec, track_id, reader| {
+                Ok(AudioTrack {
+                    track_id,
+                    packet: Self::demux_track(packet_type, AudioFourCC::from(audio_codec), reader),
+                })
+            })?;
+
+            return Ok(Self::Multitrack { multitrack_type, tracks });
+        }
+
+        let mut audio_codec = [0; 4];
+        reader.read_exact(&mut audio_codec)?;
+
+        Ok(Self::demux_track(packet_type, AudioFourCC::from(audio_codec), reader))
+    }
+
+    fn demux_track(packet_type: AudioPacketType, audio_codec: AudioFourCC, reader: &mut io::Cursor<Bytes>) -> Self {
+        let data = reader.extract_remaining();
+
+        match packet_type {
+            AudioPacketType::SequenceStart => Self::SequenceStart { audio_codec, data },
+            AudioPacketType::CodedFrames => Self::CodedFrames { audio_codec, data },
+            AudioPacketType::SequenceEnd => Self::SequenceEnd { audio_codec },
+            AudioPacketType::MultichannelConfig => Self::MultichannelConfig { audio_codec, data },
+            _ => Self::Unknown {
+                packet_type,
+                audio_codec,
+                data,
+            },
+        }
+    }
+}
+
+/// A track of a multitrack audio packet.
+///
+/// Defined by:
+/// - enhanced_rtmp-v2.pdf (Enhanced Audio)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTrack {
+    /// The id of the track, 0 is the default track.
+    pub track_id: u8,
+    /// The packet of the track.
+    pub packet: EnhancedAudioPacket,
+}
+
 nutype_enum! {
     /// FLV Sound Rate
     ///
@@ -196,6 +345,7 @@ mod tests {
             (0x06, SoundFormat::Nellymoser, "SoundFormat::Nellymoser"),
             (0x07, SoundFormat::G711ALaw, "SoundFormat::G711ALaw"),
             (0x08, SoundFormat::G711MuLaw, "SoundFormat::G711MuLaw"),
+            (0x09, SoundFormat::ExHeader, "SoundFormat::ExHeader"),
             (0x0A, SoundFormat::Aac, "SoundFormat::Aac"),
             (0x0B, SoundFormat::Speex, "SoundFormat::Speex"),
             (0x0E, SoundFormat::Mp38Khz, "SoundFormat::Mp38Khz"),
@@ -291,4 +441,65 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_audio_data_demux_enhanced() {
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010000, // ex header + sequence start
+            b'O', b'p', b'u', b's', // audio codec
+            0x01, 0x02, // data
+        ]));
+        let audio_data = AudioData::demux(&mut reader).unwrap();
+        assert_eq!(
+            audio_data.body,
+            AudioDataBody::Enhanced(EnhancedAudioPacket::SequenceStart {
+                audio_codec: AudioFourCC::Opus,
+                data: Bytes::from_static(&[0x01, 0x02]),
+            })
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010111, // ex header + mod ex
+            0x00, 0x01, // timestamp offset in nanoseconds
+            0x02, // sequence end
+            b'f', b'L', b'a', b'C', // audio codec
+        ]));
+        let audio_data = AudioData::demux(&mut reader).unwrap();
+        assert_eq!(
+            audio_data.body,
+            AudioDataBody::Enhanced(EnhancedAudioPacket::SequenceEnd {
+                audio_codec: AudioFourCC::Flac,
+            })
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010101, // ex header + multitrack
+            0x21,       // many tracks many codecs + coded frames
+            b'O', b'p', b'u', b's', 0x00, 0x00, 0x00, 0x01, 0x0a, // track 0
+            b'm', b'p', b'4', b'a', 0x01, 0x00, 0x00, 0x02, 0x0b, 0x0c, // track 1
+        ]));
+        let audio_data = AudioData::demux(&mut reader).unwrap();
+        assert_eq!(
+            audio_data.body,
+            AudioDataBody::Enhanced(EnhancedAudioPacket::Multitrack {
+                multitrack_type: AvMultitrackType::ManyTracksManyCodecs,
+                tracks: vec![
+                    AudioTrack {
+                        track_id: 0,
+                        packet: EnhancedAudioPacket::CodedFrames {
+                            audio_codec: AudioFourCC::Opus,
+                            data: Bytes::from_static(&[0x0a]),
+                        },
+                    },
+                    AudioTrack {
+                        track_id: 1,
+                        packet: EnhancedAudioPacket::CodedFrames {
+                            audio_codec: AudioFourCC::Aac,
+                            data: Bytes::from_static(&[0x0b, 0x0c]),
+                        },
+                    },
+                ],
+            })
+        );
+    }
 }
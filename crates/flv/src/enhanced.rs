@@ -0,0 +1,181 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use bytes::Bytes;
+use nutype_enum::nutype_enum;
+use scuffle_bytes_util::BytesCursorExt;
+
+nutype_enum! {
+    /// Multitrack Type
+    ///
+    /// Denotes how the tracks of a multitrack audio or video packet are laid out.
+    ///
+    /// Defined by:
+    /// - enhanced_rtmp-v2.pdf (Enhanced Audio, Enhanced Video)
+    pub enum AvMultitrackType(u8) {
+        /// A single track, the packet has no track sizes.
+        OneTrack = 0,
+        /// Several tracks of the same codec.
+        ManyTracks = 1,
+        /// Several tracks, each with its own codec.
+        ManyTracksManyCodecs = 2,
+    }
+}
+
+/// Skips the packet modifier extensions (ModEx) as long as `packet_type` is `mod_ex`,
+/// returning the packet type that follows them.
+///
+/// The only modifier defined so far is a nanosecond offset of the timestamp, which we do not need.
+///
+/// Defined by:
+/// - enhanced_rtmp-v2.pdf (Enhanced Audio, Enhanced Video)
+pub(crate) fn skip_mod_ex(reader: &mut io::Cursor<Bytes>, mut packet_type: u8, mod_ex: u8) -> io::Result<u8> {
+    while packet_type == mod_ex {
+        let mut size = reader.read_u8()? as usize + 1;
+        if size == 256 {
+            size = reader.read_u16::<BigEndian>()? as usize + 1;
+        }
+
+        reader.extract_bytes(size)?;
+
+        // The low 4 bits are the packet type, the high 4 bits the type of the modifier.
+        packet_type = reader.read_u8()? & 0b0000_1111;
+    }
+
+    Ok(packet_type)
+}
+
+/// Splits a multitrack packet into its tracks, calling `demux_track` with the FourCC,
+/// the track id and the data of each track.
+///
+/// If the packet is not [`AvMultitrackType::ManyTracksManyCodecs`] the FourCC is read once,
+/// before the tracks.
+///
+/// Defined by:
+/// - enhanced_rtmp-v2.pdf (Enhanced Audio, Enhanced Video)
+pub(crate) fn demux_tracks<T>(
+    multitrack_type: AvMultitrackType,
+    reader: &mut io::Cursor<Bytes>,
+    mut demux_track: impl FnMut([u8; 4], u8, &mut io::Cursor<Bytes>) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    fn read_fourcc(reader: &mut io::Cursor<Bytes>) -> io::Result<[u8; 4]> {
+        let mut fourcc = [0; 4];
+        io::Read::read_exact(reader, &mut fourcc)?;
+        Ok(fourcc)
+    }
+
+    let shared_fourcc = match multitrack_type {
+        AvMultitrackType::ManyTracksManyCodecs => None,
+        _ => Some(read_fourcc(reader)?),
+    };
+
+    let mut tracks = Vec::new();
+    loop {
+        let fourcc = match shared_fourcc {
+            Some(fourcc) => fourcc,
+            None => read_fourcc(reader)?,
+        };
+        let track_id = reader.read_u8()?;
+
+        // A single track takes up the rest of the packet, otherwise each track is prefixed with its size.
+        let data = match multitrack_type {
+            AvMultitrackType::OneTrack => reader.extract_remaining(),
+            _ => {
+                let size = reader.read_u24::<BigEndian>()?;
+                reader.extract_bytes(size as usize)?
+            }
+        };
+
+        tracks.push(demux_track(fourcc, track_id, &mut io::Cursor::new(data))?);
+
+        if multitrack_type == AvMultitrackType::OneTrack || reader.position() >= reader.get_ref().len() as u64 {
+            return Ok(tracks);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_av_multitrack_type() {
+        let cases = [
+            (AvMultitrackType::OneTrack, 0, "AvMultitrackType::OneTrack"),
+            (AvMultitrackType::ManyTracks, 1, "AvMultitrackType::ManyTracks"),
+            (
+                AvMultitrackType::ManyTracksManyCodecs,
+                2,
+                "AvMultitrackType::ManyTracksManyCodecs",
+            ),
+            (AvMultitrackType(3), 3, "AvMultitrackType(3)"),
+        ];
+
+        for (expected, value, name) in cases {
+            assert_eq!(AvMultitrackType::from(value), expected);
+            assert_eq!(format!("{:?}", AvMultitrackType::from(value)), name);
+        }
+    }
+
+    #[test]
+    fn test_skip_mod_ex() {
+        let mut data = vec![
+            0x02, 0x01, 0x02, 0x03, // 3 bytes of modifier data
+            0x07, // another modifier follows
+            0xff, 0x00, 0xff, // 256 bytes of modifier data, the size is in the next 2 bytes
+        ];
+        data.extend_from_slice(&[0; 256]);
+        data.extend_from_slice(&[0x01, 0xaa]);
+        let mut reader = io::Cursor::new(Bytes::from(data));
+
+        assert_eq!(skip_mod_ex(&mut reader, 7, 7).unwrap(), 1);
+        assert_eq!(reader.extract_remaining(), Bytes::from_static(&[0xaa]));
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[0xaa]));
+        assert_eq!(skip_mod_ex(&mut reader, 1, 7).unwrap(), 1);
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[test]
+    fn test_demux_tracks() {
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            b'O', b'p', b'u', b's', // shared codec
+            0x00, 0x00, 0x00, 0x02, 0x01, 0x02, // track 0
+            0x01, 0x00, 0x00, 0x01, 0x03, // track 1
+        ]));
+        let tracks = demux_tracks(AvMultitrackType::ManyTracks, &mut reader, |fourcc, track_id, reader| {
+            Ok((fourcc, track_id, reader.extract_remaining()))
+        })
+        .unwrap();
+        assert_eq!(
+            tracks,
+            [
+                (*b"Opus", 0, Bytes::from_static(&[0x01, 0x02])),
+                (*b"Opus", 1, Bytes::from_static(&[0x03])),
+            ]
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            b'h', b'v', b'c', b'1', 0x00, 0x00, 0x00, 0x01, 0x01, // track 0
+            b'a', b'v', b'0', b'1', 0x01, 0x00, 0x00, 0x00, // track 1, empty
+        ]));
+        let tracks = demux_tracks(
+            AvMultitrackType::ManyTracksManyCodecs,
+            &mut reader,
+            |fourcc, track_id, reader| Ok((fourcc, track_id, reader.extract_remaining())),
+        )
+        .unwrap();
+        assert_eq!(
+            tracks,
+            [(*b"hvc1", 0, Bytes::from_static(&[0x01])), (*b"av01", 1, Bytes::new()),]
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[b'a', b'v', b'0', b'1', 0x02, 0x01, 0x02, 0x03]));
+        let tracks = demux_tracks(AvMultitrackType::OneTrack, &mut reader, |fourcc, track_id, reader| {
+            Ok((fourcc, track_id, reader.extract_remaining()))
+        })
+        .unwrap();
+        assert_eq!(tracks, [(*b"av01", 2, Bytes::from_static(&[0x01, 0x02, 0x03]))]);
+    }
+}
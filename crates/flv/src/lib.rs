@@ -25,12 +25,14 @@ pub mod aac;
 pub mod audio;
 pub mod av1;
 pub mod avc;
+pub mod enhanced;
 pub mod file;
 pub mod header;
 pub mod hevc;
 pub mod script;
 pub mod tag;
 pub mod video;
+pub mod vp9;
 
 pub use crate::file::FlvFile;
 pub use crate::header::FlvHeader;
@@ -294,6 +296,22 @@ mod tests {
                         time_scale: 120,
                     },
                 ),
+                fixed_frame_rate_flag: true,
+                nal_hrd_parameters: None,
+                vcl_hrd_parameters: None,
+                low_delay_hrd_flag: false,
+                pic_struct_present_flag: false,
+                bitstream_restriction: Some(
+                    BitstreamRestriction {
+                        motion_vectors_over_pic_boundaries_flag: true,
+                        max_bytes_per_pic_denom: 0,
+                        max_bits_per_mb_denom: 0,
+                        log2_max_mv_length_horizontal: 11,
+                        log2_max_mv_length_vertical: 11,
+                        max_num_reorder_frames: 2,
+                        max_dec_frame_buffering: 4,
+                    },
+                ),
             }
             ");
         }
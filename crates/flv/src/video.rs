@@ -9,7 +9,9 @@ use scuffle_h265::HEVCDecoderConfigurationRecord;
 
 use super::av1::Av1Packet;
 use super::avc::AvcPacket;
+use super::enhanced::{AvMultitrackType, demux_tracks, skip_mod_ex};
 use super::hevc::HevcPacket;
+use super::vp9::Vp9Packet;
 
 nutype_enum! {
     /// FLV Frame Type
@@ -167,56 +169,31 @@ impl VideoTagBody {
                 }),
             },
             VideoPacketType::Enhanced(packet_type) => {
+                let packet_type = EnhancedPacketType::from(skip_mod_ex(reader, packet_type.0, EnhancedPacketType::ModEx.0)?);
+
+                if packet_type == EnhancedPacketType::Multitrack {
+                    let byte = reader.read_u8()?;
+                    let multitrack_type = AvMultitrackType::from(byte >> 4);
+                    let packet_type = EnhancedPacketType::from(byte & 0b0000_1111);
+
+                    let tracks = demux_tracks(multitrack_type, reader, |video_codec, track_id, reader| {
+                        Ok(VideoTrack {
+                            track_id,
+                            packet: EnhancedPacket::demux(packet_type, VideoFourCC::from(video_codec), reader)?,
+                        })
+                    })?;
+
+                    return Ok(VideoTagBody::Enhanced(EnhancedPacket::Multitrack { multitrack_type, tracks }));
+                }
+
                 let mut video_codec = [0; 4];
                 reader.read_exact(&mut video_codec)?;
-                let video_codec = VideoFourCC::from(video_codec);
-
-                match packet_type {
-                    EnhancedPacketType::SequenceEnd => {
-                        return Ok(VideoTagBody::Enhanced(EnhancedPacket::SequenceEnd { video_codec }));
-                    }
-                    EnhancedPacketType::Metadata => {
-                        return Ok(VideoTagBody::Enhanced(EnhancedPacket::Metadata {
-                            video_codec,
-                            data: reader.extract_remaining(),
-                        }));
-                    }
-                    _ => {}
-                }
 
-                match (video_codec, packet_type) {
-                    (VideoFourCC::Av1, EnhancedPacketType::SequenceStart) => Ok(VideoTagBody::Enhanced(
-                        EnhancedPacket::Av1(Av1Packet::SequenceStart(AV1CodecConfigurationRecord::demux(reader)?)),
-                    )),
-                    (VideoFourCC::Av1, EnhancedPacketType::Mpeg2SequenceStart) => {
-                        Ok(VideoTagBody::Enhanced(EnhancedPacket::Av1(Av1Packet::SequenceStart(
-                            AV1VideoDescriptor::demux(reader)?.codec_configuration_record,
-                        ))))
-                    }
-                    (VideoFourCC::Av1, EnhancedPacketType::CodedFrames) => Ok(VideoTagBody::Enhanced(EnhancedPacket::Av1(
-                        Av1Packet::Raw(reader.extract_remaining()),
-                    ))),
-                    (VideoFourCC::Hevc, EnhancedPacketType::SequenceStart) => Ok(VideoTagBody::Enhanced(
-                        EnhancedPacket::Hevc(HevcPacket::SequenceStart(HEVCDecoderConfigurationRecord::demux(reader)?)),
-                    )),
-                    (VideoFourCC::Hevc, EnhancedPacketType::CodedFrames) => {
-                        Ok(VideoTagBody::Enhanced(EnhancedPacket::Hevc(HevcPacket::Nalu {
-                            composition_time: Some(reader.read_i24::<BigEndian>()?),
-                            data: reader.extract_remaining(),
-                        })))
-                    }
-                    (VideoFourCC::Hevc, EnhancedPacketType::CodedFramesX) => {
-                        Ok(VideoTagBody::Enhanced(EnhancedPacket::Hevc(HevcPacket::Nalu {
-                            composition_time: None,
-                            data: reader.extract_remaining(),
-                        })))
-                    }
-                    _ => Ok(VideoTagBody::Enhanced(EnhancedPacket::Unknown {
-                        packet_type,
-                        video_codec,
-                        data: reader.extract_remaining(),
-                    })),
-                }
+                Ok(VideoTagBody::Enhanced(EnhancedPacket::demux(
+                    packet_type,
+                    VideoFourCC::from(video_codec),
+                    reader,
+                )?))
             }
         }
     }
@@ -240,6 +217,13 @@ pub enum EnhancedPacket {
     Av1(Av1Packet),
     /// Hevc (H.265) Video Packet
     Hevc(HevcPacket),
+    /// VP9 Video Packet
+    Vp9(Vp9Packet),
+    /// Several video tracks in one packet
+    Multitrack {
+        multitrack_type: AvMultitrackType,
+        tracks: Vec<VideoTrack>,
+    },
     /// We don't know how to parse it
     Unknown {
         packet_type: EnhancedPacketType,
@@ -248,6 +232,73 @@ pub enum EnhancedPacket {
     },
 }
 
+impl EnhancedPacket {
+    /// Demux an enhanced packet of a single track from the given reader.
+    /// The reader will consume all the data from the reader.
+    pub fn demux(
+        packet_type: EnhancedPacketType,
+        video_codec: VideoFourCC,
+        reader: &mut io::Cursor<Bytes>,
+    ) -> io::Result<Self> {
+        match packet_type {
+            EnhancedPacketType::SequenceEnd => return Ok(EnhancedPacket::SequenceEnd { video_codec }),
+            EnhancedPacketType::Metadata => {
+                return Ok(EnhancedPacket::Metadata {
+                    video_codec,
+                    data: reader.extract_remaining(),
+                });
+            }
+            _ => {}
+        }
+
+        match (video_codec, packet_type) {
+            (VideoFourCC::Av1, EnhancedPacketType::SequenceStart) => Ok(EnhancedPacket::Av1(Av1Packet::SequenceStart(
+                AV1CodecConfigurationRecord::demux(reader)?,
+            ))),
+            (VideoFourCC::Av1, EnhancedPacketType::Mpeg2SequenceStart) => Ok(EnhancedPacket::Av1(Av1Packet::SequenceStart(
+                AV1VideoDescriptor::demux(reader)?.codec_configuration_record,
+            ))),
+            (VideoFourCC::Av1, EnhancedPacketType::CodedFrames) => {
+                Ok(EnhancedPacket::Av1(Av1Packet::Raw(reader.extract_remaining())))
+            }
+            (VideoFourCC::Hevc, EnhancedPacketType::SequenceStart) => Ok(EnhancedPacket::Hevc(HevcPacket::SequenceStart(
+                HEVCDecoderConfigurationRecord::demux(reader)?,
+            ))),
+            (VideoFourCC::Hevc, EnhancedPacketType::CodedFrames) => Ok(EnhancedPacket::Hevc(HevcPacket::Nalu {
+                composition_time: Some(reader.read_i24::<BigEndian>()?),
+                data: reader.extract_remaining(),
+            })),
+            (VideoFourCC::Hevc, EnhancedPacketType::CodedFramesX) => Ok(EnhancedPacket::Hevc(HevcPacket::Nalu {
+                composition_time: None,
+                data: reader.extract_remaining(),
+            })),
+            (VideoFourCC::Vp9, EnhancedPacketType::SequenceStart) => {
+                Ok(EnhancedPacket::Vp9(Vp9Packet::SequenceStart(reader.extract_remaining())))
+            }
+            (VideoFourCC::Vp9, EnhancedPacketType::CodedFrames) => {
+                Ok(EnhancedPacket::Vp9(Vp9Packet::Raw(reader.extract_remaining())))
+            }
+            _ => Ok(EnhancedPacket::Unknown {
+                packet_type,
+                video_codec,
+                data: reader.extract_remaining(),
+            }),
+        }
+    }
+}
+
+/// A track of a multitrack video packet.
+///
+/// Defined by:
+/// - enhanced_rtmp-v2.pdf (Enhanced Video)
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoTrack {
+    /// The id of the track, 0 is the default track.
+    pub track_id: u8,
+    /// The packet of the track.
+    pub packet: EnhancedPacket,
+}
+
 nutype_enum! {
     /// FLV Video FourCC
     ///
@@ -287,6 +338,10 @@ nutype_enum! {
         Metadata = 4,
         /// MPEG-2 Sequence Start
         Mpeg2SequenceStart = 5,
+        /// Multitrack, the packet contains several tracks
+        Multitrack = 6,
+        /// Packet modifier extensions precede the actual packet type
+        ModEx = 7,
     }
 }
 
@@ -324,8 +379,9 @@ mod tests {
                 5,
                 "EnhancedPacketType::Mpeg2SequenceStart",
             ),
-            (EnhancedPacketType(6), 6, "EnhancedPacketType(6)"),
-            (EnhancedPacketType(7), 7, "EnhancedPacketType(7)"),
+            (EnhancedPacketType::Multitrack, 6, "EnhancedPacketType::Multitrack"),
+            (EnhancedPacketType::ModEx, 7, "EnhancedPacketType::ModEx"),
+            (EnhancedPacketType(8), 8, "EnhancedPacketType(8)"),
         ];
 
         for (expected, value, name) in cases {
@@ -579,4 +635,118 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_video_data_body_vp9() {
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010000, // enhanced + keyframe + sequence start
+            b'v', b'p', b'0', b'9', // video codec
+            0x01, 0x02, 0x03, // data
+        ]));
+        let body = VideoTagHeader::demux(&mut reader).unwrap();
+        assert_eq!(
+            body.body,
+            VideoTagBody::Enhanced(EnhancedPacket::Vp9(Vp9Packet::SequenceStart(Bytes::from_static(&[
+                0x01, 0x02, 0x03
+            ]))))
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10100001, // enhanced + interframe + coded frames
+            b'v', b'p', b'0', b'9', // video codec
+            0x04, 0x05, // data
+        ]));
+        let body = VideoTagHeader::demux(&mut reader).unwrap();
+        assert_eq!(
+            body,
+            VideoTagHeader {
+                frame_type: FrameType::Interframe,
+                body: VideoTagBody::Enhanced(EnhancedPacket::Vp9(Vp9Packet::Raw(Bytes::from_static(&[0x04, 0x05])))),
+            }
+        );
+    }
+
+    #[test]
+    fn test_video_data_demux_mod_ex() {
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10100111, // enhanced + interframe + mod ex
+            0x02, 0x00, 0x01, 0x02, // timestamp offset in nanoseconds
+            0x03, // coded frames x
+            b'h', b'v', b'c', b'1', // video codec
+            0x01, 0x02, // data
+        ]));
+        let body = VideoTagHeader::demux(&mut reader).unwrap();
+        assert_eq!(
+            body.body,
+            VideoTagBody::Enhanced(EnhancedPacket::Hevc(HevcPacket::Nalu {
+                composition_time: None,
+                data: Bytes::from_static(&[0x01, 0x02]),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_video_data_demux_multitrack() {
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010110, // enhanced + keyframe + multitrack
+            0x11,       // many tracks + coded frames
+            b'h', b'v', b'c', b'1', // video codec
+            0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x01, 0x0a, 0x0b, // track 0
+            0x01, 0x00, 0x00, 0x04, 0x00, 0x00, 0x02, 0x0c, // track 1
+        ]));
+        let body = VideoTagHeader::demux(&mut reader).unwrap();
+        assert_eq!(
+            body,
+            VideoTagHeader {
+                frame_type: FrameType::Keyframe,
+                body: VideoTagBody::Enhanced(EnhancedPacket::Multitrack {
+                    multitrack_type: AvMultitrackType::ManyTracks,
+                    tracks: vec![
+                        VideoTrack {
+                            track_id: 0,
+                            packet: EnhancedPacket::Hevc(HevcPacket::Nalu {
+                                composition_time: Some(1),
+                                data: Bytes::from_static(&[0x0a, 0x0b]),
+                            }),
+                        },
+                        VideoTrack {
+                            track_id: 1,
+                            packet: EnhancedPacket::Hevc(HevcPacket::Nalu {
+                                composition_time: Some(2),
+                                data: Bytes::from_static(&[0x0c]),
+                            }),
+                        },
+                    ],
+                }),
+            }
+        );
+
+        let mut reader = io::Cursor::new(Bytes::from_static(&[
+            0b10010110, // enhanced + keyframe + multitrack
+            0x22,       // many tracks many codecs + sequence end
+            b'h', b'v', b'c', b'1', 0x00, 0x00, 0x00, 0x00, // track 0
+            b'a', b'v', b'0', b'1', 0x01, 0x00, 0x00, 0x00, // track 1
+        ]));
+        let body = VideoTagHeader::demux(&mut reader).unwrap();
+        assert_eq!(
+            body.body,
+            VideoTagBody::Enhanced(EnhancedPacket::Multitrack {
+                multitrack_type: AvMultitrackType::ManyTracksManyCodecs,
+                tracks: vec![
+                    VideoTrack {
+                        track_id: 0,
+                        packet: EnhancedPacket::SequenceEnd {
+                            video_codec: VideoFourCC::Hevc,
+                        },
+                    },
+                    VideoTrack {
+                        track_id: 1,
+                        packet: EnhancedPacket::SequenceEnd {
+                            video_codec: VideoFourCC::Av1,
+                        },
+                    },
+                ],
+            })
+        );
+    }
 }
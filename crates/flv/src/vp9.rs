@@ -0,0 +1,12 @@
+use bytes::Bytes;
+
+/// VP9 Packet
+/// This is a container for vp9 data.
+/// This enum contains the data for the different types of vp9 packets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vp9Packet {
+    /// VP9 Sequence Start, the raw VPCodecConfigurationRecord
+    SequenceStart(Bytes),
+    /// VP9 Raw Data
+    Raw(Bytes),
+}
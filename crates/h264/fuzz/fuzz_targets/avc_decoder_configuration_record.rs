@@ -0,0 +1,17 @@
+#![no_main]
+
+use std::io;
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use scuffle_h264::{AVCDecoderConfigurationRecord, Sps};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(config) = AVCDecoderConfigurationRecord::parse(&mut io::Cursor::new(Bytes::copy_from_slice(data))) else {
+        return;
+    };
+
+    for sps in &config.sps {
+        let _ = Sps::parse_with_emulation_prevention(io::Cursor::new(sps));
+    }
+});
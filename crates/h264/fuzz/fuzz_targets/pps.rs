@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scuffle_h264::Pps;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Pps::parse(data);
+    let _ = Pps::parse_with_emulation_prevention(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scuffle_h264::RecoveryPoint;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = RecoveryPoint::parse_sei_with_emulation_prevention(data);
+});
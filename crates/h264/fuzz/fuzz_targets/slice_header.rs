@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scuffle_h264::{Pps, SliceHeader, Sps};
+
+fuzz_target!(|data: &[u8]| {
+    // The first two bytes are the sizes of the sps and the pps, the rest is the slice.
+    let [sps_len, pps_len, data @ ..] = data else {
+        return;
+    };
+    let (sps, data) = data.split_at((*sps_len as usize).min(data.len()));
+    let (pps, slice) = data.split_at((*pps_len as usize).min(data.len()));
+
+    let (Ok(sps), Ok(pps)) = (
+        Sps::parse_with_emulation_prevention(sps),
+        Pps::parse_with_emulation_prevention(pps),
+    ) else {
+        return;
+    };
+
+    let _ = SliceHeader::parse_with_emulation_prevention(slice, &sps, &pps);
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scuffle_h264::Sps;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Sps::parse(data);
+    let _ = Sps::parse_with_emulation_prevention(data);
+
+    if let Ok(sps) = Sps::parse_with_emulation_prevention(data) {
+        // The derived values must not overflow for anything the parser accepts.
+        let _ = (sps.width(), sps.height(), sps.frame_rate());
+
+        let mut buf = Vec::new();
+        sps.build_with_emulation_prevention(&mut buf)
+            .expect("failed to build a parsed sps");
+    }
+});
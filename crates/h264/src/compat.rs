@@ -0,0 +1,468 @@
+use crate::Sps;
+
+/// The `level_idc` used for level 1b.
+///
+/// Profiles above High signal level 1b with a `level_idc` of 9, Baseline, Main and Extended
+/// use a `level_idc` of 11 together with `constraint_set3_flag`. [`Sps::level`] returns 9 for
+/// both.
+pub const LEVEL_1B: u8 = 9;
+
+/// The limits of a level that can be checked from the SPS alone.
+///
+/// ISO/IEC-14496-10-2022 - Table A-1
+struct LevelLimits {
+    level_idc: u8,
+    /// `MaxMBPS`, the maximum macroblock processing rate in macroblocks per second.
+    max_mbps: u64,
+    /// `MaxFS`, the maximum frame size in macroblocks.
+    max_fs: u64,
+    /// `MaxDpbMbs`, the maximum decoded picture buffer size in macroblocks.
+    max_dpb_mbs: u64,
+}
+
+/// All levels, from lowest to highest.
+const LEVELS: [LevelLimits; 20] = {
+    const fn level(level_idc: u8, max_mbps: u64, max_fs: u64, max_dpb_mbs: u64) -> LevelLimits {
+        LevelLimits {
+            level_idc,
+            max_mbps,
+            max_fs,
+            max_dpb_mbs,
+        }
+    }
+
+    [
+        level(10, 1485, 99, 396),
+        level(LEVEL_1B, 1485, 99, 396),
+        level(11, 3000, 396, 900),
+        level(12, 6000, 396, 2376),
+        level(13, 11880, 396, 2376),
+        level(20, 11880, 396, 2376),
+        level(21, 19800, 792, 4752),
+        level(22, 20250, 1620, 8100),
+        level(30, 40500, 1620, 8100),
+        level(31, 108000, 3600, 18000),
+        level(32, 216000, 5120, 20480),
+        level(40, 245760, 8192, 32768),
+        level(41, 245760, 8192, 32768),
+        level(42, 522240, 8704, 34816),
+        level(50, 589824, 22080, 110400),
+        level(51, 983040, 36864, 184320),
+        level(52, 2073600, 36864, 184320),
+        level(60, 4177920, 139264, 696320),
+        level(61, 8355840, 139264, 696320),
+        level(62, 16711680, 139264, 696320),
+    ]
+};
+
+/// Returns the position of a level in [`LEVELS`], `None` if the `level_idc` is not a level.
+fn level_index(level_idc: u8) -> Option<usize> {
+    LEVELS.iter().position(|level| level.level_idc == level_idc)
+}
+
+/// The H.264 profiles a decoder can support.
+///
+/// ISO/IEC-14496-10-2022 - A.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum H264Profile {
+    /// Constrained Baseline, the common subset of the Baseline and Main profiles.
+    ConstrainedBaseline,
+    /// Baseline, `profile_idc` 66.
+    Baseline,
+    /// Extended, `profile_idc` 88.
+    Extended,
+    /// Main, `profile_idc` 77.
+    Main,
+    /// High, `profile_idc` 100.
+    High,
+    /// High 10, `profile_idc` 110.
+    High10,
+    /// High 4:2:2, `profile_idc` 122.
+    High422,
+    /// High 4:4:4 Predictive, `profile_idc` 244, and CAVLC 4:4:4 Intra, `profile_idc` 44.
+    High444,
+}
+
+impl H264Profile {
+    /// Returns true if a decoder of this profile can decode streams of the `stream` profile.
+    ///
+    /// Baseline and Extended decoders support features, such as flexible macroblock ordering,
+    /// that decoders of the other profiles do not, so only Constrained Baseline streams can be
+    /// decoded by both.
+    pub const fn decodes(self, stream: Self) -> bool {
+        match self {
+            Self::ConstrainedBaseline => matches!(stream, Self::ConstrainedBaseline),
+            Self::Baseline => matches!(stream, Self::ConstrainedBaseline | Self::Baseline),
+            Self::Extended => matches!(stream, Self::ConstrainedBaseline | Self::Baseline | Self::Extended),
+            Self::Main => matches!(stream, Self::ConstrainedBaseline | Self::Main),
+            Self::High => matches!(stream, Self::ConstrainedBaseline | Self::Main | Self::High),
+            Self::High10 => matches!(stream, Self::ConstrainedBaseline | Self::Main | Self::High | Self::High10),
+            Self::High422 => !matches!(stream, Self::Baseline | Self::Extended | Self::High444),
+            Self::High444 => !matches!(stream, Self::Baseline | Self::Extended),
+        }
+    }
+
+    /// Returns the `chroma_format_idc` values the profile allows.
+    pub const fn chroma_format_idcs(self) -> &'static [u8] {
+        match self {
+            Self::ConstrainedBaseline | Self::Baseline | Self::Extended | Self::Main => &[1],
+            Self::High | Self::High10 => &[0, 1],
+            Self::High422 => &[0, 1, 2],
+            Self::High444 => &[0, 1, 2, 3],
+        }
+    }
+}
+
+/// What a decoder, such as the one of a playback device, can decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    /// The highest profile supported.
+    pub max_profile: H264Profile,
+    /// The highest level supported, as a `level_idc`, [`LEVEL_1B`] for level 1b.
+    pub max_level_idc: u8,
+    /// The supported `chroma_format_idc` values.
+    pub chroma_format_idcs: Vec<u8>,
+}
+
+impl DeviceCapabilities {
+    /// Creates the capabilities of a decoder supporting a profile and level, with all
+    /// the chroma formats of the profile.
+    pub fn new(max_profile: H264Profile, max_level_idc: u8) -> Self {
+        Self {
+            max_profile,
+            max_level_idc,
+            chroma_format_idcs: max_profile.chroma_format_idcs().to_vec(),
+        }
+    }
+}
+
+/// Why a stream has to be re-encoded, see [`TranscodeAction::Reencode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReencodeReason {
+    /// The profile of the stream is not supported.
+    UnsupportedProfile,
+    /// The chroma format of the stream is not supported.
+    UnsupportedChromaFormat,
+    /// The frame size, frame rate or number of reference frames exceed the highest supported level.
+    LevelExceeded,
+}
+
+/// The least work needed to make a stream playable on a device, see [`Sps::transcode_action`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscodeAction {
+    /// The stream can be passed through as is.
+    Passthrough,
+    /// The stream signals a higher level than the device supports, but fits the given one.
+    ///
+    /// Rewriting the level of the SPS, with [`Sps::set_level`], is enough. The level is also
+    /// in the `AVCLevelIndication` of the decoder configuration record, if there is one.
+    RewriteLevel {
+        /// The `level_idc` to signal.
+        level_idc: u8,
+    },
+    /// The stream has to be re-encoded.
+    Reencode(ReencodeReason),
+}
+
+impl Sps {
+    /// Returns true if the profile signals level 1b with `constraint_set3_flag`.
+    const fn signals_1b_with_constraint_set3(&self) -> bool {
+        matches!(self.profile_idc, 66 | 77 | 88)
+    }
+
+    /// Returns the level as a `level_idc`, [`LEVEL_1B`] for level 1b.
+    pub const fn level(&self) -> u8 {
+        if self.signals_1b_with_constraint_set3() && self.level_idc == 11 && self.constraint_set3_flag {
+            LEVEL_1B
+        } else {
+            self.level_idc
+        }
+    }
+
+    /// Sets the level, given as a `level_idc`, [`LEVEL_1B`] for level 1b.
+    ///
+    /// Level 1b is signalled with `constraint_set3_flag` if the profile requires it.
+    pub const fn set_level(&mut self, level_idc: u8) {
+        if self.signals_1b_with_constraint_set3() {
+            self.constraint_set3_flag = level_idc == LEVEL_1B;
+            self.level_idc = if level_idc == LEVEL_1B { 11 } else { level_idc };
+        } else {
+            self.level_idc = level_idc;
+        }
+    }
+
+    /// Returns the `chroma_format_idc`, which is 1 (4:2:0) if the SPS does not signal it.
+    pub fn chroma_format_idc(&self) -> u8 {
+        self.ext.as_ref().map_or(1, |ext| ext.chroma_format_idc)
+    }
+
+    /// Returns the least capable profile the stream conforms to, taking the constraint flags into account.
+    ///
+    /// Returns `None` for profiles that are not in [`H264Profile`], such as the scalable and multiview profiles.
+    pub const fn profile(&self) -> Option<H264Profile> {
+        // A.2.1 and A.2.2, every profile can signal that it also abides by the Baseline or Main profile.
+        let baseline = self.profile_idc == 66 || self.constraint_set0_flag;
+        let main = self.profile_idc == 77 || self.constraint_set1_flag;
+
+        Some(match self.profile_idc {
+            _ if baseline && main => H264Profile::ConstrainedBaseline,
+            66 | 77 | 88 if baseline => H264Profile::Baseline,
+            66 | 77 | 88 if main => H264Profile::Main,
+            88 => H264Profile::Extended,
+            100 => H264Profile::High,
+            110 => H264Profile::High10,
+            122 => H264Profile::High422,
+            44 | 244 => H264Profile::High444,
+            _ => return None,
+        })
+    }
+
+    /// Returns the lowest level whose frame size, macroblock rate and decoded picture buffer
+    /// size limits the stream fits, as a `level_idc`.
+    ///
+    /// The macroblock rate is only checked if the SPS has timing info. Limits that depend on
+    /// the bitstream, such as the bitrate, are not checked. Returns `None` if the stream
+    /// exceeds the highest level.
+    pub fn min_level(&self) -> Option<u8> {
+        let width_in_mbs = self.pic_width_in_mbs_minus1.saturating_add(1);
+        let height_in_mbs = (2 - self.mb_adaptive_frame_field_flag.is_none() as u64)
+            .saturating_mul(self.pic_height_in_map_units_minus1.saturating_add(1));
+        let frame_size = width_in_mbs.saturating_mul(height_in_mbs);
+        let mb_rate = self
+            .frame_rate()
+            .map(|frame_rate| (frame_rate * frame_size as f64).ceil() as u64);
+        let dpb_size = frame_size.saturating_mul(self.max_num_ref_frames as u64);
+
+        LEVELS
+            .iter()
+            .find(|level| {
+                // A.3.1, neither dimension may exceed Sqrt(MaxFS * 8).
+                frame_size <= level.max_fs
+                    && width_in_mbs.saturating_mul(width_in_mbs) <= level.max_fs * 8
+                    && height_in_mbs.saturating_mul(height_in_mbs) <= level.max_fs * 8
+                    && dpb_size <= level.max_dpb_mbs
+                    && mb_rate.is_none_or(|mb_rate| mb_rate <= level.max_mbps)
+            })
+            .map(|level| level.level_idc)
+    }
+
+    /// Returns the least work needed to make the stream playable on a device, so an ingest can
+    /// decide between passing a stream through and transcoding it.
+    ///
+    /// A stream whose profile and chroma format are supported but which signals a higher level
+    /// than the device supports only needs its level rewritten if its frame size, frame rate and
+    /// number of reference frames fit the level of the device, see [`Sps::min_level`].
+    pub fn transcode_action(&self, capabilities: &DeviceCapabilities) -> TranscodeAction {
+        let supported = self
+            .profile()
+            .is_some_and(|profile| capabilities.max_profile.decodes(profile));
+        if !supported {
+            return TranscodeAction::Reencode(ReencodeReason::UnsupportedProfile);
+        }
+
+        if !capabilities.chroma_format_idcs.contains(&self.chroma_format_idc()) {
+            return TranscodeAction::Reencode(ReencodeReason::UnsupportedChromaFormat);
+        }
+
+        let Some(max_level) = level_index(capabilities.max_level_idc) else {
+            return TranscodeAction::Reencode(ReencodeReason::LevelExceeded);
+        };
+
+        if level_index(self.level()).is_some_and(|level| level <= max_level) {
+            return TranscodeAction::Passthrough;
+        }
+
+        match self.min_level().and_then(level_index) {
+            Some(level) if level <= max_level => TranscodeAction::RewriteLevel {
+                level_idc: capabilities.max_level_idc,
+            },
+            _ => TranscodeAction::Reencode(ReencodeReason::LevelExceeded),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use crate::{
+        DeviceCapabilities, H264Profile, LEVEL_1B, NALUnitType, ReencodeReason, Sps, SpsExtended, TimingInfo,
+        TranscodeAction,
+    };
+
+    /// A 1920x1088 progressive SPS at 30 fps with 4 reference frames.
+    fn sps(profile_idc: u8, level_idc: u8) -> Sps {
+        Sps {
+            nal_ref_idc: 3,
+            nal_unit_type: NALUnitType::SPS,
+            profile_idc,
+            constraint_set0_flag: false,
+            constraint_set1_flag: false,
+            constraint_set2_flag: false,
+            constraint_set3_flag: false,
+            constraint_set4_flag: false,
+            constraint_set5_flag: false,
+            level_idc,
+            seq_parameter_set_id: 0,
+            ext: None,
+            log2_max_frame_num_minus4: 0,
+            pic_order_cnt_type: 2,
+            log2_max_pic_order_cnt_lsb_minus4: None,
+            pic_order_cnt_type1: None,
+            max_num_ref_frames: 4,
+            gaps_in_frame_num_value_allowed_flag: false,
+            pic_width_in_mbs_minus1: 119,
+            pic_height_in_map_units_minus1: 67,
+            mb_adaptive_frame_field_flag: None,
+            direct_8x8_inference_flag: true,
+            frame_crop_info: None,
+            sample_aspect_ratio: None,
+            overscan_appropriate_flag: None,
+            color_config: None,
+            chroma_sample_loc: None,
+            timing_info: Some(TimingInfo {
+                num_units_in_tick: NonZeroU32::new(1).unwrap(),
+                time_scale: NonZeroU32::new(60).unwrap(),
+            }),
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
+        }
+    }
+
+    #[test]
+    fn test_profile() {
+        assert_eq!(sps(66, 30).profile(), Some(H264Profile::Baseline));
+        assert_eq!(sps(77, 30).profile(), Some(H264Profile::Main));
+        assert_eq!(sps(88, 30).profile(), Some(H264Profile::Extended));
+        assert_eq!(sps(100, 30).profile(), Some(H264Profile::High));
+        assert_eq!(sps(110, 30).profile(), Some(H264Profile::High10));
+        assert_eq!(sps(122, 30).profile(), Some(H264Profile::High422));
+        assert_eq!(sps(244, 30).profile(), Some(H264Profile::High444));
+        assert_eq!(sps(44, 30).profile(), Some(H264Profile::High444));
+        assert_eq!(sps(83, 30).profile(), None);
+
+        // Constraint flags make a stream decodable by less capable decoders.
+        let mut constrained = sps(66, 30);
+        constrained.constraint_set1_flag = true;
+        assert_eq!(constrained.profile(), Some(H264Profile::ConstrainedBaseline));
+
+        let mut constrained = sps(77, 30);
+        constrained.constraint_set0_flag = true;
+        assert_eq!(constrained.profile(), Some(H264Profile::ConstrainedBaseline));
+
+        let mut extended = sps(88, 30);
+        extended.constraint_set1_flag = true;
+        assert_eq!(extended.profile(), Some(H264Profile::Main));
+        extended.constraint_set1_flag = false;
+        extended.constraint_set0_flag = true;
+        assert_eq!(extended.profile(), Some(H264Profile::Baseline));
+    }
+
+    #[test]
+    fn test_profile_decodes() {
+        assert!(H264Profile::High.decodes(H264Profile::Main));
+        assert!(H264Profile::High.decodes(H264Profile::ConstrainedBaseline));
+        assert!(!H264Profile::High.decodes(H264Profile::Baseline));
+        assert!(!H264Profile::High.decodes(H264Profile::High10));
+        assert!(!H264Profile::Main.decodes(H264Profile::High));
+        assert!(H264Profile::Baseline.decodes(H264Profile::ConstrainedBaseline));
+        assert!(!H264Profile::Baseline.decodes(H264Profile::Main));
+        assert!(H264Profile::Extended.decodes(H264Profile::Baseline));
+        assert!(H264Profile::High422.decodes(H264Profile::High10));
+        assert!(!H264Profile::High422.decodes(H264Profile::High444));
+        assert!(H264Profile::High444.decodes(H264Profile::High422));
+        assert!(!H264Profile::High444.decodes(H264Profile::Extended));
+    }
+
+    #[test]
+    fn test_level() {
+        let mut baseline = sps(66, 11);
+        assert_eq!(baseline.level(), 11);
+        baseline.set_level(LEVEL_1B);
+        assert_eq!((baseline.level_idc, baseline.constraint_set3_flag), (11, true));
+        assert_eq!(baseline.level(), LEVEL_1B);
+        baseline.set_level(31);
+        assert_eq!((baseline.level_idc, baseline.constraint_set3_flag), (31, false));
+
+        let mut high = sps(100, 11);
+        high.set_level(LEVEL_1B);
+        assert_eq!((high.level_idc, high.constraint_set3_flag), (LEVEL_1B, false));
+        assert_eq!(high.level(), LEVEL_1B);
+
+        // 8160 macroblocks per frame, 244800 per second, 32640 in the decoded picture buffer.
+        assert_eq!(sps(100, 51).min_level(), Some(40));
+
+        let mut no_timing = sps(100, 51);
+        no_timing.timing_info = None;
+        no_timing.max_num_ref_frames = 1;
+        assert_eq!(no_timing.min_level(), Some(40));
+
+        let mut interlaced = sps(100, 51);
+        interlaced.pic_height_in_map_units_minus1 = 33;
+        interlaced.mb_adaptive_frame_field_flag = Some(true);
+        assert_eq!(interlaced.min_level(), Some(40));
+
+        let mut huge = sps(100, 51);
+        huge.pic_width_in_mbs_minus1 = 2000;
+        assert_eq!(huge.min_level(), None);
+    }
+
+    #[test]
+    fn test_transcode_action() {
+        let device = DeviceCapabilities::new(H264Profile::High, 41);
+        assert_eq!(device.chroma_format_idcs, [0, 1]);
+
+        assert_eq!(sps(100, 40).transcode_action(&device), TranscodeAction::Passthrough);
+        assert_eq!(sps(77, 41).transcode_action(&device), TranscodeAction::Passthrough);
+
+        // Encoders often signal a higher level than the stream needs.
+        assert_eq!(
+            sps(100, 51).transcode_action(&device),
+            TranscodeAction::RewriteLevel { level_idc: 41 }
+        );
+
+        let mut fast = sps(100, 51);
+        fast.timing_info = Some(TimingInfo {
+            num_units_in_tick: NonZeroU32::new(1).unwrap(),
+            time_scale: NonZeroU32::new(120).unwrap(),
+        });
+        assert_eq!(
+            fast.transcode_action(&device),
+            TranscodeAction::Reencode(ReencodeReason::LevelExceeded)
+        );
+
+        assert_eq!(
+            sps(66, 30).transcode_action(&device),
+            TranscodeAction::Reencode(ReencodeReason::UnsupportedProfile)
+        );
+        assert_eq!(
+            sps(110, 30).transcode_action(&device),
+            TranscodeAction::Reencode(ReencodeReason::UnsupportedProfile)
+        );
+
+        let mut monochrome = sps(100, 40);
+        monochrome.ext = Some(SpsExtended {
+            chroma_format_idc: 0,
+            separate_color_plane_flag: false,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            qpprime_y_zero_transform_bypass_flag: false,
+            scaling_matrix: Vec::new(),
+        });
+        assert_eq!(monochrome.transcode_action(&device), TranscodeAction::Passthrough);
+
+        let color_only = DeviceCapabilities {
+            chroma_format_idcs: vec![1],
+            ..device
+        };
+        assert_eq!(
+            monochrome.transcode_action(&color_only),
+            TranscodeAction::Reencode(ReencodeReason::UnsupportedChromaFormat)
+        );
+    }
+}
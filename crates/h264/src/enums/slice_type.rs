@@ -0,0 +1,33 @@
+use nutype_enum::nutype_enum;
+
+nutype_enum! {
+    /// The `SliceType` is a nutype enum for `slice_type` as defined in
+    /// ISO/IEC-14496-10-2022 - 7.4.3 Table 7-6.
+    ///
+    /// Values 5..=9 have the same meaning as values 0..=4 but additionally signal that
+    /// all slices of the picture have the same type. Use [`SliceType::from_slice_type`]
+    /// to map a raw `slice_type` onto one of the variants below.
+    pub enum SliceType(u8) {
+        /// P (predicted) slice.
+        P = 0,
+
+        /// B (bi-predicted) slice.
+        B = 1,
+
+        /// I (intra) slice.
+        I = 2,
+
+        /// SP (switching P) slice.
+        SP = 3,
+
+        /// SI (switching I) slice.
+        SI = 4,
+    }
+}
+
+impl SliceType {
+    /// Maps a raw `slice_type` value (0..=9) onto a [`SliceType`].
+    pub const fn from_slice_type(slice_type: u8) -> Self {
+        Self(slice_type % 5)
+    }
+}
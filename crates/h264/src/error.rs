@@ -0,0 +1,152 @@
+use std::{fmt, io};
+
+/// An error that occurred while parsing an H.264 syntax structure.
+///
+/// Carries the syntax element that could not be parsed, the structure it belongs to
+/// and the bit offset at which the element starts. The offset counts from the start of
+/// the NAL unit, after emulation prevention bytes have been removed.
+///
+/// Converts into an [`io::Error`], so parsers returning it can be used with `?`
+/// in functions that return [`io::Result`].
+#[derive(Debug)]
+pub struct H264ParseError {
+    structure: &'static str,
+    element: &'static str,
+    bit_offset: u64,
+    kind: H264ParseErrorKind,
+}
+
+/// Why parsing an H.264 syntax element failed, see [`H264ParseError::kind`].
+#[derive(Debug)]
+pub enum H264ParseErrorKind {
+    /// Reading the element failed, usually because the input ended early.
+    Io(io::Error),
+    /// The value is outside the range allowed by the spec.
+    OutOfRange,
+    /// The value is not allowed, the reason is given.
+    Invalid(&'static str),
+}
+
+impl H264ParseError {
+    pub(crate) const fn new(
+        structure: &'static str,
+        element: &'static str,
+        bit_offset: u64,
+        kind: H264ParseErrorKind,
+    ) -> Self {
+        Self {
+            structure,
+            element,
+            bit_offset,
+            kind,
+        }
+    }
+
+    /// The syntax structure that was being parsed, such as `seq_parameter_set_data` or `vui_parameters`.
+    pub const fn structure(&self) -> &'static str {
+        self.structure
+    }
+
+    /// The syntax element that could not be parsed, such as `pic_order_cnt_type`.
+    pub const fn element(&self) -> &'static str {
+        self.element
+    }
+
+    /// The offset in bits at which the element starts.
+    pub const fn bit_offset(&self) -> u64 {
+        self.bit_offset
+    }
+
+    /// Why parsing the element failed.
+    pub const fn kind(&self) -> &H264ParseErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for H264ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{} at bit {}: {}",
+            self.structure, self.element, self.bit_offset, self.kind
+        )
+    }
+}
+
+impl fmt::Display for H264ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::OutOfRange => write!(f, "value is out of range"),
+            Self::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for H264ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            H264ParseErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<H264ParseError> for io::Error {
+    fn from(err: H264ParseError) -> Self {
+        let kind = match &err.kind {
+            H264ParseErrorKind::Io(err) => err.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io;
+
+    use super::{H264ParseError, H264ParseErrorKind};
+
+    #[test]
+    fn test_parse_error_display() {
+        let err = H264ParseError::new(
+            "seq_parameter_set_data",
+            "pic_order_cnt_type",
+            42,
+            H264ParseErrorKind::OutOfRange,
+        );
+        assert_eq!(
+            err.to_string(),
+            "seq_parameter_set_data.pic_order_cnt_type at bit 42: value is out of range"
+        );
+
+        let err = H264ParseError::new(
+            "vui_parameters",
+            "time_scale",
+            96,
+            H264ParseErrorKind::Io(io::ErrorKind::UnexpectedEof.into()),
+        );
+        assert_eq!(err.to_string(), "vui_parameters.time_scale at bit 96: unexpected end of file");
+    }
+
+    #[test]
+    fn test_parse_error_into_io_error() {
+        let err: io::Error =
+            H264ParseError::new("nal_unit", "forbidden_zero_bit", 0, H264ParseErrorKind::Invalid("must be 0")).into();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "nal_unit.forbidden_zero_bit at bit 0: must be 0");
+
+        let err: io::Error = H264ParseError::new(
+            "vui_parameters",
+            "time_scale",
+            96,
+            H264ParseErrorKind::Io(io::ErrorKind::UnexpectedEof.into()),
+        )
+        .into();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.get_ref().is_some_and(|inner| inner.is::<H264ParseError>()));
+    }
+}
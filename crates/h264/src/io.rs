@@ -1,3 +1,8 @@
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::{BitReaderExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
+
+use crate::error::{H264ParseError, H264ParseErrorKind};
+
 /// A wrapper around a [`std::io::Read`] or [`std::io::Write`] that automatically inserts or removes
 /// emulation prevention bytes, when reading or writing respectively.
 pub struct EmulationPreventionIo<I> {
@@ -70,11 +75,131 @@ impl<I: std::io::Read> std::io::Read for EmulationPreventionIo<I> {
     }
 }
 
+/// Reads an exp-golomb value and checks that it is at most `max`.
+///
+/// Values in the bitstream can be up to 64 bits long, checking them against the
+/// range allowed by the spec makes the casts to the smaller field types lossless
+/// and keeps the arithmetic done with them from overflowing.
+pub(crate) fn read_exp_golomb_max<T: std::io::Read>(
+    reader: &mut BitReader<T>,
+    max: u64,
+    name: &'static str,
+) -> std::io::Result<u64> {
+    let value = reader.read_exp_golomb()?;
+    if value > max {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{name} is out of range"),
+        ));
+    }
+
+    Ok(value)
+}
+
+/// A [`BitReader`] that names the syntax elements it reads, so errors can say where parsing failed.
+///
+/// Keeps track of the bit offset itself, nested structures share the reader and switch
+/// the structure name with [`SyntaxReader::structure`].
+pub(crate) struct SyntaxReader<'a, T> {
+    reader: &'a mut BitReader<T>,
+    structure: &'static str,
+    bit_offset: u64,
+    element_offset: u64,
+}
+
+impl<'a, T: std::io::Read> SyntaxReader<'a, T> {
+    /// Creates a reader for `structure`, starting at bit offset 0.
+    pub(crate) fn new(reader: &'a mut BitReader<T>, structure: &'static str) -> Self {
+        Self {
+            reader,
+            structure,
+            bit_offset: 0,
+            element_offset: 0,
+        }
+    }
+
+    /// Runs `parse` with the structure name set to `structure`.
+    pub(crate) fn structure<R>(
+        &mut self,
+        structure: &'static str,
+        parse: impl FnOnce(&mut Self) -> Result<R, H264ParseError>,
+    ) -> Result<R, H264ParseError> {
+        let outer = std::mem::replace(&mut self.structure, structure);
+        let result = parse(self);
+        self.structure = outer;
+        result
+    }
+
+    fn read<V>(
+        &mut self,
+        element: &'static str,
+        read: impl FnOnce(&mut BitReader<T>) -> std::io::Result<V>,
+        size: impl FnOnce(&V) -> u64,
+    ) -> Result<V, H264ParseError> {
+        self.element_offset = self.bit_offset;
+        let value = read(self.reader).map_err(|err| self.error(element, H264ParseErrorKind::Io(err)))?;
+        self.bit_offset += size(&value);
+        Ok(value)
+    }
+
+    pub(crate) fn read_bit(&mut self, element: &'static str) -> Result<bool, H264ParseError> {
+        self.read(element, |reader| reader.read_bit(), |_| 1)
+    }
+
+    pub(crate) fn read_bits(&mut self, count: u8, element: &'static str) -> Result<u64, H264ParseError> {
+        self.read(element, |reader| reader.read_bits(count), |_| count.min(64) as u64)
+    }
+
+    pub(crate) fn read_u8(&mut self, element: &'static str) -> Result<u8, H264ParseError> {
+        Ok(self.read_bits(8, element)? as u8)
+    }
+
+    pub(crate) fn read_u32(&mut self, element: &'static str) -> Result<u32, H264ParseError> {
+        Ok(self.read_bits(32, element)? as u32)
+    }
+
+    pub(crate) fn read_exp_golomb(&mut self, element: &'static str) -> Result<u64, H264ParseError> {
+        self.read(element, |reader| reader.read_exp_golomb(), |value| size_of_exp_golomb(*value))
+    }
+
+    pub(crate) fn read_signed_exp_golomb(&mut self, element: &'static str) -> Result<i64, H264ParseError> {
+        self.read(
+            element,
+            |reader| reader.read_signed_exp_golomb(),
+            |value| size_of_signed_exp_golomb(*value),
+        )
+    }
+
+    /// Reads an exp-golomb value and checks that it is at most `max`, like [`read_exp_golomb_max`].
+    pub(crate) fn read_exp_golomb_max(&mut self, max: u64, element: &'static str) -> Result<u64, H264ParseError> {
+        let value = self.read_exp_golomb(element)?;
+        if value > max {
+            return Err(self.error(element, H264ParseErrorKind::OutOfRange));
+        }
+
+        Ok(value)
+    }
+
+    /// Returns an error for `element`, which must be the element read last.
+    pub(crate) fn error(&self, element: &'static str, kind: H264ParseErrorKind) -> H264ParseError {
+        H264ParseError::new(self.structure, element, self.element_offset, kind)
+    }
+
+    /// Returns an [`H264ParseErrorKind::Invalid`] error for `element`, which must be the element read last.
+    pub(crate) fn invalid(&self, element: &'static str, reason: &'static str) -> H264ParseError {
+        self.error(element, H264ParseErrorKind::Invalid(reason))
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
     use std::io::{Read, Write};
 
+    use scuffle_bytes_util::{BitReader, BitWriter};
+    use scuffle_expgolomb::BitWriterExpGolombExt;
+
+    use super::read_exp_golomb_max;
     use crate::EmulationPreventionIo;
 
     #[test]
@@ -138,4 +263,19 @@ mod tests {
         // Should match original after roundtrip
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    fn test_read_exp_golomb_max() {
+        let mut writer = BitWriter::<Vec<u8>>::default();
+        writer.write_exp_golomb(31).unwrap();
+        writer.write_exp_golomb(32).unwrap();
+        let data = writer.finish().unwrap();
+
+        let mut reader = BitReader::new(std::io::Cursor::new(data));
+        assert_eq!(read_exp_golomb_max(&mut reader, 31, "value").unwrap(), 31);
+
+        let err = read_exp_golomb_max(&mut reader, 31, "value").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "value is out of range");
+    }
 }
@@ -85,6 +85,20 @@
 //!
 //! For more examples, check out the tests in the source code for the build function.
 //!
+//! ## Untrusted input
+//!
+//! The parsers ([`Sps`], [`SpsExtended`], [`Pps`], [`SliceHeader`], [`RecoveryPoint`] and
+//! [`AVCDecoderConfigurationRecord`]) are meant to be used on untrusted input.
+//! They never panic and return an [`std::io::ErrorKind::InvalidData`] error instead, or
+//! for [`Sps`] an [`H264ParseError`] naming the syntax element and bit offset where parsing failed:
+//!
+//! - Exp-Golomb values are checked against the range allowed by the spec before they are
+//!   cast to smaller types or used in arithmetic.
+//! - Every loop reads from the input in each iteration or has a fixed upper bound, and
+//!   allocations are bounded by the size of the input or by such a bound.
+//!
+//! This is checked by the fuzz targets in the `fuzz` directory, new parsers should get one as well.
+//!
 //! ## Status
 //!
 //! This crate is currently under development and is not yet stable.
@@ -102,13 +116,27 @@
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
+mod compat;
 mod config;
 mod enums;
+mod error;
 mod io;
+mod nal_unit;
+mod pps;
+mod priority;
+mod sei;
+mod slice;
 mod sps;
 
+pub use compat::{DeviceCapabilities, H264Profile, LEVEL_1B, ReencodeReason, TranscodeAction};
 pub use enums::*;
+pub use error::{H264ParseError, H264ParseErrorKind};
 pub use io::EmulationPreventionIo;
+pub use nal_unit::{NalIterator, NalUnit, write_annexb, write_avcc};
+pub use pps::Pps;
+pub use priority::NalPriority;
+pub use sei::RecoveryPoint;
+pub use slice::*;
 pub use sps::*;
 
 pub use self::config::{AVCDecoderConfigurationRecord, AvccExtendedConfig};
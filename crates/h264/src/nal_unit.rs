@@ -0,0 +1,470 @@
+use std::io;
+
+use bytes::{Buf, Bytes};
+
+use crate::NALUnitType;
+
+/// The start code written before every NAL unit by [`write_annexb`].
+const START_CODE: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
+
+/// Writes NAL units as an Annex B byte stream (ISO/IEC 14496-10 - B.1).
+///
+/// Every NAL unit is prefixed with the 4 byte start code `00 00 00 01`, which is
+/// valid for every NAL unit type and required for the first NAL unit of an access unit,
+/// parameter sets included.
+///
+/// The NAL units are expected to be without emulation prevention bytes (header followed by the RBSP),
+/// they are inserted while writing.
+pub fn write_annexb<'a>(mut writer: impl io::Write, nal_units: impl IntoIterator<Item = &'a [u8]>) -> io::Result<()> {
+    for nal_unit in nal_units {
+        if nal_unit.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "NAL unit cannot be empty"));
+        }
+
+        writer.write_all(&START_CODE)?;
+        escape(nal_unit, |bytes| writer.write_all(bytes))?;
+    }
+
+    Ok(())
+}
+
+/// Writes NAL units in the length prefixed format used by AVCC (ISO/IEC 14496-15 - 5.3.2).
+///
+/// `length_size` is the size of the length prefix in bytes, which is
+/// [`AVCDecoderConfigurationRecord::length_size_minus_one`](crate::AVCDecoderConfigurationRecord::length_size_minus_one) + 1.
+///
+/// The NAL units are expected to be without emulation prevention bytes (header followed by the RBSP),
+/// they are inserted while writing and are included in the length.
+pub fn write_avcc<'a>(
+    mut writer: impl io::Write,
+    nal_units: impl IntoIterator<Item = &'a [u8]>,
+    length_size: u8,
+) -> io::Result<()> {
+    if !(1..=4).contains(&length_size) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "length size must be between 1 and 4",
+        ));
+    }
+
+    for nal_unit in nal_units {
+        if nal_unit.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "NAL unit cannot be empty"));
+        }
+
+        let mut len = 0u64;
+        escape(nal_unit, |bytes| {
+            len += bytes.len() as u64;
+            Ok(())
+        })?;
+
+        if len >> (length_size as u32 * 8) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "NAL unit is too large for the length size",
+            ));
+        }
+
+        writer.write_all(&len.to_be_bytes()[8 - length_size as usize..])?;
+        escape(nal_unit, |bytes| writer.write_all(bytes))?;
+    }
+
+    Ok(())
+}
+
+/// Splits a NAL unit into runs of bytes with emulation prevention bytes in between (ISO/IEC 14496-10 - 7.4.1).
+///
+/// A `0x03` is inserted after every two zero bytes that are followed by a byte `<= 0x03`,
+/// and after a trailing zero byte, so the NAL unit can not be mistaken for a start code.
+fn escape(nal_unit: &[u8], mut write: impl FnMut(&[u8]) -> io::Result<()>) -> io::Result<()> {
+    let mut zero_count = 0;
+    let mut start = 0;
+
+    for (idx, &byte) in nal_unit.iter().enumerate() {
+        if zero_count >= 2 && byte <= 0x03 {
+            write(&nal_unit[start..idx])?;
+            write(&[0x03])?;
+            start = idx;
+            zero_count = 0;
+        }
+
+        if byte == 0x00 {
+            zero_count += 1;
+        } else {
+            zero_count = 0;
+        }
+    }
+
+    write(&nal_unit[start..])?;
+
+    // 7.4.1: "When the last byte of the RBSP data is equal to 0x00 [...], a final byte equal to 0x03 is appended"
+    if nal_unit.last() == Some(&0x00) {
+        write(&[0x03])?;
+    }
+
+    Ok(())
+}
+
+/// A NAL unit read by [`NalIterator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NalUnit {
+    /// The `nal_ref_idc` is comprised of 2 bits.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.1
+    pub nal_ref_idc: u8,
+
+    /// The `nal_unit_type` is comprised of 5 bits. See the NALUnitType nutype enum for more info.
+    pub nal_unit_type: NALUnitType,
+
+    /// The NAL unit as it is in the stream, header included and with emulation prevention bytes.
+    pub data: Bytes,
+}
+
+impl NalUnit {
+    fn new(data: Bytes) -> Self {
+        let header = data[0];
+
+        Self {
+            nal_ref_idc: (header >> 5) & 0b11,
+            nal_unit_type: NALUnitType(header & 0b0001_1111),
+            data,
+        }
+    }
+
+    /// Returns the NAL unit without emulation prevention bytes (header followed by the RBSP),
+    /// which is what the parsers in this crate take without an [`EmulationPreventionIo`](crate::EmulationPreventionIo).
+    ///
+    /// The emulation prevention bytes are removed when this is called, the data is only copied if it contains any.
+    pub fn rbsp(&self) -> Bytes {
+        let Some(first) = find_emulation_prevention(&self.data, 0) else {
+            return self.data.clone();
+        };
+
+        let mut rbsp = Vec::with_capacity(self.data.len());
+        let mut start = 0;
+        let mut next = Some(first);
+        while let Some(idx) = next {
+            rbsp.extend_from_slice(&self.data[start..idx]);
+            start = idx + 1;
+            next = find_emulation_prevention(&self.data, start);
+        }
+        rbsp.extend_from_slice(&self.data[start..]);
+
+        rbsp.into()
+    }
+}
+
+/// Returns the index of the next emulation prevention byte (`0x03` after two zero bytes) at or after `from`.
+///
+/// The zero bytes are searched from `from` on, so a zero byte before an emulation prevention byte is not counted twice.
+fn find_emulation_prevention(data: &[u8], from: usize) -> Option<usize> {
+    let mut zero_count = 0;
+    for (idx, &byte) in data.iter().enumerate().skip(from) {
+        if zero_count >= 2 && byte == 0x03 {
+            return Some(idx);
+        }
+
+        if byte == 0x00 {
+            zero_count += 1;
+        } else {
+            zero_count = 0;
+        }
+    }
+
+    None
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NalFraming {
+    AnnexB,
+    Avcc { length_size: u8 },
+}
+
+/// Iterates over the NAL units of an Annex B byte stream or of length prefixed (AVCC) data.
+///
+/// The NAL units are slices of the input, see [`NalUnit::rbsp`] to remove the emulation prevention bytes.
+/// After an error the iterator yields no more NAL units.
+#[derive(Debug, Clone)]
+pub struct NalIterator {
+    data: Bytes,
+    framing: NalFraming,
+}
+
+impl NalIterator {
+    /// Iterates over an Annex B byte stream (ISO/IEC 14496-10 - B.1).
+    ///
+    /// NAL units may be prefixed with 3 or 4 byte start codes. Trailing zero bytes, which are part of
+    /// the next start code or `trailing_zero_8bits`, are not included in the NAL units,
+    /// bytes before the first start code and empty NAL units are skipped.
+    pub fn annexb(data: impl Into<Bytes>) -> Self {
+        let mut data = data.into();
+        // Skip to the first NAL unit, so every NAL unit after that starts right after a start code.
+        match find_start_code(&data) {
+            Some((_, end)) => data.advance(end),
+            None => data.clear(),
+        }
+
+        Self {
+            data,
+            framing: NalFraming::AnnexB,
+        }
+    }
+
+    /// Iterates over length prefixed NAL units (ISO/IEC 14496-15 - 5.3.2), as found in FLV and MP4 samples.
+    ///
+    /// `length_size` is the size of the length prefix in bytes, which is
+    /// [`AVCDecoderConfigurationRecord::length_size_minus_one`](crate::AVCDecoderConfigurationRecord::length_size_minus_one) + 1.
+    /// NAL units with a length of 0 are skipped.
+    pub fn avcc(data: impl Into<Bytes>, length_size: u8) -> io::Result<Self> {
+        if !(1..=4).contains(&length_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "length size must be between 1 and 4",
+            ));
+        }
+
+        Ok(Self {
+            data: data.into(),
+            framing: NalFraming::Avcc { length_size },
+        })
+    }
+
+    fn next_annexb(&mut self) -> Option<NalUnit> {
+        while !self.data.is_empty() {
+            let nal_unit = match find_start_code(&self.data) {
+                Some((start, end)) => {
+                    let nal_unit = self.data.split_to(start);
+                    self.data.advance(end - start);
+                    nal_unit
+                }
+                None => self.data.split_off(0),
+            };
+
+            let len = nal_unit.iter().rposition(|&byte| byte != 0x00).map_or(0, |idx| idx + 1);
+            if len != 0 {
+                return Some(NalUnit::new(nal_unit.slice(..len)));
+            }
+        }
+
+        None
+    }
+
+    fn next_avcc(&mut self, length_size: u8) -> Option<io::Result<NalUnit>> {
+        while !self.data.is_empty() {
+            if self.data.len() < length_size as usize {
+                self.data.clear();
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "NAL unit length is truncated",
+                )));
+            }
+
+            let len = self.data.get_uint(length_size as usize) as usize;
+            if len > self.data.len() {
+                self.data.clear();
+                return Some(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "NAL unit is truncated")));
+            }
+
+            if len != 0 {
+                return Some(Ok(NalUnit::new(self.data.split_to(len))));
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for NalIterator {
+    type Item = io::Result<NalUnit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.framing {
+            NalFraming::AnnexB => self.next_annexb().map(Ok),
+            NalFraming::Avcc { length_size } => self.next_avcc(length_size),
+        }
+    }
+}
+
+/// Returns the range of the first 3 byte start code (`00 00 01`) in `data`.
+///
+/// A zero byte before it is left to the data before the start code, so 4 byte start codes are found as well.
+fn find_start_code(data: &[u8]) -> Option<(usize, usize)> {
+    data.windows(3)
+        .position(|window| window == [0x00, 0x00, 0x01])
+        .map(|idx| (idx, idx + 3))
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::{self, Read};
+
+    use bytes::Bytes;
+
+    use crate::{EmulationPreventionIo, NALUnitType, NalIterator, write_annexb, write_avcc};
+
+    #[test]
+    fn test_write_annexb() {
+        let mut buf = Vec::new();
+        write_annexb(&mut buf, [&[0x67, 0x42, 0x00, 0x00, 0x01][..], &[0x68, 0xce]]).unwrap();
+
+        assert_eq!(
+            buf,
+            vec![
+                0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0x00, 0x00, 0x03, 0x01, // sps
+                0x00, 0x00, 0x00, 0x01, 0x68, 0xce, // pps
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_annexb_trailing_zero() {
+        let mut buf = Vec::new();
+        write_annexb(&mut buf, [&[0x65, 0x88, 0x00, 0x00][..]]).unwrap();
+
+        assert_eq!(buf, vec![0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x00, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn test_write_annexb_empty() {
+        let err = write_annexb(Vec::new(), [&[][..]]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_write_avcc() {
+        let mut buf = Vec::new();
+        write_avcc(&mut buf, [&[0x65, 0x00, 0x00, 0x00, 0x00, 0x02][..], &[0x41, 0x9a]], 4).unwrap();
+
+        assert_eq!(
+            buf,
+            vec![
+                0x00, 0x00, 0x00, 0x08, 0x65, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x02, // idr
+                0x00, 0x00, 0x00, 0x02, 0x41, 0x9a, // non idr
+            ]
+        );
+
+        let mut buf = Vec::new();
+        write_avcc(&mut buf, [&[0x41, 0x9a][..]], 2).unwrap();
+        assert_eq!(buf, vec![0x00, 0x02, 0x41, 0x9a]);
+    }
+
+    #[test]
+    fn test_write_avcc_length_size() {
+        for length_size in [0, 5] {
+            let err = write_avcc(Vec::new(), [&[0x41][..]], length_size).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+
+        // 256 bytes do not fit into a 1 byte length.
+        let nal_unit = vec![0x41; 256];
+        let err = write_avcc(Vec::new(), [nal_unit.as_slice()], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut buf = Vec::new();
+        write_avcc(&mut buf, [&nal_unit[..255]], 1).unwrap();
+        assert_eq!(buf.len(), 256);
+        assert_eq!(buf[0], 0xff);
+    }
+
+    #[test]
+    fn test_write_avcc_roundtrip() {
+        let nal_unit = [0x06, 0x05, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x03, 0xff];
+
+        let mut buf = Vec::new();
+        write_avcc(&mut buf, [&nal_unit[..]], 4).unwrap();
+
+        let len = u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize;
+        assert_eq!(len, buf.len() - 4);
+
+        let mut decoded = Vec::new();
+        EmulationPreventionIo::new(&buf[4..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, nal_unit);
+    }
+
+    #[test]
+    fn test_nal_iterator_annexb() {
+        let data = Bytes::from_static(&[
+            0xff, 0x00, // garbage before the first start code
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0x00, 0x00, 0x03, 0x01, // sps, 4 byte start code
+            0x00, 0x00, 0x01, 0x68, 0xce, 0x00, // pps, 3 byte start code and trailing_zero_8bits
+            0x00, 0x00, 0x01, // empty
+            0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x00, 0x03, // idr
+        ]);
+
+        let nal_units = NalIterator::annexb(data.clone()).collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(nal_units.len(), 3);
+
+        assert_eq!(nal_units[0].nal_unit_type, NALUnitType::SPS);
+        assert_eq!(nal_units[0].nal_ref_idc, 3);
+        assert_eq!(nal_units[0].data.as_ref(), &[0x67, 0x42, 0x00, 0x00, 0x03, 0x01]);
+        assert_eq!(nal_units[0].rbsp().as_ref(), &[0x67, 0x42, 0x00, 0x00, 0x01]);
+
+        assert_eq!(nal_units[1].nal_unit_type, NALUnitType::PPS);
+        assert_eq!(nal_units[1].data.as_ref(), &[0x68, 0xce]);
+
+        assert_eq!(nal_units[2].nal_unit_type, NALUnitType::IDRSliceLayerWithoutPartitioning);
+        assert_eq!(nal_units[2].data.as_ref(), &[0x65, 0x88, 0x00, 0x03]);
+        // a single zero byte before 0x03 is not an emulation prevention byte
+        assert_eq!(nal_units[2].rbsp(), nal_units[2].data);
+
+        // the nal units are slices of the input
+        assert_eq!(nal_units[1].data.as_ptr(), data[15..].as_ptr());
+
+        assert_eq!(NalIterator::annexb(Bytes::from_static(&[0x00, 0x00, 0x00])).count(), 0);
+        assert_eq!(NalIterator::annexb(Bytes::new()).count(), 0);
+    }
+
+    #[test]
+    fn test_nal_iterator_avcc() {
+        let data = Bytes::from_static(&[
+            0x00, 0x00, 0x00, 0x08, 0x65, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x02, // idr
+            0x00, 0x00, 0x00, 0x00, // empty
+            0x00, 0x00, 0x00, 0x02, 0x41, 0x9a, // non idr
+        ]);
+
+        let nal_units = NalIterator::avcc(data, 4).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(nal_units.len(), 2);
+        assert_eq!(nal_units[0].nal_unit_type, NALUnitType::IDRSliceLayerWithoutPartitioning);
+        assert_eq!(nal_units[0].rbsp().as_ref(), &[0x65, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(nal_units[1].nal_unit_type, NALUnitType::NonIDRSliceLayerWithoutPartitioning);
+        assert_eq!(nal_units[1].nal_ref_idc, 2);
+        assert_eq!(nal_units[1].data.as_ref(), &[0x41, 0x9a]);
+
+        let err = NalIterator::avcc(Bytes::new(), 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_nal_iterator_avcc_truncated() {
+        for data in [
+            &[0x00, 0x02, 0x41, 0x9a, 0x00, 0x03, 0x41][..],
+            &[0x00, 0x02, 0x41, 0x9a, 0x00],
+        ] {
+            let mut iter = NalIterator::avcc(Bytes::copy_from_slice(data), 2).unwrap();
+
+            assert_eq!(iter.next().unwrap().unwrap().data.as_ref(), &[0x41, 0x9a]);
+            let err = iter.next().unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            assert!(iter.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_nal_iterator_roundtrip() {
+        let nal_units = [
+            &[0x06, 0x05, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x03, 0xff][..],
+            &[0x65, 0x88, 0x00, 0x00],
+        ];
+
+        let mut annexb = Vec::new();
+        write_annexb(&mut annexb, nal_units).unwrap();
+        let mut avcc = Vec::new();
+        write_avcc(&mut avcc, nal_units, 4).unwrap();
+
+        for iter in [NalIterator::annexb(annexb), NalIterator::avcc(avcc, 4).unwrap()] {
+            let decoded = iter.map(|nal_unit| nal_unit.unwrap().rbsp()).collect::<Vec<_>>();
+            assert_eq!(decoded, nal_units);
+        }
+    }
+}
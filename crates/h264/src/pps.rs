@@ -0,0 +1,353 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::BitReaderExpGolombExt;
+
+use crate::io::read_exp_golomb_max;
+use crate::{EmulationPreventionIo, NALUnitType};
+
+/// The Picture Parameter Set.
+/// ISO/IEC-14496-10-2022 - 7.3.2.2
+///
+/// Only the fields up to and including `redundant_pic_cnt_present_flag` are parsed,
+/// which are all the fields required to parse a slice header.
+/// The optional trailing fields (`transform_8x8_mode_flag`, the picture scaling matrix
+/// and `second_chroma_qp_index_offset`) are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pps {
+    /// The `nal_ref_idc` is comprised of 2 bits.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.1
+    pub nal_ref_idc: u8,
+
+    /// The `nal_unit_type` is comprised of 5 bits. Always [`NALUnitType::PPS`].
+    pub nal_unit_type: NALUnitType,
+
+    /// The `pic_parameter_set_id` identifies the PPS that is referred to in the slice header.
+    ///
+    /// The value of this ranges from \[0, 255\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub pic_parameter_set_id: u16,
+
+    /// The `seq_parameter_set_id` refers to the active SPS.
+    ///
+    /// The value of this ranges from \[0, 31\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub seq_parameter_set_id: u16,
+
+    /// The `entropy_coding_mode_flag` is a single bit.
+    ///
+    /// 0 means CAVLC is used, 1 means CABAC is used.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub entropy_coding_mode_flag: bool,
+
+    /// The `bottom_field_pic_order_in_frame_present_flag` is a single bit.
+    ///
+    /// 1 means the `delta_pic_order_cnt_bottom` / `delta_pic_order_cnt[1]` syntax elements
+    /// are present in the slice headers of coded frames.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub bottom_field_pic_order_in_frame_present_flag: bool,
+
+    /// The `num_slice_groups_minus1` plus 1 is the number of slice groups for a picture.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub num_slice_groups_minus1: u32,
+
+    /// The `slice_group_map_type`, only present if `num_slice_groups_minus1 > 0`.
+    ///
+    /// The value of this ranges from \[0, 6\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub slice_group_map_type: Option<u8>,
+
+    /// The `slice_group_change_rate_minus1`, only present if `slice_group_map_type` is 3, 4 or 5.
+    ///
+    /// Used to derive `SliceGroupChangeRate = slice_group_change_rate_minus1 + 1`.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub slice_group_change_rate_minus1: Option<u32>,
+
+    /// The `num_ref_idx_l0_default_active_minus1` specifies the inferred value of
+    /// `num_ref_idx_l0_active_minus1` for slices that do not override it.
+    ///
+    /// The value of this ranges from \[0, 31\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub num_ref_idx_l0_default_active_minus1: u8,
+
+    /// The `num_ref_idx_l1_default_active_minus1` specifies the inferred value of
+    /// `num_ref_idx_l1_active_minus1` for slices that do not override it.
+    ///
+    /// The value of this ranges from \[0, 31\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub num_ref_idx_l1_default_active_minus1: u8,
+
+    /// The `weighted_pred_flag` is a single bit.
+    ///
+    /// 1 means explicit weighted prediction is applied to P and SP slices.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub weighted_pred_flag: bool,
+
+    /// The `weighted_bipred_idc` is comprised of 2 bits.
+    ///
+    /// 0 means default, 1 means explicit and 2 means implicit weighted prediction for B slices.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub weighted_bipred_idc: u8,
+
+    /// The `pic_init_qp_minus26` is the initial value minus 26 of `SliceQPY` for each slice.
+    ///
+    /// The value of this ranges from \[-(26 + QpBdOffsetY), 25\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub pic_init_qp_minus26: i64,
+
+    /// The `pic_init_qs_minus26` is the initial value minus 26 of `SliceQSY` for SP and SI slices.
+    ///
+    /// The value of this ranges from \[-26, 25\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub pic_init_qs_minus26: i64,
+
+    /// The `chroma_qp_index_offset` is the offset added to `QPY` and `QSY` for the Cb chroma component.
+    ///
+    /// The value of this ranges from \[-12, 12\].
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub chroma_qp_index_offset: i64,
+
+    /// The `deblocking_filter_control_present_flag` is a single bit.
+    ///
+    /// 1 means the deblocking filter syntax elements are present in the slice headers.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub deblocking_filter_control_present_flag: bool,
+
+    /// The `constrained_intra_pred_flag` is a single bit.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub constrained_intra_pred_flag: bool,
+
+    /// The `redundant_pic_cnt_present_flag` is a single bit.
+    ///
+    /// 1 means the `redundant_pic_cnt` syntax element is present in the slice headers.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.2.2
+    pub redundant_pic_cnt_present_flag: bool,
+}
+
+impl Pps {
+    /// Parses a Pps from the input bytes.
+    ///
+    /// Returns a `Pps` struct.
+    pub fn parse(reader: impl io::Read) -> io::Result<Self> {
+        let mut bit_reader = BitReader::new(reader);
+
+        let forbidden_zero_bit = bit_reader.read_bit()?;
+        if forbidden_zero_bit {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Forbidden zero bit is set"));
+        }
+
+        let nal_ref_idc = bit_reader.read_bits(2)? as u8;
+        let nal_unit_type = bit_reader.read_bits(5)? as u8;
+        if NALUnitType(nal_unit_type) != NALUnitType::PPS {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "NAL unit type is not PPS"));
+        }
+
+        let pic_parameter_set_id = read_exp_golomb_max(&mut bit_reader, 255, "pic_parameter_set_id")? as u16;
+        let seq_parameter_set_id = read_exp_golomb_max(&mut bit_reader, 31, "seq_parameter_set_id")? as u16;
+        let entropy_coding_mode_flag = bit_reader.read_bit()?;
+        let bottom_field_pic_order_in_frame_present_flag = bit_reader.read_bit()?;
+
+        let num_slice_groups_minus1 = read_exp_golomb_max(&mut bit_reader, 7, "num_slice_groups_minus1")? as u32;
+        let mut slice_group_map_type = None;
+        let mut slice_group_change_rate_minus1 = None;
+
+        if num_slice_groups_minus1 > 0 {
+            let map_type = read_exp_golomb_max(&mut bit_reader, 6, "slice_group_map_type")? as u8;
+            match map_type {
+                0 => {
+                    for _ in 0..=num_slice_groups_minus1 {
+                        // run_length_minus1
+                        bit_reader.read_exp_golomb()?;
+                    }
+                }
+                2 => {
+                    for _ in 0..num_slice_groups_minus1 {
+                        // top_left
+                        bit_reader.read_exp_golomb()?;
+                        // bottom_right
+                        bit_reader.read_exp_golomb()?;
+                    }
+                }
+                3..=5 => {
+                    // slice_group_change_direction_flag
+                    bit_reader.read_bit()?;
+                    slice_group_change_rate_minus1 =
+                        Some(
+                            read_exp_golomb_max(&mut bit_reader, u32::MAX as u64, "slice_group_change_rate_minus1")? as u32,
+                        );
+                }
+                6 => {
+                    let pic_size_in_map_units_minus1 = bit_reader.read_exp_golomb()?;
+                    let bits = (num_slice_groups_minus1 + 1).next_power_of_two().trailing_zeros() as u8;
+                    for _ in 0..=pic_size_in_map_units_minus1 {
+                        // slice_group_id
+                        bit_reader.read_bits(bits)?;
+                    }
+                }
+                1 => {}
+                _ => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid slice_group_map_type"));
+                }
+            }
+
+            slice_group_map_type = Some(map_type);
+        }
+
+        let num_ref_idx_l0_default_active_minus1 =
+            read_exp_golomb_max(&mut bit_reader, 31, "num_ref_idx_l0_default_active_minus1")? as u8;
+        let num_ref_idx_l1_default_active_minus1 =
+            read_exp_golomb_max(&mut bit_reader, 31, "num_ref_idx_l1_default_active_minus1")? as u8;
+        let weighted_pred_flag = bit_reader.read_bit()?;
+        let weighted_bipred_idc = bit_reader.read_bits(2)? as u8;
+        let pic_init_qp_minus26 = bit_reader.read_signed_exp_golomb()?;
+        let pic_init_qs_minus26 = bit_reader.read_signed_exp_golomb()?;
+        let chroma_qp_index_offset = bit_reader.read_signed_exp_golomb()?;
+        let deblocking_filter_control_present_flag = bit_reader.read_bit()?;
+        let constrained_intra_pred_flag = bit_reader.read_bit()?;
+        let redundant_pic_cnt_present_flag = bit_reader.read_bit()?;
+
+        Ok(Pps {
+            nal_ref_idc,
+            nal_unit_type: NALUnitType(nal_unit_type),
+            pic_parameter_set_id,
+            seq_parameter_set_id,
+            entropy_coding_mode_flag,
+            bottom_field_pic_order_in_frame_present_flag,
+            num_slice_groups_minus1,
+            slice_group_map_type,
+            slice_group_change_rate_minus1,
+            num_ref_idx_l0_default_active_minus1,
+            num_ref_idx_l1_default_active_minus1,
+            weighted_pred_flag,
+            weighted_bipred_idc,
+            pic_init_qp_minus26,
+            pic_init_qs_minus26,
+            chroma_qp_index_offset,
+            deblocking_filter_control_present_flag,
+            constrained_intra_pred_flag,
+            redundant_pic_cnt_present_flag,
+        })
+    }
+
+    /// Parses the Pps struct from a reader that may contain emulation prevention bytes.
+    /// Is the same as calling [`Self::parse`] with an [`EmulationPreventionIo`] wrapper.
+    pub fn parse_with_emulation_prevention(reader: impl io::Read) -> io::Result<Self> {
+        Self::parse(EmulationPreventionIo::new(reader))
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io;
+
+    use scuffle_bytes_util::BitWriter;
+    use scuffle_expgolomb::BitWriterExpGolombExt;
+
+    use crate::Pps;
+
+    #[test]
+    fn test_parse_pps_invalid_nal() {
+        let mut pps = Vec::new();
+        let mut writer = BitWriter::new(&mut pps);
+
+        writer.write_bit(false).unwrap();
+        writer.write_bits(0b11, 2).unwrap();
+        writer.write_bits(7, 5).unwrap();
+        writer.finish().unwrap();
+
+        let err = Pps::parse(io::Cursor::new(pps)).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "NAL unit type is not PPS");
+    }
+
+    #[test]
+    fn test_parse_pps() {
+        // PPS produced by x264 (high profile, CABAC, 8x8 transform)
+        let data = [0x68, 0xeb, 0xe3, 0xcb, 0x22, 0xc0];
+
+        let pps = Pps::parse(io::Cursor::new(data)).unwrap();
+
+        insta::assert_debug_snapshot!(pps, @r"
+        Pps {
+            nal_ref_idc: 3,
+            nal_unit_type: NALUnitType::PPS,
+            pic_parameter_set_id: 0,
+            seq_parameter_set_id: 0,
+            entropy_coding_mode_flag: true,
+            bottom_field_pic_order_in_frame_present_flag: false,
+            num_slice_groups_minus1: 0,
+            slice_group_map_type: None,
+            slice_group_change_rate_minus1: None,
+            num_ref_idx_l0_default_active_minus1: 2,
+            num_ref_idx_l1_default_active_minus1: 0,
+            weighted_pred_flag: true,
+            weighted_bipred_idc: 2,
+            pic_init_qp_minus26: -3,
+            pic_init_qs_minus26: 0,
+            chroma_qp_index_offset: -2,
+            deblocking_filter_control_present_flag: true,
+            constrained_intra_pred_flag: false,
+            redundant_pic_cnt_present_flag: false,
+        }
+        ");
+    }
+
+    #[test]
+    fn test_parse_pps_slice_groups() {
+        let mut pps = Vec::new();
+        let mut writer = BitWriter::new(&mut pps);
+
+        writer.write_bit(false).unwrap();
+        writer.write_bits(3, 2).unwrap();
+        writer.write_bits(8, 5).unwrap();
+
+        writer.write_exp_golomb(1).unwrap(); // pic_parameter_set_id
+        writer.write_exp_golomb(0).unwrap(); // seq_parameter_set_id
+        writer.write_bit(false).unwrap(); // entropy_coding_mode_flag
+        writer.write_bit(true).unwrap(); // bottom_field_pic_order_in_frame_present_flag
+        writer.write_exp_golomb(1).unwrap(); // num_slice_groups_minus1
+        writer.write_exp_golomb(4).unwrap(); // slice_group_map_type
+        writer.write_bit(false).unwrap(); // slice_group_change_direction_flag
+        writer.write_exp_golomb(9).unwrap(); // slice_group_change_rate_minus1
+        writer.write_exp_golomb(0).unwrap(); // num_ref_idx_l0_default_active_minus1
+        writer.write_exp_golomb(0).unwrap(); // num_ref_idx_l1_default_active_minus1
+        writer.write_bit(false).unwrap(); // weighted_pred_flag
+        writer.write_bits(0, 2).unwrap(); // weighted_bipred_idc
+        writer.write_signed_exp_golomb(0).unwrap(); // pic_init_qp_minus26
+        writer.write_signed_exp_golomb(0).unwrap(); // pic_init_qs_minus26
+        writer.write_signed_exp_golomb(0).unwrap(); // chroma_qp_index_offset
+        writer.write_bit(false).unwrap(); // deblocking_filter_control_present_flag
+        writer.write_bit(false).unwrap(); // constrained_intra_pred_flag
+        writer.write_bit(true).unwrap(); // redundant_pic_cnt_present_flag
+        writer.finish().unwrap();
+
+        let pps = Pps::parse(io::Cursor::new(pps)).unwrap();
+
+        assert_eq!(pps.pic_parameter_set_id, 1);
+        assert!(pps.bottom_field_pic_order_in_frame_present_flag);
+        assert_eq!(pps.num_slice_groups_minus1, 1);
+        assert_eq!(pps.slice_group_map_type, Some(4));
+        assert_eq!(pps.slice_group_change_rate_minus1, Some(9));
+        assert!(pps.redundant_pic_cnt_present_flag);
+    }
+}
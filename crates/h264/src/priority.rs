@@ -0,0 +1,153 @@
+use crate::NALUnitType;
+
+/// How important a NAL unit is for decoding the rest of the stream.
+///
+/// Priorities are ordered from least to most important, so when a relay has to shed
+/// load under congestion it can drop everything below a threshold.
+///
+/// The classification is based on the NAL unit header only:
+/// - `nal_ref_idc` equal to 0 means the NAL unit is not used to decode any other picture
+///   (ISO/IEC 14496-10-2022 - 7.4.1), so non reference slices can be dropped without
+///   affecting other frames.
+/// - SEI messages and filler data never affect the decoding process.
+/// - Parameter sets and IDR slices are required for all following pictures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NalPriority {
+    /// Filler data, access unit delimiters and unspecified / reserved NAL units.
+    ///
+    /// Decoders ignore these, they can always be dropped.
+    Disposable,
+    /// Supplemental enhancement information (SEI).
+    ///
+    /// Not needed for decoding, but may carry information such as captions or HDR metadata.
+    Supplemental,
+    /// A slice with `nal_ref_idc` equal to 0.
+    ///
+    /// No other picture references it, so dropping it only loses this picture.
+    NonReference,
+    /// A non IDR slice with `nal_ref_idc` not equal to 0.
+    ///
+    /// Dropping it corrupts every picture that references it until the next IDR.
+    Reference,
+    /// IDR slices, parameter sets and end of sequence / stream markers.
+    ///
+    /// Must never be dropped.
+    Critical,
+}
+
+impl NalPriority {
+    /// Classifies a NAL unit from its first header byte.
+    pub const fn from_header(header: u8) -> Self {
+        let nal_ref_idc = (header >> 5) & 0b11;
+        let nal_unit_type = NALUnitType(header & 0b0001_1111);
+
+        match nal_unit_type {
+            NALUnitType::IDRSliceLayerWithoutPartitioning
+            | NALUnitType::SPS
+            | NALUnitType::PPS
+            | NALUnitType::SPSExtension
+            | NALUnitType::SubsetSPS
+            | NALUnitType::DepthParameterSet
+            | NALUnitType::EndOfSeq
+            | NALUnitType::EndOfStream => Self::Critical,
+            NALUnitType::NonIDRSliceLayerWithoutPartitioning
+            | NALUnitType::SliceDataPartitionALayer
+            | NALUnitType::SliceDataPartitionBLayer
+            | NALUnitType::SliceDataPartitionCLayer
+            | NALUnitType::AuxCodedPictureSliceLayerWithoutPartitioning
+            | NALUnitType::PrefixNalUnit
+            | NALUnitType::SliceLayerExtension
+            | NALUnitType::SliceLayerExtension2 => {
+                if nal_ref_idc == 0 {
+                    Self::NonReference
+                } else {
+                    Self::Reference
+                }
+            }
+            NALUnitType::SEI => Self::Supplemental,
+            _ => Self::Disposable,
+        }
+    }
+
+    /// Classifies a NAL unit, without its start code or length prefix.
+    ///
+    /// Returns `None` if the NAL unit is empty.
+    pub fn from_nal_unit(nal_unit: &[u8]) -> Option<Self> {
+        nal_unit.first().map(|&header| Self::from_header(header))
+    }
+
+    /// Returns the priority of an access unit, which is the highest priority of any of its NAL units.
+    ///
+    /// An access unit is only safe to drop as a whole if the result [is droppable](Self::is_droppable).
+    /// Returns `None` if the access unit contains no NAL units.
+    pub fn from_access_unit<'a>(nal_units: impl IntoIterator<Item = &'a [u8]>) -> Option<Self> {
+        nal_units.into_iter().filter_map(Self::from_nal_unit).max()
+    }
+
+    /// Returns `true` if dropping a NAL unit with this priority does not affect
+    /// the decoding of any other picture.
+    pub const fn is_droppable(self) -> bool {
+        matches!(self, Self::Disposable | Self::Supplemental | Self::NonReference)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::NalPriority;
+
+    #[test]
+    fn test_from_header() {
+        // nal_ref_idc = 3, IDR slice
+        assert_eq!(NalPriority::from_header(0x65), NalPriority::Critical);
+        // nal_ref_idc = 3, SPS / PPS
+        assert_eq!(NalPriority::from_header(0x67), NalPriority::Critical);
+        assert_eq!(NalPriority::from_header(0x68), NalPriority::Critical);
+        // nal_ref_idc = 2, non IDR slice
+        assert_eq!(NalPriority::from_header(0x41), NalPriority::Reference);
+        // nal_ref_idc = 0, non IDR slice
+        assert_eq!(NalPriority::from_header(0x01), NalPriority::NonReference);
+        // nal_ref_idc = 0, slice data partition B
+        assert_eq!(NalPriority::from_header(0x03), NalPriority::NonReference);
+        // SEI
+        assert_eq!(NalPriority::from_header(0x06), NalPriority::Supplemental);
+        // AUD, filler data, unspecified
+        assert_eq!(NalPriority::from_header(0x09), NalPriority::Disposable);
+        assert_eq!(NalPriority::from_header(0x0C), NalPriority::Disposable);
+        assert_eq!(NalPriority::from_header(0x18), NalPriority::Disposable);
+        // end of sequence
+        assert_eq!(NalPriority::from_header(0x0A), NalPriority::Critical);
+    }
+
+    #[test]
+    fn test_droppable() {
+        assert!(NalPriority::Disposable.is_droppable());
+        assert!(NalPriority::Supplemental.is_droppable());
+        assert!(NalPriority::NonReference.is_droppable());
+        assert!(!NalPriority::Reference.is_droppable());
+        assert!(!NalPriority::Critical.is_droppable());
+
+        assert!(NalPriority::Disposable < NalPriority::Supplemental);
+        assert!(NalPriority::NonReference < NalPriority::Reference);
+        assert!(NalPriority::Reference < NalPriority::Critical);
+    }
+
+    #[test]
+    fn test_from_access_unit() {
+        assert_eq!(NalPriority::from_nal_unit(&[]), None);
+        assert_eq!(NalPriority::from_access_unit([]), None);
+
+        // AUD, SEI, non reference slice
+        let b_frame: [&[u8]; 3] = [&[0x09, 0x10], &[0x06, 0x05], &[0x01, 0x9e]];
+        assert_eq!(NalPriority::from_access_unit(b_frame), Some(NalPriority::NonReference));
+        assert!(NalPriority::from_access_unit(b_frame).unwrap().is_droppable());
+
+        // AUD, reference slice
+        let p_frame: [&[u8]; 2] = [&[0x09, 0x30], &[0x41, 0x9a]];
+        assert_eq!(NalPriority::from_access_unit(p_frame), Some(NalPriority::Reference));
+
+        // SPS, PPS, IDR slice
+        let idr: [&[u8]; 3] = [&[0x67, 0x64], &[0x68, 0xeb], &[0x65, 0x88]];
+        assert_eq!(NalPriority::from_access_unit(idr), Some(NalPriority::Critical));
+    }
+}
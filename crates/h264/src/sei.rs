@@ -0,0 +1,177 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+
+use crate::io::read_exp_golomb_max;
+use crate::{EmulationPreventionIo, NALUnitType};
+
+/// The `payloadType` of the recovery point SEI message.
+///
+/// ISO/IEC-14496-10-2022 - 7.4.2.3.1
+const RECOVERY_POINT_PAYLOAD_TYPE: u64 = 6;
+
+/// The recovery point SEI message.
+/// ISO/IEC-14496-10-2022 - D.1.8
+///
+/// Marks the picture it is sent with as a point where decoding can start without an IDR picture,
+/// encoders that use open GOPs or periodic intra refresh send it instead of IDR pictures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryPoint {
+    /// The `recovery_frame_cnt` is the number of frames, in output order, until the output
+    /// pictures are correct when decoding starts at this access unit.
+    ///
+    /// ISO/IEC-14496-10-2022 - D.2.8
+    pub recovery_frame_cnt: u32,
+
+    /// The `exact_match_flag`, 1 means the pictures from the recovery point on match the
+    /// pictures that would be decoded when decoding started at the previous IDR picture.
+    pub exact_match_flag: bool,
+
+    /// The `broken_link_flag`, 1 means pictures before the recovery point in output order
+    /// may contain serious artefacts.
+    pub broken_link_flag: bool,
+
+    /// The `changing_slice_group_idc` is comprised of 2 bits.
+    pub changing_slice_group_idc: u8,
+}
+
+impl RecoveryPoint {
+    /// Looks for a recovery point message in a SEI NAL unit.
+    ///
+    /// The reader must start at the NAL unit header. Returns `None` if the SEI NAL unit does not
+    /// contain a recovery point message.
+    pub fn parse_sei(reader: impl io::Read) -> io::Result<Option<Self>> {
+        let mut bit_reader = BitReader::new(reader);
+
+        let forbidden_zero_bit = bit_reader.read_bit()?;
+        if forbidden_zero_bit {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Forbidden zero bit is set"));
+        }
+
+        bit_reader.read_bits(2)?; // nal_ref_idc
+        let nal_unit_type = NALUnitType(bit_reader.read_bits(5)? as u8);
+        if nal_unit_type != NALUnitType::SEI {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "NAL unit type is not SEI"));
+        }
+
+        let mut rbsp = Vec::new();
+        bit_reader.into_inner().read_to_end(&mut rbsp)?;
+
+        // ISO/IEC-14496-10-2022 - 7.3.2.3.1
+        let mut remaining = rbsp.as_slice();
+        while more_rbsp_data(remaining) {
+            let payload_type = read_sei_value(&mut remaining)?;
+            let payload_size = read_sei_value(&mut remaining)?;
+
+            let payload_size = usize::try_from(payload_size).unwrap_or(usize::MAX);
+            if payload_size > remaining.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "SEI payload is truncated"));
+            }
+
+            let (payload, rest) = remaining.split_at(payload_size);
+            if payload_type == RECOVERY_POINT_PAYLOAD_TYPE {
+                return Self::parse(payload).map(Some);
+            }
+
+            remaining = rest;
+        }
+
+        Ok(None)
+    }
+
+    /// Looks for a recovery point message in a SEI NAL unit that may contain emulation prevention bytes.
+    /// Is the same as calling [`Self::parse_sei`] with an [`EmulationPreventionIo`] wrapper.
+    pub fn parse_sei_with_emulation_prevention(reader: impl io::Read) -> io::Result<Option<Self>> {
+        Self::parse_sei(EmulationPreventionIo::new(reader))
+    }
+
+    /// Parses the payload of a recovery point SEI message.
+    pub fn parse(reader: impl io::Read) -> io::Result<Self> {
+        let mut bit_reader = BitReader::new(reader);
+
+        // frame_num is at most 16 bits, so recovery_frame_cnt is below 2^16
+        let recovery_frame_cnt = read_exp_golomb_max(&mut bit_reader, 65535, "recovery_frame_cnt")? as u32;
+        let exact_match_flag = bit_reader.read_bit()?;
+        let broken_link_flag = bit_reader.read_bit()?;
+        let changing_slice_group_idc = bit_reader.read_bits(2)? as u8;
+
+        Ok(Self {
+            recovery_frame_cnt,
+            exact_match_flag,
+            broken_link_flag,
+            changing_slice_group_idc,
+        })
+    }
+}
+
+/// Returns true if `rbsp` contains more than the `rbsp_trailing_bits`.
+fn more_rbsp_data(rbsp: &[u8]) -> bool {
+    !matches!(rbsp, [] | [0x80])
+}
+
+/// Reads a `payloadType` or `payloadSize`, which is coded as a run of `0xFF` bytes followed by the last byte.
+fn read_sei_value(rbsp: &mut &[u8]) -> io::Result<u64> {
+    let mut value = 0u64;
+    loop {
+        let Some((&byte, rest)) = rbsp.split_first() else {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "SEI message is truncated"));
+        };
+
+        *rbsp = rest;
+        value = value.saturating_add(byte as u64);
+        if byte != 0xFF {
+            return Ok(value);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io;
+
+    use crate::RecoveryPoint;
+
+    #[test]
+    fn test_parse_recovery_point() {
+        // user data unregistered (5) with 17 bytes, then recovery point (6) with 1 byte:
+        // recovery_frame_cnt = 0, exact_match_flag = 1, broken_link_flag = 0, changing_slice_group_idc = 0
+        let mut data = vec![0x06, 0x05, 0x11];
+        data.extend_from_slice(&[0xAB; 17]);
+        data.extend_from_slice(&[0x06, 0x01, 0b1100_0000, 0x80]);
+
+        let recovery_point = RecoveryPoint::parse_sei(io::Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(
+            recovery_point,
+            RecoveryPoint {
+                recovery_frame_cnt: 0,
+                exact_match_flag: true,
+                broken_link_flag: false,
+                changing_slice_group_idc: 0,
+            }
+        );
+
+        // recovery_frame_cnt = 1 (010), exact_match_flag = 0, broken_link_flag = 1
+        let data = [0x06, 0x06, 0x01, 0b0100_1000, 0x80];
+        let recovery_point = RecoveryPoint::parse_sei(io::Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(recovery_point.recovery_frame_cnt, 1);
+        assert!(!recovery_point.exact_match_flag);
+        assert!(recovery_point.broken_link_flag);
+    }
+
+    #[test]
+    fn test_parse_recovery_point_missing() {
+        // only a user data unregistered message
+        let mut data = vec![0x06, 0x05, 0x02, 0x00, 0x00, 0x80];
+        assert_eq!(RecoveryPoint::parse_sei(io::Cursor::new(&data)).unwrap(), None);
+
+        // the payload is longer than the NAL unit
+        data[2] = 0x10;
+        let err = RecoveryPoint::parse_sei(io::Cursor::new(&data)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // not a SEI NAL unit
+        let err = RecoveryPoint::parse_sei(io::Cursor::new(&[0x65, 0x80])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,171 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::BitReaderExpGolombExt;
+
+use crate::io::read_exp_golomb_max;
+
+/// A memory management control operation (MMCO).
+/// ISO/IEC-14496-10-2022 - 7.4.3.3 Table 7-9
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryManagementControlOperation {
+    /// `memory_management_control_operation == 1`: mark a short-term reference picture as
+    /// "unused for reference".
+    MarkShortTermUnused {
+        /// The `difference_of_pic_nums_minus1`.
+        difference_of_pic_nums_minus1: u32,
+    },
+    /// `memory_management_control_operation == 2`: mark a long-term reference picture as
+    /// "unused for reference".
+    MarkLongTermUnused {
+        /// The `long_term_pic_num`.
+        long_term_pic_num: u32,
+    },
+    /// `memory_management_control_operation == 3`: mark a short-term reference picture as
+    /// "used for long-term reference" and assign a long-term frame index to it.
+    MarkShortTermAsLongTerm {
+        /// The `difference_of_pic_nums_minus1`.
+        difference_of_pic_nums_minus1: u32,
+        /// The `long_term_frame_idx`.
+        long_term_frame_idx: u32,
+    },
+    /// `memory_management_control_operation == 4`: specify the maximum long-term frame index.
+    SetMaxLongTermFrameIdx {
+        /// The `max_long_term_frame_idx_plus1`, 0 means no long-term frame indices are allowed.
+        max_long_term_frame_idx_plus1: u32,
+    },
+    /// `memory_management_control_operation == 5`: mark all reference pictures as
+    /// "unused for reference".
+    MarkAllUnused,
+    /// `memory_management_control_operation == 6`: mark the current picture as
+    /// "used for long-term reference".
+    MarkCurrentAsLongTerm {
+        /// The `long_term_frame_idx`.
+        long_term_frame_idx: u32,
+    },
+}
+
+/// The `dec_ref_pic_marking` syntax of a slice header.
+/// ISO/IEC-14496-10-2022 - 7.3.3.3
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecRefPicMarking {
+    /// The marking of an IDR picture.
+    Idr {
+        /// The `no_output_of_prior_pics_flag`.
+        ///
+        /// 1 means previously decoded pictures in the DPB are discarded without being output.
+        no_output_of_prior_pics_flag: bool,
+        /// The `long_term_reference_flag`.
+        ///
+        /// 1 means the IDR picture is marked as "used for long-term reference".
+        long_term_reference_flag: bool,
+    },
+    /// `adaptive_ref_pic_marking_mode_flag == 0`: the sliding window marking process is used.
+    SlidingWindow,
+    /// `adaptive_ref_pic_marking_mode_flag == 1`: the listed operations are applied in order.
+    Adaptive(Vec<MemoryManagementControlOperation>),
+}
+
+impl DecRefPicMarking {
+    /// Parses the `dec_ref_pic_marking` syntax.
+    ///
+    /// `idr_pic_flag` must be set if the slice belongs to an IDR picture.
+    pub fn parse<T: io::Read>(reader: &mut BitReader<T>, idr_pic_flag: bool) -> io::Result<Self> {
+        if idr_pic_flag {
+            return Ok(Self::Idr {
+                no_output_of_prior_pics_flag: reader.read_bit()?,
+                long_term_reference_flag: reader.read_bit()?,
+            });
+        }
+
+        let adaptive_ref_pic_marking_mode_flag = reader.read_bit()?;
+        if !adaptive_ref_pic_marking_mode_flag {
+            return Ok(Self::SlidingWindow);
+        }
+
+        let mut ops = Vec::new();
+        loop {
+            let memory_management_control_operation = reader.read_exp_golomb()?;
+            let op = match memory_management_control_operation {
+                0 => break,
+                1 => MemoryManagementControlOperation::MarkShortTermUnused {
+                    difference_of_pic_nums_minus1: read_exp_golomb_max(
+                        reader,
+                        u32::MAX as u64,
+                        "difference_of_pic_nums_minus1",
+                    )? as u32,
+                },
+                2 => MemoryManagementControlOperation::MarkLongTermUnused {
+                    long_term_pic_num: read_exp_golomb_max(reader, u32::MAX as u64, "long_term_pic_num")? as u32,
+                },
+                3 => MemoryManagementControlOperation::MarkShortTermAsLongTerm {
+                    difference_of_pic_nums_minus1: read_exp_golomb_max(
+                        reader,
+                        u32::MAX as u64,
+                        "difference_of_pic_nums_minus1",
+                    )? as u32,
+                    long_term_frame_idx: read_exp_golomb_max(reader, u32::MAX as u64, "long_term_frame_idx")? as u32,
+                },
+                4 => MemoryManagementControlOperation::SetMaxLongTermFrameIdx {
+                    max_long_term_frame_idx_plus1: read_exp_golomb_max(
+                        reader,
+                        u32::MAX as u64,
+                        "max_long_term_frame_idx_plus1",
+                    )? as u32,
+                },
+                5 => MemoryManagementControlOperation::MarkAllUnused,
+                6 => MemoryManagementControlOperation::MarkCurrentAsLongTerm {
+                    long_term_frame_idx: read_exp_golomb_max(reader, u32::MAX as u64, "long_term_frame_idx")? as u32,
+                },
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid memory_management_control_operation",
+                    ));
+                }
+            };
+
+            // The DPB holds at most 16 frames, so a well formed stream never needs this many operations.
+            if ops.len() > 66 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "too many memory management control operations",
+                ));
+            }
+
+            ops.push(op);
+        }
+
+        Ok(Self::Adaptive(ops))
+    }
+
+    /// Returns true if this marking puts long-term reference pictures into play.
+    ///
+    /// This is the case if an IDR picture is marked as long-term reference, or if any
+    /// operation marks a picture as long-term, unmarks a long-term picture or allows
+    /// long-term frame indices.
+    pub fn uses_long_term(&self) -> bool {
+        match self {
+            Self::Idr {
+                long_term_reference_flag,
+                ..
+            } => *long_term_reference_flag,
+            Self::SlidingWindow => false,
+            Self::Adaptive(ops) => ops.iter().any(|op| match op {
+                MemoryManagementControlOperation::MarkLongTermUnused { .. }
+                | MemoryManagementControlOperation::MarkShortTermAsLongTerm { .. }
+                | MemoryManagementControlOperation::MarkCurrentAsLongTerm { .. } => true,
+                MemoryManagementControlOperation::SetMaxLongTermFrameIdx {
+                    max_long_term_frame_idx_plus1,
+                } => *max_long_term_frame_idx_plus1 > 0,
+                MemoryManagementControlOperation::MarkShortTermUnused { .. }
+                | MemoryManagementControlOperation::MarkAllUnused => false,
+            }),
+        }
+    }
+
+    /// Returns true if the marking contains `memory_management_control_operation == 5`.
+    pub fn has_mmco5(&self) -> bool {
+        matches!(self, Self::Adaptive(ops) if ops.contains(&MemoryManagementControlOperation::MarkAllUnused))
+    }
+}
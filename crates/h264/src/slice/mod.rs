@@ -0,0 +1,753 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::BitReaderExpGolombExt;
+
+use crate::io::read_exp_golomb_max;
+use crate::{EmulationPreventionIo, NALUnitType, Pps, SliceType, Sps};
+
+mod dec_ref_pic_marking;
+mod pred_weight_table;
+mod ref_pic_list_modification;
+
+pub use self::dec_ref_pic_marking::{DecRefPicMarking, MemoryManagementControlOperation};
+pub use self::pred_weight_table::{PredWeight, PredWeightTable, WeightOffset};
+pub use self::ref_pic_list_modification::{RefPicListModification, RefPicListModificationOp};
+
+/// The slice header of a coded slice NAL unit.
+/// ISO/IEC-14496-10-2022 - 7.3.3
+///
+/// Only slices of non-partitioned pictures (`nal_unit_type` 1 and 5) are supported.
+/// Syntax elements that are not present in the bitstream are set to the value they are
+/// inferred to as described in ISO/IEC-14496-10-2022 - 7.4.3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceHeader {
+    /// The `nal_ref_idc` is comprised of 2 bits.
+    ///
+    /// A value of 0 means the picture is not used for reference.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.1
+    pub nal_ref_idc: u8,
+
+    /// The `nal_unit_type` is comprised of 5 bits. See the NALUnitType nutype enum for more info.
+    pub nal_unit_type: NALUnitType,
+
+    /// The `first_mb_in_slice` is the address of the first macroblock in the slice.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.3
+    pub first_mb_in_slice: u32,
+
+    /// The raw `slice_type` (0..=9). See [`SliceHeader::slice_type`] for the coding type.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.3
+    pub raw_slice_type: u8,
+
+    /// The `pic_parameter_set_id` of the PPS in use.
+    pub pic_parameter_set_id: u16,
+
+    /// The `colour_plane_id`, only present if `separate_color_plane_flag` is set in the SPS.
+    pub colour_plane_id: Option<u8>,
+
+    /// The `frame_num` is comprised of `log2_max_frame_num_minus4 + 4` bits.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.4.3
+    pub frame_num: u32,
+
+    /// The `field_pic_flag`, 1 means the slice belongs to a coded field.
+    pub field_pic_flag: bool,
+
+    /// The `bottom_field_flag`, 1 means the slice belongs to a bottom field.
+    pub bottom_field_flag: bool,
+
+    /// The `idr_pic_id`, only present for IDR pictures.
+    pub idr_pic_id: Option<u32>,
+
+    /// The `pic_order_cnt_lsb`, only present if `pic_order_cnt_type == 0`.
+    pub pic_order_cnt_lsb: Option<u32>,
+
+    /// The `delta_pic_order_cnt_bottom`, inferred to be 0 when not present.
+    pub delta_pic_order_cnt_bottom: i64,
+
+    /// The `delta_pic_order_cnt[0]` and `delta_pic_order_cnt[1]`, inferred to be 0 when not present.
+    pub delta_pic_order_cnt: [i64; 2],
+
+    /// The `redundant_pic_cnt`, only present if `redundant_pic_cnt_present_flag` is set in the PPS.
+    pub redundant_pic_cnt: Option<u32>,
+
+    /// The `direct_spatial_mv_pred_flag`, only present for B slices.
+    pub direct_spatial_mv_pred_flag: Option<bool>,
+
+    /// The `num_ref_idx_l0_active_minus1`, taken from the PPS unless overridden by the slice.
+    pub num_ref_idx_l0_active_minus1: u8,
+
+    /// The `num_ref_idx_l1_active_minus1`, taken from the PPS unless overridden by the slice.
+    pub num_ref_idx_l1_active_minus1: u8,
+
+    /// The reference picture list modifications.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.3.3.1
+    pub ref_pic_list_modification: RefPicListModification,
+
+    /// The prediction weight table, only present if explicit weighted prediction is used.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.3.3.2
+    pub pred_weight_table: Option<PredWeightTable>,
+
+    /// The decoded reference picture marking, only present if `nal_ref_idc != 0`.
+    ///
+    /// ISO/IEC-14496-10-2022 - 7.3.3.3
+    pub dec_ref_pic_marking: Option<DecRefPicMarking>,
+
+    /// The `cabac_init_idc`, only present if `entropy_coding_mode_flag` is set and
+    /// this is not an I or SI slice.
+    pub cabac_init_idc: Option<u8>,
+
+    /// The `slice_qp_delta`.
+    pub slice_qp_delta: i64,
+
+    /// The `sp_for_switch_flag`, only present for SP slices.
+    pub sp_for_switch_flag: Option<bool>,
+
+    /// The `slice_qs_delta`, only present for SP and SI slices.
+    pub slice_qs_delta: Option<i64>,
+
+    /// The `disable_deblocking_filter_idc`, inferred to be 0 when not present.
+    pub disable_deblocking_filter_idc: u8,
+
+    /// The `slice_alpha_c0_offset_div2`, inferred to be 0 when not present.
+    pub slice_alpha_c0_offset_div2: i64,
+
+    /// The `slice_beta_offset_div2`, inferred to be 0 when not present.
+    pub slice_beta_offset_div2: i64,
+
+    /// The `slice_group_change_cycle`, only present if the PPS uses slice group map types 3 to 5.
+    pub slice_group_change_cycle: Option<u32>,
+}
+
+impl SliceHeader {
+    /// Parses a slice header from the input bytes.
+    ///
+    /// The reader must start at the NAL unit header. `sps` and `pps` must be the parameter
+    /// sets referenced by the slice.
+    pub fn parse(reader: impl io::Read, sps: &Sps, pps: &Pps) -> io::Result<Self> {
+        let mut bit_reader = BitReader::new(reader);
+
+        let forbidden_zero_bit = bit_reader.read_bit()?;
+        if forbidden_zero_bit {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Forbidden zero bit is set"));
+        }
+
+        let nal_ref_idc = bit_reader.read_bits(2)? as u8;
+        let nal_unit_type = NALUnitType(bit_reader.read_bits(5)? as u8);
+        if nal_unit_type != NALUnitType::NonIDRSliceLayerWithoutPartitioning
+            && nal_unit_type != NALUnitType::IDRSliceLayerWithoutPartitioning
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "NAL unit type is not a coded slice",
+            ));
+        }
+        let idr_pic_flag = nal_unit_type == NALUnitType::IDRSliceLayerWithoutPartitioning;
+
+        let first_mb_in_slice = read_exp_golomb_max(&mut bit_reader, u32::MAX as u64, "first_mb_in_slice")? as u32;
+        let raw_slice_type = bit_reader.read_exp_golomb()?;
+        if raw_slice_type > 9 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid slice_type"));
+        }
+        let raw_slice_type = raw_slice_type as u8;
+        let slice_type = SliceType::from_slice_type(raw_slice_type);
+
+        let pic_parameter_set_id = read_exp_golomb_max(&mut bit_reader, 255, "pic_parameter_set_id")? as u16;
+        if pic_parameter_set_id != pps.pic_parameter_set_id {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "slice references a different PPS"));
+        }
+        if pps.seq_parameter_set_id != sps.seq_parameter_set_id {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "PPS references a different SPS"));
+        }
+
+        let separate_color_plane_flag = sps.ext.as_ref().is_some_and(|ext| ext.separate_color_plane_flag);
+        let colour_plane_id = if separate_color_plane_flag {
+            Some(bit_reader.read_bits(2)? as u8)
+        } else {
+            None
+        };
+
+        let frame_num = bit_reader.read_bits(sps.log2_max_frame_num_minus4.saturating_add(4))? as u32;
+
+        let mut field_pic_flag = false;
+        let mut bottom_field_flag = false;
+        // frame_mbs_only_flag is 0 iff mb_adaptive_frame_field_flag is present
+        if sps.mb_adaptive_frame_field_flag.is_some() {
+            field_pic_flag = bit_reader.read_bit()?;
+            if field_pic_flag {
+                bottom_field_flag = bit_reader.read_bit()?;
+            }
+        }
+
+        let idr_pic_id = if idr_pic_flag {
+            Some(read_exp_golomb_max(&mut bit_reader, 65535, "idr_pic_id")? as u32)
+        } else {
+            None
+        };
+
+        let mut pic_order_cnt_lsb = None;
+        let mut delta_pic_order_cnt_bottom = 0;
+        let mut delta_pic_order_cnt = [0; 2];
+
+        if sps.pic_order_cnt_type == 0 {
+            let log2_max_pic_order_cnt_lsb_minus4 = sps.log2_max_pic_order_cnt_lsb_minus4.unwrap_or(0);
+            pic_order_cnt_lsb = Some(bit_reader.read_bits(log2_max_pic_order_cnt_lsb_minus4.saturating_add(4))? as u32);
+            if pps.bottom_field_pic_order_in_frame_present_flag && !field_pic_flag {
+                delta_pic_order_cnt_bottom = bit_reader.read_signed_exp_golomb()?;
+            }
+        }
+
+        let delta_pic_order_always_zero_flag = sps
+            .pic_order_cnt_type1
+            .as_ref()
+            .is_none_or(|poc| poc.delta_pic_order_always_zero_flag);
+        if sps.pic_order_cnt_type == 1 && !delta_pic_order_always_zero_flag {
+            delta_pic_order_cnt[0] = bit_reader.read_signed_exp_golomb()?;
+            if pps.bottom_field_pic_order_in_frame_present_flag && !field_pic_flag {
+                delta_pic_order_cnt[1] = bit_reader.read_signed_exp_golomb()?;
+            }
+        }
+
+        let redundant_pic_cnt = if pps.redundant_pic_cnt_present_flag {
+            Some(read_exp_golomb_max(&mut bit_reader, 127, "redundant_pic_cnt")? as u32)
+        } else {
+            None
+        };
+
+        let direct_spatial_mv_pred_flag = if slice_type == SliceType::B {
+            Some(bit_reader.read_bit()?)
+        } else {
+            None
+        };
+
+        let mut num_ref_idx_l0_active_minus1 = pps.num_ref_idx_l0_default_active_minus1;
+        let mut num_ref_idx_l1_active_minus1 = pps.num_ref_idx_l1_default_active_minus1;
+
+        if slice_type == SliceType::P || slice_type == SliceType::SP || slice_type == SliceType::B {
+            let num_ref_idx_active_override_flag = bit_reader.read_bit()?;
+            if num_ref_idx_active_override_flag {
+                num_ref_idx_l0_active_minus1 = Self::read_num_ref_idx_active_minus1(&mut bit_reader)?;
+                if slice_type == SliceType::B {
+                    num_ref_idx_l1_active_minus1 = Self::read_num_ref_idx_active_minus1(&mut bit_reader)?;
+                }
+            }
+        }
+
+        let ref_pic_list_modification = RefPicListModification::parse(&mut bit_reader, slice_type)?;
+
+        let pred_weight_table = if (pps.weighted_pred_flag && (slice_type == SliceType::P || slice_type == SliceType::SP))
+            || (pps.weighted_bipred_idc == 1 && slice_type == SliceType::B)
+        {
+            // ISO/IEC-14496-10-2022 - 7.4.2.1.1
+            let chroma_array_type = match &sps.ext {
+                Some(ext) if ext.separate_color_plane_flag => 0,
+                Some(ext) => ext.chroma_format_idc,
+                None => 1,
+            };
+
+            Some(PredWeightTable::parse(
+                &mut bit_reader,
+                slice_type,
+                chroma_array_type,
+                num_ref_idx_l0_active_minus1,
+                num_ref_idx_l1_active_minus1,
+            )?)
+        } else {
+            None
+        };
+
+        let dec_ref_pic_marking = if nal_ref_idc != 0 {
+            Some(DecRefPicMarking::parse(&mut bit_reader, idr_pic_flag)?)
+        } else {
+            None
+        };
+
+        let cabac_init_idc = if pps.entropy_coding_mode_flag && slice_type != SliceType::I && slice_type != SliceType::SI {
+            Some(read_exp_golomb_max(&mut bit_reader, 2, "cabac_init_idc")? as u8)
+        } else {
+            None
+        };
+
+        let slice_qp_delta = bit_reader.read_signed_exp_golomb()?;
+
+        let mut sp_for_switch_flag = None;
+        let mut slice_qs_delta = None;
+        if slice_type == SliceType::SP || slice_type == SliceType::SI {
+            if slice_type == SliceType::SP {
+                sp_for_switch_flag = Some(bit_reader.read_bit()?);
+            }
+            slice_qs_delta = Some(bit_reader.read_signed_exp_golomb()?);
+        }
+
+        let mut disable_deblocking_filter_idc = 0;
+        let mut slice_alpha_c0_offset_div2 = 0;
+        let mut slice_beta_offset_div2 = 0;
+        if pps.deblocking_filter_control_present_flag {
+            disable_deblocking_filter_idc = read_exp_golomb_max(&mut bit_reader, 2, "disable_deblocking_filter_idc")? as u8;
+            if disable_deblocking_filter_idc != 1 {
+                slice_alpha_c0_offset_div2 = bit_reader.read_signed_exp_golomb()?;
+                slice_beta_offset_div2 = bit_reader.read_signed_exp_golomb()?;
+            }
+        }
+
+        let slice_group_change_cycle = match (pps.slice_group_map_type, pps.slice_group_change_rate_minus1) {
+            (Some(3..=5), Some(slice_group_change_rate_minus1)) => {
+                // Ceil(Log2(PicSizeInMapUnits ÷ SliceGroupChangeRate + 1))
+                let pic_size_in_map_units = sps
+                    .pic_width_in_mbs_minus1
+                    .saturating_add(1)
+                    .saturating_mul(sps.pic_height_in_map_units_minus1.saturating_add(1));
+                let slice_group_change_rate = slice_group_change_rate_minus1 as u64 + 1;
+                let mut bits = 0;
+                while bits < 32 && slice_group_change_rate * ((1 << bits) - 1) < pic_size_in_map_units {
+                    bits += 1;
+                }
+
+                Some(bit_reader.read_bits(bits)? as u32)
+            }
+            _ => None,
+        };
+
+        Ok(SliceHeader {
+            nal_ref_idc,
+            nal_unit_type,
+            first_mb_in_slice,
+            raw_slice_type,
+            pic_parameter_set_id,
+            colour_plane_id,
+            frame_num,
+            field_pic_flag,
+            bottom_field_flag,
+            idr_pic_id,
+            pic_order_cnt_lsb,
+            delta_pic_order_cnt_bottom,
+            delta_pic_order_cnt,
+            redundant_pic_cnt,
+            direct_spatial_mv_pred_flag,
+            num_ref_idx_l0_active_minus1,
+            num_ref_idx_l1_active_minus1,
+            ref_pic_list_modification,
+            pred_weight_table,
+            dec_ref_pic_marking,
+            cabac_init_idc,
+            slice_qp_delta,
+            sp_for_switch_flag,
+            slice_qs_delta,
+            disable_deblocking_filter_idc,
+            slice_alpha_c0_offset_div2,
+            slice_beta_offset_div2,
+            slice_group_change_cycle,
+        })
+    }
+
+    /// Parses the slice header from a reader that may contain emulation prevention bytes.
+    /// Is the same as calling [`Self::parse`] with an [`EmulationPreventionIo`] wrapper.
+    pub fn parse_with_emulation_prevention(reader: impl io::Read, sps: &Sps, pps: &Pps) -> io::Result<Self> {
+        Self::parse(EmulationPreventionIo::new(reader), sps, pps)
+    }
+
+    fn read_num_ref_idx_active_minus1<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<u8> {
+        let value = reader.read_exp_golomb()?;
+        // ISO/IEC-14496-10-2022 - 7.4.3: the range is 0 to 31 (15 for frames)
+        if value > 31 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "num_ref_idx_active_minus1 is out of range",
+            ));
+        }
+
+        Ok(value as u8)
+    }
+
+    /// Returns the coding type of the slice.
+    pub const fn slice_type(&self) -> SliceType {
+        SliceType::from_slice_type(self.raw_slice_type)
+    }
+
+    /// Returns true if the slice belongs to an IDR picture.
+    pub fn is_idr(&self) -> bool {
+        self.nal_unit_type == NALUnitType::IDRSliceLayerWithoutPartitioning
+    }
+
+    /// Returns true if the slice is coded without reference to other pictures (an I or SI slice).
+    ///
+    /// Decoding can only start at such a slice if it is an IDR slice or sent with a [`RecoveryPoint`](crate::RecoveryPoint).
+    pub fn is_intra(&self) -> bool {
+        matches!(self.slice_type(), SliceType::I | SliceType::SI)
+    }
+
+    /// Returns true if long-term reference pictures are in play for this slice.
+    ///
+    /// This is the case if the reference picture lists are reordered using long-term
+    /// pictures, or if the decoded reference picture marking creates, removes or allows
+    /// long-term reference pictures.
+    pub fn uses_long_term_references(&self) -> bool {
+        self.ref_pic_list_modification.references_long_term()
+            || self
+                .dec_ref_pic_marking
+                .as_ref()
+                .is_some_and(DecRefPicMarking::uses_long_term)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io;
+
+    use scuffle_bytes_util::BitWriter;
+    use scuffle_expgolomb::BitWriterExpGolombExt;
+
+    use crate::{
+        DecRefPicMarking, MemoryManagementControlOperation, NALUnitType, Pps, PredWeight, RefPicListModificationOp,
+        SliceHeader, SliceType, Sps, WeightOffset,
+    };
+
+    fn sps() -> Sps {
+        // High profile, 4:2:0, frame_mbs_only, pic_order_cnt_type 0
+        Sps::parse_with_emulation_prevention(io::Cursor::new(
+            b"\x67\x64\x00\x1F\xAC\xD9\x41\xE0\x6D\xF9\xE6\xA0\x20\x20\x28\x00\x00\x00\x08\x00\x00\x01\xE0\x01",
+        ))
+        .unwrap()
+    }
+
+    fn pps() -> Pps {
+        Pps {
+            nal_ref_idc: 3,
+            nal_unit_type: NALUnitType::PPS,
+            pic_parameter_set_id: 0,
+            seq_parameter_set_id: 0,
+            entropy_coding_mode_flag: true,
+            bottom_field_pic_order_in_frame_present_flag: false,
+            num_slice_groups_minus1: 0,
+            slice_group_map_type: None,
+            slice_group_change_rate_minus1: None,
+            num_ref_idx_l0_default_active_minus1: 2,
+            num_ref_idx_l1_default_active_minus1: 0,
+            weighted_pred_flag: false,
+            weighted_bipred_idc: 0,
+            pic_init_qp_minus26: 0,
+            pic_init_qs_minus26: 0,
+            chroma_qp_index_offset: 0,
+            deblocking_filter_control_present_flag: true,
+            constrained_intra_pred_flag: false,
+            redundant_pic_cnt_present_flag: false,
+        }
+    }
+
+    /// Writes everything up to and including `pic_order_cnt_lsb`.
+    fn write_start<W: io::Write>(
+        writer: &mut BitWriter<W>,
+        sps: &Sps,
+        nal_ref_idc: u8,
+        nal_unit_type: NALUnitType,
+        slice_type: u64,
+        frame_num: u64,
+        pic_order_cnt_lsb: u64,
+    ) {
+        writer.write_bit(false).unwrap();
+        writer.write_bits(nal_ref_idc as u64, 2).unwrap();
+        writer.write_bits(nal_unit_type.0 as u64, 5).unwrap();
+
+        writer.write_exp_golomb(0).unwrap(); // first_mb_in_slice
+        writer.write_exp_golomb(slice_type).unwrap();
+        writer.write_exp_golomb(0).unwrap(); // pic_parameter_set_id
+        writer.write_bits(frame_num, sps.log2_max_frame_num_minus4 + 4).unwrap();
+        if nal_unit_type == NALUnitType::IDRSliceLayerWithoutPartitioning {
+            writer.write_exp_golomb(3).unwrap(); // idr_pic_id
+        }
+        writer
+            .write_bits(pic_order_cnt_lsb, sps.log2_max_pic_order_cnt_lsb_minus4.unwrap() + 4)
+            .unwrap();
+    }
+
+    /// Writes `slice_qp_delta` and the deblocking filter fields.
+    fn write_end<W: io::Write>(mut writer: BitWriter<W>, slice_qp_delta: i64) {
+        writer.write_signed_exp_golomb(slice_qp_delta).unwrap();
+        writer.write_exp_golomb(1).unwrap(); // disable_deblocking_filter_idc
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_parse_slice_header_invalid_nal() {
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        writer.write_bit(false).unwrap();
+        writer.write_bits(0b11, 2).unwrap();
+        writer.write_bits(8, 5).unwrap();
+        writer.finish().unwrap();
+
+        let err = SliceHeader::parse(io::Cursor::new(data), &sps(), &pps()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "NAL unit type is not a coded slice");
+    }
+
+    #[test]
+    fn test_parse_slice_header_idr_long_term() {
+        let sps = sps();
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        write_start(&mut writer, &sps, 3, NALUnitType::IDRSliceLayerWithoutPartitioning, 7, 0, 0);
+        // dec_ref_pic_marking
+        writer.write_bit(false).unwrap(); // no_output_of_prior_pics_flag
+        writer.write_bit(true).unwrap(); // long_term_reference_flag
+        write_end(writer, -4);
+
+        let header = SliceHeader::parse(io::Cursor::new(data), &sps, &pps()).unwrap();
+
+        assert!(header.is_idr());
+        assert!(header.is_intra());
+        assert_eq!(header.slice_type(), SliceType::I);
+        assert_eq!(header.raw_slice_type, 7);
+        assert_eq!(header.idr_pic_id, Some(3));
+        assert_eq!(header.pic_order_cnt_lsb, Some(0));
+        assert_eq!(header.ref_pic_list_modification.l0, None);
+        assert_eq!(header.cabac_init_idc, None);
+        assert_eq!(
+            header.dec_ref_pic_marking,
+            Some(DecRefPicMarking::Idr {
+                no_output_of_prior_pics_flag: false,
+                long_term_reference_flag: true,
+            })
+        );
+        assert_eq!(header.slice_qp_delta, -4);
+        assert_eq!(header.disable_deblocking_filter_idc, 1);
+        assert!(header.uses_long_term_references());
+    }
+
+    #[test]
+    fn test_parse_slice_header_p_modifications() {
+        let sps = sps();
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        write_start(
+            &mut writer,
+            &sps,
+            2,
+            NALUnitType::NonIDRSliceLayerWithoutPartitioning,
+            0,
+            5,
+            10,
+        );
+        writer.write_bit(true).unwrap(); // num_ref_idx_active_override_flag
+        writer.write_exp_golomb(3).unwrap(); // num_ref_idx_l0_active_minus1
+
+        // ref_pic_list_modification
+        writer.write_bit(true).unwrap(); // ref_pic_list_modification_flag_l0
+        writer.write_exp_golomb(0).unwrap();
+        writer.write_exp_golomb(1).unwrap(); // abs_diff_pic_num_minus1
+        writer.write_exp_golomb(1).unwrap();
+        writer.write_exp_golomb(0).unwrap(); // abs_diff_pic_num_minus1
+        writer.write_exp_golomb(2).unwrap();
+        writer.write_exp_golomb(4).unwrap(); // long_term_pic_num
+        writer.write_exp_golomb(3).unwrap();
+
+        // dec_ref_pic_marking
+        writer.write_bit(true).unwrap(); // adaptive_ref_pic_marking_mode_flag
+        writer.write_exp_golomb(1).unwrap();
+        writer.write_exp_golomb(2).unwrap(); // difference_of_pic_nums_minus1
+        writer.write_exp_golomb(4).unwrap();
+        writer.write_exp_golomb(2).unwrap(); // max_long_term_frame_idx_plus1
+        writer.write_exp_golomb(3).unwrap();
+        writer.write_exp_golomb(0).unwrap(); // difference_of_pic_nums_minus1
+        writer.write_exp_golomb(1).unwrap(); // long_term_frame_idx
+        writer.write_exp_golomb(6).unwrap();
+        writer.write_exp_golomb(0).unwrap(); // long_term_frame_idx
+        writer.write_exp_golomb(0).unwrap();
+
+        writer.write_exp_golomb(2).unwrap(); // cabac_init_idc
+        write_end(writer, 3);
+
+        let header = SliceHeader::parse(io::Cursor::new(data), &sps, &pps()).unwrap();
+
+        assert_eq!(header.slice_type(), SliceType::P);
+        assert!(!header.is_intra());
+        assert_eq!(header.frame_num, 5);
+        assert_eq!(header.pic_order_cnt_lsb, Some(10));
+        assert_eq!(header.num_ref_idx_l0_active_minus1, 3);
+        assert_eq!(
+            header.ref_pic_list_modification.l0,
+            Some(vec![
+                RefPicListModificationOp::SubtractShortTerm {
+                    abs_diff_pic_num_minus1: 1
+                },
+                RefPicListModificationOp::AddShortTerm {
+                    abs_diff_pic_num_minus1: 0
+                },
+                RefPicListModificationOp::LongTerm { long_term_pic_num: 4 },
+            ])
+        );
+        assert_eq!(header.ref_pic_list_modification.l1, None);
+        assert_eq!(
+            header.dec_ref_pic_marking,
+            Some(DecRefPicMarking::Adaptive(vec![
+                MemoryManagementControlOperation::MarkShortTermUnused {
+                    difference_of_pic_nums_minus1: 2
+                },
+                MemoryManagementControlOperation::SetMaxLongTermFrameIdx {
+                    max_long_term_frame_idx_plus1: 2
+                },
+                MemoryManagementControlOperation::MarkShortTermAsLongTerm {
+                    difference_of_pic_nums_minus1: 0,
+                    long_term_frame_idx: 1,
+                },
+                MemoryManagementControlOperation::MarkCurrentAsLongTerm { long_term_frame_idx: 0 },
+            ]))
+        );
+        assert_eq!(header.cabac_init_idc, Some(2));
+        assert_eq!(header.slice_qp_delta, 3);
+        assert!(header.uses_long_term_references());
+    }
+
+    #[test]
+    fn test_parse_slice_header_b_weighted() {
+        let sps = sps();
+        let pps = Pps {
+            weighted_bipred_idc: 1,
+            ..pps()
+        };
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        write_start(
+            &mut writer,
+            &sps,
+            0,
+            NALUnitType::NonIDRSliceLayerWithoutPartitioning,
+            6,
+            1,
+            4,
+        );
+        writer.write_bit(true).unwrap(); // direct_spatial_mv_pred_flag
+        writer.write_bit(true).unwrap(); // num_ref_idx_active_override_flag
+        writer.write_exp_golomb(0).unwrap(); // num_ref_idx_l0_active_minus1
+        writer.write_exp_golomb(1).unwrap(); // num_ref_idx_l1_active_minus1
+
+        // ref_pic_list_modification
+        writer.write_bit(false).unwrap(); // ref_pic_list_modification_flag_l0
+        writer.write_bit(false).unwrap(); // ref_pic_list_modification_flag_l1
+
+        // pred_weight_table
+        writer.write_exp_golomb(5).unwrap(); // luma_log2_weight_denom
+        writer.write_exp_golomb(4).unwrap(); // chroma_log2_weight_denom
+        // l0[0]
+        writer.write_bit(true).unwrap();
+        writer.write_signed_exp_golomb(30).unwrap();
+        writer.write_signed_exp_golomb(-2).unwrap();
+        writer.write_bit(false).unwrap();
+        // l1[0]
+        writer.write_bit(false).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.write_signed_exp_golomb(16).unwrap();
+        writer.write_signed_exp_golomb(1).unwrap();
+        writer.write_signed_exp_golomb(15).unwrap();
+        writer.write_signed_exp_golomb(-1).unwrap();
+        // l1[1]
+        writer.write_bit(false).unwrap();
+        writer.write_bit(false).unwrap();
+
+        writer.write_exp_golomb(0).unwrap(); // cabac_init_idc
+        write_end(writer, -1);
+
+        let header = SliceHeader::parse(io::Cursor::new(data), &sps, &pps).unwrap();
+
+        assert_eq!(header.slice_type(), SliceType::B);
+        assert_eq!(header.direct_spatial_mv_pred_flag, Some(true));
+        assert_eq!(header.num_ref_idx_l0_active_minus1, 0);
+        assert_eq!(header.num_ref_idx_l1_active_minus1, 1);
+        assert_eq!(header.ref_pic_list_modification.l0, None);
+        assert_eq!(header.ref_pic_list_modification.l1, None);
+        assert_eq!(header.dec_ref_pic_marking, None);
+
+        let table = header.pred_weight_table.as_ref().unwrap();
+        assert_eq!(table.luma_log2_weight_denom, 5);
+        assert_eq!(table.chroma_log2_weight_denom, Some(4));
+        assert_eq!(
+            table.l0,
+            vec![PredWeight {
+                luma: Some(WeightOffset { weight: 30, offset: -2 }),
+                chroma: None,
+            }]
+        );
+        assert_eq!(
+            table.l1,
+            vec![
+                PredWeight {
+                    luma: None,
+                    chroma: Some([
+                        WeightOffset { weight: 16, offset: 1 },
+                        WeightOffset { weight: 15, offset: -1 },
+                    ]),
+                },
+                PredWeight::default(),
+            ]
+        );
+
+        assert_eq!(header.cabac_init_idc, Some(0));
+        assert_eq!(header.slice_qp_delta, -1);
+        assert!(!header.uses_long_term_references());
+    }
+
+    #[test]
+    fn test_parse_slice_header_sliding_window() {
+        let sps = sps();
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        write_start(
+            &mut writer,
+            &sps,
+            1,
+            NALUnitType::NonIDRSliceLayerWithoutPartitioning,
+            5,
+            2,
+            8,
+        );
+        writer.write_bit(false).unwrap(); // num_ref_idx_active_override_flag
+        writer.write_bit(false).unwrap(); // ref_pic_list_modification_flag_l0
+        writer.write_bit(false).unwrap(); // adaptive_ref_pic_marking_mode_flag
+        writer.write_exp_golomb(1).unwrap(); // cabac_init_idc
+        write_end(writer, 0);
+
+        let header = SliceHeader::parse(io::Cursor::new(data), &sps, &pps()).unwrap();
+
+        assert_eq!(header.slice_type(), SliceType::P);
+        assert_eq!(header.num_ref_idx_l0_active_minus1, 2);
+        assert_eq!(header.dec_ref_pic_marking, Some(DecRefPicMarking::SlidingWindow));
+        assert!(!header.uses_long_term_references());
+    }
+
+    #[test]
+    fn test_parse_slice_header_invalid_mmco() {
+        let sps = sps();
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        write_start(
+            &mut writer,
+            &sps,
+            1,
+            NALUnitType::NonIDRSliceLayerWithoutPartitioning,
+            2,
+            2,
+            8,
+        );
+        writer.write_bit(true).unwrap(); // adaptive_ref_pic_marking_mode_flag
+        writer.write_exp_golomb(7).unwrap();
+        write_end(writer, 0);
+
+        let err = SliceHeader::parse(io::Cursor::new(data), &sps, &pps()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "invalid memory_management_control_operation");
+    }
+}
@@ -0,0 +1,112 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::BitReaderExpGolombExt;
+
+use crate::SliceType;
+use crate::io::read_exp_golomb_max;
+
+/// An explicit weight and offset for a single component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightOffset {
+    /// The `luma_weight_lX` or `chroma_weight_lX`.
+    pub weight: i64,
+    /// The `luma_offset_lX` or `chroma_offset_lX`.
+    pub offset: i64,
+}
+
+/// The explicit weights of a single reference picture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PredWeight {
+    /// The luma weight, `None` if `luma_weight_lX_flag` is not set.
+    pub luma: Option<WeightOffset>,
+    /// The Cb and Cr weights, `None` if `chroma_weight_lX_flag` is not set or
+    /// there are no chroma components (`ChromaArrayType == 0`).
+    pub chroma: Option<[WeightOffset; 2]>,
+}
+
+/// The `pred_weight_table` syntax of a slice header.
+/// ISO/IEC-14496-10-2022 - 7.3.3.2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredWeightTable {
+    /// The `luma_log2_weight_denom`.
+    pub luma_log2_weight_denom: u8,
+    /// The `chroma_log2_weight_denom`, `None` if `ChromaArrayType == 0`.
+    pub chroma_log2_weight_denom: Option<u8>,
+    /// The weights of each entry in `RefPicList0`.
+    pub l0: Vec<PredWeight>,
+    /// The weights of each entry in `RefPicList1`, empty unless this is a B slice.
+    pub l1: Vec<PredWeight>,
+}
+
+impl PredWeightTable {
+    /// Parses the `pred_weight_table` syntax.
+    pub fn parse<T: io::Read>(
+        reader: &mut BitReader<T>,
+        slice_type: SliceType,
+        chroma_array_type: u8,
+        num_ref_idx_l0_active_minus1: u8,
+        num_ref_idx_l1_active_minus1: u8,
+    ) -> io::Result<Self> {
+        let luma_log2_weight_denom = read_exp_golomb_max(reader, 7, "luma_log2_weight_denom")? as u8;
+        let chroma_log2_weight_denom = if chroma_array_type != 0 {
+            Some(read_exp_golomb_max(reader, 7, "chroma_log2_weight_denom")? as u8)
+        } else {
+            None
+        };
+
+        let has_chroma = chroma_array_type != 0;
+        let l0 = Self::parse_list(reader, num_ref_idx_l0_active_minus1, has_chroma)?;
+        let l1 = if slice_type == SliceType::B {
+            Self::parse_list(reader, num_ref_idx_l1_active_minus1, has_chroma)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            luma_log2_weight_denom,
+            chroma_log2_weight_denom,
+            l0,
+            l1,
+        })
+    }
+
+    fn parse_list<T: io::Read>(
+        reader: &mut BitReader<T>,
+        num_ref_idx_active_minus1: u8,
+        has_chroma: bool,
+    ) -> io::Result<Vec<PredWeight>> {
+        let mut weights = Vec::with_capacity(num_ref_idx_active_minus1 as usize + 1);
+
+        for _ in 0..=num_ref_idx_active_minus1 {
+            let mut weight = PredWeight::default();
+
+            let luma_weight_flag = reader.read_bit()?;
+            if luma_weight_flag {
+                weight.luma = Some(WeightOffset {
+                    weight: reader.read_signed_exp_golomb()?,
+                    offset: reader.read_signed_exp_golomb()?,
+                });
+            }
+
+            if has_chroma {
+                let chroma_weight_flag = reader.read_bit()?;
+                if chroma_weight_flag {
+                    let cb = WeightOffset {
+                        weight: reader.read_signed_exp_golomb()?,
+                        offset: reader.read_signed_exp_golomb()?,
+                    };
+                    let cr = WeightOffset {
+                        weight: reader.read_signed_exp_golomb()?,
+                        offset: reader.read_signed_exp_golomb()?,
+                    };
+                    weight.chroma = Some([cb, cr]);
+                }
+            }
+
+            weights.push(weight);
+        }
+
+        Ok(weights)
+    }
+}
@@ -0,0 +1,111 @@
+use std::io;
+
+use scuffle_bytes_util::BitReader;
+use scuffle_expgolomb::BitReaderExpGolombExt;
+
+use crate::SliceType;
+use crate::io::read_exp_golomb_max;
+
+/// A single reordering operation of a reference picture list.
+/// ISO/IEC-14496-10-2022 - 7.4.3.1 Table 7-7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefPicListModificationOp {
+    /// `modification_of_pic_nums_idc == 0`: the short-term picture number is obtained by
+    /// subtracting `abs_diff_pic_num_minus1 + 1` from the picture number prediction value.
+    SubtractShortTerm {
+        /// The `abs_diff_pic_num_minus1`.
+        abs_diff_pic_num_minus1: u32,
+    },
+    /// `modification_of_pic_nums_idc == 1`: the short-term picture number is obtained by
+    /// adding `abs_diff_pic_num_minus1 + 1` to the picture number prediction value.
+    AddShortTerm {
+        /// The `abs_diff_pic_num_minus1`.
+        abs_diff_pic_num_minus1: u32,
+    },
+    /// `modification_of_pic_nums_idc == 2`: a long-term picture is moved in the list.
+    LongTerm {
+        /// The `long_term_pic_num` of the picture being moved.
+        long_term_pic_num: u32,
+    },
+}
+
+/// The `ref_pic_list_modification` syntax of a slice header.
+/// ISO/IEC-14496-10-2022 - 7.3.3.1
+///
+/// Each list is `None` if the corresponding `ref_pic_list_modification_flag_lX` is not set
+/// (or the list does not exist for the slice type).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefPicListModification {
+    /// The operations applied to `RefPicList0`, present for all slices except I and SI slices.
+    pub l0: Option<Vec<RefPicListModificationOp>>,
+    /// The operations applied to `RefPicList1`, present for B slices only.
+    pub l1: Option<Vec<RefPicListModificationOp>>,
+}
+
+impl RefPicListModification {
+    /// Parses the `ref_pic_list_modification` syntax for the given slice type.
+    pub fn parse<T: io::Read>(reader: &mut BitReader<T>, slice_type: SliceType) -> io::Result<Self> {
+        let mut modification = Self::default();
+
+        if slice_type != SliceType::I && slice_type != SliceType::SI {
+            modification.l0 = Self::parse_list(reader)?;
+        }
+
+        if slice_type == SliceType::B {
+            modification.l1 = Self::parse_list(reader)?;
+        }
+
+        Ok(modification)
+    }
+
+    fn parse_list<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Option<Vec<RefPicListModificationOp>>> {
+        let ref_pic_list_modification_flag = reader.read_bit()?;
+        if !ref_pic_list_modification_flag {
+            return Ok(None);
+        }
+
+        let mut ops = Vec::new();
+        loop {
+            let modification_of_pic_nums_idc = reader.read_exp_golomb()?;
+            let op = match modification_of_pic_nums_idc {
+                0 => RefPicListModificationOp::SubtractShortTerm {
+                    abs_diff_pic_num_minus1: read_exp_golomb_max(reader, u32::MAX as u64, "abs_diff_pic_num_minus1")? as u32,
+                },
+                1 => RefPicListModificationOp::AddShortTerm {
+                    abs_diff_pic_num_minus1: read_exp_golomb_max(reader, u32::MAX as u64, "abs_diff_pic_num_minus1")? as u32,
+                },
+                2 => RefPicListModificationOp::LongTerm {
+                    long_term_pic_num: read_exp_golomb_max(reader, u32::MAX as u64, "long_term_pic_num")? as u32,
+                },
+                3 => break,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid modification_of_pic_nums_idc",
+                    ));
+                }
+            };
+
+            // There can never be more operations than entries in the list (32 for fields).
+            if ops.len() > 32 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "too many reference picture list modifications",
+                ));
+            }
+
+            ops.push(op);
+        }
+
+        Ok(Some(ops))
+    }
+
+    /// Returns true if any of the operations reorders a long-term reference picture.
+    pub fn references_long_term(&self) -> bool {
+        self.l0
+            .iter()
+            .chain(self.l1.iter())
+            .flatten()
+            .any(|op| matches!(op, RefPicListModificationOp::LongTerm { .. }))
+    }
+}
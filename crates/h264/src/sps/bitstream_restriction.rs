@@ -0,0 +1,204 @@
+use std::io;
+
+use scuffle_bytes_util::BitWriter;
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb};
+
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
+
+/// `BitstreamRestriction` contains the fields that are set when `bitstream_restriction_flag == 1`.
+///
+/// ISO/IEC-14496-10-2022 - E.1.1
+///
+/// Refer to the direct fields for more information.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitstreamRestriction {
+    /// The `motion_vectors_over_pic_boundaries_flag` is a single bit.
+    ///
+    /// 0 means no motion vector refers to samples outside of the picture boundaries.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub motion_vectors_over_pic_boundaries_flag: bool,
+
+    /// The `max_bytes_per_pic_denom` ranges from \[0, 16\], 0 means there is no limit.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub max_bytes_per_pic_denom: u8,
+
+    /// The `max_bits_per_mb_denom` ranges from \[0, 16\], 0 means there is no limit.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub max_bits_per_mb_denom: u8,
+
+    /// The `log2_max_mv_length_horizontal` ranges from \[0, 15\].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub log2_max_mv_length_horizontal: u8,
+
+    /// The `log2_max_mv_length_vertical` ranges from \[0, 15\].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub log2_max_mv_length_vertical: u8,
+
+    /// The `max_num_reorder_frames` is the largest number of frames that precede a frame
+    /// in decoding order and follow it in output order.
+    ///
+    /// The value of this ranges from \[0, `max_dec_frame_buffering`\].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub max_num_reorder_frames: u8,
+
+    /// The `max_dec_frame_buffering` is the required size of the decoded picture buffer in frames.
+    ///
+    /// The value of this ranges from \[0, 16\].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub max_dec_frame_buffering: u8,
+}
+
+impl BitstreamRestriction {
+    /// Parses the fields defined when the `bitstream_restriction_flag == 1` from a bitstream.
+    /// Returns a `BitstreamRestriction` struct.
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let motion_vectors_over_pic_boundaries_flag = reader.read_bit("motion_vectors_over_pic_boundaries_flag")?;
+        let max_bytes_per_pic_denom = reader.read_exp_golomb_max(16, "max_bytes_per_pic_denom")? as u8;
+        let max_bits_per_mb_denom = reader.read_exp_golomb_max(16, "max_bits_per_mb_denom")? as u8;
+        let log2_max_mv_length_horizontal = reader.read_exp_golomb_max(15, "log2_max_mv_length_horizontal")? as u8;
+        let log2_max_mv_length_vertical = reader.read_exp_golomb_max(15, "log2_max_mv_length_vertical")? as u8;
+        let max_num_reorder_frames = reader.read_exp_golomb_max(16, "max_num_reorder_frames")? as u8;
+        let max_dec_frame_buffering = reader.read_exp_golomb_max(16, "max_dec_frame_buffering")? as u8;
+
+        if max_num_reorder_frames > max_dec_frame_buffering {
+            return Err(reader.invalid(
+                "max_dec_frame_buffering",
+                "max_num_reorder_frames cannot be greater than max_dec_frame_buffering",
+            ));
+        }
+
+        Ok(BitstreamRestriction {
+            motion_vectors_over_pic_boundaries_flag,
+            max_bytes_per_pic_denom,
+            max_bits_per_mb_denom,
+            log2_max_mv_length_horizontal,
+            log2_max_mv_length_vertical,
+            max_num_reorder_frames,
+            max_dec_frame_buffering,
+        })
+    }
+
+    /// Builds the BitstreamRestriction struct into a byte stream.
+    /// Returns a built byte stream.
+    pub fn build<T: io::Write>(&self, writer: &mut BitWriter<T>) -> io::Result<()> {
+        writer.write_bit(self.motion_vectors_over_pic_boundaries_flag)?;
+        writer.write_exp_golomb(self.max_bytes_per_pic_denom as u64)?;
+        writer.write_exp_golomb(self.max_bits_per_mb_denom as u64)?;
+        writer.write_exp_golomb(self.log2_max_mv_length_horizontal as u64)?;
+        writer.write_exp_golomb(self.log2_max_mv_length_vertical as u64)?;
+        writer.write_exp_golomb(self.max_num_reorder_frames as u64)?;
+        writer.write_exp_golomb(self.max_dec_frame_buffering as u64)?;
+        Ok(())
+    }
+
+    /// Returns the total bits of the BitstreamRestriction struct.
+    ///
+    /// Note that this isn't the bytesize since aligning it may cause some values to be different.
+    pub fn bitsize(&self) -> u64 {
+        1 + // motion_vectors_over_pic_boundaries_flag
+        size_of_exp_golomb(self.max_bytes_per_pic_denom as u64) +
+        size_of_exp_golomb(self.max_bits_per_mb_denom as u64) +
+        size_of_exp_golomb(self.log2_max_mv_length_horizontal as u64) +
+        size_of_exp_golomb(self.log2_max_mv_length_vertical as u64) +
+        size_of_exp_golomb(self.max_num_reorder_frames as u64) +
+        size_of_exp_golomb(self.max_dec_frame_buffering as u64)
+    }
+
+    /// Returns the total bytes of the BitstreamRestriction struct.
+    ///
+    /// Note that this calls [`BitstreamRestriction::bitsize()`] and calculates the number of bytes
+    /// including any necessary padding such that the bitstream is byte aligned.
+    pub fn bytesize(&self) -> u64 {
+        self.bitsize().div_ceil(8)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use scuffle_bytes_util::{BitReader, BitWriter};
+    use scuffle_expgolomb::BitWriterExpGolombExt;
+
+    use crate::io::SyntaxReader;
+    use crate::sps::BitstreamRestriction;
+
+    fn write_bitstream_restriction(max_num_reorder_frames: u64, max_dec_frame_buffering: u64) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        writer.write_bit(true).unwrap(); // motion_vectors_over_pic_boundaries_flag
+        writer.write_exp_golomb(2).unwrap(); // max_bytes_per_pic_denom
+        writer.write_exp_golomb(1).unwrap(); // max_bits_per_mb_denom
+        writer.write_exp_golomb(13).unwrap(); // log2_max_mv_length_horizontal
+        writer.write_exp_golomb(11).unwrap(); // log2_max_mv_length_vertical
+        writer.write_exp_golomb(max_num_reorder_frames).unwrap();
+        writer.write_exp_golomb(max_dec_frame_buffering).unwrap();
+        writer.finish().unwrap();
+
+        data
+    }
+
+    #[test]
+    fn test_build_size_bitstream_restriction() {
+        // create bitstream for bitstream_restriction
+        let mut data = write_bitstream_restriction(2, 4);
+
+        // parse bitstream
+        let mut reader = BitReader::new_from_slice(&mut data);
+        let bitstream_restriction =
+            BitstreamRestriction::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
+
+        assert_eq!(
+            bitstream_restriction,
+            BitstreamRestriction {
+                motion_vectors_over_pic_boundaries_flag: true,
+                max_bytes_per_pic_denom: 2,
+                max_bits_per_mb_denom: 1,
+                log2_max_mv_length_horizontal: 13,
+                log2_max_mv_length_vertical: 11,
+                max_num_reorder_frames: 2,
+                max_dec_frame_buffering: 4,
+            }
+        );
+
+        // create a writer for the builder
+        let mut buf = Vec::new();
+        let mut writer2 = BitWriter::new(&mut buf);
+
+        // build from the example result
+        bitstream_restriction.build(&mut writer2).unwrap();
+        writer2.finish().unwrap();
+
+        assert_eq!(buf, data);
+
+        // now we re-parse so we can compare the bit sizes.
+        // create a reader for the parser
+        let mut reader2 = BitReader::new_from_slice(buf);
+        let rebuilt_bitstream_restriction =
+            BitstreamRestriction::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
+
+        // now we can check the size:
+        assert_eq!(rebuilt_bitstream_restriction.bitsize(), bitstream_restriction.bitsize());
+        assert_eq!(rebuilt_bitstream_restriction.bytesize(), bitstream_restriction.bytesize());
+    }
+
+    #[test]
+    fn test_parse_bitstream_restriction_invalid_reorder() {
+        let mut data = write_bitstream_restriction(4, 2);
+
+        let mut reader = BitReader::new_from_slice(&mut data);
+        let err = BitstreamRestriction::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "vui_parameters.max_dec_frame_buffering at bit 26: max_num_reorder_frames cannot be greater than max_dec_frame_buffering"
+        );
+    }
+}
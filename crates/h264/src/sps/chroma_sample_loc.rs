@@ -1,7 +1,10 @@
 use std::io;
 
-use scuffle_bytes_util::{BitReader, BitWriter};
-use scuffle_expgolomb::{BitReaderExpGolombExt, BitWriterExpGolombExt, size_of_exp_golomb};
+use scuffle_bytes_util::BitWriter;
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb};
+
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
 
 /// `ChromaSampleLoc` contains the fields that are set when `chroma_loc_info_present_flag == 1`,
 ///
@@ -44,9 +47,11 @@ pub struct ChromaSampleLoc {
 impl ChromaSampleLoc {
     /// Parses the fields defined when the `chroma_loc_info_present_flag == 1` from a bitstream.
     /// Returns a `ChromaSampleLoc` struct.
-    pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let chroma_sample_loc_type_top_field = reader.read_exp_golomb()? as u8;
-        let chroma_sample_loc_type_bottom_field = reader.read_exp_golomb()? as u8;
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let chroma_sample_loc_type_top_field =
+            reader.read_exp_golomb_max(u8::MAX as u64, "chroma_sample_loc_type_top_field")? as u8;
+        let chroma_sample_loc_type_bottom_field =
+            reader.read_exp_golomb_max(u8::MAX as u64, "chroma_sample_loc_type_bottom_field")? as u8;
 
         Ok(ChromaSampleLoc {
             chroma_sample_loc_type_top_field,
@@ -85,6 +90,7 @@ mod tests {
     use scuffle_bytes_util::{BitReader, BitWriter};
     use scuffle_expgolomb::BitWriterExpGolombExt;
 
+    use crate::io::SyntaxReader;
     use crate::sps::ChromaSampleLoc;
 
     #[test]
@@ -99,7 +105,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let chroma_sample_loc = ChromaSampleLoc::parse(&mut reader).unwrap();
+        let chroma_sample_loc = ChromaSampleLoc::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -114,7 +120,8 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_chroma_sample_loc = ChromaSampleLoc::parse(&mut reader2).unwrap();
+        let rebuilt_chroma_sample_loc =
+            ChromaSampleLoc::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_chroma_sample_loc.bitsize(), chroma_sample_loc.bitsize());
@@ -1,9 +1,9 @@
 use std::io;
 
-use byteorder::ReadBytesExt;
-use scuffle_bytes_util::{BitReader, BitWriter};
+use scuffle_bytes_util::BitWriter;
 
-use crate::VideoFormat;
+use crate::io::SyntaxReader;
+use crate::{H264ParseError, VideoFormat};
 
 /// The color config for SPS. ISO/IEC-14496-10-2022 - E.2.1
 #[derive(Debug, Clone, PartialEq)]
@@ -38,19 +38,19 @@ pub struct ColorConfig {
 impl ColorConfig {
     /// Parses the fields defined when the `video_signal_type_present_flag == 1` from a bitstream.
     /// Returns a `ColorConfig` struct.
-    pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let video_format = reader.read_bits(3)? as u8;
-        let video_full_range_flag = reader.read_bit()?;
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let video_format = reader.read_bits(3, "video_format")? as u8;
+        let video_full_range_flag = reader.read_bit("video_full_range_flag")?;
 
         let color_primaries;
         let transfer_characteristics;
         let matrix_coefficients;
 
-        let color_description_present_flag = reader.read_bit()?;
+        let color_description_present_flag = reader.read_bit("color_description_present_flag")?;
         if color_description_present_flag {
-            color_primaries = reader.read_u8()?;
-            transfer_characteristics = reader.read_u8()?;
-            matrix_coefficients = reader.read_u8()?;
+            color_primaries = reader.read_u8("colour_primaries")?;
+            transfer_characteristics = reader.read_u8("transfer_characteristics")?;
+            matrix_coefficients = reader.read_u8("matrix_coefficients")?;
         } else {
             color_primaries = 2; // UNSPECIFIED
             transfer_characteristics = 2; // UNSPECIFIED
@@ -113,6 +113,7 @@ impl ColorConfig {
 mod tests {
     use scuffle_bytes_util::{BitReader, BitWriter};
 
+    use crate::io::SyntaxReader;
     use crate::sps::ColorConfig;
 
     #[test]
@@ -133,7 +134,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let color_config = ColorConfig::parse(&mut reader).unwrap();
+        let color_config = ColorConfig::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -147,7 +148,7 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_color_config = ColorConfig::parse(&mut reader2).unwrap();
+        let rebuilt_color_config = ColorConfig::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_color_config.bitsize(), color_config.bitsize());
@@ -169,7 +170,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let color_config = ColorConfig::parse(&mut reader).unwrap();
+        let color_config = ColorConfig::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -184,7 +185,7 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_color_config = ColorConfig::parse(&mut reader2).unwrap();
+        let rebuilt_color_config = ColorConfig::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_color_config.bitsize(), color_config.bitsize());
@@ -1,7 +1,10 @@
 use std::io;
 
-use scuffle_bytes_util::{BitReader, BitWriter};
-use scuffle_expgolomb::{BitReaderExpGolombExt, BitWriterExpGolombExt, size_of_exp_golomb};
+use scuffle_bytes_util::BitWriter;
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb};
+
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
 
 /// `FrameCropInfo` contains the frame cropping info.
 ///
@@ -63,11 +66,11 @@ pub struct FrameCropInfo {
 impl FrameCropInfo {
     /// Parses the fields defined when the `frame_cropping_flag == 1` from a bitstream.
     /// Returns a `FrameCropInfo` struct.
-    pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let frame_crop_left_offset = reader.read_exp_golomb()?;
-        let frame_crop_right_offset = reader.read_exp_golomb()?;
-        let frame_crop_top_offset = reader.read_exp_golomb()?;
-        let frame_crop_bottom_offset = reader.read_exp_golomb()?;
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let frame_crop_left_offset = reader.read_exp_golomb("frame_crop_left_offset")?;
+        let frame_crop_right_offset = reader.read_exp_golomb("frame_crop_right_offset")?;
+        let frame_crop_top_offset = reader.read_exp_golomb("frame_crop_top_offset")?;
+        let frame_crop_bottom_offset = reader.read_exp_golomb("frame_crop_bottom_offset")?;
 
         Ok(FrameCropInfo {
             frame_crop_left_offset,
@@ -112,6 +115,7 @@ mod tests {
     use scuffle_bytes_util::{BitReader, BitWriter};
     use scuffle_expgolomb::BitWriterExpGolombExt;
 
+    use crate::io::SyntaxReader;
     use crate::sps::FrameCropInfo;
 
     #[test]
@@ -129,7 +133,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let frame_crop_info = FrameCropInfo::parse(&mut reader).unwrap();
+        let frame_crop_info = FrameCropInfo::parse(&mut SyntaxReader::new(&mut reader, "seq_parameter_set_data")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -144,7 +148,8 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_frame_crop_info = FrameCropInfo::parse(&mut reader2).unwrap();
+        let rebuilt_frame_crop_info =
+            FrameCropInfo::parse(&mut SyntaxReader::new(&mut reader2, "seq_parameter_set_data")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_frame_crop_info.bitsize(), frame_crop_info.bitsize());
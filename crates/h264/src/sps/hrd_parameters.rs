@@ -0,0 +1,299 @@
+use std::io;
+
+use scuffle_bytes_util::BitWriter;
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb};
+
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
+
+/// The largest `bit_rate_value_minus1` and `cpb_size_value_minus1`.
+///
+/// ISO/IEC-14496-10-2022 - E.2.2
+const MAX_VALUE_MINUS1: u64 = u32::MAX as u64 - 1;
+
+/// The hypothetical reference decoder (HRD) parameters for SPS.
+/// ISO/IEC-14496-10-2022 - E.1.2
+///
+/// Sent for the NAL HRD and the VCL HRD, they describe the bit rate and size of the
+/// coded picture buffer (CPB) the stream is guaranteed to conform to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HrdParameters {
+    /// The `bit_rate_scale` is comprised of 4 bits.
+    ///
+    /// It is the scale of the `bit_rate_value_minus1` of every [`CpbSpec`].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.2
+    pub bit_rate_scale: u8,
+
+    /// The `cpb_size_scale` is comprised of 4 bits.
+    ///
+    /// It is the scale of the `cpb_size_value_minus1` of every [`CpbSpec`].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.2
+    pub cpb_size_scale: u8,
+
+    /// The alternative CPB specifications, of which there are `cpb_cnt_minus1 + 1`.
+    ///
+    /// The value of `cpb_cnt_minus1` ranges from \[0, 31\].
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.2
+    pub cpb_specs: Vec<CpbSpec>,
+
+    /// The `initial_cpb_removal_delay_length_minus1` is comprised of 5 bits.
+    ///
+    /// It is the length of `initial_cpb_removal_delay` in buffering period SEI messages, minus 1.
+    pub initial_cpb_removal_delay_length_minus1: u8,
+
+    /// The `cpb_removal_delay_length_minus1` is comprised of 5 bits.
+    ///
+    /// It is the length of `cpb_removal_delay` in picture timing SEI messages, minus 1.
+    pub cpb_removal_delay_length_minus1: u8,
+
+    /// The `dpb_output_delay_length_minus1` is comprised of 5 bits.
+    ///
+    /// It is the length of `dpb_output_delay` in picture timing SEI messages, minus 1.
+    pub dpb_output_delay_length_minus1: u8,
+
+    /// The `time_offset_length` is comprised of 5 bits.
+    ///
+    /// It is the length of `time_offset` in picture timing SEI messages.
+    pub time_offset_length: u8,
+}
+
+/// A CPB specification of the [`HrdParameters`].
+/// ISO/IEC-14496-10-2022 - E.1.2
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpbSpec {
+    /// The `bit_rate_value_minus1` ranges from \[0, 2^32 - 2\].
+    ///
+    /// See [`HrdParameters::bit_rate`] for the bit rate in bits per second.
+    pub bit_rate_value_minus1: u32,
+
+    /// The `cpb_size_value_minus1` ranges from \[0, 2^32 - 2\].
+    ///
+    /// See [`HrdParameters::cpb_size`] for the CPB size in bits.
+    pub cpb_size_value_minus1: u32,
+
+    /// The `cbr_flag` is a single bit.
+    ///
+    /// 0 means the stream is sent with a variable bit rate, 1 means with a constant bit rate.
+    pub cbr_flag: bool,
+}
+
+impl HrdParameters {
+    /// Parses the fields defined when the `nal_hrd_parameters_present_flag == 1` or
+    /// the `vcl_hrd_parameters_present_flag == 1` from a bitstream.
+    /// Returns a `HrdParameters` struct.
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        reader.structure("hrd_parameters", |reader| {
+            let cpb_cnt_minus1 = reader.read_exp_golomb_max(31, "cpb_cnt_minus1")?;
+            let bit_rate_scale = reader.read_bits(4, "bit_rate_scale")? as u8;
+            let cpb_size_scale = reader.read_bits(4, "cpb_size_scale")? as u8;
+
+            let mut cpb_specs = Vec::with_capacity(cpb_cnt_minus1 as usize + 1);
+            for _ in 0..=cpb_cnt_minus1 {
+                let bit_rate_value_minus1 = reader.read_exp_golomb_max(MAX_VALUE_MINUS1, "bit_rate_value_minus1")? as u32;
+                let cpb_size_value_minus1 = reader.read_exp_golomb_max(MAX_VALUE_MINUS1, "cpb_size_value_minus1")? as u32;
+                let cbr_flag = reader.read_bit("cbr_flag")?;
+
+                cpb_specs.push(CpbSpec {
+                    bit_rate_value_minus1,
+                    cpb_size_value_minus1,
+                    cbr_flag,
+                });
+            }
+
+            Ok(HrdParameters {
+                bit_rate_scale,
+                cpb_size_scale,
+                cpb_specs,
+                initial_cpb_removal_delay_length_minus1: reader.read_bits(5, "initial_cpb_removal_delay_length_minus1")?
+                    as u8,
+                cpb_removal_delay_length_minus1: reader.read_bits(5, "cpb_removal_delay_length_minus1")? as u8,
+                dpb_output_delay_length_minus1: reader.read_bits(5, "dpb_output_delay_length_minus1")? as u8,
+                time_offset_length: reader.read_bits(5, "time_offset_length")? as u8,
+            })
+        })
+    }
+
+    /// Builds the HrdParameters struct into a byte stream.
+    /// Returns a built byte stream.
+    ///
+    /// Fails if there are no CPB specifications or more than 32.
+    pub fn build<T: io::Write>(&self, writer: &mut BitWriter<T>) -> io::Result<()> {
+        if !(1..=32).contains(&self.cpb_specs.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "hrd parameters must have between 1 and 32 cpb specs",
+            ));
+        }
+
+        writer.write_exp_golomb(self.cpb_specs.len() as u64 - 1)?;
+        writer.write_bits(self.bit_rate_scale as u64, 4)?;
+        writer.write_bits(self.cpb_size_scale as u64, 4)?;
+
+        for cpb_spec in &self.cpb_specs {
+            writer.write_exp_golomb(cpb_spec.bit_rate_value_minus1 as u64)?;
+            writer.write_exp_golomb(cpb_spec.cpb_size_value_minus1 as u64)?;
+            writer.write_bit(cpb_spec.cbr_flag)?;
+        }
+
+        writer.write_bits(self.initial_cpb_removal_delay_length_minus1 as u64, 5)?;
+        writer.write_bits(self.cpb_removal_delay_length_minus1 as u64, 5)?;
+        writer.write_bits(self.dpb_output_delay_length_minus1 as u64, 5)?;
+        writer.write_bits(self.time_offset_length as u64, 5)?;
+        Ok(())
+    }
+
+    /// Returns the total bits of the HrdParameters struct.
+    ///
+    /// Note that this isn't the bytesize since aligning it may cause some values to be different.
+    pub fn bitsize(&self) -> u64 {
+        size_of_exp_golomb(self.cpb_specs.len().saturating_sub(1) as u64) +
+        4 + // bit_rate_scale
+        4 + // cpb_size_scale
+        self.cpb_specs.iter().map(|cpb_spec| {
+            size_of_exp_golomb(cpb_spec.bit_rate_value_minus1 as u64) +
+            size_of_exp_golomb(cpb_spec.cpb_size_value_minus1 as u64) +
+            1 // cbr_flag
+        }).sum::<u64>() +
+        20 // the four 5 bit lengths
+    }
+
+    /// Returns the total bytes of the HrdParameters struct.
+    ///
+    /// Note that this calls [`HrdParameters::bitsize()`] and calculates the number of bytes
+    /// including any necessary padding such that the bitstream is byte aligned.
+    pub fn bytesize(&self) -> u64 {
+        self.bitsize().div_ceil(8)
+    }
+
+    /// Returns the bit rate of the CPB specification at `index` in bits per second.
+    ///
+    /// `bit_rate = (bit_rate_value_minus1 + 1) * 2^(6 + bit_rate_scale)`
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.2
+    pub fn bit_rate(&self, index: usize) -> Option<u64> {
+        let cpb_spec = self.cpb_specs.get(index)?;
+        Some((cpb_spec.bit_rate_value_minus1 as u64 + 1) << (6 + (self.bit_rate_scale & 0xF)))
+    }
+
+    /// Returns the CPB size of the CPB specification at `index` in bits.
+    ///
+    /// `cpb_size = (cpb_size_value_minus1 + 1) * 2^(4 + cpb_size_scale)`
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.2
+    pub fn cpb_size(&self, index: usize) -> Option<u64> {
+        let cpb_spec = self.cpb_specs.get(index)?;
+        Some((cpb_spec.cpb_size_value_minus1 as u64 + 1) << (4 + (self.cpb_size_scale & 0xF)))
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use scuffle_bytes_util::{BitReader, BitWriter};
+    use scuffle_expgolomb::BitWriterExpGolombExt;
+
+    use crate::io::SyntaxReader;
+    use crate::sps::{CpbSpec, HrdParameters};
+
+    #[test]
+    fn test_build_size_hrd_parameters() {
+        // create bitstream for hrd_parameters
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        writer.write_exp_golomb(1).unwrap(); // cpb_cnt_minus1
+        writer.write_bits(4, 4).unwrap(); // bit_rate_scale
+        writer.write_bits(3, 4).unwrap(); // cpb_size_scale
+        writer.write_exp_golomb(78124).unwrap(); // bit_rate_value_minus1
+        writer.write_exp_golomb(156249).unwrap(); // cpb_size_value_minus1
+        writer.write_bit(true).unwrap(); // cbr_flag
+        writer.write_exp_golomb(0).unwrap(); // bit_rate_value_minus1
+        writer.write_exp_golomb(0).unwrap(); // cpb_size_value_minus1
+        writer.write_bit(false).unwrap(); // cbr_flag
+        writer.write_bits(23, 5).unwrap(); // initial_cpb_removal_delay_length_minus1
+        writer.write_bits(23, 5).unwrap(); // cpb_removal_delay_length_minus1
+        writer.write_bits(23, 5).unwrap(); // dpb_output_delay_length_minus1
+        writer.write_bits(24, 5).unwrap(); // time_offset_length
+        writer.finish().unwrap();
+
+        // parse bitstream
+        let mut reader = BitReader::new_from_slice(&mut data);
+        let hrd_parameters = HrdParameters::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
+
+        assert_eq!(
+            hrd_parameters.cpb_specs,
+            vec![
+                CpbSpec {
+                    bit_rate_value_minus1: 78124,
+                    cpb_size_value_minus1: 156249,
+                    cbr_flag: true,
+                },
+                CpbSpec {
+                    bit_rate_value_minus1: 0,
+                    cpb_size_value_minus1: 0,
+                    cbr_flag: false,
+                },
+            ]
+        );
+        assert_eq!(hrd_parameters.time_offset_length, 24);
+        // 78125 * 2^10 and 156250 * 2^7
+        assert_eq!(hrd_parameters.bit_rate(0), Some(80_000_000));
+        assert_eq!(hrd_parameters.cpb_size(0), Some(20_000_000));
+        assert_eq!(hrd_parameters.bit_rate(1), Some(1024));
+        assert_eq!(hrd_parameters.cpb_size(2), None);
+
+        // create a writer for the builder
+        let mut buf = Vec::new();
+        let mut writer2 = BitWriter::new(&mut buf);
+
+        // build from the example result
+        hrd_parameters.build(&mut writer2).unwrap();
+        writer2.finish().unwrap();
+
+        assert_eq!(buf, data);
+
+        // now we re-parse so we can compare the bit sizes.
+        // create a reader for the parser
+        let mut reader2 = BitReader::new_from_slice(buf);
+        let rebuilt_hrd_parameters = HrdParameters::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
+
+        // now we can check the size:
+        assert_eq!(rebuilt_hrd_parameters.bitsize(), hrd_parameters.bitsize());
+        assert_eq!(rebuilt_hrd_parameters.bytesize(), hrd_parameters.bytesize());
+    }
+
+    #[test]
+    fn test_parse_hrd_parameters_out_of_range() {
+        let mut data = Vec::new();
+        let mut writer = BitWriter::new(&mut data);
+
+        writer.write_exp_golomb(32).unwrap(); // cpb_cnt_minus1
+        writer.finish().unwrap();
+
+        let mut reader = BitReader::new_from_slice(&mut data);
+        let err = HrdParameters::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "hrd_parameters.cpb_cnt_minus1 at bit 0: value is out of range"
+        );
+    }
+
+    #[test]
+    fn test_build_hrd_parameters_no_cpb_specs() {
+        let hrd_parameters = HrdParameters {
+            bit_rate_scale: 0,
+            cpb_size_scale: 0,
+            cpb_specs: Vec::new(),
+            initial_cpb_removal_delay_length_minus1: 23,
+            cpb_removal_delay_length_minus1: 23,
+            dpb_output_delay_length_minus1: 23,
+            time_offset_length: 24,
+        };
+
+        let mut writer = BitWriter::new(Vec::new());
+        assert!(hrd_parameters.build(&mut writer).is_err());
+    }
+}
@@ -1,3 +1,6 @@
+mod bitstream_restriction;
+pub use self::bitstream_restriction::BitstreamRestriction;
+
 mod chroma_sample_loc;
 use self::chroma_sample_loc::ChromaSampleLoc;
 
@@ -7,6 +10,9 @@ use self::color_config::ColorConfig;
 mod frame_crop_info;
 use self::frame_crop_info::FrameCropInfo;
 
+mod hrd_parameters;
+pub use self::hrd_parameters::{CpbSpec, HrdParameters};
+
 mod pic_order_count_type1;
 use self::pic_order_count_type1::PicOrderCountType1;
 
@@ -19,12 +25,18 @@ pub use self::sps_ext::SpsExtended;
 mod timing_info;
 use std::io;
 
-use byteorder::ReadBytesExt;
 use scuffle_bytes_util::{BitReader, BitWriter};
-use scuffle_expgolomb::{BitReaderExpGolombExt, BitWriterExpGolombExt, size_of_exp_golomb};
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb};
 
 pub use self::timing_info::TimingInfo;
-use crate::{EmulationPreventionIo, NALUnitType};
+use crate::io::SyntaxReader;
+use crate::{EmulationPreventionIo, H264ParseError, NALUnitType};
+
+/// The largest picture width or height in macroblocks accepted by [`Sps::parse`].
+///
+/// No level allows pictures anywhere near this size, the limit only keeps the
+/// size computations from overflowing.
+const MAX_PIC_SIZE_IN_MBS: u64 = u16::MAX as u64;
 
 /// The Sequence Parameter Set.
 /// ISO/IEC-14496-10-2022 - 7.3.2
@@ -352,27 +364,85 @@ pub struct Sps {
     ///
     /// Refer to the TimingInfo struct for more info.
     pub timing_info: Option<TimingInfo>,
+
+    /// The `fixed_frame_rate_flag` is a single bit.
+    ///
+    /// It is only present if `timing_info_present_flag` is set, otherwise it is false.
+    ///
+    /// 1 means the time between two consecutive pictures in output order is constant.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub fixed_frame_rate_flag: bool,
+
+    /// An optional `HrdParameters` for the NAL HRD.
+    ///
+    /// If `nal_hrd_parameters_present_flag` is set, then the `HrdParameters` will be read and stored.
+    /// They describe the conformance of the whole NAL unit stream, including SEI and filler data.
+    ///
+    /// Refer to the HrdParameters struct for more info.
+    pub nal_hrd_parameters: Option<HrdParameters>,
+
+    /// An optional `HrdParameters` for the VCL HRD.
+    ///
+    /// If `vcl_hrd_parameters_present_flag` is set, then the `HrdParameters` will be read and stored.
+    /// They describe the conformance of the slice data only.
+    ///
+    /// Refer to the HrdParameters struct for more info.
+    pub vcl_hrd_parameters: Option<HrdParameters>,
+
+    /// The `low_delay_hrd_flag` is a single bit.
+    ///
+    /// It is only present if either of the HRD parameters is present, otherwise it is false.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub low_delay_hrd_flag: bool,
+
+    /// The `pic_struct_present_flag` is a single bit.
+    ///
+    /// 1 means picture timing SEI messages contain the `pic_struct` syntax element.
+    ///
+    /// ISO/IEC-14496-10-2022 - E.2.1
+    pub pic_struct_present_flag: bool,
+
+    /// An optional `BitstreamRestriction`.
+    ///
+    /// If `bitstream_restriction_flag` is set, then the `BitstreamRestriction` will be read and stored.
+    ///
+    /// Refer to the BitstreamRestriction struct for more info.
+    pub bitstream_restriction: Option<BitstreamRestriction>,
 }
 
 impl Sps {
     /// Parses an Sps from the input bytes.
     ///
-    /// Returns an `Sps` struct.
-    pub fn parse(reader: impl io::Read) -> io::Result<Self> {
+    /// Returns an `Sps` struct, or an [`H264ParseError`] naming the syntax element that could not be parsed.
+    pub fn parse(reader: impl io::Read) -> Result<Self, H264ParseError> {
         let mut bit_reader = BitReader::new(reader);
+        let mut reader = SyntaxReader::new(&mut bit_reader, "nal_unit");
 
-        let forbidden_zero_bit = bit_reader.read_bit()?;
+        let forbidden_zero_bit = reader.read_bit("forbidden_zero_bit")?;
         if forbidden_zero_bit {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Forbidden zero bit is set"));
+            return Err(reader.invalid("forbidden_zero_bit", "forbidden zero bit is set"));
         }
 
-        let nal_ref_idc = bit_reader.read_bits(2)? as u8;
-        let nal_unit_type = bit_reader.read_bits(5)? as u8;
+        let nal_ref_idc = reader.read_bits(2, "nal_ref_idc")? as u8;
+        let nal_unit_type = reader.read_bits(5, "nal_unit_type")? as u8;
         if NALUnitType(nal_unit_type) != NALUnitType::SPS {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "NAL unit type is not SPS"));
+            return Err(reader.invalid("nal_unit_type", "NAL unit type is not SPS"));
         }
 
-        let profile_idc = bit_reader.read_u8()?;
+        reader.structure("seq_parameter_set_data", |reader| {
+            Self::parse_data(reader, nal_ref_idc, nal_unit_type)
+        })
+    }
+
+    /// Parses the `seq_parameter_set_data` following the NAL unit header.
+    fn parse_data<T: io::Read>(
+        reader: &mut SyntaxReader<'_, T>,
+        nal_ref_idc: u8,
+        nal_unit_type: u8,
+    ) -> Result<Self, H264ParseError> {
+        let profile_idc = reader.read_u8("profile_idc")?;
 
         let constraint_set0_flag;
         let constraint_set1_flag;
@@ -382,85 +452,105 @@ impl Sps {
             // 7.4.2.1.1
             44 | 100 | 110 | 122 | 244 => {
                 // constraint_set0 thru 2 must be false in this case
-                bit_reader.read_bits(3)?;
+                reader.read_bits(3, "constraint_set0_flag")?;
                 constraint_set0_flag = false;
                 constraint_set1_flag = false;
                 constraint_set2_flag = false;
             }
             _ => {
                 // otherwise we parse the bits as expected
-                constraint_set0_flag = bit_reader.read_bit()?;
-                constraint_set1_flag = bit_reader.read_bit()?;
-                constraint_set2_flag = bit_reader.read_bit()?;
+                constraint_set0_flag = reader.read_bit("constraint_set0_flag")?;
+                constraint_set1_flag = reader.read_bit("constraint_set1_flag")?;
+                constraint_set2_flag = reader.read_bit("constraint_set2_flag")?;
             }
         }
 
         let constraint_set3_flag = if profile_idc == 44 {
-            bit_reader.read_bit()?;
+            reader.read_bit("constraint_set3_flag")?;
             false
         } else {
-            bit_reader.read_bit()?
+            reader.read_bit("constraint_set3_flag")?
         };
 
         let constraint_set4_flag = match profile_idc {
             // 7.4.2.1.1
-            77 | 88 | 100 | 118 | 128 | 134 => bit_reader.read_bit()?,
+            77 | 88 | 100 | 118 | 128 | 134 => reader.read_bit("constraint_set4_flag")?,
             _ => {
-                bit_reader.read_bit()?;
+                reader.read_bit("constraint_set4_flag")?;
                 false
             }
         };
 
         let constraint_set5_flag = match profile_idc {
-            77 | 88 | 100 | 118 => bit_reader.read_bit()?,
+            77 | 88 | 100 | 118 => reader.read_bit("constraint_set5_flag")?,
             _ => {
-                bit_reader.read_bit()?;
+                reader.read_bit("constraint_set5_flag")?;
                 false
             }
         };
-        // reserved_zero_2bits
-        bit_reader.read_bits(2)?;
+        reader.read_bits(2, "reserved_zero_2bits")?;
 
-        let level_idc = bit_reader.read_u8()?;
-        let seq_parameter_set_id = bit_reader.read_exp_golomb()? as u16;
+        let level_idc = reader.read_u8("level_idc")?;
+        let seq_parameter_set_id = reader.read_exp_golomb_max(31, "seq_parameter_set_id")? as u16;
 
         let sps_ext = match profile_idc {
             100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135 => {
-                Some(SpsExtended::parse(&mut bit_reader)?)
+                Some(SpsExtended::parse_syntax(reader)?)
             }
             _ => None,
         };
 
-        let log2_max_frame_num_minus4 = bit_reader.read_exp_golomb()? as u8;
-        let pic_order_cnt_type = bit_reader.read_exp_golomb()? as u8;
+        let log2_max_frame_num_minus4 = reader.read_exp_golomb_max(12, "log2_max_frame_num_minus4")? as u8;
+        let pic_order_cnt_type = reader.read_exp_golomb_max(2, "pic_order_cnt_type")? as u8;
 
         let mut log2_max_pic_order_cnt_lsb_minus4 = None;
         let mut pic_order_cnt_type1 = None;
 
         if pic_order_cnt_type == 0 {
-            log2_max_pic_order_cnt_lsb_minus4 = Some(bit_reader.read_exp_golomb()? as u8);
+            log2_max_pic_order_cnt_lsb_minus4 =
+                Some(reader.read_exp_golomb_max(12, "log2_max_pic_order_cnt_lsb_minus4")? as u8);
         } else if pic_order_cnt_type == 1 {
-            pic_order_cnt_type1 = Some(PicOrderCountType1::parse(&mut bit_reader)?)
+            pic_order_cnt_type1 = Some(PicOrderCountType1::parse(reader)?)
         }
 
-        let max_num_ref_frames = bit_reader.read_exp_golomb()? as u8;
-        let gaps_in_frame_num_value_allowed_flag = bit_reader.read_bit()?;
-        let pic_width_in_mbs_minus1 = bit_reader.read_exp_golomb()?;
-        let pic_height_in_map_units_minus1 = bit_reader.read_exp_golomb()?;
+        let max_num_ref_frames = reader.read_exp_golomb_max(16, "max_num_ref_frames")? as u8;
+        let gaps_in_frame_num_value_allowed_flag = reader.read_bit("gaps_in_frame_num_value_allowed_flag")?;
+        let pic_width_in_mbs_minus1 = reader.read_exp_golomb_max(MAX_PIC_SIZE_IN_MBS, "pic_width_in_mbs_minus1")?;
+        let pic_height_in_map_units_minus1 =
+            reader.read_exp_golomb_max(MAX_PIC_SIZE_IN_MBS, "pic_height_in_map_units_minus1")?;
 
-        let frame_mbs_only_flag = bit_reader.read_bit()?;
+        let frame_mbs_only_flag = reader.read_bit("frame_mbs_only_flag")?;
         let mut mb_adaptive_frame_field_flag = None;
         if !frame_mbs_only_flag {
-            mb_adaptive_frame_field_flag = Some(bit_reader.read_bit()?);
+            mb_adaptive_frame_field_flag = Some(reader.read_bit("mb_adaptive_frame_field_flag")?);
         }
 
-        let direct_8x8_inference_flag = bit_reader.read_bit()?;
+        let direct_8x8_inference_flag = reader.read_bit("direct_8x8_inference_flag")?;
 
         let mut frame_crop_info = None;
 
-        let frame_cropping_flag = bit_reader.read_bit()?;
+        let frame_cropping_flag = reader.read_bit("frame_cropping_flag")?;
         if frame_cropping_flag {
-            frame_crop_info = Some(FrameCropInfo::parse(&mut bit_reader)?)
+            let crop = FrameCropInfo::parse(reader)?;
+
+            // The crop offsets must leave a picture, this also rejects offsets large enough to overflow.
+            let frame_height_in_mbs = (2 - frame_mbs_only_flag as u64) * (pic_height_in_map_units_minus1 + 1);
+            let fits = |size_in_mbs: u64, start: u64, end: u64| {
+                start
+                    .checked_add(end)
+                    .and_then(|offset| offset.checked_mul(2))
+                    .is_some_and(|offset| offset < size_in_mbs * 16)
+            };
+            if !fits(
+                pic_width_in_mbs_minus1 + 1,
+                crop.frame_crop_left_offset,
+                crop.frame_crop_right_offset,
+            ) || !fits(frame_height_in_mbs, crop.frame_crop_top_offset, crop.frame_crop_bottom_offset)
+            {
+                return Err(reader.invalid("frame_crop_bottom_offset", "frame crop offsets are larger than the picture"));
+            }
+
+            frame_crop_info = Some(crop)
         }
 
         // setting default values for vui section
@@ -469,42 +559,73 @@ impl Sps {
         let mut color_config = None;
         let mut chroma_sample_loc = None;
         let mut timing_info = None;
-
-        let vui_parameters_present_flag = bit_reader.read_bit()?;
+        let mut fixed_frame_rate_flag = false;
+        let mut nal_hrd_parameters = None;
+        let mut vcl_hrd_parameters = None;
+        let mut low_delay_hrd_flag = false;
+        let mut pic_struct_present_flag = false;
+        let mut bitstream_restriction = None;
+
+        let vui_parameters_present_flag = reader.read_bit("vui_parameters_present_flag")?;
         if vui_parameters_present_flag {
-            // We read the VUI parameters to get the frame rate.
+            reader.structure("vui_parameters", |reader| {
+                let aspect_ratio_info_present_flag = reader.read_bit("aspect_ratio_info_present_flag")?;
+                if aspect_ratio_info_present_flag {
+                    sample_aspect_ratio = Some(SarDimensions::parse(reader)?)
+                }
 
-            let aspect_ratio_info_present_flag = bit_reader.read_bit()?;
-            if aspect_ratio_info_present_flag {
-                sample_aspect_ratio = Some(SarDimensions::parse(&mut bit_reader)?)
-            }
+                let overscan_info_present_flag = reader.read_bit("overscan_info_present_flag")?;
+                if overscan_info_present_flag {
+                    overscan_appropriate_flag = Some(reader.read_bit("overscan_appropriate_flag")?);
+                }
 
-            let overscan_info_present_flag = bit_reader.read_bit()?;
-            if overscan_info_present_flag {
-                overscan_appropriate_flag = Some(bit_reader.read_bit()?);
-            }
+                let video_signal_type_present_flag = reader.read_bit("video_signal_type_present_flag")?;
+                if video_signal_type_present_flag {
+                    color_config = Some(ColorConfig::parse(reader)?)
+                }
 
-            let video_signal_type_present_flag = bit_reader.read_bit()?;
-            if video_signal_type_present_flag {
-                color_config = Some(ColorConfig::parse(&mut bit_reader)?)
-            }
+                let chroma_loc_info_present_flag = reader.read_bit("chroma_loc_info_present_flag")?;
+                if sps_ext.as_ref().unwrap_or(&SpsExtended::default()).chroma_format_idc != 1 && chroma_loc_info_present_flag
+                {
+                    return Err(reader.invalid(
+                        "chroma_loc_info_present_flag",
+                        "chroma_loc_info_present_flag cannot be set to 1 when chroma_format_idc is not 1",
+                    ));
+                }
 
-            let chroma_loc_info_present_flag = bit_reader.read_bit()?;
-            if sps_ext.as_ref().unwrap_or(&SpsExtended::default()).chroma_format_idc != 1 && chroma_loc_info_present_flag {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "chroma_loc_info_present_flag cannot be set to 1 when chroma_format_idc is not 1",
-                ));
-            }
+                if chroma_loc_info_present_flag {
+                    chroma_sample_loc = Some(ChromaSampleLoc::parse(reader)?)
+                }
 
-            if chroma_loc_info_present_flag {
-                chroma_sample_loc = Some(ChromaSampleLoc::parse(&mut bit_reader)?)
-            }
+                let timing_info_present_flag = reader.read_bit("timing_info_present_flag")?;
+                if timing_info_present_flag {
+                    timing_info = Some(TimingInfo::parse_syntax(reader)?);
+                    fixed_frame_rate_flag = reader.read_bit("fixed_frame_rate_flag")?;
+                }
 
-            let timing_info_present_flag = bit_reader.read_bit()?;
-            if timing_info_present_flag {
-                timing_info = Some(TimingInfo::parse(&mut bit_reader)?)
-            }
+                let nal_hrd_parameters_present_flag = reader.read_bit("nal_hrd_parameters_present_flag")?;
+                if nal_hrd_parameters_present_flag {
+                    nal_hrd_parameters = Some(HrdParameters::parse(reader)?);
+                }
+
+                let vcl_hrd_parameters_present_flag = reader.read_bit("vcl_hrd_parameters_present_flag")?;
+                if vcl_hrd_parameters_present_flag {
+                    vcl_hrd_parameters = Some(HrdParameters::parse(reader)?);
+                }
+
+                if nal_hrd_parameters_present_flag || vcl_hrd_parameters_present_flag {
+                    low_delay_hrd_flag = reader.read_bit("low_delay_hrd_flag")?;
+                }
+
+                pic_struct_present_flag = reader.read_bit("pic_struct_present_flag")?;
+
+                let bitstream_restriction_flag = reader.read_bit("bitstream_restriction_flag")?;
+                if bitstream_restriction_flag {
+                    bitstream_restriction = Some(BitstreamRestriction::parse(reader)?);
+                }
+
+                Ok(())
+            })?;
         }
 
         Ok(Sps {
@@ -536,11 +657,19 @@ impl Sps {
             color_config,
             chroma_sample_loc,
             timing_info,
+            fixed_frame_rate_flag,
+            nal_hrd_parameters,
+            vcl_hrd_parameters,
+            low_delay_hrd_flag,
+            pic_struct_present_flag,
+            bitstream_restriction,
         })
     }
 
-    /// Builds the Sps struct into a byte stream.
-    /// Returns a built byte stream.
+    /// Builds the Sps struct into a byte stream, including the NAL unit header and the `rbsp_trailing_bits`.
+    ///
+    /// The output does not contain emulation prevention bytes, use [`Sps::build_with_emulation_prevention`]
+    /// to get a NAL unit that can be sent to a decoder.
     pub fn build(&self, writer: impl io::Write) -> io::Result<()> {
         let mut bit_writer = BitWriter::new(writer);
 
@@ -592,51 +721,67 @@ impl Sps {
             frame_crop_info.build(&mut bit_writer)?;
         }
 
-        match (
-            &self.sample_aspect_ratio,
-            &self.overscan_appropriate_flag,
-            &self.color_config,
-            &self.chroma_sample_loc,
-            &self.timing_info,
-        ) {
-            (None, None, None, None, None) => {
-                bit_writer.write_bit(false)?;
+        // vui_parameters_present_flag
+        bit_writer.write_bit(self.has_vui_parameters())?;
+        if self.has_vui_parameters() {
+            // aspect_ratio_info_present_flag
+            bit_writer.write_bit(self.sample_aspect_ratio.is_some())?;
+            if let Some(sar) = &self.sample_aspect_ratio {
+                sar.build(&mut bit_writer)?;
             }
-            _ => {
-                // vui_parameters_present_flag
-                bit_writer.write_bit(true)?;
 
-                // aspect_ratio_info_present_flag
-                bit_writer.write_bit(self.sample_aspect_ratio.is_some())?;
-                if let Some(sar) = &self.sample_aspect_ratio {
-                    sar.build(&mut bit_writer)?;
-                }
+            // overscan_info_present_flag
+            bit_writer.write_bit(self.overscan_appropriate_flag.is_some())?;
+            if let Some(overscan) = &self.overscan_appropriate_flag {
+                bit_writer.write_bit(*overscan)?;
+            }
 
-                // overscan_info_present_flag
-                bit_writer.write_bit(self.overscan_appropriate_flag.is_some())?;
-                if let Some(overscan) = &self.overscan_appropriate_flag {
-                    bit_writer.write_bit(*overscan)?;
-                }
+            // video_signal_type_prsent_flag
+            bit_writer.write_bit(self.color_config.is_some())?;
+            if let Some(color) = &self.color_config {
+                color.build(&mut bit_writer)?;
+            }
 
-                // video_signal_type_prsent_flag
-                bit_writer.write_bit(self.color_config.is_some())?;
-                if let Some(color) = &self.color_config {
-                    color.build(&mut bit_writer)?;
-                }
+            // chroma_log_info_present_flag
+            bit_writer.write_bit(self.chroma_sample_loc.is_some())?;
+            if let Some(chroma) = &self.chroma_sample_loc {
+                chroma.build(&mut bit_writer)?;
+            }
 
-                // chroma_log_info_present_flag
-                bit_writer.write_bit(self.chroma_sample_loc.is_some())?;
-                if let Some(chroma) = &self.chroma_sample_loc {
-                    chroma.build(&mut bit_writer)?;
-                }
+            // timing_info_present_flag
+            bit_writer.write_bit(self.timing_info.is_some())?;
+            if let Some(timing) = &self.timing_info {
+                timing.build(&mut bit_writer)?;
+                bit_writer.write_bit(self.fixed_frame_rate_flag)?;
+            }
 
-                // timing_info_present_flag
-                bit_writer.write_bit(self.timing_info.is_some())?;
-                if let Some(timing) = &self.timing_info {
-                    timing.build(&mut bit_writer)?;
-                }
+            // nal_hrd_parameters_present_flag
+            bit_writer.write_bit(self.nal_hrd_parameters.is_some())?;
+            if let Some(hrd) = &self.nal_hrd_parameters {
+                hrd.build(&mut bit_writer)?;
+            }
+
+            // vcl_hrd_parameters_present_flag
+            bit_writer.write_bit(self.vcl_hrd_parameters.is_some())?;
+            if let Some(hrd) = &self.vcl_hrd_parameters {
+                hrd.build(&mut bit_writer)?;
+            }
+
+            if self.nal_hrd_parameters.is_some() || self.vcl_hrd_parameters.is_some() {
+                bit_writer.write_bit(self.low_delay_hrd_flag)?;
+            }
+
+            bit_writer.write_bit(self.pic_struct_present_flag)?;
+
+            // bitstream_restriction_flag
+            bit_writer.write_bit(self.bitstream_restriction.is_some())?;
+            if let Some(restriction) = &self.bitstream_restriction {
+                restriction.build(&mut bit_writer)?;
             }
         }
+
+        // rbsp_trailing_bits, the stop bit followed by zero bits up to the byte boundary
+        bit_writer.write_bit(true)?;
         bit_writer.finish()?;
 
         Ok(())
@@ -644,19 +789,20 @@ impl Sps {
 
     /// Parses the Sps struct from a reader that may contain emulation prevention bytes.
     /// Is the same as calling [`Self::parse`] with an [`EmulationPreventionIo`] wrapper.
-    pub fn parse_with_emulation_prevention(reader: impl io::Read) -> io::Result<Self> {
+    pub fn parse_with_emulation_prevention(reader: impl io::Read) -> Result<Self, H264ParseError> {
         Self::parse(EmulationPreventionIo::new(reader))
     }
 
     /// Builds the Sps struct into a byte stream that may contain emulation prevention bytes.
     /// Is the same as calling [`Self::build`] with an [`EmulationPreventionIo`] wrapper.
-    pub fn build_with_emulation_prevention(self, writer: impl io::Write) -> io::Result<()> {
+    pub fn build_with_emulation_prevention(&self, writer: impl io::Write) -> io::Result<()> {
         self.build(EmulationPreventionIo::new(writer))
     }
 
     /// Returns the total byte size of the Sps struct.
     pub fn size(&self) -> u64 {
-        (1 + // forbidden zero bit
+        (
+            1 + // forbidden zero bit
         2 + // nal_ref_idc
         5 + // nal_unit_type
         8 + // profile_idc
@@ -681,18 +827,24 @@ impl Sps {
         1 + // frame_cropping_flag
         self.frame_crop_info.as_ref().map_or(0, |frame| frame.bitsize()) +
         1 + // vui_parameters_present_flag
-        if matches!(
-            (&self.sample_aspect_ratio, &self.overscan_appropriate_flag, &self.color_config, &self.chroma_sample_loc, &self.timing_info),
-            (None, None, None, None, None)
-        ) {
-            0
-        } else {
+        if self.has_vui_parameters() {
             self.sample_aspect_ratio.as_ref().map_or(1, |sar| 1 + sar.bitsize()) +
             self.overscan_appropriate_flag.map_or(1, |_| 2) +
             self.color_config.as_ref().map_or(1, |color| 1 + color.bitsize()) +
             self.chroma_sample_loc.as_ref().map_or(1, |chroma| 1 + chroma.bitsize()) +
-            self.timing_info.as_ref().map_or(1, |timing| 1 + timing.bitsize())
-        }).div_ceil(8)
+            self.timing_info.as_ref().map_or(1, |timing| 1 + timing.bitsize() + 1) + // fixed_frame_rate_flag
+            self.nal_hrd_parameters.as_ref().map_or(1, |hrd| 1 + hrd.bitsize()) +
+            self.vcl_hrd_parameters.as_ref().map_or(1, |hrd| 1 + hrd.bitsize()) +
+            (self.nal_hrd_parameters.is_some() || self.vcl_hrd_parameters.is_some()) as u64 + // low_delay_hrd_flag
+            1 + // pic_struct_present_flag
+            self.bitstream_restriction.as_ref().map_or(1, |restriction| 1 + restriction.bitsize())
+        } else {
+            0
+        } +
+        1
+            // rbsp_stop_one_bit
+        )
+        .div_ceil(8)
     }
 
     /// The height as a u64. This is computed from other fields, and isn't directly set.
@@ -703,26 +855,52 @@ impl Sps {
     /// We don't directly store `frame_mbs_only_flag` since we can tell if it's set:
     /// If `mb_adaptive_frame_field_flag` is None, then `frame_mbs_only_flag` is set (1).
     /// Otherwise `mb_adaptive_frame_field_flag` unset (0).
+    ///
+    /// Out of range fields, which [`Sps::parse`] never returns, saturate instead of overflowing.
     pub fn height(&self) -> u64 {
-        let base_height =
-            (2 - self.mb_adaptive_frame_field_flag.is_none() as u64) * (self.pic_height_in_map_units_minus1 + 1) * 16;
+        let base_height = (2 - self.mb_adaptive_frame_field_flag.is_none() as u64)
+            .saturating_mul(self.pic_height_in_map_units_minus1.saturating_add(1))
+            .saturating_mul(16);
 
         self.frame_crop_info.as_ref().map_or(base_height, |crop| {
-            base_height - (crop.frame_crop_top_offset + crop.frame_crop_bottom_offset) * 2
+            base_height.saturating_sub(
+                crop.frame_crop_top_offset
+                    .saturating_add(crop.frame_crop_bottom_offset)
+                    .saturating_mul(2),
+            )
         })
     }
 
     /// The width as a u64. This is computed from other fields, and isn't directly set.
     ///
     /// `width = ((pic_width_in_mbs_minus1 + 1) * 16) - frame_crop_right_offset * 2 - frame_crop_left_offset * 2`
+    ///
+    /// Out of range fields, which [`Sps::parse`] never returns, saturate instead of overflowing.
     pub fn width(&self) -> u64 {
-        let base_width = (self.pic_width_in_mbs_minus1 + 1) * 16;
+        let base_width = self.pic_width_in_mbs_minus1.saturating_add(1).saturating_mul(16);
 
         self.frame_crop_info.as_ref().map_or(base_width, |crop| {
-            base_width - (crop.frame_crop_left_offset + crop.frame_crop_right_offset) * 2
+            base_width.saturating_sub(
+                crop.frame_crop_left_offset
+                    .saturating_add(crop.frame_crop_right_offset)
+                    .saturating_mul(2),
+            )
         })
     }
 
+    /// Returns true if any of the VUI fields are set, so `vui_parameters_present_flag` is 1.
+    fn has_vui_parameters(&self) -> bool {
+        self.sample_aspect_ratio.is_some()
+            || self.overscan_appropriate_flag.is_some()
+            || self.color_config.is_some()
+            || self.chroma_sample_loc.is_some()
+            || self.timing_info.is_some()
+            || self.nal_hrd_parameters.is_some()
+            || self.vcl_hrd_parameters.is_some()
+            || self.pic_struct_present_flag
+            || self.bitstream_restriction.is_some()
+    }
+
     /// Returns the frame rate as a f64.
     ///
     /// If `timing_info_present_flag` is set, then the `frame_rate` will be computed, and
@@ -736,12 +914,13 @@ impl Sps {
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
-    use std::io;
+    use std::num::NonZeroU32;
 
     use scuffle_bytes_util::BitWriter;
     use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
 
-    use crate::sps::Sps;
+    use crate::H264ParseErrorKind;
+    use crate::sps::{CpbSpec, HrdParameters, Sps, TimingInfo};
 
     #[test]
     fn test_parse_sps_set_forbidden_bit() {
@@ -756,8 +935,11 @@ mod tests {
         assert!(result.is_err());
         let err = result.unwrap_err();
 
-        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
-        assert_eq!(err.to_string(), "Forbidden zero bit is set");
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
+        assert_eq!(
+            err.to_string(),
+            "nal_unit.forbidden_zero_bit at bit 0: forbidden zero bit is set"
+        );
     }
 
     #[test]
@@ -775,8 +957,8 @@ mod tests {
         assert!(result.is_err());
         let err = result.unwrap_err();
 
-        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
-        assert_eq!(err.to_string(), "NAL unit type is not SPS");
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
+        assert_eq!(err.to_string(), "nal_unit.nal_unit_type at bit 3: NAL unit type is not SPS");
     }
 
     #[test]
@@ -938,6 +1120,12 @@ mod tests {
                     time_scale: 28800,
                 },
             ),
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
 
@@ -1135,6 +1323,15 @@ mod tests {
         // 960 000 = time_scale
         // time_scale is a u32
         writer.write_bits(960000, 32).unwrap();
+        // fixed_frame_rate_flag
+        writer.write_bit(false).unwrap();
+
+        // nal_hrd_parameters_present_flag, vcl_hrd_parameters_present_flag,
+        // pic_struct_present_flag and bitstream_restriction_flag
+        writer.write_bits(0, 4).unwrap();
+
+        // rbsp_trailing_bits
+        writer.write_bit(true).unwrap();
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -1232,6 +1429,12 @@ mod tests {
                     time_scale: 960000,
                 },
             ),
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
 
@@ -1331,6 +1534,13 @@ mod tests {
 
         // timing_info_present_flag
         writer.write_bit(false).unwrap();
+
+        // nal_hrd_parameters_present_flag, vcl_hrd_parameters_present_flag,
+        // pic_struct_present_flag and bitstream_restriction_flag
+        writer.write_bits(0, 4).unwrap();
+
+        // rbsp_trailing_bits
+        writer.write_bit(true).unwrap();
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -1380,6 +1590,12 @@ mod tests {
                 },
             ),
             timing_info: None,
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
 
@@ -1421,27 +1637,30 @@ mod tests {
         writer.write_exp_golomb(0).unwrap();
         // pic_order_cnt_type is expg
         writer.write_exp_golomb(2).unwrap();
-        // log2_max_pic_order_cnt_lsb_minus4
-        writer.write_exp_golomb(0).unwrap();
+        // log2_max_pic_order_cnt_lsb_minus4 is only present with pic_order_cnt_type 0
 
         // max_num_ref_frames is expg
         writer.write_exp_golomb(0).unwrap();
         // gaps_in_frame_num_value_allowed_flag
-        writer.write_bit(false).unwrap();
-        writer.write_exp_golomb(1).unwrap();
-        writer.write_exp_golomb(2).unwrap();
+        writer.write_bit(true).unwrap();
+        // pic_width_in_mbs_minus1 is expg
+        writer.write_exp_golomb(3).unwrap();
+        // pic_height_in_map_units_minus1 is expg
+        writer.write_exp_golomb(0).unwrap();
 
         // frame_mbs_only_flag
         writer.write_bit(true).unwrap();
 
         // direct_8x8_inference_flag
-        writer.write_bit(false).unwrap();
+        writer.write_bit(true).unwrap();
         // frame_cropping_flag
         writer.write_bit(false).unwrap();
 
-        // enter vui to set redundant parameters so they get reduced
         // vui_parameters_present_flag
         writer.write_bit(false).unwrap();
+
+        // rbsp_trailing_bits
+        writer.write_bit(true).unwrap();
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -1476,6 +1695,12 @@ mod tests {
             color_config: None,
             chroma_sample_loc: None,
             timing_info: None,
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
 
@@ -1587,13 +1812,115 @@ mod tests {
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
+        assert_eq!(
+            err.to_string(),
+            "vui_parameters.chroma_loc_info_present_flag at bit 80: chroma_loc_info_present_flag cannot be set to 1 when chroma_format_idc is not 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_sps_out_of_range() {
+        let mut sps = Vec::new();
+        let mut writer = BitWriter::new(&mut sps);
+
+        // forbidden_zero_bit
+        writer.write_bit(false).unwrap();
+        // nal_ref_idc
+        writer.write_bits(0, 2).unwrap();
+        // nal_unit_type
+        writer.write_bits(7, 5).unwrap();
+        // profile_idc = 66, so there is no extension
+        writer.write_bits(66, 8).unwrap();
+        // constraint_setn_flags
+        writer.write_bits(0, 8).unwrap();
+        // level_idc
+        writer.write_bits(0, 8).unwrap();
+        // seq_parameter_set_id is at most 31, 300 would be truncated to 44 by a u8 cast
+        writer.write_exp_golomb(300).unwrap();
+        writer.finish().unwrap();
+
+        let err = Sps::parse(std::io::Cursor::new(&sps)).unwrap_err();
+        assert!(matches!(err.kind(), H264ParseErrorKind::OutOfRange));
+        assert_eq!(
+            err.to_string(),
+            "seq_parameter_set_data.seq_parameter_set_id at bit 32: value is out of range"
+        );
+    }
+
+    #[test]
+    fn test_parse_sps_crop_too_large() {
+        let mut sps = Vec::new();
+        let mut writer = BitWriter::new(&mut sps);
+
+        // forbidden_zero_bit
+        writer.write_bit(false).unwrap();
+        // nal_ref_idc
+        writer.write_bits(0, 2).unwrap();
+        // nal_unit_type
+        writer.write_bits(7, 5).unwrap();
+        // profile_idc = 66, so there is no extension
+        writer.write_bits(66, 8).unwrap();
+        // constraint_setn_flags
+        writer.write_bits(0, 8).unwrap();
+        // level_idc
+        writer.write_bits(0, 8).unwrap();
+        // seq_parameter_set_id
+        writer.write_exp_golomb(0).unwrap();
+        // log2_max_frame_num_minus4
+        writer.write_exp_golomb(0).unwrap();
+        // pic_order_cnt_type
+        writer.write_exp_golomb(2).unwrap();
+        // max_num_ref_frames
+        writer.write_exp_golomb(1).unwrap();
+        // gaps_in_frame_num_value_allowed_flag
+        writer.write_bit(false).unwrap();
+        // 16x16
+        writer.write_exp_golomb(0).unwrap();
+        writer.write_exp_golomb(0).unwrap();
+        // frame_mbs_only_flag
+        writer.write_bit(true).unwrap();
+        // direct_8x8_inference_flag
+        writer.write_bit(false).unwrap();
+        // frame_cropping_flag
+        writer.write_bit(true).unwrap();
+        // crop 2 * 2 + 2 * 4 = 12 pixels horizontally, this fits
+        writer.write_exp_golomb(2).unwrap();
+        writer.write_exp_golomb(4).unwrap();
+        // crop 2 * 8 = 16 pixels vertically, this leaves nothing
+        writer.write_exp_golomb(8).unwrap();
+        writer.write_exp_golomb(0).unwrap();
+        // vui_parameters_present_flag
+        writer.write_bit(false).unwrap();
+        writer.finish().unwrap();
+
+        let err = Sps::parse(std::io::Cursor::new(&sps)).unwrap_err();
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
         assert_eq!(
             err.to_string(),
-            "chroma_loc_info_present_flag cannot be set to 1 when chroma_format_idc is not 1"
+            "seq_parameter_set_data.frame_crop_bottom_offset at bit 61: frame crop offsets are larger than the picture"
         );
     }
 
+    #[test]
+    fn test_parse_sps_truncated() {
+        let data =
+            b"\x67\x64\x00\x1F\xAC\xD9\x41\xE0\x6D\xF9\xE6\xA0\x20\x20\x28\x00\x00\x00\x08\x00\x00\x01\xE0\x78\xC1\x8C\xB0";
+        Sps::parse_with_emulation_prevention(std::io::Cursor::new(&data[..])).unwrap();
+
+        // Every prefix of a valid sps fails cleanly, without panicking.
+        for len in 0..data.len() - 1 {
+            assert!(Sps::parse_with_emulation_prevention(std::io::Cursor::new(&data[..len])).is_err());
+        }
+
+        // The error points at the element the input ended in.
+        let err = Sps::parse_with_emulation_prevention(std::io::Cursor::new(&data[..4])).unwrap_err();
+        assert!(matches!(err.kind(), H264ParseErrorKind::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof));
+        assert_eq!(err.structure(), "seq_parameter_set_data");
+        assert_eq!(err.element(), "seq_parameter_set_id");
+        assert_eq!(err.bit_offset(), 32);
+    }
+
     #[test]
     fn test_invalid_num_units_in_tick() {
         let mut sps = Vec::new();
@@ -1697,8 +2024,11 @@ mod tests {
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
-        assert_eq!(err.to_string(), "num_units_in_tick cannot be 0");
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
+        assert_eq!(
+            err.to_string(),
+            "vui_parameters.num_units_in_tick at bit 82: num_units_in_tick cannot be 0"
+        );
     }
 
     #[test]
@@ -1804,8 +2134,11 @@ mod tests {
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
-        assert_eq!(err.to_string(), "num_units_in_tick cannot be 0");
+        assert!(matches!(err.kind(), H264ParseErrorKind::Invalid(_)));
+        assert_eq!(
+            err.to_string(),
+            "vui_parameters.num_units_in_tick at bit 82: num_units_in_tick cannot be 0"
+        );
     }
 
     #[test]
@@ -1893,6 +2226,9 @@ mod tests {
 
         // vui_parameters_present_flag
         writer.write_bit(false).unwrap();
+
+        // rbsp_trailing_bits
+        writer.write_bit(true).unwrap();
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -1946,6 +2282,12 @@ mod tests {
             color_config: None,
             chroma_sample_loc: None,
             timing_info: None,
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
 
@@ -2173,6 +2515,18 @@ mod tests {
         // time_scale is a u32
         writer.write_bits(960000, 32).unwrap();
         bit_count += 32;
+        // fixed_frame_rate_flag
+        writer.write_bit(false).unwrap();
+        bit_count += 1;
+
+        // nal_hrd_parameters_present_flag, vcl_hrd_parameters_present_flag,
+        // pic_struct_present_flag and bitstream_restriction_flag
+        writer.write_bits(0, 4).unwrap();
+        bit_count += 4;
+
+        // rbsp_trailing_bits
+        writer.write_bit(true).unwrap();
+        bit_count += 1;
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -2357,6 +2711,10 @@ mod tests {
 
         // timing_info_present_flag
         writer.write_bit(false).unwrap();
+
+        // nal_hrd_parameters_present_flag, vcl_hrd_parameters_present_flag,
+        // pic_struct_present_flag and bitstream_restriction_flag
+        writer.write_bits(0, 4).unwrap();
         writer.finish().unwrap();
 
         let result = Sps::parse(std::io::Cursor::new(&sps)).unwrap();
@@ -2408,7 +2766,74 @@ mod tests {
             color_config: None,
             chroma_sample_loc: None,
             timing_info: None,
+            fixed_frame_rate_flag: false,
+            nal_hrd_parameters: None,
+            vcl_hrd_parameters: None,
+            low_delay_hrd_flag: false,
+            pic_struct_present_flag: false,
+            bitstream_restriction: None,
         }
         ");
     }
+
+    #[test]
+    fn test_build_with_emulation_prevention_round_trip() {
+        // The SPS of a 480x852 stream, its timing info contains emulation prevention bytes
+        // and its VUI ends with the bitstream restriction.
+        let data = b"\x67\x64\x00\x1F\xAC\xD9\x41\xE0\x6D\xF9\xE6\xA0\x20\x20\x28\x00\x00\x03\x00\x08\x00\x00\x03\x01\xE0\x78\xC1\x8C\xB0";
+        let sps = Sps::parse_with_emulation_prevention(&data[..]).unwrap();
+        assert_eq!((sps.width(), sps.height(), sps.frame_rate()), (480, 852, Some(30.0)));
+
+        let mut buf = Vec::new();
+        sps.build_with_emulation_prevention(&mut buf).unwrap();
+        assert_eq!(buf, data);
+        let restriction = sps.bitstream_restriction.as_ref().unwrap();
+        assert_eq!(
+            (restriction.max_num_reorder_frames, restriction.max_dec_frame_buffering),
+            (2, 4)
+        );
+        assert_eq!(Sps::parse_with_emulation_prevention(buf.as_slice()).unwrap(), sps);
+
+        // Modify the frame rate and cropping, and regenerate the NAL unit.
+        let mut modified = sps.clone();
+        modified.timing_info = Some(TimingInfo {
+            num_units_in_tick: NonZeroU32::new(1001).unwrap(),
+            time_scale: NonZeroU32::new(120000).unwrap(),
+        });
+        modified.frame_crop_info = None;
+        modified.fixed_frame_rate_flag = true;
+        modified.nal_hrd_parameters = Some(HrdParameters {
+            bit_rate_scale: 4,
+            cpb_size_scale: 3,
+            cpb_specs: vec![CpbSpec {
+                bit_rate_value_minus1: 78124,
+                cpb_size_value_minus1: 156249,
+                cbr_flag: true,
+            }],
+            initial_cpb_removal_delay_length_minus1: 23,
+            cpb_removal_delay_length_minus1: 23,
+            dpb_output_delay_length_minus1: 23,
+            time_offset_length: 24,
+        });
+        modified.low_delay_hrd_flag = false;
+
+        let mut buf = Vec::new();
+        modified.build_with_emulation_prevention(&mut buf).unwrap();
+        // No start code emulation in the output.
+        assert!(
+            !buf.windows(3)
+                .any(|window| window[0] == 0 && window[1] == 0 && window[2] <= 2)
+        );
+
+        let reparsed = Sps::parse_with_emulation_prevention(buf.as_slice()).unwrap();
+        assert_eq!(reparsed, modified);
+        assert_eq!((reparsed.width(), reparsed.height()), (480, 864));
+        assert_eq!(reparsed.frame_rate(), Some(120000.0 / 2002.0));
+        let hrd = reparsed.nal_hrd_parameters.as_ref().unwrap();
+        assert_eq!((hrd.bit_rate(0), hrd.cpb_size(0)), (Some(80_000_000), Some(20_000_000)));
+
+        let mut raw = Vec::new();
+        modified.build(&mut raw).unwrap();
+        assert_eq!(modified.size(), raw.len() as u64);
+    }
 }
@@ -1,7 +1,10 @@
 use std::io;
 
-use scuffle_bytes_util::{BitReader, BitWriter};
-use scuffle_expgolomb::{BitReaderExpGolombExt, BitWriterExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
+use scuffle_bytes_util::BitWriter;
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
+
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
 
 /// `PicOrderCountType1` contains the fields that are set when `pic_order_cnt_type == 1`.
 ///
@@ -81,15 +84,16 @@ pub struct PicOrderCountType1 {
 impl PicOrderCountType1 {
     /// Parses the fields defined when the `pic_order_count_type == 1` from a bitstream.
     /// Returns a `PicOrderCountType1` struct.
-    pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let delta_pic_order_always_zero_flag = reader.read_bit()?;
-        let offset_for_non_ref_pic = reader.read_signed_exp_golomb()?;
-        let offset_for_top_to_bottom_field = reader.read_signed_exp_golomb()?;
-        let num_ref_frames_in_pic_order_cnt_cycle = reader.read_exp_golomb()?;
-
-        let mut offset_for_ref_frame = vec![];
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let delta_pic_order_always_zero_flag = reader.read_bit("delta_pic_order_always_zero_flag")?;
+        let offset_for_non_ref_pic = reader.read_signed_exp_golomb("offset_for_non_ref_pic")?;
+        let offset_for_top_to_bottom_field = reader.read_signed_exp_golomb("offset_for_top_to_bottom_field")?;
+        let num_ref_frames_in_pic_order_cnt_cycle =
+            reader.read_exp_golomb_max(255, "num_ref_frames_in_pic_order_cnt_cycle")?;
+
+        let mut offset_for_ref_frame = Vec::with_capacity(num_ref_frames_in_pic_order_cnt_cycle as usize);
         for _ in 0..num_ref_frames_in_pic_order_cnt_cycle {
-            offset_for_ref_frame.push(reader.read_signed_exp_golomb()?);
+            offset_for_ref_frame.push(reader.read_signed_exp_golomb("offset_for_ref_frame")?);
         }
 
         Ok(PicOrderCountType1 {
@@ -141,6 +145,7 @@ mod tests {
     use scuffle_bytes_util::{BitReader, BitWriter};
     use scuffle_expgolomb::BitWriterExpGolombExt;
 
+    use crate::io::SyntaxReader;
     use crate::sps::PicOrderCountType1;
 
     #[test]
@@ -162,7 +167,8 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let pic_order_count_type1 = PicOrderCountType1::parse(&mut reader).unwrap();
+        let pic_order_count_type1 =
+            PicOrderCountType1::parse(&mut SyntaxReader::new(&mut reader, "seq_parameter_set_data")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -177,7 +183,8 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_pic_order_count_type1 = PicOrderCountType1::parse(&mut reader2).unwrap();
+        let rebuilt_pic_order_count_type1 =
+            PicOrderCountType1::parse(&mut SyntaxReader::new(&mut reader2, "seq_parameter_set_data")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_pic_order_count_type1.bitsize(), pic_order_count_type1.bitsize());
@@ -1,9 +1,9 @@
 use std::io;
 
-use byteorder::ReadBytesExt;
-use scuffle_bytes_util::{BitReader, BitWriter};
+use scuffle_bytes_util::BitWriter;
 
-use crate::AspectRatioIdc;
+use crate::io::SyntaxReader;
+use crate::{AspectRatioIdc, H264ParseError};
 
 /// `SarDimensions` contains the fields that are set when `aspect_ratio_info_present_flag == 1`,
 /// and `aspect_ratio_idc == 255`.
@@ -44,14 +44,14 @@ pub struct SarDimensions {
 impl SarDimensions {
     /// Parses the fields defined when the `aspect_ratio_info_present_flag == 1` from a bitstream.
     /// Returns a `SarDimensions` struct.
-    pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
+    pub(crate) fn parse<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
         let mut sar_width = 0; // defaults to 0, E.2.1
         let mut sar_height = 0; // deafults to 0, E.2.1
 
-        let aspect_ratio_idc = reader.read_u8()?;
+        let aspect_ratio_idc = reader.read_u8("aspect_ratio_idc")?;
         if aspect_ratio_idc == 255 {
-            sar_width = reader.read_bits(16)? as u16;
-            sar_height = reader.read_bits(16)? as u16;
+            sar_width = reader.read_bits(16, "sar_width")? as u16;
+            sar_height = reader.read_bits(16, "sar_height")? as u16;
         }
 
         Ok(SarDimensions {
@@ -92,6 +92,7 @@ impl SarDimensions {
 mod tests {
     use scuffle_bytes_util::{BitReader, BitWriter};
 
+    use crate::io::SyntaxReader;
     use crate::sps::SarDimensions;
 
     #[test]
@@ -105,7 +106,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let sample_aspect_ratio = SarDimensions::parse(&mut reader).unwrap();
+        let sample_aspect_ratio = SarDimensions::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -120,7 +121,8 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_sample_aspect_ratio = SarDimensions::parse(&mut reader2).unwrap();
+        let rebuilt_sample_aspect_ratio =
+            SarDimensions::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_sample_aspect_ratio.bitsize(), sample_aspect_ratio.bitsize());
@@ -140,7 +142,7 @@ mod tests {
 
         // parse bitstream
         let mut reader = BitReader::new_from_slice(&mut data);
-        let sample_aspect_ratio = SarDimensions::parse(&mut reader).unwrap();
+        let sample_aspect_ratio = SarDimensions::parse(&mut SyntaxReader::new(&mut reader, "vui_parameters")).unwrap();
 
         // create a writer for the builder
         let mut buf = Vec::new();
@@ -155,7 +157,8 @@ mod tests {
         // now we re-parse so we can compare the bit sizes.
         // create a reader for the parser
         let mut reader2 = BitReader::new_from_slice(buf);
-        let rebuilt_sample_aspect_ratio = SarDimensions::parse(&mut reader2).unwrap();
+        let rebuilt_sample_aspect_ratio =
+            SarDimensions::parse(&mut SyntaxReader::new(&mut reader2, "vui_parameters")).unwrap();
 
         // now we can check the size:
         assert_eq!(rebuilt_sample_aspect_ratio.bitsize(), sample_aspect_ratio.bitsize());
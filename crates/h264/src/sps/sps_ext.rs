@@ -1,7 +1,10 @@
 use std::io;
 
 use scuffle_bytes_util::{BitReader, BitWriter};
-use scuffle_expgolomb::{BitReaderExpGolombExt, BitWriterExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
+use scuffle_expgolomb::{BitWriterExpGolombExt, size_of_exp_golomb, size_of_signed_exp_golomb};
+
+use crate::io::SyntaxReader;
+use crate::{H264ParseError, H264ParseErrorKind};
 
 /// The Sequence Parameter Set extension.
 /// ISO/IEC-14496-10-2022 - 7.3.2
@@ -99,17 +102,21 @@ impl SpsExtended {
     /// Parses an extended SPS from a bitstream.
     /// Returns an `SpsExtended` struct.
     pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let chroma_format_idc = reader.read_exp_golomb()? as u8;
+        Ok(Self::parse_syntax(&mut SyntaxReader::new(reader, "seq_parameter_set_data"))?)
+    }
+
+    pub(crate) fn parse_syntax<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let chroma_format_idc = reader.read_exp_golomb_max(3, "chroma_format_idc")? as u8;
         // Defaults to false: ISO/IEC-14496-10-2022 - 7.4.2.1.1
         let mut separate_color_plane_flag = false;
         if chroma_format_idc == 3 {
-            separate_color_plane_flag = reader.read_bit()?;
+            separate_color_plane_flag = reader.read_bit("separate_colour_plane_flag")?;
         }
 
-        let bit_depth_luma_minus8 = reader.read_exp_golomb()? as u8;
-        let bit_depth_chroma_minus8 = reader.read_exp_golomb()? as u8;
-        let qpprime_y_zero_transform_bypass_flag = reader.read_bit()?;
-        let seq_scaling_matrix_present_flag = reader.read_bit()?;
+        let bit_depth_luma_minus8 = reader.read_exp_golomb_max(6, "bit_depth_luma_minus8")? as u8;
+        let bit_depth_chroma_minus8 = reader.read_exp_golomb_max(6, "bit_depth_chroma_minus8")? as u8;
+        let qpprime_y_zero_transform_bypass_flag = reader.read_bit("qpprime_y_zero_transform_bypass_flag")?;
+        let seq_scaling_matrix_present_flag = reader.read_bit("seq_scaling_matrix_present_flag")?;
         let mut scaling_matrix: Vec<Vec<i64>> = vec![];
 
         if seq_scaling_matrix_present_flag {
@@ -117,13 +124,21 @@ impl SpsExtended {
             // for decoding, so we just skip them.
             let count = if chroma_format_idc != 3 { 8 } else { 12 };
             for i in 0..count {
-                let bit = reader.read_bit()?;
+                let bit = reader.read_bit("seq_scaling_list_present_flag")?;
                 scaling_matrix.push(vec![]);
                 if bit {
                     let size = if i < 6 { 16 } else { 64 };
                     let mut next_scale = 8;
                     for _ in 0..size {
-                        let delta_scale = reader.read_signed_exp_golomb()?;
+                        let delta_scale = reader.structure("scaling_list", |reader| {
+                            let delta_scale = reader.read_signed_exp_golomb("delta_scale")?;
+                            // ISO/IEC-14496-10-2022 - 7.4.2.1.1.1
+                            if !(-128..=127).contains(&delta_scale) {
+                                return Err(reader.error("delta_scale", H264ParseErrorKind::OutOfRange));
+                            }
+
+                            Ok(delta_scale)
+                        })?;
                         scaling_matrix[i].push(delta_scale);
                         next_scale = (next_scale + delta_scale + 256) % 256;
                         if next_scale == 0 {
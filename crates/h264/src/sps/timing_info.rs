@@ -1,9 +1,11 @@
 use std::io;
 use std::num::NonZeroU32;
 
-use byteorder::{BigEndian, ReadBytesExt};
 use scuffle_bytes_util::{BitReader, BitWriter};
 
+use crate::H264ParseError;
+use crate::io::SyntaxReader;
+
 /// `TimingInfo` contains the fields that are set when `timing_info_present_flag == 1`.
 ///
 /// This contains the following fields: `num_units_in_tick` and `time_scale`.
@@ -42,11 +44,15 @@ impl TimingInfo {
     /// Parses the fields defined when the `timing_info_present_flag == 1` from a bitstream.
     /// Returns a `TimingInfo` struct.
     pub fn parse<T: io::Read>(reader: &mut BitReader<T>) -> io::Result<Self> {
-        let num_units_in_tick = NonZeroU32::new(reader.read_u32::<BigEndian>()?)
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "num_units_in_tick cannot be 0"))?;
+        Ok(Self::parse_syntax(&mut SyntaxReader::new(reader, "vui_parameters"))?)
+    }
+
+    pub(crate) fn parse_syntax<T: io::Read>(reader: &mut SyntaxReader<'_, T>) -> Result<Self, H264ParseError> {
+        let num_units_in_tick = NonZeroU32::new(reader.read_u32("num_units_in_tick")?)
+            .ok_or_else(|| reader.invalid("num_units_in_tick", "num_units_in_tick cannot be 0"))?;
 
-        let time_scale = NonZeroU32::new(reader.read_u32::<BigEndian>()?)
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "time_scale cannot be 0"))?;
+        let time_scale = NonZeroU32::new(reader.read_u32("time_scale")?)
+            .ok_or_else(|| reader.invalid("time_scale", "time_scale cannot be 0"))?;
 
         Ok(TimingInfo {
             num_units_in_tick,
@@ -44,6 +44,23 @@
 ///     }
 /// }
 /// ```
+///
+/// Variants can be behind a `#[cfg(...)]`, for values that only exist in some
+/// versions of a library.
+///
+/// ```rust
+/// # use nutype_enum::nutype_enum;
+/// nutype_enum! {
+///     pub enum CodecId(u32) {
+///         H264 = 27,
+///         #[cfg(any())]
+///         Future = 1000,
+///     }
+/// }
+///
+/// assert_eq!(format!("{:?}", CodecId::H264), "CodecId::H264");
+/// assert_eq!(format!("{:?}", CodecId(1000)), "CodecId(1000)");
+/// ```
 #[macro_export]
 macro_rules! nutype_enum {
     (
@@ -62,12 +79,17 @@ macro_rules! nutype_enum {
 
         impl ::std::fmt::Debug for $name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                match self {
-                    $(
-                        &$name::$variant => write!(f, "{}::{}", stringify!($name), stringify!($variant)),
-                    )*
-                    _ => write!(f, "{}({:?})", stringify!($name), self.0),
-                }
+                // The variant attributes are repeated on each check, so variants
+                // behind a `#[cfg(...)]` are skipped along with their constant.
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(#[$variant_attr])*
+                    if *self == $name::$variant {
+                        return write!(f, "{}::{}", stringify!($name), stringify!($variant));
+                    }
+                )*
+
+                write!(f, "{}({:?})", stringify!($name), self.0)
             }
         }
 
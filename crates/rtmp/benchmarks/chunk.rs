@@ -0,0 +1,204 @@
+use std::io::{self, IoSlice, Write};
+
+use bytes::{Buf, Bytes, BytesMut};
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use scuffle_amf0::{Amf0Decoder, Amf0Encoder, Amf0Value};
+use scuffle_rtmp::{CHUNK_SIZE, Chunk, ChunkDecoder, ChunkEncoder, ChunkWriteBuffer, MessageParser, MessageTypeID};
+
+/// A representative mix of messages for one second of a 30fps stream:
+/// one keyframe, 29 inter frames and ~43 AAC frames.
+fn media_mix() -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut audio_timestamp = 0.0;
+
+    for frame in 0..30u32 {
+        let timestamp = frame * 1000 / 30;
+        let size = if frame == 0 { 80 * 1024 } else { 8 * 1024 };
+        chunks.push(Chunk::new(
+            6,
+            timestamp,
+            MessageTypeID::Video,
+            1,
+            Bytes::from(vec![0x17; size]),
+        ));
+
+        while audio_timestamp <= timestamp as f64 {
+            chunks.push(Chunk::new(
+                4,
+                audio_timestamp as u32,
+                MessageTypeID::Audio,
+                1,
+                Bytes::from(vec![0xaf; 300]),
+            ));
+            audio_timestamp += 1024.0 * 1000.0 / 44100.0;
+        }
+    }
+
+    chunks
+}
+
+fn encode(chunks: &[Chunk], chunk_size: usize) -> Vec<u8> {
+    let mut encoder = ChunkEncoder::default();
+    encoder.set_chunk_size(chunk_size);
+
+    let mut buf = Vec::new();
+    for chunk in chunks {
+        encoder.write_chunk(&mut buf, chunk.clone()).unwrap();
+    }
+
+    buf
+}
+
+fn payload_size(chunks: &[Chunk]) -> u64 {
+    chunks.iter().map(|chunk| chunk.payload.len() as u64).sum()
+}
+
+fn chunk_encoder(c: &mut Criterion) {
+    let chunks = media_mix();
+
+    let mut group = c.benchmark_group("chunk encoder");
+    group.throughput(Throughput::Bytes(payload_size(&chunks)));
+
+    for chunk_size in [128, CHUNK_SIZE] {
+        group.bench_with_input(BenchmarkId::new("media mix", chunk_size), &chunk_size, |b, &chunk_size| {
+            let mut encoder = ChunkEncoder::default();
+            encoder.set_chunk_size(chunk_size);
+            let mut buf = Vec::with_capacity(2 * 1024 * 1024);
+
+            b.iter(|| {
+                buf.clear();
+                for chunk in &chunks {
+                    encoder.write_chunk(&mut buf, chunk.clone()).unwrap();
+                }
+                black_box(&buf);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Encoding the media mix and writing it out, copying every payload into a write buffer
+/// or keeping the payloads as slices written with a single vectored write.
+fn chunk_write(c: &mut Criterion) {
+    let chunks = media_mix();
+
+    let mut group = c.benchmark_group("chunk write");
+    group.throughput(Throughput::Bytes(payload_size(&chunks)));
+
+    let mut encoder = ChunkEncoder::default();
+    encoder.set_chunk_size(CHUNK_SIZE);
+
+    group.bench_function("copy", |b| {
+        let mut buf = Vec::with_capacity(2 * 1024 * 1024);
+
+        b.iter(|| {
+            buf.clear();
+            for chunk in &chunks {
+                encoder.write_chunk(&mut buf, chunk.clone()).unwrap();
+            }
+            io::sink().write_all(black_box(&buf)).unwrap();
+        });
+    });
+
+    group.bench_function("vectored", |b| {
+        let mut buffer = ChunkWriteBuffer::new();
+
+        b.iter(|| {
+            for chunk in &chunks {
+                encoder.write_chunk_to_buffer(&mut buffer, chunk.clone()).unwrap();
+            }
+
+            while buffer.has_remaining() {
+                let mut slices = [IoSlice::new(&[]); 64];
+                let count = buffer.chunks_vectored(&mut slices);
+                let written = io::sink().write_vectored(black_box(&slices[..count])).unwrap();
+                buffer.advance(written);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn chunk_decoder(c: &mut Criterion) {
+    let chunks = media_mix();
+
+    let mut group = c.benchmark_group("chunk decoder");
+    group.throughput(Throughput::Bytes(payload_size(&chunks)));
+
+    for chunk_size in [128, CHUNK_SIZE] {
+        let encoded = encode(&chunks, chunk_size);
+
+        group.bench_with_input(BenchmarkId::new("media mix", chunk_size), &encoded, |b, encoded| {
+            b.iter(|| {
+                let mut decoder = ChunkDecoder::default();
+                decoder.update_max_chunk_size(chunk_size);
+
+                let mut buf = BytesMut::from(encoded.as_slice());
+                let mut count = 0;
+                while let Some(chunk) = decoder.read_chunk(&mut buf).unwrap() {
+                    black_box(MessageParser::parse(&chunk).unwrap());
+                    count += 1;
+                }
+                assert_eq!(count, chunks.len());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn amf0_decode(c: &mut Criterion) {
+    // A typical command and metadata payload as sent by OBS / ffmpeg.
+    let mut connect = Vec::new();
+    Amf0Encoder::encode_string(&mut connect, "connect").unwrap();
+    Amf0Encoder::encode_number(&mut connect, 1.0).unwrap();
+    Amf0Encoder::encode_object(
+        &mut connect,
+        &[
+            ("app".into(), Amf0Value::String("live".into())),
+            ("type".into(), Amf0Value::String("nonprivate".into())),
+            ("flashVer".into(), Amf0Value::String("FMLE/3.0 (compatible; FMSc/1.0)".into())),
+            ("tcUrl".into(), Amf0Value::String("rtmp://localhost:1935/live".into())),
+        ],
+    )
+    .unwrap();
+
+    let mut metadata = Vec::new();
+    Amf0Encoder::encode_string(&mut metadata, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut metadata, "onMetaData").unwrap();
+    Amf0Encoder::encode_object(
+        &mut metadata,
+        &[
+            ("duration".into(), Amf0Value::Number(0.0)),
+            ("width".into(), Amf0Value::Number(1920.0)),
+            ("height".into(), Amf0Value::Number(1080.0)),
+            ("videodatarate".into(), Amf0Value::Number(6000.0)),
+            ("framerate".into(), Amf0Value::Number(30.0)),
+            ("videocodecid".into(), Amf0Value::Number(7.0)),
+            ("audiodatarate".into(), Amf0Value::Number(160.0)),
+            ("audiosamplerate".into(), Amf0Value::Number(44100.0)),
+            ("audiosamplesize".into(), Amf0Value::Number(16.0)),
+            ("stereo".into(), Amf0Value::Boolean(true)),
+            ("audiocodecid".into(), Amf0Value::Number(10.0)),
+            ("encoder".into(), Amf0Value::String("Lavf61.7.100".into())),
+            ("filesize".into(), Amf0Value::Number(0.0)),
+        ],
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("amf0 decode");
+
+    for (name, payload) in [("connect", connect), ("metadata", metadata)] {
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &payload, |b, payload| {
+            b.iter(|| black_box(Amf0Decoder::new(payload).decode_all().unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, chunk_encoder, chunk_write, chunk_decoder, amf0_decode);
+criterion_main!(benches);
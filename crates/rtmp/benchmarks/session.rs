@@ -0,0 +1,167 @@
+use bytes::Bytes;
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use scuffle_amf0::{Amf0Encoder, Amf0Value};
+use scuffle_rtmp::{CHUNK_SIZE, Chunk, ChunkEncoder, MessageTypeID, Session, UniqueID};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// The size of a handshake packet (C1 / C2).
+const HANDSHAKE_SIZE: usize = 1536;
+
+fn command(
+    name: &str,
+    transaction_id: f64,
+    object: Option<Vec<(&'static str, Amf0Value<'static>)>>,
+    others: &[Amf0Value],
+) -> Bytes {
+    let mut buf = Vec::new();
+    Amf0Encoder::encode_string(&mut buf, name).unwrap();
+    Amf0Encoder::encode_number(&mut buf, transaction_id).unwrap();
+    match object {
+        Some(object) => {
+            let object: Vec<_> = object.into_iter().map(|(key, value)| (key.into(), value)).collect();
+            Amf0Encoder::encode_object(&mut buf, &object).unwrap();
+        }
+        None => Amf0Encoder::encode_null(&mut buf).unwrap(),
+    }
+    for value in others {
+        Amf0Encoder::encode(&mut buf, value).unwrap();
+    }
+    Bytes::from(buf)
+}
+
+/// Everything a publishing client sends: the (simple) handshake, the
+/// connect / createStream / publish commands and `frames` seconds of media.
+fn client_stream(seconds: u32) -> (Vec<u8>, u64) {
+    let mut buf = Vec::new();
+
+    // C0 + C1 + C2
+    buf.push(3);
+    buf.extend_from_slice(&[0; HANDSHAKE_SIZE]);
+    buf.extend_from_slice(&[0; HANDSHAKE_SIZE]);
+
+    let mut encoder = ChunkEncoder::default();
+    encoder
+        .write_chunk(
+            &mut buf,
+            Chunk::new(
+                2,
+                0,
+                MessageTypeID::SetChunkSize,
+                0,
+                Bytes::from((CHUNK_SIZE as u32).to_be_bytes().to_vec()),
+            ),
+        )
+        .unwrap();
+    encoder.set_chunk_size(CHUNK_SIZE);
+
+    let connect = command("connect", 1.0, Some(vec![("app", Amf0Value::String("live".into()))]), &[]);
+    let create_stream = command("createStream", 2.0, None, &[]);
+    let publish = command(
+        "publish",
+        3.0,
+        None,
+        &[Amf0Value::String("stream-key".into()), Amf0Value::String("live".into())],
+    );
+
+    encoder
+        .write_chunk(&mut buf, Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, connect))
+        .unwrap();
+    encoder
+        .write_chunk(&mut buf, Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, create_stream))
+        .unwrap();
+    encoder
+        .write_chunk(&mut buf, Chunk::new(3, 0, MessageTypeID::CommandAMF0, 1, publish))
+        .unwrap();
+
+    let mut payload_size = 0;
+    let mut audio_timestamp = 0.0;
+    for frame in 0..30 * seconds {
+        let timestamp = frame * 1000 / 30;
+        let size = if frame % 60 == 0 { 80 * 1024 } else { 8 * 1024 };
+        payload_size += size as u64;
+        encoder
+            .write_chunk(
+                &mut buf,
+                Chunk::new(6, timestamp, MessageTypeID::Video, 1, Bytes::from(vec![0x17; size])),
+            )
+            .unwrap();
+
+        while audio_timestamp <= timestamp as f64 {
+            payload_size += 300;
+            encoder
+                .write_chunk(
+                    &mut buf,
+                    Chunk::new(
+                        4,
+                        audio_timestamp as u32,
+                        MessageTypeID::Audio,
+                        1,
+                        Bytes::from(vec![0xaf; 300]),
+                    ),
+                )
+                .unwrap();
+            audio_timestamp += 1024.0 * 1000.0 / 44100.0;
+        }
+    }
+
+    (buf, payload_size)
+}
+
+async fn run_session(client_stream: &[u8]) {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let (mut client_reader, mut client_writer) = tokio::io::split(client);
+
+    let (data_producer, mut data_consumer) = mpsc::channel(128);
+    let (publish_producer, mut publish_consumer) = mpsc::channel(1);
+
+    let mut session = Session::new(server, data_producer, publish_producer);
+
+    let client_stream = client_stream.to_vec();
+    let writer = tokio::spawn(async move {
+        client_writer.write_all(&client_stream).await.unwrap();
+        client_writer.shutdown().await.unwrap();
+    });
+    // Discard everything the server sends back.
+    let reader = tokio::spawn(async move {
+        let mut buf = vec![0; 16 * 1024];
+        while client_reader.read(&mut buf).await.is_ok_and(|n| n > 0) {}
+    });
+    let publisher = tokio::spawn(async move {
+        let request = publish_consumer.recv().await.unwrap();
+        request.response.send(UniqueID::new_v4().into()).unwrap();
+
+        let mut count = 0;
+        while let Some(data) = data_consumer.recv().await {
+            black_box(data);
+            count += 1;
+        }
+        count
+    });
+
+    session.run().await.unwrap();
+    drop(session);
+
+    writer.await.unwrap();
+    reader.await.unwrap();
+    assert!(publisher.await.unwrap() > 0);
+}
+
+fn session(c: &mut Criterion) {
+    let mut group = c.benchmark_group("session");
+
+    let runtime = || tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+
+    for seconds in [1, 10] {
+        let (stream, payload_size) = client_stream(seconds);
+        group.throughput(Throughput::Bytes(payload_size));
+        group.bench_with_input(BenchmarkId::new("publish", format!("{seconds}s")), &stream, |b, stream| {
+            b.to_async(runtime()).iter(|| run_session(stream));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, session);
+criterion_main!(benches);
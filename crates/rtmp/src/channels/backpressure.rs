@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use scuffle_future_ext::FutureExt;
+use tokio::sync::mpsc::error::TrySendError;
+
+use super::sequence_headers::{is_audio_sequence_header, is_video_keyframe, is_video_sequence_header};
+use super::{ChannelData, DataProducer};
+
+/// Returns true if `data` is a video message a decoder can start on.
+fn is_keyframe(data: &ChannelData) -> bool {
+    match data {
+        ChannelData::Video { data, .. } => is_video_keyframe(data),
+        _ => false,
+    }
+}
+
+/// Returns true if `data` is an audio or video frame, which consumers can do without.
+fn is_frame(data: &ChannelData) -> bool {
+    match data {
+        ChannelData::Video { data, .. } => !is_video_sequence_header(data),
+        ChannelData::Audio { data, .. } => !is_audio_sequence_header(data),
+        _ => false,
+    }
+}
+
+/// What a session does with the messages of a published stream while its data
+/// channel is full, because the consumer falls behind.
+///
+/// Set with [`Session::with_backpressure_policy`](crate::Session::with_backpressure_policy).
+/// Dropped messages are counted in [`DataBufferMetrics::dropped`](crate::DataBufferMetrics::dropped).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Wait for room for up to [`ProtocolConfig::data_send_timeout`](crate::ProtocolConfig::data_send_timeout),
+    /// then end the session with [`SessionError::PublisherDropped`](crate::SessionError::PublisherDropped).
+    #[default]
+    Block,
+    /// Drop audio frames and video frames other than keyframes. After a dropped video frame,
+    /// the following video frames are dropped up to the next keyframe, as they cannot be decoded.
+    ///
+    /// Keyframes, sequence headers and metadata wait for room like with [`BackpressurePolicy::Block`].
+    DropNonKeyframe,
+    /// Keep up to `backlog` messages in the session, dropping the oldest audio or video frame
+    /// when more arrive. The backlog is sent first once there is room again.
+    ///
+    /// Sequence headers and metadata are never dropped, as consumers need them to decode the stream.
+    DropOldest {
+        /// The number of messages kept in the session.
+        backlog: usize,
+    },
+}
+
+/// Sends the messages of a session to its data channel, following a [`BackpressurePolicy`].
+#[derive(Debug, Default)]
+pub(crate) struct Backpressure {
+    policy: BackpressurePolicy,
+    /// The messages waiting for room, with [`BackpressurePolicy::DropOldest`].
+    backlog: VecDeque<ChannelData>,
+    /// A video frame was dropped, with [`BackpressurePolicy::DropNonKeyframe`].
+    waiting_for_keyframe: bool,
+    dropped: u64,
+}
+
+impl Backpressure {
+    pub fn new(policy: BackpressurePolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of messages waiting in the backlog.
+    pub fn backlog(&self) -> usize {
+        self.backlog.len()
+    }
+
+    /// Returns the number of messages dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Sends `data` to `producer`, or drops it. Returns false if the consumer is gone,
+    /// or did not make room within `timeout` when the policy waits.
+    pub async fn send(&mut self, producer: &DataProducer, data: ChannelData, timeout: Duration) -> bool {
+        match self.policy {
+            BackpressurePolicy::Block => matches!(producer.send(data).with_timeout(timeout).await, Ok(Ok(()))),
+            BackpressurePolicy::DropNonKeyframe => {
+                if is_keyframe(&data) {
+                    self.waiting_for_keyframe = false;
+                } else if is_frame(&data) {
+                    return self.send_or_drop(producer, data);
+                }
+
+                matches!(producer.send(data).with_timeout(timeout).await, Ok(Ok(())))
+            }
+            BackpressurePolicy::DropOldest { backlog } => {
+                self.backlog.push_back(data);
+                if !self.send_backlog(producer) {
+                    return false;
+                }
+
+                while self.backlog.len() > backlog {
+                    let Some(index) = self.backlog.iter().position(is_frame) else {
+                        break;
+                    };
+
+                    self.backlog.remove(index);
+                    self.dropped += 1;
+                }
+
+                true
+            }
+        }
+    }
+
+    /// Sends an audio or video frame if there is room, [`BackpressurePolicy::DropNonKeyframe`].
+    fn send_or_drop(&mut self, producer: &DataProducer, data: ChannelData) -> bool {
+        let video = matches!(data, ChannelData::Video { .. });
+        if video && self.waiting_for_keyframe {
+            self.dropped += 1;
+            return true;
+        }
+
+        match producer.try_send(data) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.waiting_for_keyframe |= video;
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// Sends as much of the backlog as there is room for, [`BackpressurePolicy::DropOldest`].
+    fn send_backlog(&mut self, producer: &DataProducer) -> bool {
+        while let Some(data) = self.backlog.pop_front() {
+            match producer.try_send(data) {
+                Ok(()) => {}
+                Err(TrySendError::Full(data)) => {
+                    self.backlog.push_front(data);
+                    break;
+                }
+                Err(TrySendError::Closed(_)) => return false,
+            }
+        }
+
+        true
+    }
+}
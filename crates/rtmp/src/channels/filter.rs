@@ -0,0 +1,38 @@
+use super::ChannelData;
+
+/// A hook that sees every media message of a published stream before it is
+/// forwarded to the data channel.
+///
+/// The filter can pass the message on unchanged, modify it, for example to rewrite
+/// its timestamp or strip SEI NAL units, or drop it by returning `None`.
+/// It is called synchronously on the session task, so it should not block.
+///
+/// Closures of the form `FnMut(ChannelData) -> Option<ChannelData>` implement this trait.
+///
+/// ```rust
+/// use scuffle_rtmp::{ChannelData, MediaTimestamp};
+///
+/// // Shift all timestamps by 10 seconds, and drop data frames such as `onTextData`.
+/// let filter = |data: ChannelData| match data {
+///     ChannelData::DataFrame { .. } => None,
+///     ChannelData::Video { timestamp, data } => Some(ChannelData::Video {
+///         timestamp: MediaTimestamp::new(timestamp.value() + 10_000, timestamp.timescale()),
+///         data,
+///     }),
+///     data => Some(data),
+/// };
+/// # let _: &dyn scuffle_rtmp::MessageFilter = &filter;
+/// ```
+pub trait MessageFilter: Send + Sync {
+    /// Inspects a message, returning the message to forward or `None` to drop it.
+    fn filter(&mut self, data: ChannelData) -> Option<ChannelData>;
+}
+
+impl<F> MessageFilter for F
+where
+    F: FnMut(ChannelData) -> Option<ChannelData> + Send + Sync,
+{
+    fn filter(&mut self, data: ChannelData) -> Option<ChannelData> {
+        self(data)
+    }
+}
@@ -0,0 +1,108 @@
+use scuffle_amf0::{Amf0Decoder, Amf0Marker, Amf0Value};
+
+/// The name publishers prefix their `onMetaData` with.
+const SET_DATA_FRAME: &str = "@setDataFrame";
+
+/// The properties of an `onMetaData` message, as sent by publishers at the start of a stream.
+///
+/// Every property is optional, encoders send what they know. Properties that are not
+/// known here, or do not have the expected type, are kept in `custom`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StreamMetadata {
+    /// The duration in seconds, usually 0 for live streams.
+    pub duration: Option<f64>,
+    /// The width of the video in pixels.
+    pub width: Option<u32>,
+    /// The height of the video in pixels.
+    pub height: Option<u32>,
+    /// The video bitrate in kilobits per second.
+    pub video_data_rate: Option<f64>,
+    /// The number of video frames per second.
+    pub frame_rate: Option<f64>,
+    /// The FLV video codec id, or with enhanced RTMP the FourCC of the codec as a big endian number.
+    pub video_codec_id: Option<u32>,
+    /// The audio bitrate in kilobits per second.
+    pub audio_data_rate: Option<f64>,
+    /// The audio sample rate in Hz.
+    pub audio_sample_rate: Option<u32>,
+    /// The number of bits per audio sample.
+    pub audio_sample_size: Option<u32>,
+    /// Whether the audio is stereo.
+    pub stereo: Option<bool>,
+    /// The FLV sound format, or with enhanced RTMP the FourCC of the codec as a big endian number.
+    pub audio_codec_id: Option<u32>,
+    /// The name of the encoder, such as `obs-output module (libobs version 30.0.0)`.
+    pub encoder: Option<String>,
+    /// All other properties, in the order they were sent.
+    pub custom: Vec<(String, Amf0Value<'static>)>,
+}
+
+impl StreamMetadata {
+    /// Parses the payload of an `onMetaData` data message, with or without the `@setDataFrame` prefix,
+    /// such as the data of [`ChannelData::Metadata`](super::ChannelData::Metadata).
+    ///
+    /// Returns `None` if `data` is not an `onMetaData` message.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut decoder = Amf0Decoder::new(data);
+
+        let mut name = decoder.decode_with_type(Amf0Marker::String).ok()?;
+        if name == Amf0Value::String(SET_DATA_FRAME.into()) {
+            name = decoder.decode_with_type(Amf0Marker::String).ok()?;
+        }
+
+        if name != Amf0Value::String("onMetaData".into()) {
+            return None;
+        }
+
+        // Sent as an object or an ECMA array, which are both decoded as objects.
+        let Amf0Value::Object(properties) = decoder.decode().ok()? else {
+            return None;
+        };
+
+        let mut metadata = Self::default();
+        for (key, value) in properties.iter() {
+            metadata.set(key, value);
+        }
+
+        Some(metadata)
+    }
+
+    fn set(&mut self, key: &str, value: &Amf0Value) {
+        let number = match value {
+            Amf0Value::Number(number) => Some(*number),
+            _ => None,
+        };
+
+        match (key, value) {
+            ("duration", _) if number.is_some() => self.duration = number,
+            ("width", _) if number.is_some() => self.width = number.map(|n| n as u32),
+            ("height", _) if number.is_some() => self.height = number.map(|n| n as u32),
+            ("videodatarate", _) if number.is_some() => self.video_data_rate = number,
+            ("framerate", _) if number.is_some() => self.frame_rate = number,
+            ("videocodecid", _) if codec_id(value).is_some() => self.video_codec_id = codec_id(value),
+            ("audiodatarate", _) if number.is_some() => self.audio_data_rate = number,
+            ("audiosamplerate", _) if number.is_some() => self.audio_sample_rate = number.map(|n| n as u32),
+            ("audiosamplesize", _) if number.is_some() => self.audio_sample_size = number.map(|n| n as u32),
+            ("stereo", Amf0Value::Boolean(stereo)) => self.stereo = Some(*stereo),
+            ("audiocodecid", _) if codec_id(value).is_some() => self.audio_codec_id = codec_id(value),
+            ("encoder", Amf0Value::String(encoder) | Amf0Value::LongString(encoder)) => {
+                self.encoder = Some(encoder.to_string())
+            }
+            _ => self.custom.push((key.to_string(), value.to_owned())),
+        }
+    }
+
+    /// Returns the custom property `key`, if it was sent.
+    pub fn custom_property(&self, key: &str) -> Option<&Amf0Value<'static>> {
+        self.custom.iter().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+}
+
+/// Codec ids are numbers, but some encoders send the FourCC of enhanced RTMP as a string.
+fn codec_id(value: &Amf0Value) -> Option<u32> {
+    match value {
+        Amf0Value::Number(number) => Some(*number as u32),
+        Amf0Value::String(fourcc) => Some(u32::from_be_bytes(fourcc.as_bytes().try_into().ok()?)),
+        _ => None,
+    }
+}
@@ -1,42 +1,233 @@
 use bytes::Bytes;
+use scuffle_amf0::Amf0Encoder;
 use tokio::sync::{mpsc, oneshot};
 
+use crate::messages::ConnectCommandObject;
+use crate::transport::PeerInfo;
+use crate::user_control_messages::UserControlEvent;
+
+mod backpressure;
+mod filter;
+mod metadata;
+mod reconnect;
+mod sequence_headers;
+mod timestamp;
+mod watermark;
+
+pub(crate) use self::backpressure::Backpressure;
+pub use self::backpressure::BackpressurePolicy;
+pub use self::filter::MessageFilter;
+pub use self::metadata::StreamMetadata;
+pub(crate) use self::reconnect::ParkedStream;
+pub use self::reconnect::ReconnectGrace;
+pub(crate) use self::sequence_headers::is_video_keyframe;
+pub use self::sequence_headers::{SequenceHeaderCache, SequenceHeaders};
+pub use self::timestamp::{MediaTimestamp, RTMP_TIMESCALE};
+pub use self::watermark::{DataBufferMetrics, DataWatermarks, WatermarkEvent};
+
 pub type UniqueID = uuid::Uuid;
 
 #[derive(Clone, Debug)]
 pub enum ChannelData {
-    Video { timestamp: u32, data: Bytes },
-    Audio { timestamp: u32, data: Bytes },
-    Metadata { timestamp: u32, data: Bytes },
+    Video {
+        timestamp: MediaTimestamp,
+        data: Bytes,
+    },
+    Audio {
+        timestamp: MediaTimestamp,
+        data: Bytes,
+    },
+    Metadata {
+        timestamp: MediaTimestamp,
+        data: Bytes,
+    },
+    /// A data message other than `onMetaData`, such as `onTextData` or `onCuePoint`.
+    /// The payload contains the AMF encoded values following the name.
+    DataFrame {
+        timestamp: MediaTimestamp,
+        name: String,
+        payload: Bytes,
+    },
+    /// The publisher reconnected within the [`ReconnectGrace`] period after an unclean
+    /// disconnect, and the stream continues on the same [`UniqueID`].
+    ///
+    /// The timestamp is the last one forwarded before the disconnect. Publishers usually
+    /// restart their timestamps at zero when they reconnect, so consumers should rebase
+    /// the following messages on it.
+    Resume {
+        timestamp: MediaTimestamp,
+    },
 }
 
+/// The `@setDataFrame` name publishers prefix their `onMetaData` with, AMF0 encoded.
+/// Players and FLV files expect the metadata without it.
+const SET_DATA_FRAME: &[u8] = b"\x02\x00\x0d@setDataFrame";
+
 impl ChannelData {
-    pub fn timestamp(&self) -> u32 {
+    pub fn timestamp(&self) -> MediaTimestamp {
         match self {
             ChannelData::Video { timestamp, .. } => *timestamp,
             ChannelData::Audio { timestamp, .. } => *timestamp,
             ChannelData::Metadata { timestamp, .. } => *timestamp,
+            ChannelData::DataFrame { timestamp, .. } => *timestamp,
+            ChannelData::Resume { timestamp } => *timestamp,
         }
     }
 
+    /// Returns the payload of the message, empty for [`ChannelData::Resume`].
     pub fn data(&self) -> &Bytes {
+        static EMPTY: Bytes = Bytes::new();
+
         match self {
             ChannelData::Video { data, .. } => data,
             ChannelData::Audio { data, .. } => data,
             ChannelData::Metadata { data, .. } => data,
+            ChannelData::DataFrame { payload, .. } => payload,
+            ChannelData::Resume { .. } => &EMPTY,
         }
     }
+
+    /// Returns the AMF0 payload of a data message as players and FLV files expect it,
+    /// `onMetaData` without the `@setDataFrame` prefix or the name of a data frame
+    /// followed by its payload.
+    ///
+    /// `None` for audio, video and resume messages, and for data frames with
+    /// names too long to be encoded as an AMF0 string.
+    pub(crate) fn script_data(&self) -> Option<Bytes> {
+        match self {
+            ChannelData::Metadata { data, .. } if data.starts_with(SET_DATA_FRAME) => {
+                Some(data.slice(SET_DATA_FRAME.len()..))
+            }
+            ChannelData::Metadata { data, .. } => Some(data.clone()),
+            ChannelData::DataFrame { name, payload, .. } => {
+                let mut data = Vec::with_capacity(3 + name.len() + payload.len());
+                Amf0Encoder::encode_string(&mut data, name).ok()?;
+                data.extend_from_slice(payload);
+                Some(Bytes::from(data))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The decision for an incoming `connect` command, see [`ConnectRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectDecision {
+    /// Accept the connection.
+    Accept,
+    /// Reject the connection with `NetConnection.Connect.Rejected`.
+    Reject,
+    /// Reject the connection with `NetConnection.Connect.Rejected` and an `ex.redirect`
+    /// info object, telling the client to reconnect to the given url instead.
+    ///
+    /// This can be used to load balance ingest at the RTMP layer.
+    Redirect(String),
+}
+
+/// Sent by the session when a client issues a `connect` command.
+/// The session waits for a [`ConnectDecision`] on `response` before continuing.
+#[derive(Debug)]
+pub struct ConnectRequest {
+    pub app_name: String,
+    /// The url the client connected to, if it sent one.
+    pub tc_url: Option<String>,
+    /// The full command object of the `connect` command, including the
+    /// enhanced RTMP codec capabilities the client advertised.
+    pub command_object: ConnectCommandObject<'static>,
+    /// Metadata about the client, see [`Session::with_peer_info`](crate::Session::with_peer_info).
+    pub peer_info: PeerInfo,
+    pub response: oneshot::Sender<ConnectDecision>,
 }
 
+/// Sent by the session when a client issues a `publish` command.
+/// The session waits for a [`PublishResponse`] on `response` before continuing,
+/// dropping `response` rejects the request.
 #[derive(Debug)]
 pub struct PublishRequest {
     pub app_name: String,
     pub stream_name: String,
-    pub response: oneshot::Sender<UniqueID>,
+    /// The id of the stream the client publishes on, clients can publish several streams per connection.
+    pub stream_id: u32,
+    pub response: oneshot::Sender<PublishResponse>,
 }
 
+/// The answer to a [`PublishRequest`].
+#[derive(Debug)]
+pub struct PublishResponse {
+    pub uid: UniqueID,
+    /// The producer the messages of the stream are sent to.
+    ///
+    /// If `None`, they are sent to the data producer the session was created with,
+    /// which only one stream of the session can publish to at a time.
+    pub data_producer: Option<DataProducer>,
+    /// The cache the sequence headers of the stream are stored in, to attach consumers to it.
+    ///
+    /// Only used with `data_producer`, the stream of the data producer of the session uses the
+    /// cache of the session, see [`Session::with_sequence_header_cache`](crate::Session::with_sequence_header_cache).
+    pub sequence_headers: Option<SequenceHeaderCache>,
+}
+
+impl PublishResponse {
+    /// Accept the stream, publishing it to the data producer of the session.
+    pub fn new(uid: UniqueID) -> Self {
+        Self {
+            uid,
+            data_producer: None,
+            sequence_headers: None,
+        }
+    }
+
+    /// Publishes the stream to `data_producer` instead of the data producer of the session.
+    pub fn with_data_producer(mut self, data_producer: DataProducer) -> Self {
+        self.data_producer = Some(data_producer);
+        self
+    }
+
+    /// Stores the sequence headers of the stream in `sequence_headers`, used with [`PublishResponse::with_data_producer`].
+    pub fn with_sequence_header_cache(mut self, sequence_headers: SequenceHeaderCache) -> Self {
+        self.sequence_headers = Some(sequence_headers);
+        self
+    }
+}
+
+impl From<UniqueID> for PublishResponse {
+    fn from(uid: UniqueID) -> Self {
+        Self::new(uid)
+    }
+}
+
+/// Sent by the session when a client issues a `play` command.
+/// The session plays the messages of the [`DataConsumer`] sent on `response`,
+/// dropping `response` rejects the request.
+///
+/// [`SequenceHeaderCache::attach`] returns a consumer starting with the sequence
+/// headers, so clients can decode a stream they joined mid-way.
+#[derive(Debug)]
+pub struct PlayRequest {
+    pub app_name: String,
+    pub stream_name: String,
+    /// The id of the stream the client plays on, clients can play several streams per connection.
+    pub stream_id: u32,
+    pub response: oneshot::Sender<DataConsumer>,
+}
+
+pub type ConnectProducer = mpsc::Sender<ConnectRequest>;
+pub type ConnectConsumer = mpsc::Receiver<ConnectRequest>;
+
 pub type PublishProducer = mpsc::Sender<PublishRequest>;
 pub type PublishConsumer = mpsc::Receiver<PublishRequest>;
 
+pub type PlayProducer = mpsc::Sender<PlayRequest>;
+pub type PlayConsumer = mpsc::Receiver<PlayRequest>;
+
 pub type DataProducer = mpsc::Sender<ChannelData>;
 pub type DataConsumer = mpsc::Receiver<ChannelData>;
+
+pub type MetadataProducer = mpsc::Sender<StreamMetadata>;
+pub type MetadataConsumer = mpsc::Receiver<StreamMetadata>;
+
+pub type UserControlProducer = mpsc::Sender<UserControlEvent>;
+pub type UserControlConsumer = mpsc::Receiver<UserControlEvent>;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{DataProducer, MediaTimestamp, SequenceHeaderCache, UniqueID};
+
+/// The stream of a publisher that disconnected uncleanly, waiting for it to reconnect.
+#[derive(Debug)]
+pub(crate) struct ParkedStream {
+    pub uid: UniqueID,
+    pub data_producer: DataProducer,
+    pub sequence_headers: SequenceHeaderCache,
+    /// The last timestamp forwarded before the disconnect.
+    pub last_timestamp: MediaTimestamp,
+}
+
+#[derive(Debug, Default)]
+struct GraceInner {
+    /// Parked streams by app and stream name, with the generation they were parked in.
+    parked: HashMap<(String, String), (u64, ParkedStream)>,
+    /// Incremented on every park, so an expiry does not remove a stream parked again later.
+    generation: u64,
+}
+
+/// Keeps the stream of a publisher that disconnected uncleanly alive for a grace period,
+/// shared by all [`Session`](crate::Session)s it is given to.
+///
+/// Encoders often drop the connection on short network blips and reconnect right away.
+/// Without a grace period every blip ends the stream, and the whole pipeline behind it
+/// is torn down and restarted.
+///
+/// When a publishing session ends without deleting its streams, each stream is parked:
+/// its [`UniqueID`], data producer and [`SequenceHeaderCache`] are kept, so consumers
+/// do not see the channel close. If a session publishes to the same app and stream name
+/// within the period, it takes over the parked stream instead of sending a
+/// [`PublishRequest`](crate::PublishRequest), and sends a
+/// [`ChannelData::Resume`](crate::ChannelData::Resume) to the consumers. Otherwise the
+/// parked stream is dropped once the period is over, which closes the channel.
+///
+/// A resumed stream forwards its data to the parked data producer. Unless another stream
+/// of the session publishes to the data producer the session was created with, the parked
+/// one takes its place and the one it was created with is dropped. Parked streams are
+/// expired by a task on the tokio runtime.
+#[derive(Clone, Debug)]
+pub struct ReconnectGrace {
+    period: Duration,
+    inner: Arc<Mutex<GraceInner>>,
+}
+
+impl ReconnectGrace {
+    /// Create a new registry, keeping parked streams for `period`.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            inner: Arc::default(),
+        }
+    }
+
+    /// Returns how long parked streams are kept.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the number of streams waiting for their publisher to reconnect.
+    pub fn parked(&self) -> usize {
+        self.lock().parked.len()
+    }
+
+    /// Parks a stream until its publisher reconnects or the period is over.
+    ///
+    /// A stream already parked under the same name is replaced.
+    pub(crate) fn park(&self, app_name: &str, stream_name: &str, stream: ParkedStream) {
+        let key = (app_name.to_string(), stream_name.to_string());
+
+        let generation = {
+            let mut inner = self.lock();
+            inner.generation += 1;
+            let generation = inner.generation;
+            inner.parked.insert(key.clone(), (generation, stream));
+            generation
+        };
+
+        let grace = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(grace.period).await;
+
+            let mut inner = grace.lock();
+            let expired = match inner.parked.get(&key) {
+                Some((parked, _)) if *parked == generation => inner.parked.remove(&key),
+                _ => None,
+            };
+
+            if let Some((_, stream)) = expired {
+                tracing::debug!(uid = %stream.uid, "reconnect grace period expired");
+            }
+        });
+    }
+
+    /// Takes the stream parked under the name, if its period is not over yet.
+    pub(crate) fn resume(&self, app_name: &str, stream_name: &str) -> Option<ParkedStream> {
+        self.lock()
+            .parked
+            .remove(&(app_name.to_string(), stream_name.to_string()))
+            .map(|(_, stream)| stream)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, GraceInner> {
+        // The lock is never held across a panic point that leaves the registry inconsistent.
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
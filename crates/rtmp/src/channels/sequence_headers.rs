@@ -0,0 +1,217 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc;
+
+use super::{ChannelData, DataConsumer, DataProducer, StreamMetadata};
+
+/// FLV video frame type of a keyframe, the same in enhanced RTMP.
+const VIDEO_FRAME_TYPE_KEYFRAME: u8 = 1;
+/// FLV video codec id of AVC (H.264).
+const VIDEO_CODEC_AVC: u8 = 7;
+/// FLV video codec id of HEVC (H.265), as used by encoders before enhanced RTMP.
+const VIDEO_CODEC_HEVC: u8 = 12;
+/// FLV sound format of AAC.
+const SOUND_FORMAT_AAC: u8 = 10;
+/// Enhanced RTMP sound format signalling an extended audio header.
+const SOUND_FORMAT_EX_HEADER: u8 = 9;
+
+/// Enhanced RTMP video packet type of a multitrack packet.
+const VIDEO_PACKET_TYPE_MULTITRACK: u8 = 6;
+/// Enhanced RTMP audio packet type of a multitrack packet.
+const AUDIO_PACKET_TYPE_MULTITRACK: u8 = 5;
+/// Enhanced RTMP packet type of packet modifier extensions, the same for audio and video.
+const PACKET_TYPE_MOD_EX: u8 = 7;
+/// Enhanced RTMP packet type of a sequence start, the same for audio and video.
+const PACKET_TYPE_SEQUENCE_START: u8 = 0;
+
+/// Returns the enhanced RTMP packet type of `data`, skipping packet modifier extensions
+/// and looking into multitrack packets. `None` if the data is cut short.
+fn ex_packet_type(data: &[u8], multitrack: u8) -> Option<u8> {
+    let mut packet_type = data.first()? & 0x0f;
+    let mut rest = data.get(1..)?;
+
+    while packet_type == PACKET_TYPE_MOD_EX {
+        // The size is stored minus one, 0xff means it follows as 16 bits.
+        let (header, size) = match *rest.first()? {
+            0xff => (3, u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?]) as usize + 1),
+            size => (1, size as usize + 1),
+        };
+        rest = rest.get(header + size..)?;
+        packet_type = rest.first()? & 0x0f;
+        rest = rest.get(1..)?;
+    }
+
+    if packet_type == multitrack {
+        // The packet type of the tracks follows the multitrack type.
+        packet_type = rest.first()? & 0x0f;
+    }
+
+    Some(packet_type)
+}
+
+/// Returns true if `data` is the payload of a video message carrying a decoder
+/// configuration record, such as an AVC or HEVC sequence header.
+pub(super) fn is_video_sequence_header(data: &[u8]) -> bool {
+    let Some(&first) = data.first() else {
+        return false;
+    };
+
+    if first & 0x80 != 0 {
+        // Enhanced RTMP: IsExHeader, the packet type is in the low nibble.
+        return ex_packet_type(data, VIDEO_PACKET_TYPE_MULTITRACK) == Some(PACKET_TYPE_SEQUENCE_START);
+    }
+
+    matches!(first & 0x0f, VIDEO_CODEC_AVC | VIDEO_CODEC_HEVC) && data.get(1) == Some(&0)
+}
+
+/// Returns true if `data` is the payload of a video message a decoder can start on,
+/// which includes the sequence headers.
+pub(crate) fn is_video_keyframe(data: &[u8]) -> bool {
+    // The frame type is in the same bits with and without the enhanced RTMP IsExHeader flag.
+    data.first()
+        .is_some_and(|first| (first >> 4) & 0x07 == VIDEO_FRAME_TYPE_KEYFRAME)
+}
+
+/// Returns true if `data` is the payload of an audio message carrying a decoder
+/// configuration, such as the AAC AudioSpecificConfig.
+pub(super) fn is_audio_sequence_header(data: &[u8]) -> bool {
+    let Some(&first) = data.first() else {
+        return false;
+    };
+
+    match first >> 4 {
+        SOUND_FORMAT_AAC => data.get(1) == Some(&0),
+        // Enhanced RTMP: the packet type is in the low nibble.
+        SOUND_FORMAT_EX_HEADER => ex_packet_type(data, AUDIO_PACKET_TYPE_MULTITRACK) == Some(PACKET_TYPE_SEQUENCE_START),
+        _ => false,
+    }
+}
+
+/// The latest sequence headers and metadata seen on a published stream.
+///
+/// Decoders cannot start on a stream without them, and publishers only send
+/// them once at the start of the stream.
+#[derive(Clone, Debug, Default)]
+pub struct SequenceHeaders {
+    /// The latest `onMetaData` message.
+    pub metadata: Option<ChannelData>,
+    /// The latest video sequence header, such as the AVC or HEVC decoder configuration record.
+    pub video: Option<ChannelData>,
+    /// The latest audio sequence header, such as the AAC AudioSpecificConfig.
+    pub audio: Option<ChannelData>,
+}
+
+impl SequenceHeaders {
+    /// Returns true if no sequence header or metadata was seen yet.
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_none() && self.video.is_none() && self.audio.is_none()
+    }
+
+    /// Returns the properties of the latest `onMetaData` message, if it could be parsed.
+    pub fn stream_metadata(&self) -> Option<StreamMetadata> {
+        StreamMetadata::parse(self.metadata.as_ref()?.data())
+    }
+
+    /// Returns the cached messages in the order they should be replayed,
+    /// metadata first, then video and audio.
+    pub fn iter(&self) -> impl Iterator<Item = &ChannelData> {
+        [&self.metadata, &self.video, &self.audio].into_iter().flatten()
+    }
+
+    /// Stores `data` if it is a sequence header or metadata.
+    fn observe(&mut self, data: &ChannelData) {
+        match data {
+            ChannelData::Video { data: payload, .. } if is_video_sequence_header(payload) => {
+                self.video = Some(data.clone());
+            }
+            ChannelData::Audio { data: payload, .. } if is_audio_sequence_header(payload) => {
+                self.audio = Some(data.clone());
+            }
+            ChannelData::Metadata { .. } => {
+                self.metadata = Some(data.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    headers: SequenceHeaders,
+    consumers: Vec<DataProducer>,
+}
+
+/// A cache of the [`SequenceHeaders`] of a published stream, shared with the
+/// [`Session`](crate::Session) publishing it.
+///
+/// Consumers attached with [`SequenceHeaderCache::attach`] while the stream is
+/// running, such as a recorder started mid-stream, first receive the cached
+/// sequence headers and then every message the session forwards, so they can
+/// initialize their decoders without waiting for the publisher to reconnect.
+///
+/// Attached consumers must not stall the publisher, so messages are sent to them
+/// without waiting. A consumer that is full or dropped is detached.
+#[derive(Clone, Debug, Default)]
+pub struct SequenceHeaderCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+impl SequenceHeaderCache {
+    /// Create a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the cached sequence headers.
+    pub fn sequence_headers(&self) -> SequenceHeaders {
+        self.lock().headers.clone()
+    }
+
+    /// Attaches a new consumer, with room for `capacity` messages.
+    ///
+    /// The cached sequence headers are queued on it right away, in addition to
+    /// the `capacity` messages.
+    pub fn attach(&self, capacity: usize) -> DataConsumer {
+        let mut inner = self.lock();
+
+        let (producer, consumer) = mpsc::channel(capacity.max(1) + inner.headers.iter().count());
+        for data in inner.headers.iter() {
+            // Cannot fail, the channel is new and has room for all headers.
+            let _ = producer.try_send(data.clone());
+        }
+
+        inner.consumers.push(producer);
+        consumer
+    }
+
+    /// Returns the number of attached consumers.
+    pub fn attached(&self) -> usize {
+        let mut inner = self.lock();
+        inner.consumers.retain(|producer| !producer.is_closed());
+        inner.consumers.len()
+    }
+
+    /// Caches `data` if it is a sequence header and forwards it to the attached consumers.
+    pub(crate) fn observe(&self, data: &ChannelData) {
+        let mut inner = self.lock();
+        inner.headers.observe(data);
+
+        inner.consumers.retain(|producer| match producer.try_send(data.clone()) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::debug!(%err, "detaching sequence header consumer");
+                false
+            }
+        });
+    }
+
+    /// Forgets the cached sequence headers, when the stream ends or a new one starts.
+    pub(crate) fn clear(&self) {
+        self.lock().headers = SequenceHeaders::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheInner> {
+        // The lock is never held across a panic point that leaves the cache inconsistent.
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
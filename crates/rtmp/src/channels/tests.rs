@@ -0,0 +1,517 @@
+use std::num::NonZero;
+
+use bytes::Bytes;
+use scuffle_amf0::{Amf0Encoder, Amf0Value};
+
+use crate::channels::{
+    Backpressure, BackpressurePolicy, ChannelData, DataBufferMetrics, DataWatermarks, MediaTimestamp, MessageFilter,
+    ParkedStream, RTMP_TIMESCALE, ReconnectGrace, SequenceHeaderCache, StreamMetadata, UniqueID, WatermarkEvent,
+};
+
+#[test]
+fn test_media_timestamp_from_millis() {
+    let timestamp = MediaTimestamp::from_millis(1500);
+    assert_eq!(timestamp.value(), 1500);
+    assert_eq!(timestamp.timescale(), RTMP_TIMESCALE);
+    assert_eq!(timestamp.as_millis(), 1500);
+    assert_eq!(timestamp.as_secs_f64(), 1.5);
+    assert_eq!(MediaTimestamp::from(1500), timestamp);
+}
+
+#[test]
+fn test_media_timestamp_rescale() {
+    let timestamp = MediaTimestamp::from_millis(33);
+
+    // 33ms in a 90kHz clock is exactly 2970 ticks.
+    let rescaled = timestamp.rescale(NonZero::new(90000).unwrap());
+    assert_eq!(rescaled.value(), 2970);
+    assert_eq!(rescaled.as_millis(), 33);
+
+    // 1001 ticks at 30000Hz is 33.3666ms, which rounds down.
+    let timestamp = MediaTimestamp::new(1001, NonZero::new(30000).unwrap());
+    assert_eq!(timestamp.as_millis(), 33);
+
+    // 2002 ticks at 30000Hz is 66.7333ms, which rounds up instead of being truncated.
+    let timestamp = MediaTimestamp::new(2002, NonZero::new(30000).unwrap());
+    assert_eq!(timestamp.as_millis(), 67);
+
+    // Ties round away from zero.
+    let timestamp = MediaTimestamp::new(1, NonZero::new(2000).unwrap());
+    assert_eq!(timestamp.as_millis(), 1);
+    let timestamp = MediaTimestamp::new(-1, NonZero::new(2000).unwrap());
+    assert_eq!(timestamp.as_millis(), -1);
+}
+
+#[test]
+fn test_media_timestamp_time_base() {
+    let timestamp = MediaTimestamp::from_millis(1000);
+
+    // 1/90000
+    assert_eq!(timestamp.to_time_base(1, NonZero::new(90000).unwrap()), Some(90000));
+    // 1001/30000, one second is 29.97 frames.
+    assert_eq!(timestamp.to_time_base(1001, NonZero::new(30000).unwrap()), Some(30));
+    assert_eq!(timestamp.to_time_base(0, NonZero::new(1).unwrap()), None);
+
+    let timestamp = MediaTimestamp::from_time_base(30, 1001, NonZero::new(30000).unwrap(), RTMP_TIMESCALE);
+    assert_eq!(timestamp, MediaTimestamp::from_millis(1001));
+
+    // Negative denominators are normalized.
+    let timestamp = MediaTimestamp::from_time_base(-90000, -1, NonZero::new(-90000).unwrap(), RTMP_TIMESCALE);
+    assert_eq!(timestamp.value(), -1000);
+
+    // Round trip through a 1/90000 time base is lossless.
+    for millis in [0, 1, 33, 999, 123_456, u32::MAX] {
+        let timestamp = MediaTimestamp::from_millis(millis);
+        let pts = timestamp.to_time_base(1, NonZero::new(90000).unwrap()).unwrap();
+        let back = MediaTimestamp::from_time_base(pts, 1, NonZero::new(90000).unwrap(), RTMP_TIMESCALE);
+        assert_eq!(back, timestamp);
+    }
+}
+
+#[test]
+fn test_media_timestamp_saturates() {
+    let timestamp = MediaTimestamp::new(i64::MAX, NonZero::new(1).unwrap());
+    assert_eq!(timestamp.as_millis(), i64::MAX);
+
+    let timestamp = MediaTimestamp::new(i64::MIN, NonZero::new(1).unwrap());
+    assert_eq!(timestamp.as_millis(), i64::MIN);
+}
+
+#[test]
+fn test_channel_data_timestamp() {
+    let data = ChannelData::Video {
+        timestamp: MediaTimestamp::from_millis(40),
+        data: Bytes::from_static(b"video"),
+    };
+
+    assert_eq!(data.timestamp(), MediaTimestamp::from_millis(40));
+    assert_eq!(data.data(), &Bytes::from_static(b"video"));
+}
+
+#[test]
+fn test_data_buffer_metrics() {
+    let (data_producer, mut data_consumer) = tokio::sync::mpsc::channel(4);
+
+    let data = ChannelData::Audio {
+        timestamp: MediaTimestamp::from_millis(0),
+        data: Bytes::new(),
+    };
+    data_producer.try_send(data.clone()).unwrap();
+    data_producer.try_send(data).unwrap();
+
+    let metrics = DataBufferMetrics::new(&data_producer, 0, &Backpressure::default());
+    assert_eq!(
+        metrics,
+        DataBufferMetrics {
+            queued: 2,
+            capacity: 4,
+            peak: 2,
+            backlog: 0,
+            dropped: 0,
+        }
+    );
+
+    data_consumer.try_recv().unwrap();
+    let metrics = DataBufferMetrics::new(&data_producer, metrics.peak, &Backpressure::default());
+    assert_eq!(metrics.queued, 1);
+    assert_eq!(metrics.peak, 2);
+}
+
+fn video_frame(millis: u32, keyframe: bool) -> ChannelData {
+    video(millis, if keyframe { &[0x17, 0x01] } else { &[0x27, 0x01] })
+}
+
+/// Returns the timestamps of the messages waiting in `data_consumer`.
+fn received(data_consumer: &mut tokio::sync::mpsc::Receiver<ChannelData>) -> Vec<i64> {
+    std::iter::from_fn(|| data_consumer.try_recv().ok())
+        .map(|data| data.timestamp().as_millis())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_backpressure_block() {
+    let (data_producer, mut data_consumer) = tokio::sync::mpsc::channel(1);
+    let mut backpressure = Backpressure::new(BackpressurePolicy::Block);
+    let timeout = std::time::Duration::from_millis(10);
+
+    assert!(backpressure.send(&data_producer, video_frame(0, false), timeout).await);
+    // The consumer does not make room in time.
+    assert!(!backpressure.send(&data_producer, video_frame(1, false), timeout).await);
+    assert_eq!(received(&mut data_consumer), [0]);
+
+    drop(data_consumer);
+    assert!(!backpressure.send(&data_producer, video_frame(2, false), timeout).await);
+    assert_eq!(backpressure.dropped(), 0);
+}
+
+#[tokio::test]
+async fn test_backpressure_drop_non_keyframe() {
+    let (data_producer, mut data_consumer) = tokio::sync::mpsc::channel(2);
+    let mut backpressure = Backpressure::new(BackpressurePolicy::DropNonKeyframe);
+    let timeout = std::time::Duration::from_millis(10);
+
+    let audio = |timestamp| ChannelData::Audio {
+        timestamp: MediaTimestamp::from_millis(timestamp),
+        data: Bytes::from_static(&[0xaf, 0x01]),
+    };
+
+    for data in [video_frame(0, true), video_frame(1, false), video_frame(2, false), audio(3)] {
+        assert!(backpressure.send(&data_producer, data, timeout).await);
+    }
+    assert_eq!(backpressure.dropped(), 2);
+    assert_eq!(received(&mut data_consumer), [0, 1]);
+
+    // Frames after a dropped video frame are dropped up to the next keyframe, audio is not.
+    for data in [video_frame(4, false), audio(5), video_frame(6, true), video_frame(7, false)] {
+        assert!(backpressure.send(&data_producer, data, timeout).await);
+    }
+    assert_eq!(backpressure.dropped(), 4);
+    assert_eq!(received(&mut data_consumer), [5, 6]);
+
+    // Keyframes wait for room.
+    assert!(backpressure.send(&data_producer, video_frame(8, true), timeout).await);
+    assert!(backpressure.send(&data_producer, video_frame(9, false), timeout).await);
+    assert!(!backpressure.send(&data_producer, video_frame(10, true), timeout).await);
+    assert_eq!(received(&mut data_consumer), [8, 9]);
+}
+
+#[tokio::test]
+async fn test_backpressure_drop_oldest() {
+    let (data_producer, mut data_consumer) = tokio::sync::mpsc::channel(1);
+    let mut backpressure = Backpressure::new(BackpressurePolicy::DropOldest { backlog: 2 });
+    let timeout = std::time::Duration::from_millis(10);
+
+    let sequence_header = video(1, &[0x17, 0x00]);
+
+    assert!(backpressure.send(&data_producer, video_frame(0, true), timeout).await);
+    assert!(backpressure.send(&data_producer, sequence_header, timeout).await);
+    for timestamp in 2..5 {
+        assert!(
+            backpressure
+                .send(&data_producer, video_frame(timestamp, false), timeout)
+                .await
+        );
+    }
+
+    // The sequence header is kept, the oldest frame is dropped.
+    assert_eq!(backpressure.backlog(), 2);
+    assert_eq!(backpressure.dropped(), 2);
+    let metrics = DataBufferMetrics::new(&data_producer, 0, &backpressure);
+    assert_eq!((metrics.queued, metrics.backlog, metrics.dropped), (1, 2, 2));
+
+    // The backlog is sent first once there is room.
+    assert_eq!(received(&mut data_consumer), [0]);
+    assert!(backpressure.send(&data_producer, video_frame(5, false), timeout).await);
+    assert_eq!(received(&mut data_consumer), [1]);
+    assert!(backpressure.send(&data_producer, video_frame(6, false), timeout).await);
+    assert_eq!(received(&mut data_consumer), [4]);
+    assert_eq!(backpressure.backlog(), 2);
+
+    drop(data_consumer);
+    assert!(!backpressure.send(&data_producer, video_frame(7, false), timeout).await);
+}
+
+#[test]
+fn test_data_watermarks() {
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut watermarks = DataWatermarks::new(1, 3, {
+        let events = events.clone();
+        move |event| events.lock().unwrap().push(event)
+    });
+    assert_eq!(watermarks.low(), 1);
+    assert_eq!(watermarks.high(), 3);
+
+    let metrics = |queued| DataBufferMetrics {
+        queued,
+        capacity: 4,
+        peak: 4,
+        backlog: 0,
+        dropped: 0,
+    };
+
+    for queued in [0, 1, 2, 3, 4, 3, 2, 1, 0, 2, 3] {
+        watermarks.observe(metrics(queued));
+    }
+
+    // Only the crossings are reported, not every level above or below the watermarks.
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            WatermarkEvent::High(metrics(3)),
+            WatermarkEvent::Low(metrics(1)),
+            WatermarkEvent::High(metrics(3)),
+        ]
+    );
+}
+
+#[test]
+fn test_data_watermarks_clamped() {
+    let watermarks = DataWatermarks::new(5, 2, |_| {});
+    assert_eq!(watermarks.low(), 1);
+    assert_eq!(watermarks.high(), 2);
+
+    let watermarks = DataWatermarks::new(0, 0, |_| {});
+    assert_eq!(watermarks.low(), 0);
+    assert_eq!(watermarks.high(), 1);
+}
+
+fn video(millis: u32, data: &'static [u8]) -> ChannelData {
+    ChannelData::Video {
+        timestamp: MediaTimestamp::from_millis(millis),
+        data: Bytes::from_static(data),
+    }
+}
+
+fn audio(millis: u32, data: &'static [u8]) -> ChannelData {
+    ChannelData::Audio {
+        timestamp: MediaTimestamp::from_millis(millis),
+        data: Bytes::from_static(data),
+    }
+}
+
+#[test]
+fn test_sequence_headers_cached() {
+    let cache = SequenceHeaderCache::new();
+    assert!(cache.sequence_headers().is_empty());
+
+    // AVC keyframe sequence header, then an AVC NALU which is not cached.
+    cache.observe(&video(0, &[0x17, 0x00, 0x00, 0x00, 0x00, 0x01]));
+    cache.observe(&video(0, &[0x17, 0x01, 0x00, 0x00, 0x00, 0x02]));
+    // AAC sequence header, then raw AAC.
+    cache.observe(&audio(0, &[0xaf, 0x00, 0x12, 0x10]));
+    cache.observe(&audio(20, &[0xaf, 0x01, 0xff]));
+    // MP3 has no sequence header.
+    cache.observe(&audio(40, &[0x2f, 0x00]));
+
+    let headers = cache.sequence_headers();
+    assert_eq!(headers.video.unwrap().data().as_ref(), &[0x17, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    assert_eq!(headers.audio.unwrap().data().as_ref(), &[0xaf, 0x00, 0x12, 0x10]);
+    assert!(headers.metadata.is_none());
+
+    // Enhanced RTMP SequenceStart replaces the previous header, coded frames do not.
+    cache.observe(&video(100, b"\x90hvc1\x01"));
+    cache.observe(&video(100, b"\x91hvc1\x02"));
+    cache.observe(&audio(100, &[0x90, b'O', b'p', b'u', b's', 0x01]));
+    cache.observe(&audio(100, &[0x91, b'O', b'p', b'u', b's', 0x02]));
+
+    let headers = cache.sequence_headers();
+    assert_eq!(headers.video.unwrap().data().as_ref(), b"\x90hvc1\x01");
+    assert_eq!(headers.audio.unwrap().data().as_ref(), &[0x90, b'O', b'p', b'u', b's', 0x01]);
+
+    // Multitrack packets and packet modifier extensions are looked into.
+    cache.observe(&video(200, b"\x96\x00av01\x00\x03"));
+    cache.observe(&video(200, b"\x96\x01av01\x00\x04"));
+    cache.observe(&audio(200, &[0x97, 0x00, 0x2a, 0x00, b'O', b'p', b'u', b's', 0x05]));
+    cache.observe(&audio(200, &[0x97, 0x00, 0x2a, 0x01, b'O', b'p', b'u', b's', 0x06]));
+    // Cut short, not a sequence header.
+    cache.observe(&audio(200, &[0x97, 0x05, 0x2a]));
+
+    let headers = cache.sequence_headers();
+    assert_eq!(headers.video.unwrap().data().as_ref(), b"\x96\x00av01\x00\x03");
+    assert_eq!(
+        headers.audio.unwrap().data().as_ref(),
+        &[0x97, 0x00, 0x2a, 0x00, b'O', b'p', b'u', b's', 0x05]
+    );
+
+    cache.clear();
+    assert!(cache.sequence_headers().is_empty());
+}
+
+#[test]
+fn test_stream_metadata_parse() {
+    let mut data = Vec::new();
+    Amf0Encoder::encode_string(&mut data, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut data, "onMetaData").unwrap();
+    Amf0Encoder::encode_object(
+        &mut data,
+        &[
+            ("duration".into(), Amf0Value::Number(0.0)),
+            ("width".into(), Amf0Value::Number(1920.0)),
+            ("height".into(), Amf0Value::Number(1080.0)),
+            ("videodatarate".into(), Amf0Value::Number(6000.0)),
+            ("framerate".into(), Amf0Value::Number(59.94)),
+            ("videocodecid".into(), Amf0Value::String("hvc1".into())),
+            ("audiodatarate".into(), Amf0Value::Number(160.0)),
+            ("audiosamplerate".into(), Amf0Value::Number(48000.0)),
+            ("audiosamplesize".into(), Amf0Value::Number(16.0)),
+            ("stereo".into(), Amf0Value::Boolean(true)),
+            ("audiocodecid".into(), Amf0Value::Number(10.0)),
+            ("encoder".into(), Amf0Value::String("obs-output module".into())),
+            ("2.1".into(), Amf0Value::Boolean(false)),
+            // The wrong type, kept as a custom property.
+            ("stereo".into(), Amf0Value::Number(2.0)),
+        ],
+    )
+    .unwrap();
+
+    let metadata = StreamMetadata::parse(&data).unwrap();
+    assert_eq!(
+        metadata,
+        StreamMetadata {
+            duration: Some(0.0),
+            width: Some(1920),
+            height: Some(1080),
+            video_data_rate: Some(6000.0),
+            frame_rate: Some(59.94),
+            video_codec_id: Some(u32::from_be_bytes(*b"hvc1")),
+            audio_data_rate: Some(160.0),
+            audio_sample_rate: Some(48000),
+            audio_sample_size: Some(16),
+            stereo: Some(true),
+            audio_codec_id: Some(10),
+            encoder: Some("obs-output module".into()),
+            custom: vec![
+                ("2.1".into(), Amf0Value::Boolean(false)),
+                ("stereo".into(), Amf0Value::Number(2.0)),
+            ],
+        }
+    );
+    assert_eq!(metadata.custom_property("2.1"), Some(&Amf0Value::Boolean(false)));
+    assert_eq!(metadata.custom_property("missing"), None);
+
+    // Without the `@setDataFrame` prefix, as an ECMA array.
+    let mut data = Vec::new();
+    Amf0Encoder::encode_string(&mut data, "onMetaData").unwrap();
+    data.extend_from_slice(&[0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x05]);
+    data.extend_from_slice(b"width");
+    data.push(0x00);
+    data.extend_from_slice(&1280.0_f64.to_be_bytes());
+
+    let cache = SequenceHeaderCache::new();
+    cache.observe(&ChannelData::Metadata {
+        timestamp: MediaTimestamp::from_millis(0),
+        data: Bytes::from(data),
+    });
+    let metadata = cache.sequence_headers().stream_metadata().unwrap();
+    assert_eq!(metadata.width, Some(1280));
+    assert_eq!(metadata.height, None);
+
+    let mut data = Vec::new();
+    Amf0Encoder::encode_string(&mut data, "onTextData").unwrap();
+    Amf0Encoder::encode_object(&mut data, &[]).unwrap();
+    assert_eq!(StreamMetadata::parse(&data), None);
+    assert_eq!(StreamMetadata::parse(&[]), None);
+}
+
+#[tokio::test]
+async fn test_sequence_headers_replayed_on_attach() {
+    let cache = SequenceHeaderCache::new();
+
+    cache.observe(&ChannelData::Metadata {
+        timestamp: MediaTimestamp::from_millis(0),
+        data: Bytes::from_static(b"metadata"),
+    });
+    cache.observe(&audio(0, &[0xaf, 0x00, 0x12, 0x10]));
+    cache.observe(&video(0, &[0x17, 0x00, 0x01]));
+    cache.observe(&video(33, &[0x27, 0x01, 0x02]));
+
+    // Attached mid-stream, the headers do not take up the room for new messages.
+    let mut consumer = cache.attach(1);
+    assert_eq!(cache.attached(), 1);
+
+    cache.observe(&video(66, &[0x27, 0x01, 0x03]));
+
+    let received: Vec<_> = std::iter::from_fn(|| consumer.try_recv().ok()).collect();
+    let payloads: Vec<&[u8]> = received.iter().map(|data| data.data().as_ref()).collect();
+    assert_eq!(
+        payloads,
+        vec![
+            b"metadata".as_slice(),
+            &[0x17, 0x00, 0x01],
+            &[0xaf, 0x00, 0x12, 0x10],
+            &[0x27, 0x01, 0x03],
+        ]
+    );
+
+    // A dropped consumer is detached.
+    drop(consumer);
+    cache.observe(&video(99, &[0x27, 0x01, 0x04]));
+    assert_eq!(cache.attached(), 0);
+
+    // A full consumer is detached instead of stalling the publisher.
+    let _consumer = cache.attach(3);
+    for millis in 0..10 {
+        cache.observe(&video(millis, &[0x27, 0x01, 0x05]));
+    }
+    assert_eq!(cache.attached(), 0);
+}
+
+#[test]
+fn test_message_filter_closure() {
+    // Drops disposable inter frames and shifts the timestamps of everything else.
+    let mut filter = |data: ChannelData| match data {
+        ChannelData::Video { ref data, .. } if data.first().is_some_and(|byte| byte >> 4 == 3) => None,
+        ChannelData::Video { timestamp, data } => Some(ChannelData::Video {
+            timestamp: MediaTimestamp::from_millis(timestamp.as_millis() as u32 + 1000),
+            data,
+        }),
+        data => Some(data),
+    };
+    let filter: &mut dyn MessageFilter = &mut filter;
+
+    let filtered = filter.filter(video(33, &[0x27, 0x01])).expect("inter frame was dropped");
+    assert_eq!(filtered.timestamp(), MediaTimestamp::from_millis(1033));
+    assert_eq!(filtered.data().as_ref(), &[0x27, 0x01]);
+
+    assert!(filter.filter(video(66, &[0x37, 0x01])).is_none());
+
+    let filtered = filter.filter(audio(0, &[0xaf, 0x01])).expect("audio was dropped");
+    assert_eq!(filtered.timestamp(), MediaTimestamp::from_millis(0));
+}
+
+#[tokio::test]
+async fn test_reconnect_grace_resume() {
+    let grace = ReconnectGrace::new(std::time::Duration::from_secs(60));
+    let (data_producer, _data_consumer) = tokio::sync::mpsc::channel(1);
+    let uid = UniqueID::new_v4();
+
+    grace.park(
+        "live",
+        "key",
+        ParkedStream {
+            uid,
+            data_producer,
+            sequence_headers: SequenceHeaderCache::new(),
+            last_timestamp: MediaTimestamp::from_millis(1500),
+        },
+    );
+    assert_eq!(grace.parked(), 1);
+
+    assert!(grace.resume("live", "other").is_none());
+    let parked = grace.resume("live", "key").expect("stream was not parked");
+    assert_eq!(parked.uid, uid);
+    assert_eq!(parked.last_timestamp, MediaTimestamp::from_millis(1500));
+
+    // A stream can only be resumed once.
+    assert!(grace.resume("live", "key").is_none());
+    assert_eq!(grace.parked(), 0);
+
+    let resume = ChannelData::Resume {
+        timestamp: parked.last_timestamp,
+    };
+    assert_eq!(resume.timestamp(), MediaTimestamp::from_millis(1500));
+    assert!(resume.data().is_empty());
+}
+
+#[tokio::test]
+async fn test_reconnect_grace_expiry() {
+    let grace = ReconnectGrace::new(std::time::Duration::from_millis(20));
+    let (data_producer, mut data_consumer) = tokio::sync::mpsc::channel(1);
+
+    grace.park(
+        "live",
+        "key",
+        ParkedStream {
+            uid: UniqueID::new_v4(),
+            data_producer,
+            sequence_headers: SequenceHeaderCache::new(),
+            last_timestamp: MediaTimestamp::from_millis(0),
+        },
+    );
+
+    // The consumer sees the channel close once the period is over.
+    let closed = tokio::time::timeout(std::time::Duration::from_secs(5), data_consumer.recv()).await;
+    assert!(matches!(closed, Ok(None)));
+    assert_eq!(grace.parked(), 0);
+    assert!(grace.resume("live", "key").is_none());
+}
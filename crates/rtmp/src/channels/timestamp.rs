@@ -0,0 +1,111 @@
+use std::num::NonZero;
+
+/// The timescale of RTMP timestamps, which are always in milliseconds.
+pub const RTMP_TIMESCALE: NonZero<u32> = NonZero::new(1000).unwrap();
+
+/// A media timestamp, expressed as a number of ticks in a timescale.
+///
+/// A timestamp with `value` 1500 and `timescale` 1000 is 1.5 seconds.
+/// In ffmpeg terms, the timestamp is `value` in the time base `1 / timescale`.
+///
+/// All conversions round to the nearest tick (ties away from zero), the same
+/// as `av_rescale_q`, so converting between RTMP milliseconds and a stream time base
+/// does not accumulate truncation errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MediaTimestamp {
+    value: i64,
+    timescale: NonZero<u32>,
+}
+
+impl MediaTimestamp {
+    /// Create a new timestamp of `value` ticks in `timescale` ticks per second.
+    pub const fn new(value: i64, timescale: NonZero<u32>) -> Self {
+        Self { value, timescale }
+    }
+
+    /// Create a new timestamp from an RTMP timestamp in milliseconds.
+    pub const fn from_millis(millis: u32) -> Self {
+        Self::new(millis as i64, RTMP_TIMESCALE)
+    }
+
+    /// Create a new timestamp from a value in the time base `numerator / denominator`,
+    /// such as an ffmpeg `Rational`, rescaled to `timescale`.
+    pub fn from_time_base(value: i64, numerator: i32, denominator: NonZero<i32>, timescale: NonZero<u32>) -> Self {
+        let value = rescale(
+            value as i128,
+            numerator as i128 * timescale.get() as i128,
+            denominator.get() as i128,
+        );
+
+        Self::new(value, timescale)
+    }
+
+    /// The number of ticks.
+    pub const fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// The number of ticks per second.
+    pub const fn timescale(&self) -> NonZero<u32> {
+        self.timescale
+    }
+
+    /// Convert the timestamp to another timescale.
+    pub fn rescale(&self, timescale: NonZero<u32>) -> Self {
+        if timescale == self.timescale {
+            return *self;
+        }
+
+        let value = rescale(self.value as i128, timescale.get() as i128, self.timescale.get() as i128);
+
+        Self::new(value, timescale)
+    }
+
+    /// Convert the timestamp to a value in the time base `numerator / denominator`,
+    /// such as an ffmpeg `Rational`.
+    ///
+    /// Returns `None` if `numerator` is 0.
+    pub fn to_time_base(&self, numerator: i32, denominator: NonZero<i32>) -> Option<i64> {
+        if numerator == 0 {
+            return None;
+        }
+
+        Some(rescale(
+            self.value as i128,
+            denominator.get() as i128,
+            numerator as i128 * self.timescale.get() as i128,
+        ))
+    }
+
+    /// The timestamp in milliseconds.
+    pub fn as_millis(&self) -> i64 {
+        self.rescale(RTMP_TIMESCALE).value
+    }
+
+    /// The timestamp in seconds.
+    pub fn as_secs_f64(&self) -> f64 {
+        self.value as f64 / self.timescale.get() as f64
+    }
+}
+
+impl From<u32> for MediaTimestamp {
+    fn from(millis: u32) -> Self {
+        Self::from_millis(millis)
+    }
+}
+
+/// Computes `value * mul / div` rounded to the nearest integer, with ties away from zero.
+/// The result saturates at the bounds of an `i64`.
+fn rescale(value: i128, mul: i128, div: i128) -> i64 {
+    let (mul, div) = if div < 0 { (-mul, -div) } else { (mul, div) };
+
+    let product = value.saturating_mul(mul);
+    let half = div / 2;
+    let rounded = if product < 0 {
+        product.saturating_sub(half) / div
+    } else {
+        product.saturating_add(half) / div
+    };
+
+    rounded.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use super::{Backpressure, DataProducer};
+
+/// How full the data channel of a session is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataBufferMetrics {
+    /// The number of messages sent by the session but not received by the consumer yet.
+    pub queued: usize,
+    /// The capacity of the channel.
+    pub capacity: usize,
+    /// The largest number of messages that were queued at once.
+    pub peak: usize,
+    /// The number of messages waiting in the session for room in the channel,
+    /// with [`BackpressurePolicy::DropOldest`](crate::BackpressurePolicy::DropOldest).
+    pub backlog: usize,
+    /// The number of messages dropped by the [`BackpressurePolicy`](crate::BackpressurePolicy).
+    pub dropped: u64,
+}
+
+impl DataBufferMetrics {
+    pub(crate) fn new(data_producer: &DataProducer, peak: usize, backpressure: &Backpressure) -> Self {
+        let capacity = data_producer.max_capacity();
+        let queued = capacity - data_producer.capacity();
+
+        Self {
+            queued,
+            capacity,
+            peak: peak.max(queued),
+            backlog: backpressure.backlog(),
+            dropped: backpressure.dropped(),
+        }
+    }
+}
+
+/// A crossing of one of the [`DataWatermarks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatermarkEvent {
+    /// The number of queued messages reached the high watermark, the consumer is falling behind.
+    High(DataBufferMetrics),
+    /// The number of queued messages dropped to the low watermark after reaching the high watermark,
+    /// the consumer caught up again.
+    Low(DataBufferMetrics),
+}
+
+/// Low and high watermarks on the data channel of a session, with a callback
+/// that is called when they are crossed.
+///
+/// This gives an application a chance to react to a consumer that lags behind,
+/// for example by asking the encoder to reduce its bitrate or by spilling to disk,
+/// before the channel is full and the session is disconnected.
+///
+/// The callback is called with [`WatermarkEvent::High`] when the number of queued
+/// messages reaches `high`, and with [`WatermarkEvent::Low`] once it drops to `low` again.
+/// In between no further events are sent, so the callback is not called for every message.
+/// The level is checked every time the session sends a message.
+pub struct DataWatermarks {
+    low: usize,
+    high: usize,
+    above_high: bool,
+    callback: Box<dyn FnMut(WatermarkEvent) + Send + Sync>,
+}
+
+impl fmt::Debug for DataWatermarks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataWatermarks")
+            .field("low", &self.low)
+            .field("high", &self.high)
+            .field("above_high", &self.above_high)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DataWatermarks {
+    /// Create new watermarks, in number of queued messages.
+    ///
+    /// `high` is at least 1 and `low` is clamped to be below `high`, so the events alternate.
+    pub fn new(low: usize, high: usize, callback: impl FnMut(WatermarkEvent) + Send + Sync + 'static) -> Self {
+        let high = high.max(1);
+
+        Self {
+            low: low.min(high - 1),
+            high,
+            above_high: false,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Returns the low watermark.
+    pub fn low(&self) -> usize {
+        self.low
+    }
+
+    /// Returns the high watermark.
+    pub fn high(&self) -> usize {
+        self.high
+    }
+
+    /// Calls the callback if `metrics` crossed one of the watermarks since the last call.
+    pub(crate) fn observe(&mut self, metrics: DataBufferMetrics) {
+        if !self.above_high && metrics.queued >= self.high {
+            self.above_high = true;
+            (self.callback)(WatermarkEvent::High(metrics));
+        } else if self.above_high && metrics.queued <= self.low {
+            self.above_high = false;
+            (self.callback)(WatermarkEvent::Low(metrics));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use super::define::DefinedChunkStreamID;
+use crate::messages::MessageTypeID;
+
+/// The chunk stream id reserved for protocol control messages.
+/// 5.4 "... MUST be sent in chunk stream ID 2 ..."
+pub const PROTOCOL_CONTROL_CHUNK_STREAM_ID: u32 = 2;
+
+/// The first chunk stream id handed out for tracks beyond the defaults.
+/// With 3 kinds and 255 extra tracks each, ids stay far below the 65599 limit
+/// of the 3 byte basic header.
+const FIRST_EXTRA_CHUNK_STREAM_ID: u32 = DefinedChunkStreamID::Video as u32 + 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum ChunkStreamKind {
+    Command,
+    Audio,
+    Video,
+}
+
+impl ChunkStreamKind {
+    fn from_message_type(msg_type_id: MessageTypeID) -> Option<Self> {
+        match msg_type_id {
+            MessageTypeID::SetChunkSize
+            | MessageTypeID::Abort
+            | MessageTypeID::Acknowledgement
+            | MessageTypeID::UserControlEvent
+            | MessageTypeID::WindowAcknowledgementSize
+            | MessageTypeID::SetPeerBandwidth => None,
+            MessageTypeID::Audio => Some(Self::Audio),
+            MessageTypeID::Video | MessageTypeID::Aggregate => Some(Self::Video),
+            MessageTypeID::DataAMF3
+            | MessageTypeID::SharedObjAMF3
+            | MessageTypeID::CommandAMF3
+            | MessageTypeID::DataAMF0
+            | MessageTypeID::SharedObjAMF0
+            | MessageTypeID::CommandAMF0 => Some(Self::Command),
+        }
+    }
+
+    const fn default_id(self) -> u32 {
+        match self {
+            Self::Command => DefinedChunkStreamID::Command as u32,
+            Self::Audio => DefinedChunkStreamID::Audio as u32,
+            Self::Video => DefinedChunkStreamID::Video as u32,
+        }
+    }
+}
+
+/// Assigns chunk stream ids to outgoing messages.
+///
+/// Track 0 uses the conventional ids from [`DefinedChunkStreamID`]
+/// (command = 3, audio = 4, video = 5). Every other (kind, track) pair, such as
+/// the additional tracks of enhanced RTMP multitrack audio and video, gets its
+/// own id starting at 6, so messages of different tracks are never interleaved
+/// on the same chunk stream. Protocol control messages always use chunk stream 2.
+///
+/// The same message type and track always get the same id.
+#[derive(Debug, Clone)]
+pub struct ChunkStreamAllocator {
+    assigned: HashMap<(ChunkStreamKind, u8), u32>,
+    next_id: u32,
+}
+
+impl Default for ChunkStreamAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkStreamAllocator {
+    /// Creates an allocator with no tracks assigned yet.
+    pub fn new() -> Self {
+        Self {
+            assigned: HashMap::new(),
+            next_id: FIRST_EXTRA_CHUNK_STREAM_ID,
+        }
+    }
+
+    /// Returns the chunk stream id for a message of the given type and track,
+    /// assigning a new one if this is the first message of the track.
+    pub fn chunk_stream_id(&mut self, msg_type_id: MessageTypeID, track: u8) -> u32 {
+        let Some(kind) = ChunkStreamKind::from_message_type(msg_type_id) else {
+            return PROTOCOL_CONTROL_CHUNK_STREAM_ID;
+        };
+
+        if track == 0 {
+            return kind.default_id();
+        }
+
+        *self.assigned.entry((kind, track)).or_insert_with(|| {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        })
+    }
+}
@@ -6,8 +6,9 @@ use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use bytes::BytesMut;
 use num_traits::FromPrimitive;
 
+use super::allocator::PROTOCOL_CONTROL_CHUNK_STREAM_ID;
 use super::define::{Chunk, ChunkBasicHeader, ChunkMessageHeader, ChunkType, INIT_CHUNK_SIZE, MAX_CHUNK_SIZE};
-use super::errors::ChunkDecodeError;
+use super::errors::{ChunkDecodeError, ProtocolViolation};
 use crate::messages::MessageTypeID;
 
 // These constants are used to limit the amount of memory we use for partial
@@ -58,6 +59,14 @@ impl ChunkDecoder {
         }
     }
 
+    /// Discards the partially received message on a chunk stream.
+    ///
+    /// Called when the peer sends an Abort message (5.4.2), because it will not
+    /// send the rest of the message.
+    pub fn abort_chunk_stream(&mut self, chunk_stream_id: u32) {
+        self.partial_chunks.retain(|(csid, _), _| *csid != chunk_stream_id);
+    }
+
     /// This function is used to read a chunk from the buffer.
     /// - will return Ok(None) if the buffer is empty.
     /// - will return Ok(Some(Chunk)) if we have a full chunk.
@@ -100,6 +109,10 @@ impl ChunkDecoder {
                 }
             };
 
+            // Checked before the payload is read, so a violating message is never
+            // handed to the rest of the session.
+            Self::validate_header(&header, &message_header)?;
+
             let (payload_range_start, payload_range_end) =
                 match self.get_payload_range(&header, &message_header, &mut cursor) {
                     Ok(data) => data,
@@ -227,6 +240,43 @@ impl ChunkDecoder {
         }
     }
 
+    /// Internal function used to check the headers of a chunk against the rules
+    /// of the spec on which streams a message may be sent.
+    fn validate_header(header: &ChunkBasicHeader, message_header: &ChunkMessageHeader) -> Result<(), ProtocolViolation> {
+        let msg_type_id = message_header.msg_type_id;
+
+        match msg_type_id {
+            // User control messages are also sent on chunk stream 2, but the spec only
+            // says they SHOULD be, so we do not enforce it for them.
+            MessageTypeID::SetChunkSize
+            | MessageTypeID::Abort
+            | MessageTypeID::Acknowledgement
+            | MessageTypeID::WindowAcknowledgementSize
+            | MessageTypeID::SetPeerBandwidth => {
+                if message_header.msg_stream_id != 0 {
+                    return Err(ProtocolViolation::ControlMessageStreamID {
+                        msg_type_id,
+                        msg_stream_id: message_header.msg_stream_id,
+                    });
+                }
+
+                if header.chunk_stream_id != PROTOCOL_CONTROL_CHUNK_STREAM_ID {
+                    return Err(ProtocolViolation::ControlChunkStreamID {
+                        msg_type_id,
+                        chunk_stream_id: header.chunk_stream_id,
+                    });
+                }
+            }
+            MessageTypeID::UserControlEvent | MessageTypeID::CommandAMF0 | MessageTypeID::CommandAMF3 => {}
+            _ if header.chunk_stream_id == PROTOCOL_CONTROL_CHUNK_STREAM_ID => {
+                return Err(ProtocolViolation::ReservedChunkStreamID { msg_type_id });
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     /// Internal function used to read the basic chunk header.
     fn read_header(&self, cursor: &mut Cursor<&[u8]>) -> Result<ChunkBasicHeader, Option<ChunkDecodeError>> {
         // The first byte of the basic header is the format of the chunk and the stream
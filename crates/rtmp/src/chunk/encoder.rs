@@ -1,9 +1,11 @@
 use std::io;
 
 use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use bytes::Bytes;
 
 use super::define::{Chunk, ChunkMessageHeader, ChunkType, INIT_CHUNK_SIZE};
 use super::errors::ChunkEncodeError;
+use super::write_buffer::ChunkWriteBuffer;
 
 pub struct ChunkEncoder {
     chunk_size: usize,
@@ -73,7 +75,26 @@ impl ChunkEncoder {
         Ok(())
     }
 
-    pub fn write_chunk(&self, writer: &mut impl io::Write, mut chunk_info: Chunk) -> Result<(), ChunkEncodeError> {
+    pub fn write_chunk(&self, writer: &mut impl io::Write, chunk_info: Chunk) -> Result<(), ChunkEncodeError> {
+        self.encode(writer, chunk_info, |writer, payload| writer.write_all(&payload))
+    }
+
+    /// Writes `chunk_info` like [`ChunkEncoder::write_chunk`], but without copying the payload,
+    /// see [`ChunkWriteBuffer`].
+    pub fn write_chunk_to_buffer(&self, buffer: &mut ChunkWriteBuffer, chunk_info: Chunk) -> Result<(), ChunkEncodeError> {
+        self.encode(buffer, chunk_info, |buffer, payload| {
+            buffer.push_bytes(payload);
+            Ok(())
+        })
+    }
+
+    /// Splits the payload of `chunk_info` into chunks, writing each with `write_payload`.
+    fn encode<W: io::Write>(
+        &self,
+        writer: &mut W,
+        mut chunk_info: Chunk,
+        write_payload: impl Fn(&mut W, Bytes) -> io::Result<()>,
+    ) -> Result<(), ChunkEncodeError> {
         Self::write_basic_header(writer, ChunkType::Type0, chunk_info.basic_header.chunk_stream_id)?;
 
         Self::write_message_header(writer, &chunk_info.message_header)?;
@@ -86,7 +107,7 @@ impl ChunkEncoder {
             };
 
             let payload_bytes = chunk_info.payload.split_to(cur_payload_size);
-            writer.write_all(&payload_bytes[..])?;
+            write_payload(writer, payload_bytes)?;
 
             if !chunk_info.payload.is_empty() {
                 Self::write_basic_header(writer, ChunkType::Type3, chunk_info.basic_header.chunk_stream_id)?;
@@ -1,6 +1,51 @@
 use std::{fmt, io};
 
 use crate::macros::from_error;
+use crate::messages::MessageTypeID;
+
+/// A rule of the chunk stream protocol that the peer broke.
+///
+/// The connection is closed when one is found, as the stream cannot be
+/// trusted to be in sync anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolViolation {
+    /// 5.4 "Protocol control messages MUST have message stream ID 0"
+    ControlMessageStreamID { msg_type_id: MessageTypeID, msg_stream_id: u32 },
+    /// 5.4 "Protocol control messages ... MUST be sent in chunk stream ID 2"
+    ControlChunkStreamID {
+        msg_type_id: MessageTypeID,
+        chunk_stream_id: u32,
+    },
+    /// 5.3.1.1 "Chunk stream ID 2 is reserved for low-level protocol control
+    /// messages and commands", so media and data may not be sent on it.
+    ReservedChunkStreamID { msg_type_id: MessageTypeID },
+}
+
+impl fmt::Display for ProtocolViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ControlMessageStreamID {
+                msg_type_id,
+                msg_stream_id,
+            } => write!(
+                f,
+                "protocol control message {:?} sent on message stream {}, expected 0",
+                msg_type_id, msg_stream_id
+            ),
+            Self::ControlChunkStreamID {
+                msg_type_id,
+                chunk_stream_id,
+            } => write!(
+                f,
+                "protocol control message {:?} sent on chunk stream {}, expected 2",
+                msg_type_id, chunk_stream_id
+            ),
+            Self::ReservedChunkStreamID { msg_type_id } => {
+                write!(f, "message {:?} sent on reserved chunk stream 2", msg_type_id)
+            }
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum ChunkDecodeError {
@@ -12,9 +57,11 @@ pub enum ChunkDecodeError {
     TooManyPreviousChunkHeaders,
     PartialChunkTooLarge(usize),
     TimestampOverflow(u32, u32),
+    ProtocolViolation(ProtocolViolation),
 }
 
 from_error!(ChunkDecodeError, Self::IO, io::Error);
+from_error!(ChunkDecodeError, Self::ProtocolViolation, ProtocolViolation);
 
 #[derive(Debug)]
 pub enum ChunkEncodeError {
@@ -52,6 +99,7 @@ impl fmt::Display for ChunkDecodeError {
             Self::TimestampOverflow(timestamp, delta) => {
                 write!(f, "timestamp overflow: timestamp: {}, delta: {}", timestamp, delta)
             }
+            Self::ProtocolViolation(violation) => write!(f, "protocol violation: {}", violation),
         }
     }
 }
@@ -1,12 +1,17 @@
+mod allocator;
 mod decoder;
 mod define;
 mod encoder;
 mod errors;
+mod write_buffer;
 
+pub use self::allocator::{ChunkStreamAllocator, PROTOCOL_CONTROL_CHUNK_STREAM_ID};
 pub use self::decoder::ChunkDecoder;
 pub use self::define::{CHUNK_SIZE, Chunk, DefinedChunkStreamID};
+pub(crate) use self::define::{INIT_CHUNK_SIZE, MAX_CHUNK_SIZE};
 pub use self::encoder::ChunkEncoder;
-pub use self::errors::{ChunkDecodeError, ChunkEncodeError};
+pub use self::errors::{ChunkDecodeError, ChunkEncodeError, ProtocolViolation};
+pub use self::write_buffer::ChunkWriteBuffer;
 
 #[cfg(test)]
 mod tests;
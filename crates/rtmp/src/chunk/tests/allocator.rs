@@ -0,0 +1,61 @@
+use crate::chunk::{ChunkStreamAllocator, DefinedChunkStreamID, PROTOCOL_CONTROL_CHUNK_STREAM_ID};
+use crate::messages::MessageTypeID;
+
+#[test]
+fn test_allocator_defaults() {
+    let mut allocator = ChunkStreamAllocator::new();
+
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::CommandAMF0, 0),
+        DefinedChunkStreamID::Command as u32
+    );
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::DataAMF0, 0),
+        DefinedChunkStreamID::Command as u32
+    );
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::Audio, 0),
+        DefinedChunkStreamID::Audio as u32
+    );
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::Video, 0),
+        DefinedChunkStreamID::Video as u32
+    );
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::SetChunkSize, 0),
+        PROTOCOL_CONTROL_CHUNK_STREAM_ID
+    );
+    assert_eq!(
+        allocator.chunk_stream_id(MessageTypeID::UserControlEvent, 3),
+        PROTOCOL_CONTROL_CHUNK_STREAM_ID
+    );
+}
+
+#[test]
+fn test_allocator_multitrack() {
+    let mut allocator = ChunkStreamAllocator::default();
+
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Video, 1), 6);
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Audio, 1), 7);
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Video, 2), 8);
+
+    // The same track keeps its id.
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Video, 1), 6);
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Audio, 1), 7);
+    assert_eq!(allocator.chunk_stream_id(MessageTypeID::Video, 0), 5);
+}
+
+#[test]
+fn test_allocator_all_tracks() {
+    let mut allocator = ChunkStreamAllocator::new();
+
+    let mut ids = std::collections::HashSet::new();
+    for track in 1..=u8::MAX {
+        for msg_type_id in [MessageTypeID::CommandAMF0, MessageTypeID::Audio, MessageTypeID::Video] {
+            assert!(ids.insert(allocator.chunk_stream_id(msg_type_id, track)));
+        }
+    }
+
+    assert_eq!(ids.iter().min(), Some(&6));
+    assert_eq!(ids.iter().max(), Some(&(6 + 3 * 255 - 1)));
+}
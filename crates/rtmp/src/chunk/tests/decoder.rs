@@ -1,7 +1,8 @@
 use byteorder::WriteBytesExt;
 use bytes::{BufMut, BytesMut};
 
-use crate::chunk::{ChunkDecodeError, ChunkDecoder};
+use crate::chunk::{ChunkDecodeError, ChunkDecoder, ProtocolViolation};
+use crate::messages::MessageTypeID;
 
 #[test]
 fn test_decoder_error_display() {
@@ -28,6 +29,32 @@ fn test_decoder_error_display() {
 
     let error = ChunkDecodeError::TimestampOverflow(100, 200);
     assert_eq!(format!("{}", error), "timestamp overflow: timestamp: 100, delta: 200");
+
+    let error = ChunkDecodeError::ProtocolViolation(ProtocolViolation::ControlMessageStreamID {
+        msg_type_id: MessageTypeID::SetChunkSize,
+        msg_stream_id: 1,
+    });
+    assert_eq!(
+        format!("{}", error),
+        "protocol violation: protocol control message SetChunkSize sent on message stream 1, expected 0"
+    );
+
+    let error = ChunkDecodeError::ProtocolViolation(ProtocolViolation::ControlChunkStreamID {
+        msg_type_id: MessageTypeID::Acknowledgement,
+        chunk_stream_id: 3,
+    });
+    assert_eq!(
+        format!("{}", error),
+        "protocol violation: protocol control message Acknowledgement sent on chunk stream 3, expected 2"
+    );
+
+    let error = ChunkDecodeError::ProtocolViolation(ProtocolViolation::ReservedChunkStreamID {
+        msg_type_id: MessageTypeID::Video,
+    });
+    assert_eq!(
+        format!("{}", error),
+        "protocol violation: message Video sent on reserved chunk stream 2"
+    );
 }
 
 #[test]
@@ -412,7 +439,7 @@ fn test_decoder_error_too_many_partial_chunks() {
         // Write another chunk with a different chunk stream id
         #[rustfmt::skip]
         buf.extend_from_slice(&[
-            (i + 2), // chunk type 0 (partial), chunk stream id i
+            (i + 3), // chunk type 0 (partial), chunk stream id i + 3
             0xFF, 0xFF, 0xFF, // timestamp
             0x00, 0x01, 0x00, // message length (max chunk size is set to 128)
             0x09, // message type id (video)
@@ -534,3 +561,122 @@ fn test_decoder_larger_chunk_size() {
         assert_eq!(chunk.payload[i], i as u8);
     }
 }
+
+#[test]
+fn test_decoder_protocol_violations() {
+    let cases = [
+        // set chunk size on message stream 1
+        (
+            2,
+            0x01,
+            1,
+            ProtocolViolation::ControlMessageStreamID {
+                msg_type_id: MessageTypeID::SetChunkSize,
+                msg_stream_id: 1,
+            },
+        ),
+        // window acknowledgement size on chunk stream 3
+        (
+            3,
+            0x05,
+            0,
+            ProtocolViolation::ControlChunkStreamID {
+                msg_type_id: MessageTypeID::WindowAcknowledgementSize,
+                chunk_stream_id: 3,
+            },
+        ),
+        // video on chunk stream 2
+        (
+            2,
+            0x09,
+            1,
+            ProtocolViolation::ReservedChunkStreamID {
+                msg_type_id: MessageTypeID::Video,
+            },
+        ),
+    ];
+
+    for (chunk_stream_id, msg_type_id, msg_stream_id, violation) in cases {
+        let mut buf = BytesMut::new();
+
+        #[rustfmt::skip]
+        buf.extend_from_slice(&[
+            chunk_stream_id, // chunk type 0
+            0x00, 0x00, 0x00, // timestamp
+            0x00, 0x00, 0x04, // message length
+            msg_type_id, // message type id
+            msg_stream_id, 0x00, 0x00, 0x00, // message stream id
+            0x00, 0x00, 0x10, 0x00, // payload
+        ]);
+
+        let mut unpacker = ChunkDecoder::default();
+        match unpacker.read_chunk(&mut buf) {
+            Err(ChunkDecodeError::ProtocolViolation(err)) => assert_eq!(err, violation),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_decoder_control_messages_allowed() {
+    let mut buf = BytesMut::new();
+
+    #[rustfmt::skip]
+    buf.extend_from_slice(&[
+        2, // chunk type 0, chunk stream id 2
+        0x00, 0x00, 0x00, // timestamp
+        0x00, 0x00, 0x04, // message length
+        0x01, // message type id (set chunk size)
+        0x00, 0x00, 0x00, 0x00, // message stream id
+        0x00, 0x00, 0x10, 0x00, // payload
+        3, // chunk type 0, chunk stream id 3
+        0x00, 0x00, 0x00, // timestamp
+        0x00, 0x00, 0x00, // message length
+        0x14, // message type id (amf0 command)
+        0x00, 0x00, 0x00, 0x00, // message stream id
+        2, // chunk type 0, chunk stream id 2
+        0x00, 0x00, 0x00, // timestamp
+        0x00, 0x00, 0x00, // message length
+        0x04, // message type id (user control event), not enforced
+        0x01, 0x00, 0x00, 0x00, // message stream id
+    ]);
+
+    let mut unpacker = ChunkDecoder::default();
+    for _ in 0..3 {
+        unpacker.read_chunk(&mut buf).expect("read chunk").expect("chunk");
+    }
+}
+
+#[test]
+fn test_decoder_abort_chunk_stream() {
+    let mut buf = BytesMut::new();
+
+    #[rustfmt::skip]
+    buf.extend_from_slice(&[
+        3, // chunk type 0, chunk stream id 3
+        0x00, 0x00, 0x00, // timestamp
+        0x00, 0x01, 0x00, // message length (256) (max chunk size is set to 128)
+        0x09, // message type id (video)
+        0x01, 0x00, 0x00, 0x00, // message stream id
+    ]);
+    buf.extend_from_slice(&[0xAA; 128]);
+
+    let mut unpacker = ChunkDecoder::default();
+    assert!(unpacker.read_chunk(&mut buf).expect("read chunk").is_none());
+
+    // The rest of the message is never sent, the next message starts from scratch.
+    unpacker.abort_chunk_stream(3);
+
+    #[rustfmt::skip]
+    buf.extend_from_slice(&[
+        (3 << 6) | 3, // chunk type 3, chunk stream id 3
+    ]);
+    buf.extend_from_slice(&[0xBB; 128]);
+    assert!(unpacker.read_chunk(&mut buf).expect("read chunk").is_none());
+
+    buf.extend_from_slice(&[(3 << 6) | 3]);
+    buf.extend_from_slice(&[0xCC; 128]);
+    let chunk = unpacker.read_chunk(&mut buf).expect("read chunk").expect("chunk");
+    assert_eq!(&chunk.payload[..128], &[0xBB; 128]);
+    assert_eq!(&chunk.payload[128..], &[0xCC; 128]);
+}
@@ -1,8 +1,8 @@
-use std::io;
+use std::io::{self, IoSlice};
 
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 
-use crate::chunk::{Chunk, ChunkEncodeError, ChunkEncoder};
+use crate::chunk::{Chunk, ChunkEncodeError, ChunkEncoder, ChunkWriteBuffer};
 use crate::messages::MessageTypeID;
 
 #[test]
@@ -203,3 +203,50 @@ fn test_encoder_extended_csid_ext() {
         ]
     );
 }
+
+#[test]
+fn test_encoder_write_chunk_to_buffer() {
+    let mut encoder = ChunkEncoder::default();
+    encoder.set_chunk_size(1024);
+
+    let payload = Bytes::from((0..2500).map(|i| i as u8).collect::<Vec<_>>());
+    let chunks = [
+        Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, Bytes::from_static(b"small")),
+        Chunk::new(6, 0xFFFFFF, MessageTypeID::Video, 1, payload.clone()),
+    ];
+
+    let mut expected = Vec::new();
+    let mut buffer = ChunkWriteBuffer::new();
+    for chunk in chunks {
+        encoder.write_chunk(&mut expected, chunk.clone()).unwrap();
+        encoder.write_chunk_to_buffer(&mut buffer, chunk).unwrap();
+    }
+
+    assert_eq!(buffer.len(), expected.len());
+    assert_eq!(buffer.to_vec(), expected);
+
+    // The small command with the first header, then the payload slices separated by the headers
+    // of the following chunks. The last slice is too small to be shared and copied after its header.
+    let mut slices = [IoSlice::new(&[]); 8];
+    assert_eq!(buffer.chunks_vectored(&mut slices), 5);
+    assert_eq!(*slices[1], payload[..1024]);
+    assert_eq!(*slices[3], payload[1024..2048]);
+    assert!(slices[4].ends_with(&payload[2048..]));
+    // The payload is shared, not copied.
+    assert_eq!(slices[1].as_ptr(), payload.as_ptr());
+
+    // Advancing across segments.
+    let mut written = Vec::new();
+    while buffer.has_remaining() {
+        let len = buffer.chunk().len().min(700);
+        written.extend_from_slice(&buffer.chunk()[..len]);
+        buffer.advance(len);
+    }
+    assert_eq!(written, expected);
+    assert!(buffer.is_empty());
+
+    io::Write::write_all(&mut buffer, b"data").unwrap();
+    assert_eq!(buffer.to_vec(), b"data");
+    buffer.clear();
+    assert!(buffer.is_empty());
+}
@@ -1,2 +1,3 @@
+mod allocator;
 mod decoder;
 mod encoder;
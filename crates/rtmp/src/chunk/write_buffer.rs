@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::io::{self, IoSlice};
+
+use bytes::{Buf, Bytes, BytesMut};
+
+/// Payloads smaller than this are copied, as they are cheaper to copy than to
+/// write as a separate slice.
+const MIN_SHARED_PAYLOAD: usize = 512;
+
+/// A write buffer of encoded chunks, filled by [`ChunkEncoder::write_chunk_to_buffer`](super::ChunkEncoder::write_chunk_to_buffer).
+///
+/// Chunk headers are copied into the buffer, payloads are kept as slices of the
+/// [`Bytes`] of the message, so large messages, such as video frames, are not copied.
+/// The buffer is a [`Buf`] of all the segments, so it can be written to a socket with
+/// a single vectored write, for example with [`AsyncWriteExt::write_all_buf`](tokio::io::AsyncWriteExt::write_all_buf).
+///
+/// It implements [`io::Write`] as well, for everything written with [`ChunkEncoder::write_chunk`](super::ChunkEncoder::write_chunk).
+#[derive(Debug, Default)]
+pub struct ChunkWriteBuffer {
+    /// The segments ready to be written.
+    segments: VecDeque<Bytes>,
+    /// Copied data, written after `segments`.
+    pending: BytesMut,
+}
+
+impl ChunkWriteBuffer {
+    /// Create a new, empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bytes in the buffer.
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(Bytes::len).sum::<usize>() + self.pending.len()
+    }
+
+    /// Returns true if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty() && self.pending.is_empty()
+    }
+
+    /// Removes all data from the buffer.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.pending.clear();
+    }
+
+    /// Copies `data` to the end of the buffer.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+    }
+
+    /// Adds `data` to the end of the buffer, without copying it unless it is small.
+    pub fn push_bytes(&mut self, data: Bytes) {
+        if data.len() < MIN_SHARED_PAYLOAD {
+            self.extend_from_slice(&data);
+            return;
+        }
+
+        if !self.pending.is_empty() {
+            self.segments.push_back(self.pending.split().freeze());
+        }
+
+        self.segments.push_back(data);
+    }
+
+    /// Returns a copy of the buffered data.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len());
+        for segment in &self.segments {
+            data.extend_from_slice(segment);
+        }
+        data.extend_from_slice(&self.pending);
+        data
+    }
+}
+
+impl io::Write for ChunkWriteBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buf for ChunkWriteBuffer {
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        match self.segments.front() {
+            Some(segment) => segment,
+            None => &self.pending,
+        }
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        while let Some(segment) = self.segments.front_mut() {
+            if cnt < segment.len() {
+                segment.advance(cnt);
+                return;
+            }
+
+            cnt -= segment.len();
+            self.segments.pop_front();
+        }
+
+        self.pending.advance(cnt);
+    }
+
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        let segments = self.segments.iter().map(|segment| segment.as_ref());
+        let pending = Some(self.pending.as_ref()).filter(|pending| !pending.is_empty());
+
+        let mut count = 0;
+        for (dst, segment) in dst.iter_mut().zip(segments.chain(pending)) {
+            *dst = IoSlice::new(segment);
+            count += 1;
+        }
+
+        count
+    }
+}
@@ -0,0 +1,145 @@
+use std::io;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::channels::{ChannelData, DataConsumer};
+
+/// The size of the FLV header, without the first previous tag size.
+const FLV_HEADER_SIZE: u32 = 9;
+/// The size of the header of an FLV tag.
+const FLV_TAG_HEADER_SIZE: u32 = 11;
+/// The largest payload an FLV tag can hold, its size is stored in 24 bits.
+const FLV_MAX_DATA_SIZE: usize = 0xFF_FFFF;
+
+/// The FLV tag type of audio tags.
+const TAG_TYPE_AUDIO: u8 = 8;
+/// The FLV tag type of video tags.
+const TAG_TYPE_VIDEO: u8 = 9;
+/// The FLV tag type of script data tags, such as `onMetaData`.
+const TAG_TYPE_SCRIPT_DATA: u8 = 18;
+
+/// Frames the messages of a published stream as an FLV file.
+///
+/// The payloads of RTMP audio, video and data messages are the same as the
+/// payloads of FLV tags, so they are copied as they are. `onMetaData` is
+/// written without the `@setDataFrame` prefix publishers send it with.
+///
+/// Each tag is followed by its previous tag size, so the FLV header and the
+/// written tags can be concatenated into a file or fed to an FLV demuxer as
+/// they are.
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use scuffle_rtmp::{ChannelData, FlvTagWriter, MediaTimestamp};
+///
+/// let mut writer = FlvTagWriter::new(true, true);
+/// let mut file = Vec::new();
+/// writer.write_header(&mut file).unwrap();
+///
+/// let data = ChannelData::Audio {
+///     timestamp: MediaTimestamp::from_millis(0),
+///     data: Bytes::from_static(&[0xaf, 0x00, 0x11, 0x90]),
+/// };
+/// assert!(writer.write_tag(&data, &mut file).unwrap());
+/// assert_eq!(file.len(), 13 + 11 + 4 + 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlvTagWriter {
+    has_audio: bool,
+    has_video: bool,
+    /// Added to the timestamps of the messages, which restart after a [`ChannelData::Resume`].
+    timestamp_offset: i64,
+    /// The timestamp of the last written tag.
+    last_timestamp: i64,
+}
+
+impl FlvTagWriter {
+    /// Creates a writer, the flags are only written to the FLV header.
+    pub fn new(has_audio: bool, has_video: bool) -> Self {
+        Self {
+            has_audio,
+            has_video,
+            timestamp_offset: 0,
+            last_timestamp: 0,
+        }
+    }
+
+    /// Writes the FLV header, followed by the previous tag size of the non-existent tag before the first.
+    pub fn write_header(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(b"FLV")?;
+        writer.write_u8(1)?; // version
+        writer.write_u8((self.has_audio as u8) << 2 | self.has_video as u8)?;
+        writer.write_u32::<BigEndian>(FLV_HEADER_SIZE)?;
+        writer.write_u32::<BigEndian>(0)?; // previous tag size
+        Ok(())
+    }
+
+    /// Writes `data` as an FLV tag, followed by its previous tag size.
+    ///
+    /// Returns false if `data` has no FLV tag, such as [`ChannelData::Resume`].
+    /// After a resume the timestamps continue after the last written tag, as
+    /// publishers restart at zero when they reconnect.
+    pub fn write_tag(&mut self, data: &ChannelData, writer: &mut impl io::Write) -> io::Result<bool> {
+        let (tag_type, payload) = match data {
+            ChannelData::Audio { data, .. } => (TAG_TYPE_AUDIO, data.clone()),
+            ChannelData::Video { data, .. } => (TAG_TYPE_VIDEO, data.clone()),
+            ChannelData::Metadata { .. } | ChannelData::DataFrame { .. } => match data.script_data() {
+                Some(payload) => (TAG_TYPE_SCRIPT_DATA, payload),
+                None => return Ok(false),
+            },
+            ChannelData::Resume { .. } => {
+                self.timestamp_offset = self.last_timestamp;
+                return Ok(false);
+            }
+        };
+
+        if payload.len() > FLV_MAX_DATA_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "payload too large for an FLV tag"));
+        }
+
+        let timestamp = data.timestamp().as_millis() + self.timestamp_offset;
+        self.last_timestamp = timestamp;
+        // FLV timestamps are 32 bits, with the upper 8 bits stored after the lower 24 bits.
+        let timestamp = timestamp as u32;
+
+        writer.write_u8(tag_type)?;
+        writer.write_u24::<BigEndian>(payload.len() as u32)?;
+        writer.write_u24::<BigEndian>(timestamp & 0xFF_FFFF)?;
+        writer.write_u8((timestamp >> 24) as u8)?;
+        writer.write_u24::<BigEndian>(0)?; // stream id
+        writer.write_all(&payload)?;
+        writer.write_u32::<BigEndian>(FLV_TAG_HEADER_SIZE + payload.len() as u32)?;
+
+        Ok(true)
+    }
+}
+
+/// Writes the messages of `consumer` to `writer` as an FLV file, until the producer is dropped.
+///
+/// The writer is flushed after every tag, so the file can be read while it is written.
+pub async fn write_flv(
+    consumer: &mut DataConsumer,
+    writer: &mut (impl AsyncWrite + Unpin),
+    has_audio: bool,
+    has_video: bool,
+) -> io::Result<()> {
+    let mut tags = FlvTagWriter::new(has_audio, has_video);
+    let mut buf = Vec::new();
+
+    tags.write_header(&mut buf)?;
+    writer.write_all(&buf).await?;
+
+    while let Some(data) = consumer.recv().await {
+        buf.clear();
+        if tags.write_tag(&data, &mut buf)? {
+            writer.write_all(&buf).await?;
+            writer.flush().await?;
+        }
+    }
+
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,117 @@
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+use crate::channels::{ChannelData, MediaTimestamp};
+use crate::flv::{FlvTagWriter, write_flv};
+
+#[test]
+fn test_flv_header() {
+    let mut buf = Vec::new();
+    FlvTagWriter::new(true, true).write_header(&mut buf).unwrap();
+    assert_eq!(buf, b"FLV\x01\x05\x00\x00\x00\x09\x00\x00\x00\x00");
+
+    let mut buf = Vec::new();
+    FlvTagWriter::new(false, true).write_header(&mut buf).unwrap();
+    assert_eq!(buf[4], 0x01);
+}
+
+#[test]
+fn test_flv_tags() {
+    let mut writer = FlvTagWriter::new(true, true);
+
+    let mut buf = Vec::new();
+    let data = ChannelData::Video {
+        timestamp: MediaTimestamp::from_millis(0x0123_4567),
+        data: Bytes::from_static(&[0x17, 0x01, 0x00, 0x00, 0x00]),
+    };
+    assert!(writer.write_tag(&data, &mut buf).unwrap());
+    assert_eq!(
+        buf,
+        [
+            9, // tag type
+            0x00, 0x00, 0x05, // data size
+            0x23, 0x45, 0x67, 0x01, // timestamp, upper 8 bits last
+            0x00, 0x00, 0x00, // stream id
+            0x17, 0x01, 0x00, 0x00, 0x00, // data
+            0x00, 0x00, 0x00, 0x10, // previous tag size
+        ]
+    );
+
+    // The @setDataFrame prefix is removed from the metadata.
+    let mut buf = Vec::new();
+    let data = ChannelData::Metadata {
+        timestamp: MediaTimestamp::from_millis(0),
+        data: Bytes::from_static(b"\x02\x00\x0d@setDataFrame\x02\x00\x0aonMetaData\x05"),
+    };
+    assert!(writer.write_tag(&data, &mut buf).unwrap());
+    assert_eq!(buf[0], 18);
+    assert_eq!(&buf[11..buf.len() - 4], b"\x02\x00\x0aonMetaData\x05");
+
+    // Data frames are written with their name.
+    let mut buf = Vec::new();
+    let data = ChannelData::DataFrame {
+        timestamp: MediaTimestamp::from_millis(0),
+        name: "onCuePoint".to_string(),
+        payload: Bytes::from_static(b"\x05"),
+    };
+    assert!(writer.write_tag(&data, &mut buf).unwrap());
+    assert_eq!(&buf[11..buf.len() - 4], b"\x02\x00\x0aonCuePoint\x05");
+}
+
+#[test]
+fn test_flv_tags_resume() {
+    let mut writer = FlvTagWriter::new(true, false);
+    let audio = |millis| ChannelData::Audio {
+        timestamp: MediaTimestamp::from_millis(millis),
+        data: Bytes::from_static(&[0xaf, 0x01]),
+    };
+
+    let mut buf = Vec::new();
+    assert!(writer.write_tag(&audio(1000), &mut buf).unwrap());
+
+    let resume = ChannelData::Resume {
+        timestamp: MediaTimestamp::from_millis(1000),
+    };
+    assert!(!writer.write_tag(&resume, &mut buf).unwrap());
+
+    // The publisher restarts at zero, the tags continue after the last one.
+    let mut buf = Vec::new();
+    assert!(writer.write_tag(&audio(20), &mut buf).unwrap());
+    assert_eq!(&buf[4..8], &[0x00, 0x03, 0xfc, 0x00]);
+}
+
+#[test]
+fn test_flv_tag_too_large() {
+    let mut writer = FlvTagWriter::new(false, true);
+    let data = ChannelData::Video {
+        timestamp: MediaTimestamp::from_millis(0),
+        data: Bytes::from(vec![0; 0x100_0000]),
+    };
+
+    let err = writer.write_tag(&data, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn test_write_flv() {
+    let (producer, mut consumer) = mpsc::channel(8);
+    producer
+        .send(ChannelData::Audio {
+            timestamp: MediaTimestamp::from_millis(0),
+            data: Bytes::from_static(&[0xaf, 0x01]),
+        })
+        .await
+        .unwrap();
+    producer
+        .send(ChannelData::Resume {
+            timestamp: MediaTimestamp::from_millis(0),
+        })
+        .await
+        .unwrap();
+    drop(producer);
+
+    let mut file = Vec::new();
+    write_flv(&mut consumer, &mut file, true, false).await.unwrap();
+    assert_eq!(file.len(), 13 + 11 + 2 + 4);
+    assert_eq!(&file[..3], b"FLV");
+}
@@ -1,21 +1,25 @@
 use std::fmt;
 
+use super::verification::HandshakeVerificationError;
 use crate::macros::from_error;
 
 #[derive(Debug)]
 pub enum HandshakeError {
     Digest(DigestError),
     IO(std::io::Error),
+    Verification(HandshakeVerificationError),
 }
 
 from_error!(HandshakeError, Self::Digest, DigestError);
 from_error!(HandshakeError, Self::IO, std::io::Error);
+from_error!(HandshakeError, Self::Verification, HandshakeVerificationError);
 
 impl fmt::Display for HandshakeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Digest(error) => write!(f, "digest error: {}", error),
             Self::IO(error) => write!(f, "io error: {}", error),
+            Self::Verification(error) => write!(f, "verification error: {}", error),
         }
     }
 }
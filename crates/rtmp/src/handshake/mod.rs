@@ -3,10 +3,12 @@ mod digest;
 mod errors;
 mod server;
 mod utils;
+mod verification;
 
 pub use self::define::{RTMP_HANDSHAKE_SIZE, ServerHandshakeState};
 pub use self::errors::*;
 pub use self::server::HandshakeServer;
+pub use self::verification::{HandshakeMetrics, HandshakeVerification, HandshakeVerificationError};
 
 #[cfg(test)]
 mod tests;
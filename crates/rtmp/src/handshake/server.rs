@@ -8,6 +8,7 @@ use scuffle_bytes_util::BytesCursorExt;
 use super::define::{RtmpVersion, SchemaVersion, ServerHandshakeState};
 use super::digest::DigestProcessor;
 use super::errors::HandshakeError;
+use super::verification::{self, HandshakeMetrics, HandshakeVerification};
 use super::{define, utils};
 
 // Simple Handshake Server
@@ -20,6 +21,9 @@ pub struct SimpleHandshakeServer {
 
     c1_bytes: Bytes,
     c1_timestamp: u32,
+
+    s1_bytes: Bytes,
+    c2_valid: bool,
 }
 
 impl Default for SimpleHandshakeServer {
@@ -28,6 +32,8 @@ impl Default for SimpleHandshakeServer {
             state: ServerHandshakeState::ReadC0C1,
             c1_bytes: Bytes::new(),
             c1_timestamp: 0,
+            s1_bytes: Bytes::new(),
+            c2_valid: false,
             version: RtmpVersion::Unknown,
             requested_version: RtmpVersion::Unknown,
         }
@@ -47,6 +53,10 @@ pub struct ComplexHandshakeServer {
     c1_digest: Bytes,
     c1_timestamp: u32,
     c1_version: u32,
+
+    s1_bytes: Bytes,
+    s1_digest: [u8; 32],
+    c2_valid: bool,
 }
 
 impl Default for ComplexHandshakeServer {
@@ -58,6 +68,9 @@ impl Default for ComplexHandshakeServer {
             version: RtmpVersion::Unknown,
             requested_version: RtmpVersion::Unknown,
             c1_version: 0,
+            s1_bytes: Bytes::new(),
+            s1_digest: [0; 32],
+            c2_valid: false,
             schema_version: SchemaVersion::Schema0,
         }
     }
@@ -138,6 +151,9 @@ impl SimpleHandshakeServer {
         //  data is the same as the one we sent in S2, but we don't care.
         //  Some clients are not strict to spec and send different data.
         // We can just ignore it and not be super strict.
+        // Whether it matches is only recorded, for `HandshakeVerification`.
+        let c2 = utils::peek(input, 0, define::RTMP_HANDSHAKE_SIZE);
+        self.c2_valid = verification::is_echo(&self.s1_bytes, &c2);
         input.seek_relative(define::RTMP_HANDSHAKE_SIZE as i64)?;
 
         Ok(())
@@ -157,6 +173,8 @@ impl SimpleHandshakeServer {
 
     /// Defined in RTMP Specification 1.0 - 5.2.3
     fn write_s1(&mut self, output: &mut Vec<u8>) -> Result<(), HandshakeError> {
+        let start = output.len();
+
         // Time (4 bytes): This field contains a timestamp, which SHOULD be
         //  used as the epoch for all future chunks sent from this endpoint.
         //  This may be 0, or some arbitrary value. To synchronize multiple
@@ -178,6 +196,9 @@ impl SimpleHandshakeServer {
             output.write_u8(rng.random())?;
         }
 
+        // Kept to check the echo in C2.
+        self.s1_bytes = Bytes::copy_from_slice(&output[start..]);
+
         Ok(())
     }
 
@@ -268,7 +289,10 @@ impl ComplexHandshakeServer {
 
     fn read_c2(&mut self, input: &mut io::Cursor<Bytes>) -> Result<(), HandshakeError> {
         // We don't care too much about the data in C2, so we just read it
-        //  and discard it.
+        //  and discard it. Whether it answers S1 is only recorded, for
+        //  `HandshakeVerification`.
+        let c2 = utils::peek(input, 0, define::RTMP_HANDSHAKE_SIZE);
+        self.c2_valid = verification::is_echo(&self.s1_bytes, &c2) || self.is_c2_digest_valid(&c2);
         input.seek_relative(define::RTMP_HANDSHAKE_SIZE as i64)?;
 
         Ok(())
@@ -282,7 +306,27 @@ impl ComplexHandshakeServer {
         Ok(())
     }
 
-    fn write_s1(&self, output: &mut Vec<u8>) -> Result<(), HandshakeError> {
+    /// Returns true if C2 carries a digest of S1, the answer of a client doing the complex handshake.
+    fn is_c2_digest_valid(&self, c2: &Bytes) -> bool {
+        if c2.len() != define::RTMP_HANDSHAKE_SIZE {
+            return false;
+        }
+
+        // Mirrors S2, with the client key and the digest of S1.
+        let key_digest = DigestProcessor::new(Bytes::new(), define::RTMP_CLIENT_KEY);
+        let Ok(key) = key_digest.make_digest(&self.s1_digest, &[]) else {
+            return false;
+        };
+
+        let data_digest = DigestProcessor::new(Bytes::new(), &key);
+        let split = define::RTMP_HANDSHAKE_SIZE - define::RTMP_DIGEST_LENGTH;
+        data_digest
+            .make_digest(&c2[..split], &[])
+            .is_ok_and(|digest| digest[..] == c2[split..])
+    }
+
+    fn write_s1(&mut self, output: &mut Vec<u8>) -> Result<(), HandshakeError> {
+        let start = output.len();
         let mut writer = Vec::new();
         // The first 4 bytes of S1 are the timestamp.
         writer.write_u32::<BigEndian>(utils::current_time())?;
@@ -310,6 +354,10 @@ impl ComplexHandshakeServer {
         output.write_all(&second)?;
         output.write_all(&third)?;
 
+        // Kept to check the answer in C2.
+        self.s1_bytes = Bytes::copy_from_slice(&output[start..]);
+        self.s1_digest = second;
+
         Ok(())
     }
 
@@ -365,28 +413,77 @@ impl ComplexHandshakeServer {
 // Client <- S1 <- Server
 // Client <- S2 <- Server
 // Client -> C2 -> Server
-pub enum HandshakeServer {
+/// The server side of the RTMP handshake.
+///
+/// The digest-based complex handshake used by Flash players and some legacy
+/// encoders is tried first. If neither digest schema validates C1, the simple
+/// handshake of the RTMP specification is used instead, so the kind of
+/// handshake is picked from C1 without configuration.
+pub struct HandshakeServer {
+    handshaker: Handshaker,
+    verification: HandshakeVerification,
+    metrics: Option<HandshakeMetrics>,
+}
+
+enum Handshaker {
     Simple(SimpleHandshakeServer),
     Complex(ComplexHandshakeServer),
 }
 
 impl Default for HandshakeServer {
     fn default() -> Self {
-        Self::Complex(ComplexHandshakeServer::default())
+        Self::new(HandshakeVerification::default(), None)
     }
 }
 
 impl HandshakeServer {
+    pub fn new(verification: HandshakeVerification, metrics: Option<HandshakeMetrics>) -> Self {
+        Self {
+            handshaker: Handshaker::Complex(ComplexHandshakeServer::default()),
+            verification,
+            metrics,
+        }
+    }
+
     pub fn state(&mut self) -> ServerHandshakeState {
-        match self {
-            HandshakeServer::Simple(handshaker) => handshaker.state,
-            HandshakeServer::Complex(handshaker) => handshaker.state,
+        match &self.handshaker {
+            Handshaker::Simple(handshaker) => handshaker.state,
+            Handshaker::Complex(handshaker) => handshaker.state,
+        }
+    }
+
+    fn c2_valid(&self) -> bool {
+        match &self.handshaker {
+            Handshaker::Simple(handshaker) => handshaker.c2_valid,
+            Handshaker::Complex(handshaker) => handshaker.c2_valid,
         }
     }
 
     pub fn handshake(&mut self, input: &mut io::Cursor<Bytes>, writer: &mut Vec<u8>) -> Result<(), HandshakeError> {
-        match self {
-            HandshakeServer::Complex(handshaker) => {
+        let result = self.verified_handshake(input, writer);
+        if let (Err(HandshakeError::Verification(_)), Some(metrics)) = (&result, &self.metrics) {
+            metrics.record_rejected();
+        }
+
+        result
+    }
+
+    fn verified_handshake(&mut self, input: &mut io::Cursor<Bytes>, writer: &mut Vec<u8>) -> Result<(), HandshakeError> {
+        let state = self.state();
+
+        if state == ServerHandshakeState::ReadC0C1 {
+            let c0c1 = utils::peek(input, 0, define::RTMP_HANDSHAKE_SIZE + 1);
+            let unknown_version = c0c1.first().is_some_and(|version| *version != RtmpVersion::Version3 as u8);
+            if let Some(metrics) = self.metrics.as_ref().filter(|_| unknown_version) {
+                metrics.record_unknown_version();
+            }
+
+            self.verification
+                .check_c1(c0c1.get(1..).unwrap_or_default(), self.metrics.as_ref())?;
+        }
+
+        match &mut self.handshaker {
+            Handshaker::Complex(handshaker) => {
                 let position = input.position();
                 let result = handshaker.handshake(input, writer);
                 if result.is_err() {
@@ -397,14 +494,18 @@ impl HandshakeServer {
 
                     // We then perform the handshake.
                     simple.handshake(input, writer)?;
-                    *self = HandshakeServer::Simple(simple);
+                    self.handshaker = Handshaker::Simple(simple);
                 }
             }
-            HandshakeServer::Simple(handshaker) => {
+            Handshaker::Simple(handshaker) => {
                 handshaker.handshake(input, writer)?;
             }
         }
 
+        if state == ServerHandshakeState::ReadC2 && self.state() == ServerHandshakeState::Finish {
+            self.verification.check_c2(self.c2_valid(), self.metrics.as_ref())?;
+        }
+
         Ok(())
     }
 }
@@ -3,7 +3,7 @@ use std::io::{Cursor, Read, Write};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 
-use super::{HandshakeError, HandshakeServer};
+use super::{HandshakeError, HandshakeMetrics, HandshakeServer, HandshakeVerification, HandshakeVerificationError};
 use crate::handshake::ServerHandshakeState;
 use crate::handshake::define::{
     SchemaVersion, {self},
@@ -136,6 +136,148 @@ fn test_complex_handshake() {
     assert_eq!(handshake_server.state(), ServerHandshakeState::Finish)
 }
 
+/// Builds C0 and C1 of a simple handshake, with `random` repeated as the random data.
+fn simple_c0c1(version: u8, random: impl Fn(usize) -> u8) -> Bytes {
+    let mut c0c1 = Vec::with_capacity(1528 + 9);
+    c0c1.write_u8(version).unwrap();
+    c0c1.write_u32::<BigEndian>(123).unwrap(); // timestamp
+    c0c1.write_u32::<BigEndian>(0).unwrap(); // zero
+
+    for i in 0..1528 {
+        c0c1.write_u8(random(i)).unwrap();
+    }
+
+    Bytes::from(c0c1)
+}
+
+/// Runs the first half of a simple handshake and returns S1.
+fn simple_s1(handshake_server: &mut HandshakeServer) -> Vec<u8> {
+    let mut writer = Vec::new();
+    handshake_server
+        .handshake(&mut Cursor::new(simple_c0c1(3, |i| (i % 256) as u8)), &mut writer)
+        .unwrap();
+
+    writer[1..1537].to_vec()
+}
+
+#[test]
+fn test_simple_handshake_c2_echo() {
+    let metrics = HandshakeMetrics::new();
+    let verification = HandshakeVerification::default().with_require_c2_echo(true);
+
+    let mut handshake_server = HandshakeServer::new(verification, Some(metrics.clone()));
+    let s1 = simple_s1(&mut handshake_server);
+    handshake_server
+        .handshake(&mut Cursor::new(Bytes::from(s1)), &mut Vec::new())
+        .unwrap();
+    assert_eq!(handshake_server.state(), ServerHandshakeState::Finish);
+
+    let mut handshake_server = HandshakeServer::new(verification, Some(metrics.clone()));
+    let mut c2 = simple_s1(&mut handshake_server);
+    c2[100] ^= 0xff;
+    let err = handshake_server
+        .handshake(&mut Cursor::new(Bytes::from(c2)), &mut Vec::new())
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        HandshakeError::Verification(HandshakeVerificationError::C2EchoMismatch)
+    ));
+
+    assert_eq!(metrics.c2_echo_mismatch(), 1);
+    assert_eq!(metrics.rejected(), 1);
+}
+
+#[test]
+fn test_simple_handshake_c2_mismatch_tolerated() {
+    let metrics = HandshakeMetrics::new();
+    let mut handshake_server = HandshakeServer::new(HandshakeVerification::default(), Some(metrics.clone()));
+    simple_s1(&mut handshake_server);
+
+    handshake_server
+        .handshake(&mut Cursor::new(Bytes::from(vec![0; 1536])), &mut Vec::new())
+        .unwrap();
+    assert_eq!(handshake_server.state(), ServerHandshakeState::Finish);
+
+    // Still counted, so the check can be observed before it is enabled.
+    assert_eq!(metrics.c2_echo_mismatch(), 1);
+    assert_eq!(metrics.rejected(), 0);
+}
+
+#[test]
+fn test_handshake_low_entropy_c1() {
+    let metrics = HandshakeMetrics::new();
+    let verification = HandshakeVerification::default().with_min_c1_distinct_bytes(64);
+
+    // Looks like the start of an HTTP request.
+    let mut handshake_server = HandshakeServer::new(verification, Some(metrics.clone()));
+    let err = handshake_server
+        .handshake(&mut Cursor::new(simple_c0c1(b'G', |_| 0)), &mut Vec::new())
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        HandshakeError::Verification(HandshakeVerificationError::LowEntropyC1 { distinct_bytes: 1 })
+    ));
+    assert_eq!(
+        err.to_string(),
+        "verification error: c1 random data has only 1 distinct bytes"
+    );
+    assert_eq!(handshake_server.state(), ServerHandshakeState::ReadC0C1);
+
+    let mut handshake_server = HandshakeServer::new(verification, Some(metrics.clone()));
+    simple_s1(&mut handshake_server);
+
+    assert_eq!(metrics.unknown_version(), 1);
+    assert_eq!(metrics.low_entropy_c1(), 1);
+    assert_eq!(metrics.rejected(), 1);
+}
+
+#[test]
+fn test_complex_handshake_c2_digest() {
+    let metrics = HandshakeMetrics::new();
+    let verification = HandshakeVerification::default().with_require_c2_echo(true);
+    let mut handshake_server = HandshakeServer::new(verification, Some(metrics.clone()));
+
+    let mut c1 = Vec::with_capacity(1536);
+    c1.write_u32::<BigEndian>(123).unwrap(); // timestamp
+    c1.write_u32::<BigEndian>(100).unwrap(); // client version
+    for i in 0..1528 {
+        c1.write_u8((i % 256) as u8).unwrap();
+    }
+
+    let data_digest = DigestProcessor::new(Bytes::from(c1), define::RTMP_CLIENT_KEY_FIRST_HALF);
+    let (first, second, third) = data_digest.generate_and_fill_digest(SchemaVersion::Schema0).unwrap();
+    let mut c0c1 = vec![3];
+    c0c1.extend_from_slice(&first);
+    c0c1.extend_from_slice(&second);
+    c0c1.extend_from_slice(&third);
+
+    let mut bytes = Vec::new();
+    handshake_server
+        .handshake(&mut Cursor::new(Bytes::from(c0c1)), &mut bytes)
+        .unwrap();
+
+    let s1 = Bytes::copy_from_slice(&bytes[1..1537]);
+    let (s1_digest, schema) = DigestProcessor::new(s1, define::RTMP_SERVER_KEY_FIRST_HALF)
+        .read_digest()
+        .unwrap();
+    // S1 uses the schema of C1.
+    assert_eq!(schema, SchemaVersion::Schema0);
+
+    // The answer of a client doing the complex handshake, random data signed with a key derived from S1.
+    let key = DigestProcessor::new(Bytes::new(), define::RTMP_CLIENT_KEY)
+        .make_digest(&s1_digest, &[])
+        .unwrap();
+    let mut c2 = vec![7; 1536 - 32];
+    let digest = DigestProcessor::new(Bytes::new(), &key).make_digest(&c2, &[]).unwrap();
+    c2.extend_from_slice(&digest);
+
+    handshake_server
+        .handshake(&mut Cursor::new(Bytes::from(c2)), &mut Vec::new())
+        .unwrap();
+    assert_eq!(handshake_server.state(), ServerHandshakeState::Finish);
+    assert_eq!(metrics.c2_echo_mismatch(), 0);
+}
+
 #[test]
 fn test_error_display() {
     let err = HandshakeError::Digest(DigestError::CannotGenerate);
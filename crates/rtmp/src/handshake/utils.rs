@@ -1,5 +1,8 @@
+use std::io;
 use std::time::SystemTime;
 
+use bytes::Bytes;
+
 pub fn current_time() -> u32 {
     let duration = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
     match duration {
@@ -7,3 +10,11 @@ pub fn current_time() -> u32 {
         _ => 0,
     }
 }
+
+/// Returns up to `len` bytes starting at `offset` past the position of `input`, without consuming them.
+pub fn peek(input: &io::Cursor<Bytes>, offset: usize, len: usize) -> Bytes {
+    let data = input.get_ref();
+    let start = (input.position() as usize).saturating_add(offset).min(data.len());
+    let end = start.saturating_add(len).min(data.len());
+    data.slice(start..end)
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytes::Bytes;
+
+use super::define;
+
+/// How strictly the handshake of a client is checked.
+///
+/// Many clients do not follow the spec closely, so by default nothing is
+/// rejected. Stricter checks reject scanners and garbage connections before
+/// they get a session, at the cost of rejecting some sloppy clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HandshakeVerification {
+    /// Rejects clients whose C2 does not answer our S1.
+    ///
+    /// In a simple handshake the random data of C2 must echo S1, in a complex
+    /// handshake C2 must either echo S1 or carry a valid digest of it.
+    pub require_c2_echo: bool,
+    /// Rejects clients whose C1 random data has fewer distinct byte values than this.
+    ///
+    /// Random data has close to 256 distinct values, while scanners tend to
+    /// send text or zeros. The spec does not require the data to be random,
+    /// so `None` disables the check.
+    pub min_c1_distinct_bytes: Option<usize>,
+}
+
+impl HandshakeVerification {
+    /// Sets whether C2 must answer S1.
+    pub fn with_require_c2_echo(mut self, require_c2_echo: bool) -> Self {
+        self.require_c2_echo = require_c2_echo;
+        self
+    }
+
+    /// Sets the minimum number of distinct byte values in the C1 random data.
+    pub fn with_min_c1_distinct_bytes(mut self, min_c1_distinct_bytes: usize) -> Self {
+        self.min_c1_distinct_bytes = Some(min_c1_distinct_bytes);
+        self
+    }
+
+    /// Checks the random data of C1, the bytes after the time and version fields.
+    pub(super) fn check_c1(&self, c1: &[u8], metrics: Option<&HandshakeMetrics>) -> Result<(), HandshakeVerificationError> {
+        let Some(min_distinct_bytes) = self.min_c1_distinct_bytes else {
+            return Ok(());
+        };
+
+        let random = c1.get(define::TIME_VERSION_LENGTH..).unwrap_or_default();
+        let mut seen = [false; 256];
+        for &byte in random {
+            seen[byte as usize] = true;
+        }
+
+        let distinct_bytes = seen.iter().filter(|seen| **seen).count();
+        if distinct_bytes < min_distinct_bytes {
+            if let Some(metrics) = metrics {
+                metrics.0.low_entropy_c1.fetch_add(1, Ordering::Relaxed);
+            }
+
+            return Err(HandshakeVerificationError::LowEntropyC1 { distinct_bytes });
+        }
+
+        Ok(())
+    }
+
+    /// Records whether C2 answered S1 and rejects it if required.
+    pub(super) fn check_c2(
+        &self,
+        c2_valid: bool,
+        metrics: Option<&HandshakeMetrics>,
+    ) -> Result<(), HandshakeVerificationError> {
+        if c2_valid {
+            return Ok(());
+        }
+
+        if let Some(metrics) = metrics {
+            metrics.0.c2_echo_mismatch.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if self.require_c2_echo {
+            Err(HandshakeVerificationError::C2EchoMismatch)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returns true if `c2` echoes the random data of `s1`.
+pub(super) fn is_echo(s1: &Bytes, c2: &Bytes) -> bool {
+    s1.len() == define::RTMP_HANDSHAKE_SIZE
+        && c2.len() == define::RTMP_HANDSHAKE_SIZE
+        && s1[define::TIME_VERSION_LENGTH..] == c2[define::TIME_VERSION_LENGTH..]
+}
+
+/// A check of [`HandshakeVerification`] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeVerificationError {
+    /// C2 did not answer S1, see [`HandshakeVerification::require_c2_echo`].
+    C2EchoMismatch,
+    /// The C1 random data had too few distinct byte values, see
+    /// [`HandshakeVerification::min_c1_distinct_bytes`].
+    LowEntropyC1 {
+        /// The number of distinct byte values.
+        distinct_bytes: usize,
+    },
+}
+
+impl std::fmt::Display for HandshakeVerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::C2EchoMismatch => write!(f, "c2 does not echo s1"),
+            Self::LowEntropyC1 { distinct_bytes } => {
+                write!(f, "c1 random data has only {} distinct bytes", distinct_bytes)
+            }
+        }
+    }
+}
+
+/// Counters of handshake anomalies, shared by all sessions they are given to.
+///
+/// Anomalies are counted even when [`HandshakeVerification`] does not reject
+/// them, so the checks can be observed before they are enabled. Cloning
+/// returns a handle to the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct HandshakeMetrics(Arc<HandshakeMetricsInner>);
+
+#[derive(Debug, Default)]
+struct HandshakeMetricsInner {
+    unknown_version: AtomicU64,
+    low_entropy_c1: AtomicU64,
+    c2_echo_mismatch: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl HandshakeMetrics {
+    /// Creates new counters, all zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of clients that requested an RTMP version other than 3 in C0.
+    pub fn unknown_version(&self) -> u64 {
+        self.0.unknown_version.load(Ordering::Relaxed)
+    }
+
+    /// The number of clients whose C1 random data had too few distinct byte values.
+    ///
+    /// Only counted when [`HandshakeVerification::min_c1_distinct_bytes`] is set.
+    pub fn low_entropy_c1(&self) -> u64 {
+        self.0.low_entropy_c1.load(Ordering::Relaxed)
+    }
+
+    /// The number of clients whose C2 did not answer S1.
+    pub fn c2_echo_mismatch(&self) -> u64 {
+        self.0.c2_echo_mismatch.load(Ordering::Relaxed)
+    }
+
+    /// The number of handshakes rejected by [`HandshakeVerification`].
+    pub fn rejected(&self) -> u64 {
+        self.0.rejected.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn record_unknown_version(&self) {
+        self.0.unknown_version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_rejected(&self) {
+        self.0.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+}
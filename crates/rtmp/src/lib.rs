@@ -1,16 +1,47 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
 mod channels;
 mod chunk;
+mod flv;
 mod handshake;
+mod listener;
 mod macros;
 mod messages;
 mod netconnection;
 mod netstream;
 mod protocol_control_messages;
 mod session;
+mod transport;
 mod user_control_messages;
 
-pub use channels::{ChannelData, DataConsumer, DataProducer, PublishConsumer, PublishProducer, PublishRequest, UniqueID};
-pub use session::{Session, SessionError};
+pub use channels::{
+    BackpressurePolicy, ChannelData, ConnectConsumer, ConnectDecision, ConnectProducer, ConnectRequest, DataBufferMetrics,
+    DataConsumer, DataProducer, DataWatermarks, MediaTimestamp, MessageFilter, MetadataConsumer, MetadataProducer,
+    PlayConsumer, PlayProducer, PlayRequest, PublishConsumer, PublishProducer, PublishRequest, PublishResponse,
+    RTMP_TIMESCALE, ReconnectGrace, SequenceHeaderCache, SequenceHeaders, StreamMetadata, UniqueID, UserControlConsumer,
+    UserControlProducer, WatermarkEvent,
+};
+pub use chunk::{
+    CHUNK_SIZE, Chunk, ChunkDecodeError, ChunkDecoder, ChunkEncodeError, ChunkEncoder, ChunkStreamAllocator,
+    ChunkWriteBuffer, DefinedChunkStreamID, PROTOCOL_CONTROL_CHUNK_STREAM_ID, ProtocolViolation,
+};
+pub use flv::{FlvTagWriter, write_flv};
+pub use handshake::{HandshakeError, HandshakeMetrics, HandshakeVerification, HandshakeVerificationError};
+pub use listener::{Keepalive, Listener, SocketOptions};
+pub use messages::{
+    AggregateMessage, Amf0Properties, CAPS_EX_MOD_EX, CAPS_EX_MULTITRACK, CAPS_EX_RECONNECT, CAPS_EX_TIMESTAMP_NANO_OFFSET,
+    CommandObject, ConnectCommandObject, FOURCC_INFO_CAN_DECODE, FOURCC_INFO_CAN_ENCODE, FOURCC_INFO_CAN_FORWARD,
+    MessageError, MessageParser, MessageTypeID, RTMP_STRINGS, RtmpMessageData, rtmp_interner,
+};
+pub use session::{
+    AuthDecision, ConnectAuth, PeerBandwidthLimitType, ProtocolConfig, PublishAuth, Session, SessionAuthHandler,
+    SessionError, SessionStats,
+};
+#[cfg(feature = "tls-rustls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls-rustls")))]
+pub use transport::TlsAcceptor;
+pub use transport::{FramedIo, PeerInfo, SplitIo, TransportKind};
+pub use user_control_messages::UserControlEvent;
 
 #[cfg(test)]
 mod tests;
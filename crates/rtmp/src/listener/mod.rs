@@ -0,0 +1,8 @@
+mod options;
+mod tcp_listener;
+
+pub use self::options::{Keepalive, SocketOptions};
+pub use self::tcp_listener::Listener;
+
+#[cfg(test)]
+mod tests;
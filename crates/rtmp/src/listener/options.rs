@@ -0,0 +1,140 @@
+use std::io;
+use std::time::Duration;
+
+use socket2::{SockRef, TcpKeepalive};
+
+/// TCP keepalive parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// How long the connection has to be idle before the first probe is sent.
+    pub time: Duration,
+    /// The time between probes. Uses the OS default if not set.
+    /// Only applied on platforms that support it.
+    pub interval: Option<Duration>,
+    /// The number of unanswered probes before the connection is dropped. Uses the OS default if not set.
+    /// Only applied on platforms that support it.
+    pub retries: Option<u32>,
+}
+
+impl Keepalive {
+    fn to_socket2(self) -> TcpKeepalive {
+        let keepalive = TcpKeepalive::new().with_time(self.time);
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            windows
+        ))]
+        let keepalive = match self.interval {
+            Some(interval) => keepalive.with_interval(interval),
+            None => keepalive,
+        };
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos"
+        ))]
+        let keepalive = match self.retries {
+            Some(retries) => keepalive.with_retries(retries),
+            None => keepalive,
+        };
+
+        keepalive
+    }
+}
+
+/// TCP options for the sockets of a [`Listener`](crate::Listener) and the connections it accepts.
+///
+/// Options that are not set are left at the OS default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Sets `TCP_NODELAY`, disabling Nagle's algorithm.
+    /// Usually wanted for ingest, as RTMP writes small control messages that should not be delayed.
+    pub nodelay: Option<bool>,
+    /// Sets `SO_RCVBUF`, the size of the receive buffer in bytes.
+    pub recv_buffer_size: Option<usize>,
+    /// Sets `SO_SNDBUF`, the size of the send buffer in bytes.
+    pub send_buffer_size: Option<usize>,
+    /// Enables `SO_KEEPALIVE` with the given parameters.
+    pub keepalive: Option<Keepalive>,
+    /// Sets `SO_LINGER`, how long closing the socket blocks to send remaining data.
+    pub linger: Option<Duration>,
+}
+
+impl SocketOptions {
+    /// Sets `TCP_NODELAY`.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Sets `SO_RCVBUF`.
+    pub fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets `SO_SNDBUF`.
+    pub fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Enables `SO_KEEPALIVE`.
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Sets `SO_LINGER`.
+    pub fn with_linger(mut self, linger: Duration) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    /// Applies the options to a connected socket, such as a [`tokio::net::TcpStream`].
+    ///
+    /// Use this to configure a single session differently from the defaults of its listener.
+    pub fn apply<'s, S>(&self, socket: &'s S) -> io::Result<()>
+    where
+        SockRef<'s>: From<&'s S>,
+    {
+        let socket = SockRef::from(socket);
+
+        self.apply_buffer_sizes(&socket)?;
+
+        if let Some(nodelay) = self.nodelay {
+            socket.set_nodelay(nodelay)?;
+        }
+
+        if let Some(keepalive) = self.keepalive {
+            socket.set_tcp_keepalive(&keepalive.to_socket2())?;
+        }
+
+        if let Some(linger) = self.linger {
+            socket.set_linger(Some(linger))?;
+        }
+
+        Ok(())
+    }
+
+    /// Buffer sizes are also set on the listening socket, so that they are
+    /// already in effect during the TCP handshake, when the window scale is negotiated.
+    pub(super) fn apply_buffer_sizes(&self, socket: &socket2::Socket) -> io::Result<()> {
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        Ok(())
+    }
+}
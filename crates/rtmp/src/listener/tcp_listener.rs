@@ -0,0 +1,84 @@
+use std::io;
+use std::net::SocketAddr;
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::SocketOptions;
+
+/// The maximum number of pending connections, the same as the default of [`tokio::net::TcpListener::bind`].
+const BACKLOG: i32 = 1024;
+
+/// A TCP listener for RTMP sessions, which applies [`SocketOptions`] to every accepted connection.
+///
+/// The accepted streams can be passed to [`Session::new`](crate::Session::new),
+/// and their addresses to [`Session::with_peer_info`](crate::Session::with_peer_info) with [`PeerInfo::tcp`](crate::PeerInfo::tcp).
+#[derive(Debug)]
+pub struct Listener {
+    inner: TcpListener,
+    options: SocketOptions,
+}
+
+impl Listener {
+    /// Binds a new listener to `addr`.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn bind(addr: SocketAddr, options: SocketOptions) -> io::Result<Self> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+
+        options.apply_buffer_sizes(&socket)?;
+        socket.bind(&addr.into())?;
+        socket.listen(BACKLOG)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            inner: TcpListener::from_std(socket.into())?,
+            options,
+        })
+    }
+
+    /// Wraps an already bound listener.
+    ///
+    /// The options are only applied to the connections it accepts, not to the listener itself.
+    pub fn from_tokio(listener: TcpListener, options: SocketOptions) -> Self {
+        Self {
+            inner: listener,
+            options,
+        }
+    }
+
+    /// Returns the local address the listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the options applied to accepted connections.
+    pub fn options(&self) -> &SocketOptions {
+        &self.options
+    }
+
+    /// Sets the options applied to connections accepted from now on.
+    pub fn set_options(&mut self, options: SocketOptions) {
+        self.options = options;
+    }
+
+    /// Accepts a new connection and applies the listener's options to it.
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        self.accept_with(&self.options).await
+    }
+
+    /// Accepts a new connection and applies `options` to it instead of the listener's options.
+    pub async fn accept_with(&self, options: &SocketOptions) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = self.inner.accept().await?;
+        options.apply(&stream)?;
+        Ok((stream, addr))
+    }
+
+    /// Returns the underlying tokio listener.
+    pub fn into_inner(self) -> TcpListener {
+        self.inner
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use socket2::SockRef;
+
+use crate::listener::{Keepalive, Listener, SocketOptions};
+
+#[tokio::test]
+async fn test_listener_socket_options() {
+    let options = SocketOptions::default()
+        .with_nodelay(true)
+        .with_recv_buffer_size(256 * 1024)
+        .with_send_buffer_size(256 * 1024)
+        .with_keepalive(Keepalive {
+            time: Duration::from_secs(30),
+            interval: Some(Duration::from_secs(5)),
+            retries: Some(3),
+        })
+        .with_linger(Duration::from_secs(1));
+
+    let listener = Listener::bind("127.0.0.1:0".parse().unwrap(), options.clone()).expect("failed to bind");
+    assert_eq!(listener.options(), &options);
+
+    let addr = listener.local_addr().unwrap();
+    let client = tokio::spawn(tokio::net::TcpStream::connect(addr));
+    let (stream, _) = listener.accept().await.expect("failed to accept");
+    let _client = client.await.unwrap().expect("failed to connect");
+
+    let socket = SockRef::from(&stream);
+    assert!(socket.nodelay().unwrap());
+    assert!(socket.keepalive().unwrap());
+    assert_eq!(socket.linger().unwrap(), Some(Duration::from_secs(1)));
+    // The OS may round the buffer sizes, linux doubles them for bookkeeping.
+    assert!(socket.recv_buffer_size().unwrap() >= 128 * 1024);
+    assert!(socket.send_buffer_size().unwrap() >= 128 * 1024);
+}
+
+#[tokio::test]
+async fn test_listener_accept_with() {
+    let listener =
+        Listener::bind("127.0.0.1:0".parse().unwrap(), SocketOptions::default().with_nodelay(true)).expect("failed to bind");
+
+    let addr = listener.local_addr().unwrap();
+    let client = tokio::spawn(tokio::net::TcpStream::connect(addr));
+    let (stream, _) = listener
+        .accept_with(&SocketOptions::default().with_nodelay(false))
+        .await
+        .expect("failed to accept");
+    let _client = client.await.unwrap().expect("failed to connect");
+
+    assert!(!SockRef::from(&stream).nodelay().unwrap());
+}
+
+#[tokio::test]
+async fn test_listener_from_tokio() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("failed to bind");
+    let mut listener = Listener::from_tokio(listener, SocketOptions::default());
+    listener.set_options(SocketOptions::default().with_nodelay(true));
+
+    let addr = listener.local_addr().unwrap();
+    let client = tokio::spawn(tokio::net::TcpStream::connect(addr));
+    let (stream, _) = listener.accept().await.expect("failed to accept");
+    let _client = client.await.unwrap().expect("failed to connect");
+
+    assert!(SockRef::from(&stream).nodelay().unwrap());
+    assert_eq!(listener.into_inner().local_addr().unwrap(), addr);
+}
@@ -0,0 +1,122 @@
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{BufMut, Bytes, BytesMut};
+use num_traits::FromPrimitive;
+
+use super::define::MessageTypeID;
+use super::errors::MessageError;
+use crate::chunk::Chunk;
+
+/// type (1 byte) + size (3 bytes) + timestamp (3 bytes) + timestamp extended (1 byte) + stream id (3 bytes)
+const SUB_MESSAGE_HEADER_SIZE: usize = 11;
+/// The size of the back pointer following every sub-message.
+const BACK_POINTER_SIZE: usize = 4;
+/// The largest payload that fits into the 3 byte size field.
+const MAX_PAYLOAD_SIZE: usize = 0xFFFFFF;
+
+/// A message contained in an Aggregate message (type 22).
+///
+/// An aggregate is a sequence of sub-messages, each with an FLV tag like header
+/// and followed by a back pointer, used by some servers and encoders to send
+/// several audio and video messages at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateMessage {
+    /// The type of the message.
+    pub msg_type_id: MessageTypeID,
+    /// The timestamp of the message, in the timeline of the aggregate's chunk stream.
+    pub timestamp: u32,
+    /// The payload of the message.
+    pub payload: Bytes,
+}
+
+impl AggregateMessage {
+    /// Reads the sub-messages of an aggregate payload.
+    ///
+    /// The timestamps of the sub-messages are rebased, so the first one has the
+    /// `timestamp` of the aggregate message and the deltas between them are kept.
+    /// Sub-messages with an unknown type are skipped.
+    pub fn read_all(payload: &Bytes, timestamp: u32) -> Result<Vec<Self>, MessageError> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+        let mut first_timestamp = None;
+
+        while offset < payload.len() {
+            let header = payload
+                .get(offset..offset + SUB_MESSAGE_HEADER_SIZE)
+                .ok_or(MessageError::InvalidAggregate("truncated sub-message header"))?;
+
+            let msg_type_id = header[0];
+            let size = BigEndian::read_u24(&header[1..4]) as usize;
+            let sub_timestamp = BigEndian::read_u24(&header[4..7]) | ((header[7] as u32) << 24);
+
+            let start = offset + SUB_MESSAGE_HEADER_SIZE;
+            let end = start + size;
+            if end + BACK_POINTER_SIZE > payload.len() {
+                return Err(MessageError::InvalidAggregate("truncated sub-message"));
+            }
+
+            // The back pointer is not needed for reading forward, and some
+            // encoders write it wrong, so it is not checked.
+            offset = end + BACK_POINTER_SIZE;
+
+            let first_timestamp = *first_timestamp.get_or_insert(sub_timestamp);
+
+            let Some(msg_type_id) = MessageTypeID::from_u8(msg_type_id) else {
+                continue;
+            };
+
+            if msg_type_id == MessageTypeID::Aggregate {
+                return Err(MessageError::InvalidAggregate("nested aggregate"));
+            }
+
+            messages.push(Self {
+                msg_type_id,
+                timestamp: timestamp.wrapping_add(sub_timestamp.wrapping_sub(first_timestamp)),
+                payload: payload.slice(start..end),
+            });
+        }
+
+        Ok(messages)
+    }
+
+    /// Builds an aggregate message chunk from `messages`, for relaying them as one message.
+    ///
+    /// The aggregate gets the timestamp of the first message.
+    pub fn write_all(chunk_stream_id: u32, msg_stream_id: u32, messages: &[Self]) -> Result<Chunk, MessageError> {
+        let first = messages.first().ok_or(MessageError::InvalidAggregate("no sub-messages"))?;
+
+        let size = messages
+            .iter()
+            .map(|message| SUB_MESSAGE_HEADER_SIZE + message.payload.len() + BACK_POINTER_SIZE)
+            .sum();
+        let mut payload = BytesMut::with_capacity(size);
+
+        for message in messages {
+            if message.msg_type_id == MessageTypeID::Aggregate {
+                return Err(MessageError::InvalidAggregate("nested aggregate"));
+            }
+
+            if message.payload.len() > MAX_PAYLOAD_SIZE {
+                return Err(MessageError::InvalidAggregate("sub-message too large"));
+            }
+
+            let mut header = [0; SUB_MESSAGE_HEADER_SIZE];
+            header[0] = message.msg_type_id as u8;
+            BigEndian::write_u24(&mut header[1..4], message.payload.len() as u32);
+            BigEndian::write_u24(&mut header[4..7], message.timestamp & 0xFFFFFF);
+            header[7] = (message.timestamp >> 24) as u8;
+            // The stream id is always 0, the aggregate's message stream id applies.
+
+            payload.put_slice(&header);
+            payload.put_slice(&message.payload);
+            payload.put_u32((SUB_MESSAGE_HEADER_SIZE + message.payload.len()) as u32);
+        }
+
+        Ok(Chunk::new(
+            chunk_stream_id,
+            first.timestamp,
+            MessageTypeID::Aggregate,
+            msg_stream_id,
+            payload.freeze(),
+        ))
+    }
+}
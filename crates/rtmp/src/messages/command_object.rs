@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+
+use scuffle_amf0::{Amf0Interner, Amf0Value};
+
+use super::errors::MessageError;
+
+/// The properties of an AMF0 object, in the order they are encoded.
+pub type Amf0Properties<'a> = Vec<(Cow<'a, str>, Amf0Value<'a>)>;
+
+/// A typed command object, decoded from and encoded to an AMF0 object.
+///
+/// Implementations should keep the properties they do not know about, so that
+/// objects round trip without losing fields that newer clients send.
+pub trait CommandObject<'a>: Sized {
+    /// Creates the command object from the properties of an AMF0 object.
+    fn from_properties(properties: Amf0Properties<'a>) -> Self;
+
+    /// Returns the properties of the AMF0 object for this command object.
+    fn to_properties(&self) -> Amf0Properties<'a>;
+
+    /// Decodes the command object of a command message.
+    /// A null command object is treated as an empty object.
+    fn decode(value: Amf0Value<'a>) -> Result<Self, MessageError> {
+        match value {
+            Amf0Value::Object(properties) => Ok(Self::from_properties(properties.into_owned())),
+            Amf0Value::Null => Ok(Self::from_properties(Vec::new())),
+            value => Err(MessageError::InvalidCommandObject(value.marker())),
+        }
+    }
+
+    /// Encodes the command object as an AMF0 object.
+    fn encode(&self) -> Amf0Value<'a> {
+        Amf0Value::Object(Cow::Owned(self.to_properties()))
+    }
+}
+
+/// The raw properties, for commands that do not have a typed command object.
+impl<'a> CommandObject<'a> for Amf0Properties<'a> {
+    fn from_properties(properties: Amf0Properties<'a>) -> Self {
+        properties
+    }
+
+    fn to_properties(&self) -> Amf0Properties<'a> {
+        self.clone()
+    }
+}
+
+/// Enhanced RTMP `FourCcInfoMask`: the codec can be decoded.
+pub const FOURCC_INFO_CAN_DECODE: u32 = 0x01;
+/// Enhanced RTMP `FourCcInfoMask`: the codec can be encoded.
+pub const FOURCC_INFO_CAN_ENCODE: u32 = 0x02;
+/// Enhanced RTMP `FourCcInfoMask`: the codec can be forwarded without decoding it.
+pub const FOURCC_INFO_CAN_FORWARD: u32 = 0x04;
+
+/// Enhanced RTMP `CapsExMask`: reconnect requests are supported.
+pub const CAPS_EX_RECONNECT: u32 = 0x01;
+/// Enhanced RTMP `CapsExMask`: multitrack audio and video are supported.
+pub const CAPS_EX_MULTITRACK: u32 = 0x02;
+/// Enhanced RTMP `CapsExMask`: packet modifier extensions (ModEx) are supported.
+pub const CAPS_EX_MOD_EX: u32 = 0x04;
+/// Enhanced RTMP `CapsExMask`: the nanosecond timestamp offset ModEx is supported.
+pub const CAPS_EX_TIMESTAMP_NANO_OFFSET: u32 = 0x08;
+
+/// The command object of a `connect` command.
+///
+/// Includes the enhanced RTMP capability negotiation fields
+/// (<https://github.com/veovera/enhanced-rtmp>). Properties that are not known,
+/// or do not have the expected type, are kept in `others`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectCommandObject<'a> {
+    /// The name of the application the client connects to.
+    pub app: Option<Cow<'a, str>>,
+    /// The url of the server the client connects to.
+    pub tc_url: Option<Cow<'a, str>>,
+    /// The version of the client.
+    pub flash_ver: Option<Cow<'a, str>>,
+    /// The url of the swf file making the connection.
+    pub swf_url: Option<Cow<'a, str>>,
+    /// The url of the web page the swf file was loaded from.
+    pub page_url: Option<Cow<'a, str>>,
+    /// The legacy audio codec support flags.
+    pub audio_codecs: Option<f64>,
+    /// The legacy video codec support flags.
+    pub video_codecs: Option<f64>,
+    /// The AMF encoding the client wants to use.
+    pub object_encoding: Option<f64>,
+    /// Enhanced RTMP: the FourCCs of the codecs the client supports.
+    pub fourcc_list: Option<Vec<Cow<'a, str>>>,
+    /// Enhanced RTMP: the video codec capabilities, FourCC to capability flags.
+    pub video_fourcc_info_map: Option<Vec<(Cow<'a, str>, f64)>>,
+    /// Enhanced RTMP: the audio codec capabilities, FourCC to capability flags.
+    pub audio_fourcc_info_map: Option<Vec<(Cow<'a, str>, f64)>>,
+    /// Enhanced RTMP: the extended capability flags.
+    pub caps_ex: Option<f64>,
+    /// All other properties, in the order they were received.
+    pub others: Amf0Properties<'a>,
+}
+
+impl<'a> ConnectCommandObject<'a> {
+    /// Returns true if the client advertised enhanced RTMP support.
+    pub fn is_enhanced(&self) -> bool {
+        self.fourcc_list.is_some()
+            || self.video_fourcc_info_map.is_some()
+            || self.audio_fourcc_info_map.is_some()
+            || self.caps_ex.is_some()
+    }
+
+    /// Returns the enhanced RTMP capabilities of a server that forwards streams without
+    /// decoding them, in answer to the capabilities the client advertised.
+    ///
+    /// Every codec the client listed is accepted with [`FOURCC_INFO_CAN_FORWARD`], as are
+    /// multitrack streams and packet modifier extensions. `None` if the client does not
+    /// support enhanced RTMP, as legacy clients do not expect the fields.
+    ///
+    /// The properties of the result belong in the command object of the `_result`.
+    pub fn forward_capabilities(&self) -> Option<ConnectCommandObject<'a>> {
+        fn forward<'a>(fourccs: impl Iterator<Item = Cow<'a, str>>) -> Vec<(Cow<'a, str>, f64)> {
+            fourccs.map(|fourcc| (fourcc, FOURCC_INFO_CAN_FORWARD as f64)).collect()
+        }
+
+        if !self.is_enhanced() {
+            return None;
+        }
+
+        // Enhanced RTMP v1 clients only send the list of video codecs.
+        let video_fourccs = match (&self.video_fourcc_info_map, &self.fourcc_list) {
+            (Some(map), _) => Some(forward(map.iter().map(|(fourcc, _)| fourcc.clone()))),
+            (None, Some(list)) => Some(forward(list.iter().cloned())),
+            (None, None) => None,
+        };
+
+        Some(ConnectCommandObject {
+            fourcc_list: self.fourcc_list.clone(),
+            video_fourcc_info_map: video_fourccs,
+            audio_fourcc_info_map: self
+                .audio_fourcc_info_map
+                .as_ref()
+                .map(|map| forward(map.iter().map(|(fourcc, _)| fourcc.clone()))),
+            caps_ex: Some((CAPS_EX_MULTITRACK | CAPS_EX_MOD_EX | CAPS_EX_TIMESTAMP_NANO_OFFSET) as f64),
+            ..Default::default()
+        })
+    }
+
+    /// Converts the command object into one that owns all its data.
+    pub fn into_owned(self) -> ConnectCommandObject<'static> {
+        self.into_interned(&Amf0Interner::new())
+    }
+
+    /// Converts the command object into one that owns all its data, without allocating
+    /// the strings known to `interner`, such as [`RTMP_STRINGS`](crate::RTMP_STRINGS).
+    pub fn into_interned(self, interner: &Amf0Interner) -> ConnectCommandObject<'static> {
+        let owned = |s: Cow<'_, str>| interner.intern(&s);
+        let owned_map = |map: Vec<(Cow<'_, str>, f64)>| -> Vec<(Cow<'static, str>, f64)> {
+            map.into_iter().map(|(key, value)| (owned(key), value)).collect()
+        };
+
+        ConnectCommandObject {
+            app: self.app.map(owned),
+            tc_url: self.tc_url.map(owned),
+            flash_ver: self.flash_ver.map(owned),
+            swf_url: self.swf_url.map(owned),
+            page_url: self.page_url.map(owned),
+            audio_codecs: self.audio_codecs,
+            video_codecs: self.video_codecs,
+            object_encoding: self.object_encoding,
+            fourcc_list: self.fourcc_list.map(|list| list.into_iter().map(owned).collect()),
+            video_fourcc_info_map: self.video_fourcc_info_map.map(owned_map),
+            audio_fourcc_info_map: self.audio_fourcc_info_map.map(owned_map),
+            caps_ex: self.caps_ex,
+            others: self
+                .others
+                .into_iter()
+                .map(|(key, value)| (owned(key), interner.intern_value(&value)))
+                .collect(),
+        }
+    }
+}
+
+fn as_string<'a>(value: &Amf0Value<'a>) -> Option<Cow<'a, str>> {
+    match value {
+        Amf0Value::String(s) | Amf0Value::LongString(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn as_number(value: &Amf0Value<'_>) -> Option<f64> {
+    match value {
+        Amf0Value::Number(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn as_string_list<'a>(value: &Amf0Value<'a>) -> Option<Vec<Cow<'a, str>>> {
+    match value {
+        Amf0Value::StrictArray(values) => values.iter().map(as_string).collect(),
+        _ => None,
+    }
+}
+
+fn as_number_map<'a>(value: &Amf0Value<'a>) -> Option<Vec<(Cow<'a, str>, f64)>> {
+    match value {
+        Amf0Value::Object(properties) => properties
+            .iter()
+            .map(|(key, value)| Some((key.clone(), as_number(value)?)))
+            .collect(),
+        _ => None,
+    }
+}
+
+impl<'a> CommandObject<'a> for ConnectCommandObject<'a> {
+    fn from_properties(properties: Amf0Properties<'a>) -> Self {
+        let mut object = Self::default();
+
+        for (key, value) in properties {
+            let known = match key.as_ref() {
+                "app" => as_string(&value).map(|v| object.app = Some(v)),
+                "tcUrl" => as_string(&value).map(|v| object.tc_url = Some(v)),
+                "flashVer" => as_string(&value).map(|v| object.flash_ver = Some(v)),
+                "swfUrl" => as_string(&value).map(|v| object.swf_url = Some(v)),
+                "pageUrl" => as_string(&value).map(|v| object.page_url = Some(v)),
+                "audioCodecs" => as_number(&value).map(|v| object.audio_codecs = Some(v)),
+                "videoCodecs" => as_number(&value).map(|v| object.video_codecs = Some(v)),
+                "objectEncoding" => as_number(&value).map(|v| object.object_encoding = Some(v)),
+                "fourCcList" => as_string_list(&value).map(|v| object.fourcc_list = Some(v)),
+                "videoFourCcInfoMap" => as_number_map(&value).map(|v| object.video_fourcc_info_map = Some(v)),
+                "audioFourCcInfoMap" => as_number_map(&value).map(|v| object.audio_fourcc_info_map = Some(v)),
+                "capsEx" => as_number(&value).map(|v| object.caps_ex = Some(v)),
+                _ => None,
+            };
+
+            if known.is_none() {
+                object.others.push((key, value));
+            }
+        }
+
+        object
+    }
+
+    fn to_properties(&self) -> Amf0Properties<'a> {
+        fn string<'a>(key: &'static str, value: &Option<Cow<'a, str>>) -> Option<(Cow<'a, str>, Amf0Value<'a>)> {
+            value.clone().map(|v| (key.into(), Amf0Value::String(v)))
+        }
+
+        fn number<'a>(key: &'static str, value: Option<f64>) -> Option<(Cow<'a, str>, Amf0Value<'a>)> {
+            value.map(|v| (key.into(), Amf0Value::Number(v)))
+        }
+
+        fn number_map<'a>(
+            key: &'static str,
+            value: &Option<Vec<(Cow<'a, str>, f64)>>,
+        ) -> Option<(Cow<'a, str>, Amf0Value<'a>)> {
+            value.as_ref().map(|map| {
+                let properties = map
+                    .iter()
+                    .map(|(fourcc, flags)| (fourcc.clone(), Amf0Value::Number(*flags)))
+                    .collect::<Vec<_>>();
+                (key.into(), Amf0Value::Object(Cow::Owned(properties)))
+            })
+        }
+
+        let fourcc_list = self.fourcc_list.as_ref().map(|list| {
+            let values = list.iter().cloned().map(Amf0Value::String).collect::<Vec<_>>();
+            ("fourCcList".into(), Amf0Value::StrictArray(Cow::Owned(values)))
+        });
+
+        [
+            string("app", &self.app),
+            string("tcUrl", &self.tc_url),
+            string("flashVer", &self.flash_ver),
+            string("swfUrl", &self.swf_url),
+            string("pageUrl", &self.page_url),
+            number("audioCodecs", self.audio_codecs),
+            number("videoCodecs", self.video_codecs),
+            number("objectEncoding", self.object_encoding),
+            fourcc_list,
+            number_map("videoFourCcInfoMap", &self.video_fourcc_info_map),
+            number_map("audioFourCcInfoMap", &self.audio_fourcc_info_map),
+            number("capsEx", self.caps_ex),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.others.iter().cloned())
+        .collect()
+    }
+}
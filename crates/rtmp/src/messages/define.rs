@@ -1,7 +1,12 @@
+use std::borrow::Cow;
+
 use bytes::Bytes;
 use num_derive::FromPrimitive;
 use scuffle_amf0::Amf0Value;
 
+use super::aggregate::AggregateMessage;
+use crate::user_control_messages::UserControlEvent;
+
 #[derive(Debug)]
 pub enum RtmpMessageData<'a> {
     Amf0Command {
@@ -13,15 +18,42 @@ pub enum RtmpMessageData<'a> {
     AmfData {
         data: Bytes,
     },
+    /// A data message other than `onMetaData` (for example `onTextData` or
+    /// `onCuePoint`). The `@setDataFrame` wrapper, if any, is stripped.
+    DataFrame {
+        name: Cow<'a, str>,
+        /// The AMF encoded values following the name.
+        payload: Bytes,
+    },
     SetChunkSize {
         chunk_size: u32,
     },
+    /// The peer will not send the rest of the message on this chunk stream.
+    Abort {
+        chunk_stream_id: u32,
+    },
+    /// The peer received this many bytes so far, wrapping around at 2^32.
+    Acknowledgement {
+        sequence_number: u32,
+    },
+    /// The peer expects an [`Acknowledgement`](RtmpMessageData::Acknowledgement)
+    /// every time this many bytes were received from it.
+    WindowAcknowledgementSize {
+        window_size: u32,
+    },
+    UserControlEvent {
+        event: UserControlEvent,
+    },
     AudioData {
         data: Bytes,
     },
     VideoData {
         data: Bytes,
     },
+    /// The sub-messages of an Aggregate message, with rebased timestamps.
+    Aggregate {
+        messages: Vec<AggregateMessage>,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, FromPrimitive)]
@@ -1,18 +1,23 @@
 use std::fmt;
 
-use scuffle_amf0::Amf0ReadError;
+use scuffle_amf0::{Amf0Marker, Amf0ReadError};
 
 use crate::macros::from_error;
 use crate::protocol_control_messages::ProtocolControlMessageError;
+use crate::user_control_messages::EventMessagesError;
 
 #[derive(Debug)]
 pub enum MessageError {
     Amf0Read(Amf0ReadError),
     ProtocolControlMessage(ProtocolControlMessageError),
+    UserControlEvent(EventMessagesError),
+    InvalidCommandObject(Amf0Marker),
+    InvalidAggregate(&'static str),
 }
 
 from_error!(MessageError, Self::Amf0Read, Amf0ReadError);
 from_error!(MessageError, Self::ProtocolControlMessage, ProtocolControlMessageError);
+from_error!(MessageError, Self::UserControlEvent, EventMessagesError);
 
 impl fmt::Display for MessageError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -21,6 +26,9 @@ impl fmt::Display for MessageError {
             Self::ProtocolControlMessage(error) => {
                 write!(f, "protocol control message error: {}", error)
             }
+            Self::UserControlEvent(error) => write!(f, "user control event error: {}", error),
+            Self::InvalidCommandObject(marker) => write!(f, "invalid command object: {:?}", marker),
+            Self::InvalidAggregate(reason) => write!(f, "invalid aggregate message: {}", reason),
         }
     }
 }
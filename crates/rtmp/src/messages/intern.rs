@@ -0,0 +1,86 @@
+use std::sync::{Arc, LazyLock};
+
+use scuffle_amf0::Amf0Interner;
+
+/// AMF0 strings RTMP clients commonly send: command names, the keys of command
+/// objects and metadata, and enhanced RTMP FourCCs.
+pub const RTMP_STRINGS: &[&str] = &[
+    // Commands
+    "connect",
+    "createStream",
+    "deleteStream",
+    "closeStream",
+    "releaseStream",
+    "publish",
+    "play",
+    "pause",
+    "seek",
+    "FCPublish",
+    "FCUnpublish",
+    "getStreamLength",
+    "_checkbw",
+    "_result",
+    "_error",
+    "onStatus",
+    "onBWDone",
+    "@setDataFrame",
+    "onMetaData",
+    // Publishing types
+    "live",
+    "record",
+    "append",
+    // Connect command object
+    "app",
+    "type",
+    "nonprivate",
+    "flashVer",
+    "swfUrl",
+    "tcUrl",
+    "pageUrl",
+    "fpad",
+    "capabilities",
+    "audioCodecs",
+    "videoCodecs",
+    "videoFunction",
+    "objectEncoding",
+    "fourCcList",
+    "videoFourCcInfoMap",
+    "audioFourCcInfoMap",
+    "capsEx",
+    // Enhanced RTMP FourCCs
+    "*",
+    "avc1",
+    "hvc1",
+    "av01",
+    "vp08",
+    "vp09",
+    "Opus",
+    "mp4a",
+    "fLaC",
+    "ac-3",
+    "ec-3",
+    ".mp3",
+    // onMetaData
+    "duration",
+    "fileSize",
+    "width",
+    "height",
+    "videodatarate",
+    "framerate",
+    "videocodecid",
+    "audiodatarate",
+    "audiosamplerate",
+    "audiosamplesize",
+    "stereo",
+    "audiocodecid",
+    "encoder",
+];
+
+/// Returns an interner of [`RTMP_STRINGS`].
+///
+/// The interner is built once and shared, so short-lived sessions do not pay for building it.
+pub fn rtmp_interner() -> Arc<Amf0Interner> {
+    static INTERNER: LazyLock<Arc<Amf0Interner>> = LazyLock::new(|| Arc::new(RTMP_STRINGS.iter().copied().collect()));
+
+    INTERNER.clone()
+}
@@ -1,9 +1,18 @@
+mod aggregate;
+mod command_object;
 mod define;
 mod errors;
+mod intern;
 mod parser;
 
+pub use self::aggregate::AggregateMessage;
+pub use self::command_object::{
+    Amf0Properties, CAPS_EX_MOD_EX, CAPS_EX_MULTITRACK, CAPS_EX_RECONNECT, CAPS_EX_TIMESTAMP_NANO_OFFSET, CommandObject,
+    ConnectCommandObject, FOURCC_INFO_CAN_DECODE, FOURCC_INFO_CAN_ENCODE, FOURCC_INFO_CAN_FORWARD,
+};
 pub use self::define::{MessageTypeID, RtmpMessageData};
 pub use self::errors::MessageError;
+pub use self::intern::{RTMP_STRINGS, rtmp_interner};
 pub use self::parser::MessageParser;
 
 #[cfg(test)]
@@ -1,9 +1,12 @@
-use scuffle_amf0::{Amf0Decoder, Amf0Marker};
+use bytes::Bytes;
+use scuffle_amf0::{Amf0Decoder, Amf0Marker, Amf0Value};
 
+use super::aggregate::AggregateMessage;
 use super::define::{MessageTypeID, RtmpMessageData};
 use super::errors::MessageError;
 use crate::chunk::Chunk;
 use crate::protocol_control_messages::ProtocolControlMessageReader;
+use crate::user_control_messages::{EventMessagesError, EventMessagesReader};
 
 pub struct MessageParser;
 
@@ -11,23 +14,9 @@ impl MessageParser {
     pub fn parse(chunk: &Chunk) -> Result<Option<RtmpMessageData<'_>>, MessageError> {
         match chunk.message_header.msg_type_id {
             // Protocol Control Messages
-            MessageTypeID::CommandAMF0 => {
-                let mut amf_reader = Amf0Decoder::new(&chunk.payload);
-                let command_name = amf_reader.decode_with_type(Amf0Marker::String)?;
-                let transaction_id = amf_reader.decode_with_type(Amf0Marker::Number)?;
-                let command_object = match amf_reader.decode_with_type(Amf0Marker::Object) {
-                    Ok(val) => val,
-                    Err(_) => amf_reader.decode_with_type(Amf0Marker::Null)?,
-                };
-
-                let others = amf_reader.decode_all()?;
-
-                Ok(Some(RtmpMessageData::Amf0Command {
-                    command_name,
-                    transaction_id,
-                    command_object,
-                    others,
-                }))
+            MessageTypeID::CommandAMF0 => Self::parse_command(&chunk.payload).map(Some),
+            MessageTypeID::CommandAMF3 => {
+                Self::parse_command(&chunk.payload[Self::amf3_body_start(&chunk.payload)..]).map(Some)
             }
             // Data Messages - AUDIO
             MessageTypeID::Audio => Ok(Some(RtmpMessageData::AudioData {
@@ -43,11 +32,114 @@ impl MessageParser {
 
                 Ok(Some(RtmpMessageData::SetChunkSize { chunk_size }))
             }
-            // Metadata
-            MessageTypeID::DataAMF0 | MessageTypeID::DataAMF3 => Ok(Some(RtmpMessageData::AmfData {
-                data: chunk.payload.clone(),
+            MessageTypeID::Abort => {
+                let chunk_stream_id = ProtocolControlMessageReader::read_abort(&chunk.payload)?;
+
+                Ok(Some(RtmpMessageData::Abort { chunk_stream_id }))
+            }
+            MessageTypeID::Acknowledgement => {
+                let sequence_number = ProtocolControlMessageReader::read_acknowledgement(&chunk.payload)?;
+
+                Ok(Some(RtmpMessageData::Acknowledgement { sequence_number }))
+            }
+            MessageTypeID::WindowAcknowledgementSize => {
+                let window_size = ProtocolControlMessageReader::read_window_acknowledgement_size(&chunk.payload)?;
+
+                Ok(Some(RtmpMessageData::WindowAcknowledgementSize { window_size }))
+            }
+            // User Control Messages
+            MessageTypeID::UserControlEvent => match EventMessagesReader::read(&chunk.payload) {
+                Ok(event) => Ok(Some(RtmpMessageData::UserControlEvent { event })),
+                // Events outside of the spec, such as the SWF verification of Flash Player, are ignored.
+                Err(EventMessagesError::UnknownEventType(_)) => Ok(None),
+                Err(error) => Err(error.into()),
+            },
+            // Aggregate
+            MessageTypeID::Aggregate => Ok(Some(RtmpMessageData::Aggregate {
+                messages: AggregateMessage::read_all(&chunk.payload, chunk.message_header.timestamp)?,
             })),
+            // Metadata
+            MessageTypeID::DataAMF0 => Ok(Some(Self::parse_data(&chunk.payload, 0))),
+            MessageTypeID::DataAMF3 => Ok(Some(Self::parse_data(&chunk.payload, Self::amf3_body_start(&chunk.payload)))),
             _ => Ok(None),
         }
     }
+
+    /// Commands are the command name, the transaction id and the command object,
+    /// followed by any arguments.
+    fn parse_command(payload: &[u8]) -> Result<RtmpMessageData<'_>, MessageError> {
+        let mut amf_reader = Amf0Decoder::new(payload);
+        let command_name = amf_reader.decode_with_type(Amf0Marker::String)?;
+        let transaction_id = amf_reader.decode_with_type(Amf0Marker::Number)?;
+        let command_object = match amf_reader.decode_with_type(Amf0Marker::Object) {
+            Ok(val) => val,
+            Err(_) => amf_reader.decode_with_type(Amf0Marker::Null)?,
+        };
+
+        let others = amf_reader.decode_all()?;
+
+        Ok(RtmpMessageData::Amf0Command {
+            command_name,
+            transaction_id,
+            command_object,
+            others,
+        })
+    }
+
+    /// AMF3 command and data messages start with a format byte of 0, after which the
+    /// values are AMF0 encoded, switching to AMF3 per value with the AVM+ marker.
+    /// The AMF0 decoder converts those, so they are parsed like their AMF0 counterparts.
+    ///
+    /// Returns where the values start, as some clients leave out the format byte.
+    fn amf3_body_start(payload: &[u8]) -> usize {
+        usize::from(payload.first() == Some(&0))
+    }
+
+    /// Data messages start with the name of the handler as an AMF0 string,
+    /// optionally prefixed with `@setDataFrame`.
+    /// `onMetaData` and anything we cannot make sense of is passed through
+    /// as [`RtmpMessageData::AmfData`].
+    fn parse_data(payload: &Bytes, start: usize) -> RtmpMessageData<'_> {
+        const SET_DATA_FRAME: &str = "@setDataFrame";
+
+        // marker (1 byte) + length (2 bytes) + the string itself
+        fn encoded_len(name: &str) -> usize {
+            3 + name.len()
+        }
+
+        let mut amf_reader = Amf0Decoder::new(&payload[start..]);
+
+        let mut name = match amf_reader.decode_with_type(Amf0Marker::String) {
+            Ok(Amf0Value::String(name)) => name,
+            _ => {
+                return RtmpMessageData::AmfData {
+                    data: payload.slice(start..),
+                };
+            }
+        };
+        let mut offset = start + encoded_len(&name);
+
+        if name == SET_DATA_FRAME {
+            name = match amf_reader.decode_with_type(Amf0Marker::String) {
+                Ok(Amf0Value::String(name)) => name,
+                _ => {
+                    return RtmpMessageData::AmfData {
+                        data: payload.slice(start..),
+                    };
+                }
+            };
+            offset += encoded_len(&name);
+        }
+
+        if name == "onMetaData" {
+            return RtmpMessageData::AmfData {
+                data: payload.slice(start..),
+            };
+        }
+
+        RtmpMessageData::DataFrame {
+            name,
+            payload: payload.slice(offset..),
+        }
+    }
 }
@@ -1,11 +1,15 @@
 use std::borrow::Cow;
 
 use bytes::Bytes;
-use scuffle_amf0::{Amf0Encoder, Amf0Marker, Amf0ReadError, Amf0Value};
+use scuffle_amf0::{Amf0Decoder, Amf0Encoder, Amf0Marker, Amf0ReadError, Amf0Value};
 
-use super::{MessageError, MessageParser, MessageTypeID, RtmpMessageData};
+use super::{
+    AggregateMessage, CommandObject, ConnectCommandObject, MessageError, MessageParser, MessageTypeID, RTMP_STRINGS,
+    RtmpMessageData, rtmp_interner,
+};
 use crate::chunk::{Chunk, ChunkEncodeError};
 use crate::protocol_control_messages::ProtocolControlMessageError;
+use crate::user_control_messages::{EventMessagesError, UserControlEvent};
 
 #[test]
 fn test_error_display() {
@@ -18,6 +22,15 @@ fn test_error_display() {
         error.to_string(),
         "protocol control message error: chunk encode error: unknown read state"
     );
+
+    let error = MessageError::UserControlEvent(EventMessagesError::UnknownEventType(31));
+    assert_eq!(error.to_string(), "user control event error: unknown event type: 31");
+
+    let error = MessageError::InvalidCommandObject(Amf0Marker::String);
+    assert_eq!(error.to_string(), "invalid command object: String");
+
+    let error = MessageError::InvalidAggregate("nested aggregate");
+    assert_eq!(error.to_string(), "invalid aggregate message: nested aggregate");
 }
 
 #[test]
@@ -49,6 +62,38 @@ fn test_parse_command() {
     }
 }
 
+#[test]
+fn test_parse_amf3_command() {
+    let mut payload = vec![0];
+    Amf0Encoder::encode_string(&mut payload, "connect").unwrap();
+    Amf0Encoder::encode_number(&mut payload, 1.0).unwrap();
+    // An AMF3 dynamic object with the member "app" set to "live".
+    payload.extend_from_slice(&[
+        0x11, 0x0a, 0x0b, 0x01, 0x07, b'a', b'p', b'p', 0x06, 0x09, b'l', b'i', b'v', b'e', 0x01,
+    ]);
+
+    let chunk = Chunk::new(0, 0, MessageTypeID::CommandAMF3, 0, Bytes::from(payload));
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::Amf0Command {
+            command_name,
+            transaction_id,
+            command_object,
+            others,
+        } => {
+            assert_eq!(command_name, Amf0Value::String(Cow::Borrowed("connect")));
+            assert_eq!(transaction_id, Amf0Value::Number(1.0));
+            assert_eq!(
+                command_object,
+                Amf0Value::Object(vec![("app".into(), Amf0Value::String("live".into()))].into())
+            );
+            assert_eq!(others, vec![]);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
 #[test]
 fn test_parse_audio_packet() {
     let chunk = Chunk::new(0, 0, MessageTypeID::Audio, 0, vec![0x00, 0x00, 0x00, 0x00].into());
@@ -88,6 +133,89 @@ fn test_parse_set_chunk_size() {
     }
 }
 
+#[test]
+fn test_parse_abort() {
+    let chunk = Chunk::new(2, 0, MessageTypeID::Abort, 0, vec![0x00, 0x00, 0x00, 0x03].into());
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::Abort { chunk_stream_id } => {
+            assert_eq!(chunk_stream_id, 3);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_acknowledgement() {
+    let chunk = Chunk::new(2, 0, MessageTypeID::Acknowledgement, 0, vec![0x00, 0x00, 0x10, 0x00].into());
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::Acknowledgement { sequence_number } => {
+            assert_eq!(sequence_number, 4096);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+
+    let chunk = Chunk::new(
+        2,
+        0,
+        MessageTypeID::WindowAcknowledgementSize,
+        0,
+        vec![0x00, 0x26, 0x25, 0xa0].into(),
+    );
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::WindowAcknowledgementSize { window_size } => {
+            assert_eq!(window_size, 2_500_000);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_user_control_event() {
+    let chunk = Chunk::new(
+        2,
+        0,
+        MessageTypeID::UserControlEvent,
+        0,
+        vec![0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x0b, 0xb8].into(),
+    );
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::UserControlEvent { event } => {
+            assert_eq!(
+                event,
+                UserControlEvent::SetBufferLength {
+                    stream_id: 1,
+                    buffer_length: 3000,
+                }
+            );
+        }
+        _ => unreachable!("wrong message type"),
+    }
+
+    // Events outside of the spec are ignored.
+    let chunk = Chunk::new(
+        2,
+        0,
+        MessageTypeID::UserControlEvent,
+        0,
+        vec![0x00, 0x1f, 0x00, 0x00, 0x00, 0x01].into(),
+    );
+    assert!(MessageParser::parse(&chunk).expect("no errors").is_none());
+
+    let chunk = Chunk::new(2, 0, MessageTypeID::UserControlEvent, 0, vec![0x00, 0x06, 0x00].into());
+    assert!(matches!(
+        MessageParser::parse(&chunk),
+        Err(MessageError::UserControlEvent(EventMessagesError::IO(_)))
+    ));
+}
+
 #[test]
 fn test_parse_metadata() {
     let mut amf0_writer = Vec::new();
@@ -107,9 +235,393 @@ fn test_parse_metadata() {
     }
 }
 
+#[test]
+fn test_parse_set_data_frame_metadata() {
+    let mut amf0_writer = Vec::new();
+
+    Amf0Encoder::encode_string(&mut amf0_writer, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut amf0_writer, "onMetaData").unwrap();
+    Amf0Encoder::encode_object(&mut amf0_writer, &[("duration".into(), Amf0Value::Number(0.0))]).unwrap();
+
+    let amf_data = Bytes::from(amf0_writer);
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF0, 0, amf_data.clone());
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::AmfData { data } => {
+            assert_eq!(data, amf_data);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_data_frame() {
+    let mut payload = Vec::new();
+    Amf0Encoder::encode_object(&mut payload, &[("text".into(), Amf0Value::String("hello".into()))]).unwrap();
+
+    let mut amf0_writer = Vec::new();
+    Amf0Encoder::encode_string(&mut amf0_writer, "onTextData").unwrap();
+    amf0_writer.extend_from_slice(&payload);
+
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF0, 0, Bytes::from(amf0_writer));
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::DataFrame { name, payload: data } => {
+            assert_eq!(name, "onTextData");
+            assert_eq!(data, payload);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_amf3_data_frame() {
+    let mut payload = Vec::new();
+    Amf0Encoder::encode_number(&mut payload, 1.0).unwrap();
+
+    let mut amf0_writer = vec![0];
+    Amf0Encoder::encode_string(&mut amf0_writer, "onTextData").unwrap();
+    amf0_writer.extend_from_slice(&payload);
+
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF3, 0, Bytes::from(amf0_writer));
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::DataFrame { name, payload: data } => {
+            assert_eq!(name, "onTextData");
+            assert_eq!(data, payload);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+
+    let mut amf0_writer = vec![0];
+    Amf0Encoder::encode_string(&mut amf0_writer, "onMetaData").unwrap();
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF3, 0, Bytes::from(amf0_writer.clone()));
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::AmfData { data } => assert_eq!(data, amf0_writer[1..]),
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_set_data_frame_cue_point() {
+    let mut payload = Vec::new();
+    Amf0Encoder::encode_object(&mut payload, &[("name".into(), Amf0Value::String("ad".into()))]).unwrap();
+
+    let mut amf0_writer = Vec::new();
+    Amf0Encoder::encode_string(&mut amf0_writer, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut amf0_writer, "onCuePoint").unwrap();
+    amf0_writer.extend_from_slice(&payload);
+
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF0, 0, Bytes::from(amf0_writer));
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::DataFrame { name, payload: data } => {
+            assert_eq!(name, "onCuePoint");
+            assert_eq!(data, payload);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
+#[test]
+fn test_parse_data_without_name() {
+    let mut amf0_writer = Vec::new();
+    Amf0Encoder::encode_number(&mut amf0_writer, 1.0).unwrap();
+
+    let amf_data = Bytes::from(amf0_writer);
+    let chunk = Chunk::new(0, 0, MessageTypeID::DataAMF0, 0, amf_data.clone());
+
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+    match message {
+        RtmpMessageData::AmfData { data } => {
+            assert_eq!(data, amf_data);
+        }
+        _ => unreachable!("wrong message type"),
+    }
+}
+
 #[test]
 fn test_unsupported_message_type() {
-    let chunk = Chunk::new(0, 0, MessageTypeID::Aggregate, 0, vec![0x00, 0x00, 0x00, 0x00].into());
+    let chunk = Chunk::new(0, 0, MessageTypeID::SharedObjAMF0, 0, vec![0x00, 0x00, 0x00, 0x00].into());
 
     assert!(MessageParser::parse(&chunk).expect("no errors").is_none())
 }
+
+#[test]
+fn test_parse_aggregate() {
+    #[rustfmt::skip]
+    let payload = Bytes::from_static(&[
+        // audio at 1000
+        0x08, 0x00, 0x00, 0x02, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x00, 0x00,
+        0xaf, 0x01,
+        0x00, 0x00, 0x00, 0x0d,
+        // unknown type at 1010, skipped
+        0x07, 0x00, 0x00, 0x01, 0x00, 0x03, 0xf2, 0x00, 0x00, 0x00, 0x00,
+        0xff,
+        0x00, 0x00, 0x00, 0x0c,
+        // video at 1020
+        0x09, 0x00, 0x00, 0x03, 0x00, 0x03, 0xfc, 0x00, 0x00, 0x00, 0x00,
+        0x17, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x0e,
+    ]);
+
+    // The aggregate is at 5000, the deltas between the sub-messages are kept.
+    let chunk = Chunk::new(0, 5000, MessageTypeID::Aggregate, 1, payload.clone());
+    let message = MessageParser::parse(&chunk).expect("no errors").expect("message");
+
+    let RtmpMessageData::Aggregate { messages } = message else {
+        unreachable!("wrong message type");
+    };
+
+    assert_eq!(
+        messages,
+        vec![
+            AggregateMessage {
+                msg_type_id: MessageTypeID::Audio,
+                timestamp: 5000,
+                payload: Bytes::from_static(&[0xaf, 0x01]),
+            },
+            AggregateMessage {
+                msg_type_id: MessageTypeID::Video,
+                timestamp: 5020,
+                payload: Bytes::from_static(&[0x17, 0x01, 0x00]),
+            },
+        ]
+    );
+
+    // Truncated sub-messages are an error.
+    for len in [5, 14, payload.len() - 1] {
+        let chunk = Chunk::new(0, 5000, MessageTypeID::Aggregate, 1, payload.slice(..len));
+        assert!(matches!(MessageParser::parse(&chunk), Err(MessageError::InvalidAggregate(_))));
+    }
+}
+
+#[test]
+fn test_write_aggregate() {
+    let messages = vec![
+        AggregateMessage {
+            msg_type_id: MessageTypeID::Audio,
+            timestamp: 0x01000010,
+            payload: Bytes::from_static(&[0xaf, 0x01]),
+        },
+        AggregateMessage {
+            msg_type_id: MessageTypeID::Video,
+            timestamp: 0x01000020,
+            payload: Bytes::from_static(&[0x17, 0x01, 0x00]),
+        },
+    ];
+
+    let chunk = AggregateMessage::write_all(5, 1, &messages).expect("no errors");
+    assert_eq!(chunk.message_header.msg_type_id, MessageTypeID::Aggregate);
+    assert_eq!(chunk.message_header.timestamp, 0x01000010);
+    assert_eq!(chunk.message_header.msg_stream_id, 1);
+
+    #[rustfmt::skip]
+    assert_eq!(
+        chunk.payload,
+        Bytes::from_static(&[
+            0x08, 0x00, 0x00, 0x02, 0x00, 0x00, 0x10, 0x01, 0x00, 0x00, 0x00,
+            0xaf, 0x01,
+            0x00, 0x00, 0x00, 0x0d,
+            0x09, 0x00, 0x00, 0x03, 0x00, 0x00, 0x20, 0x01, 0x00, 0x00, 0x00,
+            0x17, 0x01, 0x00,
+            0x00, 0x00, 0x00, 0x0e,
+        ])
+    );
+
+    // Round trips through the parser.
+    let RtmpMessageData::Aggregate { messages: parsed } = MessageParser::parse(&chunk).expect("no errors").expect("message")
+    else {
+        unreachable!("wrong message type");
+    };
+    assert_eq!(parsed, messages);
+
+    assert!(matches!(
+        AggregateMessage::write_all(5, 1, &[]),
+        Err(MessageError::InvalidAggregate("no sub-messages"))
+    ));
+
+    let nested = AggregateMessage {
+        msg_type_id: MessageTypeID::Aggregate,
+        timestamp: 0,
+        payload: Bytes::new(),
+    };
+    assert!(matches!(
+        AggregateMessage::write_all(5, 1, &[nested]),
+        Err(MessageError::InvalidAggregate("nested aggregate"))
+    ));
+}
+
+#[test]
+fn test_connect_command_object_enhanced_rtmp() {
+    let properties = vec![
+        ("app".into(), Amf0Value::String("live".into())),
+        ("tcUrl".into(), Amf0Value::String("rtmp://localhost/live".into())),
+        ("objectEncoding".into(), Amf0Value::Number(0.0)),
+        (
+            "fourCcList".into(),
+            Amf0Value::StrictArray(Cow::Owned(vec![
+                Amf0Value::String("av01".into()),
+                Amf0Value::String("hvc1".into()),
+            ])),
+        ),
+        (
+            "videoFourCcInfoMap".into(),
+            Amf0Value::Object(Cow::Owned(vec![
+                ("av01".into(), Amf0Value::Number(1.0)),
+                ("hvc1".into(), Amf0Value::Number(3.0)),
+            ])),
+        ),
+        ("capsEx".into(), Amf0Value::Number(1.0)),
+        ("fpad".into(), Amf0Value::Boolean(false)),
+        // Known key with an unexpected type is kept as is.
+        ("audioCodecs".into(), Amf0Value::String("aac".into())),
+    ];
+
+    let mut buf = Vec::new();
+    Amf0Encoder::encode_object(&mut buf, &properties).unwrap();
+
+    let value = Amf0Decoder::new(&buf).decode().unwrap();
+    let object = ConnectCommandObject::decode(value).unwrap();
+
+    assert_eq!(object.app.as_deref(), Some("live"));
+    assert_eq!(object.tc_url.as_deref(), Some("rtmp://localhost/live"));
+    assert_eq!(object.object_encoding, Some(0.0));
+    assert_eq!(object.fourcc_list, Some(vec!["av01".into(), "hvc1".into()]));
+    assert_eq!(
+        object.video_fourcc_info_map,
+        Some(vec![("av01".into(), 1.0), ("hvc1".into(), 3.0)])
+    );
+    assert_eq!(object.audio_fourcc_info_map, None);
+    assert_eq!(object.caps_ex, Some(1.0));
+    assert_eq!(object.audio_codecs, None);
+    assert_eq!(
+        object.others,
+        vec![
+            ("fpad".into(), Amf0Value::Boolean(false)),
+            ("audioCodecs".into(), Amf0Value::String("aac".into())),
+        ]
+    );
+
+    // Encoding again keeps every property.
+    let encoded = object.clone().into_owned().encode();
+    let Amf0Value::Object(encoded_properties) = &encoded else {
+        panic!("expected object");
+    };
+    assert_eq!(encoded_properties.len(), properties.len());
+    for property in &properties {
+        assert!(encoded_properties.contains(property), "missing {property:?}");
+    }
+
+    let mut buf = Vec::new();
+    Amf0Encoder::encode(&mut buf, &encoded).unwrap();
+    let value = Amf0Decoder::new(&buf).decode().unwrap();
+    assert_eq!(ConnectCommandObject::decode(value).unwrap(), object);
+}
+
+#[test]
+fn test_command_object_decode() {
+    let object = ConnectCommandObject::decode(Amf0Value::Null).unwrap();
+    assert_eq!(object, ConnectCommandObject::default());
+
+    let properties = Vec::<(Cow<str>, Amf0Value)>::decode(Amf0Value::Object(Cow::Owned(vec![(
+        "app".into(),
+        Amf0Value::String("live".into()),
+    )])))
+    .unwrap();
+    assert_eq!(properties, vec![("app".into(), Amf0Value::String("live".into()))]);
+
+    let error = ConnectCommandObject::decode(Amf0Value::Number(1.0)).unwrap_err();
+    assert!(matches!(error, MessageError::InvalidCommandObject(Amf0Marker::Number)));
+}
+
+#[test]
+fn test_connect_command_object_forward_capabilities() {
+    let legacy = ConnectCommandObject {
+        app: Some("live".into()),
+        ..Default::default()
+    };
+    assert!(!legacy.is_enhanced());
+    assert_eq!(legacy.forward_capabilities(), None);
+
+    // Enhanced RTMP v1 only lists the video codecs.
+    let v1 = ConnectCommandObject {
+        fourcc_list: Some(vec!["hvc1".into(), "av01".into()]),
+        ..Default::default()
+    };
+    let capabilities = v1.forward_capabilities().unwrap();
+    assert_eq!(capabilities.fourcc_list, Some(vec!["hvc1".into(), "av01".into()]));
+    assert_eq!(
+        capabilities.video_fourcc_info_map,
+        Some(vec![("hvc1".into(), 4.0), ("av01".into(), 4.0)])
+    );
+    assert_eq!(capabilities.audio_fourcc_info_map, None);
+    assert_eq!(capabilities.caps_ex, Some(14.0));
+
+    let v2 = ConnectCommandObject {
+        fourcc_list: Some(vec!["*".into()]),
+        video_fourcc_info_map: Some(vec![("hvc1".into(), 1.0)]),
+        audio_fourcc_info_map: Some(vec![("Opus".into(), 3.0)]),
+        caps_ex: Some(1.0),
+        ..Default::default()
+    };
+    let capabilities = v2.forward_capabilities().unwrap();
+    assert_eq!(
+        capabilities.to_properties(),
+        vec![
+            (
+                "fourCcList".into(),
+                Amf0Value::StrictArray(Cow::Owned(vec![Amf0Value::String("*".into())]))
+            ),
+            (
+                "videoFourCcInfoMap".into(),
+                Amf0Value::Object(Cow::Owned(vec![("hvc1".into(), Amf0Value::Number(4.0))]))
+            ),
+            (
+                "audioFourCcInfoMap".into(),
+                Amf0Value::Object(Cow::Owned(vec![("Opus".into(), Amf0Value::Number(4.0))]))
+            ),
+            ("capsEx".into(), Amf0Value::Number(14.0)),
+        ]
+    );
+}
+
+#[test]
+fn test_connect_command_object_into_interned() {
+    let interner = rtmp_interner();
+    assert_eq!(interner.len(), RTMP_STRINGS.len());
+    // Built once and shared.
+    assert!(std::sync::Arc::ptr_eq(&interner, &rtmp_interner()));
+
+    let object = ConnectCommandObject::from_properties(vec![
+        ("app".into(), Amf0Value::String("live".into())),
+        ("tcUrl".into(), Amf0Value::String("rtmp://localhost/live".into())),
+        (
+            "fourCcList".into(),
+            Amf0Value::StrictArray(Cow::Owned(vec![Amf0Value::String("hvc1".into())])),
+        ),
+        ("type".into(), Amf0Value::String("nonprivate".into())),
+        ("custom".into(), Amf0Value::String("value".into())),
+    ]);
+
+    let interned = object.clone().into_interned(&interner);
+    assert_eq!(interned, object.clone().into_owned());
+
+    assert!(matches!(interned.app, Some(Cow::Borrowed("live"))));
+    assert!(matches!(interned.tc_url, Some(Cow::Owned(_))));
+    assert!(matches!(interned.fourcc_list.as_deref(), Some([Cow::Borrowed("hvc1")])));
+    assert!(matches!(
+        &interned.others[0],
+        (Cow::Borrowed("type"), Amf0Value::String(Cow::Borrowed("nonprivate")))
+    ));
+    assert!(matches!(
+        &interned.others[1],
+        (Cow::Owned(_), Amf0Value::String(Cow::Owned(_)))
+    ));
+}
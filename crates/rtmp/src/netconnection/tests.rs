@@ -31,6 +31,7 @@ fn test_netconnection_connect_response() {
         "idk",
         "description",
         0.0,
+        &[("capsEx".into(), Amf0Value::Number(2.0))],
     )
     .unwrap();
 
@@ -52,6 +53,7 @@ fn test_netconnection_connect_response() {
         Amf0Value::Object(Cow::Owned(vec![
             ("fmsVer".into(), Amf0Value::String("flashver".into())),
             ("capabilities".into(), Amf0Value::Number(31.0)),
+            ("capsEx".into(), Amf0Value::Number(2.0)),
         ]))
     ); // command object
     assert_eq!(
@@ -88,3 +90,49 @@ fn test_netconnection_create_stream_response() {
     assert_eq!(values[2], Amf0Value::Null); // command object
     assert_eq!(values[3], Amf0Value::Number(1.0)); // stream id
 }
+
+#[test]
+fn test_netconnection_connect_error() {
+    let encoder = ChunkEncoder::default();
+    let mut buf = BytesMut::new();
+
+    NetConnection::write_connect_error(
+        &encoder,
+        &mut (&mut buf).writer(),
+        1.0,
+        "NetConnection.Connect.Rejected",
+        "description",
+        Some("rtmp://other/live"),
+    )
+    .unwrap();
+
+    let mut decoder = ChunkDecoder::default();
+
+    let chunk = decoder.read_chunk(&mut buf).expect("read chunk").expect("chunk");
+    assert_eq!(chunk.basic_header.chunk_stream_id, 0x03);
+    assert_eq!(chunk.message_header.msg_type_id as u8, 0x14);
+    assert_eq!(chunk.message_header.msg_stream_id, 0);
+
+    let mut amf0_reader = Amf0Decoder::new(&chunk.payload);
+    let values = amf0_reader.decode_all().unwrap();
+
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], Amf0Value::String("_error".into())); // command name
+    assert_eq!(values[1], Amf0Value::Number(1.0)); // transaction id
+    assert_eq!(values[2], Amf0Value::Null); // command object
+    assert_eq!(
+        values[3],
+        Amf0Value::Object(Cow::Owned(vec![
+            ("level".into(), Amf0Value::String("error".into())),
+            ("code".into(), Amf0Value::String("NetConnection.Connect.Rejected".into())),
+            ("description".into(), Amf0Value::String("description".into())),
+            (
+                "ex".into(),
+                Amf0Value::Object(Cow::Owned(vec![
+                    ("code".into(), Amf0Value::Number(302.0)),
+                    ("redirect".into(), Amf0Value::String("rtmp://other/live".into())),
+                ]))
+            ),
+        ]))
+    ); // info object
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::io;
 
 use bytes::Bytes;
@@ -19,29 +20,34 @@ impl NetConnection {
         Ok(())
     }
 
+    /// Writes the `_result` of a `connect` command.
+    ///
+    /// `properties` are added to the command object after `fmsVer` and `capabilities`,
+    /// such as the enhanced RTMP capabilities of the server.
     #[allow(clippy::too_many_arguments)]
-    pub fn write_connect_response(
+    pub fn write_connect_response<'a>(
         encoder: &ChunkEncoder,
         writer: &mut impl io::Write,
         transaction_id: f64,
-        fmsver: &str,
+        fmsver: &'a str,
         capabilities: f64,
         code: &str,
         level: &str,
         description: &str,
         encoding: f64,
+        properties: &[(Cow<'a, str>, Amf0Value<'a>)],
     ) -> Result<(), NetConnectionError> {
         let mut amf0_writer = Vec::new();
 
+        let mut command_object = vec![
+            ("fmsVer".into(), Amf0Value::String(fmsver.into())),
+            ("capabilities".into(), Amf0Value::Number(capabilities)),
+        ];
+        command_object.extend_from_slice(properties);
+
         Amf0Encoder::encode_string(&mut amf0_writer, "_result")?;
         Amf0Encoder::encode_number(&mut amf0_writer, transaction_id)?;
-        Amf0Encoder::encode_object(
-            &mut amf0_writer,
-            &[
-                ("fmsVer".into(), Amf0Value::String(fmsver.into())),
-                ("capabilities".into(), Amf0Value::Number(capabilities)),
-            ],
-        )?;
+        Amf0Encoder::encode_object(&mut amf0_writer, &command_object)?;
         Amf0Encoder::encode_object(
             &mut amf0_writer,
             &[
@@ -55,6 +61,44 @@ impl NetConnection {
         Self::write_chunk(encoder, Bytes::from(amf0_writer), writer)
     }
 
+    /// Writes an `_error` response to a `connect` command.
+    ///
+    /// If `redirect` is set the info object carries an `ex` object with code 302
+    /// and the alternate url, which clients use to reconnect somewhere else.
+    pub fn write_connect_error(
+        encoder: &ChunkEncoder,
+        writer: &mut impl io::Write,
+        transaction_id: f64,
+        code: &str,
+        description: &str,
+        redirect: Option<&str>,
+    ) -> Result<(), NetConnectionError> {
+        let mut amf0_writer = Vec::new();
+
+        let mut info = vec![
+            ("level".into(), Amf0Value::String("error".into())),
+            ("code".into(), Amf0Value::String(code.into())),
+            ("description".into(), Amf0Value::String(description.into())),
+        ];
+
+        if let Some(redirect) = redirect {
+            info.push((
+                "ex".into(),
+                Amf0Value::Object(Cow::Owned(vec![
+                    ("code".into(), Amf0Value::Number(302.0)),
+                    ("redirect".into(), Amf0Value::String(redirect.into())),
+                ])),
+            ));
+        }
+
+        Amf0Encoder::encode_string(&mut amf0_writer, "_error")?;
+        Amf0Encoder::encode_number(&mut amf0_writer, transaction_id)?;
+        Amf0Encoder::encode_null(&mut amf0_writer)?;
+        Amf0Encoder::encode_object(&mut amf0_writer, &info)?;
+
+        Self::write_chunk(encoder, Bytes::from(amf0_writer), writer)
+    }
+
     pub fn write_create_stream_response(
         encoder: &ChunkEncoder,
         writer: &mut impl io::Write,
@@ -4,7 +4,7 @@ use bytes::Bytes;
 use scuffle_amf0::{Amf0Encoder, Amf0Value};
 
 use super::errors::NetStreamError;
-use crate::chunk::{Chunk, ChunkEncoder, DefinedChunkStreamID};
+use crate::chunk::{Chunk, ChunkEncoder, ChunkWriteBuffer, DefinedChunkStreamID};
 use crate::messages::MessageTypeID;
 
 pub struct NetStreamWriter {}
@@ -49,4 +49,23 @@ impl NetStreamWriter {
 
         Self::write_chunk(encoder, Bytes::from(amf0_writer), writer)
     }
+
+    /// Writes a message of a stream the client is playing, such as audio, video or metadata.
+    /// The payload is not copied.
+    pub fn write_stream_data(
+        encoder: &ChunkEncoder,
+        writer: &mut ChunkWriteBuffer,
+        chunk_stream_id: DefinedChunkStreamID,
+        msg_type_id: MessageTypeID,
+        stream_id: u32,
+        timestamp: u32,
+        data: Bytes,
+    ) -> Result<(), NetStreamError> {
+        encoder.write_chunk_to_buffer(
+            writer,
+            Chunk::new(chunk_stream_id as u32, timestamp, msg_type_id, stream_id, data),
+        )?;
+
+        Ok(())
+    }
 }
@@ -21,6 +21,29 @@ fn test_reader_read_set_chunk_size() {
     assert_eq!(chunk_size, 1);
 }
 
+#[test]
+fn test_reader_read_abort() {
+    let data = vec![0x00, 0x00, 0x00, 0x03];
+    let chunk_stream_id = ProtocolControlMessageReader::read_abort(&data).unwrap();
+    assert_eq!(chunk_stream_id, 3);
+}
+
+#[test]
+fn test_reader_read_acknowledgement() {
+    let data = vec![0x00, 0x26, 0x25, 0xa0];
+    let sequence_number = ProtocolControlMessageReader::read_acknowledgement(&data).unwrap();
+    assert_eq!(sequence_number, 2_500_000);
+
+    assert!(ProtocolControlMessageReader::read_acknowledgement(&data[..3]).is_err());
+}
+
+#[test]
+fn test_reader_read_window_acknowledgement_size() {
+    let data = vec![0x00, 0x26, 0x25, 0xa0];
+    let window_size = ProtocolControlMessageReader::read_window_acknowledgement_size(&data).unwrap();
+    assert_eq!(window_size, 2_500_000);
+}
+
 #[test]
 fn test_writer_write_set_chunk_size() {
     let encoder = ChunkEncoder::default();
@@ -37,6 +60,22 @@ fn test_writer_write_set_chunk_size() {
     assert_eq!(chunk.payload, vec![0x00, 0x00, 0x00, 0x01]);
 }
 
+#[test]
+fn test_writer_acknowledgement() {
+    let encoder = ChunkEncoder::default();
+    let mut buf = BytesMut::new();
+
+    ProtocolControlMessagesWriter::write_acknowledgement(&encoder, &mut (&mut buf).writer(), 2_500_000).unwrap();
+
+    let mut decoder = ChunkDecoder::default();
+
+    let chunk = decoder.read_chunk(&mut buf).expect("read chunk").expect("chunk");
+    assert_eq!(chunk.basic_header.chunk_stream_id, 0x02);
+    assert_eq!(chunk.message_header.msg_type_id as u8, 0x03);
+    assert_eq!(chunk.message_header.msg_stream_id, 0);
+    assert_eq!(chunk.payload, vec![0x00, 0x26, 0x25, 0xa0]);
+}
+
 #[test]
 fn test_writer_window_acknowledgement_size() {
     let encoder = ChunkEncoder::default();
@@ -32,6 +32,26 @@ impl ProtocolControlMessagesWriter {
         Ok(())
     }
 
+    /// Tells the peer how many bytes were received from it so far, wrapping around at 2^32.
+    pub fn write_acknowledgement(
+        encoder: &ChunkEncoder,
+        writer: &mut impl io::Write,
+        sequence_number: u32,
+    ) -> Result<(), ProtocolControlMessageError> {
+        encoder.write_chunk(
+            writer,
+            Chunk::new(
+                2, // chunk stream must be 2
+                0, // timestamps are ignored
+                MessageTypeID::Acknowledgement,
+                0, // message stream id is ignored
+                Bytes::from(sequence_number.to_be_bytes().to_vec()),
+            ),
+        )?;
+
+        Ok(())
+    }
+
     pub fn write_window_acknowledgement_size(
         encoder: &ChunkEncoder,
         writer: &mut impl io::Write,
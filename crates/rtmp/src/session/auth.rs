@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+
+use crate::messages::ConnectCommandObject;
+use crate::transport::PeerInfo;
+
+/// The decision of a [`SessionAuthHandler`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthDecision {
+    /// Let the client continue.
+    Allow,
+    /// Reject the client, with a description sent along with the error status.
+    Reject(String),
+}
+
+/// A `connect` command, checked by [`SessionAuthHandler::on_connect`].
+#[derive(Debug)]
+pub struct ConnectAuth<'a> {
+    pub app_name: &'a str,
+    /// The url the client connected to, if it sent one.
+    pub tc_url: Option<&'a str>,
+    /// The full command object of the `connect` command.
+    pub command_object: &'a ConnectCommandObject<'static>,
+    /// Metadata about the client, see [`Session::with_peer_info`](crate::Session::with_peer_info).
+    pub peer_info: &'a PeerInfo,
+}
+
+/// A `publish` command, checked by [`SessionAuthHandler::on_publish`].
+#[derive(Debug)]
+pub struct PublishAuth<'a> {
+    pub app_name: &'a str,
+    /// The name of the stream, which is where encoders put the stream key.
+    pub stream_name: &'a str,
+    /// Metadata about the client, see [`Session::with_peer_info`](crate::Session::with_peer_info).
+    pub peer_info: &'a PeerInfo,
+}
+
+/// A hook the session asks before acknowledging a `connect` or `publish` command,
+/// see [`Session::with_auth_handler`](crate::Session::with_auth_handler).
+///
+/// Rejections are answered with the status encoders expect, `NetConnection.Connect.Rejected`
+/// for `connect` and `NetStream.Publish.BadName` for `publish`, so they show an error
+/// instead of retrying right away. The session ends afterwards.
+///
+/// The checks run before the [`ConnectRequest`](crate::ConnectRequest) and
+/// [`PublishRequest`](crate::PublishRequest) are sent. Both methods allow everything by default.
+///
+/// ```rust
+/// use scuffle_rtmp::{AuthDecision, PublishAuth, SessionAuthHandler};
+///
+/// struct StreamKeys;
+///
+/// #[async_trait::async_trait]
+/// impl SessionAuthHandler for StreamKeys {
+///     async fn on_publish(&self, request: PublishAuth<'_>) -> AuthDecision {
+///         if request.stream_name == "secret" {
+///             AuthDecision::Allow
+///         } else {
+///             AuthDecision::Reject("Invalid stream key.".to_string())
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait SessionAuthHandler: Send + Sync {
+    /// Checks a `connect` command.
+    async fn on_connect(&self, request: ConnectAuth<'_>) -> AuthDecision {
+        let _ = request;
+        AuthDecision::Allow
+    }
+
+    /// Checks a `publish` command.
+    async fn on_publish(&self, request: PublishAuth<'_>) -> AuthDecision {
+        let _ = request;
+        AuthDecision::Allow
+    }
+}
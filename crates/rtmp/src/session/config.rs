@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use crate::chunk::{CHUNK_SIZE, INIT_CHUNK_SIZE, MAX_CHUNK_SIZE};
+use crate::handshake::HandshakeVerification;
+
+/// How the peer should apply the bandwidth sent in a Set Peer Bandwidth message.
+///
+/// 5.4.5 "The Limit Type is one of the following values ..."
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PeerBandwidthLimitType {
+    /// The peer should limit its output bandwidth to the indicated window size.
+    Hard = 0,
+    /// The peer should limit its output bandwidth to the indicated window size
+    /// or the limit already in effect, whichever is smaller.
+    Soft = 1,
+    /// Treated as hard if the previous limit was hard, otherwise ignored.
+    #[default]
+    Dynamic = 2,
+}
+
+/// Protocol tunables of a [`Session`](crate::Session).
+///
+/// The defaults work with common encoders such as OBS and FFmpeg.
+/// The handshake size is fixed by the spec and cannot be changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolConfig {
+    /// How long to wait for each part of the handshake.
+    pub handshake_timeout: Duration,
+    /// Which anomalies in the handshake reject the client, nothing by default.
+    pub handshake_verification: HandshakeVerification,
+    /// How long to wait for data from the client once the handshake is done.
+    /// The session ends with an error if the client sends nothing for this long.
+    pub read_timeout: Duration,
+    /// How long to wait for data to be written to the client.
+    pub write_timeout: Duration,
+    /// How long to wait for room in the data channel before the publisher is
+    /// considered dropped. Messages the [`BackpressurePolicy`](crate::BackpressurePolicy)
+    /// drops do not wait.
+    pub data_send_timeout: Duration,
+    /// The chunk size the server sends with, announced with a Set Chunk Size
+    /// message after the handshake. Clamped to the chunk sizes the
+    /// [`ChunkDecoder`](crate::ChunkDecoder) accepts, 128 bytes to 64 KB.
+    pub chunk_size: usize,
+    /// The window acknowledgement size sent to the client on connect,
+    /// the number of bytes it may receive before sending an acknowledgement.
+    ///
+    /// Until the client sends its own window, the server acknowledges the
+    /// bytes it received from the client at this window as well.
+    pub window_ack_size: u32,
+    /// The bandwidth sent to the client in a Set Peer Bandwidth message on connect.
+    pub peer_bandwidth: u32,
+    /// How the client should apply [`peer_bandwidth`](ProtocolConfig::peer_bandwidth).
+    pub peer_bandwidth_limit_type: PeerBandwidthLimitType,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(2),
+            handshake_verification: HandshakeVerification::default(),
+            read_timeout: Duration::from_millis(2500),
+            write_timeout: Duration::from_secs(2),
+            data_send_timeout: Duration::from_secs(2),
+            chunk_size: CHUNK_SIZE,
+            window_ack_size: 2_500_000,
+            peer_bandwidth: 2_500_000,
+            peer_bandwidth_limit_type: PeerBandwidthLimitType::Dynamic,
+        }
+    }
+}
+
+impl ProtocolConfig {
+    /// Sets the handshake timeout.
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Sets which anomalies in the handshake reject the client.
+    pub fn with_handshake_verification(mut self, verification: HandshakeVerification) -> Self {
+        self.handshake_verification = verification;
+        self
+    }
+
+    /// Sets the read timeout.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Sets the write timeout.
+    pub fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
+
+    /// Sets the data send timeout.
+    pub fn with_data_send_timeout(mut self, timeout: Duration) -> Self {
+        self.data_send_timeout = timeout;
+        self
+    }
+
+    /// Sets the chunk size the server sends with.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the window acknowledgement size.
+    pub fn with_window_ack_size(mut self, window_ack_size: u32) -> Self {
+        self.window_ack_size = window_ack_size;
+        self
+    }
+
+    /// Sets the peer bandwidth and how it should be applied.
+    pub fn with_peer_bandwidth(mut self, peer_bandwidth: u32, limit_type: PeerBandwidthLimitType) -> Self {
+        self.peer_bandwidth = peer_bandwidth;
+        self.peer_bandwidth_limit_type = limit_type;
+        self
+    }
+
+    /// Returns the chunk size clamped to the supported range.
+    pub(crate) fn clamped_chunk_size(&self) -> usize {
+        self.chunk_size.clamp(INIT_CHUNK_SIZE, MAX_CHUNK_SIZE)
+    }
+}
@@ -1,7 +1,7 @@
 #[derive(Debug, PartialEq, Eq, Clone)]
 
 /// RTMP Commands are defined in the RTMP specification
-pub(super) enum RtmpCommand {
+pub(super) enum RtmpCommand<'a> {
     /// NetConnection.connect
     Connect,
     /// NetConnection.createStream
@@ -10,6 +10,10 @@ pub(super) enum RtmpCommand {
     Publish,
     /// NetStream.play
     Play,
+    /// NetStream.pause
+    Pause,
+    /// NetStream.seek
+    Seek,
     /// NetStream.deleteStream
     DeleteStream,
     /// NetStream.closeStream
@@ -17,20 +21,22 @@ pub(super) enum RtmpCommand {
     /// NetStream.releaseStream
     ReleaseStream,
     /// Unknown command
-    Unknown(String),
+    Unknown(&'a str),
 }
 
-impl From<&str> for RtmpCommand {
-    fn from(command: &str) -> Self {
+impl<'a> From<&'a str> for RtmpCommand<'a> {
+    fn from(command: &'a str) -> Self {
         match command {
             "connect" => Self::Connect,
             "createStream" => Self::CreateStream,
             "deleteStream" => Self::DeleteStream,
             "publish" => Self::Publish,
             "play" => Self::Play,
+            "pause" => Self::Pause,
+            "seek" => Self::Seek,
             "closeStream" => Self::CloseStream,
             "releaseStream" => Self::ReleaseStream,
-            _ => Self::Unknown(command.to_string()),
+            _ => Self::Unknown(command),
         }
     }
 }
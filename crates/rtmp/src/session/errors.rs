@@ -26,10 +26,14 @@ pub enum SessionError {
     NoAppName,
     NoStreamName,
     PublishRequestDenied,
+    PlayRequestDenied,
     ConnectRequestDenied,
+    ConnectRedirected(String),
     PlayNotSupported,
     PublisherDropped,
     InvalidChunkSize(usize),
+    StreamInUse(u32),
+    TooManyStreams,
 }
 
 impl SessionError {
@@ -75,10 +79,14 @@ impl fmt::Display for SessionError {
             Self::NoAppName => write!(f, "no app name"),
             Self::NoStreamName => write!(f, "no stream name"),
             Self::PublishRequestDenied => write!(f, "publish request denied"),
+            Self::PlayRequestDenied => write!(f, "play request denied"),
             Self::ConnectRequestDenied => write!(f, "connect request denied"),
+            Self::ConnectRedirected(url) => write!(f, "connect redirected: {}", url),
             Self::InvalidChunkSize(size) => write!(f, "invalid chunk size: {}", size),
             Self::PlayNotSupported => write!(f, "play not supported"),
             Self::PublisherDropped => write!(f, "publisher dropped"),
+            Self::StreamInUse(id) => write!(f, "stream in use: {}", id),
+            Self::TooManyStreams => write!(f, "too many streams"),
             Self::Timeout(error) => write!(f, "timeout: {}", error),
         }
     }
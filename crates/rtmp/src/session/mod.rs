@@ -1,9 +1,17 @@
+mod auth;
+mod config;
 mod define;
 mod errors;
+mod play;
 mod server_session;
+mod stats;
+mod stream;
 
+pub use self::auth::{AuthDecision, ConnectAuth, PublishAuth, SessionAuthHandler};
+pub use self::config::{PeerBandwidthLimitType, ProtocolConfig};
 pub use self::errors::SessionError;
 pub use self::server_session::Session;
+pub use self::stats::SessionStats;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,112 @@
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+
+use crate::channels::{ChannelData, DataConsumer, is_video_keyframe};
+use crate::chunk::DefinedChunkStreamID;
+use crate::messages::MessageTypeID;
+
+/// A message of a played stream, ready to be written to the client.
+#[derive(Debug, PartialEq)]
+pub(super) struct PlayMessage {
+    pub chunk_stream_id: DefinedChunkStreamID,
+    pub msg_type_id: MessageTypeID,
+    pub timestamp: u32,
+    pub payload: Bytes,
+}
+
+/// The stream a client is playing, and where it is in it.
+#[derive(Debug)]
+pub(super) struct PlayState {
+    /// The stream id the client issued `play` on.
+    pub stream_id: u32,
+    consumer: DataConsumer,
+    paused: bool,
+    /// Video is dropped until the next keyframe, so the client does not get frames it cannot decode.
+    waiting_for_keyframe: bool,
+    /// Added to the timestamps of the consumer, which restart after a [`ChannelData::Resume`].
+    timestamp_offset: i64,
+    /// The timestamp of the last message sent to the client.
+    last_timestamp: i64,
+}
+
+impl PlayState {
+    pub fn new(stream_id: u32, consumer: DataConsumer) -> Self {
+        Self {
+            stream_id,
+            consumer,
+            paused: false,
+            waiting_for_keyframe: true,
+            timestamp_offset: 0,
+            last_timestamp: 0,
+        }
+    }
+
+    /// Polls for the next message of the stream, `None` once the stream ended.
+    ///
+    /// Polled together with the other played streams, raced against reading from the client.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<ChannelData>> {
+        self.consumer.poll_recv(cx)
+    }
+
+    /// Pauses or unpauses the stream. The stream is live, so messages received while
+    /// paused are dropped and playback continues at the next keyframe.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            self.waiting_for_keyframe = true;
+        }
+
+        self.paused = paused;
+    }
+
+    /// Seeking is not possible in a live stream, playback continues at the next keyframe.
+    pub fn seek(&mut self) {
+        self.waiting_for_keyframe = true;
+    }
+
+    /// Turns a message of the stream into the message to send to the client,
+    /// `None` if it should not be sent.
+    pub fn message(&mut self, data: ChannelData) -> Option<PlayMessage> {
+        if let ChannelData::Resume { .. } = data {
+            // The publisher reconnected and starts over, continue after what was sent already.
+            self.timestamp_offset = self.last_timestamp;
+            self.waiting_for_keyframe = true;
+            return None;
+        }
+
+        if self.paused {
+            return None;
+        }
+
+        let timestamp = data.timestamp().as_millis() + self.timestamp_offset;
+
+        let (chunk_stream_id, msg_type_id, payload) = match data {
+            ChannelData::Video { data, .. } => {
+                if self.waiting_for_keyframe {
+                    if !is_video_keyframe(&data) {
+                        return None;
+                    }
+
+                    self.waiting_for_keyframe = false;
+                }
+
+                (DefinedChunkStreamID::Video, MessageTypeID::Video, data)
+            }
+            ChannelData::Audio { data, .. } => (DefinedChunkStreamID::Audio, MessageTypeID::Audio, data),
+            ChannelData::Metadata { .. } | ChannelData::DataFrame { .. } => {
+                (DefinedChunkStreamID::Command, MessageTypeID::DataAMF0, data.script_data()?)
+            }
+            ChannelData::Resume { .. } => return None,
+        };
+
+        self.last_timestamp = timestamp;
+
+        Some(PlayMessage {
+            chunk_stream_id,
+            msg_type_id,
+            // RTMP timestamps wrap around.
+            timestamp: timestamp as u32,
+            payload,
+        })
+    }
+}
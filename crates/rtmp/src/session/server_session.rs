@@ -1,25 +1,41 @@
 use std::borrow::Cow;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use bytes::BytesMut;
-use scuffle_amf0::Amf0Value;
+use futures::future::{self, Either};
+use scuffle_amf0::{Amf0Interner, Amf0Value};
 use scuffle_bytes_util::BytesCursorExt;
 use scuffle_future_ext::FutureExt;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, watch};
 
+use super::auth::{AuthDecision, ConnectAuth, PublishAuth, SessionAuthHandler};
+use super::config::ProtocolConfig;
 use super::define::RtmpCommand;
 use super::errors::SessionError;
-use crate::channels::{ChannelData, DataProducer, PublishRequest, UniqueID};
-use crate::chunk::{CHUNK_SIZE, ChunkDecoder, ChunkEncoder};
-use crate::handshake::{HandshakeServer, ServerHandshakeState};
-use crate::messages::{MessageParser, RtmpMessageData};
+use super::play::PlayState;
+use super::stats::SessionStats;
+use super::stream::{PublishState, StreamState, poll_play_streams};
+use crate::channels::{
+    Backpressure, BackpressurePolicy, ChannelData, ConnectDecision, ConnectProducer, ConnectRequest, DataBufferMetrics,
+    DataProducer, DataWatermarks, MediaTimestamp, MessageFilter, MetadataProducer, ParkedStream, PlayProducer, PlayRequest,
+    PublishRequest, ReconnectGrace, SequenceHeaderCache, SequenceHeaders, StreamMetadata, UniqueID, UserControlProducer,
+};
+use crate::chunk::{Chunk, ChunkDecodeError, ChunkDecoder, ChunkEncoder, ChunkWriteBuffer};
+use crate::handshake::{HandshakeMetrics, HandshakeServer, ServerHandshakeState};
+use crate::messages::{CommandObject, ConnectCommandObject, MessageParser, RtmpMessageData, rtmp_interner};
 use crate::netconnection::NetConnection;
 use crate::netstream::NetStreamWriter;
 use crate::protocol_control_messages::ProtocolControlMessagesWriter;
-use crate::user_control_messages::EventMessagesWriter;
+use crate::transport::PeerInfo;
+use crate::user_control_messages::{EventMessagesWriter, UserControlEvent};
 use crate::{PublishProducer, handshake};
 
+/// The maximum number of streams a client can create on a connection.
+const MAX_STREAMS: usize = 32;
+
 pub struct Session<S> {
     /// When you connect via rtmp, you specify the app name in the url
     /// For example: rtmp://localhost:1935/live/xyz
@@ -32,17 +48,22 @@ pub struct Session<S> {
     /// per RTMP connection (using different stream keys) as per the RTMP spec.
     app_name: Option<String>,
 
-    /// This is a unique id for this session
-    /// This is issued when the client connects to the server
+    /// The unique id of the stream the client last published.
     uid: Option<UniqueID>,
 
     /// Used to read and write data
     io: S,
 
+    /// Metadata about the remote peer, set by whoever accepted the connection.
+    peer_info: PeerInfo,
+
+    /// Protocol tunables, such as timeouts and the chunk size.
+    config: ProtocolConfig,
+
     /// Buffer to read data into
     read_buf: BytesMut,
     /// Buffer to write data to
-    write_buf: Vec<u8>,
+    write_buf: ChunkWriteBuffer,
 
     /// Sometimes when doing the handshake we read too much data,
     /// this flag is used to indicate that we have data ready to parse and we
@@ -55,41 +76,323 @@ pub struct Session<S> {
     /// This is used to convert rtmp messages into chunks
     chunk_encoder: ChunkEncoder,
 
-    /// StreamID
-    stream_id: u32,
+    /// The streams the client created, by stream id.
+    streams: HashMap<u32, StreamState>,
 
-    /// Data Producer
+    /// The stream id the next `createStream` command is answered with.
+    next_stream_id: u32,
+
+    /// The data producer of the session, published to by the stream that was not
+    /// given a data producer of its own in its [`PublishResponse`](crate::PublishResponse).
     data_producer: DataProducer,
 
-    /// Is Publishing
-    is_publishing: bool,
+    /// What happens to messages while the data producer of a stream is full.
+    backpressure_policy: BackpressurePolicy,
+
+    /// If set, called when the number of messages queued on the data producer of the session crosses the watermarks.
+    data_watermarks: Option<DataWatermarks>,
+
+    /// If set, called with every media message before it is forwarded.
+    message_filter: Option<Box<dyn MessageFilter>>,
+
+    /// The sequence headers of the stream published to the data producer of the session,
+    /// replayed to consumers attached mid-stream.
+    sequence_headers: SequenceHeaderCache,
+
+    /// If set, the stream is parked here when the publisher disconnects uncleanly.
+    reconnect_grace: Option<ReconnectGrace>,
+
+    /// The counters handshake anomalies are recorded in, if any.
+    handshake_metrics: Option<HandshakeMetrics>,
+
+    /// If set, the user control events sent by the client are forwarded here.
+    user_control_producer: Option<UserControlProducer>,
+
+    /// The buffer length the client last set, in milliseconds.
+    buffer_length: Option<u32>,
+
+    /// The properties of the latest `onMetaData` message of any published stream.
+    metadata: Option<StreamMetadata>,
+
+    /// If set, the properties of every `onMetaData` message are forwarded here.
+    metadata_producer: Option<MetadataProducer>,
+
+    /// Statistics of the session, published to `stats_sender` after every flush.
+    stats: SessionStats,
+
+    /// Sends the statistics to the receivers returned by [`Session::subscribe_stats`].
+    stats_sender: watch::Sender<SessionStats>,
+
+    /// `stats.bytes_received` when the last acknowledgement was sent to the client.
+    bytes_acknowledged: u64,
+
+    /// The window the client asked to be acknowledged at, if it sent one.
+    client_window_ack_size: Option<u32>,
+
+    /// The number of bytes the client acknowledged receiving, wrapping around at 2^32.
+    client_acknowledged: u32,
 
     /// when the publisher connects and tries to publish a stream, we need to
     /// send a publish request to the server
     publish_request_producer: PublishProducer,
+
+    /// If set, connect requests are sent here to decide whether to accept,
+    /// reject or redirect the connection. Otherwise all connections are accepted.
+    connect_request_producer: Option<ConnectProducer>,
+
+    /// If set, play requests are sent here to find the stream to play.
+    /// Otherwise the client cannot play streams.
+    play_request_producer: Option<PlayProducer>,
+
+    /// Interns the AMF0 strings of the commands kept past the message they came in.
+    interner: Arc<Amf0Interner>,
+
+    /// If set, asked before `connect` and `publish` commands are acknowledged.
+    auth_handler: Option<Arc<dyn SessionAuthHandler>>,
 }
 
 impl<S> Session<S> {
+    /// Creates a new session over `io`, usually a [`tokio::net::TcpStream`].
+    ///
+    /// Any [`AsyncRead`](tokio::io::AsyncRead) + [`AsyncWrite`](tokio::io::AsyncWrite) stream works,
+    /// such as a TLS stream for RTMPS (see `TlsAcceptor` with the `tls-rustls` feature),
+    /// or [`FramedIo`](crate::FramedIo) and [`SplitIo`](crate::SplitIo) for message based transports.
     pub fn new(io: S, data_producer: DataProducer, publish_request_producer: PublishProducer) -> Self {
         Self {
             uid: None,
             app_name: None,
             io,
+            peer_info: PeerInfo::default(),
+            config: ProtocolConfig::default(),
             skip_read: false,
             chunk_decoder: ChunkDecoder::default(),
             chunk_encoder: ChunkEncoder::default(),
             read_buf: BytesMut::new(),
-            write_buf: Vec::new(),
+            write_buf: ChunkWriteBuffer::new(),
+            streams: HashMap::new(),
+            next_stream_id: 1,
             data_producer,
-            stream_id: 0,
-            is_publishing: false,
+            backpressure_policy: BackpressurePolicy::default(),
+            data_watermarks: None,
+            message_filter: None,
+            sequence_headers: SequenceHeaderCache::new(),
+            reconnect_grace: None,
+            handshake_metrics: None,
+            user_control_producer: None,
+            buffer_length: None,
+            metadata: None,
+            metadata_producer: None,
+            stats: SessionStats::default(),
+            stats_sender: watch::Sender::new(SessionStats::default()),
+            bytes_acknowledged: 0,
+            client_window_ack_size: None,
+            client_acknowledged: 0,
             publish_request_producer,
+            connect_request_producer: None,
+            play_request_producer: None,
+            interner: rtmp_interner(),
+            auth_handler: None,
         }
     }
 
+    /// Sets a producer to send [`ConnectRequest`]s to, allowing connections to
+    /// be rejected or redirected before anything is published.
+    pub fn with_connect_producer(mut self, connect_request_producer: ConnectProducer) -> Self {
+        self.connect_request_producer = Some(connect_request_producer);
+        self
+    }
+
+    /// Sets a handler to authenticate clients before their `connect` and `publish`
+    /// commands are acknowledged, see [`SessionAuthHandler`].
+    pub fn with_auth_handler(mut self, auth_handler: Arc<dyn SessionAuthHandler>) -> Self {
+        self.auth_handler = Some(auth_handler);
+        self
+    }
+
+    /// Sets a producer to send [`PlayRequest`]s to, allowing clients to play streams.
+    ///
+    /// Without it, a `play` command fails with [`SessionError::PlayNotSupported`].
+    pub fn with_play_producer(mut self, play_request_producer: PlayProducer) -> Self {
+        self.play_request_producer = Some(play_request_producer);
+        self
+    }
+
+    /// Sets watermarks on the data producer, to be notified when the consumer
+    /// falls behind before the session is disconnected for it.
+    pub fn with_data_watermarks(mut self, data_watermarks: DataWatermarks) -> Self {
+        self.data_watermarks = Some(data_watermarks);
+        self
+    }
+
+    /// Sets what happens to media messages while the data producer of a stream is full,
+    /// by default the session waits for room, see [`BackpressurePolicy`].
+    pub fn with_backpressure_policy(mut self, policy: BackpressurePolicy) -> Self {
+        self.backpressure_policy = policy;
+        self
+    }
+
+    /// Returns what happens to media messages while the data producer of a stream is full.
+    pub fn backpressure_policy(&self) -> BackpressurePolicy {
+        self.backpressure_policy
+    }
+
+    /// Sets a filter that can modify or drop each media message before it is forwarded
+    /// to the data producer.
+    pub fn with_message_filter(mut self, message_filter: impl MessageFilter + 'static) -> Self {
+        self.message_filter = Some(Box::new(message_filter));
+        self
+    }
+
+    /// Sets the cache the sequence headers of the published stream are stored in,
+    /// to attach consumers to the stream while the session is running.
+    pub fn with_sequence_header_cache(mut self, sequence_headers: SequenceHeaderCache) -> Self {
+        self.sequence_headers = sequence_headers;
+        self
+    }
+
+    /// Sets the registry the stream is parked in when the publisher disconnects uncleanly,
+    /// and taken over from when a publisher reconnects, see [`ReconnectGrace`].
+    pub fn with_reconnect_grace(mut self, reconnect_grace: ReconnectGrace) -> Self {
+        self.reconnect_grace = Some(reconnect_grace);
+        self
+    }
+
+    /// Sets the counters anomalies in the handshake of the client are recorded in.
+    ///
+    /// Which anomalies reject the client is set with
+    /// [`ProtocolConfig::handshake_verification`].
+    pub fn with_handshake_metrics(mut self, handshake_metrics: HandshakeMetrics) -> Self {
+        self.handshake_metrics = Some(handshake_metrics);
+        self
+    }
+
+    /// Sets a producer the user control events sent by the client, such as
+    /// [`UserControlEvent::SetBufferLength`], are forwarded to.
+    ///
+    /// Events are dropped if the producer is full, so a slow consumer does not stall the session.
+    pub fn with_user_control_producer(mut self, user_control_producer: UserControlProducer) -> Self {
+        self.user_control_producer = Some(user_control_producer);
+        self
+    }
+
+    /// Sets a producer the properties of `onMetaData` messages of the published stream are forwarded to,
+    /// parsed into a [`StreamMetadata`].
+    ///
+    /// Metadata is dropped if the producer is full, so a slow consumer does not stall the session.
+    /// The latest metadata is also available with [`Session::metadata`].
+    pub fn with_metadata_producer(mut self, metadata_producer: MetadataProducer) -> Self {
+        self.metadata_producer = Some(metadata_producer);
+        self
+    }
+
+    /// Sets the metadata about the remote peer, which is passed along with every [`ConnectRequest`].
+    pub fn with_peer_info(mut self, peer_info: PeerInfo) -> Self {
+        self.peer_info = peer_info;
+        self
+    }
+
+    /// Sets the interner for the AMF0 strings of commands, such as the command object of a
+    /// [`ConnectRequest`]. Defaults to [`rtmp_interner`], which is shared by all sessions.
+    pub fn with_interner(mut self, interner: Arc<Amf0Interner>) -> Self {
+        self.interner = interner;
+        self
+    }
+
+    /// Returns the interner for the AMF0 strings of commands.
+    pub fn interner(&self) -> &Arc<Amf0Interner> {
+        &self.interner
+    }
+
+    /// Sets the protocol tunables of the session, such as timeouts and the chunk size.
+    pub fn with_protocol_config(mut self, config: ProtocolConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the protocol tunables of the session.
+    pub fn protocol_config(&self) -> &ProtocolConfig {
+        &self.config
+    }
+
+    /// Returns the metadata about the remote peer.
+    pub fn peer_info(&self) -> &PeerInfo {
+        &self.peer_info
+    }
+
     pub fn uid(&self) -> Option<UniqueID> {
         self.uid
     }
+
+    /// Returns the buffer length the client last set with [`UserControlEvent::SetBufferLength`], in milliseconds.
+    pub fn buffer_length(&self) -> Option<u32> {
+        self.buffer_length
+    }
+
+    /// Returns the number of bytes received from the client, including the handshake.
+    pub fn bytes_received(&self) -> u64 {
+        self.stats.bytes_received
+    }
+
+    /// Returns the statistics of the session.
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Returns a receiver of the statistics of the session, so they can be sampled
+    /// from another task while the session runs. They are published when the session
+    /// flushes its writes, and once more when it ends.
+    pub fn subscribe_stats(&self) -> watch::Receiver<SessionStats> {
+        self.stats_sender.subscribe()
+    }
+
+    /// Returns the number of bytes the client last acknowledged receiving, wrapping around at 2^32.
+    pub fn client_acknowledged(&self) -> u32 {
+        self.client_acknowledged
+    }
+
+    /// Returns the properties of the latest `onMetaData` message of any published stream.
+    pub fn metadata(&self) -> Option<&StreamMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns the latest sequence headers and metadata of the stream published to the data producer of the session.
+    pub fn sequence_headers(&self) -> SequenceHeaders {
+        self.sequence_headers.sequence_headers()
+    }
+
+    /// Returns the cache of the sequence headers, which consumers can be attached to.
+    pub fn sequence_header_cache(&self) -> &SequenceHeaderCache {
+        &self.sequence_headers
+    }
+
+    /// Returns how full the data producer of the session is.
+    pub fn data_buffer_metrics(&self) -> DataBufferMetrics {
+        match self.session_stream() {
+            Some(publish) => publish.data_buffer_metrics(),
+            None => DataBufferMetrics::new(&self.data_producer, 0, &Backpressure::new(self.backpressure_policy)),
+        }
+    }
+
+    /// Returns the ids of the streams the client created, in no particular order.
+    pub fn stream_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.streams.keys().copied()
+    }
+
+    /// Returns the number of streams the client is publishing.
+    pub fn publishing_streams(&self) -> usize {
+        self.streams
+            .values()
+            .filter(|stream| matches!(stream, StreamState::Publishing(_)))
+            .count()
+    }
+
+    /// Returns the stream publishing to the data producer of the session, if any.
+    fn session_stream(&self) -> Option<&PublishState> {
+        self.streams.values().find_map(|stream| match stream {
+            StreamState::Publishing(publish) if publish.session_producer => Some(publish),
+            _ => None,
+        })
+    }
 }
 
 impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
@@ -99,7 +402,7 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
     /// false This can be used to detect non-graceful disconnects (ie. the
     /// client crashed)
     pub async fn run(&mut self) -> Result<bool, SessionError> {
-        let mut handshaker = HandshakeServer::default();
+        let mut handshaker = HandshakeServer::new(self.config.handshake_verification, self.handshake_metrics.clone());
         // Run the handshake to completion
         while !self.do_handshake(&mut handshaker).await? {
             self.flush().await?;
@@ -120,6 +423,12 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
                 tracing::debug!("Client closed the connection");
                 false
             }
+            Err(SessionError::ChunkDecode(ChunkDecodeError::ProtocolViolation(violation))) => {
+                // The stream is out of sync with the spec, so anything we read after this
+                // cannot be trusted. Close the connection instead of guessing.
+                tracing::warn!(%violation, "closing connection after protocol violation");
+                return Err(ChunkDecodeError::ProtocolViolation(violation).into());
+            }
             Err(e) => {
                 return Err(e);
             }
@@ -127,11 +436,43 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             self.flush().await?;
         }
 
-        // We should technically check the stream_map here
-        // However most clients just disconnect without cleanly stopping the subscrition
-        // streams (play streams) So we just check that all publishers have disconnected
+        // Most clients just disconnect without cleanly stopping the subscription
+        // streams (play streams), so we just check that all publishers have disconnected
         // cleanly
-        Ok(!self.is_publishing)
+        let publishing = self.publishing_streams() > 0;
+        if publishing {
+            self.park_streams();
+        }
+
+        self.publish_stats();
+
+        Ok(!publishing)
+    }
+
+    /// Parks the published streams in the reconnect grace registry, if any,
+    /// so a reconnecting publisher can resume them.
+    fn park_streams(&mut self) {
+        let (Some(reconnect_grace), Some(app_name)) = (&self.reconnect_grace, &self.app_name) else {
+            return;
+        };
+
+        for stream in self.streams.values() {
+            let StreamState::Publishing(publish) = stream else {
+                continue;
+            };
+
+            tracing::debug!(uid = %publish.uid, "publisher disconnected uncleanly, parking stream");
+            reconnect_grace.park(
+                app_name,
+                &publish.stream_name,
+                ParkedStream {
+                    uid: publish.uid,
+                    data_producer: publish.data_producer.clone(),
+                    sequence_headers: publish.sequence_headers.clone(),
+                    last_timestamp: publish.last_timestamp,
+                },
+            );
+        }
     }
 
     /// This is the first stage of the session
@@ -148,14 +489,17 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             let n = self
                 .io
                 .read_buf(&mut self.read_buf)
-                .with_timeout(Duration::from_secs(2))
+                .with_timeout(self.config.handshake_timeout)
                 .await??;
             bytes_read += n;
+            self.stats.bytes_received += n as u64;
         }
 
         let mut cursor = std::io::Cursor::new(self.read_buf.split().freeze());
 
-        handshaker.handshake(&mut cursor, &mut self.write_buf)?;
+        let mut output = Vec::new();
+        handshaker.handshake(&mut cursor, &mut output)?;
+        self.write_buf.push_bytes(output.into());
 
         if handshaker.state() == ServerHandshakeState::Finish {
             let over_read = cursor.extract_remaining();
@@ -187,32 +531,89 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
         if self.skip_read {
             self.skip_read = false;
         } else {
-            self.read_buf.reserve(CHUNK_SIZE);
-
-            let n = self
-                .io
-                .read_buf(&mut self.read_buf)
-                .with_timeout(Duration::from_millis(2500))
-                .await??;
-
-            if n == 0 {
-                return Ok(false);
+            self.read_buf.reserve(self.config.clamped_chunk_size());
+
+            let playing = self.streams.values().any(|stream| matches!(stream, StreamState::Playing(_)));
+
+            let ready = if playing {
+                // Players rarely send anything, so there is no read timeout while playing.
+                let read = std::pin::pin!(self.io.read_buf(&mut self.read_buf));
+                let streams = &mut self.streams;
+                let recv = future::poll_fn(|cx| poll_play_streams(streams, cx));
+
+                match future::select(read, recv).await {
+                    Either::Left((n, _)) => Either::Left(n?),
+                    Either::Right((data, _)) => Either::Right(data),
+                }
+            } else {
+                Either::Left(
+                    self.io
+                        .read_buf(&mut self.read_buf)
+                        .with_timeout(self.config.read_timeout)
+                        .await??,
+                )
+            };
+
+            match ready {
+                Either::Left(0) => return Ok(false),
+                Either::Left(n) => self.stats.bytes_received += n as u64,
+                Either::Right((stream_id, data)) => {
+                    self.on_play_data(stream_id, data)?;
+                    return Ok(true);
+                }
             }
         }
 
         self.parse_chunks().await?;
+        self.acknowledge()?;
 
         Ok(true)
     }
 
+    /// Sends an acknowledgement if a window of bytes was received from the client since the last one.
+    ///
+    /// Some encoders stop sending once a window is not acknowledged.
+    fn acknowledge(&mut self) -> Result<(), SessionError> {
+        let window_ack_size = self.client_window_ack_size.unwrap_or(self.config.window_ack_size);
+        if window_ack_size == 0 || self.stats.bytes_received - self.bytes_acknowledged < window_ack_size as u64 {
+            return Ok(());
+        }
+
+        ProtocolControlMessagesWriter::write_acknowledgement(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            // The sequence number wraps around.
+            self.stats.bytes_received as u32,
+        )?;
+        self.bytes_acknowledged = self.stats.bytes_received;
+
+        Ok(())
+    }
+
     /// Parse data from the client into rtmp messages and process them
     async fn parse_chunks(&mut self) -> Result<(), SessionError> {
         while let Some(chunk) = self.chunk_decoder.read_chunk(&mut self.read_buf)? {
             let timestamp = chunk.message_header.timestamp;
             let msg_stream_id = chunk.message_header.msg_stream_id;
 
-            if let Some(msg) = MessageParser::parse(&chunk)? {
-                self.process_messages(msg, msg_stream_id, timestamp).await?;
+            match MessageParser::parse(&chunk)? {
+                Some(RtmpMessageData::Aggregate { messages }) => {
+                    for message in messages {
+                        let sub_chunk = Chunk::new(
+                            chunk.basic_header.chunk_stream_id,
+                            message.timestamp,
+                            message.msg_type_id,
+                            msg_stream_id,
+                            message.payload,
+                        );
+
+                        if let Some(msg) = MessageParser::parse(&sub_chunk)? {
+                            self.process_messages(msg, msg_stream_id, message.timestamp).await?;
+                        }
+                    }
+                }
+                Some(msg) => self.process_messages(msg, msg_stream_id, timestamp).await?,
+                None => {}
             }
         }
 
@@ -226,6 +627,8 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
         stream_id: u32,
         timestamp: u32,
     ) -> Result<(), SessionError> {
+        let timestamp = MediaTimestamp::from_millis(timestamp);
+
         match rtmp_msg {
             RtmpMessageData::Amf0Command {
                 command_name,
@@ -239,6 +642,18 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             RtmpMessageData::SetChunkSize { chunk_size } => {
                 self.on_set_chunk_size(chunk_size as usize)?;
             }
+            RtmpMessageData::Abort { chunk_stream_id } => {
+                self.chunk_decoder.abort_chunk_stream(chunk_stream_id);
+            }
+            RtmpMessageData::Acknowledgement { sequence_number } => {
+                self.client_acknowledged = sequence_number;
+            }
+            RtmpMessageData::WindowAcknowledgementSize { window_size } => {
+                self.client_window_ack_size = Some(window_size);
+            }
+            RtmpMessageData::UserControlEvent { event } => {
+                self.on_user_control_event(event)?;
+            }
             RtmpMessageData::AudioData { data } => {
                 self.on_data(stream_id, ChannelData::Audio { timestamp, data }).await?;
             }
@@ -248,6 +663,43 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             RtmpMessageData::AmfData { data } => {
                 self.on_data(stream_id, ChannelData::Metadata { timestamp, data }).await?;
             }
+            RtmpMessageData::DataFrame { name, payload } => {
+                self.on_data(
+                    stream_id,
+                    ChannelData::DataFrame {
+                        timestamp,
+                        name: name.into_owned(),
+                        payload,
+                    },
+                )
+                .await?;
+            }
+            // Unpacked in `parse_chunks`, aggregates cannot be nested.
+            RtmpMessageData::Aggregate { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// on_user_control_event is called when the client tells us about an event on a stream.
+    /// Ping requests are answered, everything else is only recorded and forwarded.
+    fn on_user_control_event(&mut self, event: UserControlEvent) -> Result<(), SessionError> {
+        match event {
+            UserControlEvent::PingRequest { timestamp } => {
+                EventMessagesWriter::write_ping_response(&self.chunk_encoder, &mut self.write_buf, timestamp)?;
+            }
+            UserControlEvent::SetBufferLength { buffer_length, .. } => {
+                self.buffer_length = Some(buffer_length);
+            }
+            _ => {}
+        }
+
+        let dropped = self
+            .user_control_producer
+            .as_ref()
+            .is_some_and(|user_control_producer| user_control_producer.try_send(event).is_err());
+        if dropped {
+            tracing::debug!(?event, "dropped user control event");
         }
 
         Ok(())
@@ -255,28 +707,90 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
 
     /// Set the server chunk size to the client
     async fn send_set_chunk_size(&mut self) -> Result<(), SessionError> {
-        ProtocolControlMessagesWriter::write_set_chunk_size(&self.chunk_encoder, &mut self.write_buf, CHUNK_SIZE as u32)?;
-        self.chunk_encoder.set_chunk_size(CHUNK_SIZE);
+        let chunk_size = self.config.clamped_chunk_size();
+        ProtocolControlMessagesWriter::write_set_chunk_size(&self.chunk_encoder, &mut self.write_buf, chunk_size as u32)?;
+        self.chunk_encoder.set_chunk_size(chunk_size);
+        self.stats.send_chunk_size = chunk_size;
 
         Ok(())
     }
 
     /// on_data is called when we receive a data message from the client (a
     /// published_stream) Such as audio, video, or metadata
-    /// We then forward the data to the specified publisher
-    async fn on_data(&self, stream_id: u32, data: ChannelData) -> Result<(), SessionError> {
-        if stream_id != self.stream_id || !self.is_publishing {
+    /// We then forward the data to the data producer of the stream
+    async fn on_data(&mut self, stream_id: u32, data: ChannelData) -> Result<(), SessionError> {
+        let Some(StreamState::Publishing(publish)) = self.streams.get_mut(&stream_id) else {
             return Err(SessionError::UnknownStreamID(stream_id));
         };
 
-        if matches!(
-            self.data_producer.send(data).with_timeout(Duration::from_secs(2)).await,
-            Err(_) | Ok(Err(_))
-        ) {
-            tracing::debug!("Publisher dropped");
+        match &data {
+            ChannelData::Audio { .. } => self.stats.audio_messages += 1,
+            ChannelData::Video { .. } => self.stats.video_messages += 1,
+            _ => self.stats.data_messages += 1,
+        }
+
+        let data = match &mut self.message_filter {
+            Some(message_filter) => match message_filter.filter(data) {
+                Some(data) => data,
+                None => return Ok(()),
+            },
+            None => data,
+        };
+
+        publish.sequence_headers.observe(&data);
+        publish.last_timestamp = data.timestamp();
+        self.stats.last_timestamp = Some(publish.last_timestamp);
+
+        if let ChannelData::Metadata { data, .. } = &data {
+            self.on_metadata(data);
+        }
+
+        self.send_data(stream_id, data).await
+    }
+
+    /// Parses the properties of an `onMetaData` message and forwards them to the metadata producer, if any.
+    fn on_metadata(&mut self, data: &[u8]) {
+        let Some(metadata) = StreamMetadata::parse(data) else {
+            tracing::debug!("ignoring metadata that could not be parsed");
+            return;
+        };
+
+        let dropped = self
+            .metadata_producer
+            .as_ref()
+            .is_some_and(|metadata_producer| metadata_producer.try_send(metadata.clone()).is_err());
+        if dropped {
+            tracing::debug!("dropped metadata");
+        }
+
+        self.metadata = Some(metadata);
+    }
+
+    /// Sends `data` to the data producer of the stream, failing if the consumer dropped or
+    /// does not make room within the data send timeout.
+    async fn send_data(&mut self, stream_id: u32, data: ChannelData) -> Result<(), SessionError> {
+        let Some(StreamState::Publishing(publish)) = self.streams.get_mut(&stream_id) else {
+            return Err(SessionError::UnknownStreamID(stream_id));
+        };
+
+        // The watermarks are set on the data producer of the session.
+        let mut data_watermarks = self.data_watermarks.as_mut().filter(|_| publish.session_producer);
+
+        // Checked before sending as well, so the high watermark is reported
+        // while the session waits for room in a full channel.
+        publish.observe_data_buffer(data_watermarks.as_deref_mut());
+
+        if !publish
+            .backpressure
+            .send(&publish.data_producer, data, self.config.data_send_timeout)
+            .await
+        {
+            tracing::debug!(stream_id, "Publisher dropped");
             return Err(SessionError::PublisherDropped);
         }
 
+        publish.observe_data_buffer(data_watermarks);
+
         Ok(())
     }
 
@@ -316,12 +830,24 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
                 self.on_command_delete_stream(transaction_id, stream_id, &obj, others).await?;
             }
             RtmpCommand::Play => {
-                return Err(SessionError::PlayNotSupported);
+                self.on_command_play(transaction_id, stream_id, &obj, others).await?;
+            }
+            RtmpCommand::Pause => {
+                self.on_command_pause(transaction_id, stream_id, &obj, others)?;
+            }
+            RtmpCommand::Seek => {
+                self.on_command_seek(transaction_id, stream_id, &obj, others)?;
             }
             RtmpCommand::Publish => {
                 self.on_command_publish(transaction_id, stream_id, &obj, others).await?;
             }
-            RtmpCommand::CloseStream | RtmpCommand::ReleaseStream => {
+            RtmpCommand::CloseStream => {
+                // Sent by players to stop playing, publishers stop with deleteStream.
+                if let Some(stream @ StreamState::Playing(_)) = self.streams.get_mut(&stream_id) {
+                    *stream = StreamState::Idle;
+                }
+            }
+            RtmpCommand::ReleaseStream => {
                 // Not sure what this is for
             }
             RtmpCommand::Unknown(_) => {}
@@ -334,6 +860,7 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
     /// from the client We then update the chunk size of the unpacketizer
     fn on_set_chunk_size(&mut self, chunk_size: usize) -> Result<(), SessionError> {
         if self.chunk_decoder.update_max_chunk_size(chunk_size) {
+            self.stats.receive_chunk_size = chunk_size;
             Ok(())
         } else {
             Err(SessionError::InvalidChunkSize(chunk_size))
@@ -343,34 +870,78 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
     /// on_command_connect is called when we receive a amf0 command message with
     /// the name "connect" We then handle the connect message
     /// This is called when the client first connects to the server
-    async fn on_command_connect(
+    async fn on_command_connect<'a>(
         &mut self,
         transaction_id: f64,
         _stream_id: u32,
-        command_obj: &[(Cow<'_, str>, Amf0Value<'_>)],
+        command_obj: &[(Cow<'a, str>, Amf0Value<'a>)],
         _others: Vec<Amf0Value<'_>>,
     ) -> Result<(), SessionError> {
+        // Kept past the message by the auth handler and the connect request.
+        let command_obj = ConnectCommandObject::from_properties(command_obj.to_vec()).into_interned(&self.interner);
+        let Some(app_name) = command_obj.app.as_deref() else {
+            return Err(SessionError::NoAppName);
+        };
+
+        if let AuthDecision::Reject(description) = self.authenticate_connect(app_name, &command_obj).await {
+            NetConnection::write_connect_error(
+                &self.chunk_encoder,
+                &mut self.write_buf,
+                transaction_id,
+                "NetConnection.Connect.Rejected",
+                &description,
+                None,
+            )?;
+            self.flush().await?;
+
+            return Err(SessionError::ConnectRequestDenied);
+        }
+
+        match self.request_connect(app_name, &command_obj).await {
+            ConnectDecision::Accept => {
+                self.stats.connected_at = Some(SystemTime::now());
+            }
+            ConnectDecision::Reject => {
+                NetConnection::write_connect_error(
+                    &self.chunk_encoder,
+                    &mut self.write_buf,
+                    transaction_id,
+                    "NetConnection.Connect.Rejected",
+                    "Connection Rejected.",
+                    None,
+                )?;
+                self.flush().await?;
+
+                return Err(SessionError::ConnectRequestDenied);
+            }
+            ConnectDecision::Redirect(url) => {
+                NetConnection::write_connect_error(
+                    &self.chunk_encoder,
+                    &mut self.write_buf,
+                    transaction_id,
+                    "NetConnection.Connect.Rejected",
+                    "Connection Redirected.",
+                    Some(&url),
+                )?;
+                self.flush().await?;
+
+                return Err(SessionError::ConnectRedirected(url));
+            }
+        }
+
         ProtocolControlMessagesWriter::write_window_acknowledgement_size(
             &self.chunk_encoder,
             &mut self.write_buf,
-            CHUNK_SIZE as u32,
+            self.config.window_ack_size,
         )?;
 
         ProtocolControlMessagesWriter::write_set_peer_bandwidth(
             &self.chunk_encoder,
             &mut self.write_buf,
-            CHUNK_SIZE as u32,
-            2, // 2 = dynamic
+            self.config.peer_bandwidth,
+            self.config.peer_bandwidth_limit_type as u8,
         )?;
 
-        let app_name = command_obj.iter().find(|(key, _)| key == "app");
-        let app_name = match app_name {
-            Some((_, Amf0Value::String(app))) => app,
-            _ => {
-                return Err(SessionError::NoAppName);
-            }
-        };
-
         self.app_name = Some(app_name.to_string());
 
         // The only AMF encoding supported by this server is AMF0
@@ -382,6 +953,16 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
         // - SRS does not support AMF3 (https://github.com/ossrs/srs/blob/dcd02fe69cdbd7f401a7b8d139d95b522deb55b1/trunk/src/protocol/srs_protocol_rtmp_stack.cpp#L599)
         // However, the new enhanced-rtmp-v1 spec from YouTube does encourage the use of AMF3 over AMF0 (https://github.com/veovera/enhanced-rtmp)
         // We will eventually support this spec but for now we will stick to AMF0
+        // Clients that send their commands as AMF3 messages anyway are understood,
+        // the message parser converts those to AMF0.
+        //
+        // Enhanced RTMP has the server answer with the codecs it supports, such as HEVC and AV1.
+        // Media is forwarded as is, so we accept whatever the client offers.
+        let capabilities = command_obj
+            .forward_capabilities()
+            .map(|capabilities| capabilities.to_properties())
+            .unwrap_or_default();
+
         NetConnection::write_connect_response(
             &self.chunk_encoder,
             &mut self.write_buf,
@@ -392,11 +973,56 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             "status", // Again not sure what this is but other media servers use it.
             "Connection Succeeded.",
             0.0,
+            &capabilities,
         )?;
 
         Ok(())
     }
 
+    /// Asks the [`SessionAuthHandler`], if any, whether a `connect` command is allowed.
+    /// Without a handler every connection is allowed, a rejected one is answered
+    /// with `NetConnection.Connect.Rejected` before the connect request consumer is asked.
+    async fn authenticate_connect(&self, app_name: &str, command_obj: &ConnectCommandObject<'static>) -> AuthDecision {
+        let Some(auth_handler) = &self.auth_handler else {
+            return AuthDecision::Allow;
+        };
+
+        auth_handler
+            .on_connect(ConnectAuth {
+                app_name,
+                tc_url: command_obj.tc_url.as_deref(),
+                command_object: command_obj,
+                peer_info: &self.peer_info,
+            })
+            .await
+    }
+
+    /// Asks the connect request consumer, if any, what to do with a connection.
+    /// A dropped request or consumer rejects the connection.
+    async fn request_connect(&self, app_name: &str, command_obj: &ConnectCommandObject<'static>) -> ConnectDecision {
+        let Some(connect_request_producer) = &self.connect_request_producer else {
+            return ConnectDecision::Accept;
+        };
+
+        let (response, waiter) = oneshot::channel();
+
+        if connect_request_producer
+            .send(ConnectRequest {
+                app_name: app_name.to_string(),
+                tc_url: command_obj.tc_url.as_deref().map(str::to_string),
+                command_object: command_obj.clone(),
+                peer_info: self.peer_info.clone(),
+                response,
+            })
+            .await
+            .is_err()
+        {
+            return ConnectDecision::Reject;
+        }
+
+        waiter.await.unwrap_or(ConnectDecision::Reject)
+    }
+
     /// on_command_create_stream is called when we receive a amf0 command
     /// message with the name "createStream" We then handle the createStream
     /// message This is called when the client wants to create a stream
@@ -408,8 +1034,20 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
         _command_obj: &[(Cow<'_, str>, Amf0Value<'_>)],
         _others: Vec<Amf0Value<'_>>,
     ) -> Result<(), SessionError> {
-        // 1.0 is the Stream ID of the stream we are creating
-        NetConnection::write_create_stream_response(&self.chunk_encoder, &mut self.write_buf, transaction_id, 1.0)?;
+        if self.streams.len() >= MAX_STREAMS {
+            return Err(SessionError::TooManyStreams);
+        }
+
+        let stream_id = self.next_stream_id;
+        self.next_stream_id += 1;
+        self.streams.insert(stream_id, StreamState::Idle);
+
+        NetConnection::write_create_stream_response(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            transaction_id,
+            stream_id as f64,
+        )?;
 
         Ok(())
     }
@@ -430,9 +1068,11 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             _ => 0.0,
         } as u32;
 
-        if self.stream_id == stream_id && self.is_publishing {
-            self.stream_id = 0;
-            self.is_publishing = false;
+        if let Some(StreamState::Publishing(publish)) = self.streams.remove(&stream_id) {
+            // Cleared for the next stream published to the data producer of the session.
+            if publish.session_producer {
+                self.sequence_headers.clear();
+            }
         }
 
         NetStreamWriter::write_on_status(
@@ -464,33 +1104,199 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             }
         };
 
-        let Some(app_name) = &self.app_name else {
+        let Some(app_name) = self.app_name.clone() else {
             return Err(SessionError::NoAppName);
         };
 
+        self.check_stream_idle(stream_id)?;
+
+        if let Some(auth_handler) = &self.auth_handler {
+            let decision = auth_handler
+                .on_publish(PublishAuth {
+                    app_name: &app_name,
+                    stream_name,
+                    peer_info: &self.peer_info,
+                })
+                .await;
+
+            if let AuthDecision::Reject(description) = decision {
+                NetStreamWriter::write_on_status(
+                    &self.chunk_encoder,
+                    &mut self.write_buf,
+                    transaction_id,
+                    "error",
+                    "NetStream.Publish.BadName",
+                    &description,
+                )?;
+                self.flush().await?;
+
+                return Err(SessionError::PublishRequestDenied);
+            }
+        }
+
+        let parked = self
+            .reconnect_grace
+            .as_ref()
+            .and_then(|reconnect_grace| reconnect_grace.resume(&app_name, stream_name));
+
+        let stream_name = stream_name.to_string();
+        let mut resumed = None;
+
+        let publish = if let Some(parked) = parked {
+            tracing::debug!(uid = %parked.uid, "publisher reconnected, resuming stream");
+
+            let mut publish = PublishState::new(
+                stream_name,
+                parked.uid,
+                parked.data_producer,
+                parked.sequence_headers,
+                self.backpressure_policy,
+            );
+            publish.last_timestamp = parked.last_timestamp;
+
+            // Takes the place of the data producer of the session, unless another stream publishes to it.
+            if self.session_stream().is_none() {
+                self.data_producer = publish.data_producer.clone();
+                self.sequence_headers = publish.sequence_headers.clone();
+                publish.session_producer = true;
+            }
+
+            resumed = Some(parked.last_timestamp);
+            publish
+        } else {
+            let (response, waiter) = oneshot::channel();
+
+            if self
+                .publish_request_producer
+                .send(PublishRequest {
+                    app_name: app_name.clone(),
+                    stream_name: stream_name.clone(),
+                    stream_id,
+                    response,
+                })
+                .await
+                .is_err()
+            {
+                return Err(SessionError::PublishRequestDenied);
+            }
+
+            let Ok(response) = waiter.await else {
+                return Err(SessionError::PublishRequestDenied);
+            };
+
+            match response.data_producer {
+                Some(data_producer) => PublishState::new(
+                    stream_name,
+                    response.uid,
+                    data_producer,
+                    response.sequence_headers.unwrap_or_default(),
+                    self.backpressure_policy,
+                ),
+                None => {
+                    if self.session_stream().is_some() {
+                        tracing::debug!(stream_id, "data producer of the session is used by another stream");
+                        return Err(SessionError::PublishRequestDenied);
+                    }
+
+                    // A new stream may use other codecs than the previous one.
+                    self.sequence_headers.clear();
+
+                    let mut publish = PublishState::new(
+                        stream_name,
+                        response.uid,
+                        self.data_producer.clone(),
+                        self.sequence_headers.clone(),
+                        self.backpressure_policy,
+                    );
+                    publish.session_producer = true;
+                    publish
+                }
+            }
+        };
+
+        self.uid = Some(publish.uid);
+        self.streams.insert(stream_id, StreamState::Publishing(publish));
+
+        if let Some(timestamp) = resumed {
+            let resume = ChannelData::Resume { timestamp };
+            if let Some(StreamState::Publishing(publish)) = self.streams.get(&stream_id) {
+                publish.sequence_headers.observe(&resume);
+            }
+            self.send_data(stream_id, resume).await?;
+        }
+
+        EventMessagesWriter::write_stream_begin(&self.chunk_encoder, &mut self.write_buf, stream_id)?;
+
+        NetStreamWriter::write_on_status(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            transaction_id,
+            "status",
+            "NetStream.Publish.Start",
+            "",
+        )?;
+
+        Ok(())
+    }
+
+    /// on_command_play is called when we receive a amf0 command message with
+    /// the name "play" play commands are used to play a stream from the server
+    /// ie. the user wants to watch a stream
+    async fn on_command_play(
+        &mut self,
+        transaction_id: f64,
+        stream_id: u32,
+        _command_obj: &[(Cow<'_, str>, Amf0Value<'_>)],
+        others: Vec<Amf0Value<'_>>,
+    ) -> Result<(), SessionError> {
+        let Some(play_request_producer) = &self.play_request_producer else {
+            return Err(SessionError::PlayNotSupported);
+        };
+
+        let stream_name = match others.first() {
+            Some(Amf0Value::String(val)) => val,
+            _ => {
+                return Err(SessionError::NoStreamName);
+            }
+        };
+
+        let Some(app_name) = self.app_name.clone() else {
+            return Err(SessionError::NoAppName);
+        };
+
+        self.check_stream_idle(stream_id)?;
+
         let (response, waiter) = oneshot::channel();
 
-        if self
-            .publish_request_producer
-            .send(PublishRequest {
-                app_name: app_name.clone(),
+        let consumer = match play_request_producer
+            .send(PlayRequest {
+                app_name,
                 stream_name: stream_name.to_string(),
+                stream_id,
                 response,
             })
             .await
-            .is_err()
         {
-            return Err(SessionError::PublishRequestDenied);
-        }
-
-        let Ok(uid) = waiter.await else {
-            return Err(SessionError::PublishRequestDenied);
+            Ok(()) => waiter.await.ok(),
+            Err(_) => None,
         };
 
-        self.uid = Some(uid);
+        let Some(consumer) = consumer else {
+            NetStreamWriter::write_on_status(
+                &self.chunk_encoder,
+                &mut self.write_buf,
+                transaction_id,
+                "error",
+                "NetStream.Play.StreamNotFound",
+                "",
+            )?;
+            self.flush().await?;
+
+            return Err(SessionError::PlayRequestDenied);
+        };
 
-        self.is_publishing = true;
-        self.stream_id = stream_id;
+        self.streams
+            .insert(stream_id, StreamState::Playing(PlayState::new(stream_id, consumer)));
 
         EventMessagesWriter::write_stream_begin(&self.chunk_encoder, &mut self.write_buf, stream_id)?;
 
@@ -499,22 +1305,163 @@ impl<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin> Session<S> {
             &mut self.write_buf,
             transaction_id,
             "status",
-            "NetStream.Publish.Start",
+            "NetStream.Play.Reset",
+            "",
+        )?;
+
+        NetStreamWriter::write_on_status(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            transaction_id,
+            "status",
+            "NetStream.Play.Start",
+            "",
+        )?;
+
+        Ok(())
+    }
+
+    /// on_command_pause is called when we receive a amf0 command message with
+    /// the name "pause", the first argument tells whether to pause or unpause.
+    fn on_command_pause(
+        &mut self,
+        transaction_id: f64,
+        stream_id: u32,
+        _command_obj: &[(Cow<'_, str>, Amf0Value<'_>)],
+        others: Vec<Amf0Value<'_>>,
+    ) -> Result<(), SessionError> {
+        let Some(StreamState::Playing(play)) = self.streams.get_mut(&stream_id) else {
+            return Err(SessionError::UnknownStreamID(stream_id));
+        };
+
+        let paused = matches!(others.first(), Some(Amf0Value::Boolean(true)));
+        play.set_paused(paused);
+
+        NetStreamWriter::write_on_status(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            transaction_id,
+            "status",
+            if paused {
+                "NetStream.Pause.Notify"
+            } else {
+                "NetStream.Unpause.Notify"
+            },
             "",
         )?;
 
         Ok(())
     }
 
+    /// on_command_seek is called when we receive a amf0 command message with
+    /// the name "seek". Streams are live, so playback continues at the next keyframe.
+    fn on_command_seek(
+        &mut self,
+        transaction_id: f64,
+        stream_id: u32,
+        _command_obj: &[(Cow<'_, str>, Amf0Value<'_>)],
+        _others: Vec<Amf0Value<'_>>,
+    ) -> Result<(), SessionError> {
+        let Some(StreamState::Playing(play)) = self.streams.get_mut(&stream_id) else {
+            return Err(SessionError::UnknownStreamID(stream_id));
+        };
+
+        play.seek();
+
+        NetStreamWriter::write_on_status(
+            &self.chunk_encoder,
+            &mut self.write_buf,
+            transaction_id,
+            "status",
+            "NetStream.Seek.Notify",
+            "",
+        )?;
+
+        Ok(())
+    }
+
+    /// on_play_data is called with every message of the streams the client is playing,
+    /// `None` once the stream ended.
+    fn on_play_data(&mut self, stream_id: u32, data: Option<ChannelData>) -> Result<(), SessionError> {
+        let Some(StreamState::Playing(play)) = self.streams.get_mut(&stream_id) else {
+            return Ok(());
+        };
+
+        let Some(data) = data else {
+            // The publisher stopped, the client can keep the stream to play another one.
+            self.streams.insert(stream_id, StreamState::Idle);
+
+            EventMessagesWriter::write_event(
+                &self.chunk_encoder,
+                &mut self.write_buf,
+                UserControlEvent::StreamEof { stream_id },
+            )?;
+
+            NetStreamWriter::write_on_status(
+                &self.chunk_encoder,
+                &mut self.write_buf,
+                0.0,
+                "status",
+                "NetStream.Play.UnpublishNotify",
+                "",
+            )?;
+
+            return Ok(());
+        };
+
+        if let Some(message) = play.message(data) {
+            NetStreamWriter::write_stream_data(
+                &self.chunk_encoder,
+                &mut self.write_buf,
+                message.chunk_stream_id,
+                message.msg_type_id,
+                play.stream_id,
+                message.timestamp,
+                message.payload,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `stream_id` can start publishing or playing.
+    ///
+    /// Clients are expected to create the stream first, but streams that were not
+    /// created are accepted as well, as long as the limit is not reached.
+    fn check_stream_idle(&self, stream_id: u32) -> Result<(), SessionError> {
+        match self.streams.get(&stream_id) {
+            Some(stream) if !stream.is_idle() => Err(SessionError::StreamInUse(stream_id)),
+            Some(_) => Ok(()),
+            None if self.streams.len() >= MAX_STREAMS => Err(SessionError::TooManyStreams),
+            None => Ok(()),
+        }
+    }
+
     async fn flush(&mut self) -> Result<(), SessionError> {
         if !self.write_buf.is_empty() {
+            let len = self.write_buf.len();
+            // Written with vectored writes if the transport supports them, the buffer
+            // holds the payloads of played messages without copying them.
             self.io
-                .write_all(self.write_buf.as_ref())
-                .with_timeout(Duration::from_secs(2))
+                .write_all_buf(&mut self.write_buf)
+                .with_timeout(self.config.write_timeout)
                 .await??;
-            self.write_buf.clear();
+            // Message based transports send the buffered data as one message on flush.
+            self.io.flush().with_timeout(self.config.write_timeout).await??;
+            self.stats.bytes_sent += len as u64;
         }
 
+        self.publish_stats();
+
         Ok(())
     }
+
+    /// Publishes the statistics to the subscribed receivers, if they changed.
+    fn publish_stats(&self) {
+        self.stats_sender.send_if_modified(|stats| {
+            let modified = *stats != self.stats;
+            *stats = self.stats;
+            modified
+        });
+    }
 }
@@ -0,0 +1,46 @@
+use std::time::SystemTime;
+
+use crate::channels::MediaTimestamp;
+use crate::chunk::INIT_CHUNK_SIZE;
+
+/// Statistics of a session, for dashboards and debugging.
+///
+/// Read with [`Session::stats`](crate::Session::stats) from the task running the session,
+/// or sampled from elsewhere with [`Session::subscribe_stats`](crate::Session::subscribe_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionStats {
+    /// The number of bytes received from the client, including the handshake.
+    pub bytes_received: u64,
+    /// The number of bytes sent to the client, including the handshake.
+    pub bytes_sent: u64,
+    /// The number of audio messages the client published.
+    pub audio_messages: u64,
+    /// The number of video messages the client published.
+    pub video_messages: u64,
+    /// The number of metadata and other data messages the client published.
+    pub data_messages: u64,
+    /// The timestamp of the last message forwarded to the data producer.
+    pub last_timestamp: Option<MediaTimestamp>,
+    /// The chunk size the client sends with.
+    pub receive_chunk_size: usize,
+    /// The chunk size the server sends with.
+    pub send_chunk_size: usize,
+    /// When the `connect` command of the client was accepted.
+    pub connected_at: Option<SystemTime>,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            bytes_received: 0,
+            bytes_sent: 0,
+            audio_messages: 0,
+            video_messages: 0,
+            data_messages: 0,
+            last_timestamp: None,
+            receive_chunk_size: INIT_CHUNK_SIZE,
+            send_chunk_size: INIT_CHUNK_SIZE,
+            connected_at: None,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::task::{Context, Poll};
+
+use super::play::PlayState;
+use crate::channels::{
+    Backpressure, BackpressurePolicy, ChannelData, DataBufferMetrics, DataProducer, DataWatermarks, MediaTimestamp,
+    SequenceHeaderCache, UniqueID,
+};
+
+/// A stream the client created with `createStream`.
+///
+/// A stream either publishes or plays, a client that does both creates a stream for each.
+#[derive(Debug)]
+pub(super) enum StreamState {
+    /// Created, but not publishing or playing yet.
+    Idle,
+    Publishing(PublishState),
+    Playing(PlayState),
+}
+
+impl StreamState {
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Self::Idle)
+    }
+}
+
+/// A stream the client is publishing.
+#[derive(Debug)]
+pub(super) struct PublishState {
+    pub stream_name: String,
+    pub uid: UniqueID,
+    /// Whether the stream publishes to the data producer of the session, rather than its own.
+    pub session_producer: bool,
+    pub data_producer: DataProducer,
+    /// The largest number of messages that were queued on the data producer at once.
+    peak_data_queued: usize,
+    /// What happens to messages while the data producer is full.
+    pub backpressure: Backpressure,
+    /// The sequence headers of the stream, replayed to consumers attached mid-stream.
+    pub sequence_headers: SequenceHeaderCache,
+    /// The timestamp of the last message forwarded to the data producer.
+    pub last_timestamp: MediaTimestamp,
+}
+
+impl PublishState {
+    pub fn new(
+        stream_name: String,
+        uid: UniqueID,
+        data_producer: DataProducer,
+        sequence_headers: SequenceHeaderCache,
+        policy: BackpressurePolicy,
+    ) -> Self {
+        Self {
+            stream_name,
+            uid,
+            session_producer: false,
+            data_producer,
+            peak_data_queued: 0,
+            backpressure: Backpressure::new(policy),
+            sequence_headers,
+            last_timestamp: MediaTimestamp::from_millis(0),
+        }
+    }
+
+    /// Returns how full the data producer is.
+    pub fn data_buffer_metrics(&self) -> DataBufferMetrics {
+        DataBufferMetrics::new(&self.data_producer, self.peak_data_queued, &self.backpressure)
+    }
+
+    /// Records the current level of the data producer and checks it against the watermarks, if any.
+    pub fn observe_data_buffer(&mut self, data_watermarks: Option<&mut DataWatermarks>) {
+        let metrics = self.data_buffer_metrics();
+        self.peak_data_queued = metrics.peak;
+
+        if let Some(data_watermarks) = data_watermarks {
+            data_watermarks.observe(metrics);
+        }
+    }
+}
+
+/// Polls the streams the client is playing for their next message,
+/// returning it with the id of its stream.
+pub(super) fn poll_play_streams(
+    streams: &mut HashMap<u32, StreamState>,
+    cx: &mut Context<'_>,
+) -> Poll<(u32, Option<ChannelData>)> {
+    for (stream_id, stream) in streams.iter_mut() {
+        let StreamState::Playing(play) = stream else {
+            continue;
+        };
+
+        if let Poll::Ready(data) = play.poll_recv(cx) {
+            return Poll::Ready((*stream_id, data));
+        }
+    }
+
+    Poll::Pending
+}
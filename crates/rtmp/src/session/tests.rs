@@ -1,13 +1,23 @@
-use scuffle_amf0::Amf0Marker;
+use std::borrow::Cow;
+use std::sync::Arc;
 
-use crate::chunk::{ChunkDecodeError, ChunkEncodeError};
+use bytes::{Bytes, BytesMut};
+use scuffle_amf0::{Amf0Decoder, Amf0Encoder, Amf0Marker, Amf0Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use super::play::{PlayMessage, PlayState};
+use crate::chunk::{Chunk, ChunkDecodeError, ChunkDecoder, ChunkEncodeError, ChunkEncoder, DefinedChunkStreamID};
 use crate::handshake::{DigestError, HandshakeError};
-use crate::messages::MessageError;
+use crate::messages::{MessageError, MessageTypeID};
 use crate::netconnection::NetConnectionError;
 use crate::netstream::NetStreamError;
-use crate::protocol_control_messages::ProtocolControlMessageError;
-use crate::user_control_messages::EventMessagesError;
-use crate::{SessionError, UniqueID};
+use crate::protocol_control_messages::{ProtocolControlMessageError, ProtocolControlMessagesWriter};
+use crate::user_control_messages::{EventMessagesError, EventMessagesWriter};
+use crate::{
+    AuthDecision, ChannelData, ConnectAuth, ConnectDecision, MediaTimestamp, PeerBandwidthLimitType, ProtocolConfig,
+    PublishAuth, PublishResponse, Session, SessionAuthHandler, SessionError, SessionStats, UniqueID, UserControlEvent,
+};
 
 #[test]
 fn test_error_display() {
@@ -69,9 +79,15 @@ fn test_error_display() {
     let error = SessionError::PublishRequestDenied;
     assert_eq!(error.to_string(), "publish request denied");
 
+    let error = SessionError::PlayRequestDenied;
+    assert_eq!(error.to_string(), "play request denied");
+
     let error = SessionError::ConnectRequestDenied;
     assert_eq!(error.to_string(), "connect request denied");
 
+    let error = SessionError::ConnectRedirected("rtmp://other/live".into());
+    assert_eq!(error.to_string(), "connect redirected: rtmp://other/live");
+
     let error = SessionError::PlayNotSupported;
     assert_eq!(error.to_string(), "play not supported");
 
@@ -80,4 +96,871 @@ fn test_error_display() {
 
     let error = SessionError::InvalidChunkSize(123);
     assert_eq!(error.to_string(), "invalid chunk size: 123");
+
+    let error = SessionError::StreamInUse(1);
+    assert_eq!(error.to_string(), "stream in use: 1");
+
+    let error = SessionError::TooManyStreams;
+    assert_eq!(error.to_string(), "too many streams");
+}
+
+/// Runs a session for a client that only connects, answering the connect request with `decision`.
+/// Returns the result of the session and the AMF0 commands the server sent back.
+async fn run_connect(decision: Option<ConnectDecision>) -> (Result<bool, SessionError>, Vec<Bytes>) {
+    let (result, chunks) = run_connect_with_config(decision, ProtocolConfig::default()).await;
+    let commands = chunks
+        .into_iter()
+        .filter(|chunk| chunk.message_header.msg_type_id == MessageTypeID::CommandAMF0)
+        .map(|chunk| chunk.payload)
+        .collect();
+
+    (result, commands)
+}
+
+/// Like [`run_connect`], but with a custom `config` and returning all chunks the server sent back.
+async fn run_connect_with_config(
+    decision: Option<ConnectDecision>,
+    config: ProtocolConfig,
+) -> (Result<bool, SessionError>, Vec<Chunk>) {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, _data_consumer) = mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = mpsc::channel(1);
+
+    let mut session = Session::new(server, data_producer, publish_producer).with_protocol_config(config);
+
+    if let Some(decision) = decision {
+        let (connect_producer, mut connect_consumer) = mpsc::channel(1);
+        session = session.with_connect_producer(connect_producer);
+
+        tokio::spawn(async move {
+            let request = connect_consumer.recv().await.unwrap();
+            assert_eq!(request.app_name, "live");
+            assert_eq!(request.tc_url.as_deref(), Some("rtmp://localhost/live"));
+            assert_eq!(request.command_object.app.as_deref(), Some("live"));
+            request.response.send(decision).unwrap();
+        });
+    }
+
+    // C0 + C1 + C2
+    let mut buf = vec![3];
+    buf.extend_from_slice(&[0; 1536 * 2]);
+
+    let mut connect = Vec::new();
+    Amf0Encoder::encode_string(&mut connect, "connect").unwrap();
+    Amf0Encoder::encode_number(&mut connect, 1.0).unwrap();
+    Amf0Encoder::encode_object(
+        &mut connect,
+        &[
+            ("app".into(), Amf0Value::String("live".into())),
+            ("tcUrl".into(), Amf0Value::String("rtmp://localhost/live".into())),
+        ],
+    )
+    .unwrap();
+
+    ChunkEncoder::default()
+        .write_chunk(
+            &mut buf,
+            Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, Bytes::from(connect)),
+        )
+        .unwrap();
+
+    client.write_all(&buf).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    let result = session.run().await;
+    drop(session);
+
+    let mut output = Vec::new();
+    client.read_to_end(&mut output).await.unwrap();
+
+    // Skip S0 + S1 + S2
+    let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+    let mut decoder = ChunkDecoder::default();
+    let mut chunks = Vec::new();
+    while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+        if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+            let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+            assert!(decoder.update_max_chunk_size(chunk_size as usize));
+        }
+
+        chunks.push(chunk);
+    }
+
+    (result, chunks)
+}
+
+#[tokio::test]
+async fn test_session_connect_accept() {
+    for decision in [None, Some(ConnectDecision::Accept)] {
+        let (result, commands) = run_connect(decision).await;
+        assert!(result.unwrap());
+        assert_eq!(commands.len(), 1);
+
+        let values = Amf0Decoder::new(&commands[0]).decode_all().unwrap();
+        assert_eq!(values[0], Amf0Value::String("_result".into()));
+        // The client does not speak enhanced RTMP, so no capabilities are sent.
+        assert!(matches!(&values[2], Amf0Value::Object(properties) if properties.len() == 2));
+    }
+}
+
+#[tokio::test]
+async fn test_session_connect_reject() {
+    let (result, commands) = run_connect(Some(ConnectDecision::Reject)).await;
+    assert!(matches!(result, Err(SessionError::ConnectRequestDenied)));
+    assert_eq!(commands.len(), 1);
+
+    let values = Amf0Decoder::new(&commands[0]).decode_all().unwrap();
+    assert_eq!(values[0], Amf0Value::String("_error".into()));
+    assert_eq!(values[1], Amf0Value::Number(1.0));
+    assert_eq!(
+        values[3],
+        Amf0Value::Object(Cow::Owned(vec![
+            ("level".into(), Amf0Value::String("error".into())),
+            ("code".into(), Amf0Value::String("NetConnection.Connect.Rejected".into())),
+            ("description".into(), Amf0Value::String("Connection Rejected.".into())),
+        ]))
+    );
+}
+
+#[tokio::test]
+async fn test_session_connect_redirect() {
+    let (result, commands) = run_connect(Some(ConnectDecision::Redirect("rtmp://other/live".into()))).await;
+    assert!(matches!(result, Err(SessionError::ConnectRedirected(url)) if url == "rtmp://other/live"));
+    assert_eq!(commands.len(), 1);
+
+    let values = Amf0Decoder::new(&commands[0]).decode_all().unwrap();
+    assert_eq!(values[0], Amf0Value::String("_error".into()));
+    let Amf0Value::Object(info) = &values[3] else {
+        panic!("expected info object");
+    };
+    assert_eq!(
+        info.iter().find(|(key, _)| key == "ex").map(|(_, value)| value),
+        Some(&Amf0Value::Object(Cow::Owned(vec![
+            ("code".into(), Amf0Value::Number(302.0)),
+            ("redirect".into(), Amf0Value::String("rtmp://other/live".into())),
+        ])))
+    );
+}
+
+#[tokio::test]
+async fn test_session_protocol_config() {
+    /// Returns the payloads of the chunks with the given message type.
+    fn payloads(chunks: &[Chunk], msg_type_id: MessageTypeID) -> Vec<&[u8]> {
+        chunks
+            .iter()
+            .filter(|chunk| chunk.message_header.msg_type_id == msg_type_id)
+            .map(|chunk| chunk.payload.as_ref())
+            .collect()
+    }
+
+    let (result, chunks) = run_connect_with_config(None, ProtocolConfig::default()).await;
+    assert!(result.unwrap());
+    assert_eq!(payloads(&chunks, MessageTypeID::SetChunkSize), [4096u32.to_be_bytes()]);
+    assert_eq!(
+        payloads(&chunks, MessageTypeID::WindowAcknowledgementSize),
+        [2_500_000u32.to_be_bytes()]
+    );
+    assert_eq!(
+        payloads(&chunks, MessageTypeID::SetPeerBandwidth),
+        [[0x00, 0x26, 0x25, 0xA0, 2]]
+    );
+
+    let config = ProtocolConfig::default()
+        .with_chunk_size(1024)
+        .with_window_ack_size(5_000_000)
+        .with_peer_bandwidth(1_000_000, PeerBandwidthLimitType::Hard);
+    let (result, chunks) = run_connect_with_config(None, config).await;
+    assert!(result.unwrap());
+    assert_eq!(payloads(&chunks, MessageTypeID::SetChunkSize), [1024u32.to_be_bytes()]);
+    assert_eq!(
+        payloads(&chunks, MessageTypeID::WindowAcknowledgementSize),
+        [5_000_000u32.to_be_bytes()]
+    );
+    assert_eq!(
+        payloads(&chunks, MessageTypeID::SetPeerBandwidth),
+        [[0x00, 0x0F, 0x42, 0x40, 0]]
+    );
+
+    // The chunk size is clamped to what the decoder accepts.
+    let (result, chunks) = run_connect_with_config(None, ProtocolConfig::default().with_chunk_size(1)).await;
+    assert!(result.unwrap());
+    assert_eq!(payloads(&chunks, MessageTypeID::SetChunkSize), [128u32.to_be_bytes()]);
+}
+
+#[tokio::test]
+async fn test_session_handshake_timeout() {
+    let (_client, server) = tokio::io::duplex(1024);
+    let (data_producer, _data_consumer) = mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = mpsc::channel(1);
+
+    let config = ProtocolConfig::default().with_handshake_timeout(std::time::Duration::from_millis(10));
+    let mut session = Session::new(server, data_producer, publish_producer).with_protocol_config(config.clone());
+    assert_eq!(session.protocol_config(), &config);
+
+    // The client never sends the handshake.
+    assert!(matches!(session.run().await, Err(SessionError::Timeout(_))));
+}
+
+#[tokio::test]
+async fn test_session_user_control_events() {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, _data_consumer) = mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = mpsc::channel(1);
+    let (user_control_producer, mut user_control_consumer) = mpsc::channel(4);
+
+    let mut session =
+        Session::new(server, data_producer, publish_producer).with_user_control_producer(user_control_producer);
+
+    // C0 + C1 + C2
+    let mut buf = vec![3];
+    buf.extend_from_slice(&[0; 1536 * 2]);
+
+    let encoder = ChunkEncoder::default();
+    let events = [
+        UserControlEvent::SetBufferLength {
+            stream_id: 0,
+            buffer_length: 3000,
+        },
+        UserControlEvent::PingRequest { timestamp: 1234 },
+    ];
+    for event in events {
+        EventMessagesWriter::write_event(&encoder, &mut buf, event).unwrap();
+    }
+
+    client.write_all(&buf).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    session.run().await.unwrap();
+    assert_eq!(session.buffer_length(), Some(3000));
+    drop(session);
+
+    for event in events {
+        assert_eq!(user_control_consumer.recv().await, Some(event));
+    }
+
+    let mut output = Vec::new();
+    client.read_to_end(&mut output).await.unwrap();
+
+    // Skip S0 + S1 + S2, the ping request is answered.
+    let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+    let mut decoder = ChunkDecoder::default();
+    let mut responses = Vec::new();
+    while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+        if chunk.message_header.msg_type_id == MessageTypeID::UserControlEvent {
+            responses.push(chunk.payload);
+        }
+    }
+    assert_eq!(responses, [Bytes::from_static(&[0x00, 0x07, 0x00, 0x00, 0x04, 0xd2])]);
+}
+
+/// Encodes an AMF0 command sent by the client on `msg_stream_id`.
+fn encode_command(buf: &mut Vec<u8>, msg_stream_id: u32, name: &str, transaction_id: f64, args: &[Amf0Value]) {
+    let mut command = Vec::new();
+    Amf0Encoder::encode_string(&mut command, name).unwrap();
+    Amf0Encoder::encode_number(&mut command, transaction_id).unwrap();
+    Amf0Encoder::encode_null(&mut command).unwrap();
+    for arg in args {
+        Amf0Encoder::encode(&mut command, arg).unwrap();
+    }
+
+    ChunkEncoder::default()
+        .write_chunk(
+            buf,
+            Chunk::new(3, 0, MessageTypeID::CommandAMF0, msg_stream_id, Bytes::from(command)),
+        )
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_session_acknowledgement() {
+    // Without a window from the client, the configured window is used.
+    for client_window_ack_size in [None, Some(1000)] {
+        let (mut client, server) = tokio::io::duplex(128 * 1024);
+        let (data_producer, _data_consumer) = mpsc::channel(1);
+        let (publish_producer, _publish_consumer) = mpsc::channel(1);
+
+        let config = ProtocolConfig::default().with_window_ack_size(client_window_ack_size.map_or(500, |_| 1_000_000));
+        let mut session = Session::new(server, data_producer, publish_producer).with_protocol_config(config);
+
+        let mut buf = connect_request();
+        let encoder = ChunkEncoder::default();
+        if let Some(window_ack_size) = client_window_ack_size {
+            ProtocolControlMessagesWriter::write_window_acknowledgement_size(&encoder, &mut buf, window_ack_size).unwrap();
+        }
+        ProtocolControlMessagesWriter::write_acknowledgement(&encoder, &mut buf, 1234).unwrap();
+        for _ in 0..200 {
+            EventMessagesWriter::write_ping_response(&encoder, &mut buf, 0).unwrap();
+        }
+        client.write_all(&buf).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        assert!(session.run().await.unwrap());
+        assert_eq!(session.bytes_received(), buf.len() as u64);
+        assert_eq!(session.client_acknowledged(), 1234);
+        drop(session);
+
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+
+        let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+        let mut decoder = ChunkDecoder::default();
+        let mut acknowledged = Vec::new();
+        while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+            if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+                let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+                assert!(decoder.update_max_chunk_size(chunk_size as usize));
+            } else if chunk.message_header.msg_type_id == MessageTypeID::Acknowledgement {
+                acknowledged.push(u32::from_be_bytes(chunk.payload[..4].try_into().unwrap()));
+            }
+        }
+
+        // Every acknowledgement is at least a window after the previous one.
+        let window_ack_size = client_window_ack_size.unwrap_or(500);
+        assert!(!acknowledged.is_empty());
+        assert!(acknowledged[0] >= window_ack_size);
+        assert!(acknowledged.windows(2).all(|pair| pair[1] - pair[0] >= window_ack_size));
+        assert!(*acknowledged.last().unwrap() as usize <= buf.len());
+    }
+}
+
+#[tokio::test]
+async fn test_session_stats() {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, _data_consumer) = mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = mpsc::channel(1);
+
+    let mut session = Session::new(server, data_producer, publish_producer);
+    let stats = session.subscribe_stats();
+    assert_eq!(*stats.borrow(), SessionStats::default());
+
+    let mut buf = connect_request();
+    ProtocolControlMessagesWriter::write_set_chunk_size(&ChunkEncoder::default(), &mut buf, 8192).unwrap();
+    client.write_all(&buf).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    assert!(session.run().await.unwrap());
+    drop(session);
+
+    let mut output = Vec::new();
+    client.read_to_end(&mut output).await.unwrap();
+
+    let stats = *stats.borrow();
+    assert_eq!(stats.bytes_received, buf.len() as u64);
+    assert_eq!(stats.bytes_sent, output.len() as u64);
+    assert_eq!(stats.audio_messages + stats.video_messages + stats.data_messages, 0);
+    assert_eq!(stats.last_timestamp, None);
+    assert_eq!(stats.receive_chunk_size, 8192);
+    assert_eq!(stats.send_chunk_size, ProtocolConfig::default().clamped_chunk_size());
+    assert!(stats.connected_at.is_some());
+}
+
+#[tokio::test]
+async fn test_session_metadata() {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, mut data_consumer) = mpsc::channel(1);
+    let (publish_producer, mut publish_consumer) = mpsc::channel(1);
+    let (metadata_producer, mut metadata_consumer) = mpsc::channel(1);
+
+    let mut session = Session::new(server, data_producer, publish_producer).with_metadata_producer(metadata_producer);
+
+    tokio::spawn(async move {
+        let request = publish_consumer.recv().await.unwrap();
+        request.response.send(UniqueID::nil().into()).unwrap();
+    });
+
+    let mut metadata = Vec::new();
+    Amf0Encoder::encode_string(&mut metadata, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut metadata, "onMetaData").unwrap();
+    Amf0Encoder::encode_object(&mut metadata, &[("width".into(), Amf0Value::Number(1920.0))]).unwrap();
+
+    let mut buf = connect_request();
+    encode_command(&mut buf, 1, "publish", 2.0, &[Amf0Value::String("stream".into())]);
+    ChunkEncoder::default()
+        .write_chunk(&mut buf, Chunk::new(4, 0, MessageTypeID::DataAMF0, 1, Bytes::from(metadata)))
+        .unwrap();
+    client.write_all(&buf).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    // The client disconnects while publishing.
+    assert!(!session.run().await.unwrap());
+    assert_eq!(session.metadata().and_then(|metadata| metadata.width), Some(1920));
+    assert_eq!(metadata_consumer.recv().await.unwrap().width, Some(1920));
+    assert!(matches!(data_consumer.recv().await, Some(ChannelData::Metadata { .. })));
+}
+
+#[tokio::test]
+async fn test_session_multiple_streams() {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, mut data_consumer) = mpsc::channel(4);
+    let (publish_producer, mut publish_consumer) = mpsc::channel(1);
+    let (stream_producer, mut stream_consumer) = mpsc::channel(4);
+
+    let mut session = Session::new(server, data_producer, publish_producer);
+
+    tokio::spawn(async move {
+        // The first stream publishes to the data producer of the session, the second to its own.
+        let request = publish_consumer.recv().await.unwrap();
+        assert_eq!((request.stream_name.as_str(), request.stream_id), ("first", 1));
+        request.response.send(UniqueID::nil().into()).unwrap();
+
+        let request = publish_consumer.recv().await.unwrap();
+        assert_eq!((request.stream_name.as_str(), request.stream_id), ("second", 2));
+        request
+            .response
+            .send(PublishResponse::new(UniqueID::max()).with_data_producer(stream_producer))
+            .unwrap();
+    });
+
+    let mut buf = connect_request();
+    encode_command(&mut buf, 0, "createStream", 2.0, &[]);
+    encode_command(&mut buf, 0, "createStream", 3.0, &[]);
+    encode_command(&mut buf, 1, "publish", 4.0, &[Amf0Value::String("first".into())]);
+    encode_command(&mut buf, 2, "publish", 5.0, &[Amf0Value::String("second".into())]);
+
+    let encoder = ChunkEncoder::default();
+    encoder
+        .write_chunk(
+            &mut buf,
+            Chunk::new(4, 0, MessageTypeID::Audio, 1, Bytes::from_static(&[0xaf, 1])),
+        )
+        .unwrap();
+    encoder
+        .write_chunk(
+            &mut buf,
+            Chunk::new(6, 0, MessageTypeID::Video, 2, Bytes::from_static(&[0x17, 1])),
+        )
+        .unwrap();
+    encode_command(&mut buf, 0, "deleteStream", 6.0, &[Amf0Value::Number(2.0)]);
+    client.write_all(&buf).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    // The client disconnects while the first stream is still publishing.
+    assert!(!session.run().await.unwrap());
+    assert_eq!(session.stream_ids().collect::<Vec<_>>(), [1]);
+    assert_eq!(session.publishing_streams(), 1);
+    drop(session);
+
+    assert!(matches!(data_consumer.recv().await, Some(ChannelData::Audio { .. })));
+    assert!(data_consumer.recv().await.is_none());
+    assert!(matches!(stream_consumer.recv().await, Some(ChannelData::Video { .. })));
+    // Deleting the stream dropped its data producer.
+    assert!(stream_consumer.recv().await.is_none());
+
+    let mut output = Vec::new();
+    client.read_to_end(&mut output).await.unwrap();
+
+    let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+    let mut decoder = ChunkDecoder::default();
+    let mut stream_ids = Vec::new();
+    while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+        if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+            let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+            assert!(decoder.update_max_chunk_size(chunk_size as usize));
+        } else if chunk.message_header.msg_type_id == MessageTypeID::CommandAMF0 {
+            let values = Amf0Decoder::new(&chunk.payload).decode_all().unwrap();
+            if let [
+                Amf0Value::String(name),
+                Amf0Value::Number(transaction_id),
+                _,
+                Amf0Value::Number(stream_id),
+            ] = values.as_slice()
+                && name == "_result"
+                && *transaction_id != 1.0
+            {
+                stream_ids.push(*stream_id);
+            }
+        }
+    }
+
+    // Every createStream is answered with a new stream id.
+    assert_eq!(stream_ids, [1.0, 2.0]);
+}
+
+/// Returns the C0 + C1 + C2 handshake and a `connect` command to the "live" app.
+fn connect_request() -> Vec<u8> {
+    let mut buf = vec![3];
+    buf.extend_from_slice(&[0; 1536 * 2]);
+
+    let mut connect = Vec::new();
+    Amf0Encoder::encode_string(&mut connect, "connect").unwrap();
+    Amf0Encoder::encode_number(&mut connect, 1.0).unwrap();
+    Amf0Encoder::encode_object(&mut connect, &[("app".into(), Amf0Value::String("live".into()))]).unwrap();
+
+    ChunkEncoder::default()
+        .write_chunk(
+            &mut buf,
+            Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, Bytes::from(connect)),
+        )
+        .unwrap();
+
+    buf
+}
+
+/// Returns the code of an `onStatus` command, if `chunk` is one.
+fn on_status_code(chunk: &Chunk) -> Option<String> {
+    if chunk.message_header.msg_type_id != MessageTypeID::CommandAMF0 {
+        return None;
+    }
+
+    let values = Amf0Decoder::new(&chunk.payload).decode_all().unwrap();
+    if values.first() != Some(&Amf0Value::String("onStatus".into())) {
+        return None;
+    }
+
+    let Some(Amf0Value::Object(info)) = values.get(3) else {
+        return None;
+    };
+
+    info.iter().find_map(|(key, value)| match value {
+        Amf0Value::String(code) if key == "code" => Some(code.to_string()),
+        _ => None,
+    })
+}
+
+#[tokio::test]
+async fn test_session_play() {
+    let (mut client, server) = tokio::io::duplex(128 * 1024);
+    let (data_producer, _data_consumer) = mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = mpsc::channel(1);
+    let (play_producer, mut play_consumer) = mpsc::channel(1);
+
+    let mut session = Session::new(server, data_producer, publish_producer).with_play_producer(play_producer);
+
+    let mut metadata = Vec::new();
+    Amf0Encoder::encode_string(&mut metadata, "@setDataFrame").unwrap();
+    Amf0Encoder::encode_string(&mut metadata, "onMetaData").unwrap();
+    let metadata = Bytes::from(metadata);
+
+    let timestamp = MediaTimestamp::from_millis;
+    let (stream_producer, stream_consumer) = mpsc::channel(16);
+    for data in [
+        ChannelData::Metadata {
+            timestamp: timestamp(0),
+            data: metadata.clone(),
+        },
+        // Not a keyframe, dropped.
+        ChannelData::Video {
+            timestamp: timestamp(0),
+            data: Bytes::from_static(&[0x27, 1]),
+        },
+        ChannelData::Video {
+            timestamp: timestamp(40),
+            data: Bytes::from_static(&[0x17, 1]),
+        },
+        ChannelData::Audio {
+            timestamp: timestamp(50),
+            data: Bytes::from_static(&[0xaf, 1]),
+        },
+        ChannelData::Resume {
+            timestamp: timestamp(50),
+        },
+        // Not a keyframe after the publisher reconnected, dropped.
+        ChannelData::Video {
+            timestamp: timestamp(0),
+            data: Bytes::from_static(&[0x27, 1]),
+        },
+        ChannelData::Video {
+            timestamp: timestamp(10),
+            data: Bytes::from_static(&[0x17, 1]),
+        },
+    ] {
+        stream_producer.try_send(data).unwrap();
+    }
+    drop(stream_producer);
+
+    tokio::spawn(async move {
+        let request = play_consumer.recv().await.unwrap();
+        assert_eq!(request.app_name, "live");
+        assert_eq!(request.stream_name, "stream");
+        request.response.send(stream_consumer).unwrap();
+    });
+
+    let mut buf = connect_request();
+    encode_command(&mut buf, 1, "play", 2.0, &[Amf0Value::String("stream".into())]);
+    client.write_all(&buf).await.unwrap();
+
+    let read_chunks = async {
+        let mut output = BytesMut::new();
+        let mut decoder = ChunkDecoder::default();
+        let mut chunks = Vec::new();
+
+        // Skip S0 + S1 + S2
+        while output.len() < 1 + 1536 * 2 {
+            assert_ne!(client.read_buf(&mut output).await.unwrap(), 0);
+        }
+        let _ = output.split_to(1 + 1536 * 2);
+
+        // Read until the end of the played stream, then close the connection.
+        loop {
+            while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+                if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+                    let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+                    assert!(decoder.update_max_chunk_size(chunk_size as usize));
+                }
+
+                chunks.push(chunk);
+            }
+
+            if chunks
+                .last()
+                .and_then(on_status_code)
+                .is_some_and(|code| code == "NetStream.Play.UnpublishNotify")
+            {
+                break;
+            }
+
+            assert_ne!(client.read_buf(&mut output).await.unwrap(), 0);
+        }
+
+        client.shutdown().await.unwrap();
+        chunks
+    };
+
+    let (result, chunks) = tokio::join!(session.run(), read_chunks);
+    assert!(result.unwrap());
+
+    let codes: Vec<_> = chunks.iter().filter_map(on_status_code).collect();
+    assert_eq!(
+        codes,
+        [
+            "NetStream.Play.Reset",
+            "NetStream.Play.Start",
+            "NetStream.Play.UnpublishNotify"
+        ]
+    );
+
+    let events: Vec<_> = chunks
+        .iter()
+        .filter(|chunk| chunk.message_header.msg_type_id == MessageTypeID::UserControlEvent)
+        .map(|chunk| chunk.payload.as_ref())
+        .collect();
+    // StreamBegin and StreamEOF of stream 1.
+    assert_eq!(events, [[0, 0, 0, 0, 0, 1], [0, 1, 0, 0, 0, 1]]);
+
+    let media: Vec<_> = chunks
+        .iter()
+        .filter(|chunk| chunk.message_header.msg_stream_id == 1)
+        .map(|chunk| {
+            (
+                chunk.message_header.msg_type_id,
+                chunk.message_header.timestamp,
+                chunk.payload.as_ref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        media,
+        [
+            (MessageTypeID::DataAMF0, 0, &metadata[16..]),
+            (MessageTypeID::Video, 40, [0x17, 1].as_slice()),
+            (MessageTypeID::Audio, 50, [0xaf, 1].as_slice()),
+            (MessageTypeID::Video, 60, [0x17, 1].as_slice()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_session_play_denied() {
+    for with_play_producer in [false, true] {
+        let (mut client, server) = tokio::io::duplex(128 * 1024);
+        let (data_producer, _data_consumer) = mpsc::channel(1);
+        let (publish_producer, _publish_consumer) = mpsc::channel(1);
+
+        let mut session = Session::new(server, data_producer, publish_producer);
+        if with_play_producer {
+            let (play_producer, mut play_consumer) = mpsc::channel(1);
+            session = session.with_play_producer(play_producer);
+
+            // Dropping the request rejects it.
+            tokio::spawn(async move { play_consumer.recv().await });
+        }
+
+        let mut buf = connect_request();
+        encode_command(&mut buf, 1, "play", 2.0, &[Amf0Value::String("stream".into())]);
+        client.write_all(&buf).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = session.run().await;
+        drop(session);
+
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+
+        let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+        let mut decoder = ChunkDecoder::default();
+        let mut codes = Vec::new();
+        while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+            if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+                let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+                assert!(decoder.update_max_chunk_size(chunk_size as usize));
+            }
+
+            codes.extend(on_status_code(&chunk));
+        }
+
+        if with_play_producer {
+            assert!(matches!(result, Err(SessionError::PlayRequestDenied)));
+            assert_eq!(codes, ["NetStream.Play.StreamNotFound"]);
+        } else {
+            assert!(matches!(result, Err(SessionError::PlayNotSupported)));
+            assert!(codes.is_empty());
+        }
+    }
+}
+
+/// Allows the "live" app and the "secret" stream key.
+struct StreamKeys;
+
+#[async_trait::async_trait]
+impl SessionAuthHandler for StreamKeys {
+    async fn on_connect(&self, request: ConnectAuth<'_>) -> AuthDecision {
+        assert_eq!(request.command_object.app.as_deref(), Some(request.app_name));
+        if request.app_name == "live" {
+            AuthDecision::Allow
+        } else {
+            AuthDecision::Reject("Unknown app.".to_string())
+        }
+    }
+
+    async fn on_publish(&self, request: PublishAuth<'_>) -> AuthDecision {
+        assert_eq!(request.app_name, "live");
+        if request.stream_name == "secret" {
+            AuthDecision::Allow
+        } else {
+            AuthDecision::Reject("Invalid stream key.".to_string())
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_session_auth_handler() {
+    for (app_name, stream_key) in [("live", "secret"), ("live", "wrong"), ("other", "secret")] {
+        let (mut client, server) = tokio::io::duplex(128 * 1024);
+        let (data_producer, _data_consumer) = mpsc::channel(1);
+        let (publish_producer, mut publish_consumer) = mpsc::channel(1);
+
+        let mut session = Session::new(server, data_producer, publish_producer).with_auth_handler(Arc::new(StreamKeys));
+
+        let publish = tokio::spawn(async move {
+            let request = publish_consumer.recv().await?;
+            request.response.send(UniqueID::nil().into()).unwrap();
+            Some(request.stream_name)
+        });
+
+        let mut buf = vec![3];
+        buf.extend_from_slice(&[0; 1536 * 2]);
+        let mut connect = Vec::new();
+        Amf0Encoder::encode_string(&mut connect, "connect").unwrap();
+        Amf0Encoder::encode_number(&mut connect, 1.0).unwrap();
+        Amf0Encoder::encode_object(&mut connect, &[("app".into(), Amf0Value::String(app_name.into()))]).unwrap();
+        ChunkEncoder::default()
+            .write_chunk(
+                &mut buf,
+                Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, Bytes::from(connect)),
+            )
+            .unwrap();
+        encode_command(&mut buf, 1, "publish", 2.0, &[Amf0Value::String(stream_key.into())]);
+        client.write_all(&buf).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let result = session.run().await;
+        drop(session);
+
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+
+        let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+        let mut decoder = ChunkDecoder::default();
+        let mut commands = Vec::new();
+        while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+            if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+                let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+                assert!(decoder.update_max_chunk_size(chunk_size as usize));
+            } else if chunk.message_header.msg_type_id == MessageTypeID::CommandAMF0 {
+                commands.push(chunk.payload);
+            }
+        }
+
+        // The info objects of the commands.
+        let commands = commands
+            .iter()
+            .map(|command| Amf0Decoder::new(command).decode_all().unwrap().swap_remove(3))
+            .collect::<Vec<_>>();
+
+        let status = |code: &str, description: &str| {
+            Amf0Value::Object(Cow::Owned(vec![
+                ("level".into(), Amf0Value::String("error".into())),
+                ("code".into(), Amf0Value::String(code.to_string().into())),
+                ("description".into(), Amf0Value::String(description.to_string().into())),
+            ]))
+        };
+
+        match (app_name, stream_key) {
+            ("live", "secret") => {
+                // The client disconnects while publishing.
+                assert!(!result.unwrap());
+                assert_eq!(publish.await.unwrap().as_deref(), Some("secret"));
+            }
+            ("live", _) => {
+                assert!(matches!(result, Err(SessionError::PublishRequestDenied)));
+                assert_eq!(
+                    commands.last(),
+                    Some(&status("NetStream.Publish.BadName", "Invalid stream key."))
+                );
+                assert_eq!(publish.await.unwrap(), None);
+            }
+            _ => {
+                assert!(matches!(result, Err(SessionError::ConnectRequestDenied)));
+                assert_eq!(commands, [status("NetConnection.Connect.Rejected", "Unknown app.")]);
+                assert_eq!(publish.await.unwrap(), None);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_play_state_pause_seek() {
+    let (_producer, consumer) = mpsc::channel(1);
+    let mut play = PlayState::new(1, consumer);
+
+    let video = |timestamp, keyframe| ChannelData::Video {
+        timestamp: MediaTimestamp::from_millis(timestamp),
+        data: Bytes::from_static(if keyframe { &[0x17, 1] } else { &[0x27, 1] }),
+    };
+    let sent = |timestamp, keyframe: bool| PlayMessage {
+        chunk_stream_id: DefinedChunkStreamID::Video,
+        msg_type_id: MessageTypeID::Video,
+        timestamp,
+        payload: Bytes::from_static(if keyframe { &[0x17, 1] } else { &[0x27, 1] }),
+    };
+
+    assert_eq!(play.message(video(0, true)), Some(sent(0, true)));
+    assert_eq!(play.message(video(40, false)), Some(sent(40, false)));
+
+    // Everything is dropped while paused, and playback continues at the next keyframe.
+    play.set_paused(true);
+    assert_eq!(play.message(video(80, true)), None);
+    play.set_paused(false);
+    assert_eq!(play.message(video(120, false)), None);
+    assert_eq!(play.message(video(160, true)), Some(sent(160, true)));
+    assert_eq!(play.message(video(200, false)), Some(sent(200, false)));
+
+    // Seeking continues at the next keyframe as well.
+    play.seek();
+    assert_eq!(play.message(video(240, false)), None);
+    assert_eq!(play.message(video(280, true)), Some(sent(280, true)));
+
+    // Data frames are sent with their name.
+    let message = play
+        .message(ChannelData::DataFrame {
+            timestamp: MediaTimestamp::from_millis(300),
+            name: "onTextData".into(),
+            payload: Bytes::from_static(&[0x05]),
+        })
+        .unwrap();
+    assert_eq!(message.msg_type_id, MessageTypeID::DataAMF0);
+    assert_eq!(
+        Amf0Decoder::new(&message.payload).decode_all().unwrap(),
+        [Amf0Value::String("onTextData".into()), Amf0Value::Null]
+    );
 }
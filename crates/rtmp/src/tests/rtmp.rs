@@ -70,7 +70,7 @@ async fn test_basic_rtmp_clean() {
     assert_eq!(event.stream_name, "stream-key");
 
     let stream_id = UniqueID::new_v4();
-    event.response.send(stream_id).expect("failed to send response");
+    event.response.send(stream_id.into()).expect("failed to send response");
 
     let mut got_video = false;
     let mut got_audio = false;
@@ -86,6 +86,7 @@ async fn test_basic_rtmp_clean() {
             ChannelData::Video { .. } => got_video = true,
             ChannelData::Audio { .. } => got_audio = true,
             ChannelData::Metadata { .. } => got_metadata = true,
+            ChannelData::DataFrame { .. } | ChannelData::Resume { .. } => {}
         }
     }
 
@@ -168,7 +169,7 @@ async fn test_basic_rtmp_unclean() {
     assert_eq!(event.stream_name, "stream-key");
 
     let stream_id = UniqueID::new_v4();
-    event.response.send(stream_id).expect("failed to send response");
+    event.response.send(stream_id.into()).expect("failed to send response");
 
     let mut got_video = false;
     let mut got_audio = false;
@@ -184,6 +185,7 @@ async fn test_basic_rtmp_unclean() {
             ChannelData::Video { .. } => got_video = true,
             ChannelData::Audio { .. } => got_audio = true,
             ChannelData::Metadata { .. } => got_metadata = true,
+            ChannelData::DataFrame { .. } | ChannelData::Resume { .. } => {}
         }
 
         if got_video && got_audio && got_metadata {
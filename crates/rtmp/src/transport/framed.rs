@@ -0,0 +1,146 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{Sink, Stream};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Adapts a message based transport, such as a WebSocket, to the byte stream a [`Session`](crate::Session) reads and writes.
+///
+/// The transport is a [`Stream`] of received messages and a [`Sink`] for sent
+/// messages. Messages do not have to line up with RTMP chunks, received
+/// messages are read as one continuous stream of bytes. Writes are buffered and
+/// every flush of the session is sent as one message. Empty messages are
+/// skipped and the end of the stream ends the session.
+///
+/// # Example
+///
+/// With `tokio-tungstenite`, binary messages are passed through and
+/// everything else is dropped:
+///
+/// ```rust,ignore
+/// use futures::{SinkExt, StreamExt};
+/// use scuffle_rtmp::{FramedIo, PeerInfo, Session, TransportKind};
+/// use tokio_tungstenite::tungstenite::Message;
+///
+/// let ws = tokio_tungstenite::accept_async(stream).await?
+///     .filter_map(|message| async move {
+///         match message {
+///             Ok(Message::Binary(data)) => Some(Ok(data)),
+///             // Pings are answered by tungstenite itself.
+///             Ok(_) => None,
+///             Err(err) => Some(Err(std::io::Error::other(err))),
+///         }
+///     })
+///     .with(|data| async move { Ok::<_, std::io::Error>(Message::Binary(data)) })
+///     .sink_map_err(std::io::Error::other);
+///
+/// let session = Session::new(FramedIo::new(Box::pin(ws)), data_producer, publish_producer)
+///     .with_peer_info(PeerInfo::tcp(addr).with_kind(TransportKind::WebSocket));
+/// ```
+#[derive(Debug)]
+pub struct FramedIo<T> {
+    inner: T,
+    /// The rest of the last received message, that did not fit into the read buffer.
+    pending: Bytes,
+    /// The bytes written since the last flush, sent as one message on the next flush.
+    write_buf: BytesMut,
+}
+
+impl<T> FramedIo<T> {
+    /// Wraps a message based transport.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            pending: Bytes::new(),
+            write_buf: BytesMut::new(),
+        }
+    }
+
+    /// Returns a reference to the wrapped transport.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped transport.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped transport.
+    ///
+    /// Bytes of a received message that were not read yet and written bytes
+    /// that were not flushed yet are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Stream<Item = io::Result<Bytes>> + Unpin> AsyncRead for FramedIo<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        while this.pending.is_empty() {
+            match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(Ok(message)) => this.pending = message,
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                // Nothing put into the buffer is the end of the stream.
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+
+        let len = this.pending.len().min(buf.remaining());
+        buf.put_slice(&this.pending[..len]);
+        this.pending.advance(len);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: Sink<Bytes, Error = io::Error> + Unpin> FramedIo<T> {
+    /// Sends the buffered bytes as one message, if there are any.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !self.write_buf.is_empty() {
+            ready!(Pin::new(&mut self.inner).poll_ready(cx))?;
+            Pin::new(&mut self.inner).start_send(self.write_buf.split().freeze())?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: Sink<Bytes, Error = io::Error> + Unpin> AsyncWrite for FramedIo<T> {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.write_buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut len = 0;
+        for buf in bufs {
+            self.write_buf.extend_from_slice(buf);
+            len += buf.len();
+        }
+
+        Poll::Ready(Ok(len))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_send(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_send(cx))?;
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -0,0 +1,91 @@
+use std::net::SocketAddr;
+
+mod framed;
+mod split;
+#[cfg(feature = "tls-rustls")]
+mod tls;
+
+pub use self::framed::FramedIo;
+pub use self::split::SplitIo;
+#[cfg(feature = "tls-rustls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls-rustls")))]
+pub use self::tls::TlsAcceptor;
+
+/// The kind of connection a session runs over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransportKind {
+    /// Plain RTMP over TCP.
+    #[default]
+    Tcp,
+    /// RTMP over WebSocket, as used by some web based encoders. See [`FramedIo`].
+    WebSocket,
+    /// RTMP over a QUIC stream. See [`SplitIo`].
+    Quic,
+    /// Any other transport.
+    Other,
+}
+
+/// Metadata about the remote peer of a session.
+///
+/// The session does not look at the transport it runs over, so this is set by
+/// whoever accepted the connection, with [`Session::with_peer_info`](crate::Session::with_peer_info).
+/// It is passed along with every [`ConnectRequest`](crate::ConnectRequest).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// The kind of connection.
+    pub kind: TransportKind,
+    /// The address of the peer, if known.
+    pub remote_addr: Option<SocketAddr>,
+    /// Whether the connection is encrypted, for example by TLS or QUIC.
+    pub secure: bool,
+    /// The server name the client asked for with TLS SNI, if any.
+    pub server_name: Option<String>,
+    /// The protocol negotiated with TLS ALPN, if any.
+    pub alpn_protocol: Option<Vec<u8>>,
+}
+
+impl PeerInfo {
+    /// Creates peer info for a plain TCP connection from `remote_addr`.
+    pub fn tcp(remote_addr: SocketAddr) -> Self {
+        Self {
+            kind: TransportKind::Tcp,
+            remote_addr: Some(remote_addr),
+            secure: false,
+            server_name: None,
+            alpn_protocol: None,
+        }
+    }
+
+    /// Sets the kind of connection.
+    pub fn with_kind(mut self, kind: TransportKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the address of the peer.
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.remote_addr = Some(remote_addr);
+        self
+    }
+
+    /// Sets whether the connection is encrypted.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets the server name the client asked for.
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Sets the negotiated ALPN protocol.
+    pub fn with_alpn_protocol(mut self, alpn_protocol: impl Into<Vec<u8>>) -> Self {
+        self.alpn_protocol = Some(alpn_protocol.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,74 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Joins a receiving and a sending half into the single duplex stream a [`Session`](crate::Session) runs over.
+///
+/// Transports such as QUIC hand out the two directions of a bidirectional
+/// stream as separate types, for example `quinn::RecvStream` and
+/// `quinn::SendStream`, which already implement [`AsyncRead`] and [`AsyncWrite`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use scuffle_rtmp::{PeerInfo, Session, SplitIo, TransportKind};
+///
+/// let connection = endpoint.accept().await.unwrap().await?;
+/// let (send, recv) = connection.accept_bi().await?;
+///
+/// let session = Session::new(SplitIo::new(recv, send), data_producer, publish_producer).with_peer_info(
+///     PeerInfo::default()
+///         .with_kind(TransportKind::Quic)
+///         .with_remote_addr(connection.remote_address())
+///         .with_secure(true),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct SplitIo<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R, W> SplitIo<R, W> {
+    /// Joins `reader` and `writer`.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    /// Returns the receiving half.
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the sending half.
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns both halves.
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R: AsyncRead + Unpin, W: Unpin> AsyncRead for SplitIo<R, W> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+impl<R: Unpin, W: AsyncWrite + Unpin> AsyncWrite for SplitIo<R, W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,472 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use scuffle_amf0::{Amf0Decoder, Amf0Encoder, Amf0Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::chunk::{Chunk, ChunkDecoder, ChunkEncoder};
+use crate::messages::MessageTypeID;
+use crate::transport::{FramedIo, PeerInfo, SplitIo, TransportKind};
+use crate::{ChannelData, ConnectDecision, MediaTimestamp, Session};
+
+/// A message based transport made of two channels, like a WebSocket with the framing already stripped.
+struct Channel {
+    incoming: mpsc::UnboundedReceiver<io::Result<Bytes>>,
+    outgoing: mpsc::UnboundedSender<Bytes>,
+    /// The number of times the sink was flushed.
+    flushes: Arc<AtomicUsize>,
+}
+
+impl Channel {
+    fn new() -> (Self, mpsc::UnboundedSender<io::Result<Bytes>>, mpsc::UnboundedReceiver<Bytes>) {
+        let (incoming_tx, incoming) = mpsc::unbounded();
+        let (outgoing, outgoing_rx) = mpsc::unbounded();
+        let channel = Self {
+            incoming,
+            outgoing,
+            flushes: Arc::default(),
+        };
+        (channel, incoming_tx, outgoing_rx)
+    }
+}
+
+impl Stream for Channel {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.incoming.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<Bytes> for Channel {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.outgoing.poll_ready_unpin(cx).map_err(io::Error::other)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> io::Result<()> {
+        self.outgoing.start_send_unpin(item).map_err(io::Error::other)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.flushes.fetch_add(1, Ordering::Relaxed);
+        self.outgoing.poll_flush_unpin(cx).map_err(io::Error::other)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.outgoing.poll_close_unpin(cx).map_err(io::Error::other)
+    }
+}
+
+#[test]
+fn test_peer_info() {
+    let addr = "127.0.0.1:1935".parse().unwrap();
+
+    let peer_info = PeerInfo::tcp(addr);
+    assert_eq!(peer_info.kind, TransportKind::Tcp);
+    assert_eq!(peer_info.remote_addr, Some(addr));
+    assert!(!peer_info.secure);
+
+    let peer_info = PeerInfo::default()
+        .with_kind(TransportKind::Quic)
+        .with_remote_addr(addr)
+        .with_secure(true);
+    assert_eq!(peer_info.kind, TransportKind::Quic);
+    assert_eq!(peer_info.remote_addr, Some(addr));
+    assert!(peer_info.secure);
+
+    let peer_info = PeerInfo::tcp(addr)
+        .with_server_name("live.example.com")
+        .with_alpn_protocol(b"rtmp");
+    assert_eq!(peer_info.server_name.as_deref(), Some("live.example.com"));
+    assert_eq!(peer_info.alpn_protocol.as_deref(), Some(&b"rtmp"[..]));
+}
+
+#[tokio::test]
+async fn test_framed_io_read() {
+    let (channel, incoming, _outgoing) = Channel::new();
+    let mut io = FramedIo::new(channel);
+
+    // Messages are read as one stream of bytes, across read calls and skipping empty messages.
+    incoming.unbounded_send(Ok(Bytes::from_static(b"hello "))).unwrap();
+    incoming.unbounded_send(Ok(Bytes::new())).unwrap();
+    incoming.unbounded_send(Ok(Bytes::from_static(b"world"))).unwrap();
+    drop(incoming);
+
+    let mut buf = [0; 4];
+    io.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hell");
+
+    let mut rest = Vec::new();
+    io.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, b"o world");
+}
+
+#[tokio::test]
+async fn test_framed_io_read_error() {
+    let (channel, incoming, _outgoing) = Channel::new();
+    let mut io = FramedIo::new(channel);
+
+    incoming
+        .unbounded_send(Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")))
+        .unwrap();
+
+    let err = io.read(&mut [0; 16]).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+}
+
+#[tokio::test]
+async fn test_framed_io_write() {
+    let (channel, _incoming, mut outgoing) = Channel::new();
+    let mut io = FramedIo::new(channel);
+
+    // Writes are buffered until a flush sends them as one message, flushing nothing sends nothing.
+    io.write_all(b"hello").await.unwrap();
+    io.write_all(b" ").await.unwrap();
+    let vectored = [io::IoSlice::new(b"wor"), io::IoSlice::new(b"ld")];
+    assert_eq!(io.write_vectored(&vectored).await.unwrap(), 5);
+    io.flush().await.unwrap();
+    io.flush().await.unwrap();
+    // Shutting down sends what was not flushed yet.
+    io.write_all(b"bye").await.unwrap();
+    io.shutdown().await.unwrap();
+
+    let messages = outgoing.by_ref().collect::<Vec<_>>().await;
+    assert_eq!(messages, [Bytes::from_static(b"hello world"), Bytes::from_static(b"bye")]);
+}
+
+#[tokio::test]
+async fn test_split_io() {
+    let (mut client_read, server_write) = tokio::io::duplex(64);
+    let (server_read, mut client_write) = tokio::io::duplex(64);
+    let mut io = SplitIo::new(server_read, server_write);
+
+    client_write.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    io.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    io.write_all(b"pong").await.unwrap();
+    io.shutdown().await.unwrap();
+    let mut buf = Vec::new();
+    client_read.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"pong");
+
+    let (_reader, _writer) = io.into_inner();
+}
+
+#[tokio::test]
+async fn test_session_over_framed_io() {
+    let (channel, incoming, outgoing) = Channel::new();
+    let (data_producer, _data_consumer) = tokio::sync::mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = tokio::sync::mpsc::channel(1);
+    let (connect_producer, mut connect_consumer) = tokio::sync::mpsc::channel(1);
+
+    let peer_info = PeerInfo::tcp("127.0.0.1:4000".parse().unwrap()).with_kind(TransportKind::WebSocket);
+    let mut session = Session::new(FramedIo::new(channel), data_producer, publish_producer)
+        .with_connect_producer(connect_producer)
+        .with_peer_info(peer_info.clone());
+    assert_eq!(session.peer_info(), &peer_info);
+
+    tokio::spawn(async move {
+        let request = connect_consumer.recv().await.unwrap();
+        assert_eq!(request.peer_info, peer_info);
+        request.response.send(ConnectDecision::Accept).unwrap();
+    });
+
+    let mut connect = Vec::new();
+    Amf0Encoder::encode_string(&mut connect, "connect").unwrap();
+    Amf0Encoder::encode_number(&mut connect, 1.0).unwrap();
+    Amf0Encoder::encode_object(&mut connect, &[("app".into(), Amf0Value::String("live".into()))]).unwrap();
+    let mut chunk = Vec::new();
+    ChunkEncoder::default()
+        .write_chunk(
+            &mut chunk,
+            Chunk::new(3, 0, MessageTypeID::CommandAMF0, 0, Bytes::from(connect)),
+        )
+        .unwrap();
+
+    // The messages do not line up with the handshake or the chunks.
+    let mut handshake = vec![3];
+    handshake.extend_from_slice(&[0; 1536 * 2]);
+    incoming
+        .unbounded_send(Ok(Bytes::copy_from_slice(&handshake[..1000])))
+        .unwrap();
+    let mut rest = handshake[1000..].to_vec();
+    rest.extend_from_slice(&chunk);
+    incoming.unbounded_send(Ok(Bytes::from(rest))).unwrap();
+    drop(incoming);
+
+    assert!(session.run().await.unwrap());
+    drop(session);
+
+    let output = outgoing.collect::<Vec<_>>().await.concat();
+
+    // Skip S0 + S1 + S2
+    let mut output = BytesMut::from(&output[1 + 1536 * 2..]);
+    let mut decoder = ChunkDecoder::default();
+    let mut commands = Vec::new();
+    while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+        if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+            let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+            assert!(decoder.update_max_chunk_size(chunk_size as usize));
+        } else if chunk.message_header.msg_type_id == MessageTypeID::CommandAMF0 {
+            commands.push(chunk.payload);
+        }
+    }
+
+    assert_eq!(commands.len(), 1);
+    let values = Amf0Decoder::new(&commands[0]).decode_all().unwrap();
+    assert_eq!(values[0], Amf0Value::String("_result".into()));
+}
+
+#[tokio::test]
+async fn test_session_over_framed_io_play() {
+    let (channel, incoming, mut outgoing) = Channel::new();
+    let flushes = channel.flushes.clone();
+    let (data_producer, _data_consumer) = tokio::sync::mpsc::channel(1);
+    let (publish_producer, _publish_consumer) = tokio::sync::mpsc::channel(1);
+    let (play_producer, mut play_consumer) = tokio::sync::mpsc::channel(1);
+
+    let mut session =
+        Session::new(FramedIo::new(channel), data_producer, publish_producer).with_play_producer(play_producer);
+
+    // Large enough to be written to the session buffer as their own segments.
+    let payload = Bytes::from_iter([0x17].into_iter().chain((0..1023).map(|i| i as u8)));
+    let (stream_producer, stream_consumer) = tokio::sync::mpsc::channel(16);
+    for timestamp in [0, 40, 80] {
+        stream_producer
+            .try_send(ChannelData::Video {
+                timestamp: MediaTimestamp::from_millis(timestamp),
+                data: payload.clone(),
+            })
+            .unwrap();
+    }
+    drop(stream_producer);
+
+    tokio::spawn(async move {
+        let request = play_consumer.recv().await.unwrap();
+        request.response.send(stream_consumer).unwrap();
+    });
+
+    let mut input = vec![3];
+    input.extend_from_slice(&[0; 1536 * 2]);
+    let encoder = ChunkEncoder::default();
+    for (msg_stream_id, name, args) in [
+        (
+            0,
+            "connect",
+            vec![Amf0Value::Object(
+                vec![("app".into(), Amf0Value::String("live".into()))].into(),
+            )],
+        ),
+        (1, "play", vec![Amf0Value::Null, Amf0Value::String("stream".into())]),
+    ] {
+        let mut command = Vec::new();
+        Amf0Encoder::encode_string(&mut command, name).unwrap();
+        Amf0Encoder::encode_number(&mut command, 1.0).unwrap();
+        for arg in &args {
+            Amf0Encoder::encode(&mut command, arg).unwrap();
+        }
+        encoder
+            .write_chunk(
+                &mut input,
+                Chunk::new(3, 0, MessageTypeID::CommandAMF0, msg_stream_id, Bytes::from(command)),
+            )
+            .unwrap();
+    }
+    incoming.unbounded_send(Ok(Bytes::from(input))).unwrap();
+
+    let read_chunks = async {
+        let mut messages = Vec::new();
+        let mut output = BytesMut::new();
+        let mut decoder = ChunkDecoder::default();
+        let mut chunks = Vec::new();
+
+        // Read until the end of the played stream, then close the connection.
+        while !chunks.iter().any(|chunk: &Chunk| {
+            // StreamEOF of stream 1.
+            chunk.message_header.msg_type_id == MessageTypeID::UserControlEvent && chunk.payload[..] == [0, 1, 0, 0, 0, 1]
+        }) {
+            let message = outgoing.next().await.unwrap();
+            output.extend_from_slice(&message);
+            messages.push(message);
+
+            // Skip S0 + S1 + S2
+            if messages.len() == 1 {
+                let _ = output.split_to(1 + 1536 * 2);
+            }
+
+            while let Some(chunk) = decoder.read_chunk(&mut output).unwrap() {
+                if chunk.message_header.msg_type_id == MessageTypeID::SetChunkSize {
+                    let chunk_size = u32::from_be_bytes(chunk.payload[..4].try_into().unwrap());
+                    assert!(decoder.update_max_chunk_size(chunk_size as usize));
+                }
+
+                chunks.push(chunk);
+            }
+        }
+
+        drop(incoming);
+        (messages, chunks)
+    };
+
+    let (result, (messages, chunks)) = tokio::join!(session.run(), read_chunks);
+    assert!(result.unwrap());
+    drop(session);
+    assert_eq!(outgoing.next().await, None);
+
+    // Every flush of the session is sent as one message, not one message per segment.
+    assert_eq!(messages.len(), flushes.load(Ordering::Relaxed));
+    assert_eq!(messages[0].len(), 1 + 1536 * 2);
+
+    let media: Vec<_> = chunks
+        .iter()
+        .filter(|chunk| chunk.message_header.msg_type_id == MessageTypeID::Video)
+        .map(|chunk| chunk.payload.clone())
+        .collect();
+    assert_eq!(media, [payload.clone(), payload.clone(), payload]);
+}
+
+#[cfg(feature = "tls-rustls")]
+mod tls {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use tokio_rustls::rustls::crypto::{CryptoProvider, aws_lc_rs};
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, ServerConfig, SignatureScheme};
+
+    use crate::transport::{PeerInfo, TransportKind};
+    use crate::{Listener, SocketOptions, TlsAcceptor};
+
+    /// Accepts any certificate, the test certificate is not valid for any name.
+    #[derive(Debug)]
+    struct AcceptAnyCert(CryptoProvider);
+
+    impl ServerCertVerifier for AcceptAnyCert {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+            tokio_rustls::rustls::crypto::verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+            tokio_rustls::rustls::crypto::verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+
+    fn server_config() -> ServerConfig {
+        let certfile = std::fs::File::open("../../assets/cert.pem").expect("cert not found");
+        let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(certfile))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("failed to load certs");
+        let keyfile = std::fs::File::open("../../assets/key.pem").expect("key not found");
+        let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(keyfile))
+            .expect("failed to load key")
+            .expect("no key found");
+
+        let mut config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .expect("failed to build config");
+        config.alpn_protocols = vec![b"rtmp".to_vec()];
+        config
+    }
+
+    fn client_config() -> ClientConfig {
+        let mut config = ClientConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(aws_lc_rs::default_provider())))
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"http/1.1".to_vec(), b"rtmp".to_vec()];
+        config
+    }
+
+    #[tokio::test]
+    async fn test_tls_accept_from() {
+        let listener = Listener::bind("127.0.0.1:0".parse().unwrap(), SocketOptions::default()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = TlsAcceptor::new(Arc::new(server_config()));
+
+        let client = tokio::spawn(async move {
+            let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config()));
+            let mut stream = connector
+                .connect(ServerName::try_from("live.example.com").unwrap(), stream)
+                .await
+                .unwrap();
+
+            stream.write_all(&[3]).await.unwrap();
+            stream.flush().await.unwrap();
+            let mut buf = [0; 1];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [3]);
+            stream.get_ref().0.local_addr().unwrap()
+        });
+
+        let (mut stream, peer_info) = acceptor.accept_from(&listener).await.unwrap();
+
+        let mut buf = [0; 1];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&buf).await.unwrap();
+        stream.flush().await.unwrap();
+
+        let client_addr = client.await.unwrap();
+        assert_eq!(
+            peer_info,
+            PeerInfo {
+                kind: TransportKind::Tcp,
+                remote_addr: Some(client_addr),
+                secure: true,
+                server_name: Some("live.example.com".to_string()),
+                alpn_protocol: Some(b"rtmp".to_vec()),
+            }
+        );
+    }
+}
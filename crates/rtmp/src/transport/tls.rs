@@ -0,0 +1,79 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+
+use super::PeerInfo;
+use crate::Listener;
+
+/// Accepts RTMPS connections, RTMP over TLS.
+///
+/// The TLS handshake is done before the RTMP handshake, afterwards the stream is
+/// passed to [`Session::new`](crate::Session::new) like a plain TCP stream.
+///
+/// The certificate and the ALPN protocol are picked by the [`ServerConfig`]. To serve a
+/// different certificate depending on the server name (SNI) the client asked for, set its
+/// [`cert_resolver`](ServerConfig::cert_resolver). The negotiated server name and ALPN protocol
+/// end up in the [`PeerInfo`] of the session, see [`PeerInfo::tls`].
+///
+/// A client that never finishes the TLS handshake keeps [`TlsAcceptor::accept`] waiting,
+/// so it should be run with a timeout, on its own task.
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    inner: tokio_rustls::TlsAcceptor,
+}
+
+impl std::fmt::Debug for TlsAcceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsAcceptor").finish_non_exhaustive()
+    }
+}
+
+impl From<Arc<ServerConfig>> for TlsAcceptor {
+    fn from(config: Arc<ServerConfig>) -> Self {
+        Self::new(config)
+    }
+}
+
+impl TlsAcceptor {
+    /// Creates a new acceptor from a rustls server config.
+    pub fn new(config: Arc<ServerConfig>) -> Self {
+        Self {
+            inner: tokio_rustls::TlsAcceptor::from(config),
+        }
+    }
+
+    /// Does the TLS handshake on an accepted connection.
+    pub async fn accept<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: S) -> io::Result<TlsStream<S>> {
+        self.inner.accept(stream).await
+    }
+
+    /// Accepts a new connection from `listener` and does the TLS handshake on it.
+    ///
+    /// Connections are accepted one at a time, so prefer [`Listener::accept`] followed by
+    /// [`TlsAcceptor::accept`] on a new task when clients are not trusted.
+    pub async fn accept_from(&self, listener: &Listener) -> io::Result<(TlsStream<TcpStream>, PeerInfo)> {
+        let (stream, addr) = listener.accept().await?;
+        let stream = self.accept(stream).await?;
+        let peer_info = PeerInfo::tls(addr, &stream);
+        Ok((stream, peer_info))
+    }
+}
+
+impl PeerInfo {
+    /// Creates peer info for a TLS connection over TCP from `remote_addr`, with the
+    /// server name and ALPN protocol the client negotiated.
+    pub fn tls<S>(remote_addr: SocketAddr, stream: &TlsStream<S>) -> Self {
+        let (_, connection) = stream.get_ref();
+
+        Self {
+            server_name: connection.server_name().map(str::to_owned),
+            alpn_protocol: connection.alpn_protocol().map(<[u8]>::to_vec),
+            ..Self::tcp(remote_addr).with_secure(true)
+        }
+    }
+}
@@ -1 +1,41 @@
 pub const RTMP_EVENT_STREAM_BEGIN: u16 = 0;
+pub const RTMP_EVENT_STREAM_EOF: u16 = 1;
+pub const RTMP_EVENT_STREAM_DRY: u16 = 2;
+pub const RTMP_EVENT_SET_BUFFER_LENGTH: u16 = 3;
+pub const RTMP_EVENT_STREAM_IS_RECORDED: u16 = 4;
+pub const RTMP_EVENT_PING_REQUEST: u16 = 6;
+pub const RTMP_EVENT_PING_RESPONSE: u16 = 7;
+
+/// A user control event, sent on message stream 0 to tell the peer about events on a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserControlEvent {
+    /// The stream became functional, sent by the server before any data of the stream.
+    StreamBegin { stream_id: u32 },
+    /// The playback of the stream is over.
+    StreamEof { stream_id: u32 },
+    /// There is no more data on the stream for now.
+    StreamDry { stream_id: u32 },
+    /// The client buffers this many milliseconds of the stream.
+    SetBufferLength { stream_id: u32, buffer_length: u32 },
+    /// The stream is a recorded stream.
+    StreamIsRecorded { stream_id: u32 },
+    /// Asks the peer to reply with a [`PingResponse`](Self::PingResponse), with the local time in milliseconds.
+    PingRequest { timestamp: u32 },
+    /// The reply to a [`PingRequest`](Self::PingRequest), with its timestamp.
+    PingResponse { timestamp: u32 },
+}
+
+impl UserControlEvent {
+    /// Returns the event type, as written on the wire.
+    pub fn event_type(&self) -> u16 {
+        match self {
+            Self::StreamBegin { .. } => RTMP_EVENT_STREAM_BEGIN,
+            Self::StreamEof { .. } => RTMP_EVENT_STREAM_EOF,
+            Self::StreamDry { .. } => RTMP_EVENT_STREAM_DRY,
+            Self::SetBufferLength { .. } => RTMP_EVENT_SET_BUFFER_LENGTH,
+            Self::StreamIsRecorded { .. } => RTMP_EVENT_STREAM_IS_RECORDED,
+            Self::PingRequest { .. } => RTMP_EVENT_PING_REQUEST,
+            Self::PingResponse { .. } => RTMP_EVENT_PING_RESPONSE,
+        }
+    }
+}
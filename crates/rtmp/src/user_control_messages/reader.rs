@@ -0,0 +1,43 @@
+use std::io::Cursor;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use super::define::{self, UserControlEvent};
+use super::errors::EventMessagesError;
+
+pub struct EventMessagesReader;
+
+impl EventMessagesReader {
+    pub fn read(data: &[u8]) -> Result<UserControlEvent, EventMessagesError> {
+        let mut cursor = Cursor::new(data);
+        let event_type = cursor.read_u16::<BigEndian>()?;
+
+        let event = match event_type {
+            define::RTMP_EVENT_STREAM_BEGIN => UserControlEvent::StreamBegin {
+                stream_id: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_STREAM_EOF => UserControlEvent::StreamEof {
+                stream_id: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_STREAM_DRY => UserControlEvent::StreamDry {
+                stream_id: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_SET_BUFFER_LENGTH => UserControlEvent::SetBufferLength {
+                stream_id: cursor.read_u32::<BigEndian>()?,
+                buffer_length: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_STREAM_IS_RECORDED => UserControlEvent::StreamIsRecorded {
+                stream_id: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_PING_REQUEST => UserControlEvent::PingRequest {
+                timestamp: cursor.read_u32::<BigEndian>()?,
+            },
+            define::RTMP_EVENT_PING_RESPONSE => UserControlEvent::PingResponse {
+                timestamp: cursor.read_u32::<BigEndian>()?,
+            },
+            event_type => return Err(EventMessagesError::UnknownEventType(event_type)),
+        };
+
+        Ok(event)
+    }
+}
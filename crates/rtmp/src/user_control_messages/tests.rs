@@ -1,12 +1,15 @@
 use bytes::{BufMut, Bytes, BytesMut};
 
 use crate::chunk::{ChunkDecoder, ChunkEncodeError, ChunkEncoder};
-use crate::user_control_messages::{EventMessagesError, EventMessagesWriter};
+use crate::user_control_messages::{EventMessagesError, EventMessagesReader, EventMessagesWriter, UserControlEvent};
 
 #[test]
 fn test_error_display() {
     let error = EventMessagesError::ChunkEncode(ChunkEncodeError::UnknownReadState);
     assert_eq!(format!("{}", error), "chunk encode error: unknown read state");
+
+    let error = EventMessagesError::UnknownEventType(26);
+    assert_eq!(format!("{}", error), "unknown event type: 26");
 }
 
 #[test]
@@ -24,3 +27,66 @@ fn test_write_stream_begin() {
     assert_eq!(chunk.message_header.msg_stream_id, 0);
     assert_eq!(chunk.payload, Bytes::from(vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x01]));
 }
+
+#[test]
+fn test_read_events() {
+    let cases = [
+        (
+            vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x01],
+            UserControlEvent::StreamBegin { stream_id: 1 },
+        ),
+        (
+            vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x01],
+            UserControlEvent::StreamEof { stream_id: 1 },
+        ),
+        (
+            vec![0x00, 0x02, 0x00, 0x00, 0x00, 0x01],
+            UserControlEvent::StreamDry { stream_id: 1 },
+        ),
+        (
+            vec![0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c],
+            UserControlEvent::SetBufferLength {
+                stream_id: 1,
+                buffer_length: 300,
+            },
+        ),
+        (
+            vec![0x00, 0x04, 0x00, 0x00, 0x00, 0x01],
+            UserControlEvent::StreamIsRecorded { stream_id: 1 },
+        ),
+        (
+            vec![0x00, 0x06, 0x00, 0x01, 0x00, 0x00],
+            UserControlEvent::PingRequest { timestamp: 65536 },
+        ),
+        (
+            vec![0x00, 0x07, 0x00, 0x01, 0x00, 0x00],
+            UserControlEvent::PingResponse { timestamp: 65536 },
+        ),
+    ];
+
+    for (payload, expected) in cases {
+        assert_eq!(EventMessagesReader::read(&payload).unwrap(), expected);
+
+        // The writer produces the same payload.
+        let mut buf = BytesMut::new();
+        EventMessagesWriter::write_event(&ChunkEncoder::default(), &mut (&mut buf).writer(), expected).unwrap();
+        let chunk = ChunkDecoder::default()
+            .read_chunk(&mut buf)
+            .expect("read chunk")
+            .expect("chunk");
+        assert_eq!(chunk.payload, Bytes::from(payload));
+    }
+}
+
+#[test]
+fn test_read_invalid_events() {
+    assert!(matches!(
+        EventMessagesReader::read(&[0x00, 0x1a, 0x00, 0x00, 0x00, 0x00]),
+        Err(EventMessagesError::UnknownEventType(26))
+    ));
+    assert!(matches!(EventMessagesReader::read(&[0x00]), Err(EventMessagesError::IO(_))));
+    assert!(matches!(
+        EventMessagesReader::read(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x01]),
+        Err(EventMessagesError::IO(_))
+    ));
+}
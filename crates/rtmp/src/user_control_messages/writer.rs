@@ -2,7 +2,7 @@ use std::io;
 
 use byteorder::{BigEndian, WriteBytesExt};
 
-use super::define;
+use super::define::UserControlEvent;
 use super::errors::EventMessagesError;
 use crate::chunk::{Chunk, ChunkEncoder};
 use crate::messages::MessageTypeID;
@@ -14,12 +14,44 @@ impl EventMessagesWriter {
         encoder: &ChunkEncoder,
         writer: &mut impl io::Write,
         stream_id: u32,
+    ) -> Result<(), EventMessagesError> {
+        Self::write_event(encoder, writer, UserControlEvent::StreamBegin { stream_id })
+    }
+
+    pub fn write_ping_response(
+        encoder: &ChunkEncoder,
+        writer: &mut impl io::Write,
+        timestamp: u32,
+    ) -> Result<(), EventMessagesError> {
+        Self::write_event(encoder, writer, UserControlEvent::PingResponse { timestamp })
+    }
+
+    pub fn write_event(
+        encoder: &ChunkEncoder,
+        writer: &mut impl io::Write,
+        event: UserControlEvent,
     ) -> Result<(), EventMessagesError> {
         let mut data = Vec::new();
 
-        data.write_u16::<BigEndian>(define::RTMP_EVENT_STREAM_BEGIN)
-            .expect("write u16");
-        data.write_u32::<BigEndian>(stream_id).expect("write u32");
+        data.write_u16::<BigEndian>(event.event_type()).expect("write u16");
+        match event {
+            UserControlEvent::StreamBegin { stream_id }
+            | UserControlEvent::StreamEof { stream_id }
+            | UserControlEvent::StreamDry { stream_id }
+            | UserControlEvent::StreamIsRecorded { stream_id } => {
+                data.write_u32::<BigEndian>(stream_id).expect("write u32");
+            }
+            UserControlEvent::SetBufferLength {
+                stream_id,
+                buffer_length,
+            } => {
+                data.write_u32::<BigEndian>(stream_id).expect("write u32");
+                data.write_u32::<BigEndian>(buffer_length).expect("write u32");
+            }
+            UserControlEvent::PingRequest { timestamp } | UserControlEvent::PingResponse { timestamp } => {
+                data.write_u32::<BigEndian>(timestamp).expect("write u32");
+            }
+        }
 
         encoder.write_chunk(writer, Chunk::new(0x02, 0, MessageTypeID::UserControlEvent, 0, data.into()))?;
 